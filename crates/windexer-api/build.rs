@@ -0,0 +1,7 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/geyser.proto");
+        tonic_build::compile_protos("proto/geyser.proto").expect("Failed to compile gRPC protos");
+    }
+}
@@ -1,15 +1,17 @@
-use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{RwLock, broadcast};
 use anyhow::Result;
+use serde_json::Value;
 
 use crate::account_endpoints::AccountData;
+use crate::cache::{CacheConfig, CacheStats, ReadThroughCache};
 use crate::helius::HeliusClient;
+use windexer_common::types::Commitment;
 
 pub struct AccountDataManager {
     helius_client: Arc<HeliusClient>,
     
-    cache: Arc<RwLock<HashMap<String, AccountData>>>,
+    cache: ReadThroughCache<AccountData>,
     
     update_sender: broadcast::Sender<AccountData>,
     
@@ -18,11 +20,15 @@ pub struct AccountDataManager {
 
 impl AccountDataManager {
     pub fn new(helius_client: Arc<HeliusClient>) -> Self {
+        Self::with_cache_config(helius_client, CacheConfig::default())
+    }
+
+    pub fn with_cache_config(helius_client: Arc<HeliusClient>, cache_config: CacheConfig) -> Self {
         let (tx, _) = broadcast::channel(10000); // Buffer for 10,000 account updates
         
         Self {
             helius_client,
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: ReadThroughCache::new(cache_config),
             update_sender: tx,
             initialized: Arc::new(RwLock::new(false)),
         }
@@ -55,81 +61,30 @@ impl AccountDataManager {
         self.helius_client.subscribe_account_updates(pubkey).await
     }
     
-    /// Get account data from cache
-    pub async fn get_account(&self, pubkey: &str) -> Result<AccountData> {
-        // Check cache first
-        {
-            let cache = self.cache.read().await;
-            if let Some(account) = cache.get(pubkey) {
-                return Ok(account.clone());
-            }
-        }
-        
-        // Not in cache, fetch from Helius
-        let response = self.helius_client.get_account_info(pubkey).await?;
-        
-        tracing::debug!("Helius account response: {:?}", response);
-        
-        // Parse the response
-        let result = response.get("result").ok_or_else(|| anyhow::anyhow!("Missing result field in response"))?;
-        let context = result.get("context").ok_or_else(|| anyhow::anyhow!("Missing context field in result"))?;
-        let value = result.get("value").ok_or_else(|| anyhow::anyhow!("Missing value field in result"))?;
-        
-        let slot = context.get("slot").and_then(|s| s.as_u64()).unwrap_or(0) as u64;
-        
-        // Handle null value (account not found)
-        if value.is_null() {
-            return Err(anyhow::anyhow!("Account not found"));
-        }
-        
-        // Extract account data
-        let lamports = value.get("lamports").and_then(|l| l.as_u64()).unwrap_or(0);
-        let owner = value.get("owner").and_then(|o| o.as_str()).unwrap_or("").to_string();
-        let executable = value.get("executable").and_then(|e| e.as_bool()).unwrap_or(false);
-        let rent_epoch = value.get("rentEpoch").and_then(|r| r.as_u64()).unwrap_or(0);
-        
-        // Data might be encoded as base64 or array of bytes
-        let data_base64 = if let Some(data) = value.get("data") {
-            if data.is_array() && data.as_array().unwrap().len() >= 2 {
-                let data_array = data.as_array().unwrap();
-                Some(data_array[0].as_str().unwrap_or("").to_string())
-            } else {
-                None
-            }
-        } else {
-            None
-        };
-        
-        let data = Vec::new(); // We'd need to decode the base64 data if needed
-        
-        let account = AccountData {
-            pubkey: pubkey.to_string(),
-            lamports,
-            owner,
-            executable,
-            rent_epoch,
-            data,
-            data_base64,
-            slot,
-            updated_at: chrono::Utc::now().timestamp(),
-        };
-        
-        // Update cache
-        let mut cache = self.cache.write().await;
-        cache.insert(pubkey.to_string(), account.clone());
-        
-        Ok(account)
+    /// Get account data at `commitment`, from cache or Helius
+    pub async fn get_account(&self, pubkey: &str, commitment: Commitment) -> Result<AccountData> {
+        let helius_client = self.helius_client.clone();
+        let owned_pubkey = pubkey.to_string();
+        let key = format!("{}:{}", pubkey, commitment.as_str());
+        let (account, _) = self.cache.get_or_load(&key, move || async move {
+            let response = helius_client.get_account_info(&owned_pubkey, commitment).await?;
+            tracing::debug!("Helius account response: {:?}", response);
+            parse_account(&owned_pubkey, &response)
+        }).await?;
+
+        account.ok_or_else(|| anyhow::anyhow!("Account not found"))
     }
     
     /// Get accounts by program ID
     pub async fn get_accounts_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<AccountData>> {
         // For now, return accounts from our cache that match the program
         // In a real implementation, we would use getProgramAccounts from Helius
-        let cache = self.cache.read().await;
         let mut matching_accounts = Vec::new();
         
-        for account in cache.values() {
-            if account.owner == program_id {
+        for account in self.cache.values().await {
+            // The same account may be cached at several commitments
+            let seen = matching_accounts.iter().any(|found: &AccountData| found.pubkey == account.pubkey);
+            if account.owner == program_id && !seen {
                 matching_accounts.push(account.clone());
                 if matching_accounts.len() >= limit {
                     break;
@@ -144,4 +99,48 @@ impl AccountDataManager {
     pub fn subscribe(&self) -> broadcast::Receiver<AccountData> {
         self.update_sender.subscribe()
     }
+
+    pub async fn cache_stats(&self) -> CacheStats {
+        self.cache.stats().await
+    }
+}
+
+/// Parses a `getAccountInfo` response; `None` if the account does not exist
+fn parse_account(pubkey: &str, response: &Value) -> Result<Option<AccountData>> {
+    let result = response.get("result").ok_or_else(|| anyhow::anyhow!("Missing result field in response"))?;
+    let context = result.get("context").ok_or_else(|| anyhow::anyhow!("Missing context field in result"))?;
+    let value = result.get("value").ok_or_else(|| anyhow::anyhow!("Missing value field in result"))?;
+    
+    let slot = context.get("slot").and_then(|s| s.as_u64()).unwrap_or(0);
+    
+    // Handle null value (account not found)
+    if value.is_null() {
+        return Ok(None);
+    }
+    
+    // Extract account data
+    let lamports = value.get("lamports").and_then(|l| l.as_u64()).unwrap_or(0);
+    let owner = value.get("owner").and_then(|o| o.as_str()).unwrap_or("").to_string();
+    let executable = value.get("executable").and_then(|e| e.as_bool()).unwrap_or(false);
+    let rent_epoch = value.get("rentEpoch").and_then(|r| r.as_u64()).unwrap_or(0);
+    
+    // Data might be encoded as base64 or array of bytes
+    let data_base64 = value.get("data")
+        .and_then(|data| data.as_array())
+        .filter(|data| data.len() >= 2)
+        .map(|data| data[0].as_str().unwrap_or("").to_string());
+    
+    let data = Vec::new(); // We'd need to decode the base64 data if needed
+    
+    Ok(Some(AccountData {
+        pubkey: pubkey.to_string(),
+        lamports,
+        owner,
+        executable,
+        rent_epoch,
+        data,
+        data_base64,
+        slot,
+        updated_at: chrono::Utc::now().timestamp(),
+    }))
 }
\ No newline at end of file
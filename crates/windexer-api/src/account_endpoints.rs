@@ -1,16 +1,28 @@
 use axum::{
     extract::{Path, Query, State, WebSocketUpgrade},
     response::IntoResponse,
-    routing::get,
-    Json, Router,
+    Json,
 };
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
+use windexer_common::{
+    feed::FeedEvent,
+    filter::{Filter, Predicate, Subject},
+    types::{AccountDiffConfig, ApiScope, DataDiffer, DataPatch, Page},
+};
+
+use crate::auth::{scoped, RouteScope};
 use crate::rest::AppState;
-use crate::types::{ApiResponse, ApiError};
+use crate::subscriptions::{
+    parse_filter, parse_pubkeys, stream_to_sse, stream_to_websocket, stream_to_websocket_with, subscription_hub,
+    Commitment, Subscription, SubscriptionHub,
+};
+use crate::types::{recent_page, ApiResponse, ApiError, CommitmentParams};
+use crate::tenant::TenantContext;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct AccountData {
     pub pubkey: String,
     pub lamports: u64,
@@ -23,21 +35,99 @@ pub struct AccountData {
     pub updated_at: i64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AccountQueryParams {
     pub limit: Option<usize>,
     pub before: Option<String>,
     pub after: Option<String>,
     pub program: Option<String>,
+    pub cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AccountUpdateParams {
     pub program: Option<String>,
-    pub pubkeys: Option<String>, // Comma-separated list of pubkeys
+    /// Comma-separated list of pubkeys
+    pub pubkeys: Option<String>,
+    pub commitment: Option<Commitment>,
+    /// Filter expression, e.g. `owner:{program_id}; size:165`, required
+    /// along with `program` and `pubkeys`
+    pub filter: Option<String>,
+    /// Send large accounts as patches of their changed bytes, WebSocket only
+    pub diff: Option<bool>,
+}
+
+/// Account update of a `diff` subscription
+///
+/// A `patch` applies to the last `snapshot` or `patch` of its account sent
+/// on the subscription; an `account` is sent in full and not followed by
+/// patches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AccountUpdate {
+    Account(AccountData),
+    Snapshot(AccountData),
+    Patch(AccountPatch),
+}
+
+/// Changed bytes of an account's data, with its other fields in full
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountPatch {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub slot: u64,
+    pub updated_at: i64,
+    pub data_len: usize,
+    pub patches: Vec<DataPatch>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl AccountUpdate {
+    /// `account` as a patch of the version last sent through `differ`, when
+    /// that's worth it
+    fn diff(differ: &mut DataDiffer<String>, account: AccountData) -> Self {
+        let Some(diff) = differ.update(account.pubkey.clone(), account.slot, &account.data) else {
+            return if differ.is_tracked(&account.pubkey) {
+                AccountUpdate::Snapshot(account)
+            } else {
+                AccountUpdate::Account(account)
+            };
+        };
+        AccountUpdate::Patch(AccountPatch {
+            pubkey: account.pubkey,
+            lamports: account.lamports,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            slot: account.slot,
+            updated_at: account.updated_at,
+            data_len: account.data.len(),
+            patches: diff.patches,
+        })
+    }
+}
+
+impl From<&windexer_common::types::AccountData> for AccountData {
+    fn from(account: &windexer_common::types::AccountData) -> Self {
+        Self {
+            pubkey: account.pubkey.to_string(),
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: account.data.clone(),
+            data_base64: Some(base64::encode(&account.data)),
+            slot: account.slot,
+            updated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AccountBalance {
     pub address: String,
     pub lamports: u64,
@@ -45,7 +135,7 @@ pub struct AccountBalance {
     pub updated_at: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TokenBalance {
     pub mint: String,
     pub owner: String,
@@ -54,30 +144,54 @@ pub struct TokenBalance {
     pub ui_amount: f64,
 }
 
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}",
+    tag = "accounts",
+    params(("pubkey" = String, Path, description = "Account address"), CommitmentParams),
+    responses(
+        (status = 200, description = "Account state", body = ApiResponse<AccountData>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_account(
     State(state): State<AppState>,
     Path(pubkey): Path<String>,
+    Query(params): Query<CommitmentParams>,
 ) -> Result<Json<ApiResponse<AccountData>>, ApiError> {
     let account_manager = state.account_data_manager.ok_or_else(|| {
         ApiError::Internal("Account data manager not initialized".to_string())
     })?;
     
-    match account_manager.get_account(&pubkey).await {
+    match account_manager.get_account(&pubkey, params.level()).await {
         Ok(account) => Ok(Json(ApiResponse::success(account))),
         Err(e) => Err(ApiError::Internal(format!("Failed to fetch account: {}", e)))
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}/balance",
+    tag = "accounts",
+    params(("pubkey" = String, Path, description = "Account address"), CommitmentParams),
+    responses(
+        (status = 200, description = "SOL balance", body = ApiResponse<AccountBalance>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_account_balance(
     State(state): State<AppState>,
     Path(address): Path<String>,
+    Query(params): Query<CommitmentParams>,
 ) -> Result<Json<ApiResponse<AccountBalance>>, ApiError> {
     // Get the account data manager from app state
     let account_manager = state.account_data_manager.ok_or_else(|| {
         ApiError::Internal("Account data manager not initialized".to_string())
     })?;
     
-    match account_manager.get_account(&address).await {
+    match account_manager.get_account(&address, params.level()).await {
         Ok(account) => {
             let balance = AccountBalance {
                 address: address,
@@ -101,6 +215,17 @@ pub async fn get_account_balance(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}/tokens",
+    tag = "accounts",
+    params(("pubkey" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "SPL token balances", body = ApiResponse<Vec<TokenBalance>>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_account_tokens(
     State(state): State<AppState>,
     Path(address): Path<String>,
@@ -135,142 +260,117 @@ pub async fn get_account_tokens(
     Ok(Json(ApiResponse::success(tokens)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/accounts/program/{program_id}",
+    tag = "accounts",
+    params(("program_id" = String, Path, description = "Owning program"), AccountQueryParams),
+    responses(
+        (status = 200, description = "Accounts owned by the program", body = ApiResponse<Page<AccountData>>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_accounts_by_program(
     State(state): State<AppState>,
     Path(program_id): Path<String>,
     Query(params): Query<AccountQueryParams>,
-) -> Result<Json<ApiResponse<Vec<AccountData>>>, ApiError> {
-    let account_manager = state.account_data_manager.ok_or_else(|| {
+) -> Result<Json<ApiResponse<Page<AccountData>>>, ApiError> {
+    let account_manager = state.account_data_manager.as_ref().ok_or_else(|| {
         ApiError::Internal("Account data manager not initialized".to_string())
     })?;
     
     let limit = params.limit.unwrap_or(10);
     
-    match account_manager.get_accounts_by_program(&program_id, limit).await {
-        Ok(accounts) => Ok(Json(ApiResponse::success(accounts))),
-        Err(e) => Err(ApiError::Internal(format!("Failed to fetch accounts by program: {}", e)))
-    }
+    let program_id = &program_id;
+    let page = recent_page(params.cursor.as_deref(), limit, |account: &AccountData| account.slot, |_, n| async move {
+        account_manager.get_accounts_by_program(program_id, n).await
+            .map_err(|e| ApiError::Internal(format!("Failed to fetch accounts by program: {}", e)))
+    }).await?;
+    Ok(Json(ApiResponse::success(page)))
+}
+
+fn account_filter(params: &AccountUpdateParams) -> Result<Filter, ApiError> {
+    let pubkeys = params.pubkeys.as_deref()
+        .into_iter()
+        .flat_map(|pubkeys| pubkeys.split(','))
+        .filter(|pubkey| !pubkey.trim().is_empty());
+    Ok(parse_filter(params.filter.as_deref(), Subject::Accounts).map_err(ApiError::BadRequest)?
+        .and_any_of(Predicate::Accounts, parse_pubkeys(pubkeys, "pubkeys").map_err(ApiError::BadRequest)?)
+        .and_any_of(Predicate::Owners, parse_pubkeys(params.program.as_deref(), "program").map_err(ApiError::BadRequest)?))
 }
 
+fn subscribe_accounts(hub: &std::sync::Arc<SubscriptionHub>, commitment: Commitment, filter: Filter) -> Subscription<AccountData> {
+    hub.subscribe(commitment, move |event| match event {
+        FeedEvent::Account(account) if filter.matches_account_data(account) => {
+            Some((account.slot, AccountData::from(&**account)))
+        }
+        _ => None,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws/accounts",
+    tag = "streams",
+    params(AccountUpdateParams),
+    responses((status = 101, description = "WebSocket of `AccountData` updates, or of `AccountUpdate`s with `diff=true`"))
+)]
 pub async fn account_stream(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(params): Query<AccountUpdateParams>,
-) -> impl IntoResponse {
-    let pubkeys = params.pubkeys
-        .map(|p| p.split(',').map(|s| s.to_string()).collect::<Vec<_>>())
-        .unwrap_or_default();
-    
-    let program = params.program;
-    
-    ws.on_upgrade(move |socket| async move {
-        handle_account_websocket(socket, state, pubkeys, program).await
-    })
-}
+) -> Result<impl IntoResponse, ApiError> {
+    let hub = subscription_hub(&state)?;
+    let filter = account_filter(&params)?;
+    let commitment = params.commitment.unwrap_or_default();
+    let diff = params.diff.unwrap_or(false);
 
-async fn handle_account_websocket(
-    socket: axum::extract::ws::WebSocket,
-    state: AppState,
-    pubkeys: Vec<String>,
-    program: Option<String>,
-) {
-    use axum::extract::ws::Message;
-    use futures::{SinkExt, StreamExt};
-    use std::time::Duration;
-    
-    state.metrics.set_metric("active_account_streams", serde_json::json!(1)).await;
-    
-    let (sender, receiver) = socket.split();
-    
-    let (tx, rx) = broadcast::channel::<AccountData>(1000);
-    
-    let tx_clone = tx.clone();
-    let pubkeys_clone = pubkeys.clone();
-    let program_clone = program.clone();
-    
-    let mut simulation_task = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(2));
-        
-        loop {
-            interval.tick().await;
-            
-            let pubkey = if !pubkeys_clone.is_empty() {
-                pubkeys_clone[fastrand::usize(..pubkeys_clone.len())].clone()
-            } else {
-                format!("simulated{}", fastrand::u64(..100000))
-            };
-            
-            let owner = program_clone.clone().unwrap_or_else(|| {
-                "11111111111111111111111111111111".to_string()
-            });
-            
-            let account = AccountData {
-                pubkey,
-                lamports: fastrand::u64(..1000000000),
-                owner,
-                executable: false,
-                rent_epoch: 0,
-                data: vec![],
-                data_base64: Some("".to_string()),
-                slot: fastrand::u64(..1000000),
-                updated_at: chrono::Utc::now().timestamp(),
-            };
-            
-            let _ = tx_clone.send(account);
-        }
-    });
-    
-    let ws_sender = sender;
-    
-    tokio::spawn(async move {
-        let mut sender = ws_sender;
-        let mut receiver = receiver;
-        let mut rx = rx;
-        
-        loop {
-            tokio::select! {
-                result = receiver.next() => {
-                    match result {
-                        Some(Ok(Message::Text(text))) => {
-                            if text == "ping" {
-                                if sender.send(Message::Text("pong".to_string())).await.is_err() {
-                                    break;
-                                }
-                            }
-                        },
-                        Some(Ok(Message::Close(_))) | None => break,
-                        _ => {}
-                    }
-                },
-                
-                result = rx.recv() => {
-                    if let Ok(account) = result {
-                        let matches_pubkey = pubkeys.is_empty() || pubkeys.contains(&account.pubkey);
-                        let matches_program = program.is_none() || program.as_ref() == Some(&account.owner);
-                        
-                        if matches_pubkey && matches_program {
-                            if let Ok(json) = serde_json::to_string(&account) {
-                                if sender.send(Message::Text(json)).await.is_err() {
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    // The connection outlives the request and its tenant scope
+    let tenant = TenantContext::current();
+    Ok(ws.on_upgrade(move |socket| tenant.scope(async move {
+        let subscription = subscribe_accounts(&hub, commitment, filter);
+        state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+
+        if diff {
+            let mut differ = DataDiffer::new(AccountDiffConfig::default());
+            stream_to_websocket_with(socket, subscription, |account| AccountUpdate::diff(&mut differ, account)).await;
+        } else {
+            stream_to_websocket(socket, subscription).await;
         }
-        
-        simulation_task.abort();
-        
-        state.metrics.set_metric("active_account_streams", serde_json::json!(0)).await;
-    });
+        state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+    })))
 }
 
-pub fn create_account_router() -> Router<AppState> {
-    Router::new()
-        .route("/account/:pubkey", get(get_account))
-        .route("/account/:pubkey/balance", get(get_account_balance))
-        .route("/account/:pubkey/tokens", get(get_account_tokens))
-        .route("/accounts/program/:program_id", get(get_accounts_by_program))
-        .route("/ws/accounts", get(account_stream))
+/// Account updates as Server-Sent Events, for clients that cannot use WebSockets
+#[utoipa::path(
+    get,
+    path = "/stream/accounts",
+    tag = "streams",
+    params(AccountUpdateParams),
+    responses((status = 200, description = "`account` events carrying `AccountData`", content_type = "text/event-stream"))
+)]
+pub async fn account_events(
+    State(state): State<AppState>,
+    Query(params): Query<AccountUpdateParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let hub = subscription_hub(&state)?;
+    let filter = account_filter(&params)?;
+
+    let subscription = subscribe_accounts(&hub, params.commitment.unwrap_or_default(), filter);
+    state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+    Ok(stream_to_sse(subscription, "account"))
+}
+
+pub fn create_account_router() -> OpenApiRouter<AppState> {
+    let reads = OpenApiRouter::new()
+        .routes(routes!(get_account))
+        .routes(routes!(get_account_balance))
+        .routes(routes!(get_account_tokens))
+        .routes(routes!(get_accounts_by_program));
+    let streams = OpenApiRouter::new()
+        .routes(routes!(account_stream))
+        .routes(routes!(account_events));
+
+    scoped(reads, RouteScope::ReadWrite).merge(scoped(streams, RouteScope::All(ApiScope::Stream)))
 }
\ No newline at end of file
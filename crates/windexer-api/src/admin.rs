@@ -0,0 +1,805 @@
+//! Admin API
+//!
+//! Routine maintenance without restarting the process, under `/admin` and
+//! only served when authentication is enabled, so every route needs the
+//! `admin` scope:
+//!
+//! - `GET /admin/config` dumps the running configuration
+//! - `POST /admin/filters/reload` re-reads the data filters
+//! - `GET /admin/peers`, `POST`/`DELETE /admin/peers/{peer_id}/ban` manage peers
+//! - `GET /admin/peers/allowlist`, `POST`/`DELETE /admin/peers/{peer_id}/allow` manage the allowlist
+//! - `GET /admin/topics`, `POST /admin/topics/subscribe` and `POST /admin/topics/unsubscribe` manage gossip subscriptions
+//! - `POST /admin/topics/subscribe/filter` subscribes to the topics carrying what a filter expression matches
+//! - `GET`/`PUT /admin/log-level` read and change log levels, for the whole process or one module
+//! - `GET /admin/dead-letters`, `POST /admin/dead-letters/retry` and `POST /admin/dead-letters/purge` manage failed deliveries
+//! - `GET /admin/store` shows indexing progress and the latest slot per commitment
+//! - `POST /admin/prune` deletes data before a slot
+//! - `GET`/`POST /admin/backfills` and `POST /admin/backfills/{id}/stop` run backfills
+//!
+//! Peers, topics, filters, log levels and dead letters belong to other
+//! components, which the embedding binary attaches with [`ApiServer::set_peer_control`](crate::rest::ApiServer::set_peer_control),
+//! [`ApiServer::set_topic_control`](crate::rest::ApiServer::set_topic_control),
+//! [`ApiServer::set_filter_control`](crate::rest::ApiServer::set_filter_control),
+//! [`ApiServer::set_log_control`](crate::rest::ApiServer::set_log_control)
+//! and [`ApiServer::set_dead_letter_control`](crate::rest::ApiServer::set_dead_letter_control).
+//! Store statistics, pruning and backfills need the `store` feature.
+
+use {
+    crate::{
+        auth::{create_key_router, scoped, RouteScope},
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{
+        extract::{Path, Query, State},
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    std::sync::Arc,
+    tracing::info,
+    utoipa::{IntoParams, ToSchema},
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::{
+        control::{
+            DeadLetterControl, DeadLetterInfo, DeadLetterRequest, FilterControl, LogControl, LogLevelRequest,
+            PeerControl, PeerInfo, RetryReport, TopicControl, TopicInfo,
+        },
+        filter::Filter,
+        types::ApiScope,
+    },
+};
+
+fn peer_control(state: &AppState) -> Result<&Arc<dyn PeerControl>, ApiError> {
+    state.peer_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No network node is attached".to_string()))
+}
+
+fn topic_control(state: &AppState) -> Result<&Arc<dyn TopicControl>, ApiError> {
+    state.topic_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No network node is attached".to_string()))
+}
+
+fn log_control(state: &AppState) -> Result<&Arc<dyn LogControl>, ApiError> {
+    state.log_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("Log levels cannot be changed in this process".to_string()))
+}
+
+fn dead_letter_control(state: &AppState) -> Result<&Arc<dyn DeadLetterControl>, ApiError> {
+    state.dead_letter_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No dead-letter queue is attached".to_string()))
+}
+
+fn filter_control(state: &AppState) -> Result<&Arc<dyn FilterControl>, ApiError> {
+    state.filter_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No filter configuration is attached".to_string()))
+}
+
+/// A peer of the attached network node
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Peer {
+    pub peer_id: String,
+    pub connected: bool,
+    pub banned: bool,
+    pub allowed: bool,
+}
+
+impl From<PeerInfo> for Peer {
+    fn from(peer: PeerInfo) -> Self {
+        Self {
+            peer_id: peer.peer_id,
+            connected: peer.connected,
+            banned: peer.banned,
+            allowed: peer.allowed,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/config",
+    tag = "admin",
+    responses((status = 200, description = "Running configuration and filters", body = ApiResponse<Object>))
+)]
+pub async fn get_config(State(state): State<AppState>) -> Result<Json<ApiResponse<Value>>, ApiError> {
+    let mut config = state.config.read().await.clone();
+    if let (Some(filters), Value::Object(config)) = (&state.filter_control, &mut config) {
+        let filters = filters.filters().await
+            .map_err(|e| ApiError::Internal(format!("Failed to read filters: {}", e)))?;
+        config.insert("filters".to_string(), filters);
+    }
+    Ok(Json(ApiResponse::success(config)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/filters/reload",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Filters now in effect", body = ApiResponse<Object>),
+        (status = 404, description = "No filter configuration is attached", body = ApiError)
+    )
+)]
+pub async fn reload_filters(State(state): State<AppState>) -> Result<Json<ApiResponse<Value>>, ApiError> {
+    let filters = filter_control(&state)?.reload_filters().await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to reload filters: {}", e)))?;
+    info!("Reloaded filter configuration");
+    Ok(Json(ApiResponse::success(filters)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/peers",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Connected and banned peers", body = ApiResponse<Vec<Peer>>),
+        (status = 404, description = "No network node is attached", body = ApiError)
+    )
+)]
+pub async fn list_peers(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<Peer>>>, ApiError> {
+    let peers = peer_control(&state)?.peers().await
+        .map_err(|e| ApiError::Internal(format!("Failed to list peers: {}", e)))?;
+    Ok(Json(ApiResponse::success(peers.into_iter().map(Peer::from).collect())))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/peers/{peer_id}/ban",
+    tag = "admin",
+    params(("peer_id" = String, Path, description = "libp2p peer id")),
+    responses(
+        (status = 200, description = "Id of the banned peer", body = ApiResponse<String>),
+        (status = 400, description = "Invalid peer id", body = ApiError)
+    )
+)]
+pub async fn ban_peer(State(state): State<AppState>, Path(peer_id): Path<String>) -> Result<Json<ApiResponse<String>>, ApiError> {
+    peer_control(&state)?.ban_peer(&peer_id).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Banned peer {}", peer_id);
+    Ok(Json(ApiResponse::success(peer_id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/peers/{peer_id}/ban",
+    tag = "admin",
+    params(("peer_id" = String, Path, description = "libp2p peer id")),
+    responses(
+        (status = 200, description = "Id of the unbanned peer", body = ApiResponse<String>),
+        (status = 400, description = "Invalid or unbanned peer id", body = ApiError)
+    )
+)]
+pub async fn unban_peer(State(state): State<AppState>, Path(peer_id): Path<String>) -> Result<Json<ApiResponse<String>>, ApiError> {
+    peer_control(&state)?.unban_peer(&peer_id).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Unbanned peer {}", peer_id);
+    Ok(Json(ApiResponse::success(peer_id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/peers/allowlist",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Peers allowed to connect; every peer may while empty", body = ApiResponse<Vec<String>>),
+        (status = 404, description = "No network node is attached", body = ApiError)
+    )
+)]
+pub async fn list_allowed_peers(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<String>>>, ApiError> {
+    let peers = peer_control(&state)?.allowlist().await
+        .map_err(|e| ApiError::Internal(format!("Failed to list allowed peers: {}", e)))?;
+    Ok(Json(ApiResponse::success(peers)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/peers/{peer_id}/allow",
+    tag = "admin",
+    params(("peer_id" = String, Path, description = "libp2p peer id")),
+    responses(
+        (status = 200, description = "Id of the allowed peer", body = ApiResponse<String>),
+        (status = 400, description = "Invalid peer id", body = ApiError)
+    )
+)]
+pub async fn allow_peer(State(state): State<AppState>, Path(peer_id): Path<String>) -> Result<Json<ApiResponse<String>>, ApiError> {
+    peer_control(&state)?.allow_peer(&peer_id).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Allowed peer {}", peer_id);
+    Ok(Json(ApiResponse::success(peer_id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/peers/{peer_id}/allow",
+    tag = "admin",
+    params(("peer_id" = String, Path, description = "libp2p peer id")),
+    responses(
+        (status = 200, description = "Id of the peer removed from the allowlist", body = ApiResponse<String>),
+        (status = 400, description = "Invalid or unlisted peer id", body = ApiError)
+    )
+)]
+pub async fn disallow_peer(State(state): State<AppState>, Path(peer_id): Path<String>) -> Result<Json<ApiResponse<String>>, ApiError> {
+    peer_control(&state)?.disallow_peer(&peer_id).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Removed peer {} from the allowlist", peer_id);
+    Ok(Json(ApiResponse::success(peer_id)))
+}
+
+/// A gossip topic of the attached network node
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GossipTopic {
+    pub topic: String,
+    pub description: String,
+    pub subscribed: bool,
+}
+
+impl From<TopicInfo> for GossipTopic {
+    fn from(topic: TopicInfo) -> Self {
+        Self {
+            topic: topic.topic,
+            description: topic.description,
+            subscribed: topic.subscribed,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TopicRequest {
+    /// Topic name, e.g. `transactions` or `accounts/{program_id}`
+    pub topic: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/topics",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Well-known and subscribed topics", body = ApiResponse<Vec<GossipTopic>>),
+        (status = 404, description = "No network node is attached", body = ApiError)
+    )
+)]
+pub async fn list_topics(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<GossipTopic>>>, ApiError> {
+    let topics = topic_control(&state)?.topics().await
+        .map_err(|e| ApiError::Internal(format!("Failed to list topics: {}", e)))?;
+    Ok(Json(ApiResponse::success(topics.into_iter().map(GossipTopic::from).collect())))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/topics/subscribe",
+    tag = "admin",
+    request_body = TopicRequest,
+    responses(
+        (status = 200, description = "The subscribed topic", body = ApiResponse<String>),
+        (status = 400, description = "Unknown topic", body = ApiError)
+    )
+)]
+pub async fn subscribe_topic(
+    State(state): State<AppState>,
+    Json(request): Json<TopicRequest>,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    topic_control(&state)?.subscribe(&request.topic).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Subscribed to topic {}", request.topic);
+    Ok(Json(ApiResponse::success(request.topic)))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TopicFilterRequest {
+    /// Filter expression, e.g. `owner:{program_id}; size:165`
+    pub filter: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/topics/subscribe/filter",
+    tag = "admin",
+    request_body = TopicFilterRequest,
+    responses(
+        (status = 200, description = "The subscribed topics", body = ApiResponse<Vec<String>>),
+        (status = 400, description = "Invalid filter, or one no topic carries", body = ApiError)
+    )
+)]
+pub async fn subscribe_topic_filter(
+    State(state): State<AppState>,
+    Json(request): Json<TopicFilterRequest>,
+) -> Result<Json<ApiResponse<Vec<String>>>, ApiError> {
+    let filter = request.filter.parse::<Filter>()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let topics = topic_control(&state)?.subscribe_filter(&filter).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Subscribed to topics {:?} for filter {}", topics, filter);
+    Ok(Json(ApiResponse::success(topics)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/topics/unsubscribe",
+    tag = "admin",
+    request_body = TopicRequest,
+    responses(
+        (status = 200, description = "The unsubscribed topic", body = ApiResponse<String>),
+        (status = 400, description = "Unknown or unsubscribed topic", body = ApiError)
+    )
+)]
+pub async fn unsubscribe_topic(
+    State(state): State<AppState>,
+    Json(request): Json<TopicRequest>,
+) -> Result<Json<ApiResponse<String>>, ApiError> {
+    topic_control(&state)?.unsubscribe(&request.topic).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Unsubscribed from topic {}", request.topic);
+    Ok(Json(ApiResponse::success(request.topic)))
+}
+
+/// Log directives in effect
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogLevels {
+    /// Comma separated `level` and `module=level` directives, e.g. `info,windexer_network=debug`
+    pub directives: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/log-level",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Log directives in effect", body = ApiResponse<LogLevels>),
+        (status = 404, description = "Log levels cannot be changed", body = ApiError)
+    )
+)]
+pub async fn get_log_level(State(state): State<AppState>) -> Result<Json<ApiResponse<LogLevels>>, ApiError> {
+    let directives = log_control(&state)?.directives();
+    Ok(Json(ApiResponse::success(LogLevels { directives })))
+}
+
+#[utoipa::path(
+    put,
+    path = "/admin/log-level",
+    tag = "admin",
+    request_body = LogLevelRequest,
+    responses(
+        (status = 200, description = "Log directives now in effect", body = ApiResponse<LogLevels>),
+        (status = 400, description = "Invalid level or directives", body = ApiError),
+        (status = 404, description = "Log levels cannot be changed", body = ApiError)
+    )
+)]
+pub async fn set_log_level(
+    State(state): State<AppState>,
+    Json(request): Json<LogLevelRequest>,
+) -> Result<Json<ApiResponse<LogLevels>>, ApiError> {
+    let control = log_control(&state)?;
+    request.apply(control.as_ref())
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+
+    let directives = control.directives();
+    info!("Log directives changed to {}", directives);
+    Ok(Json(ApiResponse::success(LogLevels { directives })))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeadLetterQuery {
+    /// Entries to list, oldest first; 100 by default
+    pub limit: Option<usize>,
+}
+
+/// Dead letters purged
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PurgeReport {
+    pub purged: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/dead-letters",
+    tag = "admin",
+    params(DeadLetterQuery),
+    responses(
+        (status = 200, description = "Deliveries that failed, oldest first", body = ApiResponse<Vec<DeadLetterInfo>>),
+        (status = 404, description = "No dead-letter queue is attached", body = ApiError)
+    )
+)]
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Json<ApiResponse<Vec<DeadLetterInfo>>>, ApiError> {
+    let dead_letters = dead_letter_control(&state)?.dead_letters(query.limit.unwrap_or(100)).await
+        .map_err(|e| ApiError::Internal(format!("Failed to list dead letters: {}", e)))?;
+    Ok(Json(ApiResponse::success(dead_letters)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/dead-letters/retry",
+    tag = "admin",
+    request_body = DeadLetterRequest,
+    responses(
+        (status = 200, description = "Entries delivered and failed again", body = ApiResponse<RetryReport>),
+        (status = 404, description = "No dead-letter queue is attached", body = ApiError)
+    )
+)]
+pub async fn retry_dead_letters(
+    State(state): State<AppState>,
+    Json(request): Json<DeadLetterRequest>,
+) -> Result<Json<ApiResponse<RetryReport>>, ApiError> {
+    let report = dead_letter_control(&state)?.retry(request.ids.as_deref()).await
+        .map_err(|e| ApiError::Internal(format!("Failed to retry dead letters: {}", e)))?;
+    info!("Retried dead letters: {} delivered, {} failed again", report.delivered, report.failed);
+    Ok(Json(ApiResponse::success(report)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/dead-letters/purge",
+    tag = "admin",
+    request_body = DeadLetterRequest,
+    responses(
+        (status = 200, description = "Entries purged", body = ApiResponse<PurgeReport>),
+        (status = 404, description = "No dead-letter queue is attached", body = ApiError)
+    )
+)]
+pub async fn purge_dead_letters(
+    State(state): State<AppState>,
+    Json(request): Json<DeadLetterRequest>,
+) -> Result<Json<ApiResponse<PurgeReport>>, ApiError> {
+    let purged = dead_letter_control(&state)?.purge(request.ids.as_deref()).await
+        .map_err(|e| ApiError::Internal(format!("Failed to purge dead letters: {}", e)))?;
+    info!("Purged {} dead letters", purged);
+    Ok(Json(ApiResponse::success(PurgeReport { purged })))
+}
+
+#[cfg(feature = "store")]
+mod maintenance {
+    use {
+        super::*,
+        std::{
+            collections::BTreeMap,
+            path::PathBuf,
+            sync::atomic::{AtomicBool, AtomicU64, Ordering},
+        },
+        tokio::sync::{broadcast::error::RecvError, RwLock},
+        tracing::{error, warn},
+        windexer_common::{
+            feed::{DataFeed, FeedEvent},
+            types::Commitment,
+        },
+        windexer_store::{
+            backfill::{Backfill, BackfillCheckpoint, BackfillConfig},
+            retention::DataKind,
+            traits::Storage,
+        },
+    };
+
+    fn storage(state: &AppState) -> Result<Arc<dyn Storage>, ApiError> {
+        state.store().ok_or_else(|| ApiError::Internal("Storage not initialized".to_string()))
+    }
+
+    #[derive(Debug, Default, Serialize, ToSchema)]
+    pub struct StoreStats {
+        /// Latest rooted slot whose data was fully written
+        pub last_processed_slot: u64,
+        pub total_accounts: u64,
+        pub total_transactions: u64,
+        /// Latest slot at each commitment, where the store tracks it
+        pub processed_slot: Option<u64>,
+        pub confirmed_slot: Option<u64>,
+        pub finalized_slot: Option<u64>,
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/admin/store",
+        tag = "admin",
+        responses((status = 200, description = "Indexing progress and latest slots", body = ApiResponse<StoreStats>))
+    )]
+    pub async fn store_stats(State(state): State<AppState>) -> Result<Json<ApiResponse<StoreStats>>, ApiError> {
+        let storage = storage(&state)?;
+        let indexed = storage.load_indexer_state().await.map_err(ApiError::storage)?.unwrap_or_default();
+        // Stores that do not track commitments answer unsupported
+        let latest = |commitment| {
+            let storage = storage.clone();
+            async move { storage.get_latest_slot_with_commitment(commitment).await.ok().flatten() }
+        };
+        Ok(Json(ApiResponse::success(StoreStats {
+            last_processed_slot: indexed.last_processed_slot,
+            total_accounts: indexed.total_accounts,
+            total_transactions: indexed.total_transactions,
+            processed_slot: latest(Commitment::Processed).await,
+            confirmed_slot: latest(Commitment::Confirmed).await,
+            finalized_slot: latest(Commitment::Finalized).await,
+        })))
+    }
+
+    #[derive(Debug, Deserialize, ToSchema)]
+    pub struct PruneRequest {
+        /// `accounts`, `transactions` or `blocks`
+        #[schema(value_type = String)]
+        pub kind: DataKind,
+        /// Data from slots before this one is deleted
+        pub before_slot: u64,
+    }
+
+    #[derive(Debug, Serialize, ToSchema)]
+    pub struct PruneResult {
+        pub removed: u64,
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/admin/prune",
+        tag = "admin",
+        request_body = PruneRequest,
+        responses(
+            (status = 200, description = "Number of rows removed", body = ApiResponse<PruneResult>),
+            (status = 400, description = "The store cannot prune", body = ApiError)
+        )
+    )]
+    pub async fn prune(
+        State(state): State<AppState>,
+        Json(request): Json<PruneRequest>,
+    ) -> Result<Json<ApiResponse<PruneResult>>, ApiError> {
+        let removed = storage(&state)?.prune_before_slot(request.kind, request.before_slot).await
+            .map_err(|e| ApiError::BadRequest(format!("Failed to prune: {}", e)))?;
+        info!("Pruned {} {:?} rows before slot {}", removed, request.kind, request.before_slot);
+        Ok(Json(ApiResponse::success(PruneResult { removed })))
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub enum BackfillState {
+        Running,
+        Completed,
+        Stopped,
+        Failed,
+    }
+
+    #[derive(Debug, Clone, Serialize, ToSchema)]
+    pub struct BackfillJobInfo {
+        pub id: u64,
+        pub state: BackfillState,
+        pub start_slot: Option<u64>,
+        pub end_slot: Option<u64>,
+        #[schema(value_type = Option<String>)]
+        pub checkpoint_path: PathBuf,
+        /// Progress when the run ended
+        #[schema(value_type = Option<Object>)]
+        pub checkpoint: Option<BackfillCheckpoint>,
+        pub error: Option<String>,
+        /// Unix timestamp in seconds
+        pub started_at: i64,
+    }
+
+    struct BackfillJob {
+        info: Arc<RwLock<BackfillJobInfo>>,
+        stop: Arc<AtomicBool>,
+    }
+
+    /// Backfills started through the admin API
+    pub struct BackfillJobs {
+        template: BackfillConfig,
+        jobs: RwLock<BTreeMap<u64, BackfillJob>>,
+        next_id: AtomicU64,
+    }
+
+    impl BackfillJobs {
+        /// Jobs take the RPC endpoint, limits and snapshots from `template`; each
+        /// keeps its checkpoint next to `template.checkpoint_path`, named after
+        /// its range so a restarted job resumes
+        pub fn new(template: BackfillConfig) -> Self {
+            Self {
+                template,
+                jobs: RwLock::new(BTreeMap::new()),
+                next_id: AtomicU64::new(1),
+            }
+        }
+
+        fn checkpoint_path(&self, start_slot: Option<u64>, end_slot: Option<u64>) -> PathBuf {
+            let start = start_slot.map_or_else(|| "resume".to_string(), |slot| slot.to_string());
+            let end = end_slot.map_or_else(|| "latest".to_string(), |slot| slot.to_string());
+            self.template.checkpoint_path.with_file_name(format!("backfill-{}-{}.json", start, end))
+        }
+
+        async fn list(&self) -> Vec<BackfillJobInfo> {
+            let mut infos = Vec::new();
+            for job in self.jobs.read().await.values() {
+                infos.push(job.info.read().await.clone());
+            }
+            infos
+        }
+
+        async fn start(&self, storage: Arc<dyn Storage>, request: BackfillRequest) -> Result<BackfillJobInfo, ApiError> {
+            let config = BackfillConfig {
+                start_slot: request.start_slot,
+                end_slot: request.end_slot,
+                checkpoint_path: self.checkpoint_path(request.start_slot, request.end_slot),
+                skip_votes: request.skip_votes.unwrap_or(self.template.skip_votes),
+                ..self.template.clone()
+            };
+            self.launch(storage, config).await
+        }
+
+        /// Whether gaps in the live slot stream are backfilled automatically
+        pub fn heals_gaps(&self) -> bool {
+            self.template.heal_gaps
+        }
+
+        /// Start a job backfilling every gap the feed reports
+        pub fn heal_gaps(self: Arc<Self>, storage: Arc<dyn Storage>, feed: &DataFeed) {
+            let mut events = feed.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(FeedEvent::SlotGap(gap)) => {
+                            if let Err(e) = self.launch(storage.clone(), self.template.for_gap(&gap)).await {
+                                warn!("Failed to backfill slots {}..={}: {}", gap.first_slot, gap.last_slot, e);
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("Missed {} feed events while watching for slot gaps", skipped);
+                        }
+                        Err(RecvError::Closed) => return,
+                    }
+                }
+            });
+        }
+
+        async fn launch(&self, storage: Arc<dyn Storage>, config: BackfillConfig) -> Result<BackfillJobInfo, ApiError> {
+            let checkpoint_path = config.checkpoint_path.clone();
+            let (start_slot, end_slot) = (config.start_slot, config.end_slot);
+
+            let mut jobs = self.jobs.write().await;
+            for job in jobs.values() {
+                let info = job.info.read().await;
+                if info.state == BackfillState::Running && info.checkpoint_path == checkpoint_path {
+                    return Err(ApiError::BadRequest(format!("Backfill {} is already running this range", info.id)));
+                }
+            }
+
+            let backfill = Backfill::new(config, storage)
+                .map_err(|e| ApiError::Internal(format!("Failed to start backfill: {}", e)))?;
+
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let info = Arc::new(RwLock::new(BackfillJobInfo {
+                id,
+                state: BackfillState::Running,
+                start_slot,
+                end_slot,
+                checkpoint_path,
+                checkpoint: None,
+                error: None,
+                started_at: chrono::Utc::now().timestamp(),
+            }));
+            let stop = backfill.stop_handle();
+            jobs.insert(id, BackfillJob { info: info.clone(), stop });
+
+            let job = info.clone();
+            tokio::spawn(async move {
+                let result = backfill.run().await;
+                let mut info = job.write().await;
+                match result {
+                    Ok(checkpoint) => {
+                        info.state = if checkpoint.is_complete() { BackfillState::Completed } else { BackfillState::Stopped };
+                        info.checkpoint = Some(checkpoint);
+                    }
+                    Err(e) => {
+                        error!("Backfill {} failed: {:#}", info.id, e);
+                        info.state = BackfillState::Failed;
+                        info.error = Some(format!("{:#}", e));
+                        info.checkpoint = BackfillCheckpoint::load(&info.checkpoint_path).ok().flatten();
+                    }
+                }
+            });
+
+            info!("Started backfill {} ({:?}..={:?})", id, start_slot, end_slot);
+            let info = info.read().await.clone();
+            Ok(info)
+        }
+
+        async fn stop(&self, id: u64) -> Result<BackfillJobInfo, ApiError> {
+            let jobs = self.jobs.read().await;
+            let job = jobs.get(&id).ok_or_else(|| ApiError::NotFound(format!("Backfill {} not found", id)))?;
+            job.stop.store(true, Ordering::Relaxed);
+            let info = job.info.read().await.clone();
+            Ok(info)
+        }
+    }
+
+    #[derive(Debug, Deserialize, ToSchema)]
+    pub struct BackfillRequest {
+        /// Defaults to the slot after the store's last processed slot
+        pub start_slot: Option<u64>,
+        /// Defaults to the latest finalized slot
+        pub end_slot: Option<u64>,
+        pub skip_votes: Option<bool>,
+    }
+
+    fn backfills(state: &AppState) -> Result<&Arc<BackfillJobs>, ApiError> {
+        state.backfills.as_ref()
+            .ok_or_else(|| ApiError::NotFound("Backfills are not configured".to_string()))
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/admin/backfills",
+        tag = "admin",
+        responses((status = 200, description = "Backfills started since the server started", body = ApiResponse<Vec<BackfillJobInfo>>))
+    )]
+    pub async fn list_backfills(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<BackfillJobInfo>>>, ApiError> {
+        Ok(Json(ApiResponse::success(backfills(&state)?.list().await)))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/admin/backfills",
+        tag = "admin",
+        request_body = BackfillRequest,
+        responses(
+            (status = 200, description = "The started backfill", body = ApiResponse<BackfillJobInfo>),
+            (status = 400, description = "The range is already being backfilled", body = ApiError)
+        )
+    )]
+    pub async fn start_backfill(
+        State(state): State<AppState>,
+        Json(request): Json<BackfillRequest>,
+    ) -> Result<Json<ApiResponse<BackfillJobInfo>>, ApiError> {
+        if let (Some(start), Some(end)) = (request.start_slot, request.end_slot) {
+            if start > end {
+                return Err(ApiError::BadRequest(format!("Slot range {}..={} is empty", start, end)));
+            }
+        }
+        let job = backfills(&state)?.start(storage(&state)?, request).await?;
+        Ok(Json(ApiResponse::success(job)))
+    }
+
+    #[utoipa::path(
+        post,
+        path = "/admin/backfills/{id}/stop",
+        tag = "admin",
+        params(("id" = u64, Path, description = "Backfill id")),
+        responses(
+            (status = 200, description = "The backfill, which stops after its current batch", body = ApiResponse<BackfillJobInfo>),
+            (status = 404, description = "Not found", body = ApiError)
+        )
+    )]
+    pub async fn stop_backfill(
+        State(state): State<AppState>,
+        Path(id): Path<u64>,
+    ) -> Result<Json<ApiResponse<BackfillJobInfo>>, ApiError> {
+        Ok(Json(ApiResponse::success(backfills(&state)?.stop(id).await?)))
+    }
+}
+
+#[cfg(feature = "store")]
+pub use maintenance::{
+    BackfillJobInfo, BackfillJobs, BackfillRequest, BackfillState, PruneRequest, PruneResult, StoreStats,
+};
+
+pub fn create_admin_router() -> OpenApiRouter<AppState> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_config))
+        .routes(routes!(reload_filters))
+        .routes(routes!(list_peers))
+        .routes(routes!(ban_peer, unban_peer))
+        .routes(routes!(list_allowed_peers))
+        .routes(routes!(allow_peer, disallow_peer))
+        .routes(routes!(list_topics))
+        .routes(routes!(subscribe_topic))
+        .routes(routes!(subscribe_topic_filter))
+        .routes(routes!(unsubscribe_topic))
+        .routes(routes!(get_log_level, set_log_level))
+        .routes(routes!(list_dead_letters))
+        .routes(routes!(retry_dead_letters))
+        .routes(routes!(purge_dead_letters))
+        .merge(create_key_router());
+
+    #[cfg(feature = "store")]
+    let router = router
+        .routes(routes!(maintenance::store_stats))
+        .routes(routes!(maintenance::prune))
+        .routes(routes!(maintenance::list_backfills, maintenance::start_backfill))
+        .routes(routes!(maintenance::stop_backfill));
+
+    scoped(router, RouteScope::All(ApiScope::Admin))
+}
@@ -0,0 +1,449 @@
+//! Rolling statistics
+//!
+//! [`StatsService`] aggregates the stored transactions of the last hour and
+//! day: transactions per second, fees, and the most active programs and
+//! accounts. Windows are measured in slots at the nominal 400ms slot time.
+//!
+//! Transactions are counted into buckets of [`BUCKET_SLOTS`] slots. Each
+//! refresh only reads the rooted slots written since the previous one, drops
+//! buckets older than a day and recomputes the cached windows from the
+//! remaining buckets. Every bucket keeps its [`MAX_TRACKED`] most active
+//! programs and accounts, so the top lists are approximate for keys that are
+//! active in many buckets without leading any of them.
+
+use {
+    crate::{
+        auth::{scoped, RouteScope},
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    anyhow::Result,
+    axum::{
+        extract::{Query, State},
+        Json,
+    },
+    futures::StreamExt,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::Arc,
+        time::Duration,
+    },
+    tokio::{sync::RwLock, task::JoinHandle},
+    tracing::{debug, warn},
+    utoipa::{IntoParams, ToSchema},
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::types::TransactionData,
+    windexer_store::traits::Storage,
+};
+
+const SLOT_DURATION_MS: u64 = 400;
+
+/// About a minute of slots
+pub const BUCKET_SLOTS: u64 = 150;
+
+/// Programs and accounts kept per bucket
+pub const MAX_TRACKED: usize = 1_000;
+
+/// Entries returned unless `limit` is given
+const DEFAULT_LIMIT: usize = 10;
+
+/// Largest `limit` accepted, and the length of the cached top lists
+const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum StatsWindow {
+    #[default]
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "24h")]
+    Day,
+}
+
+impl StatsWindow {
+    const ALL: [StatsWindow; 2] = [StatsWindow::Hour, StatsWindow::Day];
+
+    fn slots(self) -> u64 {
+        let seconds = match self {
+            StatsWindow::Hour => 60 * 60,
+            StatsWindow::Day => 24 * 60 * 60,
+        };
+        seconds * 1000 / SLOT_DURATION_MS
+    }
+}
+
+/// Transactions touching a program or account
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Activity {
+    pub pubkey: String,
+    pub transactions: u64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WindowStats {
+    pub window: StatsWindow,
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub transactions: u64,
+    pub vote_transactions: u64,
+    pub failed_transactions: u64,
+    /// All transactions per second, votes included
+    pub tps: f64,
+    pub non_vote_tps: f64,
+    /// Lamports
+    pub total_fees: u64,
+    /// Lamports per transaction
+    pub average_fee: f64,
+    /// Programs invoked by the most transactions
+    pub top_programs: Vec<Activity>,
+    /// Accounts referenced by the most non-vote transactions, programs excluded
+    pub top_accounts: Vec<Activity>,
+    /// Unix timestamp in seconds
+    pub computed_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TpsStats {
+    pub window: StatsWindow,
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub tps: f64,
+    pub non_vote_tps: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FeeStats {
+    pub window: StatsWindow,
+    pub start_slot: u64,
+    pub end_slot: u64,
+    pub transactions: u64,
+    pub total_fees: u64,
+    pub average_fee: f64,
+}
+
+#[derive(Debug, Default)]
+struct Bucket {
+    transactions: u64,
+    votes: u64,
+    failed: u64,
+    fees: u64,
+    programs: HashMap<String, u64>,
+    accounts: HashMap<String, u64>,
+}
+
+impl Bucket {
+    fn add(&mut self, transaction: &TransactionData) {
+        self.transactions += 1;
+        self.fees += transaction.serializable_meta.fee;
+        if transaction.serializable_meta.status != Some(0) {
+            self.failed += 1;
+        }
+        if transaction.is_vote {
+            self.votes += 1;
+            return;
+        }
+
+        let keys = &transaction.message.account_keys;
+        let mut programs: Vec<u8> = transaction.message.instructions.iter()
+            .map(|instruction| instruction.program_id_index)
+            .collect();
+        programs.sort_unstable();
+        programs.dedup();
+
+        for (index, key) in keys.iter().enumerate() {
+            let counts = if programs.binary_search(&(index as u8)).is_ok() {
+                &mut self.programs
+            } else {
+                &mut self.accounts
+            };
+            *counts.entry(key.to_string()).or_default() += 1;
+        }
+    }
+
+    fn merge(&mut self, other: Bucket) {
+        self.transactions += other.transactions;
+        self.votes += other.votes;
+        self.failed += other.failed;
+        self.fees += other.fees;
+        for (program, count) in other.programs {
+            *self.programs.entry(program).or_default() += count;
+        }
+        for (account, count) in other.accounts {
+            *self.accounts.entry(account).or_default() += count;
+        }
+        truncate_counts(&mut self.programs, MAX_TRACKED);
+        truncate_counts(&mut self.accounts, MAX_TRACKED);
+    }
+}
+
+fn truncate_counts(counts: &mut HashMap<String, u64>, len: usize) {
+    if counts.len() > len {
+        *counts = top(counts, len).into_iter().map(|activity| (activity.pubkey, activity.transactions)).collect();
+    }
+}
+
+fn top(counts: &HashMap<String, u64>, len: usize) -> Vec<Activity> {
+    let mut activity: Vec<Activity> = counts.iter()
+        .map(|(pubkey, &transactions)| Activity { pubkey: pubkey.clone(), transactions })
+        .collect();
+    activity.sort_unstable_by(|a, b| b.transactions.cmp(&a.transactions).then_with(|| a.pubkey.cmp(&b.pubkey)));
+    activity.truncate(len);
+    activity
+}
+
+#[derive(Default)]
+struct StatsState {
+    /// Keyed by `slot / BUCKET_SLOTS`
+    buckets: BTreeMap<u64, Bucket>,
+    scanned_to: Option<u64>,
+    windows: HashMap<StatsWindow, Arc<WindowStats>>,
+}
+
+impl StatsState {
+    fn compute(&self, window: StatsWindow, end_slot: u64) -> WindowStats {
+        let start_slot = (end_slot + 1).saturating_sub(window.slots());
+        let mut total = Bucket::default();
+        for bucket in self.buckets.range(start_slot / BUCKET_SLOTS..).map(|(_, bucket)| bucket) {
+            total.transactions += bucket.transactions;
+            total.votes += bucket.votes;
+            total.failed += bucket.failed;
+            total.fees += bucket.fees;
+            for (program, count) in &bucket.programs {
+                *total.programs.entry(program.clone()).or_default() += count;
+            }
+            for (account, count) in &bucket.accounts {
+                *total.accounts.entry(account.clone()).or_default() += count;
+            }
+        }
+
+        let seconds = ((end_slot - start_slot + 1) * SLOT_DURATION_MS) as f64 / 1000.0;
+        WindowStats {
+            window,
+            start_slot,
+            end_slot,
+            transactions: total.transactions,
+            vote_transactions: total.votes,
+            failed_transactions: total.failed,
+            tps: total.transactions as f64 / seconds,
+            non_vote_tps: (total.transactions - total.votes) as f64 / seconds,
+            total_fees: total.fees,
+            average_fee: if total.transactions == 0 { 0.0 } else { total.fees as f64 / total.transactions as f64 },
+            top_programs: top(&total.programs, MAX_LIMIT),
+            top_accounts: top(&total.accounts, MAX_LIMIT),
+            computed_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Cached rolling statistics over a store
+pub struct StatsService {
+    storage: Arc<dyn Storage>,
+    state: RwLock<StatsState>,
+}
+
+impl StatsService {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            state: RwLock::new(StatsState::default()),
+        }
+    }
+
+    /// Statistics as of the last refresh, `None` before the first one
+    pub async fn window(&self, window: StatsWindow) -> Option<Arc<WindowStats>> {
+        self.state.read().await.windows.get(&window).cloned()
+    }
+
+    /// Read the slots rooted since the last refresh and recompute the windows
+    pub async fn refresh(&self) -> Result<()> {
+        let latest = match self.storage.get_latest_rooted_slot().await? {
+            Some(slot) => slot,
+            None => match self.storage.get_recent_blocks(1).await?.first() {
+                Some(block) => block.slot,
+                None => return Ok(()),
+            },
+        };
+        let oldest = (latest + 1).saturating_sub(StatsWindow::Day.slots());
+        let from = self.state.read().await.scanned_to.map_or(oldest, |slot| (slot + 1).max(oldest));
+
+        let mut scanned = BTreeMap::<u64, Bucket>::new();
+        if from <= latest {
+            let mut transactions = self.storage.stream_transactions_by_slot_range(from, latest);
+            while let Some(transaction) = transactions.next().await {
+                let transaction = transaction?;
+                scanned.entry(transaction.slot / BUCKET_SLOTS).or_default().add(&transaction);
+            }
+        }
+
+        let mut state = self.state.write().await;
+        for (index, bucket) in scanned {
+            state.buckets.entry(index).or_default().merge(bucket);
+        }
+        state.buckets = state.buckets.split_off(&(oldest / BUCKET_SLOTS));
+        state.scanned_to = Some(latest.max(state.scanned_to.unwrap_or_default()));
+
+        for window in StatsWindow::ALL {
+            let stats = Arc::new(state.compute(window, latest));
+            state.windows.insert(window, stats);
+        }
+        debug!("Refreshed statistics through slot {}", latest);
+        Ok(())
+    }
+
+    /// Refresh every `interval` until the task is aborted
+    pub fn spawn(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.refresh().await {
+                    warn!("Failed to refresh statistics: {:#}", e);
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StatsParams {
+    /// `1h` (default) or `24h`
+    pub window: Option<StatsWindow>,
+    /// Number of programs or accounts (default 10, at most 100)
+    pub limit: Option<usize>,
+}
+
+async fn window_stats(state: &AppState, params: &StatsParams) -> Result<Arc<WindowStats>, ApiError> {
+    let stats = state.stats.as_ref()
+        .ok_or_else(|| ApiError::NotFound("Statistics are not enabled".to_string()))?;
+    stats.window(params.window.unwrap_or_default()).await
+        .ok_or_else(|| ApiError::Unavailable("Statistics are still being computed".to_string()))
+}
+
+fn limit(params: &StatsParams) -> usize {
+    params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats",
+    tag = "stats",
+    params(StatsParams),
+    responses(
+        (status = 200, description = "All statistics of the window", body = ApiResponse<WindowStats>),
+        (status = 503, description = "Not computed yet", body = ApiError)
+    )
+)]
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ApiResponse<WindowStats>>, ApiError> {
+    let mut stats = WindowStats::clone(&*window_stats(&state, &params).await?);
+    stats.top_programs.truncate(limit(&params));
+    stats.top_accounts.truncate(limit(&params));
+    Ok(Json(ApiResponse::success(stats)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/tps",
+    tag = "stats",
+    params(StatsParams),
+    responses((status = 200, description = "Transactions per second", body = ApiResponse<TpsStats>))
+)]
+pub async fn get_tps(
+    State(state): State<AppState>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ApiResponse<TpsStats>>, ApiError> {
+    let stats = window_stats(&state, &params).await?;
+    Ok(Json(ApiResponse::success(TpsStats {
+        window: stats.window,
+        start_slot: stats.start_slot,
+        end_slot: stats.end_slot,
+        tps: stats.tps,
+        non_vote_tps: stats.non_vote_tps,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/fees",
+    tag = "stats",
+    params(StatsParams),
+    responses((status = 200, description = "Fee totals in lamports", body = ApiResponse<FeeStats>))
+)]
+pub async fn get_fees(
+    State(state): State<AppState>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ApiResponse<FeeStats>>, ApiError> {
+    let stats = window_stats(&state, &params).await?;
+    Ok(Json(ApiResponse::success(FeeStats {
+        window: stats.window,
+        start_slot: stats.start_slot,
+        end_slot: stats.end_slot,
+        transactions: stats.transactions,
+        total_fees: stats.total_fees,
+        average_fee: stats.average_fee,
+    })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/programs",
+    tag = "stats",
+    params(StatsParams),
+    responses((status = 200, description = "Most invoked programs", body = ApiResponse<Vec<Activity>>))
+)]
+pub async fn get_top_programs(
+    State(state): State<AppState>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ApiResponse<Vec<Activity>>>, ApiError> {
+    let stats = window_stats(&state, &params).await?;
+    Ok(Json(ApiResponse::success(stats.top_programs.iter().take(limit(&params)).cloned().collect())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats/accounts",
+    tag = "stats",
+    params(StatsParams),
+    responses((status = 200, description = "Most referenced accounts", body = ApiResponse<Vec<Activity>>))
+)]
+pub async fn get_top_accounts(
+    State(state): State<AppState>,
+    Query(params): Query<StatsParams>,
+) -> Result<Json<ApiResponse<Vec<Activity>>>, ApiError> {
+    let stats = window_stats(&state, &params).await?;
+    Ok(Json(ApiResponse::success(stats.top_accounts.iter().take(limit(&params)).cloned().collect())))
+}
+
+pub fn create_stats_router() -> OpenApiRouter<AppState> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_stats))
+        .routes(routes!(get_tps))
+        .routes(routes!(get_fees))
+        .routes(routes!(get_top_programs))
+        .routes(routes!(get_top_accounts));
+    scoped(router, RouteScope::ReadWrite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merged_buckets_keep_the_most_active_keys() {
+        let mut bucket = Bucket::default();
+        let mut other = Bucket::default();
+        for index in 0..MAX_TRACKED as u64 + 5 {
+            other.programs.insert(format!("program-{index}"), index + 1);
+        }
+        other.transactions = 3;
+        bucket.merge(other);
+
+        assert_eq!(bucket.transactions, 3);
+        assert_eq!(bucket.programs.len(), MAX_TRACKED);
+        assert!(!bucket.programs.contains_key("program-0"));
+        assert_eq!(top(&bucket.programs, 1)[0].transactions, MAX_TRACKED as u64 + 5);
+    }
+}
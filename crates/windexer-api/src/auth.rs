@@ -0,0 +1,630 @@
+//! Authentication and API keys
+//!
+//! When enabled, every route except health, status and the API docs needs a
+//! credential: an API key in the `X-API-Key` header, or a bearer token in
+//! `Authorization` that is either an API key or an HS256 JWT signed with the
+//! configured secret. WebSocket clients that cannot set headers may pass
+//! `api_key` as a query parameter instead.
+//!
+//! Credentials carry scopes: `read_only` for queries, `stream` for live
+//! subscriptions over WebSocket, SSE and gRPC, and `admin` for everything,
+//! including the key management routes under `/admin/keys`. Each router
+//! declares the scope its routes need with [`scoped`]: reads need
+//! `read_only` and any other method needs `admin`, unless the router says
+//! otherwise, as the query and RPC routers do for their POST reads. Each
+//! credential is rate limited per minute. Keys and tokens may be limited to
+//! one tenant, see [`crate::tenant`].
+//!
+//! API keys look like `wdx_<id>_<secret>`. Only a hash of the secret is kept,
+//! in the store when one is set so keys survive restarts.
+
+use {
+    crate::{
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{
+        extract::{Path, Query, Request, State},
+        http::{header, HeaderMap, Method},
+        middleware::{self, Next},
+        response::Response,
+        Json, Router,
+    },
+    jsonwebtoken::{Algorithm, DecodingKey, Validation},
+    rand::RngCore,
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{
+        collections::HashMap,
+        sync::Arc,
+        time::{Duration, Instant},
+    },
+    tokio::sync::{Mutex, RwLock},
+    tracing::{info, warn},
+    utoipa::ToSchema,
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::types::{ApiKeyRecord, ApiScope},
+};
+
+/// Prefix of every issued API key
+pub const API_KEY_PREFIX: &str = "wdx_";
+
+/// Length of the rate limiting window
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub enabled: bool,
+    /// HS256 secret for JWT bearer tokens; JWTs are rejected without one
+    pub jwt_secret: Option<String>,
+    /// Requests per minute for credentials without their own limit; 0 disables limiting
+    pub default_rate_limit_per_minute: u32,
+    /// Static key with the admin scope, for issuing the first stored keys
+    pub admin_key: Option<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            jwt_secret: None,
+            default_rate_limit_per_minute: 600,
+            admin_key: None,
+        }
+    }
+}
+
+/// Claims accepted in JWT bearer tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: u64,
+    #[serde(default)]
+    pub scopes: Vec<ApiScope>,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    /// Tenant the token is limited to
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+/// The authenticated caller, available to handlers as a request extension
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub subject: String,
+    pub scopes: Vec<ApiScope>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub tenant: Option<String>,
+}
+
+impl Principal {
+    pub fn grants(&self, required: ApiScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Compare every byte rather than stopping at the first difference, so the
+/// time taken does not tell how much of a secret was guessed right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+/// Split `wdx_<id>_<secret>` into its id and secret
+fn parse_api_key(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix(API_KEY_PREFIX)?.split_once('_')
+}
+
+/// Issues, revokes and checks API keys
+#[derive(Default)]
+pub struct ApiKeyManager {
+    keys: RwLock<HashMap<String, ApiKeyRecord>>,
+    #[cfg(feature = "store")]
+    storage: RwLock<Option<Arc<dyn windexer_store::traits::Storage>>>,
+}
+
+impl ApiKeyManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the keys kept in `storage` and persist changes there from now on
+    #[cfg(feature = "store")]
+    pub async fn attach_storage(&self, storage: Arc<dyn windexer_store::traits::Storage>) -> anyhow::Result<()> {
+        let stored = storage.load_api_keys().await?;
+        info!("Loaded {} API keys from the store", stored.len());
+
+        let mut keys = self.keys.write().await;
+        for key in stored {
+            keys.insert(key.id.clone(), key);
+        }
+        *self.storage.write().await = Some(storage);
+        Ok(())
+    }
+
+    #[cfg(feature = "store")]
+    async fn persist(&self, key: &ApiKeyRecord) -> anyhow::Result<()> {
+        match &*self.storage.read().await {
+            Some(storage) => storage.save_api_key(key).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "store"))]
+    async fn persist(&self, _key: &ApiKeyRecord) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Issue a key, returning its record and the key itself, which is not kept
+    pub async fn create_key(
+        &self,
+        name: String,
+        scopes: Vec<ApiScope>,
+        rate_limit_per_minute: Option<u32>,
+        expires_at: Option<i64>,
+        tenant: Option<String>,
+    ) -> anyhow::Result<(ApiKeyRecord, String)> {
+        let id = random_hex(8);
+        let secret = random_hex(32);
+        let record = ApiKeyRecord {
+            id: id.clone(),
+            name,
+            secret_hash: hash_secret(&secret),
+            scopes,
+            rate_limit_per_minute,
+            created_at: chrono::Utc::now().timestamp(),
+            expires_at,
+            revoked: false,
+            tenant,
+        };
+
+        self.persist(&record).await?;
+        self.keys.write().await.insert(id.clone(), record.clone());
+        Ok((record, format!("{}{}_{}", API_KEY_PREFIX, id, secret)))
+    }
+
+    /// Reject a key from now on while keeping its record
+    pub async fn revoke_key(&self, id: &str) -> anyhow::Result<bool> {
+        let mut keys = self.keys.write().await;
+        let Some(key) = keys.get(id) else {
+            return Ok(false);
+        };
+
+        let revoked = ApiKeyRecord { revoked: true, ..key.clone() };
+        self.persist(&revoked).await?;
+        keys.insert(id.to_string(), revoked);
+        Ok(true)
+    }
+
+    pub async fn delete_key(&self, id: &str) -> anyhow::Result<bool> {
+        #[cfg(feature = "store")]
+        {
+            if let Some(storage) = &*self.storage.read().await {
+                storage.delete_api_key(id).await?;
+            }
+        }
+        Ok(self.keys.write().await.remove(id).is_some())
+    }
+
+    pub async fn list_keys(&self) -> Vec<ApiKeyRecord> {
+        let mut keys: Vec<ApiKeyRecord> = self.keys.read().await.values().cloned().collect();
+        keys.sort_by_key(|key| key.created_at);
+        keys
+    }
+
+    /// The active key matching `key`, if any
+    pub async fn authenticate(&self, key: &str) -> Option<ApiKeyRecord> {
+        let (id, secret) = parse_api_key(key)?;
+        let keys = self.keys.read().await;
+        let record = keys.get(id)?;
+
+        (constant_time_eq(record.secret_hash.as_bytes(), hash_secret(secret).as_bytes())
+            && record.is_active(chrono::Utc::now().timestamp()))
+            .then(|| record.clone())
+    }
+}
+
+struct RateWindow {
+    started: Instant,
+    requests: u32,
+}
+
+/// Fixed one-minute request windows per key
+#[derive(Default)]
+pub struct RateLimiter {
+    windows: Mutex<HashMap<String, RateWindow>>,
+}
+
+impl RateLimiter {
+    /// Count a request against `key`, failing once `limit` is reached; 0 is unlimited
+    pub async fn check(&self, key: &str, limit: u32) -> Result<(), ApiError> {
+        if limit == 0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut windows = self.windows.lock().await;
+        windows.retain(|_, window| now.duration_since(window.started) < RATE_WINDOW);
+
+        let window = windows.entry(key.to_string())
+            .or_insert(RateWindow { started: now, requests: 0 });
+        if window.requests >= limit {
+            return Err(ApiError::RateLimited(format!("Rate limit of {} requests per minute exceeded", limit)));
+        }
+        window.requests += 1;
+        Ok(())
+    }
+}
+
+/// Checks credentials and enforces scopes and rate limits
+pub struct AuthService {
+    config: AuthConfig,
+    keys: ApiKeyManager,
+    jwt_key: Option<DecodingKey>,
+    limiter: RateLimiter,
+}
+
+impl AuthService {
+    pub fn new(config: AuthConfig) -> Self {
+        let jwt_key = config.jwt_secret.as_ref().map(|secret| DecodingKey::from_secret(secret.as_bytes()));
+
+        Self {
+            config,
+            keys: ApiKeyManager::new(),
+            jwt_key,
+            limiter: RateLimiter::default(),
+        }
+    }
+
+    pub fn keys(&self) -> &ApiKeyManager {
+        &self.keys
+    }
+
+    fn is_admin_key(&self, credential: &str) -> bool {
+        // Digests have the same length whatever the key's, so comparing them
+        // does not reveal the admin key's length either
+        self.config.admin_key.as_ref().is_some_and(|admin_key| {
+            constant_time_eq(&Sha256::digest(admin_key.as_bytes()), &Sha256::digest(credential.as_bytes()))
+        })
+    }
+
+    pub async fn authenticate(&self, credential: &str) -> Result<Principal, ApiError> {
+        if self.is_admin_key(credential) {
+            return Ok(Principal {
+                subject: "admin".to_string(),
+                scopes: vec![ApiScope::Admin],
+                rate_limit_per_minute: Some(0),
+                tenant: None,
+            });
+        }
+
+        if credential.starts_with(API_KEY_PREFIX) {
+            let key = self.keys.authenticate(credential).await
+                .ok_or_else(|| ApiError::Unauthorized("Invalid or revoked API key".to_string()))?;
+            return Ok(Principal {
+                subject: format!("key:{}", key.id),
+                scopes: key.scopes,
+                rate_limit_per_minute: key.rate_limit_per_minute,
+                tenant: key.tenant,
+            });
+        }
+
+        let jwt_key = self.jwt_key.as_ref()
+            .ok_or_else(|| ApiError::Unauthorized("Invalid API key".to_string()))?;
+        let claims = jsonwebtoken::decode::<Claims>(credential, jwt_key, &Validation::new(Algorithm::HS256))
+            .map_err(|e| ApiError::Unauthorized(format!("Invalid token: {}", e)))?
+            .claims;
+        Ok(Principal {
+            subject: format!("jwt:{}", claims.sub),
+            scopes: claims.scopes,
+            rate_limit_per_minute: claims.rate_limit_per_minute,
+            tenant: claims.tenant,
+        })
+    }
+
+    /// Count a request against the caller's per-minute limit
+    pub async fn check_rate_limit(&self, principal: &Principal) -> Result<(), ApiError> {
+        let limit = principal.rate_limit_per_minute.unwrap_or(self.config.default_rate_limit_per_minute);
+        self.limiter.check(&principal.subject, limit).await
+    }
+}
+
+/// Routes served without a credential, relative to the API prefix
+const PUBLIC_PATHS: &[&str] = &["/health", "/health/live", "/health/ready", "/status", "/openapi.json", "/docs"];
+
+/// Whether `path` is one of [`PUBLIC_PATHS`] under `prefix`, or a Swagger UI
+/// asset under its `/docs/`
+fn is_public(prefix: &str, path: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|path| PUBLIC_PATHS.contains(&path) || path.starts_with("/docs/"))
+}
+
+/// Credential from the `X-API-Key` header or a bearer token
+pub fn header_credential(headers: &HeaderMap) -> Option<String> {
+    if let Some(key) = headers.get("x-api-key").and_then(|value| value.to_str().ok()) {
+        return Some(key.to_string());
+    }
+    if let Some(token) = headers.get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    {
+        return Some(token.trim().to_string());
+    }
+    None
+}
+
+fn credential(request: &Request) -> Option<String> {
+    if let Some(credential) = header_credential(request.headers()) {
+        return Some(credential);
+    }
+    Query::<HashMap<String, String>>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(mut params)| params.remove("api_key"))
+}
+
+/// Marks requests let through because authentication is disabled
+#[derive(Debug, Clone, Copy)]
+struct AuthDisabled;
+
+/// Middleware rejecting requests without a valid credential; the scope each
+/// route needs is checked by the layer [`scoped`] puts on its router
+pub async fn require_auth(State(state): State<AppState>, mut request: Request, next: Next) -> Result<Response, ApiError> {
+    let Some(auth) = state.auth.clone() else {
+        request.extensions_mut().insert(AuthDisabled);
+        return Ok(next.run(request).await);
+    };
+    if is_public(&state.path_prefix, request.uri().path()) || request.method() == Method::OPTIONS {
+        return Ok(next.run(request).await);
+    }
+
+    let credential = credential(&request)
+        .ok_or_else(|| ApiError::Unauthorized("Missing API key or bearer token".to_string()))?;
+    let principal = auth.authenticate(&credential).await?;
+    auth.check_rate_limit(&principal).await?;
+
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+/// Scope the routes of a router need
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteScope {
+    /// `read_only` for GET and HEAD, `admin` for any other method
+    ReadWrite,
+    /// The same scope for every method
+    All(ApiScope),
+}
+
+impl RouteScope {
+    pub fn required(self, method: &Method) -> ApiScope {
+        match self {
+            RouteScope::All(scope) => scope,
+            RouteScope::ReadWrite if method == Method::GET || method == Method::HEAD => ApiScope::ReadOnly,
+            RouteScope::ReadWrite => ApiScope::Admin,
+        }
+    }
+}
+
+/// Reject callers whose credential lacks the route's scope. Only requests
+/// [`require_auth`] let through with authentication disabled may come
+/// without a [`Principal`].
+async fn check_scope(scope: RouteScope, request: Request, next: Next) -> Result<Response, ApiError> {
+    let Some(principal) = request.extensions().get::<Principal>() else {
+        if request.extensions().get::<AuthDisabled>().is_some() {
+            return Ok(next.run(request).await);
+        }
+        return Err(ApiError::Unauthorized("Missing API key or bearer token".to_string()));
+    };
+
+    let required = scope.required(request.method());
+    if !principal.grants(required) {
+        warn!("{} lacks the {} scope for {} {}", principal.subject, required, request.method(), request.uri().path());
+        return Err(ApiError::Forbidden(format!("Requires the {} scope", required)));
+    }
+    Ok(next.run(request).await)
+}
+
+/// Require `scope` for every route of `router`
+pub fn scoped<S: Clone + Send + Sync + 'static>(router: OpenApiRouter<S>, scope: RouteScope) -> OpenApiRouter<S> {
+    router.route_layer(middleware::from_fn(move |request, next| check_scope(scope, request, next)))
+}
+
+/// [`scoped`] for routers left out of the OpenAPI document
+pub fn scoped_router<S: Clone + Send + Sync + 'static>(router: Router<S>, scope: RouteScope) -> Router<S> {
+    router.route_layer(middleware::from_fn(move |request, next| check_scope(scope, request, next)))
+}
+
+/// An API key as listed by the admin routes, without its secret hash
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub revoked: bool,
+    pub tenant: Option<String>,
+}
+
+impl From<ApiKeyRecord> for ApiKeyInfo {
+    fn from(key: ApiKeyRecord) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            scopes: key.scopes,
+            rate_limit_per_minute: key.rate_limit_per_minute,
+            created_at: key.created_at,
+            expires_at: key.expires_at,
+            revoked: key.revoked,
+            tenant: key.tenant,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub expires_at: Option<i64>,
+    /// Limit the key to one tenant's data
+    pub tenant: Option<String>,
+}
+
+/// A newly issued key; `key` is only ever shown here
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedKey {
+    pub key: String,
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+}
+
+fn auth_service(state: &AppState) -> Result<&Arc<AuthService>, ApiError> {
+    state.auth.as_ref().ok_or_else(|| ApiError::NotFound("Authentication is disabled".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/keys",
+    tag = "admin",
+    responses((status = 200, description = "Issued keys", body = ApiResponse<Vec<ApiKeyInfo>>))
+)]
+async fn list_keys(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<ApiKeyInfo>>>, ApiError> {
+    let keys = auth_service(&state)?.keys().list_keys().await;
+    Ok(Json(ApiResponse::success(keys.into_iter().map(ApiKeyInfo::from).collect())))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/keys",
+    tag = "admin",
+    request_body = CreateKeyRequest,
+    responses(
+        (status = 200, description = "The new key, shown only once", body = ApiResponse<CreatedKey>),
+        (status = 400, description = "Invalid request", body = ApiError)
+    )
+)]
+async fn create_key(
+    State(state): State<AppState>,
+    Json(request): Json<CreateKeyRequest>,
+) -> Result<Json<ApiResponse<CreatedKey>>, ApiError> {
+    if request.scopes.is_empty() {
+        return Err(ApiError::BadRequest("A key needs at least one scope".to_string()));
+    }
+    if let Some(tenant) = &request.tenant {
+        if state.tenants.get(tenant).is_none() {
+            return Err(ApiError::BadRequest(format!("Unknown tenant {}", tenant)));
+        }
+    }
+
+    let (record, key) = auth_service(&state)?.keys()
+        .create_key(request.name, request.scopes, request.rate_limit_per_minute, request.expires_at, request.tenant)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store API key: {}", e)))?;
+    info!("Issued API key {} ({})", record.id, record.name);
+
+    Ok(Json(ApiResponse::success(CreatedKey { key, info: record.into() })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/keys/{id}/revoke",
+    tag = "admin",
+    params(("id" = String, Path, description = "Key id")),
+    responses(
+        (status = 200, description = "Id of the revoked key", body = ApiResponse<String>),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+async fn revoke_key(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<ApiResponse<String>>, ApiError> {
+    let revoked = auth_service(&state)?.keys().revoke_key(&id).await
+        .map_err(|e| ApiError::Internal(format!("Failed to store API key: {}", e)))?;
+    if !revoked {
+        return Err(ApiError::NotFound(format!("API key {} not found", id)));
+    }
+    Ok(Json(ApiResponse::success(id)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/admin/keys/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Key id")),
+    responses(
+        (status = 200, description = "Id of the deleted key", body = ApiResponse<String>),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+async fn delete_key(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<ApiResponse<String>>, ApiError> {
+    let deleted = auth_service(&state)?.keys().delete_key(&id).await
+        .map_err(|e| ApiError::Internal(format!("Failed to delete API key: {}", e)))?;
+    if !deleted {
+        return Err(ApiError::NotFound(format!("API key {} not found", id)));
+    }
+    Ok(Json(ApiResponse::success(id)))
+}
+
+/// Key management routes, served under `/admin` by [`crate::admin::create_admin_router`]
+pub fn create_key_router() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(list_keys, create_key))
+        .routes(routes!(delete_key))
+        .routes(routes!(revoke_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_issued_key_authenticates_until_revoked() {
+        let keys = ApiKeyManager::new();
+        let (record, key) = keys.create_key("test".to_string(), vec![ApiScope::ReadOnly], None, None, None).await.unwrap();
+
+        assert_eq!(keys.authenticate(&key).await.map(|key| key.id), Some(record.id.clone()));
+        assert!(keys.authenticate(&format!("{}{}_wrong", API_KEY_PREFIX, record.id)).await.is_none());
+
+        keys.revoke_key(&record.id).await.unwrap();
+        assert!(keys.authenticate(&key).await.is_none());
+    }
+
+    #[test]
+    fn test_writes_need_admin_unless_the_router_says_otherwise() {
+        assert_eq!(RouteScope::ReadWrite.required(&Method::GET), ApiScope::ReadOnly);
+        assert_eq!(RouteScope::ReadWrite.required(&Method::PUT), ApiScope::Admin);
+        assert_eq!(RouteScope::ReadWrite.required(&Method::POST), ApiScope::Admin);
+        assert_eq!(RouteScope::All(ApiScope::ReadOnly).required(&Method::POST), ApiScope::ReadOnly);
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_route_ending_in_status_needs_a_key() {
+        use {axum::body::Body, tower::ServiceExt};
+
+        let config = crate::rest::ApiConfig {
+            auth: AuthConfig { enabled: true, admin_key: Some("admin".to_string()), ..AuthConfig::default() },
+            ..crate::rest::ApiConfig::default()
+        };
+        let router = crate::rest::ApiServer::new(config).create_router();
+
+        assert!(is_public("/api", "/api/status"));
+        assert!(!is_public("/api", "/api/admin/keys/status"));
+        assert!(!is_public("/api", "/api/admin/peers/docs/ban"));
+
+        let request = Request::builder().method(Method::DELETE).uri("/api/admin/keys/status").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+}
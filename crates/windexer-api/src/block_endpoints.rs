@@ -5,12 +5,17 @@ use axum::{
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 use tokio::sync::broadcast;
 
+use windexer_common::types::{ApiScope, Page};
+
+use crate::auth::{scoped, scoped_router, RouteScope};
 use crate::rest::AppState;
-use crate::types::{ApiResponse, ApiError};
+use crate::types::{recent_page, ApiResponse, ApiError};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct BlockData {
     pub slot: u64,
     pub parent_slot: u64,
@@ -23,7 +28,7 @@ pub struct BlockData {
     pub rewards: Option<Vec<Reward>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Reward {
     pub pubkey: String,
     pub lamports: i64,
@@ -31,13 +36,49 @@ pub struct Reward {
     pub reward_type: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl From<&windexer_common::types::BlockData> for BlockData {
+    fn from(block: &windexer_common::types::BlockData) -> Self {
+        Self {
+            slot: block.slot,
+            parent_slot: block.parent_slot.unwrap_or_default(),
+            blockhash: block.blockhash.clone().unwrap_or_default(),
+            previous_blockhash: block.parent_blockhash.clone().unwrap_or_default(),
+            block_time: block.timestamp,
+            block_height: block.block_height,
+            transaction_count: block.transaction_count.unwrap_or_default(),
+            leader: String::new(),
+            rewards: block.rewards.as_ref().map(|rewards| {
+                rewards.iter().map(|reward| Reward {
+                    pubkey: reward.pubkey.clone(),
+                    lamports: reward.lamports,
+                    post_balance: reward.post_balance,
+                    reward_type: reward.reward_type.map(|reward_type| reward_type.to_string()),
+                }).collect()
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct BlockQueryParams {
     pub limit: Option<usize>,
     pub before: Option<u64>,
     pub after: Option<u64>,
+    pub cursor: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/blocks/{slot}",
+    tag = "blocks",
+    params(("slot" = u64, Path, description = "Block slot")),
+    responses(
+        (status = 200, description = "Block", body = ApiResponse<BlockData>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_block(
     State(state): State<AppState>,
     Path(slot): Path<u64>,
@@ -58,6 +99,16 @@ pub async fn get_block(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/blocks/latest",
+    tag = "blocks",
+    responses(
+        (status = 200, description = "Latest block", body = ApiResponse<BlockData>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_latest_block(
     State(state): State<AppState>,
 ) -> Result<Json<ApiResponse<BlockData>>, ApiError> {
@@ -77,28 +128,50 @@ pub async fn get_latest_block(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/blocks",
+    tag = "blocks",
+    params(BlockQueryParams),
+    responses(
+        (status = 200, description = "Recent blocks", body = ApiResponse<Page<BlockData>>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_blocks(
     State(state): State<AppState>,
     Query(params): Query<BlockQueryParams>,
-) -> Result<Json<ApiResponse<Vec<BlockData>>>, ApiError> {
+) -> Result<Json<ApiResponse<Page<BlockData>>>, ApiError> {
     let limit = params.limit.unwrap_or(10);
     
     let helius_client = state.helius_client.as_ref().ok_or_else(|| {
         ApiError::Internal("Helius client not initialized".to_string())
     })?;
     
-    match helius_client.get_blocks(limit).await {
-        Ok(blocks) => {
-            tracing::debug!("Helius blocks: {:?}", blocks);
-            Ok(Json(ApiResponse::success(blocks)))
-        }
-        Err(e) => {
-            tracing::error!("Error fetching blocks from Helius: {}", e);
-            Err(ApiError::Internal(format!("Failed to fetch blocks: {}", e)))
+    // Resume at the slot the cursor is anchored on rather than the tip
+    let page = recent_page(params.cursor.as_deref(), limit, |block: &BlockData| block.slot, |cursor, n| async move {
+        let from = (cursor.offset > 0).then_some(cursor.slot);
+        match helius_client.get_blocks_from(from, n).await {
+            Ok(blocks) => {
+                tracing::debug!("Helius blocks: {:?}", blocks);
+                Ok(blocks)
+            }
+            Err(e) => {
+                tracing::error!("Error fetching blocks from Helius: {}", e);
+                Err(ApiError::Internal(format!("Failed to fetch blocks: {}", e)))
+            }
         }
-    }
+    }).await?;
+    Ok(Json(ApiResponse::success(page)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/ws/blocks",
+    tag = "streams",
+    responses((status = 101, description = "WebSocket of `BlockData` updates"))
+)]
 pub async fn block_stream(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
@@ -254,19 +327,22 @@ async fn handle_block_websocket(
     });
 }
 
-pub fn create_block_router() -> Router<AppState> {
-    Router::new()
-        .route("/blocks/latest", get(get_latest_block))
-        .route("/blocks/:slot", get(get_block))
-        .route("/blocks", get(get_blocks))
-        .route("/ws/blocks", get(block_stream))
+pub fn create_block_router() -> OpenApiRouter<AppState> {
+    let reads = OpenApiRouter::new()
+        .routes(routes!(get_latest_block))
+        .routes(routes!(get_block))
+        .routes(routes!(get_blocks));
+    let streams = OpenApiRouter::new().routes(routes!(block_stream));
+
+    scoped(reads, RouteScope::ReadWrite).merge(scoped(streams, RouteScope::All(ApiScope::Stream)))
 }
 
 pub fn create_jito_compat_blocks_router() -> Router<AppState> {
-    Router::new()
+    let router = Router::new()
         .route("/blocks", get(get_blocks_jito_compat))
         .route("/blocks/:slot", get(get_block_by_slot_jito_compat))
-        .route("/blocks/latest", get(get_latest_block_jito_compat))
+        .route("/blocks/latest", get(get_latest_block_jito_compat));
+    scoped_router(router, RouteScope::ReadWrite)
 }
 
 async fn get_blocks_jito_compat(
@@ -0,0 +1,252 @@
+//! Read-through cache for upstream lookups
+//!
+//! [`ReadThroughCache`] sits in front of Helius in the account and
+//! transaction data managers. Values live for `ttl`, and lookups the upstream
+//! answered with "not found" are remembered for the shorter `negative_ttl`,
+//! so repeated requests for missing keys don't each cost an upstream call.
+//! Concurrent misses for the same key share one upstream request. Failed
+//! loads are not cached.
+
+use {
+    anyhow::{anyhow, Result},
+    futures::future::{BoxFuture, FutureExt, Shared},
+    serde::Serialize,
+    std::{
+        collections::{HashMap, VecDeque},
+        future::Future,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        time::{Duration, Instant},
+    },
+    tokio::sync::RwLock,
+};
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// How long a found value is served without asking upstream again
+    pub ttl: Duration,
+    /// How long a "not found" answer is remembered
+    pub negative_ttl: Duration,
+    /// Entries kept before the oldest are evicted
+    pub capacity: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(30),
+            negative_ttl: Duration::from_secs(5),
+            capacity: 100_000,
+        }
+    }
+}
+
+/// Where a cached lookup was answered from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Cache,
+    /// Joined a load started by a concurrent request
+    Coalesced,
+    Upstream,
+}
+
+/// Counters since the cache was created
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub negative_hits: u64,
+    pub misses: u64,
+    pub coalesced: u64,
+    pub load_errors: u64,
+    pub evictions: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    negative_hits: AtomicU64,
+    misses: AtomicU64,
+    coalesced: AtomicU64,
+    load_errors: AtomicU64,
+    evictions: AtomicU64,
+}
+
+struct Entry<V> {
+    /// `None` caches a "not found" answer
+    value: Option<V>,
+    inserted_at: Instant,
+    expires_at: Instant,
+}
+
+type Flight<V> = Shared<BoxFuture<'static, Result<Option<V>, Arc<anyhow::Error>>>>;
+
+struct Inner<V> {
+    config: CacheConfig,
+    entries: RwLock<HashMap<String, Entry<V>>>,
+    /// Keys in insertion order, for eviction
+    order: RwLock<VecDeque<(String, Instant)>>,
+    inflight: Mutex<HashMap<String, Flight<V>>>,
+    counters: Counters,
+}
+
+impl<V: Clone + Send + Sync + 'static> Inner<V> {
+    async fn insert(&self, key: &str, value: Option<V>) {
+        let now = Instant::now();
+        let ttl = if value.is_some() { self.config.ttl } else { self.config.negative_ttl };
+        let mut entries = self.entries.write().await;
+        let mut order = self.order.write().await;
+
+        while entries.len() >= self.config.capacity && !entries.contains_key(key) {
+            let Some((oldest, inserted_at)) = order.pop_front() else { break };
+            // Keys re-inserted since are found again later in the queue
+            if entries.get(&oldest).is_some_and(|entry| entry.inserted_at == inserted_at) {
+                entries.remove(&oldest);
+                self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        entries.insert(key.to_string(), Entry { value, inserted_at: now, expires_at: now + ttl });
+        order.push_back((key.to_string(), now));
+        // Stale queue entries of keys that were overwritten would otherwise pile up
+        if order.len() > self.config.capacity.saturating_mul(2) {
+            order.retain(|(key, inserted_at)| entries.get(key).is_some_and(|entry| entry.inserted_at == *inserted_at));
+        }
+    }
+}
+
+/// TTL cache keyed by string that loads misses once, however many callers wait
+pub struct ReadThroughCache<V> {
+    inner: Arc<Inner<V>>,
+}
+
+impl<V: Clone + Send + Sync + 'static> ReadThroughCache<V> {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                config,
+                entries: RwLock::new(HashMap::new()),
+                order: RwLock::new(VecDeque::new()),
+                inflight: Mutex::new(HashMap::new()),
+                counters: Counters::default(),
+            }),
+        }
+    }
+
+    /// Unexpired cached answer for `key`; `Some(None)` is a cached "not found"
+    pub async fn peek(&self, key: &str) -> Option<Option<V>> {
+        let entries = self.inner.entries.read().await;
+        entries.get(key)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Returns the cached answer for `key`, or loads it with `load`, where
+    /// `Ok(None)` means upstream does not have it
+    pub async fn get_or_load<F, Fut>(&self, key: &str, load: F) -> Result<(Option<V>, Source)>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<V>>> + Send + 'static,
+    {
+        let counters = &self.inner.counters;
+        if let Some(value) = self.peek(key).await {
+            let counter = if value.is_some() { &counters.hits } else { &counters.negative_hits };
+            counter.fetch_add(1, Ordering::Relaxed);
+            return Ok((value, Source::Cache));
+        }
+
+        let (flight, source) = {
+            let mut inflight = self.inner.inflight.lock().expect("inflight lock poisoned");
+            match inflight.get(key) {
+                Some(flight) => {
+                    counters.coalesced.fetch_add(1, Ordering::Relaxed);
+                    (flight.clone(), Source::Coalesced)
+                }
+                None => {
+                    counters.misses.fetch_add(1, Ordering::Relaxed);
+                    // The flight finishes itself, so it completes even if this
+                    // caller goes away, as long as another one picks it up
+                    let inner = self.inner.clone();
+                    let owned_key = key.to_string();
+                    let load = load();
+                    let flight = async move {
+                        let result = load.await;
+                        match &result {
+                            Ok(value) => inner.insert(&owned_key, value.clone()).await,
+                            Err(_) => {
+                                inner.counters.load_errors.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                        inner.inflight.lock().expect("inflight lock poisoned").remove(&owned_key);
+                        result.map_err(Arc::new)
+                    }
+                    .boxed()
+                    .shared();
+                    inflight.insert(key.to_string(), flight.clone());
+                    (flight, Source::Upstream)
+                }
+            }
+        };
+
+        let value = flight.await.map_err(|e| anyhow!("{:#}", e))?;
+        Ok((value, source))
+    }
+
+    /// Unexpired values, for lookups the upstream cannot answer by key
+    pub async fn values(&self) -> Vec<V> {
+        let now = Instant::now();
+        let entries = self.inner.entries.read().await;
+        entries.values()
+            .filter(|entry| entry.expires_at > now)
+            .filter_map(|entry| entry.value.clone())
+            .collect()
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let counters = &self.inner.counters;
+        CacheStats {
+            entries: self.inner.entries.read().await.len(),
+            hits: counters.hits.load(Ordering::Relaxed),
+            negative_hits: counters.negative_hits.load(Ordering::Relaxed),
+            misses: counters.misses.load(Ordering::Relaxed),
+            coalesced: counters.coalesced.load(Ordering::Relaxed),
+            load_errors: counters.load_errors.load(Ordering::Relaxed),
+            evictions: counters.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::atomic::AtomicUsize,
+    };
+
+    #[tokio::test]
+    async fn coalesces_concurrent_misses_and_caches_not_found() {
+        let cache = ReadThroughCache::<u64>::new(CacheConfig::default());
+        let loads = Arc::new(AtomicUsize::new(0));
+
+        let lookups = (0..8).map(|_| {
+            let loads = loads.clone();
+            cache.get_or_load("missing", move || async move {
+                loads.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok(None)
+            })
+        });
+        for result in futures::future::join_all(lookups).await {
+            assert_eq!(result.unwrap().0, None);
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        let (value, source) = cache.get_or_load("missing", || async { Ok(Some(1)) }).await.unwrap();
+        assert_eq!((value, source), (None, Source::Cache));
+
+        let stats = cache.stats().await;
+        assert_eq!((stats.misses, stats.coalesced, stats.negative_hits), (1, 7, 1));
+    }
+}
@@ -1,20 +1,21 @@
 // crates/windexer-api/src/endpoints.rs
 
 use axum::{
-    routing::{get, post},
     Json, extract::{State, Path, Query},
     response::{IntoResponse, Response},
     http::StatusCode,
-    Router,
 };
 use serde::{Serialize, Deserialize};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::types::{ApiResponse, ApiError};
+use crate::auth::{scoped, RouteScope};
 use crate::rest::AppState;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ValidatorInfo {
     pub identity: String,
     pub version: String,
@@ -23,7 +24,7 @@ pub struct ValidatorInfo {
     pub metrics: HashMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct DeploymentInfo {
     pub id: String,
     pub environment: String,
@@ -33,7 +34,7 @@ pub struct DeploymentInfo {
     pub nodes: Vec<NodeSummary>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct NodeSummary {
     pub id: String,
     pub node_type: String,
@@ -41,7 +42,7 @@ pub struct NodeSummary {
     pub address: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct DeploymentConfig {
     pub node_count: usize,
     pub indexer_count: usize,
@@ -50,12 +51,18 @@ pub struct DeploymentConfig {
     pub env_vars: HashMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateDeploymentRequest {
     pub config: DeploymentConfig,
     pub restart: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/deployment",
+    tag = "deployment",
+    responses((status = 200, description = "Deployment layout", body = ApiResponse<DeploymentInfo>))
+)]
 pub async fn get_deployment_info(
     State(state): State<AppState>
 ) -> Json<ApiResponse<DeploymentInfo>> {
@@ -85,6 +92,13 @@ pub async fn get_deployment_info(
     Json(ApiResponse::success(info))
 }
 
+#[utoipa::path(
+    post,
+    path = "/deployment",
+    tag = "deployment",
+    request_body = UpdateDeploymentRequest,
+    responses((status = 200, description = "Updated deployment layout", body = ApiResponse<DeploymentInfo>))
+)]
 pub async fn update_deployment(
     State(state): State<AppState>,
     Json(request): Json<UpdateDeploymentRequest>
@@ -125,6 +139,12 @@ pub async fn update_deployment(
     Json(ApiResponse::success(info))
 }
 
+#[utoipa::path(
+    get,
+    path = "/validator",
+    tag = "deployment",
+    responses((status = 200, description = "Validator information", body = ApiResponse<ValidatorInfo>))
+)]
 pub async fn get_validator_info(
     State(state): State<AppState>
 ) -> Json<ApiResponse<ValidatorInfo>> {
@@ -144,9 +164,9 @@ pub async fn get_validator_info(
     Json(ApiResponse::success(info))
 }
 
-pub fn create_deployment_router() -> Router<AppState> {
-    Router::new()
-        .route("/deployment", get(get_deployment_info))
-        .route("/deployment", post(update_deployment))
-        .route("/validator", get(get_validator_info))
-} 
\ No newline at end of file
+pub fn create_deployment_router() -> OpenApiRouter<AppState> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_deployment_info, update_deployment))
+        .routes(routes!(get_validator_info));
+    scoped(router, RouteScope::ReadWrite)
+}
\ No newline at end of file
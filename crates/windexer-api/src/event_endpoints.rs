@@ -0,0 +1,153 @@
+use axum::{
+    extract::{Query, State, WebSocketUpgrade},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use std::{str::FromStr, sync::Arc};
+use utoipa::IntoParams;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use windexer_common::{feed::FeedEvent, types::ApiScope};
+use windexer_store::decoders::{DecoderRegistry, ProgramEvent};
+
+use crate::auth::{scoped, RouteScope};
+use crate::rest::AppState;
+use crate::subscriptions::{stream_to_sse, stream_to_websocket, subscription_hub, Commitment, Subscription, SubscriptionHub};
+use crate::types::{ApiResponse, ApiError};
+use crate::tenant::TenantContext;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventQueryParams {
+    /// Program that logged the events
+    pub program: Option<String>,
+    /// Event name: `invoke`, `success`, `failure`, `data` or the name of an
+    /// event in the program's IDL
+    pub name: Option<String>,
+    /// Events to return, newest first; at most 1000
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventUpdateParams {
+    /// Program that logged the events
+    pub program: Option<String>,
+    /// Event name: `invoke`, `success`, `failure`, `data` or the name of an
+    /// event in the program's IDL
+    pub name: Option<String>,
+    pub commitment: Option<Commitment>,
+}
+
+fn parse_program(program: Option<&str>) -> Result<Option<Pubkey>, ApiError> {
+    program
+        .map(|program| Pubkey::from_str(program).map_err(|e| ApiError::BadRequest(format!("Invalid program {}: {}", program, e))))
+        .transpose()
+}
+
+#[utoipa::path(
+    get,
+    path = "/events",
+    tag = "events",
+    params(EventQueryParams),
+    responses(
+        (status = 200, description = "Latest events logged by transactions, newest first", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError)
+    )
+)]
+pub async fn get_events(
+    State(state): State<AppState>,
+    Query(params): Query<EventQueryParams>,
+) -> Result<Json<ApiResponse<Vec<ProgramEvent>>>, ApiError> {
+    parse_program(params.program.as_deref())?;
+    let storage = state.store().ok_or_else(|| {
+        ApiError::Internal("Storage not initialized".to_string())
+    })?;
+
+    let limit = params.limit.unwrap_or(100).min(1000);
+    match storage.get_events(params.program.as_deref(), params.name.as_deref(), limit).await {
+        Ok(events) => Ok(Json(ApiResponse::success(events))),
+        Err(e) => Err(ApiError::BadRequest(format!("Failed to fetch events: {}", e))),
+    }
+}
+
+/// Decoders for live events: those of the loaded IDLs, or the built-in ones
+fn decoders(state: &AppState) -> Arc<DecoderRegistry> {
+    match &state.idl_registry {
+        Some(idl_registry) => idl_registry.decoders().clone(),
+        None => Arc::new(DecoderRegistry::with_builtin()),
+    }
+}
+
+fn subscribe_events(
+    hub: &Arc<SubscriptionHub>,
+    decoders: Arc<DecoderRegistry>,
+    params: EventUpdateParams,
+) -> Result<Subscription<Vec<ProgramEvent>>, ApiError> {
+    let program = parse_program(params.program.as_deref())?;
+    let name = params.name;
+
+    Ok(hub.subscribe(params.commitment.unwrap_or_default(), move |event| match event {
+        FeedEvent::Transaction(transaction) => {
+            let events: Vec<ProgramEvent> = decoders.decode_events(transaction)
+                .into_iter()
+                .filter(|event| event.matches(program.as_ref(), name.as_deref()))
+                .collect();
+            (!events.is_empty()).then_some((transaction.slot, events))
+        }
+        _ => None,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws/events",
+    tag = "streams",
+    params(EventUpdateParams),
+    responses((status = 101, description = "WebSocket of the matching events of each transaction"))
+)]
+pub async fn event_stream(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(params): Query<EventUpdateParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let hub = subscription_hub(&state)?;
+    let subscription = subscribe_events(&hub, decoders(&state), params)?;
+
+    // The connection outlives the request and its tenant scope
+    let tenant = TenantContext::current();
+    Ok(ws.on_upgrade(move |socket| tenant.scope(async move {
+        state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+        stream_to_websocket(socket, subscription).await;
+        state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+    })))
+}
+
+/// Events as Server-Sent Events, for clients that cannot use WebSockets
+#[utoipa::path(
+    get,
+    path = "/stream/events",
+    tag = "streams",
+    params(EventUpdateParams),
+    responses((status = 200, description = "`events` events carrying the matching events of each transaction", content_type = "text/event-stream"))
+)]
+pub async fn event_events(
+    State(state): State<AppState>,
+    Query(params): Query<EventUpdateParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let hub = subscription_hub(&state)?;
+    let subscription = subscribe_events(&hub, decoders(&state), params)?;
+    state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+    Ok(stream_to_sse(subscription, "events"))
+}
+
+pub fn create_event_router() -> OpenApiRouter<AppState> {
+    let reads = OpenApiRouter::new().routes(routes!(get_events));
+    let streams = OpenApiRouter::new()
+        .routes(routes!(event_stream))
+        .routes(routes!(event_events));
+
+    scoped(reads, RouteScope::ReadWrite).merge(scoped(streams, RouteScope::All(ApiScope::Stream)))
+}
@@ -0,0 +1,595 @@
+//! GraphQL API
+//!
+//! Serves accounts, transactions, blocks and token balances at `/graphql`,
+//! with live account and transaction subscriptions over WebSocket at
+//! `/graphql/ws`. Resolvers read through the same [`AppState`] as the REST
+//! routes: the account and transaction data managers, the Helius client for
+//! blocks, the store for token balances and the subscription hub for live
+//! updates.
+//!
+//! Relations can be followed from one object to the next, such as
+//! `block { transactions { items { accounts { tokenBalances } } } }`. Lists
+//! are paged like the REST API, with `first` and an opaque `after` cursor.
+
+use {
+    crate::{
+        account_endpoints::AccountData,
+        auth::{scoped_router, RouteScope},
+        block_endpoints::BlockData,
+        rest::AppState,
+        subscriptions::{parse_filter, parse_pubkeys, Commitment, Lagged},
+        tenant::TenantContext,
+        transaction_endpoints::{self, InstructionData, TransactionData},
+        types::{recent_page, ApiError},
+    },
+    async_graphql::{
+        futures_util::{stream, Stream},
+        Context, Enum, Error, Object, OutputType, Result, Schema, SimpleObject, Subscription,
+    },
+    async_graphql::http::ALL_WEBSOCKET_PROTOCOLS,
+    async_graphql_axum::{GraphQL, GraphQLProtocol, GraphQLWebSocket},
+    axum::{
+        extract::WebSocketUpgrade,
+        response::{IntoResponse, Response},
+        routing::get,
+        Router,
+    },
+    windexer_common::{
+        feed::FeedEvent,
+        filter::{Predicate, Subject},
+        types::{ApiScope, Cursor, Page as CommonPage, MAX_PAGE_WINDOW},
+    },
+};
+
+/// Items per page unless `first` is given
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// Largest `first` accepted
+const MAX_PAGE_SIZE: usize = 100;
+
+/// Deepest relation nesting accepted per query
+const MAX_QUERY_DEPTH: usize = 10;
+
+pub type WindexerSchema = Schema<QueryRoot, async_graphql::EmptyMutation, SubscriptionRoot>;
+
+fn page_size(first: Option<usize>) -> usize {
+    first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+}
+
+/// Offset and size of a page of a list in a fixed order
+fn page_bounds(first: Option<usize>, after: Option<&str>) -> Result<(usize, usize)> {
+    let offset = Cursor::decode_opt(after)?.offset;
+    let offset = usize::try_from(offset).ok()
+        .filter(|offset| *offset <= MAX_PAGE_WINDOW)
+        .ok_or_else(|| Error::new(format!("Pages reach at most {} items back", MAX_PAGE_WINDOW)))?;
+    Ok((offset, page_size(first)))
+}
+
+/// A page of results and the cursor of the next one
+#[derive(SimpleObject)]
+#[graphql(concrete(name = "AccountPage", params(Account)))]
+#[graphql(concrete(name = "TransactionPage", params(Transaction)))]
+#[graphql(concrete(name = "BlockPage", params(Block)))]
+pub struct Page<T: OutputType> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T: OutputType> Page<T> {
+    fn from_offset<U>(items: Vec<U>, offset: usize, limit: usize) -> Self
+    where
+        T: From<U>,
+    {
+        Self::from_common(CommonPage::from_offset(items, offset, limit))
+    }
+
+    fn from_common<U>(page: CommonPage<U>) -> Self
+    where
+        T: From<U>,
+    {
+        Self {
+            items: page.items.into_iter().map(T::from).collect(),
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq, Default)]
+#[graphql(remote = "crate::subscriptions::Commitment")]
+pub enum CommitmentLevel {
+    #[default]
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+#[derive(SimpleObject)]
+pub struct TokenBalance {
+    pub owner: String,
+    pub mint: String,
+    /// Raw amount in base units
+    pub amount: String,
+    pub decimals: Option<u8>,
+    pub ui_amount: Option<f64>,
+    /// Token accounts the amount is summed over
+    pub token_accounts: u32,
+}
+
+#[cfg(feature = "store")]
+impl From<windexer_store::index::TokenBalance> for TokenBalance {
+    fn from(balance: windexer_store::index::TokenBalance) -> Self {
+        Self {
+            owner: balance.owner.to_string(),
+            mint: balance.mint.to_string(),
+            amount: balance.amount.to_string(),
+            decimals: balance.decimals,
+            ui_amount: balance.decimals.map(|decimals| balance.amount as f64 / 10f64.powi(decimals as i32)),
+            token_accounts: balance.token_accounts,
+        }
+    }
+}
+
+#[cfg(feature = "store")]
+fn storage(state: &AppState) -> Result<std::sync::Arc<dyn windexer_store::traits::Storage>> {
+    state.store().ok_or_else(|| Error::new("Store not initialized"))
+}
+
+async fn token_balances(state: &AppState, owner: &str) -> Result<Vec<TokenBalance>> {
+    #[cfg(feature = "store")]
+    {
+        let balances = storage(state)?.get_token_balances(owner).await?;
+        Ok(balances.into_iter().map(TokenBalance::from).collect())
+    }
+    #[cfg(not(feature = "store"))]
+    {
+        let _ = (state, owner);
+        Err(Error::new("Token balances need the store feature"))
+    }
+}
+
+async fn token_holders(state: &AppState, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+    #[cfg(feature = "store")]
+    {
+        let holders = storage(state)?.get_token_holders(mint, limit).await?;
+        Ok(holders.into_iter().map(TokenBalance::from).collect())
+    }
+    #[cfg(not(feature = "store"))]
+    {
+        let _ = (state, mint, limit);
+        Err(Error::new("Token balances need the store feature"))
+    }
+}
+
+/// Commitment of point lookups; finalized when omitted, as in Solana RPC
+fn lookup_commitment(commitment: Option<CommitmentLevel>) -> Commitment {
+    commitment.map_or(Commitment::Finalized, Into::into)
+}
+
+async fn fetch_account(state: &AppState, pubkey: &str, commitment: Commitment) -> Result<AccountData> {
+    let manager = state.account_data_manager.as_ref()
+        .ok_or_else(|| Error::new("Account data manager not initialized"))?;
+    Ok(manager.get_account(pubkey, commitment).await?)
+}
+
+async fn fetch_transaction(state: &AppState, signature: &str, commitment: Commitment) -> Result<TransactionData> {
+    let manager = state.transaction_data_manager.as_ref()
+        .ok_or_else(|| Error::new("Transaction data manager not initialized"))?;
+    Ok(manager.get_transaction(signature, commitment).await?)
+}
+
+async fn transactions_by_account(state: &AppState, account: &str, after: Option<&str>, limit: usize) -> Result<Page<Transaction>> {
+    let page = recent_page(after, limit, |transaction: &TransactionData| transaction.slot, |_, n| async move {
+        transaction_endpoints::transactions_by_account(state, account, n).await
+    }).await?;
+    Ok(Page::from_common(page))
+}
+
+async fn fetch_block(state: &AppState, slot: u64) -> Result<BlockData> {
+    let helius = state.helius_client.as_ref()
+        .ok_or_else(|| Error::new("Helius client not initialized"))?;
+    Ok(helius.get_block_by_slot(slot).await?)
+}
+
+/// Transactions of a block, in block order
+async fn block_transactions(state: &AppState, slot: u64, offset: usize, limit: usize) -> Result<Page<Transaction>> {
+    #[cfg(feature = "store")]
+    {
+        if let Some(storage) = state.store() {
+            let mut transactions = storage.get_transactions_by_slot_range(slot, slot, offset + limit + 1).await?;
+            transactions.sort_by_key(|transaction| transaction.index);
+            let transactions: Vec<TransactionData> = transactions.iter().map(TransactionData::from).collect();
+            return Ok(Page::from_offset(transactions, offset, limit));
+        }
+    }
+
+    let helius = state.helius_client.as_ref()
+        .ok_or_else(|| Error::new("Helius client not initialized"))?;
+    let config = serde_json::json!({
+        "transactionDetails": "signatures",
+        "rewards": false,
+        "maxSupportedTransactionVersion": 0,
+    });
+    let response = helius.call("getBlock", serde_json::json!([slot, config])).await?;
+    if let Some(error) = response.get("error") {
+        return Err(Error::new(format!("Helius API error: {}", error)));
+    }
+
+    let signatures: Vec<&str> = response["result"]["signatures"].as_array()
+        .map(|signatures| signatures.iter().filter_map(|signature| signature.as_str()).collect())
+        .unwrap_or_default();
+    let mut transactions = Vec::new();
+    for signature in signatures.iter().skip(offset).take(limit) {
+        transactions.push(Transaction(fetch_transaction(state, signature, Commitment::Confirmed).await?));
+    }
+
+    let next_cursor = (signatures.len() > offset + limit)
+        .then(|| Cursor { slot: 0, offset: (offset + limit) as u64 }.encode());
+    Ok(Page { items: transactions, next_cursor })
+}
+
+pub struct Account(AccountData);
+
+impl From<AccountData> for Account {
+    fn from(account: AccountData) -> Self {
+        Self(account)
+    }
+}
+
+#[Object]
+impl Account {
+    async fn pubkey(&self) -> &str {
+        &self.0.pubkey
+    }
+
+    async fn lamports(&self) -> u64 {
+        self.0.lamports
+    }
+
+    async fn owner(&self) -> &str {
+        &self.0.owner
+    }
+
+    async fn executable(&self) -> bool {
+        self.0.executable
+    }
+
+    async fn rent_epoch(&self) -> u64 {
+        self.0.rent_epoch
+    }
+
+    async fn data_base64(&self) -> Option<&str> {
+        self.0.data_base64.as_deref()
+    }
+
+    async fn slot(&self) -> u64 {
+        self.0.slot
+    }
+
+    /// Transactions that reference this account, newest first
+    async fn transactions(&self, ctx: &Context<'_>, first: Option<usize>, after: Option<String>) -> Result<Page<Transaction>> {
+        transactions_by_account(ctx.data::<AppState>()?, &self.0.pubkey, after.as_deref(), page_size(first)).await
+    }
+
+    /// Token balances held by this account
+    async fn token_balances(&self, ctx: &Context<'_>) -> Result<Vec<TokenBalance>> {
+        token_balances(ctx.data::<AppState>()?, &self.0.pubkey).await
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Instruction {
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    /// Base58-encoded instruction data
+    pub data: String,
+    /// Invocation depth, 1 for top-level instructions
+    pub depth: u32,
+    /// Instructions invoked by this one, in execution order
+    pub inner_instructions: Vec<Instruction>,
+}
+
+impl From<&InstructionData> for Instruction {
+    fn from(instruction: &InstructionData) -> Self {
+        Self {
+            program_id: instruction.program_id.clone(),
+            accounts: instruction.accounts.clone(),
+            data: instruction.data.clone(),
+            depth: instruction.depth,
+            inner_instructions: instruction.inner_instructions.iter().map(Instruction::from).collect(),
+        }
+    }
+}
+
+pub struct Transaction(TransactionData);
+
+impl From<TransactionData> for Transaction {
+    fn from(transaction: TransactionData) -> Self {
+        Self(transaction)
+    }
+}
+
+#[Object]
+impl Transaction {
+    async fn signature(&self) -> &str {
+        &self.0.signature
+    }
+
+    async fn slot(&self) -> u64 {
+        self.0.slot
+    }
+
+    async fn block_time(&self) -> Option<i64> {
+        self.0.block_time
+    }
+
+    async fn success(&self) -> bool {
+        self.0.success
+    }
+
+    async fn err(&self) -> Option<async_graphql::Json<serde_json::Value>> {
+        self.0.err.clone().map(async_graphql::Json)
+    }
+
+    async fn fee(&self) -> u64 {
+        self.0.fee
+    }
+
+    async fn recent_blockhash(&self) -> &str {
+        &self.0.recent_blockhash
+    }
+
+    async fn program_ids(&self) -> &[String] {
+        &self.0.program_ids
+    }
+
+    async fn account_keys(&self) -> &[String] {
+        &self.0.accounts
+    }
+
+    async fn logs(&self) -> Option<&[String]> {
+        self.0.logs.as_deref()
+    }
+
+    async fn instructions(&self) -> Vec<Instruction> {
+        self.0.instructions.iter().map(Instruction::from).collect()
+    }
+
+    /// Accounts the transaction references, in message order
+    async fn accounts(&self, ctx: &Context<'_>) -> Result<Vec<Account>> {
+        let state = ctx.data::<AppState>()?;
+        let mut accounts = Vec::with_capacity(self.0.accounts.len());
+        for pubkey in &self.0.accounts {
+            accounts.push(Account(fetch_account(state, pubkey, Commitment::Finalized).await?));
+        }
+        Ok(accounts)
+    }
+
+    /// Block the transaction landed in
+    async fn block(&self, ctx: &Context<'_>) -> Result<Block> {
+        Ok(Block(fetch_block(ctx.data::<AppState>()?, self.0.slot).await?))
+    }
+}
+
+pub struct Block(BlockData);
+
+impl From<BlockData> for Block {
+    fn from(block: BlockData) -> Self {
+        Self(block)
+    }
+}
+
+#[Object]
+impl Block {
+    async fn slot(&self) -> u64 {
+        self.0.slot
+    }
+
+    async fn parent_slot(&self) -> u64 {
+        self.0.parent_slot
+    }
+
+    async fn blockhash(&self) -> &str {
+        &self.0.blockhash
+    }
+
+    async fn previous_blockhash(&self) -> &str {
+        &self.0.previous_blockhash
+    }
+
+    async fn block_time(&self) -> Option<i64> {
+        self.0.block_time
+    }
+
+    async fn block_height(&self) -> Option<u64> {
+        self.0.block_height
+    }
+
+    async fn transaction_count(&self) -> u64 {
+        self.0.transaction_count
+    }
+
+    async fn leader(&self) -> &str {
+        &self.0.leader
+    }
+
+    /// Transactions in the block, in block order
+    async fn transactions(&self, ctx: &Context<'_>, first: Option<usize>, after: Option<String>) -> Result<Page<Transaction>> {
+        let (offset, limit) = page_bounds(first, after.as_deref())?;
+        block_transactions(ctx.data::<AppState>()?, self.0.slot, offset, limit).await
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn account(&self, ctx: &Context<'_>, pubkey: String, commitment: Option<CommitmentLevel>) -> Result<Account> {
+        Ok(Account(fetch_account(ctx.data::<AppState>()?, &pubkey, lookup_commitment(commitment)).await?))
+    }
+
+    /// Accounts owned by a program
+    async fn accounts(&self, ctx: &Context<'_>, program: String, first: Option<usize>, after: Option<String>) -> Result<Page<Account>> {
+        let manager = ctx.data::<AppState>()?.account_data_manager.as_ref()
+            .ok_or_else(|| Error::new("Account data manager not initialized"))?;
+        let program = &program;
+        let page = recent_page(after.as_deref(), page_size(first), |account: &AccountData| account.slot, |_, n| async move {
+            manager.get_accounts_by_program(program, n).await.map_err(|e| ApiError::Internal(e.to_string()))
+        }).await?;
+        Ok(Page::from_common(page))
+    }
+
+    async fn transaction(&self, ctx: &Context<'_>, signature: String, commitment: Option<CommitmentLevel>) -> Result<Transaction> {
+        Ok(Transaction(fetch_transaction(ctx.data::<AppState>()?, &signature, lookup_commitment(commitment)).await?))
+    }
+
+    /// Recent transactions, newest first, optionally by program or account
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        program: Option<String>,
+        account: Option<String>,
+        first: Option<usize>,
+        after: Option<String>,
+    ) -> Result<Page<Transaction>> {
+        let state = ctx.data::<AppState>()?;
+        let limit = page_size(first);
+
+        let program = match (program, account) {
+            (Some(_), Some(_)) => return Err(Error::new("Filter by program or account, not both")),
+            (None, Some(account)) => return transactions_by_account(state, &account, after.as_deref(), limit).await,
+            (program, None) => program,
+        };
+        let program = program.as_deref();
+        let page = recent_page(after.as_deref(), limit, |transaction: &TransactionData| transaction.slot, |_, n| async move {
+            match program {
+                Some(program) => transaction_endpoints::transactions_by_program(state, program, n).await,
+                None => state.transaction_data_manager.as_ref()
+                    .ok_or_else(|| ApiError::Internal("Transaction data manager not initialized".to_string()))?
+                    .get_recent_transactions(n).await
+                    .map_err(|e| ApiError::Internal(e.to_string())),
+            }
+        }).await?;
+        Ok(Page::from_common(page))
+    }
+
+    async fn block(&self, ctx: &Context<'_>, slot: u64) -> Result<Block> {
+        Ok(Block(fetch_block(ctx.data::<AppState>()?, slot).await?))
+    }
+
+    /// Latest blocks, newest first
+    async fn blocks(&self, ctx: &Context<'_>, first: Option<usize>, after: Option<String>) -> Result<Page<Block>> {
+        let helius = ctx.data::<AppState>()?.helius_client.as_ref()
+            .ok_or_else(|| Error::new("Helius client not initialized"))?;
+        let page = recent_page(after.as_deref(), page_size(first), |block: &BlockData| block.slot, |cursor, n| async move {
+            let from = (cursor.offset > 0).then_some(cursor.slot);
+            helius.get_blocks_from(from, n).await.map_err(|e| ApiError::Internal(e.to_string()))
+        }).await?;
+        Ok(Page::from_common(page))
+    }
+
+    /// Token balances of an owner
+    async fn token_balances(&self, ctx: &Context<'_>, owner: String) -> Result<Vec<TokenBalance>> {
+        token_balances(ctx.data::<AppState>()?, &owner).await
+    }
+
+    /// Largest holders of a mint
+    async fn token_holders(&self, ctx: &Context<'_>, mint: String, first: Option<usize>) -> Result<Vec<TokenBalance>> {
+        let limit = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        token_holders(ctx.data::<AppState>()?, &mint, limit).await
+    }
+}
+
+fn lagged_error(Lagged(skipped): Lagged) -> Error {
+    Error::new(format!("Subscriber fell behind and missed {} updates", skipped))
+}
+
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// Live account updates, by address, owning program and filter expression
+    async fn accounts(
+        &self,
+        ctx: &Context<'_>,
+        pubkeys: Option<Vec<String>>,
+        program: Option<String>,
+        filter: Option<String>,
+        commitment: Option<CommitmentLevel>,
+    ) -> Result<impl Stream<Item = Result<Account>>> {
+        let hub = ctx.data::<AppState>()?.subscriptions.clone()
+            .ok_or_else(|| Error::new("Live subscriptions need a data feed"))?;
+        let filter = parse_filter(filter.as_deref(), Subject::Accounts).map_err(Error::new)?
+            .and_any_of(Predicate::Accounts, parse_pubkeys(pubkeys.iter().flatten().map(String::as_str), "pubkeys").map_err(Error::new)?)
+            .and_any_of(Predicate::Owners, parse_pubkeys(program.as_deref(), "program").map_err(Error::new)?);
+
+        let subscription = hub.subscribe(commitment.unwrap_or_default().into(), move |event| match event {
+            FeedEvent::Account(account) if filter.matches_account_data(account) => {
+                Some((account.slot, AccountData::from(&**account)))
+            }
+            _ => None,
+        });
+        Ok(stream::unfold(subscription, |mut subscription| async move {
+            let update = subscription.recv().await?;
+            Some((update.map(Account).map_err(lagged_error), subscription))
+        }))
+    }
+
+    /// Live transactions, by mentioned account, invoked program and filter
+    /// expression
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        account: Option<String>,
+        program: Option<String>,
+        filter: Option<String>,
+        commitment: Option<CommitmentLevel>,
+    ) -> Result<impl Stream<Item = Result<Transaction>>> {
+        let hub = ctx.data::<AppState>()?.subscriptions.clone()
+            .ok_or_else(|| Error::new("Live subscriptions need a data feed"))?;
+        let filter = parse_filter(filter.as_deref(), Subject::Transactions).map_err(Error::new)?
+            .and_any_of(Predicate::Accounts, parse_pubkeys(account.as_deref(), "account").map_err(Error::new)?)
+            .and_any_of(Predicate::Programs, parse_pubkeys(program.as_deref(), "program").map_err(Error::new)?);
+
+        let subscription = hub.subscribe(commitment.unwrap_or_default().into(), move |event| match event {
+            FeedEvent::Transaction(transaction) if filter.matches_transaction_data(transaction) => {
+                Some((transaction.slot, TransactionData::from(&**transaction)))
+            }
+            _ => None,
+        });
+        Ok(stream::unfold(subscription, |mut subscription| async move {
+            let update = subscription.recv().await?;
+            Some((update.map(Transaction).map_err(lagged_error), subscription))
+        }))
+    }
+}
+
+pub fn build_schema(state: AppState) -> WindexerSchema {
+    Schema::build(QueryRoot, async_graphql::EmptyMutation, SubscriptionRoot)
+        .data(state)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+/// Serve subscriptions over a WebSocket in the tenant of the upgrade request,
+/// so they only stream that tenant's data
+async fn graphql_ws(schema: WindexerSchema, protocol: GraphQLProtocol, upgrade: WebSocketUpgrade) -> Response {
+    let tenant = TenantContext::current();
+    upgrade
+        .protocols(ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |stream| tenant.scope(GraphQLWebSocket::new(stream, schema, protocol).serve()))
+        .into_response()
+}
+
+pub fn create_graphql_router(state: AppState) -> Router<AppState> {
+    let schema = build_schema(state);
+
+    // The schema has no mutations, so queries sent with POST only read
+    let queries = Router::new().route_service("/graphql", GraphQL::new(schema.clone()));
+    let subscriptions = Router::new().route(
+        "/graphql/ws",
+        get(move |protocol: GraphQLProtocol, upgrade: WebSocketUpgrade| graphql_ws(schema.clone(), protocol, upgrade)),
+    );
+
+    scoped_router(queries, RouteScope::All(ApiScope::ReadOnly))
+        .merge(scoped_router(subscriptions, RouteScope::All(ApiScope::Stream)))
+}
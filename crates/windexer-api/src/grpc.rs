@@ -0,0 +1,496 @@
+//! gRPC streaming API
+//!
+//! Serves account, transaction and block meta updates over the
+//! Yellowstone-style `geyser` protocol in `proto/geyser.proto`. Live updates
+//! come from the node's [`SubscriptionHub`]; a request with `from_slot` first
+//! replays what the local store holds from that slot, so a client can
+//! reconnect without gaps. Delivery is at least once around the replay boundary.
+//!
+//! Updates are held back until their slot reaches the requested commitment.
+//! Subscribers that fall too far behind the feed get a `DATA_LOSS` status
+//! and should reconnect with `from_slot`.
+//!
+//! When authentication is enabled, every call needs a credential with the
+//! `stream` scope in its `x-api-key` or `authorization` metadata, checked
+//! the same way as on the REST API. The streams carry every tenant's data,
+//! so tenant-bound credentials are refused.
+
+// tonic handlers return `Status` errors by design
+#![allow(clippy::result_large_err)]
+
+pub mod proto {
+    tonic::include_proto!("geyser");
+}
+
+use {
+    crate::{
+        auth::{header_credential, AuthService},
+        rest::AppState,
+        subscriptions::{parse_pubkeys, Commitment, Lagged, Subscription, SubscriptionHub},
+        types::ApiError,
+    },
+    axum::http::{self, HeaderMap},
+    futures::{future::BoxFuture, StreamExt},
+    proto::{
+        geyser_server::{Geyser, GeyserServer},
+        BlockHeight, CommitmentLevel, GetSlotRequest, GetSlotResponse, SubscribeAccountsRequest,
+        SubscribeBlocksRequest, SubscribeTransactionsRequest, SubscribeUpdateAccount,
+        SubscribeUpdateAccountInfo, SubscribeUpdateBlockMeta, SubscribeUpdateTransaction,
+        SubscribeUpdateTransactionInfo, UnixTimestamp,
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashSet,
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        task::{Context, Poll},
+    },
+    tokio::sync::{broadcast::error::RecvError, mpsc},
+    tokio_stream::wrappers::ReceiverStream,
+    tonic::{body::BoxBody, transport::Server, Request, Response, Status},
+    tower::{Layer, Service},
+    tracing::{info, warn},
+    windexer_common::{
+        feed::FeedEvent,
+        filter::{Filter, Predicate, TransactionStatus},
+        shutdown::ShutdownToken,
+        types::{AccountData, ApiScope, BlockData, TransactionData},
+    },
+    windexer_store::{traits::Storage, StorageError},
+};
+
+/// Updates buffered per subscriber between the hub and the connection
+const SUBSCRIBER_BUFFER: usize = 1024;
+
+/// Blocks replayed per `from_slot` request
+const MAX_REPLAY_BLOCKS: usize = 10_000;
+
+fn requested_commitment(commitment: Option<i32>) -> Result<Commitment, Status> {
+    let level = match commitment {
+        None => return Ok(Commitment::Processed),
+        Some(value) => CommitmentLevel::try_from(value)
+            .map_err(|_| Status::invalid_argument(format!("Unknown commitment level {}", value)))?,
+    };
+    Ok(match level {
+        CommitmentLevel::Processed => Commitment::Processed,
+        CommitmentLevel::Confirmed => Commitment::Confirmed,
+        CommitmentLevel::Finalized => Commitment::Finalized,
+    })
+}
+
+fn request_pubkeys(values: &[String], field: &str) -> Result<HashSet<Pubkey>, Status> {
+    parse_pubkeys(values.iter().map(String::as_str), field).map_err(Status::invalid_argument)
+}
+
+fn account_filter(request: &SubscribeAccountsRequest) -> Result<Filter, Status> {
+    Ok(Filter::all()
+        .and_any_of(Predicate::Accounts, request_pubkeys(&request.accounts, "accounts")?)
+        .and_any_of(Predicate::Owners, request_pubkeys(&request.owners, "owners")?))
+}
+
+fn transaction_filter(request: &SubscribeTransactionsRequest) -> Result<Filter, Status> {
+    let mut filter = Filter::all()
+        .and_any_of(Predicate::Accounts, request_pubkeys(&request.accounts, "accounts")?)
+        .and_any_of(Predicate::Programs, request_pubkeys(&request.programs, "programs")?);
+    if let Some(vote) = request.vote {
+        filter = filter.and(Predicate::Vote(vote));
+    }
+    if let Some(failed) = request.failed {
+        let status = if failed { TransactionStatus::Failed } else { TransactionStatus::Success };
+        filter = filter.and(Predicate::Status(status));
+    }
+    Ok(filter)
+}
+
+impl From<&AccountData> for SubscribeUpdateAccount {
+    fn from(account: &AccountData) -> Self {
+        Self {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: account.pubkey.to_bytes().to_vec(),
+                lamports: account.lamports,
+                owner: account.owner.to_bytes().to_vec(),
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                data: account.data.clone(),
+                write_version: account.write_version,
+                txn_signature: account.transaction_signature.map(|signature| signature.as_ref().to_vec()),
+            }),
+            slot: account.slot,
+            is_startup: account.is_startup,
+        }
+    }
+}
+
+impl From<&TransactionData> for SubscribeUpdateTransaction {
+    fn from(transaction: &TransactionData) -> Self {
+        Self {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: transaction.signature.as_ref().to_vec(),
+                is_vote: transaction.is_vote,
+                index: transaction.index as u64,
+                success: transaction.serializable_meta.status == Some(0),
+                fee: transaction.serializable_meta.fee,
+                account_keys: transaction.message.account_keys.iter().map(|key| key.to_bytes().to_vec()).collect(),
+                message: bincode::serialize(&transaction.message).unwrap_or_default(),
+                signatures: transaction.signatures.iter().map(|signature| signature.as_ref().to_vec()).collect(),
+                log_messages: transaction.serializable_meta.log_messages.clone().unwrap_or_default(),
+            }),
+            slot: transaction.slot,
+        }
+    }
+}
+
+impl From<&BlockData> for SubscribeUpdateBlockMeta {
+    fn from(block: &BlockData) -> Self {
+        Self {
+            slot: block.slot,
+            blockhash: block.blockhash.clone().unwrap_or_default(),
+            block_time: block.timestamp.map(|timestamp| UnixTimestamp { timestamp }),
+            block_height: block.block_height.map(|block_height| BlockHeight { block_height }),
+            parent_slot: block.parent_slot.unwrap_or_default(),
+            parent_blockhash: block.parent_blockhash.clone().unwrap_or_default(),
+            executed_transaction_count: block.transaction_count.unwrap_or_default(),
+            entries_count: block.entry_count,
+        }
+    }
+}
+
+/// Pass live updates on to a subscriber until either side goes away
+async fn relay<T>(mut subscription: Subscription<T>, sender: mpsc::Sender<Result<T, Status>>) {
+    loop {
+        let update = tokio::select! {
+            _ = sender.closed() => return,
+            update = subscription.recv() => match update {
+                Some(update) => update,
+                None => return,
+            },
+        };
+
+        let update = update.map_err(|Lagged(skipped)| {
+            Status::data_loss(format!("Subscriber fell behind and missed {} updates", skipped))
+        });
+        let failed = update.is_err();
+        if sender.send(update).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// Highest slot seen at each commitment level
+#[derive(Default)]
+struct SlotTracker {
+    processed: AtomicU64,
+    confirmed: AtomicU64,
+    finalized: AtomicU64,
+}
+
+impl SlotTracker {
+    fn get(&self, level: Commitment) -> u64 {
+        match level {
+            Commitment::Processed => &self.processed,
+            Commitment::Confirmed => &self.confirmed,
+            Commitment::Finalized => &self.finalized,
+        }
+        .load(Ordering::Relaxed)
+    }
+
+    /// A slot at a level implies the same slot at every lower level
+    fn record(&self, slot: u64, level: Commitment) {
+        self.processed.fetch_max(slot, Ordering::Relaxed);
+        if level >= Commitment::Confirmed {
+            self.confirmed.fetch_max(slot, Ordering::Relaxed);
+        }
+        if level == Commitment::Finalized {
+            self.finalized.fetch_max(slot, Ordering::Relaxed);
+        }
+    }
+}
+
+/// `geyser.Geyser` service over the API state
+pub struct GeyserService {
+    storage: Option<Arc<dyn Storage>>,
+    hub: Arc<SubscriptionHub>,
+    slots: Arc<SlotTracker>,
+}
+
+impl GeyserService {
+    /// Must be called from within a tokio runtime.
+    pub fn new(state: &AppState) -> anyhow::Result<Self> {
+        let hub = state.subscriptions.clone()
+            .ok_or_else(|| anyhow::anyhow!("The gRPC API needs a data feed"))?;
+        let slots = Arc::new(SlotTracker::default());
+
+        let tracker = slots.clone();
+        let mut events = hub.feed().subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(FeedEvent::SlotStatus(status)) => {
+                        if let Some(level) = Commitment::from_slot_status(status.status.as_str()) {
+                            tracker.record(status.slot, level);
+                        }
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+
+        Ok(Self {
+            storage: state.storage.clone(),
+            hub,
+            slots,
+        })
+    }
+
+    /// Storage to replay from, required when `from_slot` is set
+    fn replay_storage(&self, from_slot: Option<u64>) -> Result<Option<(Arc<dyn Storage>, u64)>, Status> {
+        match (from_slot, &self.storage) {
+            (None, _) => Ok(None),
+            (Some(from_slot), Some(storage)) => Ok(Some((storage.clone(), from_slot))),
+            (Some(_), None) => Err(Status::failed_precondition("Replay needs a local store")),
+        }
+    }
+}
+
+/// Last slot to replay; live updates take over after it
+async fn replay_end(storage: &dyn Storage) -> u64 {
+    storage.get_latest_rooted_slot().await.ok().flatten().unwrap_or(u64::MAX)
+}
+
+fn storage_error(e: anyhow::Error) -> Status {
+    let code = StorageError::code_of(&e).grpc_code();
+    Status::new(tonic::Code::from(code), format!("Replay failed: {}", e))
+}
+
+#[tonic::async_trait]
+impl Geyser for GeyserService {
+    type SubscribeAccountsStream = ReceiverStream<Result<SubscribeUpdateAccount, Status>>;
+    type SubscribeTransactionsStream = ReceiverStream<Result<SubscribeUpdateTransaction, Status>>;
+    type SubscribeBlocksStream = ReceiverStream<Result<SubscribeUpdateBlockMeta, Status>>;
+
+    async fn subscribe_accounts(
+        &self,
+        request: Request<SubscribeAccountsRequest>,
+    ) -> Result<Response<Self::SubscribeAccountsStream>, Status> {
+        let request = request.into_inner();
+        let filter = account_filter(&request)?;
+        let level = requested_commitment(request.commitment)?;
+        let replay = self.replay_storage(request.from_slot)?;
+        let live_filter = filter.clone();
+        let subscription = self.hub.subscribe(level, move |event| match event {
+            FeedEvent::Account(account) if live_filter.matches_account_data(account) => {
+                Some((account.slot, SubscribeUpdateAccount::from(&**account)))
+            }
+            _ => None,
+        });
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+
+        tokio::spawn(async move {
+            if let Some((storage, from_slot)) = replay {
+                let end_slot = replay_end(&*storage).await;
+                let mut accounts = storage.stream_accounts_by_slot_range(from_slot, end_slot);
+                while let Some(account) = accounts.next().await {
+                    let update = match account {
+                        Ok(account) if filter.matches_account_data(&account) => Ok(SubscribeUpdateAccount::from(&account)),
+                        Ok(_) => continue,
+                        Err(e) => Err(storage_error(e)),
+                    };
+                    let failed = update.is_err();
+                    if sender.send(update).await.is_err() || failed {
+                        return;
+                    }
+                }
+            }
+
+            relay(subscription, sender).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn subscribe_transactions(
+        &self,
+        request: Request<SubscribeTransactionsRequest>,
+    ) -> Result<Response<Self::SubscribeTransactionsStream>, Status> {
+        let request = request.into_inner();
+        let filter = transaction_filter(&request)?;
+        let level = requested_commitment(request.commitment)?;
+        let replay = self.replay_storage(request.from_slot)?;
+        let live_filter = filter.clone();
+        let subscription = self.hub.subscribe(level, move |event| match event {
+            FeedEvent::Transaction(transaction) if live_filter.matches_transaction_data(transaction) => {
+                Some((transaction.slot, SubscribeUpdateTransaction::from(&**transaction)))
+            }
+            _ => None,
+        });
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+
+        tokio::spawn(async move {
+            if let Some((storage, from_slot)) = replay {
+                let end_slot = replay_end(&*storage).await;
+                let mut transactions = storage.stream_transactions_by_slot_range(from_slot, end_slot);
+                while let Some(transaction) = transactions.next().await {
+                    let update = match transaction {
+                        Ok(transaction) if filter.matches_transaction_data(&transaction) => Ok(SubscribeUpdateTransaction::from(&transaction)),
+                        Ok(_) => continue,
+                        Err(e) => Err(storage_error(e)),
+                    };
+                    let failed = update.is_err();
+                    if sender.send(update).await.is_err() || failed {
+                        return;
+                    }
+                }
+            }
+
+            relay(subscription, sender).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn subscribe_blocks(
+        &self,
+        request: Request<SubscribeBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeBlocksStream>, Status> {
+        let request = request.into_inner();
+        let level = requested_commitment(request.commitment)?;
+        let replay = self.replay_storage(request.from_slot)?;
+        let subscription = self.hub.subscribe(level, |event| match event {
+            FeedEvent::Block(block) => Some((block.slot, SubscribeUpdateBlockMeta::from(&**block))),
+            _ => None,
+        });
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+
+        tokio::spawn(async move {
+            if let Some((storage, from_slot)) = replay {
+                let end_slot = replay_end(&*storage).await;
+                let blocks = match storage.get_blocks_by_slot_range(from_slot, end_slot, MAX_REPLAY_BLOCKS).await {
+                    Ok(blocks) => blocks,
+                    Err(e) => {
+                        let _ = sender.send(Err(storage_error(e))).await;
+                        return;
+                    }
+                };
+                for block in &blocks {
+                    if sender.send(Ok(SubscribeUpdateBlockMeta::from(block))).await.is_err() {
+                        return;
+                    }
+                }
+            }
+
+            relay(subscription, sender).await;
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn get_slot(&self, request: Request<GetSlotRequest>) -> Result<Response<GetSlotResponse>, Status> {
+        let level = requested_commitment(request.into_inner().commitment)?;
+        let mut slot = self.slots.get(level);
+
+        // Nothing has been rooted since startup; fall back to the store
+        if slot == 0 && level == Commitment::Finalized {
+            if let Some(storage) = &self.storage {
+                slot = storage.get_latest_rooted_slot().await
+                    .map_err(|e| Status::from(ApiError::storage(e)))?
+                    .unwrap_or_default();
+            }
+        }
+        Ok(Response::new(GetSlotResponse { slot }))
+    }
+}
+
+/// Serve the gRPC API until `shutdown` is cancelled or the server fails
+/// Check the caller's credential before the call reaches the service
+async fn authorize(auth: &AuthService, headers: &HeaderMap) -> Result<(), Status> {
+    let credential = header_credential(headers)
+        .ok_or_else(|| Status::unauthenticated("Missing API key or bearer token"))?;
+    let principal = auth.authenticate(&credential).await.map_err(Status::from)?;
+    if !principal.grants(ApiScope::Stream) {
+        warn!("{} lacks the {} scope for gRPC", principal.subject, ApiScope::Stream);
+        return Err(Status::permission_denied(format!("Requires the {} scope", ApiScope::Stream)));
+    }
+    if let Some(tenant) = &principal.tenant {
+        return Err(Status::permission_denied(format!("Credential is limited to tenant {}; gRPC streams are not", tenant)));
+    }
+    auth.check_rate_limit(&principal).await.map_err(Status::from)
+}
+
+/// Authenticates every call with [`AuthService`]. Tonic interceptors are
+/// synchronous, so this is a tower layer that awaits the check instead.
+#[derive(Clone)]
+pub struct AuthLayer {
+    auth: Arc<AuthService>,
+}
+
+impl AuthLayer {
+    pub fn new(auth: Arc<AuthService>) -> Self {
+        Self { auth }
+    }
+}
+
+impl<S> Layer<S> for AuthLayer {
+    type Service = Authenticated<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Authenticated { auth: self.auth.clone(), inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct Authenticated<S> {
+    auth: Arc<AuthService>,
+    inner: S,
+}
+
+impl<S, B> Service<http::Request<B>> for Authenticated<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        // Call the service that was polled ready and keep a fresh clone
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let auth = self.auth.clone();
+
+        Box::pin(async move {
+            if let Err(status) = authorize(&auth, request.headers()).await {
+                return Ok(status.into_http());
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+/// Serve `service`, authenticating calls with `auth` when it is set
+pub async fn serve(
+    addr: SocketAddr,
+    service: GeyserService,
+    auth: Option<Arc<AuthService>>,
+    shutdown: ShutdownToken,
+) -> anyhow::Result<()> {
+    info!("Starting gRPC API on {}", addr);
+    Server::builder()
+        .layer(tower::util::option_layer(auth.map(AuthLayer::new)))
+        .add_service(GeyserServer::new(service))
+        .serve_with_shutdown(addr, shutdown.cancelled_owned())
+        .await
+        .map_err(|e| {
+            warn!("gRPC API stopped: {}", e);
+            e.into()
+        })
+}
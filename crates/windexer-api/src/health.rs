@@ -1,19 +1,97 @@
 // src/health.rs
 
+//! Health checks
+//!
+//! Every check answers one of two probes. Liveness checks say whether the
+//! process works at all; an orchestrator restarts it when they fail.
+//! Readiness covers the liveness checks and the dependencies: the store,
+//! the upstream RPC, gossip peers and how far the data trails the chain
+//! tip. A service that is not ready is taken out of rotation until its
+//! dependencies recover. `/health/live` and `/health/ready` answer 503 when
+//! any of their checks is unhealthy; `/health` reports every check.
+
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use futures::future::join_all;
+use serde_json::json;
 use tokio::sync::RwLock;
 
+use windexer_common::control::PeerControl;
+use windexer_common::rpc_provider::{CircuitState, RpcProvider};
+
 use crate::types::{HealthStatus, HealthResponse, HealthCheckResult};
 
-// Change from Fn() -> bool to async health checks
 pub type HealthCheckFn = Arc<dyn Fn() -> bool + Send + Sync>;
-pub type AsyncHealthCheckFn = Arc<dyn Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = HealthCheckResult> + Send>> + Send + Sync>;
+pub type AsyncHealthCheckFn = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = HealthCheckResult> + Send>> + Send + Sync>;
+
+/// Checks still running after this are reported unhealthy
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The probe a check answers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Probe {
+    /// The process works and must not be restarted
+    Liveness,
+    /// The service can take traffic
+    Readiness,
+}
+
+/// Thresholds of the dependency checks the server registers
+#[derive(Debug, Clone)]
+pub struct HealthChecksConfig {
+    /// Store or upstream answers slower than this are degraded
+    pub slow_response: Duration,
+    /// Fewer connected gossip peers than this are degraded, none unhealthy
+    pub min_peers: usize,
+    /// Slots the store may trail the upstream's finalized slot while ready
+    pub max_slot_lag: u64,
+}
+
+impl Default for HealthChecksConfig {
+    fn default() -> Self {
+        Self {
+            slow_response: Duration::from_secs(1),
+            min_peers: 1,
+            max_slot_lag: 150,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Check {
+    Sync(HealthCheckFn),
+    Async(AsyncHealthCheckFn),
+}
+
+impl Check {
+    async fn run(&self) -> HealthCheckResult {
+        match self {
+            Check::Sync(check) => if check() {
+                result(HealthStatus::Healthy, "Check passed")
+            } else {
+                result(HealthStatus::Unhealthy, "Check failed")
+            },
+            Check::Async(check) => match tokio::time::timeout(CHECK_TIMEOUT, check()).await {
+                Ok(result) => result,
+                Err(_) => result(HealthStatus::Unhealthy, format!("Timed out after {:?}", CHECK_TIMEOUT)),
+            },
+        }
+    }
+}
+
+fn result(status: HealthStatus, details: impl Into<String>) -> HealthCheckResult {
+    HealthCheckResult {
+        status,
+        details: Some(details.into()),
+        metrics: None,
+    }
+}
 
 pub struct HealthService {
-    checks: Arc<RwLock<HashMap<String, HealthCheckFn>>>,
-    async_checks: Arc<RwLock<HashMap<String, AsyncHealthCheckFn>>>,
+    checks: Arc<RwLock<HashMap<String, (Probe, Check)>>>,
     start_time: Instant,
 }
 
@@ -21,107 +99,59 @@ impl HealthService {
     pub fn new() -> Self {
         Self {
             checks: Arc::new(RwLock::new(HashMap::new())),
-            async_checks: Arc::new(RwLock::new(HashMap::new())),
             start_time: Instant::now(),
         }
     }
 
+    /// Register a liveness check
     pub async fn register(&self, name: &str, check: HealthCheckFn) {
         let mut checks = self.checks.write().await;
-        checks.insert(name.to_string(), check);
+        checks.insert(name.to_string(), (Probe::Liveness, Check::Sync(check)));
     }
-    
-    // Add a method for registering async health checks
+
+    /// Register a readiness check
     pub async fn register_async(&self, name: &str, check: AsyncHealthCheckFn) {
-        let mut checks = self.async_checks.write().await;
-        checks.insert(name.to_string(), check);
+        self.register_probe(name, Probe::Readiness, check).await;
+    }
+
+    pub async fn register_probe(&self, name: &str, probe: Probe, check: AsyncHealthCheckFn) {
+        let mut checks = self.checks.write().await;
+        checks.insert(name.to_string(), (probe, Check::Async(check)));
     }
 
     pub async fn unregister(&self, name: &str) {
         let mut checks = self.checks.write().await;
         checks.remove(name);
-        
-        let mut async_checks = self.async_checks.write().await;
-        async_checks.remove(name);
     }
 
     pub fn uptime(&self) -> u64 {
         self.start_time.elapsed().as_secs()
     }
 
+    /// Run every check
     pub async fn check_all(&self) -> HealthResponse {
-        // First collect all check names from both sync and async checks
-        let check_names: Vec<String> = {
+        self.check(Probe::Readiness).await
+    }
+
+    /// Run the checks `probe` covers, concurrently; readiness covers all
+    pub async fn check(&self, probe: Probe) -> HealthResponse {
+        let checks: Vec<(String, Check)> = {
             let checks = self.checks.read().await;
-            checks.keys().cloned().collect()
-        };
-        
-        let async_check_names: Vec<String> = {
-            let checks = self.async_checks.read().await;
-            checks.keys().cloned().collect()
+            checks.iter()
+                .filter(|(_, (check_probe, _))| probe == Probe::Readiness || *check_probe == probe)
+                .map(|(name, (_, check))| (name.clone(), check.clone()))
+                .collect()
         };
-        
-        let mut results = HashMap::new();
-        let mut all_healthy = true;
-        let mut any_healthy = false;
-        
-        // Run synchronous checks
-        for name in check_names {
-            let check_fn = {
-                let checks = self.checks.read().await;
-                checks.get(&name).cloned()
-            };
-            
-            if let Some(check) = check_fn {
-                let is_healthy = check();
-                
-                let result = if is_healthy {
-                    all_healthy &= true;
-                    any_healthy |= true;
-                    
-                    HealthCheckResult {
-                        status: HealthStatus::Healthy,
-                        details: Some("Check passed".to_string()),
-                        metrics: None,
-                    }
-                } else {
-                    all_healthy = false;
-                    
-                    HealthCheckResult {
-                        status: HealthStatus::Unhealthy,
-                        details: Some("Check failed".to_string()),
-                        metrics: None,
-                    }
-                };
-                
-                results.insert(name, result);
-            }
-        }
-        
-        // Run async checks
-        for name in async_check_names {
-            let check_fn = {
-                let checks = self.async_checks.read().await;
-                checks.get(&name).cloned()
-            };
-            
-            if let Some(check) = check_fn {
-                let result = check().await;
-                
-                if result.status == HealthStatus::Healthy {
-                    all_healthy &= true;
-                    any_healthy |= true;
-                } else {
-                    all_healthy = false;
-                    if result.status == HealthStatus::Degraded {
-                        any_healthy |= true;
-                    }
-                }
-                
-                results.insert(name, result);
-            }
-        }
-        
+
+        let results: HashMap<String, HealthCheckResult> = join_all(checks.into_iter().map(|(name, check)| async move {
+            (name, check.run().await)
+        }))
+        .await
+        .into_iter()
+        .collect();
+
+        let all_healthy = results.values().all(|result| result.status == HealthStatus::Healthy);
+        let any_healthy = results.values().any(|result| result.status != HealthStatus::Unhealthy);
         let status = if all_healthy {
             HealthStatus::Healthy
         } else if any_healthy {
@@ -129,11 +159,189 @@ impl HealthService {
         } else {
             HealthStatus::Unhealthy
         };
-        
+
         HealthResponse {
             status,
             checks: results,
             uptime: self.uptime(),
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Whether a probe passes: no check it covers is unhealthy
+pub fn passes(response: &HealthResponse) -> bool {
+    response.checks.values().all(|check| check.status != HealthStatus::Unhealthy)
+}
+
+/// Health check over the upstream RPC: unhealthy when `getSlot` fails,
+/// degraded when it is slower than `slow` or some of the providers behind
+/// `provider` have open circuits
+pub fn upstream_check(provider: Arc<dyn RpcProvider>, slow: Duration) -> AsyncHealthCheckFn {
+    Arc::new(move || {
+        let provider = provider.clone();
+        Box::pin(async move {
+            let upstreams = provider.health();
+            let open = upstreams.iter().filter(|upstream| upstream.state == CircuitState::Open).count();
+            let mut metrics: HashMap<String, serde_json::Value> = upstreams.iter()
+                .map(|upstream| (upstream.name.clone(), json!(upstream)))
+                .collect();
+
+            let started = Instant::now();
+            let reply = provider.call("getSlot", json!([{"commitment": "finalized"}])).await;
+            let latency = started.elapsed();
+            metrics.insert("latency_ms".to_string(), json!(latency.as_millis() as u64));
+
+            let (status, details) = match reply.as_ref().map(|reply| reply.get("result").and_then(|slot| slot.as_u64())) {
+                Ok(Some(slot)) => {
+                    metrics.insert("slot".to_string(), json!(slot));
+                    if open > 0 {
+                        (HealthStatus::Degraded, format!("{} of {} upstream providers available", upstreams.len() - open, upstreams.len()))
+                    } else if latency > slow {
+                        (HealthStatus::Degraded, format!("{} answered in {:?}", provider.name(), latency))
+                    } else {
+                        (HealthStatus::Healthy, format!("{} is at slot {}", provider.name(), slot))
+                    }
+                }
+                Ok(None) => (HealthStatus::Unhealthy, format!("{} returned no slot", provider.name())),
+                Err(e) => (HealthStatus::Unhealthy, format!("{} is unreachable: {}", provider.name(), e)),
+            };
+
+            HealthCheckResult {
+                status,
+                details: Some(details),
+                metrics: Some(metrics),
+            }
+        })
+    })
+}
+
+/// Health check over the network node's connected peers: unhealthy with
+/// none, degraded below `min_peers`
+pub fn peer_count_check(peers: Arc<dyn PeerControl>, min_peers: usize) -> AsyncHealthCheckFn {
+    Arc::new(move || {
+        let peers = peers.clone();
+        Box::pin(async move {
+            let connected = match peers.peers().await {
+                Ok(peers) => peers.iter().filter(|peer| peer.connected).count(),
+                Err(e) => return result(HealthStatus::Unhealthy, format!("Failed to list peers: {}", e)),
+            };
+            let status = if connected >= min_peers {
+                HealthStatus::Healthy
+            } else if connected > 0 {
+                HealthStatus::Degraded
+            } else {
+                HealthStatus::Unhealthy
+            };
+
+            HealthCheckResult {
+                status,
+                details: Some(format!("{} peers connected, {} wanted", connected, min_peers)),
+                metrics: Some(HashMap::from([
+                    ("connected".to_string(), json!(connected)),
+                    ("min_peers".to_string(), json!(min_peers)),
+                ])),
+            }
+        })
+    })
+}
+
+/// Health check over the store: unhealthy when it fails to answer the
+/// latest rooted slot, degraded when slower than `slow`
+#[cfg(feature = "store")]
+pub fn storage_check(storage: Arc<dyn windexer_store::traits::Storage>, slow: Duration) -> AsyncHealthCheckFn {
+    Arc::new(move || {
+        let storage = storage.clone();
+        Box::pin(async move {
+            let started = Instant::now();
+            let latest = storage.get_latest_rooted_slot().await;
+            let latency = started.elapsed();
+            let mut metrics = HashMap::from([("latency_ms".to_string(), json!(latency.as_millis() as u64))]);
+
+            let (status, details) = match latest {
+                Ok(slot) => {
+                    metrics.insert("latest_rooted_slot".to_string(), json!(slot));
+                    if latency > slow {
+                        (HealthStatus::Degraded, format!("Store answered in {:?}", latency))
+                    } else {
+                        (HealthStatus::Healthy, "Store is responding".to_string())
+                    }
+                }
+                Err(e) => (HealthStatus::Unhealthy, format!("Store is unavailable: {}", e)),
+            };
+
+            HealthCheckResult {
+                status,
+                details: Some(details),
+                metrics: Some(metrics),
+            }
+        })
+    })
+}
+
+/// Health check over how far the store's latest rooted slot trails the
+/// upstream's finalized slot: unhealthy beyond `max_lag` slots
+#[cfg(feature = "store")]
+pub fn chain_lag_check(
+    storage: Arc<dyn windexer_store::traits::Storage>,
+    upstream: Arc<dyn RpcProvider>,
+    max_lag: u64,
+) -> AsyncHealthCheckFn {
+    Arc::new(move || {
+        let storage = storage.clone();
+        let upstream = upstream.clone();
+        Box::pin(async move {
+            let (local, tip) = tokio::join!(
+                storage.get_latest_rooted_slot(),
+                upstream.call("getSlot", json!([{"commitment": "finalized"}])),
+            );
+            let local = match local {
+                Ok(slot) => slot.unwrap_or(0),
+                Err(e) => return result(HealthStatus::Unhealthy, format!("Store is unavailable: {}", e)),
+            };
+            let tip = match tip.map(|reply| reply.get("result").and_then(|slot| slot.as_u64())) {
+                Ok(Some(tip)) => tip,
+                // The upstream check reports an unreachable upstream
+                Ok(None) | Err(_) => return result(HealthStatus::Degraded, "Chain tip unknown"),
+            };
+
+            let lag = tip.saturating_sub(local);
+            let status = if lag <= max_lag {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Unhealthy
+            };
+
+            HealthCheckResult {
+                status,
+                details: Some(format!("{} slots behind the chain tip, at most {} allowed", lag, max_lag)),
+                metrics: Some(HashMap::from([
+                    ("local_slot".to_string(), json!(local)),
+                    ("tip_slot".to_string(), json!(tip)),
+                    ("lag".to_string(), json!(lag)),
+                ])),
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn liveness_ignores_dependencies() {
+        let health = HealthService::new();
+        health.register("process", Arc::new(|| true)).await;
+        health.register_async("store", Arc::new(|| {
+            Box::pin(async { result(HealthStatus::Unhealthy, "Store is unavailable") })
+        })).await;
+
+        let live = health.check(Probe::Liveness).await;
+        assert!(passes(&live));
+        assert_eq!(live.checks.len(), 1);
+
+        let ready = health.check(Probe::Readiness).await;
+        assert!(!passes(&ready));
+        assert_eq!(ready.status, HealthStatus::Degraded);
+    }
+}
@@ -1,18 +1,33 @@
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, mpsc, watch};
 use serde::{Serialize, Deserialize};
 use anyhow::{Result, anyhow};
+use windexer_common::{
+    lookup_tables::{self, LookupTables},
+    rpc_provider::{HeliusProvider, RpcProvider},
+    types::Commitment,
+};
 
+/// `getMultipleAccounts` request limit
+const ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Client for the upstream RPC provider, Helius unless built with
+/// [`HeliusClient::with_provider`]
 #[derive(Debug, Clone)]
 pub struct HeliusClient {
-    /// Base URL for Helius HTTP API
-    base_url: String,
-    /// API key
-    api_key: String,
-    /// HTTP client
-    client: reqwest::Client,
+    /// Upstream serving requests
+    provider: Arc<dyn RpcProvider>,
     /// WebSocket connection (if established)
     ws_connection: Arc<RwLock<Option<String>>>,
+    /// Subscription requests, sent whenever `process_messages` connects
+    subscriptions: Arc<RwLock<Vec<serde_json::Value>>>,
+    /// Outgoing messages of the running `process_messages` loop
+    outgoing: Arc<RwLock<Option<mpsc::Sender<String>>>>,
+    /// Signals `process_messages` to stop
+    shutdown: Arc<watch::Sender<()>>,
+    /// Lookup tables fetched to resolve transactions returned without their
+    /// loaded addresses
+    lookup_tables: Arc<LookupTables>,
 }
 
 // Various request structs for Helius API
@@ -34,18 +49,25 @@ pub struct GetTransactionRequest {
 
 impl HeliusClient {
     pub fn new(api_key: &str) -> Self {
-        let client = reqwest::Client::new();
-        let base_url = format!("https://mainnet.helius-rpc.com/?api-key={}", api_key);
-        
+        Self::with_provider(Arc::new(HeliusProvider::new(api_key, "mainnet")))
+    }
+
+    pub fn with_provider(provider: Arc<dyn RpcProvider>) -> Self {
         Self {
-            client,
-            base_url,
-            api_key: api_key.to_string(),
+            provider,
             ws_connection: Arc::new(RwLock::new(None)),
+            subscriptions: Arc::new(RwLock::new(Vec::new())),
+            outgoing: Arc::new(RwLock::new(None)),
+            shutdown: Arc::new(watch::channel(()).0),
+            lookup_tables: Arc::new(LookupTables::new()),
         }
     }
 
-    pub async fn get_account_info(&self, pubkey: &str) -> Result<serde_json::Value> {
+    pub fn provider_name(&self) -> &str {
+        self.provider.name()
+    }
+
+    pub async fn get_account_info(&self, pubkey: &str, commitment: Commitment) -> Result<serde_json::Value> {
         let request = GetAccountInfoRequest {
             jsonrpc: "2.0".to_string(),
             id: "1".to_string(),
@@ -53,23 +75,21 @@ impl HeliusClient {
             params: vec![
                 serde_json::json!(pubkey),
                 serde_json::json!({
-                    "encoding": "base64"
+                    "encoding": "base64",
+                    "commitment": commitment
                 })
             ],
         };
 
-        let response = self.client.post(&self.base_url)
-            .json(&request)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+        let response = self.send_request(serde_json::to_value(&request)?).await?;
 
         tracing::debug!("Helius getAccountInfo response: {:?}", response);
         Ok(response)
     }
 
-    pub async fn get_transaction(&self, signature: &str) -> Result<serde_json::Value> {
+    pub async fn get_transaction(&self, signature: &str, commitment: Commitment) -> Result<serde_json::Value> {
+        // getTransaction does not accept processed
+        let commitment = commitment.max(Commitment::Confirmed);
         let request = serde_json::json!({
             "jsonrpc": "2.0",
             "id": "1",
@@ -78,16 +98,67 @@ impl HeliusClient {
                 signature,
                 {
                     "encoding": "json",
+                    "commitment": commitment,
                     "maxSupportedTransactionVersion": 0
                 }
             ]
         });
 
-        let response = self.send_request(request).await?;
+        let mut response = self.send_request(request).await?;
         tracing::debug!("Helius getTransaction response: {:?}", response);
+        self.resolve_lookups(&mut response).await;
         Ok(response)
     }
 
+    /// Fills in the loaded addresses of a version 0 transaction returned
+    /// without them, fetching the lookup tables it names
+    async fn resolve_lookups(&self, response: &mut serde_json::Value) {
+        let Some(result) = response.get_mut("result").filter(|result| result.is_object()) else {
+            return;
+        };
+        let lookups = lookup_tables::lookups_from_json(&result["transaction"]["message"]);
+        if lookups.is_empty() || result["meta"].get("loadedAddresses").is_some() {
+            return;
+        }
+
+        let missing: Vec<String> = self.lookup_tables.missing(&lookups).iter().map(|table| table.to_string()).collect();
+        for chunk in missing.chunks(ACCOUNTS_PER_REQUEST) {
+            if let Err(e) = self.fetch_lookup_tables(chunk).await {
+                tracing::warn!("Failed to fetch lookup tables {:?}: {}", chunk, e);
+            }
+        }
+
+        let mut meta = result["meta"].take();
+        if !self.lookup_tables.fill_loaded_addresses(&result["transaction"], &mut meta) {
+            tracing::warn!("Could not resolve the lookup tables of a transaction, keeping its static keys only");
+        }
+        result["meta"] = meta;
+    }
+
+    async fn fetch_lookup_tables(&self, tables: &[String]) -> Result<()> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "1",
+            "method": "getMultipleAccounts",
+            "params": [tables, { "encoding": "base64" }]
+        });
+        let response = self.send_request(request).await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("getMultipleAccounts failed: {}", error));
+        }
+
+        let values = response["result"]["value"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for (table, value) in tables.iter().zip(values) {
+            let addresses = value["data"][0].as_str()
+                .and_then(|data| base64::decode(data).ok())
+                .and_then(|data| lookup_tables::parse_lookup_table(&data));
+            if let (Ok(table), Some(addresses)) = (table.parse(), addresses) {
+                self.lookup_tables.insert(table, addresses);
+            }
+        }
+        Ok(())
+    }
+
     pub async fn get_latest_block(&self) -> Result<crate::block_endpoints::BlockData> {
         let request = serde_json::json!({
             "jsonrpc": "2.0",
@@ -132,10 +203,8 @@ impl HeliusClient {
     }
 
     pub async fn connect_websocket(&self) -> Result<()> {
-        let ws_url = format!("wss://mainnet.helius-rpc.com/?api-key={}", self.api_key);
-        
         let mut connection = self.ws_connection.write().await;
-        *connection = Some(ws_url.clone());
+        *connection = self.provider.ws_url();
         
         let request = serde_json::json!({
             "jsonrpc": "2.0",
@@ -172,6 +241,7 @@ impl HeliusClient {
         });
         
         tracing::info!("Subscribing to account updates for {}", pubkey);
+        self.subscribe(subscription).await?;
         
         let request = serde_json::json!({
             "jsonrpc": "2.0",
@@ -208,6 +278,7 @@ impl HeliusClient {
         });
         
         tracing::info!("Subscribing to program updates for {}", program_id);
+        self.subscribe(subscription).await?;
         
         let request = serde_json::json!({
             "jsonrpc": "2.0",
@@ -237,32 +308,148 @@ impl HeliusClient {
         });
         
         tracing::info!("Subscribing to slot updates");
+        self.subscribe(subscription).await
+    }
+
+    /// Subscribe to logs of transactions mentioning `pubkey`
+    pub async fn subscribe_logs(&self, pubkey: &str) -> Result<()> {
+        let subscription = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "logsSubscribe",
+            "params": [
+                { "mentions": [pubkey] },
+                { "commitment": "confirmed" }
+            ]
+        });
         
+        tracing::info!("Subscribing to transaction logs mentioning {}", pubkey);
+        self.subscribe(subscription).await
+    }
+
+    /// Records a subscription and sends it on the running connection, if any
+    async fn subscribe(&self, subscription: serde_json::Value) -> Result<()> {
+        {
+            let mut subscriptions = self.subscriptions.write().await;
+            if subscriptions.contains(&subscription) {
+                return Ok(());
+            }
+            subscriptions.push(subscription.clone());
+        }
+        
+        if let Some(outgoing) = &*self.outgoing.read().await {
+            outgoing.send(subscription.to_string()).await
+                .map_err(|_| anyhow!("WebSocket connection closed"))?;
+        }
         Ok(())
     }
     
+    /// Connects to the upstream WebSocket, sends every subscription and
+    /// passes each message to `message_handler` until [`HeliusClient::stop_processing`]
+    /// is called, which returns `Ok`. A dropped connection or a handler
+    /// error ends the loop with that error, so the caller can decide
+    /// whether to call it again. Only one loop may run per client.
+    #[cfg(feature = "websocket")]
+    pub async fn process_messages<F>(&self, mut message_handler: F) -> Result<()>
+    where
+        F: FnMut(serde_json::Value) -> Result<()> + Send + 'static
+    {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+        
+        let url = self.provider.ws_url()
+            .ok_or_else(|| anyhow!("Upstream {} has no WebSocket endpoint", self.provider.name()))?;
+        let (tx, mut rx) = mpsc::channel(100);
+        {
+            let mut outgoing = self.outgoing.write().await;
+            if outgoing.is_some() {
+                return Err(anyhow!("Already processing WebSocket messages"));
+            }
+            *outgoing = Some(tx);
+        }
+        // Subscribe before connecting so a stop request during the handshake is seen
+        let mut shutdown = self.shutdown.subscribe();
+        
+        let result: Result<()> = async {
+            let (stream, _) = tokio_tungstenite::connect_async(url.as_str()).await
+                .map_err(|e| anyhow!("Failed to connect to WebSocket: {}", e))?;
+            let (mut write, mut read) = stream.split();
+            
+            for subscription in self.subscriptions.read().await.iter() {
+                write.send(Message::Text(subscription.to_string())).await
+                    .map_err(|e| anyhow!("Failed to send subscription: {}", e))?;
+            }
+            tracing::info!("Processing WebSocket messages from {}", self.provider.name());
+            
+            loop {
+                tokio::select! {
+                    _ = shutdown.changed() => {
+                        let _ = write.send(Message::Close(None)).await;
+                        return Ok(());
+                    }
+                    Some(message) = rx.recv() => {
+                        write.send(Message::Text(message)).await
+                            .map_err(|e| anyhow!("Failed to send WebSocket message: {}", e))?;
+                    }
+                    message = read.next() => match message {
+                        Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                            Ok(value) => message_handler(value)
+                                .map_err(|e| e.context("WebSocket message handler failed"))?,
+                            Err(e) => tracing::warn!("Ignoring malformed WebSocket message: {}", e),
+                        },
+                        Some(Ok(Message::Close(_))) | None => return Err(anyhow!("WebSocket closed by the server")),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(anyhow!("WebSocket error: {}", e)),
+                    }
+                }
+            }
+        }
+        .await;
+        
+        *self.outgoing.write().await = None;
+        result
+    }
+    
+    #[cfg(not(feature = "websocket"))]
     pub async fn process_messages<F>(&self, _message_handler: F) -> Result<()>
     where
         F: FnMut(serde_json::Value) -> Result<()> + Send + 'static
     {
-        Ok(())
+        Err(anyhow!("windexer-api was built without the websocket feature"))
+    }
+    
+    /// Stops a running `process_messages` loop
+    pub fn stop_processing(&self) {
+        self.shutdown.send_replace(());
+    }
+
+    /// Call a JSON-RPC method, returning the whole response including any error
+    pub async fn call(&self, method: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        self.send_request(request).await
     }
 
     async fn send_request(&self, request: serde_json::Value) -> Result<serde_json::Value> {
-        let response = self.client.post(&self.base_url)
-            .json(&request)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
-            
-        Ok(response)
+        self.provider.send(&request).await
     }
 
     pub async fn get_blocks(&self, limit: usize) -> Result<Vec<crate::block_endpoints::BlockData>> {
-        let response = self.get_latest_block().await?;
-        let latest_slot = response.slot;
-        let slots: Vec<u64> = (0..limit as u64).map(|i| latest_slot.saturating_sub(i)).collect();
+        self.get_blocks_from(None, limit).await
+    }
+
+    /// Up to `limit` blocks, newest first, from slot `from` or the latest one
+    pub async fn get_blocks_from(&self, from: Option<u64>, limit: usize) -> Result<Vec<crate::block_endpoints::BlockData>> {
+        let from = match from {
+            Some(slot) => slot,
+            None => self.get_latest_block().await?.slot,
+        };
+        let slots: Vec<u64> = (0..limit as u64).map_while(|i| from.checked_sub(i)).collect();
         let mut blocks = Vec::new();
         for slot in slots {
             match self.get_block_by_slot(slot).await {
@@ -286,18 +473,13 @@ impl HeliusClient {
                 {
                     "encoding": "json",
                     "transactionDetails": "full",
-                    "rewards": true
+                    "rewards": true,
+                    "maxSupportedTransactionVersion": 0
                 }
             ]
         });
         
-        let response = self.client
-            .post(&self.base_url)
-            .json(&payload)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+        let response = self.send_request(payload).await?;
             
         if let Some(error) = response.get("error") {
             return Err(anyhow::anyhow!("Helius API error: {}", error));
@@ -0,0 +1,233 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use serde_json::Value;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+use windexer_store::{
+    decoders::{IdlRegistry, IdlSummary, ParsedAccount, ParsedInstruction},
+    index::CpiCall,
+};
+
+use crate::auth::{scoped, RouteScope};
+use crate::rest::AppState;
+use crate::types::{ApiResponse, ApiError};
+
+fn idl_registry(state: &AppState) -> Result<&std::sync::Arc<IdlRegistry>, ApiError> {
+    state.idl_registry.as_ref().ok_or_else(|| {
+        ApiError::Internal("IDL registry not initialized".to_string())
+    })
+}
+
+fn parse_program_id(program_id: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(program_id)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid program id {}: {}", program_id, e)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/idls",
+    tag = "idls",
+    responses((status = 200, description = "Registered IDLs", body = Object))
+)]
+pub async fn list_idls(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<IdlSummary>>>, ApiError> {
+    Ok(Json(ApiResponse::success(idl_registry(&state)?.list())))
+}
+
+/// Upload an IDL for the program address it declares
+#[utoipa::path(
+    post,
+    path = "/idls",
+    tag = "idls",
+    request_body(content = Object, description = "Anchor IDL naming its program in `address` or `metadata.address`"),
+    responses(
+        (status = 200, description = "Registered IDL", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn upload_idl(
+    State(state): State<AppState>,
+    Json(idl): Json<Value>,
+) -> Result<Json<ApiResponse<IdlSummary>>, ApiError> {
+    let summary = idl_registry(&state)?
+        .load(&idl, None)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid IDL: {:#}", e)))?;
+    tracing::info!("Loaded IDL {} for program {}", summary.name, summary.program_id);
+    Ok(Json(ApiResponse::success(summary)))
+}
+
+/// Upload or replace the IDL of a specific program
+#[utoipa::path(
+    put,
+    path = "/idls/{program_id}",
+    tag = "idls",
+    params(("program_id" = String, Path, description = "Program the IDL describes")),
+    request_body(content = Object, description = "Anchor IDL"),
+    responses(
+        (status = 200, description = "Registered IDL", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn put_idl(
+    State(state): State<AppState>,
+    Path(program_id): Path<String>,
+    Json(idl): Json<Value>,
+) -> Result<Json<ApiResponse<IdlSummary>>, ApiError> {
+    let program_id = parse_program_id(&program_id)?;
+    let summary = idl_registry(&state)?
+        .load(&idl, Some(program_id))
+        .map_err(|e| ApiError::BadRequest(format!("Invalid IDL: {:#}", e)))?;
+    tracing::info!("Loaded IDL {} for program {}", summary.name, summary.program_id);
+    Ok(Json(ApiResponse::success(summary)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/idls/{program_id}",
+    tag = "idls",
+    params(("program_id" = String, Path, description = "Program the IDL describes")),
+    responses(
+        (status = 200, description = "Whether an IDL was removed", body = ApiResponse<bool>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn delete_idl(
+    State(state): State<AppState>,
+    Path(program_id): Path<String>,
+) -> Result<Json<ApiResponse<bool>>, ApiError> {
+    let program_id = parse_program_id(&program_id)?;
+    match idl_registry(&state)?.remove(&program_id) {
+        true => Ok(Json(ApiResponse::success(true))),
+        false => Err(ApiError::NotFound(format!("No IDL loaded for program {}", program_id))),
+    }
+}
+
+/// Reload every IDL from the configured directory
+#[utoipa::path(
+    post,
+    path = "/idls/refresh",
+    tag = "idls",
+    responses(
+        (status = 200, description = "IDLs fetched from chain", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn refresh_idls(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<IdlSummary>>>, ApiError> {
+    let registry = idl_registry(&state)?.clone();
+    // Directory reads are blocking
+    let loaded = tokio::task::spawn_blocking(move || registry.reload())
+        .await
+        .map_err(|e| ApiError::Internal(format!("IDL refresh task failed: {}", e)))?
+        .map_err(|e| ApiError::BadRequest(format!("Failed to refresh IDLs: {:#}", e)))?;
+    Ok(Json(ApiResponse::success(loaded)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/account/{pubkey}/decoded",
+    tag = "idls",
+    params(("pubkey" = String, Path, description = "Account address")),
+    responses(
+        (status = 200, description = "Account decoded with its program's IDL", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn get_decoded_account(
+    State(state): State<AppState>,
+    Path(pubkey): Path<String>,
+) -> Result<Json<ApiResponse<ParsedAccount>>, ApiError> {
+    let storage = state.store().ok_or_else(|| {
+        ApiError::Internal("Storage not initialized".to_string())
+    })?;
+
+    match storage.get_decoded_account(&pubkey).await {
+        Ok(Some(account)) => Ok(Json(ApiResponse::success(account))),
+        Ok(None) => Err(ApiError::NotFound(format!("No decoded data for account {}", pubkey))),
+        Err(e) => Err(ApiError::BadRequest(format!("Failed to decode account {}: {}", pubkey, e))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/transaction/{signature}/decoded",
+    tag = "idls",
+    params(("signature" = String, Path, description = "Transaction signature")),
+    responses(
+        (status = 200, description = "Instructions decoded with their programs' IDLs", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn get_decoded_instructions(
+    State(state): State<AppState>,
+    Path(signature): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ParsedInstruction>>>, ApiError> {
+    let storage = state.store().ok_or_else(|| {
+        ApiError::Internal("Storage not initialized".to_string())
+    })?;
+
+    match storage.get_decoded_instructions(&signature).await {
+        Ok(instructions) => Ok(Json(ApiResponse::success(instructions))),
+        Err(e) => Err(ApiError::BadRequest(format!("Failed to decode transaction {}: {}", signature, e))),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CpiParams {
+    /// Calls to return, newest first; at most 1000
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/transactions/program/{program_id}/cpi",
+    tag = "transactions",
+    params(("program_id" = String, Path, description = "Invoked program"), CpiParams),
+    responses(
+        (status = 200, description = "Recent calls into the program made by other programs", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError)
+    )
+)]
+pub async fn get_cpi_calls(
+    State(state): State<AppState>,
+    Path(program_id): Path<String>,
+    Query(params): Query<CpiParams>,
+) -> Result<Json<ApiResponse<Vec<CpiCall>>>, ApiError> {
+    parse_program_id(&program_id)?;
+    let storage = state.store().ok_or_else(|| {
+        ApiError::Internal("Storage not initialized".to_string())
+    })?;
+
+    let limit = params.limit.unwrap_or(100).min(1000);
+    match storage.get_cpi_calls(&program_id, limit).await {
+        Ok(calls) => Ok(Json(ApiResponse::success(calls))),
+        Err(e) => Err(ApiError::BadRequest(format!("Failed to fetch calls into {}: {}", program_id, e))),
+    }
+}
+
+pub fn create_idl_router() -> OpenApiRouter<AppState> {
+    // Uploading, replacing, deleting and refreshing IDLs need `admin`
+    let router = OpenApiRouter::new()
+        .routes(routes!(list_idls, upload_idl))
+        .routes(routes!(refresh_idls))
+        .routes(routes!(put_idl, delete_idl))
+        .routes(routes!(get_decoded_account))
+        .routes(routes!(get_decoded_instructions))
+        .routes(routes!(get_cpi_calls));
+    scoped(router, RouteScope::ReadWrite)
+}
@@ -7,12 +7,39 @@ pub mod server;
 pub mod endpoints;
 
 // Export new streaming modules
+pub mod admin;
+#[cfg(feature = "store")]
+pub mod analytics;
+pub mod auth;
 pub mod account_endpoints;
 pub mod transaction_endpoints;
 pub mod block_endpoints;
+pub mod cache;
 pub mod account_data_manager;
 pub mod transaction_data_manager;
 pub mod helius;
+pub mod openapi;
+pub mod peer_endpoints;
+pub mod rpc;
+pub mod staking_endpoints;
+pub mod subscriptions;
+pub mod tenant;
+#[cfg(feature = "store")]
+pub mod event_endpoints;
+#[cfg(feature = "store")]
+pub mod nft_endpoints;
+#[cfg(feature = "store")]
+pub mod idl_endpoints;
+#[cfg(feature = "store")]
+pub mod proof_endpoints;
+#[cfg(feature = "store")]
+pub mod query;
+#[cfg(feature = "store")]
+pub mod search;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "graphql")]
+pub mod graphql;
 
 // Re-export main types for convenience
 pub use types::{ApiResponse, ApiError, StatusResponse, HealthResponse, HealthStatus, HealthCheckResult, NodeInfo};
@@ -6,8 +6,10 @@ use axum::{
     http::{StatusCode, Method},
     response::{IntoResponse, Response},
 };
+use clap::{Parser, Subcommand};
 use serde::{Serialize, Deserialize};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tower_http::cors::{CorsLayer, Any};
@@ -17,17 +19,49 @@ use tracing::{info, error};
 
 use crate::server::run_api_server;
 use crate::rest::{ApiServer, ApiConfig};
+use crate::auth::AuthConfig;
 use crate::types::NodeInfo;
+use windexer_common::{
+    config::{ConfigArgs, ConfigCommand, ConfigLoader, Validate},
+    errors::{Error as ConfigError, Result as ConfigResult},
+    lag::LagConfig,
+    logging,
+    rpc_provider::{HealthConfig, MultiplexStrategy, RpcProviderConfig},
+    shutdown::{Shutdown, Stage, DEFAULT_SHUTDOWN_TIMEOUT},
+};
 
 mod account_data_manager;
 mod account_endpoints;
+mod admin;
+#[cfg(feature = "store")]
+mod analytics;
+mod auth;
 mod block_endpoints;
+mod cache;
 mod endpoints;
+#[cfg(feature = "store")]
+mod event_endpoints;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod health;
 mod helius;
+#[cfg(feature = "store")]
+mod idl_endpoints;
 mod metrics;
+#[cfg(feature = "store")]
+mod nft_endpoints;
+#[cfg(feature = "store")]
+mod query;
 mod rest;
+mod openapi;
+mod rpc;
+#[cfg(feature = "store")]
+mod search;
 mod server;
+mod subscriptions;
+mod tenant;
 mod transaction_data_manager;
 mod transaction_endpoints;
 mod types;
@@ -92,32 +126,154 @@ struct TransactionResponse {
     status: String,
 }
 
+#[derive(Parser, Debug)]
+#[command(name = "windexer-api", version, about = "wIndexer API server")]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Port to listen on, on all interfaces
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Address to listen on, overriding `port`
+    #[arg(long)]
+    bind_addr: Option<String>,
+
+    /// JSON file with the upstream RPC provider
+    #[arg(long)]
+    upstream_rpc_config: Option<PathBuf>,
+
+    /// Upstream JSON-RPC URLs, tried in order
+    #[arg(long, value_delimiter = ',')]
+    upstream_rpc_urls: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Configuration checks
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// Server settings, from a `--config` file, `WINDEXER_*` variables and
+/// flags. The unprefixed variables the server used to read still work.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    port: u16,
+    bind_addr: Option<String>,
+    service_name: String,
+    service_version: String,
+    admin_api_key: Option<String>,
+    jwt_secret: Option<String>,
+    upstream_rpc_config: Option<PathBuf>,
+    upstream_rpc_urls: Vec<String>,
+    helius_api_key: Option<String>,
+    /// Seconds to finish requests in flight after Ctrl+C or SIGTERM
+    shutdown_timeout_secs: u64,
+    /// Alert when the indexed slot trails the upstream's tip, e.g.
+    /// `WINDEXER_SLOT_LAG__THRESHOLD_SLOTS=150`
+    slot_lag: Option<LagConfig>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            port: 3001,
+            bind_addr: None,
+            service_name: "windexer-api".to_string(),
+            service_version: env!("CARGO_PKG_VERSION").to_string(),
+            admin_api_key: None,
+            jwt_secret: None,
+            upstream_rpc_config: None,
+            upstream_rpc_urls: Vec::new(),
+            helius_api_key: None,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT.as_secs(),
+            slot_lag: None,
+        }
+    }
+}
+
+impl Settings {
+    fn bind_addr(&self) -> String {
+        self.bind_addr.clone().unwrap_or_else(|| format!("0.0.0.0:{}", self.port))
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> ConfigResult<()> {
+        let bind_addr = self.bind_addr();
+        SocketAddr::from_str(&bind_addr)
+            .map_err(|e| ConfigError::Config(format!("Invalid bind address {}: {}", bind_addr, e)))?;
+        if let Some(path) = &self.upstream_rpc_config {
+            if !path.is_file() {
+                return Err(ConfigError::Config(format!("Upstream RPC config {} does not exist", path.display())));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Args {
+    fn loader(&self) -> Result<ConfigLoader> {
+        let mut loader = self.config.loader()?;
+        for (var, key) in [
+            ("API_PORT", "port"),
+            ("BIND_ADDR", "bind_addr"),
+            ("SERVICE_NAME", "service_name"),
+            ("SERVICE_VERSION", "service_version"),
+            ("ADMIN_API_KEY", "admin_api_key"),
+            ("JWT_SECRET", "jwt_secret"),
+            ("UPSTREAM_RPC_CONFIG", "upstream_rpc_config"),
+            ("UPSTREAM_RPC_URLS", "upstream_rpc_urls"),
+            ("HELIUS_API_KEY", "helius_api_key"),
+        ] {
+            loader = loader.env_alias(var, key);
+        }
+        Ok(loader
+            .set_some("port", self.port)
+            .set_some("bind_addr", self.bind_addr.clone())
+            .set_some("upstream_rpc_config", self.upstream_rpc_config.clone())
+            .set_some("upstream_rpc_urls", (!self.upstream_rpc_urls.is_empty()).then(|| self.upstream_rpc_urls.clone())))
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    let subscriber = tracing_subscriber::fmt()
-        .with_env_filter(std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()))
-        .finish();
-    
-    if let Err(e) = tracing::subscriber::set_global_default(subscriber) {
-        eprintln!("Warning: Failed to set global tracing subscriber: {}", e);
+    let args = Args::parse();
+    let loader = args.loader()?;
+    if let Some(Command::Config(ConfigCommand::Validate)) = args.command {
+        println!("{}", loader.check::<Settings>()?);
+        return Ok(());
     }
 
-    let port = std::env::var("API_PORT")
-        .unwrap_or_else(|_| "3001".to_string())
-        .parse::<u16>()
-        .unwrap_or(3001);
-    
-    let bind_addr = std::env::var("BIND_ADDR")
-        .unwrap_or_else(|_| format!("0.0.0.0:{}", port));
-    
-    let service_name = std::env::var("SERVICE_NAME")
-        .unwrap_or_else(|_| "windexer-api".to_string());
-    
-    let version = std::env::var("SERVICE_VERSION")
-        .unwrap_or_else(|_| env!("CARGO_PKG_VERSION").to_string());
+    let log_filter = match logging::init("info") {
+        Ok(filter) => Some(filter),
+        Err(e) => {
+            eprintln!("Warning: Failed to set global tracing subscriber: {}", e);
+            None
+        }
+    };
+
+    let settings: Settings = loader.load()?;
+    let bind_addr = settings.bind_addr();
+    let service_name = settings.service_name.clone();
+    let version = settings.service_version.clone();
 
-    let helius_api_key = std::env::var("HELIUS_API_KEY")
-        .unwrap_or_else(|_| "test-api-key".to_string());
+    let upstream = upstream_config(&settings)?.build()?;
+
+    let admin_key = settings.admin_api_key.clone();
+    let jwt_secret = settings.jwt_secret.clone();
+    let auth = AuthConfig {
+        enabled: admin_key.is_some() || jwt_secret.is_some(),
+        jwt_secret,
+        admin_key,
+        ..Default::default()
+    };
 
     let node_info = Some(NodeInfo {
         node_id: "api-node-1".to_string(),
@@ -134,15 +290,21 @@ async fn main() -> Result<()> {
         enable_metrics: true,
         node_info: node_info.clone(),
         path_prefix: Some("/api".to_string()),
+        grpc_addr: None,
+        auth,
+        stats_refresh_interval: Some(Duration::from_secs(60)),
+        tenants: Vec::new(),
+        health_checks: Default::default(),
+        slot_lag: settings.slot_lag.clone(),
     };
 
-    let helius_client = Arc::new(helius::HeliusClient::new(&helius_api_key));
+    let helius_client = Arc::new(helius::HeliusClient::with_provider(upstream.clone()));
 
     match helius_client.get_latest_block().await {
-        Ok(_) => info!("Successfully connected to Helius API"),
+        Ok(_) => info!("Successfully connected to upstream RPC {}", helius_client.provider_name()),
         Err(e) => {
-            error!("Failed to connect to Helius API: {}", e);
-            return Err(anyhow::anyhow!("Failed to connect to Helius API: {}", e));
+            error!("Failed to connect to upstream RPC {}: {}", helius_client.provider_name(), e);
+            return Err(anyhow::anyhow!("Failed to connect to upstream RPC: {}", e));
         }
     }
 
@@ -163,13 +325,18 @@ async fn main() -> Result<()> {
         // We'll continue even if this fails, as it might be a transient error
     }
 
+    #[cfg(feature = "websocket")]
+    transaction_data_manager.spawn_live_updates();
+
     let mut server = ApiServer::new(config);
     
     server.set_account_data_manager(account_data_manager);
     server.set_transaction_data_manager(transaction_data_manager);
-    server.set_helius_client(helius_client);
-    let health = server.health();
-    health.register("api", Arc::new(|| true)).await;
+    server.set_helius_client(helius_client.clone());
+    server.set_upstream(upstream);
+    if let Some(log_filter) = log_filter {
+        server.set_log_control(log_filter);
+    }
     
     let metrics = server.metrics();
     metrics.register_collector(|| {
@@ -180,10 +347,53 @@ async fn main() -> Result<()> {
         metrics
     });
 
+    let shutdown = Shutdown::new(Duration::from_secs(settings.shutdown_timeout_secs));
+    let upstream_token = shutdown.token(Stage::Services);
+    tokio::spawn(async move {
+        upstream_token.cancelled().await;
+        helius_client.stop_processing();
+    });
+
     info!("Starting API server on {}", bind_addr);
-    server.start().await?;
+    let token = shutdown.token(Stage::Ingress);
+    let mut serving = tokio::spawn(async move { server.start_with_shutdown(token).await });
+    tokio::select! {
+        result = &mut serving => return result?,
+        _ = shutdown.signalled() => {}
+    }
+    serving.await?
+}
+
+/// Upstream RPC provider from `upstream_rpc_config` (a JSON file), then
+/// `upstream_rpc_urls` (tried in order), then Helius with `helius_api_key`.
+/// The last two are multiplexed so their health is tracked.
+fn upstream_config(settings: &Settings) -> Result<RpcProviderConfig> {
+    if let Some(path) = &settings.upstream_rpc_config {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        return serde_json::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Invalid upstream RPC config in {}: {}", path.display(), e));
+    }
+
+    if !settings.upstream_rpc_urls.is_empty() {
+        let providers = settings.upstream_rpc_urls.iter()
+            .map(|url| RpcProviderConfig::Rpc { url: url.clone(), ws_url: None })
+            .collect();
+        return Ok(RpcProviderConfig::Multi {
+            strategy: MultiplexStrategy::Failover,
+            providers,
+            health: HealthConfig::default(),
+        });
+    }
 
-    Ok(())
+    Ok(RpcProviderConfig::Multi {
+        strategy: MultiplexStrategy::Failover,
+        providers: vec![RpcProviderConfig::Helius {
+            api_key: settings.helius_api_key.clone().unwrap_or_else(|| "test-api-key".to_string()),
+            network: "mainnet".to_string(),
+        }],
+        health: HealthConfig::default(),
+    })
 }
 
 async fn status_handler() -> Json<ApiResponse<StatusResponse>> {
@@ -2,8 +2,87 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 use serde_json::Value;
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use windexer_common::metrics::{self, Collector, HistogramVec, IntCounterVec, MetricSet};
+
+use crate::rest::AppState;
+
+const SUBSYSTEM: &str = "api";
+
+/// Route label of requests that matched no route, so probes of random
+/// paths do not each get a series
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Per-route request metrics, labeled with the route template rather than
+/// the requested path
+///
+/// | metric                                    | type      | labels                      |
+/// |-------------------------------------------|-----------|-----------------------------|
+/// | `windexer_api_requests_total`             | counter   | `route`, `method`, `status` |
+/// | `windexer_api_request_duration_seconds`   | histogram | `route`, `method`           |
+#[derive(Debug)]
+pub struct RequestMetrics {
+    requests: IntCounterVec,
+    duration: HistogramVec,
+}
+
+impl RequestMetrics {
+    pub fn new() -> Self {
+        Self {
+            requests: metrics::counter_vec(
+                SUBSYSTEM,
+                "requests_total",
+                "Requests served per route, method and status",
+                &["route", "method", "status"],
+            ),
+            duration: metrics::histogram_vec(
+                SUBSYSTEM,
+                "request_duration_seconds",
+                "Request latency per route and method",
+                &["route", "method"],
+                metrics::latency_buckets(),
+            ),
+        }
+    }
+
+    pub fn observe(&self, route: &str, method: &str, status: u16, seconds: f64) {
+        self.requests
+            .with_label_values(&[route, method, &status.to_string()])
+            .inc();
+        self.duration.with_label_values(&[route, method]).observe(seconds);
+    }
+}
+
+impl Default for RequestMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MetricSet for RequestMetrics {
+    fn collectors(&self) -> Vec<&dyn Collector> {
+        vec![&self.requests, &self.duration]
+    }
+}
+
+/// Middleware recording every request in [`AppState::requests`]
+pub async fn track_requests(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let route = request.extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+    let method = request.method().clone();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+    state.requests.observe(&route, method.as_str(), response.status().as_u16(), started.elapsed().as_secs_f64());
+    response
+}
 
 /// Metrics service for collecting and retrieving metrics
 #[derive(Debug)]
@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use windexer_store::index::{CompressedAsset, NftMetadata};
+
+use crate::auth::{scoped, RouteScope};
+use crate::rest::AppState;
+use crate::types::{ApiResponse, ApiError};
+
+fn storage(state: &AppState) -> Result<std::sync::Arc<dyn windexer_store::traits::Storage>, ApiError> {
+    state.store().ok_or_else(|| {
+        ApiError::Internal("Storage not initialized".to_string())
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/nfts/owner/{owner}",
+    tag = "nfts",
+    params(("owner" = String, Path, description = "Wallet address")),
+    responses(
+        (status = 200, description = "Metadata of the NFTs held by the owner", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn get_nfts_by_owner(
+    State(state): State<AppState>,
+    Path(owner): Path<String>,
+) -> Result<Json<ApiResponse<Vec<NftMetadata>>>, ApiError> {
+    match storage(&state)?.get_nfts_by_owner(&owner).await {
+        Ok(nfts) => Ok(Json(ApiResponse::success(nfts))),
+        Err(e) => {
+            tracing::error!("Error fetching NFTs for owner {}: {}", owner, e);
+            Err(ApiError::BadRequest(format!("Failed to fetch NFTs for {}: {}", owner, e)))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/nfts/{mint}",
+    tag = "nfts",
+    params(("mint" = String, Path, description = "NFT mint")),
+    responses(
+        (status = 200, description = "Metadata of the NFT", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn get_nft_by_mint(
+    State(state): State<AppState>,
+    Path(mint): Path<String>,
+) -> Result<Json<ApiResponse<NftMetadata>>, ApiError> {
+    match storage(&state)?.get_nft_by_mint(&mint).await {
+        Ok(Some(nft)) => Ok(Json(ApiResponse::success(nft))),
+        Ok(None) => Err(ApiError::NotFound(format!("No NFT metadata for mint {}", mint))),
+        Err(e) => {
+            tracing::error!("Error fetching NFT {}: {}", mint, e);
+            Err(ApiError::BadRequest(format!("Failed to fetch NFT {}: {}", mint, e)))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/nfts/compressed/{asset_id}",
+    tag = "nfts",
+    params(("asset_id" = String, Path, description = "Compressed NFT asset id")),
+    responses(
+        (status = 200, description = "Leaf of the compressed NFT with the root of its tree and a proof", body = Object),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
+pub async fn get_compressed_asset(
+    State(state): State<AppState>,
+    Path(asset_id): Path<String>,
+) -> Result<Json<ApiResponse<CompressedAsset>>, ApiError> {
+    match storage(&state)?.get_compressed_asset(&asset_id).await {
+        Ok(Some(asset)) => Ok(Json(ApiResponse::success(asset))),
+        Ok(None) => Err(ApiError::NotFound(format!("No compressed asset {}", asset_id))),
+        Err(e) => {
+            tracing::error!("Error fetching compressed asset {}: {}", asset_id, e);
+            Err(ApiError::BadRequest(format!("Failed to fetch compressed asset {}: {}", asset_id, e)))
+        }
+    }
+}
+
+pub fn create_nft_router() -> OpenApiRouter<AppState> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(get_nfts_by_owner))
+        .routes(routes!(get_nft_by_mint))
+        .routes(routes!(get_compressed_asset));
+    scoped(router, RouteScope::ReadWrite)
+}
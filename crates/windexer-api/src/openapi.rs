@@ -0,0 +1,78 @@
+//! OpenAPI document and Swagger UI
+//!
+//! The routers are built with [`OpenApiRouter`](utoipa_axum::router::OpenApiRouter),
+//! which collects each handler's `#[utoipa::path]` as it is routed, so the
+//! document always lists exactly the routes being served. [`ApiDoc`] only adds
+//! what no handler knows about: the service description and the
+//! authentication schemes.
+
+use {
+    crate::rest::AppState,
+    axum::Router,
+    utoipa::{
+        openapi::{
+            security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+            server::Server,
+            OpenApi as OpenApiDocument,
+        },
+        Modify, OpenApi,
+    },
+    utoipa_swagger_ui::SwaggerUi,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "wIndexer API",
+        description = "Solana accounts, transactions and blocks indexed by wIndexer",
+    ),
+    modifiers(&SecuritySchemes),
+    security(("api_key" = []), ("bearer" = [])),
+    tags(
+        (name = "service", description = "Health, status and metrics"),
+        (name = "accounts", description = "Account state and balances"),
+        (name = "transactions", description = "Transactions by signature, program and account"),
+        (name = "blocks", description = "Blocks by slot"),
+        (name = "streams", description = "Live updates over WebSocket and Server-Sent Events"),
+        (name = "query", description = "Filter documents over stored data"),
+        (name = "search", description = "Slots, pubkeys, signatures and blockhashes by prefix"),
+        (name = "stats", description = "Rolling transaction, fee and activity statistics"),
+        (name = "rpc", description = "Solana JSON-RPC"),
+        (name = "nfts", description = "NFT metadata"),
+        (name = "idls", description = "Anchor IDLs and decoded data"),
+        (name = "events", description = "Program invocations and events read from transaction logs"),
+        (name = "network", description = "Peers of the attached network node"),
+        (name = "proofs", description = "Network attestations and merkle proofs of slot data"),
+        (name = "staking", description = "Operators, rewards, slashes and Solana epoch of the staking service"),
+        (name = "admin", description = "API keys and maintenance"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Credentials accepted when authentication is enabled
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+        );
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// Serve `openapi` at `<prefix>/openapi.json` and Swagger UI at `<prefix>/docs`
+pub fn docs_router(prefix: &str, mut openapi: OpenApiDocument) -> Router<AppState> {
+    if !prefix.is_empty() {
+        openapi.servers = Some(vec![Server::new(prefix)]);
+    }
+
+    SwaggerUi::new(format!("{}/docs", prefix))
+        .url(format!("{}/openapi.json", prefix), openapi)
+        .into()
+}
@@ -0,0 +1,67 @@
+//! Network peers
+//!
+//! `GET /peers` lists every peer in the attached node's registry, including
+//! peers seen before the last restart, with what the node last observed of
+//! each. Banning and allowlisting stay under the admin API.
+
+use {
+    crate::{
+        auth::{scoped, RouteScope},
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{extract::State, Json},
+    serde::Serialize,
+    utoipa::ToSchema,
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::control::PeerRecord,
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NetworkPeer {
+    pub peer_id: String,
+    pub connected: bool,
+    /// Unix timestamp in seconds
+    pub last_seen: i64,
+    pub topics: Vec<String>,
+    pub protocol_version: Option<String>,
+    /// Round-trip time of the latest ping
+    pub latency_ms: Option<u64>,
+    /// Stake of the peer's operator, when known
+    pub stake: Option<u64>,
+}
+
+impl From<PeerRecord> for NetworkPeer {
+    fn from(peer: PeerRecord) -> Self {
+        Self {
+            peer_id: peer.peer_id,
+            connected: peer.connected,
+            last_seen: peer.last_seen,
+            topics: peer.topics,
+            protocol_version: peer.protocol_version,
+            latency_ms: peer.latency_ms,
+            stake: peer.stake,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/peers",
+    tag = "network",
+    responses(
+        (status = 200, description = "Known peers, most recently seen first", body = ApiResponse<Vec<NetworkPeer>>),
+        (status = 404, description = "No network node is attached", body = ApiError)
+    )
+)]
+pub async fn list_network_peers(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<NetworkPeer>>>, ApiError> {
+    let peer_control = state.peer_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No network node is attached".to_string()))?;
+    let peers = peer_control.registry().await
+        .map_err(|e| ApiError::Internal(format!("Failed to read peer registry: {}", e)))?;
+    Ok(Json(ApiResponse::success(peers.into_iter().map(NetworkPeer::from).collect())))
+}
+
+pub fn create_peer_router() -> OpenApiRouter<AppState> {
+    scoped(OpenApiRouter::new().routes(routes!(list_network_peers)), RouteScope::ReadWrite)
+}
@@ -0,0 +1,158 @@
+//! Data availability proofs
+//!
+//! `GET /proof/slot/{slot}` returns what a light client needs to check data
+//! served by any single node against the network: the slot's content hash,
+//! the operator votes that network-finalized it, and merkle proofs tying
+//! the slot's block, transactions and account updates to that hash. A
+//! client recomputes a record's leaf from its canonical encoding (see
+//! `windexer_common::types::content_hash`), walks the proof up to the
+//! content hash and checks enough stake signed it.
+
+use {
+    crate::{
+        auth::{scoped, RouteScope},
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{
+        extract::{Path, Query, State},
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::hash::Hash,
+    utoipa::{IntoParams, ToSchema},
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::types::{CanonicalEncode, MerkleProof, SlotContent},
+};
+
+/// Upper bound on the records read for one slot
+const MAX_SLOT_RECORDS: usize = 1_000_000;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct OperatorSignature {
+    pub operator: String,
+    /// Signature over the slot and content hash
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RecordProof {
+    /// Transaction signature or account pubkey; the slot for the block
+    pub key: String,
+    /// Hash of the record's canonical encoding
+    pub leaf: String,
+    /// Position among the slot's leaves
+    pub index: usize,
+    /// Bottom up
+    pub siblings: Vec<String>,
+}
+
+impl RecordProof {
+    fn new(key: String, leaf: Hash, proof: MerkleProof) -> Self {
+        Self {
+            key,
+            leaf: leaf.to_string(),
+            index: proof.index,
+            siblings: proof.siblings.iter().map(Hash::to_string).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SlotProof {
+    pub slot: u64,
+    pub content_hash: String,
+    /// Stake of the operators that signed
+    pub stake: u64,
+    pub total_stake: u64,
+    pub signatures: Vec<OperatorSignature>,
+    pub leaf_count: usize,
+    pub block: RecordProof,
+    pub transactions: Vec<RecordProof>,
+    pub accounts: Vec<RecordProof>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProofParams {
+    /// Only prove this transaction
+    pub transaction: Option<String>,
+    /// Only prove the updates of this account
+    pub account: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/proof/slot/{slot}",
+    tag = "proofs",
+    params(("slot" = u64, Path, description = "Network-finalized slot"), ProofParams),
+    responses(
+        (status = 200, description = "Content hash, operator signatures and merkle proofs", body = ApiResponse<SlotProof>),
+        (status = 404, description = "Slot not network-finalized, or record not in the slot", body = ApiError),
+        (status = 503, description = "Stored data does not match the attested hash", body = ApiError)
+    )
+)]
+pub async fn get_slot_proof(
+    State(state): State<AppState>,
+    Path(slot): Path<u64>,
+    Query(params): Query<ProofParams>,
+) -> Result<Json<ApiResponse<SlotProof>>, ApiError> {
+    let storage = state.store()
+        .ok_or_else(|| ApiError::Internal("Storage not initialized".to_string()))?;
+
+    let attestation = storage.get_slot_attestation(slot).await.map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::NotFound(format!("Slot {} is not network-finalized", slot)))?;
+    let block = storage.get_block(slot).await.map_err(ApiError::storage)?
+        .ok_or_else(|| ApiError::NotFound(format!("Block {} is not stored", slot)))?;
+    let transactions = storage.get_transactions_by_slot_range(slot, slot, MAX_SLOT_RECORDS).await.map_err(ApiError::storage)?;
+    let accounts = storage.get_accounts_by_slot_range(slot, slot, MAX_SLOT_RECORDS).await.map_err(ApiError::storage)?;
+
+    let content = SlotContent::new(&block, &transactions, &accounts);
+    if content.root() != attestation.content_hash {
+        return Err(ApiError::Unavailable(format!(
+            "Stored data of slot {} does not match its attested hash {}",
+            slot, attestation.content_hash
+        )));
+    }
+    let prove = |key: String, leaf: Hash| {
+        content.proof(&leaf).map(|proof| RecordProof::new(key, leaf, proof))
+    };
+
+    let filtered = params.transaction.is_some() || params.account.is_some();
+    let transactions: Vec<RecordProof> = transactions.iter()
+        .filter(|tx| !filtered || params.transaction.as_deref() == Some(tx.signature.to_string().as_str()))
+        .filter_map(|tx| prove(tx.signature.to_string(), tx.content_hash()))
+        .collect();
+    let accounts: Vec<RecordProof> = accounts.iter()
+        .filter(|account| !filtered || params.account.as_deref() == Some(account.pubkey.to_string().as_str()))
+        .filter_map(|account| prove(account.pubkey.to_string(), account.content_hash()))
+        .collect();
+    if params.transaction.is_some() && transactions.is_empty() {
+        return Err(ApiError::NotFound(format!("Transaction is not in slot {}", slot)));
+    }
+    if params.account.is_some() && accounts.is_empty() {
+        return Err(ApiError::NotFound(format!("Account was not updated in slot {}", slot)));
+    }
+
+    Ok(Json(ApiResponse::success(SlotProof {
+        slot,
+        content_hash: attestation.content_hash.to_string(),
+        stake: attestation.stake,
+        total_stake: attestation.total_stake,
+        signatures: attestation.votes.iter()
+            .map(|vote| OperatorSignature {
+                operator: vote.voter.to_string(),
+                signature: vote.signature.to_string(),
+            })
+            .collect(),
+        leaf_count: content.leaves().len(),
+        block: prove(slot.to_string(), content.block)
+            .ok_or_else(|| ApiError::Internal("Block is missing from its own slot".to_string()))?,
+        transactions,
+        accounts,
+    })))
+}
+
+pub fn create_proof_router() -> OpenApiRouter<AppState> {
+    scoped(OpenApiRouter::new().routes(routes!(get_slot_proof)), RouteScope::ReadWrite)
+}
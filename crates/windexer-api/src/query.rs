@@ -0,0 +1,408 @@
+//! Unified query endpoint
+//!
+//! `POST /query` takes a filter document instead of one route per filter
+//! combination:
+//!
+//! ```json
+//! {
+//!   "entity": "transactions",
+//!   "slot": { "from": 250000000, "to": 250000100 },
+//!   "filter": { "program": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "success": true },
+//!   "fields": ["signature", "slot", "fee"],
+//!   "sort": { "field": "fee", "order": "desc" },
+//!   "limit": 50
+//! }
+//! ```
+//!
+//! The most selective filter picks the store lookup (pubkeys, then the account
+//! or program index, then the slot range, then the most recent rows) and the
+//! remaining filters are applied to what it returns. Sorting and projection
+//! work on the same JSON fields the REST routes return. An optional
+//! `commitment` caps the slot range at the newest slot that reached it.
+
+use {
+    crate::{
+        account_endpoints::AccountData,
+        auth::{scoped, RouteScope},
+        block_endpoints::BlockData,
+        rest::AppState,
+        transaction_endpoints::TransactionData,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{extract::State, Json},
+    serde::{Deserialize, Serialize},
+    serde_json::{Map, Value},
+    std::{cmp::Ordering, sync::Arc},
+    utoipa::ToSchema,
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::types::{ApiScope, Commitment},
+    windexer_store::{traits::Storage, StorageError},
+};
+
+/// Rows returned unless `limit` is given
+const DEFAULT_LIMIT: usize = 100;
+
+/// Largest `limit` accepted
+const MAX_LIMIT: usize = 1000;
+
+/// Rows read from the store per requested row when filters are applied after the lookup
+const SCAN_FACTOR: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Entity {
+    Accounts,
+    Transactions,
+    Blocks,
+}
+
+/// Inclusive slot bounds; either end may be left open
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+pub struct SlotRange {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+}
+
+impl SlotRange {
+    fn bounds(&self) -> (u64, u64) {
+        (self.from.unwrap_or(0), self.to.unwrap_or(u64::MAX))
+    }
+
+    fn contains(&self, slot: u64) -> bool {
+        let (from, to) = self.bounds();
+        (from..=to).contains(&slot)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QueryFilter {
+    /// Accounts with these addresses
+    #[serde(default)]
+    pub pubkeys: Vec<String>,
+    /// Accounts owned by this program
+    pub owner: Option<String>,
+    pub min_lamports: Option<u64>,
+    pub max_lamports: Option<u64>,
+    /// Transactions mentioning this account
+    pub account: Option<String>,
+    /// Transactions invoking this program
+    pub program: Option<String>,
+    pub is_vote: Option<bool>,
+    pub success: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct Sort {
+    pub field: String,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct QueryRequest {
+    pub entity: Entity,
+    pub slot: Option<SlotRange>,
+    #[serde(default)]
+    pub filter: QueryFilter,
+    /// Fields to return; all of them when omitted
+    pub fields: Option<Vec<String>>,
+    /// Defaults to descending slot
+    pub sort: Option<Sort>,
+    pub limit: Option<usize>,
+    /// Only return data from slots that reached this commitment; defaults to processed
+    pub commitment: Option<Commitment>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryResult {
+    pub entity: Entity,
+    pub count: usize,
+    #[schema(value_type = Vec<Object>)]
+    pub items: Vec<Value>,
+}
+
+impl QueryRequest {
+    fn validate(&self) -> Result<(), ApiError> {
+        if let Some(SlotRange { from: Some(from), to: Some(to) }) = self.slot {
+            if from > to {
+                return Err(ApiError::BadRequest(format!("Slot range {}..{} is empty", from, to)));
+            }
+        }
+        if self.limit == Some(0) {
+            return Err(ApiError::BadRequest("limit must be positive".to_string()));
+        }
+
+        let filter = &self.filter;
+        let misplaced = match self.entity {
+            Entity::Accounts => [
+                ("account", filter.account.is_some()),
+                ("program", filter.program.is_some()),
+                ("is_vote", filter.is_vote.is_some()),
+                ("success", filter.success.is_some()),
+            ].into_iter().find(|(_, set)| *set),
+            Entity::Transactions => [
+                ("pubkeys", !filter.pubkeys.is_empty()),
+                ("owner", filter.owner.is_some()),
+                ("min_lamports", filter.min_lamports.is_some()),
+                ("max_lamports", filter.max_lamports.is_some()),
+            ].into_iter().find(|(_, set)| *set),
+            Entity::Blocks => [
+                ("pubkeys", !filter.pubkeys.is_empty()),
+                ("owner", filter.owner.is_some()),
+                ("min_lamports", filter.min_lamports.is_some()),
+                ("max_lamports", filter.max_lamports.is_some()),
+                ("account", filter.account.is_some()),
+                ("program", filter.program.is_some()),
+                ("is_vote", filter.is_vote.is_some()),
+                ("success", filter.success.is_some()),
+            ].into_iter().find(|(_, set)| *set),
+        };
+        match misplaced {
+            Some((name, _)) => Err(ApiError::BadRequest(format!("Filter {} does not apply to {:?}", name, self.entity))),
+            None => Ok(()),
+        }
+    }
+
+    /// Caps the slot range at `bound`, the newest slot visible at the requested commitment
+    fn clamp_to(&mut self, bound: u64) {
+        let range = self.slot.get_or_insert_with(SlotRange::default);
+        range.to = Some(range.to.map_or(bound, |to| to.min(bound)));
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+    }
+
+    /// Rows to read from the store so that enough survive the remaining filters
+    fn scan_limit(&self, filtered: bool) -> usize {
+        if filtered || self.sort.is_some() {
+            self.limit() * SCAN_FACTOR
+        } else {
+            self.limit()
+        }
+    }
+}
+
+fn to_rows<T: Serialize>(items: impl IntoIterator<Item = T>) -> Result<Vec<Value>, ApiError> {
+    items.into_iter()
+        .map(|item| serde_json::to_value(item).map_err(|e| ApiError::Internal(e.to_string())))
+        .collect()
+}
+
+fn store_error(e: anyhow::Error) -> ApiError {
+    ApiError::from_code(StorageError::code_of(&e), format!("Query failed: {}", e))
+}
+
+async fn query_accounts(storage: &Arc<dyn Storage>, request: &QueryRequest) -> Result<Vec<Value>, ApiError> {
+    let filter = &request.filter;
+    let slot = request.slot.unwrap_or_default();
+
+    let accounts = if !filter.pubkeys.is_empty() {
+        let mut accounts = Vec::with_capacity(filter.pubkeys.len());
+        for pubkey in &filter.pubkeys {
+            let account = match slot.to {
+                Some(to) => storage.get_account_at_slot(pubkey, to).await,
+                None => storage.get_account(pubkey).await,
+            };
+            accounts.extend(account.map_err(store_error)?);
+        }
+        accounts
+    } else {
+        let filtered = filter.owner.is_some() || filter.min_lamports.is_some() || filter.max_lamports.is_some();
+        let scan = request.scan_limit(filtered);
+        match request.slot {
+            Some(range) => {
+                let (from, to) = range.bounds();
+                storage.get_accounts_by_slot_range(from, to, scan).await
+            }
+            None => storage.get_recent_accounts(scan).await,
+        }.map_err(store_error)?
+    };
+
+    let owner = filter.owner.as_deref();
+    to_rows(accounts.iter()
+        .filter(|account| slot.contains(account.slot))
+        .filter(|account| owner.map_or(true, |owner| account.owner.to_string() == owner))
+        .filter(|account| filter.min_lamports.map_or(true, |min| account.lamports >= min))
+        .filter(|account| filter.max_lamports.map_or(true, |max| account.lamports <= max))
+        .map(AccountData::from))
+}
+
+async fn query_transactions(storage: &Arc<dyn Storage>, request: &QueryRequest) -> Result<Vec<Value>, ApiError> {
+    let filter = &request.filter;
+    let slot = request.slot.unwrap_or_default();
+    let residual = filter.is_vote.is_some() || filter.success.is_some();
+    let indexed_scan = request.scan_limit(
+        residual || request.slot.is_some() || (filter.account.is_some() && filter.program.is_some()),
+    );
+
+    let transactions = if let Some(account) = &filter.account {
+        storage.get_transactions_by_account(account, indexed_scan).await
+    } else if let Some(program) = &filter.program {
+        storage.get_transactions_by_program(program, indexed_scan).await
+    } else if let Some(range) = request.slot {
+        let (from, to) = range.bounds();
+        storage.get_transactions_by_slot_range(from, to, request.scan_limit(residual)).await
+    } else {
+        storage.get_recent_transactions(request.scan_limit(residual)).await
+    }.map_err(store_error)?;
+
+    let mentions = |transaction: &windexer_common::types::TransactionData, key: &str| {
+        transaction.message.account_keys.iter().any(|account| account.to_string() == key)
+    };
+    to_rows(transactions.iter()
+        .filter(|transaction| slot.contains(transaction.slot))
+        .filter(|transaction| filter.account.as_deref().map_or(true, |account| mentions(transaction, account)))
+        .filter(|transaction| filter.program.as_deref().map_or(true, |program| mentions(transaction, program)))
+        .filter(|transaction| filter.is_vote.map_or(true, |is_vote| transaction.is_vote == is_vote))
+        .filter(|transaction| {
+            filter.success.map_or(true, |success| (transaction.serializable_meta.status == Some(0)) == success)
+        })
+        .map(TransactionData::from))
+}
+
+async fn query_blocks(storage: &Arc<dyn Storage>, request: &QueryRequest) -> Result<Vec<Value>, ApiError> {
+    let scan = request.scan_limit(false);
+    let blocks = match request.slot {
+        Some(range) => {
+            let (from, to) = range.bounds();
+            storage.get_blocks_by_slot_range(from, to, scan).await
+        }
+        None => storage.get_recent_blocks(scan).await,
+    }.map_err(store_error)?;
+
+    to_rows(blocks.iter().map(BlockData::from))
+}
+
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => {
+            a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal)
+        }
+        (Some(Value::String(a)), Some(Value::String(b))) => a.cmp(b),
+        (Some(Value::Bool(a)), Some(Value::Bool(b))) => a.cmp(b),
+        (Some(Value::Null) | None, Some(Value::Null) | None) => Ordering::Equal,
+        (Some(Value::Null) | None, _) => Ordering::Less,
+        (_, Some(Value::Null) | None) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Sort, truncate and project rows
+fn shape(mut rows: Vec<Value>, request: &QueryRequest) -> Result<Vec<Value>, ApiError> {
+    let known = |field: &str| rows.first().map_or(true, |row| row.get(field).is_some());
+
+    let (field, order) = match &request.sort {
+        Some(sort) => (sort.field.as_str(), sort.order),
+        None => ("slot", SortOrder::Desc),
+    };
+    if !known(field) {
+        return Err(ApiError::BadRequest(format!("Cannot sort {:?} by unknown field {}", request.entity, field)));
+    }
+    if let Some(fields) = &request.fields {
+        if let Some(unknown) = fields.iter().find(|field| !known(field)) {
+            return Err(ApiError::BadRequest(format!("Unknown {:?} field {}", request.entity, unknown)));
+        }
+    }
+
+    rows.sort_by(|a, b| {
+        let ordering = compare(a.get(field), b.get(field));
+        match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+    rows.truncate(request.limit());
+
+    if let Some(fields) = &request.fields {
+        rows = rows.into_iter()
+            .map(|row| {
+                let mut row = match row {
+                    Value::Object(row) => row,
+                    other => return other,
+                };
+                Value::Object(fields.iter()
+                    .filter_map(|field| row.remove_entry(field.as_str()))
+                    .collect::<Map<String, Value>>())
+            })
+            .collect();
+    }
+    Ok(rows)
+}
+
+#[utoipa::path(
+    post,
+    path = "/query",
+    tag = "query",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "Matching rows", body = ApiResponse<QueryResult>),
+        (status = 400, description = "Invalid request", body = ApiError)
+    )
+)]
+pub async fn run_query(
+    State(state): State<AppState>,
+    Json(mut request): Json<QueryRequest>,
+) -> Result<Json<ApiResponse<QueryResult>>, ApiError> {
+    request.validate()?;
+    let storage = state.store()
+        .ok_or_else(|| ApiError::Internal("Storage not initialized".to_string()))?;
+
+    if let Some(commitment) = request.commitment {
+        let bound = storage.get_latest_slot_with_commitment(commitment).await.map_err(store_error)?;
+        if let Some(bound) = bound {
+            request.clamp_to(bound);
+        }
+    }
+
+    let rows = match request.entity {
+        Entity::Accounts => query_accounts(&storage, &request).await?,
+        Entity::Transactions => query_transactions(&storage, &request).await?,
+        Entity::Blocks => query_blocks(&storage, &request).await?,
+    };
+    let items = shape(rows, &request)?;
+
+    Ok(Json(ApiResponse::success(QueryResult {
+        entity: request.entity,
+        count: items.len(),
+        items,
+    })))
+}
+
+pub fn create_query_router() -> OpenApiRouter<AppState> {
+    // Queries are reads sent with POST
+    scoped(OpenApiRouter::new().routes(routes!(run_query)), RouteScope::All(ApiScope::ReadOnly))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn test_shape_sorts_truncates_and_projects() {
+        let request: QueryRequest = serde_json::from_value(json!({
+            "entity": "blocks",
+            "fields": ["slot"],
+            "sort": { "field": "transaction_count", "order": "asc" },
+            "limit": 2,
+        })).unwrap();
+        let rows = vec![
+            json!({ "slot": 1, "transaction_count": 30 }),
+            json!({ "slot": 2, "transaction_count": 10 }),
+            json!({ "slot": 3, "transaction_count": 20 }),
+        ];
+
+        let items = shape(rows, &request).unwrap();
+        assert_eq!(items, vec![json!({ "slot": 2 }), json!({ "slot": 3 })]);
+    }
+}
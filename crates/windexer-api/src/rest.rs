@@ -1,16 +1,25 @@
 use axum::{
     Router,
-    routing::get,
+    middleware,
     extract::State,
-    http::{Method, HeaderValue, header},
+    http::{Method, HeaderValue, StatusCode, header},
 };
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tower_http::cors::{CorsLayer, Any};
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
 use tokio::sync::RwLock;
 use std::net::SocketAddr;
 use std::collections::HashMap;
 use serde_json::Value;
+use windexer_common::alerts::{self, AlertBus};
+use windexer_common::control::{DeadLetterControl, EpochStatus, FilterControl, LogControl, PeerControl, StakingControl, TopicControl};
+use windexer_common::lag::{LagConfig, LagMonitor};
+use windexer_common::metrics::{MetricsRegistry, TEXT_CONTENT_TYPE};
+use windexer_common::rpc_provider::RpcProvider;
+use windexer_common::types::ApiScope;
+use windexer_common::shutdown::{Shutdown, ShutdownToken, Stage};
 use tokio::net::TcpListener;
 use tracing::{debug, info, error, warn};
 use axum::extract::connect_info::IntoMakeServiceWithConnectInfo;
@@ -21,14 +30,35 @@ use std::convert::Infallible;
 use std::future::Future;
 use std::pin::Pin;
 
-use crate::health::HealthService;
-use crate::metrics::MetricsService;
+use crate::health::{self, HealthChecksConfig, HealthService, Probe};
+use crate::metrics::{track_requests, MetricsService, RequestMetrics};
 use crate::types::{ApiResponse, HealthResponse, StatusResponse};
 
 use crate::account_endpoints::create_account_router;
 use crate::transaction_endpoints::create_transaction_router;
 use crate::block_endpoints::create_block_router;
 use crate::endpoints::create_deployment_router;
+use crate::rpc::create_rpc_router;
+use crate::admin::create_admin_router;
+use crate::peer_endpoints::create_peer_router;
+use crate::auth::{require_auth, scoped, AuthConfig, AuthService, RouteScope};
+use crate::openapi::{docs_router, ApiDoc};
+use crate::tenant::{select_tenant, TenantConfig, TenantRegistry};
+#[cfg(feature = "store")]
+use crate::event_endpoints::create_event_router;
+#[cfg(feature = "store")]
+use crate::nft_endpoints::create_nft_router;
+#[cfg(feature = "store")]
+use crate::idl_endpoints::create_idl_router;
+#[cfg(feature = "store")]
+use crate::proof_endpoints::create_proof_router;
+#[cfg(feature = "store")]
+use crate::query::create_query_router;
+#[cfg(feature = "store")]
+use crate::analytics::create_stats_router;
+#[cfg(feature = "store")]
+use crate::search::create_search_router;
+use crate::staking_endpoints::create_staking_router;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -42,6 +72,49 @@ pub struct AppState {
     pub account_data_manager: Option<Arc<crate::account_data_manager::AccountDataManager>>,
     pub transaction_data_manager: Option<Arc<crate::transaction_data_manager::TransactionDataManager>>,
     pub helius_client: Option<Arc<crate::helius::HeliusClient>>,
+    pub subscriptions: Option<Arc<crate::subscriptions::SubscriptionHub>>,
+    /// Set when authentication is enabled
+    pub auth: Option<Arc<AuthService>>,
+    /// Prefix the API routes are nested under, empty for none
+    pub path_prefix: String,
+    pub peer_control: Option<Arc<dyn PeerControl>>,
+    pub filter_control: Option<Arc<dyn FilterControl>>,
+    pub topic_control: Option<Arc<dyn TopicControl>>,
+    pub log_control: Option<Arc<dyn LogControl>>,
+    /// Deliveries that failed, kept for retrying
+    pub dead_letter_control: Option<Arc<dyn DeadLetterControl>>,
+    /// Operators, rewards and slashes of the staking service
+    pub staking_control: Option<Arc<dyn StakingControl>>,
+    /// Solana epoch followed by the staking service
+    pub epoch_status: Option<Arc<dyn EpochStatus>>,
+    pub tenants: Arc<TenantRegistry>,
+    /// Operational alerts, streamed at `/stream/alerts`
+    pub alerts: AlertBus,
+    pub lag: Option<Arc<LagMonitor>>,
+    /// Metrics of every component, served at `/metrics`
+    pub registry: MetricsRegistry,
+    pub requests: Arc<RequestMetrics>,
+    /// Shared store; handlers use [`AppState::store`] to respect the request's tenant
+    #[cfg(feature = "store")]
+    pub storage: Option<Arc<dyn windexer_store::traits::Storage>>,
+    #[cfg(feature = "store")]
+    pub idl_registry: Option<Arc<windexer_store::decoders::IdlRegistry>>,
+    #[cfg(feature = "store")]
+    pub backfills: Option<Arc<crate::admin::BackfillJobs>>,
+    #[cfg(feature = "store")]
+    pub stats: Option<Arc<crate::analytics::StatsService>>,
+}
+
+impl AppState {
+    /// Store of the request's tenant, or the shared store outside of a tenant
+    #[cfg(feature = "store")]
+    pub fn store(&self) -> Option<Arc<dyn windexer_store::traits::Storage>> {
+        let storage = self.storage.as_ref()?;
+        match crate::tenant::current_tenant() {
+            Some(tenant) => storage.tenant(&tenant),
+            None => Some(storage.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,6 +125,17 @@ pub struct ApiConfig {
     pub enable_metrics: bool,
     pub node_info: Option<crate::types::NodeInfo>,
     pub path_prefix: Option<String>,
+    /// Address of the gRPC streaming API; needs the `grpc` feature
+    pub grpc_addr: Option<std::net::SocketAddr>,
+    pub auth: AuthConfig,
+    /// How often `/stats` is recomputed from the store; `None` disables it
+    pub stats_refresh_interval: Option<Duration>,
+    /// Tenants selectable with `X-Windexer-Tenant` or a tenant-bound credential
+    pub tenants: Vec<TenantConfig>,
+    /// Thresholds of the store, upstream, peer and chain lag checks
+    pub health_checks: HealthChecksConfig,
+    /// Watch how far the data feed trails the upstream's tip; `None` disables it
+    pub slot_lag: Option<LagConfig>,
 }
 
 impl Default for ApiConfig {
@@ -63,6 +147,12 @@ impl Default for ApiConfig {
             enable_metrics: true,
             node_info: None,
             path_prefix: Some("/api".to_string()),
+            grpc_addr: None,
+            auth: AuthConfig::default(),
+            stats_refresh_interval: Some(Duration::from_secs(60)),
+            tenants: Vec::new(),
+            health_checks: HealthChecksConfig::default(),
+            slot_lag: None,
         }
     }
 }
@@ -72,12 +162,26 @@ pub struct ApiServer {
     health_service: Arc<HealthService>,
     metrics_service: Arc<MetricsService>,
     state: AppState,
+    upstream: Option<Arc<dyn RpcProvider>>,
 }
 
 impl ApiServer {
     pub fn new(config: ApiConfig) -> Self {
         let health_service = Arc::new(HealthService::new());
         let metrics_service = Arc::new(MetricsService::new());
+        let alerts = AlertBus::new();
+        let registry = MetricsRegistry::new();
+        let requests = Arc::new(RequestMetrics::new());
+        if let Err(e) = registry.register(requests.clone()) {
+            warn!("Failed to register the request metrics: {}", e);
+        }
+        let lag = config.slot_lag.clone().map(|lag_config| {
+            let monitor = Arc::new(LagMonitor::new(lag_config, alerts.clone()));
+            if let Err(e) = registry.register(monitor.clone()) {
+                warn!("Failed to register the slot lag gauges: {}", e);
+            }
+            monitor
+        });
 
         let state = AppState {
             health: health_service.clone(),
@@ -86,6 +190,12 @@ impl ApiServer {
             config: Arc::new(RwLock::new(serde_json::json!({
                 "service_name": config.service_name,
                 "version": config.version,
+                "bind_addr": config.bind_addr,
+                "path_prefix": config.path_prefix,
+                "grpc_addr": config.grpc_addr,
+                "enable_metrics": config.enable_metrics,
+                "auth_enabled": config.auth.enabled,
+                "tenants": config.tenants.iter().map(|tenant| &tenant.id).collect::<Vec<_>>(),
             }))),
             service_name: config.service_name.clone(),
             version: config.version.clone(),
@@ -93,6 +203,29 @@ impl ApiServer {
             account_data_manager: None,
             transaction_data_manager: None,
             helius_client: None,
+            subscriptions: None,
+            auth: config.auth.enabled.then(|| Arc::new(AuthService::new(config.auth.clone()))),
+            path_prefix: config.path_prefix.clone().unwrap_or_default(),
+            peer_control: None,
+            filter_control: None,
+            topic_control: None,
+            log_control: None,
+            dead_letter_control: None,
+            staking_control: None,
+            epoch_status: None,
+            tenants: Arc::new(TenantRegistry::new(config.tenants.clone())),
+            alerts,
+            lag,
+            registry,
+            requests,
+            #[cfg(feature = "store")]
+            storage: None,
+            #[cfg(feature = "store")]
+            idl_registry: None,
+            #[cfg(feature = "store")]
+            backfills: None,
+            #[cfg(feature = "store")]
+            stats: None,
         };
 
         Self {
@@ -100,6 +233,7 @@ impl ApiServer {
             health_service,
             metrics_service,
             state,
+            upstream: None,
         }
     }
 
@@ -115,6 +249,72 @@ impl ApiServer {
         self.state.helius_client = Some(helius_client);
     }
 
+    /// Serve live subscriptions from the node's data feed
+    pub fn set_feed(&mut self, feed: windexer_common::feed::DataFeed) {
+        self.state.subscriptions = Some(Arc::new(crate::subscriptions::SubscriptionHub::new(feed)));
+    }
+
+    /// Let the admin routes list and ban the network node's peers
+    pub fn set_peer_control(&mut self, peer_control: Arc<dyn PeerControl>) {
+        self.state.peer_control = Some(peer_control);
+    }
+
+    /// Let the admin routes show and reload the data filters
+    pub fn set_filter_control(&mut self, filter_control: Arc<dyn FilterControl>) {
+        self.state.filter_control = Some(filter_control);
+    }
+
+    /// Let the admin routes list and change the network node's topic subscriptions
+    pub fn set_topic_control(&mut self, topic_control: Arc<dyn TopicControl>) {
+        self.state.topic_control = Some(topic_control);
+    }
+
+    /// Let the admin routes change the process's log levels
+    pub fn set_log_control(&mut self, log_control: Arc<dyn LogControl>) {
+        self.state.log_control = Some(log_control);
+    }
+
+    /// Let the admin routes list, retry and purge failed deliveries
+    pub fn set_dead_letter_control(&mut self, dead_letter_control: Arc<dyn DeadLetterControl>) {
+        self.state.dead_letter_control = Some(dead_letter_control);
+    }
+
+    /// Serve the staking service's operators, rewards and slashes under
+    /// `/staking`
+    pub fn set_staking_control(&mut self, staking_control: Arc<dyn StakingControl>) {
+        self.state.staking_control = Some(staking_control);
+    }
+
+    /// Serve the Solana epoch the staking service follows at `/staking/epoch`
+    pub fn set_epoch_status(&mut self, epoch_status: Arc<dyn EpochStatus>) {
+        self.state.epoch_status = Some(epoch_status);
+    }
+
+    #[cfg(feature = "store")]
+    pub fn set_storage(&mut self, storage: Arc<dyn windexer_store::traits::Storage>) {
+        self.state.stats = self.config.stats_refresh_interval
+            .map(|_| Arc::new(crate::analytics::StatsService::new(storage.clone())));
+        self.state.storage = Some(storage);
+    }
+
+    #[cfg(feature = "store")]
+    pub fn set_idl_registry(&mut self, idl_registry: Arc<windexer_store::decoders::IdlRegistry>) {
+        self.state.idl_registry = Some(idl_registry);
+    }
+
+    /// Allow backfills through the admin routes, each run with `template`'s
+    /// RPC endpoint and limits over the requested range
+    #[cfg(feature = "store")]
+    pub fn set_backfill_config(&mut self, template: windexer_store::backfill::BackfillConfig) {
+        self.state.backfills = Some(Arc::new(crate::admin::BackfillJobs::new(template)));
+    }
+
+    /// Check the upstream RPC for readiness, and with a store, how far the
+    /// store trails the chain tip
+    pub fn set_upstream(&mut self, upstream: Arc<dyn RpcProvider>) {
+        self.upstream = Some(upstream);
+    }
+
     pub fn health(&self) -> Arc<HealthService> {
         self.health_service.clone()
     }
@@ -123,59 +323,197 @@ impl ApiServer {
         self.metrics_service.clone()
     }
 
+    /// Registry served at `/metrics`; register the metrics of the network
+    /// node and the storage factory here to export them with the server's
+    pub fn metrics_registry(&self) -> MetricsRegistry {
+        self.state.registry.clone()
+    }
+
+    pub fn alerts(&self) -> AlertBus {
+        self.state.alerts.clone()
+    }
+
+    /// The slot lag monitor, when `slot_lag` is configured; the network node
+    /// can report the tip it learns from gossip with [`LagMonitor::observe_tip`]
+    pub fn lag_monitor(&self) -> Option<Arc<LagMonitor>> {
+        self.state.lag.clone()
+    }
+
     pub async fn start(&self) -> anyhow::Result<()> {
+        self.start_with_shutdown(Shutdown::default().token(Stage::Ingress)).await
+    }
+
+    /// Serve until `shutdown` is cancelled, then finish the requests in
+    /// flight before returning
+    pub async fn start_with_shutdown(&self, shutdown: ShutdownToken) -> anyhow::Result<()> {
         tracing::info!("Starting {} API server on {}", self.config.service_name, self.config.bind_addr);
+        self.register_health_checks().await;
+        self.start_lag_monitor(&shutdown);
 
-        let mut router = self.create_router();
+        #[cfg(feature = "store")]
+        {
+            if let (Some(auth), Some(storage)) = (&self.state.auth, &self.state.storage) {
+                if let Err(e) = auth.keys().attach_storage(storage.clone()).await {
+                    warn!("Failed to load API keys from the store, new keys will not be persisted: {}", e);
+                }
+            }
+            if let (Some(stats), Some(interval)) = (&self.state.stats, self.config.stats_refresh_interval) {
+                stats.clone().spawn(interval);
+            }
+            if let (Some(backfills), Some(storage), Some(hub)) = (&self.state.backfills, &self.state.storage, &self.state.subscriptions) {
+                if backfills.heals_gaps() {
+                    backfills.clone().heal_gaps(storage.clone(), hub.feed());
+                }
+            }
+        }
+
+        let router = self.create_router();
+
+        if let Some(grpc_addr) = self.config.grpc_addr {
+            self.start_grpc(grpc_addr, shutdown.clone())?;
+        }
 
-        let jito_blocks_router = crate::block_endpoints::create_jito_compat_blocks_router()
-            .with_state(self.state.clone());
-        let jito_tx_router = crate::transaction_endpoints::create_jito_compat_transaction_router()
-            .with_state(self.state.clone());
-        
-        router = router
-            .merge(jito_blocks_router)
-            .merge(jito_tx_router);
-        
         let listener = tokio::net::TcpListener::bind(self.config.bind_addr).await?;
         tracing::info!("Listening on {}", self.config.bind_addr);
 
-        axum::serve(listener, router).await?;
+        axum::serve(listener, router)
+            .with_graceful_shutdown(shutdown.cancelled_owned())
+            .await?;
+        tracing::info!("{} API server stopped", self.config.service_name);
 
         Ok(())
     }
 
-    fn create_router(&self) -> Router {
+    /// Readiness checks for the dependencies attached to the server
+    async fn register_health_checks(&self) {
+        let thresholds = &self.config.health_checks;
+        if let Some(upstream) = &self.upstream {
+            self.health_service.register_async("upstream", health::upstream_check(upstream.clone(), thresholds.slow_response)).await;
+        }
+        if let Some(peers) = &self.state.peer_control {
+            self.health_service.register_async("peers", health::peer_count_check(peers.clone(), thresholds.min_peers)).await;
+        }
+        #[cfg(feature = "store")]
+        if let Some(storage) = &self.state.storage {
+            self.health_service.register_async("storage", health::storage_check(storage.clone(), thresholds.slow_response)).await;
+            if let Some(upstream) = &self.upstream {
+                self.health_service
+                    .register_async("chain_lag", health::chain_lag_check(storage.clone(), upstream.clone(), thresholds.max_slot_lag))
+                    .await;
+            }
+        }
+    }
+
+    /// Follow the data feed and the upstream's tip, and post alerts to the
+    /// configured webhooks
+    fn start_lag_monitor(&self, shutdown: &ShutdownToken) {
+        let Some(monitor) = &self.state.lag else { return };
+        match &self.state.subscriptions {
+            Some(hub) => drop(monitor.follow_feed(hub.feed(), Some(shutdown.clone()))),
+            None => warn!("Slot lag monitoring needs a data feed for the indexed slot"),
+        }
+        match &self.upstream {
+            Some(upstream) => drop(monitor.poll_tip(upstream.clone(), Some(shutdown.clone()))),
+            None => info!("No upstream RPC to poll the chain tip from; waiting for gossip to report it"),
+        }
+        let webhooks = monitor.config().webhooks.clone();
+        if !webhooks.is_empty() {
+            alerts::spawn_webhooks(&self.state.alerts, webhooks, Some(shutdown.clone()));
+        }
+    }
+
+    #[cfg(feature = "grpc")]
+    fn start_grpc(&self, addr: SocketAddr, shutdown: ShutdownToken) -> anyhow::Result<()> {
+        let service = crate::grpc::GeyserService::new(&self.state)?;
+        tokio::spawn(crate::grpc::serve(addr, service, self.state.auth.clone(), shutdown));
+        Ok(())
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    fn start_grpc(&self, addr: SocketAddr, _shutdown: ShutdownToken) -> anyhow::Result<()> {
+        warn!("Not starting gRPC API on {}: built without the grpc feature", addr);
+        Ok(())
+    }
+
+    pub(crate) fn create_router(&self) -> Router {
         let cors = CorsLayer::new()
             .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
             .allow_headers(Any)
             .allow_origin(Any)
             .expose_headers(Any);
 
-        let mut router = Router::new()
-            .route("/health", get(health_handler))
-            .route("/status", get(status_handler));
+        // Health and status are public; every other router sets the scope
+        // its routes need
+        let mut api = OpenApiRouter::with_openapi(ApiDoc::openapi())
+            .routes(routes!(health_handler))
+            .routes(routes!(liveness_handler))
+            .routes(routes!(readiness_handler))
+            .routes(routes!(status_handler))
+            .merge(scoped(OpenApiRouter::new().routes(routes!(alert_events)), RouteScope::All(ApiScope::Stream)));
 
         if self.config.enable_metrics {
-            router = router.route("/metrics", get(metrics_handler));
+            api = api.merge(scoped(OpenApiRouter::new().routes(routes!(metrics_handler)), RouteScope::ReadWrite));
         }
 
-        router = router
+        api = api
             .merge(create_account_router())
             .merge(create_transaction_router())
             .merge(create_block_router())
-            .merge(create_deployment_router());
+            .merge(create_deployment_router())
+            .merge(create_rpc_router())
+            .merge(create_peer_router())
+            .merge(create_staking_router());
 
-        if let Some(prefix) = &self.config.path_prefix {
-            router = Router::new().nest(prefix, router);
+        #[cfg(feature = "store")]
+        {
+            api = api
+                .merge(create_nft_router())
+                .merge(create_idl_router())
+                .merge(create_event_router())
+                .merge(create_query_router())
+                .merge(create_search_router())
+                .merge(create_stats_router())
+                .merge(create_proof_router());
         }
 
-        router = router.layer(cors);
+        if self.state.auth.is_some() {
+            api = api.merge(create_admin_router());
+        }
+
+        let (mut router, openapi) = api.split_for_parts();
 
-        router.with_state(self.state.clone())
+        #[cfg(feature = "graphql")]
+        {
+            router = router.merge(crate::graphql::create_graphql_router(self.state.clone()));
+        }
+
+        let prefix = self.config.path_prefix.clone().unwrap_or_default();
+        if !prefix.is_empty() {
+            router = Router::new().nest(&prefix, router);
+        }
+        router = router.merge(docs_router(&prefix, openapi));
+
+        let jito_blocks_router = crate::block_endpoints::create_jito_compat_blocks_router();
+        let jito_tx_router = crate::transaction_endpoints::create_jito_compat_transaction_router();
+
+        router
+            .merge(jito_blocks_router)
+            .merge(jito_tx_router)
+            .layer(middleware::from_fn_with_state(self.state.clone(), select_tenant))
+            .layer(middleware::from_fn_with_state(self.state.clone(), require_auth))
+            .layer(middleware::from_fn_with_state(self.state.clone(), track_requests))
+            .layer(cors)
+            .with_state(self.state.clone())
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "service",
+    security(()),
+    responses((status = 200, description = "Health of the service and its checks", body = HealthResponse))
+)]
 async fn health_handler(
     State(state): State<AppState>
 ) -> axum::Json<HealthResponse> {
@@ -183,6 +521,56 @@ async fn health_handler(
     axum::Json(response)
 }
 
+/// One probe's checks, with 503 when any of them is unhealthy
+async fn probe_response(state: &AppState, probe: Probe) -> (StatusCode, axum::Json<HealthResponse>) {
+    let response = state.health.check(probe).await;
+    let status = if health::passes(&response) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, axum::Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "service",
+    security(()),
+    responses(
+        (status = 200, description = "The process works", body = HealthResponse),
+        (status = 503, description = "A liveness check failed; restart the process", body = HealthResponse)
+    )
+)]
+async fn liveness_handler(
+    State(state): State<AppState>
+) -> (StatusCode, axum::Json<HealthResponse>) {
+    probe_response(&state, Probe::Liveness).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "service",
+    security(()),
+    responses(
+        (status = 200, description = "The service and its dependencies can take traffic", body = HealthResponse),
+        (status = 503, description = "A dependency is unavailable or the data is too far behind", body = HealthResponse)
+    )
+)]
+async fn readiness_handler(
+    State(state): State<AppState>
+) -> (StatusCode, axum::Json<HealthResponse>) {
+    probe_response(&state, Probe::Readiness).await
+}
+
+#[utoipa::path(
+    get,
+    path = "/status",
+    tag = "service",
+    security(()),
+    responses((status = 200, description = "Service name, version and uptime", body = ApiResponse<StatusResponse>))
+)]
 async fn status_handler(
     State(state): State<AppState>
 ) -> axum::Json<ApiResponse<StatusResponse>> {
@@ -197,9 +585,57 @@ async fn status_handler(
     axum::Json(ApiResponse::success(status))
 }
 
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "service",
+    responses(
+        (status = 200, description = "Every registered metric in the Prometheus text format", content_type = "text/plain"),
+        (status = 200, description = "Service metrics, with `Accept: application/json`", body = Object, content_type = "application/json")
+    )
+)]
 async fn metrics_handler(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let wants_json = headers.get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+    if !wants_json {
+        return ([(header::CONTENT_TYPE, TEXT_CONTENT_TYPE)], state.registry.render()).into_response();
+    }
+
+    let mut metrics = state.metrics.get_metrics().await;
+    if let serde_json::Value::Object(metrics) = &mut metrics {
+        if let Some(manager) = &state.account_data_manager {
+            metrics.insert("account_cache".to_string(), serde_json::json!(manager.cache_stats().await));
+        }
+        if let Some(manager) = &state.transaction_data_manager {
+            metrics.insert("transaction_cache".to_string(), serde_json::json!(manager.cache_stats().await));
+        }
+        if let Some(lag) = &state.lag {
+            metrics.insert("slot_lag".to_string(), serde_json::json!({
+                "lag": lag.lag(),
+                "indexed_slot": lag.local_slot(),
+                "tip_slot": lag.tip_slot(),
+                "threshold": lag.config().threshold_slots,
+                "lagging": lag.is_lagging(),
+            }));
+        }
+    }
+    axum::Json(metrics).into_response()
+}
+
+/// Operational alerts, such as the data trailing the chain tip, as
+/// Server-Sent Events
+#[utoipa::path(
+    get,
+    path = "/stream/alerts",
+    tag = "streams",
+    responses((status = 200, description = "`alert` events carrying `Alert`", content_type = "text/event-stream"))
+)]
+async fn alert_events(
     State(state): State<AppState>
-) -> axum::Json<serde_json::Value> {
-    let metrics = state.metrics.get_metrics().await;
-    axum::Json(metrics)
+) -> impl IntoResponse {
+    crate::subscriptions::broadcast_to_sse(state.alerts.subscribe(), "alert")
 }
\ No newline at end of file
@@ -0,0 +1,657 @@
+//! Solana JSON-RPC compatibility
+//!
+//! `POST /rpc` speaks the Solana JSON-RPC protocol, so existing SDK clients
+//! can point at windexer directly. `getAccountInfo`, `getMultipleAccounts`,
+//! `getTransaction`, `getBlock`, `getSignaturesForAddress` and `getSlot` are
+//! answered from the local store.
+//!
+//! Reads honour the `commitment` in their configuration, finalized by
+//! default: data from slots that have not reached it is left to upstream.
+//!
+//! Requests the store cannot answer fully — data it has not indexed, encodings
+//! it does not produce, and every other method — are forwarded to Helius when
+//! a client is configured. Without one, lookups answer with what the store
+//! has, and failed transactions report only their status code as `err`.
+
+use {
+    crate::{
+        auth::{scoped, RouteScope},
+        rest::AppState,
+    },
+    axum::{extract::State, Json},
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    serde_json::{json, Value},
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::{
+        types::{AccountData, ApiScope, TransactionData},
+        utils::transaction_status::{SerializableTokenBalance, SerializableTransactionMeta},
+    },
+};
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+/// Largest account data returned base58-encoded, as enforced by Solana RPC
+const MAX_BASE58_BYTES: usize = 128;
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+
+    fn internal(error: impl std::fmt::Display) -> Self {
+        Self::new(INTERNAL_ERROR, error.to_string())
+    }
+}
+
+/// How much of a request the local store could answer
+enum Answer {
+    /// Complete local result
+    Local(Value),
+    /// Best local result; upstream is asked instead when available
+    Partial(Value),
+    /// Only upstream can answer
+    Unsupported,
+}
+
+/// Positional parameters of a request
+struct Params<'a>(&'a [Value]);
+
+impl<'a> Params<'a> {
+    fn new(params: &'a Value) -> Result<Self, RpcError> {
+        match params {
+            Value::Array(values) => Ok(Self(values)),
+            Value::Null => Ok(Self(&[])),
+            _ => Err(RpcError::invalid_params("Params must be an array")),
+        }
+    }
+
+    fn required<T: DeserializeOwned>(&self, index: usize, name: &str) -> Result<T, RpcError> {
+        let value = self.0.get(index)
+            .ok_or_else(|| RpcError::invalid_params(format!("Missing parameter {}", name)))?;
+        serde_json::from_value(value.clone())
+            .map_err(|e| RpcError::invalid_params(format!("Invalid parameter {}: {}", name, e)))
+    }
+
+    /// Optional configuration object; absent or `null` gives the default
+    fn config<T: DeserializeOwned + Default>(&self, index: usize) -> Result<T, RpcError> {
+        match self.0.get(index) {
+            None | Some(Value::Null) => Ok(T::default()),
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| RpcError::invalid_params(format!("Invalid configuration: {}", e))),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccountConfig {
+    commitment: Option<String>,
+    encoding: Option<String>,
+    data_slice: Option<DataSlice>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct DataSlice {
+    offset: usize,
+    length: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionConfig {
+    commitment: Option<String>,
+    encoding: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockConfig {
+    commitment: Option<String>,
+    encoding: Option<String>,
+    transaction_details: Option<String>,
+    rewards: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignaturesConfig {
+    commitment: Option<String>,
+    limit: Option<usize>,
+    before: Option<String>,
+    until: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitmentConfig {
+    commitment: Option<String>,
+}
+
+/// Encode account data as requested, or `None` for encodings only upstream produces
+fn encode_account(account: &AccountData, encoding: Option<&str>, slice: Option<DataSlice>) -> Option<Result<Value, RpcError>> {
+    let data = match slice {
+        Some(DataSlice { offset, length }) => {
+            let start = offset.min(account.data.len());
+            &account.data[start..start.saturating_add(length).min(account.data.len())]
+        }
+        None => &account.data[..],
+    };
+
+    let encoded = match encoding.unwrap_or("binary") {
+        "binary" | "base58" if data.len() > MAX_BASE58_BYTES => {
+            return Some(Err(RpcError::invalid_params(format!(
+                "Encoded binary (base 58) data should be less than {} bytes, please use Base64 encoding.",
+                MAX_BASE58_BYTES
+            ))));
+        }
+        "binary" => json!(bs58::encode(data).into_string()),
+        "base58" => json!([bs58::encode(data).into_string(), "base58"]),
+        "base64" => json!([base64::encode(data), "base64"]),
+        _ => return None,
+    };
+
+    Some(Ok(json!({
+        "lamports": account.lamports,
+        "owner": account.owner.to_string(),
+        "data": encoded,
+        "executable": account.executable,
+        "rentEpoch": account.rent_epoch,
+        "space": account.data.len(),
+    })))
+}
+
+fn transaction_failed(meta: &SerializableTransactionMeta) -> bool {
+    meta.status != Some(0)
+}
+
+fn encode_error(meta: &SerializableTransactionMeta) -> Value {
+    match meta.status {
+        Some(0) => Value::Null,
+        status => json!(status),
+    }
+}
+
+fn encode_meta(meta: &SerializableTransactionMeta) -> Value {
+    let err = encode_error(meta);
+    let status = if transaction_failed(meta) { json!({ "Err": err }) } else { json!({ "Ok": null }) };
+
+    let inner_instructions = meta.inner_instructions.as_ref().map(|inner| {
+        inner.iter()
+            .map(|inner| json!({
+                "index": inner.index,
+                "instructions": inner.instructions.iter()
+                    .map(|instruction| json!({
+                        "programIdIndex": instruction.program_id_index,
+                        "accounts": instruction.accounts,
+                        "data": bs58::encode(&instruction.data).into_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>()
+    });
+    let token_balances = |balances: &Option<Vec<SerializableTokenBalance>>| {
+        balances.as_ref().map(|balances| {
+            balances.iter()
+                .map(|balance| json!({
+                    "accountIndex": balance.account_index,
+                    "mint": balance.mint,
+                    "owner": balance.owner,
+                    "uiTokenAmount": {
+                        "uiAmount": balance.ui_token_amount.ui_amount,
+                        "decimals": balance.ui_token_amount.decimals,
+                        "amount": balance.ui_token_amount.amount,
+                        "uiAmountString": balance.ui_token_amount.ui_amount_string,
+                    },
+                }))
+                .collect::<Vec<_>>()
+        })
+    };
+    let rewards = meta.rewards.as_ref().map(|rewards| {
+        rewards.iter()
+            .map(|reward| json!({
+                "pubkey": reward.pubkey,
+                "lamports": reward.lamports,
+                "postBalance": reward.post_balance,
+                "rewardType": reward.reward_type,
+                "commission": reward.commission,
+            }))
+            .collect::<Vec<_>>()
+    });
+
+    json!({
+        "err": err,
+        "status": status,
+        "fee": meta.fee,
+        "preBalances": meta.pre_balances,
+        "postBalances": meta.post_balances,
+        "innerInstructions": inner_instructions,
+        "logMessages": meta.log_messages,
+        "preTokenBalances": token_balances(&meta.pre_token_balances),
+        "postTokenBalances": token_balances(&meta.post_token_balances),
+        "rewards": rewards,
+    })
+}
+
+/// `json` encoding of a transaction and its status
+fn encode_transaction(transaction: &TransactionData) -> Value {
+    let message = &transaction.message;
+
+    json!({
+        "transaction": {
+            "signatures": transaction.signatures.iter().map(|signature| signature.to_string()).collect::<Vec<_>>(),
+            "message": {
+                "header": {
+                    "numRequiredSignatures": message.header.num_required_signatures,
+                    "numReadonlySignedAccounts": message.header.num_readonly_signed_accounts,
+                    "numReadonlyUnsignedAccounts": message.header.num_readonly_unsigned_accounts,
+                },
+                "accountKeys": message.account_keys.iter().map(|key| key.to_string()).collect::<Vec<_>>(),
+                "recentBlockhash": message.recent_blockhash.to_string(),
+                "instructions": message.instructions.iter()
+                    .map(|instruction| json!({
+                        "programIdIndex": instruction.program_id_index,
+                        "accounts": instruction.accounts,
+                        "data": bs58::encode(&instruction.data).into_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+            },
+        },
+        "meta": encode_meta(&transaction.serializable_meta),
+    })
+}
+
+/// Whether a requested transaction encoding is produced locally
+fn local_encoding(encoding: Option<&str>) -> bool {
+    matches!(encoding, None | Some("json"))
+}
+
+#[cfg(feature = "store")]
+mod local {
+    use {
+        super::*,
+        std::{str::FromStr, sync::Arc},
+        windexer_common::types::Commitment,
+        windexer_store::traits::Storage,
+    };
+
+    /// Transactions fetched per `getBlock`
+    const MAX_BLOCK_TRANSACTIONS: usize = 100_000;
+
+    /// Signatures returned per `getSignaturesForAddress` by default and at most
+    const MAX_SIGNATURES: usize = 1_000;
+
+    /// Transactions scanned when paging `getSignaturesForAddress` with `before` or `until`
+    const MAX_SIGNATURE_SCAN: usize = 10_000;
+
+    pub(super) async fn answer(state: &AppState, method: &str, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let Some(storage) = state.store() else {
+            return Ok(Answer::Unsupported);
+        };
+
+        match method {
+            "getAccountInfo" => get_account_info(&storage, params).await,
+            "getMultipleAccounts" => get_multiple_accounts(&storage, params).await,
+            "getTransaction" => get_transaction(&storage, params).await,
+            "getBlock" => get_block(&storage, params).await,
+            "getSignaturesForAddress" => get_signatures_for_address(&storage, params).await,
+            "getSlot" => get_slot(&storage, params).await,
+            _ => Ok(Answer::Unsupported),
+        }
+    }
+
+    fn check_pubkey(pubkey: &str) -> Result<(), RpcError> {
+        solana_sdk::pubkey::Pubkey::from_str(pubkey)
+            .map(|_| ())
+            .map_err(|_| RpcError::invalid_params(format!("Invalid param: {} is not a valid pubkey", pubkey)))
+    }
+
+    /// Requested commitment; Solana RPC defaults to finalized
+    fn commitment(commitment: Option<&str>) -> Result<Commitment, RpcError> {
+        match commitment {
+            None => Ok(Commitment::Finalized),
+            Some(name) => Commitment::from_rpc(name)
+                .ok_or_else(|| RpcError::invalid_params(format!("Invalid commitment: {}", name))),
+        }
+    }
+
+    /// Highest slot visible at `commitment`, or `None` if every slot is
+    async fn visible_slot(storage: &Arc<dyn Storage>, commitment: Commitment) -> Option<u64> {
+        // Backends without slot status tracking can't tell settled data apart
+        storage.get_latest_slot_with_commitment(commitment).await.unwrap_or(None)
+    }
+
+    fn visible(bound: Option<u64>, slot: u64) -> bool {
+        bound.map_or(true, |bound| slot <= bound)
+    }
+
+    /// Look accounts up, returning their encodings and whether all were found
+    async fn lookup_accounts(storage: &Arc<dyn Storage>, pubkeys: &[String], config: &AccountConfig) -> Result<Option<(Vec<Value>, bool, u64)>, RpcError> {
+        let mut values = Vec::with_capacity(pubkeys.len());
+        let mut complete = true;
+        let bound = visible_slot(storage, commitment(config.commitment.as_deref())?).await;
+        let mut context_slot = match bound {
+            Some(bound) => bound,
+            None => visible_slot(storage, Commitment::Finalized).await.unwrap_or_default(),
+        };
+
+        for pubkey in pubkeys {
+            check_pubkey(pubkey)?;
+            let mut account = storage.get_account(pubkey).await.map_err(RpcError::internal)?;
+            if let (Some(bound), Some(latest)) = (bound, &account) {
+                // The newest version is not settled yet; fall back to the last one
+                // that is, or let upstream answer if the backend keeps no history
+                if latest.slot > bound {
+                    account = storage.get_account_at_slot(pubkey, bound).await.unwrap_or(None);
+                }
+            }
+            match account {
+                Some(account) => {
+                    context_slot = context_slot.max(account.slot);
+                    match encode_account(&account, config.encoding.as_deref(), config.data_slice) {
+                        Some(value) => values.push(value?),
+                        None => return Ok(None),
+                    }
+                }
+                None => {
+                    complete = false;
+                    values.push(Value::Null);
+                }
+            }
+        }
+        Ok(Some((values, complete, context_slot)))
+    }
+
+    fn with_context(slot: u64, value: Value) -> Value {
+        json!({ "context": { "slot": slot }, "value": value })
+    }
+
+    async fn get_account_info(storage: &Arc<dyn Storage>, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let pubkey: String = params.required(0, "pubkey")?;
+        let config: AccountConfig = params.config(1)?;
+
+        let Some((mut values, complete, slot)) = lookup_accounts(storage, &[pubkey], &config).await? else {
+            return Ok(Answer::Unsupported);
+        };
+        let result = with_context(slot, values.pop().unwrap_or_default());
+        Ok(if complete { Answer::Local(result) } else { Answer::Partial(result) })
+    }
+
+    async fn get_multiple_accounts(storage: &Arc<dyn Storage>, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let pubkeys: Vec<String> = params.required(0, "pubkeys")?;
+        let config: AccountConfig = params.config(1)?;
+
+        let Some((values, complete, slot)) = lookup_accounts(storage, &pubkeys, &config).await? else {
+            return Ok(Answer::Unsupported);
+        };
+        let result = with_context(slot, json!(values));
+        Ok(if complete { Answer::Local(result) } else { Answer::Partial(result) })
+    }
+
+    async fn get_transaction(storage: &Arc<dyn Storage>, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let signature: String = params.required(0, "signature")?;
+        let config: TransactionConfig = params.config(1)?;
+        if !local_encoding(config.encoding.as_deref()) {
+            return Ok(Answer::Unsupported);
+        }
+
+        let bound = visible_slot(storage, commitment(config.commitment.as_deref())?).await;
+        let Some(transaction) = storage.get_transaction(&signature).await.map_err(RpcError::internal)? else {
+            return Ok(Answer::Partial(Value::Null));
+        };
+        if !visible(bound, transaction.slot) {
+            return Ok(Answer::Partial(Value::Null));
+        }
+        let block_time = storage.get_block(transaction.slot).await
+            .map_err(RpcError::internal)?
+            .and_then(|block| block.timestamp);
+
+        let mut result = encode_transaction(&transaction);
+        result["slot"] = json!(transaction.slot);
+        result["blockTime"] = json!(block_time);
+
+        // Only the status code of a failure is stored
+        Ok(if transaction_failed(&transaction.serializable_meta) {
+            Answer::Partial(result)
+        } else {
+            Answer::Local(result)
+        })
+    }
+
+    async fn get_block(storage: &Arc<dyn Storage>, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let slot: u64 = params.required(0, "slot")?;
+        let config: BlockConfig = params.config(1)?;
+        let details = config.transaction_details.as_deref().unwrap_or("full");
+        if !local_encoding(config.encoding.as_deref()) || !matches!(details, "full" | "signatures" | "none") {
+            return Ok(Answer::Unsupported);
+        }
+
+        let bound = visible_slot(storage, commitment(config.commitment.as_deref())?).await;
+        if !visible(bound, slot) {
+            return Ok(Answer::Partial(Value::Null));
+        }
+        let Some(block) = storage.get_block(slot).await.map_err(RpcError::internal)? else {
+            return Ok(Answer::Partial(Value::Null));
+        };
+
+        let mut result = json!({
+            "blockhash": block.blockhash,
+            "previousBlockhash": block.parent_blockhash,
+            "parentSlot": block.parent_slot,
+            "blockTime": block.timestamp,
+            "blockHeight": block.block_height,
+        });
+        if config.rewards.unwrap_or(true) {
+            result["rewards"] = json!(block.rewards.clone().unwrap_or_default());
+        }
+
+        let mut complete = true;
+        if details != "none" {
+            let mut transactions = storage.get_transactions_by_slot_range(slot, slot, MAX_BLOCK_TRANSACTIONS).await
+                .map_err(RpcError::internal)?;
+            transactions.sort_by_key(|transaction| transaction.index);
+            complete = block.transaction_count.map_or(true, |count| count as usize <= transactions.len());
+
+            if details == "full" {
+                result["transactions"] = json!(transactions.iter().map(encode_transaction).collect::<Vec<_>>());
+            } else {
+                result["signatures"] = json!(transactions.iter()
+                    .map(|transaction| transaction.signature.to_string())
+                    .collect::<Vec<_>>());
+            }
+        }
+
+        Ok(if complete { Answer::Local(result) } else { Answer::Partial(result) })
+    }
+
+    async fn get_signatures_for_address(storage: &Arc<dyn Storage>, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let address: String = params.required(0, "address")?;
+        check_pubkey(&address)?;
+        let config: SignaturesConfig = params.config(1)?;
+        let bound = visible_slot(storage, commitment(config.commitment.as_deref())?).await;
+        let limit = config.limit.unwrap_or(MAX_SIGNATURES);
+        if limit == 0 || limit > MAX_SIGNATURES {
+            return Err(RpcError::invalid_params(format!("Invalid limit; max {}", MAX_SIGNATURES)));
+        }
+
+        let scan = if config.before.is_some() || config.until.is_some() { MAX_SIGNATURE_SCAN } else { limit };
+        let transactions = match storage.get_transactions_by_account(&address, scan).await {
+            Ok(transactions) => transactions,
+            // The backend keeps no account index
+            Err(_) => return Ok(Answer::Unsupported),
+        };
+
+        let mut page = transactions.iter().peekable();
+        if let Some(before) = &config.before {
+            if !transactions.iter().any(|transaction| transaction.signature.to_string() == *before) {
+                return Ok(Answer::Partial(json!([])));
+            }
+            while page.next_if(|transaction| transaction.signature.to_string() != *before).is_some() {}
+            page.next();
+        }
+
+        let rooted = visible_slot(storage, Commitment::Finalized).await.unwrap_or_default();
+        let confirmed = visible_slot(storage, Commitment::Confirmed).await.unwrap_or_default();
+        let signatures: Vec<Value> = page
+            .take_while(|transaction| config.until.as_ref().map_or(true, |until| transaction.signature.to_string() != *until))
+            .filter(|transaction| visible(bound, transaction.slot))
+            .take(limit)
+            .map(|transaction| json!({
+                "signature": transaction.signature.to_string(),
+                "slot": transaction.slot,
+                "err": encode_error(&transaction.serializable_meta),
+                "memo": null,
+                "blockTime": null,
+                "confirmationStatus": if transaction.slot <= rooted {
+                    Commitment::Finalized
+                } else if transaction.slot <= confirmed {
+                    Commitment::Confirmed
+                } else {
+                    Commitment::Processed
+                },
+            }))
+            .collect();
+
+        Ok(if signatures.is_empty() { Answer::Partial(json!(signatures)) } else { Answer::Local(json!(signatures)) })
+    }
+
+    async fn get_slot(storage: &Arc<dyn Storage>, params: &Params<'_>) -> Result<Answer, RpcError> {
+        let config: CommitmentConfig = params.config(0)?;
+        let commitment = commitment(config.commitment.as_deref())?;
+
+        // The store does not track the tip of processed slots
+        Ok(match visible_slot(storage, commitment).await {
+            Some(slot) => Answer::Local(json!(slot)),
+            None => Answer::Partial(json!(visible_slot(storage, Commitment::Finalized).await.unwrap_or_default())),
+        })
+    }
+}
+
+#[cfg(not(feature = "store"))]
+mod local {
+    use super::*;
+
+    pub(super) async fn answer(_state: &AppState, _method: &str, _params: &Params<'_>) -> Result<Answer, RpcError> {
+        Ok(Answer::Unsupported)
+    }
+}
+
+/// Forward a request to Helius
+async fn upstream(helius: &crate::helius::HeliusClient, request: &RpcRequest) -> Result<Value, RpcError> {
+    let response = helius.call(&request.method, request.params.clone()).await
+        .map_err(|e| RpcError::internal(format!("Upstream request failed: {}", e)))?;
+
+    if let Some(error) = response.get("error") {
+        return Err(serde_json::from_value(error.clone()).unwrap_or_else(|_| RpcError::internal(error)));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+async fn execute(state: &AppState, request: &RpcRequest) -> Result<Value, RpcError> {
+    let params = Params::new(&request.params)?;
+    let helius = state.helius_client.as_deref();
+
+    match (local::answer(state, &request.method, &params).await?, helius) {
+        (Answer::Local(result), _) => Ok(result),
+        (Answer::Partial(_), Some(helius)) | (Answer::Unsupported, Some(helius)) => upstream(helius, request).await,
+        (Answer::Partial(result), None) => Ok(result),
+        (Answer::Unsupported, None) => Err(RpcError::new(METHOD_NOT_FOUND, "Method not found")),
+    }
+}
+
+async fn respond(state: &AppState, request: Value) -> Value {
+    let request: RpcRequest = match serde_json::from_value(request) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e));
+            return json!({ "jsonrpc": "2.0", "id": null, "error": error });
+        }
+    };
+
+    match execute(state, &request).await {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": request.id, "error": error }),
+    }
+}
+
+/// Handle a single request or a batch
+#[utoipa::path(
+    post,
+    path = "/rpc",
+    tag = "rpc",
+    request_body(content = Object, description = "Solana JSON-RPC request or batch"),
+    responses((status = 200, description = "JSON-RPC response or batch", body = Object))
+)]
+pub async fn rpc_handler(State(state): State<AppState>, Json(body): Json<Value>) -> Json<Value> {
+    match body {
+        Value::Array(requests) if requests.is_empty() => {
+            let error = RpcError::new(INVALID_REQUEST, "Empty batch");
+            Json(json!({ "jsonrpc": "2.0", "id": null, "error": error }))
+        }
+        Value::Array(requests) => {
+            let responses = futures::future::join_all(requests.into_iter().map(|request| respond(&state, request))).await;
+            Json(Value::Array(responses))
+        }
+        request => Json(respond(&state, request).await),
+    }
+}
+
+pub fn create_rpc_router() -> OpenApiRouter<AppState> {
+    // JSON-RPC reads are sent with POST
+    scoped(OpenApiRouter::new().routes(routes!(rpc_handler)), RouteScope::All(ApiScope::ReadOnly))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn test_account_encodings() {
+        let account = AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 42,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+            data: vec![1; 200],
+            write_version: 0,
+            slot: 7,
+            is_startup: false,
+            transaction_signature: None,
+        };
+
+        let sliced = encode_account(&account, Some("base64"), Some(DataSlice { offset: 190, length: 20 })).unwrap().unwrap();
+        assert_eq!(sliced["data"], json!([base64::encode([1; 10]), "base64"]));
+        assert_eq!(sliced["space"], json!(200));
+
+        assert!(encode_account(&account, None, None).unwrap().is_err());
+        assert!(encode_account(&account, Some("jsonParsed"), None).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+//! Search endpoint
+//!
+//! `GET /search?q=...` resolves what a user pasted into an explorer search
+//! box: a slot, or a whole or partial pubkey, transaction signature or
+//! blockhash. Whole pubkeys, signatures and slots are looked up directly;
+//! partial keys and blockhashes need the store's search index. Each result
+//! carries the route it resolves to, relative to the API's path prefix.
+
+use {
+    crate::{
+        auth::{scoped, RouteScope},
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{
+        extract::{Query, State},
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::str::FromStr,
+    tracing::debug,
+    utoipa::{IntoParams, ToSchema},
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_store::index::{SearchHit, SearchKind},
+};
+
+/// Results returned unless `limit` is given
+const DEFAULT_LIMIT: usize = 10;
+
+/// Largest `limit` accepted
+const MAX_LIMIT: usize = 100;
+
+/// Shorter queries only match slots; a prefix this short matches too much to be useful
+const MIN_PREFIX_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchEntity {
+    Account,
+    Transaction,
+    Block,
+}
+
+impl From<SearchKind> for SearchEntity {
+    fn from(kind: SearchKind) -> Self {
+        match kind {
+            SearchKind::Account => Self::Account,
+            SearchKind::Transaction => Self::Transaction,
+            SearchKind::Block => Self::Block,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchResult {
+    pub entity: SearchEntity,
+    /// Pubkey, signature, blockhash or slot
+    pub key: String,
+    /// Slot the entity was last seen at
+    pub slot: Option<u64>,
+    /// Route returning the entity, e.g. `/account/{pubkey}`
+    pub link: String,
+}
+
+impl SearchResult {
+    fn account(pubkey: String, slot: Option<u64>) -> Self {
+        Self {
+            entity: SearchEntity::Account,
+            link: format!("/account/{}", pubkey),
+            key: pubkey,
+            slot,
+        }
+    }
+
+    fn transaction(signature: String, slot: Option<u64>) -> Self {
+        Self {
+            entity: SearchEntity::Transaction,
+            link: format!("/transaction/{}", signature),
+            key: signature,
+            slot,
+        }
+    }
+
+    fn block(key: String, slot: u64) -> Self {
+        Self {
+            entity: SearchEntity::Block,
+            link: format!("/blocks/{}", slot),
+            key,
+            slot: Some(slot),
+        }
+    }
+}
+
+impl From<SearchHit> for SearchResult {
+    fn from(hit: SearchHit) -> Self {
+        match hit.kind {
+            SearchKind::Account => Self::account(hit.key, Some(hit.slot)),
+            SearchKind::Transaction => Self::transaction(hit.key, Some(hit.slot)),
+            SearchKind::Block => Self::block(hit.key, hit.slot),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    /// Slot, or a whole or partial pubkey, signature or blockhash
+    pub q: String,
+    /// Maximum number of results (default 10, at most 100)
+    pub limit: Option<usize>,
+}
+
+fn is_base58(query: &str) -> bool {
+    query.bytes().all(|byte| byte.is_ascii_alphanumeric() && !matches!(byte, b'0' | b'O' | b'I' | b'l'))
+}
+
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Exact matches first, then keys starting with the query", body = ApiResponse<Vec<SearchResult>>),
+        (status = 400, description = "Not a slot, pubkey, signature or blockhash", body = ApiError)
+    )
+)]
+pub async fn search(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<ApiResponse<Vec<SearchResult>>>, ApiError> {
+    let query = params.q.trim();
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let slot = query.parse::<u64>().ok();
+    if query.is_empty() || (slot.is_none() && !is_base58(query)) {
+        return Err(ApiError::BadRequest(format!("'{}' is not a slot, pubkey, signature or blockhash", query)));
+    }
+
+    let storage = state.store()
+        .ok_or_else(|| ApiError::Internal("Storage not initialized".to_string()))?;
+    let mut results = Vec::new();
+    let mut looked_up = slot.is_some();
+
+    if let Some(slot) = slot {
+        if let Some(block) = storage.get_block(slot).await.map_err(ApiError::storage)? {
+            results.push(SearchResult::block(block.slot.to_string(), block.slot));
+        }
+    }
+    if let Ok(pubkey) = Pubkey::from_str(query) {
+        looked_up = true;
+        if let Some(account) = storage.get_account(&pubkey.to_string()).await.map_err(ApiError::storage)? {
+            results.push(SearchResult::account(query.to_string(), Some(account.slot)));
+        }
+    }
+    if let Ok(signature) = Signature::from_str(query) {
+        looked_up = true;
+        if let Some(transaction) = storage.get_transaction(&signature.to_string()).await.map_err(ApiError::storage)? {
+            results.push(SearchResult::transaction(query.to_string(), Some(transaction.slot)));
+        }
+    }
+
+    if query.len() >= MIN_PREFIX_LEN && is_base58(query) {
+        match storage.search_prefix(query, limit).await {
+            Ok(hits) => {
+                for hit in hits.into_iter().map(SearchResult::from) {
+                    if !results.iter().any(|result| result.entity == hit.entity && result.key == hit.key) {
+                        results.push(hit);
+                    }
+                }
+            }
+            // Whole keys were already looked up, so only partial ones need the index
+            Err(e) if looked_up => debug!("Prefix search unavailable: {}", e),
+            Err(e) => return Err(ApiError::BadRequest(format!("Prefix search is unavailable: {}", e))),
+        }
+    }
+
+    results.truncate(limit);
+    Ok(Json(ApiResponse::success(results)))
+}
+
+pub fn create_search_router() -> OpenApiRouter<AppState> {
+    scoped(OpenApiRouter::new().routes(routes!(search)), RouteScope::ReadWrite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_characters_outside_base58() {
+        assert!(is_base58("4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T"));
+        assert!(!is_base58("0xdeadbeef"));
+        assert!(!is_base58("So1ana"));
+    }
+}
@@ -1,8 +1,8 @@
 // src/server.rs
 
 use std::net::SocketAddr;
-use std::sync::Arc;
 use std::sync::RwLock;
+use std::time::Duration;
 use anyhow::Result;
 use tracing::{info, error};
 use axum::Router;
@@ -10,6 +10,7 @@ use axum::Router;
 use crate::rest::{ApiServer, ApiConfig, AppState};
 use crate::types::NodeInfo;
 use crate::types::{HealthStatus, HealthCheckResult};
+use windexer_common::shutdown::{Shutdown, Stage};
 
 pub async fn run_api_server(
     bind_addr: SocketAddr,
@@ -24,16 +25,18 @@ pub async fn run_api_server(
         enable_metrics: true,
         node_info,
         path_prefix: Some("/api".to_string()),
+        grpc_addr: None,
+        auth: Default::default(),
+        stats_refresh_interval: Some(Duration::from_secs(60)),
+        tenants: Vec::new(),
+        health_checks: Default::default(),
+        slot_lag: None,
     };
     
     info!("Starting API server for {} v{}", config.service_name, config.version);
     
     let server = ApiServer::new(config);
     
-    let health = server.health();
-    
-    health.register("system", Arc::new(|| true)).await;
-    
     server.start().await?;
     
     Ok(())
@@ -46,9 +49,18 @@ pub async fn run_api_server_with_config(
     info!("Starting API server for {} v{}", config.service_name, config.version);
     
     let server = ApiServer::new(config);
+    let shutdown = Shutdown::default();
+    let token = shutdown.token(Stage::Ingress);
+    if let Some(signal) = shutdown_signal {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            if signal.await.is_ok() {
+                shutdown.shutdown().await;
+            }
+        });
+    }
     
-    // TODO: Implement shutdown handling with the signal
-    server.start().await?;
+    server.start_with_shutdown(token).await?;
     
     Ok(())
 }
@@ -58,7 +70,6 @@ pub fn create_url_health_check(
     timeout_ms: u64,
     name: &str,
 ) -> impl Fn() -> std::pin::Pin<Box<dyn std::future::Future<Output = HealthCheckResult> + Send>> {
-    use std::time::Duration;
     let name = name.to_string();
     
     move || {
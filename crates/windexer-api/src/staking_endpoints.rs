@@ -0,0 +1,324 @@
+//! Staking operators, performance, rewards and slashes
+//!
+//! The staking service is attached with
+//! [`ApiServer::set_staking_control`](crate::rest::ApiServer::set_staking_control)
+//! and its epoch tracking with
+//! [`ApiServer::set_epoch_status`](crate::rest::ApiServer::set_epoch_status):
+//!
+//! - `GET /staking/operators` lists operators by stake
+//! - `GET /staking/operators/{pubkey}` shows one operator with its delegations
+//! - `GET /staking/operators/{pubkey}/performance?window=7d` serves the
+//!   operator's performance in every epoch closed within the window, for
+//!   dashboards; without a staking service attached it is read from the store
+//! - `POST /staking/operators` registers an operator and needs the `admin` scope
+//! - `GET /staking/rewards/{pubkey}` shows the unpaid rewards of an operator or staker
+//! - `GET /staking/slashing-events` lists slashes, newest first; without a
+//!   staking service attached they are read from the store
+//! - `GET /staking/epoch` serves the Solana epoch the service follows
+
+use {
+    crate::{
+        auth::{scoped, RouteScope},
+        rest::AppState,
+        types::{ApiError, ApiResponse},
+    },
+    axum::{
+        extract::{Path, Query, State},
+        Json,
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{str::FromStr, sync::Arc},
+    tracing::info,
+    utoipa::{IntoParams, ToSchema},
+    utoipa_axum::{router::OpenApiRouter, routes},
+    windexer_common::{
+        control::{EpochInfo, OperatorDetail, OperatorStatus, RewardBalance, StakingControl},
+        types::{PerformanceRecord, SlashRecord},
+        utils::current_timestamp,
+    },
+};
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1_000;
+
+/// Performance window served unless `window` is given
+const DEFAULT_WINDOW: &str = "7d";
+
+fn staking_control(state: &AppState) -> Result<&Arc<dyn StakingControl>, ApiError> {
+    state.staking_control.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No staking service is attached".to_string()))
+}
+
+fn validate_pubkey(pubkey: &str) -> Result<(), ApiError> {
+    Pubkey::from_str(pubkey)
+        .map(|_| ())
+        .map_err(|e| ApiError::BadRequest(format!("Invalid pubkey {}: {}", pubkey, e)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/staking/operators",
+    tag = "staking",
+    responses(
+        (status = 200, description = "Operators, largest stake first", body = ApiResponse<Vec<OperatorStatus>>),
+        (status = 404, description = "No staking service is attached", body = ApiError)
+    )
+)]
+pub async fn list_operators(State(state): State<AppState>) -> Result<Json<ApiResponse<Vec<OperatorStatus>>>, ApiError> {
+    let operators = staking_control(&state)?.operators().await
+        .map_err(|e| ApiError::Internal(format!("Failed to list operators: {}", e)))?;
+    Ok(Json(ApiResponse::success(operators)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/staking/operators/{pubkey}",
+    tag = "staking",
+    params(("pubkey" = String, Path, description = "Operator pubkey")),
+    responses(
+        (status = 200, description = "The operator with its delegations", body = ApiResponse<OperatorDetail>),
+        (status = 400, description = "Invalid pubkey", body = ApiError),
+        (status = 404, description = "Unknown operator or no staking service is attached", body = ApiError)
+    )
+)]
+pub async fn get_operator(
+    State(state): State<AppState>,
+    Path(pubkey): Path<String>,
+) -> Result<Json<ApiResponse<OperatorDetail>>, ApiError> {
+    validate_pubkey(&pubkey)?;
+    let operator = staking_control(&state)?.operator(&pubkey).await
+        .map_err(|e| ApiError::Internal(format!("Failed to read operator {}: {}", pubkey, e)))?
+        .ok_or_else(|| ApiError::NotFound(format!("Operator {} not found", pubkey)))?;
+    Ok(Json(ApiResponse::success(operator)))
+}
+
+/// Seconds in a window like `90m`, `24h` or `7d`
+fn parse_window(window: &str) -> Result<i64, ApiError> {
+    let invalid = || ApiError::BadRequest(format!("Invalid window {}, expected a number of m, h or d like 7d", window));
+    let split = window.len().checked_sub(1).filter(|at| window.is_char_boundary(*at)).ok_or_else(invalid)?;
+    let (count, unit) = window.split_at(split);
+    let unit = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    count.checked_mul(unit).filter(|seconds| *seconds > 0).ok_or_else(invalid)
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PerformanceParams {
+    /// How far back to serve epochs, like `24h` or `30d`; `7d` by default
+    pub window: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/staking/operators/{pubkey}/performance",
+    tag = "staking",
+    params(("pubkey" = String, Path, description = "Operator pubkey"), PerformanceParams),
+    responses(
+        (status = 200, description = "Performance per epoch closed within the window, oldest first", body = ApiResponse<Vec<PerformanceRecord>>),
+        (status = 400, description = "Invalid pubkey or window", body = ApiError),
+        (status = 404, description = "Unknown operator or no staking service or store is attached", body = ApiError)
+    )
+)]
+pub async fn get_operator_performance(
+    State(state): State<AppState>,
+    Path(pubkey): Path<String>,
+    Query(params): Query<PerformanceParams>,
+) -> Result<Json<ApiResponse<Vec<PerformanceRecord>>>, ApiError> {
+    validate_pubkey(&pubkey)?;
+    let since = current_timestamp() - parse_window(params.window.as_deref().unwrap_or(DEFAULT_WINDOW))?;
+    let records = match &state.staking_control {
+        Some(staking) => staking.performance(&pubkey, since).await
+            .map_err(|e| ApiError::Internal(format!("Failed to read performance of {}: {}", pubkey, e)))?
+            .ok_or_else(|| ApiError::NotFound(format!("Operator {} not found", pubkey)))?,
+        None => stored_performance(&state, &pubkey, since).await?,
+    };
+    Ok(Json(ApiResponse::success(records)))
+}
+
+#[cfg(feature = "store")]
+async fn stored_performance(state: &AppState, operator: &str, since: i64) -> Result<Vec<PerformanceRecord>, ApiError> {
+    let storage = state.store()
+        .ok_or_else(|| ApiError::NotFound("No staking service or store is attached".to_string()))?;
+    storage.load_performance_records(Some(operator), since).await
+        .map_err(ApiError::storage)
+}
+
+#[cfg(not(feature = "store"))]
+async fn stored_performance(_state: &AppState, _operator: &str, _since: i64) -> Result<Vec<PerformanceRecord>, ApiError> {
+    Err(ApiError::NotFound("No staking service is attached".to_string()))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterOperatorRequest {
+    /// Operator pubkey
+    pub pubkey: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/staking/operators",
+    tag = "staking",
+    request_body = RegisterOperatorRequest,
+    responses(
+        (status = 200, description = "The registered operator", body = ApiResponse<OperatorStatus>),
+        (status = 400, description = "Invalid or already registered pubkey", body = ApiError),
+        (status = 404, description = "No staking service is attached", body = ApiError)
+    )
+)]
+pub async fn register_operator(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterOperatorRequest>,
+) -> Result<Json<ApiResponse<OperatorStatus>>, ApiError> {
+    validate_pubkey(&request.pubkey)?;
+    let operator = staking_control(&state)?.register_operator(&request.pubkey).await
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    info!("Registered operator {}", request.pubkey);
+    Ok(Json(ApiResponse::success(operator)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/staking/rewards/{pubkey}",
+    tag = "staking",
+    params(("pubkey" = String, Path, description = "Operator or staker pubkey")),
+    responses(
+        (status = 200, description = "Rewards not yet paid out", body = ApiResponse<RewardBalance>),
+        (status = 400, description = "Invalid pubkey", body = ApiError),
+        (status = 404, description = "No staking service is attached", body = ApiError)
+    )
+)]
+pub async fn get_rewards(
+    State(state): State<AppState>,
+    Path(pubkey): Path<String>,
+) -> Result<Json<ApiResponse<RewardBalance>>, ApiError> {
+    validate_pubkey(&pubkey)?;
+    let rewards = staking_control(&state)?.rewards(&pubkey).await
+        .map_err(|e| ApiError::Internal(format!("Failed to read rewards of {}: {}", pubkey, e)))?;
+    Ok(Json(ApiResponse::success(rewards)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/staking/epoch",
+    tag = "staking",
+    responses(
+        (status = 200, description = "Current Solana epoch", body = ApiResponse<EpochInfo>),
+        (status = 404, description = "No epoch tracking is attached or no slot was seen yet", body = ApiError)
+    )
+)]
+pub async fn get_epoch(State(state): State<AppState>) -> Result<Json<ApiResponse<EpochInfo>>, ApiError> {
+    let epochs = state.epoch_status.as_ref()
+        .ok_or_else(|| ApiError::NotFound("No epoch tracking is attached".to_string()))?;
+    let epoch = epochs.current_epoch()
+        .ok_or_else(|| ApiError::NotFound("No slot seen yet".to_string()))?;
+    Ok(Json(ApiResponse::success(epoch)))
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SlashingEvent {
+    pub operator: String,
+    /// Kind of violation, like `double_vote` or `divergent_data`
+    pub violation: String,
+    /// Stake deducted, in lamports
+    pub amount: u64,
+    /// Stake the operator held before the slash
+    pub stake_before: u64,
+    pub epoch: u64,
+    /// Unix timestamp in seconds
+    pub timestamp: i64,
+}
+
+impl From<SlashRecord> for SlashingEvent {
+    fn from(record: SlashRecord) -> Self {
+        Self {
+            operator: record.operator,
+            violation: record.violation,
+            amount: record.amount,
+            stake_before: record.stake_before,
+            epoch: record.epoch,
+            timestamp: record.timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SlashingEventParams {
+    /// Only slashes of this operator
+    pub operator: Option<String>,
+    /// Most events returned, 100 by default and at most 1000
+    pub limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/staking/slashing-events",
+    tag = "staking",
+    params(SlashingEventParams),
+    responses(
+        (status = 200, description = "Slashes, newest first", body = ApiResponse<Vec<SlashingEvent>>),
+        (status = 400, description = "Invalid operator pubkey", body = ApiError),
+        (status = 404, description = "No staking service or store is attached", body = ApiError)
+    )
+)]
+pub async fn get_slashing_events(
+    State(state): State<AppState>,
+    Query(params): Query<SlashingEventParams>,
+) -> Result<Json<ApiResponse<Vec<SlashingEvent>>>, ApiError> {
+    if let Some(operator) = &params.operator {
+        validate_pubkey(operator)?;
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let events = match &state.staking_control {
+        Some(staking) => staking.slash_events(params.operator.as_deref(), limit).await
+            .map_err(|e| ApiError::Internal(format!("Failed to read slashes: {}", e)))?,
+        None => stored_slash_events(&state, params.operator.as_deref(), limit).await?,
+    };
+
+    Ok(Json(ApiResponse::success(events.into_iter().map(SlashingEvent::from).collect())))
+}
+
+#[cfg(feature = "store")]
+async fn stored_slash_events(state: &AppState, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>, ApiError> {
+    let storage = state.store()
+        .ok_or_else(|| ApiError::NotFound("No staking service or store is attached".to_string()))?;
+    storage.load_slash_events(operator, limit).await
+        .map_err(ApiError::storage)
+}
+
+#[cfg(not(feature = "store"))]
+async fn stored_slash_events(_state: &AppState, _operator: Option<&str>, _limit: usize) -> Result<Vec<SlashRecord>, ApiError> {
+    Err(ApiError::NotFound("No staking service is attached".to_string()))
+}
+
+pub fn create_staking_router() -> OpenApiRouter<AppState> {
+    let router = OpenApiRouter::new()
+        .routes(routes!(list_operators, register_operator))
+        .routes(routes!(get_operator))
+        .routes(routes!(get_operator_performance))
+        .routes(routes!(get_rewards))
+        .routes(routes!(get_slashing_events))
+        .routes(routes!(get_epoch));
+    scoped(router, RouteScope::ReadWrite)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_windows() {
+        assert_eq!(parse_window("7d").unwrap(), 7 * 24 * 60 * 60);
+        assert_eq!(parse_window("90m").unwrap(), 90 * 60);
+        for invalid in ["", "d", "0h", "7w", "-1d", "7é"] {
+            assert!(parse_window(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
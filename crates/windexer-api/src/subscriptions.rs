@@ -0,0 +1,365 @@
+//! Live subscription hub
+//!
+//! [`SubscriptionHub`] turns the node's [`DataFeed`], which carries everything
+//! the store and the network ingest, into filtered per-client streams for the
+//! WebSocket, Server-Sent Events and gRPC APIs. Each subscription selects the events it wants and
+//! holds them back until their slot reaches the requested commitment.
+//!
+//! A subscriber that falls more than the feed capacity behind receives a
+//! single [`Lagged`] error and its subscription ends.
+
+use {
+    axum::{
+        extract::ws::{Message, WebSocket},
+        response::sse::{Event, KeepAlive, Sse},
+    },
+    futures::{stream, SinkExt, Stream, StreamExt},
+    serde::Serialize,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, BTreeSet, HashSet},
+        convert::Infallible,
+        str::FromStr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+    tokio::sync::{broadcast::{self, error::RecvError}, mpsc},
+    windexer_common::{
+        feed::{DataFeed, FeedEvent},
+        filter::{Filter, Subject},
+    },
+};
+
+pub use windexer_common::types::Commitment;
+
+/// Updates buffered per subscription between the feed and the client
+const SUBSCRIPTION_BUFFER: usize = 1024;
+
+/// Slots whose updates are held while waiting for their commitment
+const MAX_PENDING_SLOTS: usize = 512;
+
+/// The subscriber fell behind the feed and missed this many updates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged(pub u64);
+
+/// Parse base58 addresses, naming the offending `field` on error
+pub fn parse_pubkeys<'a>(values: impl IntoIterator<Item = &'a str>, field: &str) -> Result<HashSet<Pubkey>, String> {
+    values
+        .into_iter()
+        .map(|value| Pubkey::from_str(value.trim()).map_err(|_| format!("Invalid {} entry {}", field, value)))
+        .collect()
+}
+
+/// Parse the `filter` parameter of a stream of `subject`
+pub fn parse_filter(filter: Option<&str>, subject: Subject) -> Result<Filter, String> {
+    let Some(filter) = filter else {
+        return Ok(Filter::all());
+    };
+    let filter: Filter = filter.parse().map_err(|e| format!("Invalid filter: {}", e))?;
+    filter.check(subject).map_err(|e| format!("Invalid filter: {}", e))?;
+    Ok(filter)
+}
+
+/// Holds updates back until their slot reaches the requested commitment
+struct CommitmentGate<T> {
+    level: Commitment,
+    pending: BTreeMap<u64, Vec<T>>,
+    /// Recent slots already at the requested commitment
+    reached: BTreeSet<u64>,
+}
+
+impl<T> CommitmentGate<T> {
+    fn new(level: Commitment) -> Self {
+        Self {
+            level,
+            pending: BTreeMap::new(),
+            reached: BTreeSet::new(),
+        }
+    }
+
+    /// Accept an update, returning it if it can be sent right away
+    fn push(&mut self, slot: u64, update: T) -> Vec<T> {
+        if self.level == Commitment::Processed || self.reached.contains(&slot) {
+            return vec![update];
+        }
+        self.pending.entry(slot).or_default().push(update);
+        while self.pending.len() > MAX_PENDING_SLOTS {
+            self.pending.pop_first();
+        }
+        Vec::new()
+    }
+
+    /// Release the updates of a slot that reached `reached`
+    fn reached(&mut self, slot: u64, reached: Commitment) -> Vec<T> {
+        if reached < self.level {
+            return Vec::new();
+        }
+        self.reached.insert(slot);
+        while self.reached.len() > MAX_PENDING_SLOTS {
+            self.reached.pop_first();
+        }
+
+        let ready = self.pending.remove(&slot).unwrap_or_default();
+        // Older slots that never finalized were on an abandoned fork
+        if self.level == Commitment::Finalized {
+            self.pending = self.pending.split_off(&slot);
+        }
+        ready
+    }
+}
+
+/// Fans the data feed out to filtered subscriptions
+#[derive(Debug)]
+pub struct SubscriptionHub {
+    feed: DataFeed,
+    active: AtomicUsize,
+}
+
+impl SubscriptionHub {
+    pub fn new(feed: DataFeed) -> Self {
+        Self {
+            feed,
+            active: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn feed(&self) -> &DataFeed {
+        &self.feed
+    }
+
+    /// Number of open subscriptions
+    pub fn active_subscriptions(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Start a subscription; `select` picks the events it wants and converts
+    /// them, returning the slot each belongs to. Within a request for a
+    /// tenant, only the events the tenant may see are offered to `select`.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn subscribe<T, F>(self: &Arc<Self>, commitment: Commitment, mut select: F) -> Subscription<T>
+    where
+        T: Send + 'static,
+        F: FnMut(&FeedEvent) -> Option<(u64, T)> + Send + 'static,
+    {
+        let scope = crate::tenant::current_scope();
+        let select = move |event: &FeedEvent| match &scope {
+            Some(scope) if !scope(event) => None,
+            _ => select(event),
+        };
+
+        let (sender, receiver) = mpsc::channel(SUBSCRIPTION_BUFFER);
+        tokio::spawn(forward(self.feed.subscribe(), commitment, sender, select));
+        self.active.fetch_add(1, Ordering::Relaxed);
+
+        Subscription {
+            receiver,
+            hub: self.clone(),
+        }
+    }
+}
+
+/// Updates of one subscription; dropping it ends the subscription
+pub struct Subscription<T> {
+    receiver: mpsc::Receiver<Result<T, Lagged>>,
+    hub: Arc<SubscriptionHub>,
+}
+
+impl<T> Subscription<T> {
+    /// Next update, or `None` once the feed has closed or the subscriber lagged
+    pub async fn recv(&mut self) -> Option<Result<T, Lagged>> {
+        self.receiver.recv().await
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.hub.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Send a subscription's updates to a WebSocket client as JSON text frames
+///
+/// Answers `ping` with `pong`. When the client lags it is sent an error
+/// object and the socket is closed so it can reconnect.
+pub async fn stream_to_websocket<T: Serialize>(socket: WebSocket, subscription: Subscription<T>) {
+    stream_to_websocket_with(socket, subscription, |update| update).await
+}
+
+/// Like [`stream_to_websocket`], sending each update as `convert` turns it
+pub async fn stream_to_websocket_with<T, U, F>(socket: WebSocket, mut subscription: Subscription<T>, mut convert: F)
+where
+    U: Serialize,
+    F: FnMut(T) -> U,
+{
+    let (mut sender, mut receiver) = socket.split();
+
+    loop {
+        tokio::select! {
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) if text == "ping" => {
+                    if sender.send(Message::Text("pong".to_string())).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                _ => {}
+            },
+            update = subscription.recv() => match update {
+                Some(Ok(update)) => {
+                    let Ok(json) = serde_json::to_string(&convert(update)) else { continue };
+                    if sender.send(Message::Text(json)).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Err(Lagged(skipped))) => {
+                    let error = serde_json::json!({
+                        "error": format!("Subscriber fell behind and missed {} updates", skipped),
+                    });
+                    let _ = sender.send(Message::Text(error.to_string())).await;
+                    let _ = sender.send(Message::Close(None)).await;
+                    return;
+                }
+                None => {
+                    let _ = sender.send(Message::Close(None)).await;
+                    return;
+                }
+            },
+        }
+    }
+}
+
+/// Forward matching feed events to a subscription until it is dropped
+async fn forward<T, F>(
+    mut events: broadcast::Receiver<FeedEvent>,
+    level: Commitment,
+    sender: mpsc::Sender<Result<T, Lagged>>,
+    mut select: F,
+) where
+    F: FnMut(&FeedEvent) -> Option<(u64, T)>,
+{
+    let mut gate = CommitmentGate::new(level);
+
+    loop {
+        let event = tokio::select! {
+            _ = sender.closed() => return,
+            event = events.recv() => event,
+        };
+
+        let ready = match event {
+            Ok(FeedEvent::SlotStatus(status)) => match Commitment::from_slot_status(status.status.as_str()) {
+                Some(reached) => gate.reached(status.slot, reached),
+                None => continue,
+            },
+            Ok(event) => {
+                let mut ready = match select(&event) {
+                    Some((slot, update)) => gate.push(slot, update),
+                    None => Vec::new(),
+                };
+                // Backfilled blocks arrive rooted without a separate status update
+                if let FeedEvent::Block(block) = &event {
+                    if let Some(reached) = Commitment::from_slot_status(block.status.as_str()) {
+                        ready.extend(gate.reached(block.slot, reached));
+                    }
+                }
+                ready
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                let _ = sender.send(Err(Lagged(skipped))).await;
+                return;
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        for update in ready {
+            if sender.send(Ok(update)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// The hub serving live routes, which exists once a data feed is set
+pub fn subscription_hub(state: &crate::rest::AppState) -> Result<Arc<SubscriptionHub>, crate::types::ApiError> {
+    state.subscriptions.clone().ok_or_else(|| {
+        crate::types::ApiError::Internal("Live subscriptions need a data feed".to_string())
+    })
+}
+
+/// Serve a subscription as Server-Sent Events named `event`
+///
+/// A lagging client is sent an `error` event and the stream ends. Comments
+/// are sent while idle so proxies keep the connection open.
+pub fn stream_to_sse<T: Serialize + Send + 'static>(
+    subscription: Subscription<T>,
+    event: &'static str,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = stream::unfold(Some(subscription), move |subscription| async move {
+        let mut subscription = subscription?;
+        loop {
+            match subscription.recv().await? {
+                Ok(update) => {
+                    let Ok(data) = Event::default().event(event).json_data(&update) else { continue };
+                    return Some((Ok(data), Some(subscription)));
+                }
+                Err(Lagged(skipped)) => {
+                    let error = Event::default()
+                        .event("error")
+                        .data(format!("Subscriber fell behind and missed {} updates", skipped));
+                    return Some((Ok(error), None));
+                }
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Serve every message of a broadcast channel as Server-Sent Events named
+/// `event`, ending like [`stream_to_sse`] when the client lags
+pub fn broadcast_to_sse<T: Serialize + Clone + Send + 'static>(
+    receiver: broadcast::Receiver<T>,
+    event: &'static str,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = stream::unfold(Some(receiver), move |receiver| async move {
+        let mut receiver = receiver?;
+        loop {
+            match receiver.recv().await {
+                Ok(message) => {
+                    let Ok(data) = Event::default().event(event).json_data(&message) else { continue };
+                    return Some((Ok(data), Some(receiver)));
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    let error = Event::default()
+                        .event("error")
+                        .data(format!("Subscriber fell behind and missed {} updates", skipped));
+                    return Some((Ok(error), None));
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finalized_gate_releases_rooted_slots_and_drops_forks() {
+        let mut gate = CommitmentGate::new(Commitment::Finalized);
+        assert!(gate.push(10, "a").is_empty());
+        assert!(gate.push(11, "b").is_empty());
+        assert!(gate.push(12, "c").is_empty());
+
+        assert!(gate.reached(11, Commitment::Confirmed).is_empty());
+        assert_eq!(gate.reached(11, Commitment::Finalized), vec!["b"]);
+        assert!(gate.reached(10, Commitment::Finalized).is_empty());
+        assert_eq!(gate.reached(12, Commitment::Finalized), vec!["c"]);
+        assert_eq!(gate.push(12, "d"), vec!["d"]);
+    }
+}
@@ -0,0 +1,169 @@
+//! Tenants
+//!
+//! One deployment can serve several isolated tenants, each with its own copy
+//! of the data it is interested in (the store's `tenants` backend). A request
+//! selects a tenant with the `X-Windexer-Tenant` header. API keys and JWTs
+//! issued for a tenant are limited to it and select it without the header.
+//!
+//! For the rest of the request, [`AppState::store`] returns the tenant's
+//! store, so every store-backed route answers from the tenant's data. Each
+//! tenant has a request quota shared by all of its callers, on top of the
+//! per-credential limits. Tenant-bound credentials cannot use the admin
+//! routes; `/stats` is shared by all tenants.
+//!
+//! Live streams only carry the accounts and transactions the tenant's filter
+//! lets into its store, and every block and slot update. The tenant is
+//! captured when a subscription starts; work that outlives the handler, such
+//! as a WebSocket connection, runs in it with [`TenantContext::scope`].
+
+use {
+    crate::{
+        auth::{Principal, RateLimiter},
+        rest::AppState,
+        types::ApiError,
+    },
+    axum::{
+        extract::{Request, State},
+        middleware::Next,
+        response::Response,
+    },
+    std::{collections::HashMap, future::Future, sync::Arc},
+    windexer_common::feed::FeedEvent,
+};
+
+/// Header selecting the tenant of a request
+pub const TENANT_HEADER: &str = "x-windexer-tenant";
+
+/// Whether a feed event may be streamed to a tenant's subscribers
+pub type TenantScope = Arc<dyn Fn(&FeedEvent) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+struct SelectedTenant {
+    id: String,
+    scope: TenantScope,
+}
+
+tokio::task_local! {
+    static CURRENT_TENANT: Option<SelectedTenant>;
+}
+
+/// The tenant selected for the request being handled, if any
+pub fn current_tenant() -> Option<String> {
+    TenantContext::current().0.map(|tenant| tenant.id)
+}
+
+/// Live data the tenant of the request being handled may see; `None` when
+/// no tenant is selected
+pub fn current_scope() -> Option<TenantScope> {
+    TenantContext::current().0.map(|tenant| tenant.scope)
+}
+
+/// The tenant of a request, carried into work that outlives its handler
+#[derive(Clone)]
+pub struct TenantContext(Option<SelectedTenant>);
+
+impl TenantContext {
+    pub fn current() -> Self {
+        Self(CURRENT_TENANT.try_with(Clone::clone).ok().flatten())
+    }
+
+    /// Run `future` in this tenant
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT_TENANT.scope(self.0, future)
+    }
+}
+
+/// Feed events the tenant's store receives: blocks and slot updates always,
+/// accounts and transactions when they pass the tenant's filter
+#[cfg(feature = "store")]
+fn tenant_scope(state: &AppState, id: &str) -> TenantScope {
+    let Some(matcher) = state.storage.as_ref().and_then(|storage| storage.tenant_matcher(id)) else {
+        return Arc::new(|event: &FeedEvent| !matches!(event, FeedEvent::Account(_) | FeedEvent::Transaction(_)));
+    };
+    Arc::new(move |event: &FeedEvent| match event {
+        FeedEvent::Account(account) => matcher.matches_account(account),
+        FeedEvent::Transaction(transaction) => matcher.matches_transaction(transaction),
+        _ => true,
+    })
+}
+
+/// Without a store no tenant receives accounts or transactions
+#[cfg(not(feature = "store"))]
+fn tenant_scope(_state: &AppState, _id: &str) -> TenantScope {
+    Arc::new(|event: &FeedEvent| !matches!(event, FeedEvent::Account(_) | FeedEvent::Transaction(_)))
+}
+
+#[derive(Debug, Clone)]
+pub struct TenantConfig {
+    /// Same id as the tenant's store
+    pub id: String,
+    /// Requests per minute across all of the tenant's callers; `None` is unlimited
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// Tenants the API serves
+#[derive(Default)]
+pub struct TenantRegistry {
+    tenants: HashMap<String, TenantConfig>,
+    limiter: RateLimiter,
+}
+
+impl TenantRegistry {
+    pub fn new(tenants: impl IntoIterator<Item = TenantConfig>) -> Self {
+        Self {
+            tenants: tenants.into_iter().map(|tenant| (tenant.id.clone(), tenant)).collect(),
+            limiter: RateLimiter::default(),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&TenantConfig> {
+        self.tenants.get(id)
+    }
+}
+
+fn requested_tenant(request: &Request) -> Result<Option<String>, ApiError> {
+    request.headers().get(TENANT_HEADER)
+        .map(|value| {
+            value.to_str()
+                .map(|tenant| tenant.trim().to_string())
+                .map_err(|_| ApiError::BadRequest(format!("Invalid {} header", TENANT_HEADER)))
+        })
+        .transpose()
+}
+
+/// Middleware selecting the request's tenant; runs after authentication
+pub async fn select_tenant(State(state): State<AppState>, request: Request, next: Next) -> Result<Response, ApiError> {
+    let requested = requested_tenant(&request)?;
+    let bound = request.extensions().get::<Principal>().and_then(|principal| principal.tenant.clone());
+
+    let tenant = match (bound, requested) {
+        (Some(bound), Some(requested)) if bound != requested => {
+            return Err(ApiError::Forbidden(format!("Credential is limited to tenant {}", bound)));
+        }
+        (Some(bound), _) => {
+            if request.uri().path().contains("/admin/") {
+                return Err(ApiError::Forbidden("Tenant credentials cannot use the admin routes".to_string()));
+            }
+            Some(bound)
+        }
+        (None, requested) => requested,
+    };
+
+    let Some(id) = tenant else {
+        return Ok(next.run(request).await);
+    };
+    let config = state.tenants.get(&id)
+        .ok_or_else(|| ApiError::NotFound(format!("Unknown tenant {}", id)))?;
+    #[cfg(feature = "store")]
+    {
+        if state.storage.as_ref().is_some_and(|storage| storage.tenant(&id).is_none()) {
+            return Err(ApiError::NotFound(format!("Tenant {} has no store", id)));
+        }
+    }
+    if let Some(limit) = config.rate_limit_per_minute {
+        state.tenants.limiter.check(&format!("tenant:{}", id), limit).await?;
+    }
+
+    let scope = tenant_scope(&state, &id);
+    Ok(CURRENT_TENANT.scope(Some(SelectedTenant { id, scope }), next.run(request)).await)
+}
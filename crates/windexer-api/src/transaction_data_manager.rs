@@ -5,13 +5,15 @@ use anyhow::Result;
 use serde_json::Value;
 use chrono;
 
-use crate::transaction_endpoints::TransactionData;
+use crate::cache::{CacheConfig, CacheStats, ReadThroughCache, Source};
+use crate::transaction_endpoints::{json_instructions, program_ids, TransactionData};
 use crate::helius::HeliusClient;
+use windexer_common::{lookup_tables, types::Commitment};
 
 pub struct TransactionDataManager {
     helius_client: Arc<HeliusClient>,
     
-    cache: Arc<RwLock<HashMap<String, TransactionData>>>,
+    cache: ReadThroughCache<TransactionData>,
     
     recent_transactions: Arc<RwLock<VecDeque<String>>>,
     
@@ -23,24 +25,29 @@ pub struct TransactionDataManager {
     
     initialized: Arc<RwLock<bool>>,
     
-    max_cache_size: usize,
-    
     max_recent_transactions: usize,
 }
 
 impl TransactionDataManager {
     pub fn new(helius_client: Arc<HeliusClient>) -> Self {
+        Self::with_cache_config(helius_client, CacheConfig {
+            ttl: std::time::Duration::from_secs(300), // Confirmed transactions don't change
+            capacity: 100000, // Store up to 100,000 transactions in cache
+            ..CacheConfig::default()
+        })
+    }
+
+    pub fn with_cache_config(helius_client: Arc<HeliusClient>, cache_config: CacheConfig) -> Self {
         let (tx, _) = broadcast::channel(10000); // Buffer for 10,000 transaction updates
         
         Self {
             helius_client,
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: ReadThroughCache::new(cache_config),
             recent_transactions: Arc::new(RwLock::new(VecDeque::new())),
             program_transactions: Arc::new(RwLock::new(HashMap::new())),
             account_transactions: Arc::new(RwLock::new(HashMap::new())),
             update_sender: tx,
             initialized: Arc::new(RwLock::new(false)),
-            max_cache_size: 100000, // Store up to 100,000 transactions in cache
             max_recent_transactions: 1000, // Keep 1,000 recent transactions per program/account
         }
     }
@@ -88,146 +95,92 @@ impl TransactionDataManager {
         let mut program_txs = self.program_transactions.write().await;
         program_txs.entry(program_id.to_string()).or_insert_with(VecDeque::new);
         
-        // Subscribe using the Helius client
-        self.helius_client.subscribe_program_updates(program_id).await
+        // Live transactions arrive as logs notifications
+        self.helius_client.subscribe_logs(program_id).await
     }
     
-    pub async fn get_transaction(&self, signature: &str) -> Result<TransactionData> {
-        // Check cache first
-        {
-            let cache = self.cache.read().await;
-            if let Some(tx) = cache.get(signature) {
-                return Ok(tx.clone());
-            }
-        }
-        
-        let response = self.helius_client.get_transaction(signature).await?;
-        
-        tracing::debug!("Helius transaction response: {:?}", response);
-        
-        if let Some(error) = response.get("error") {
-            return Err(anyhow::anyhow!("Helius API error: {}", error));
-        }
-        
-        let result = response.get("result").ok_or_else(|| anyhow::anyhow!("Missing result field in response"))?;
-        
-        if result.is_null() {
-            return Err(anyhow::anyhow!("Transaction not found"));
-        }
-        
-        let slot = result.get("slot").and_then(|s| s.as_u64())
-            .ok_or_else(|| anyhow::anyhow!("Missing slot field in result"))?;
-        
-        let block_time = result.get("blockTime").and_then(|b| b.as_i64());
-        
-        let meta = result.get("meta").ok_or_else(|| anyhow::anyhow!("Missing meta field in result"))?;
-        let err = meta.get("err").and_then(|e| {
-            if e.is_null() {
-                None
-            } else {
-                Some(e.clone())
+    /// Streams transactions of subscribed programs to [`TransactionDataManager::subscribe`]
+    ///
+    /// Logs notifications only carry signatures, so each transaction is then
+    /// fetched through the cache. The WebSocket is reopened after a delay
+    /// whenever it fails, until [`HeliusClient::stop_processing`] is called.
+    pub fn spawn_live_updates(self: &Arc<Self>) {
+        let (signatures_tx, mut signatures_rx) = mpsc::channel::<String>(10000);
+        
+        let client = self.helius_client.clone();
+        tokio::spawn(async move {
+            loop {
+                let signatures_tx = signatures_tx.clone();
+                let handler = move |message: Value| {
+                    if message.get("method").and_then(|m| m.as_str()) != Some("logsNotification") {
+                        return Ok(());
+                    }
+                    let Some(signature) = message.pointer("/params/result/value/signature").and_then(|s| s.as_str()) else {
+                        return Ok(());
+                    };
+                    if signatures_tx.try_send(signature.to_string()).is_err() {
+                        tracing::warn!("Dropping live transaction {}: fetch queue is full", signature);
+                    }
+                    Ok(())
+                };
+                
+                match client.process_messages(handler).await {
+                    Ok(()) => break,
+                    Err(e) => {
+                        tracing::warn!("Live transaction stream failed, reconnecting in 5s: {:#}", e);
+                        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    }
+                }
             }
         });
-        let fee = meta.get("fee").and_then(|f| f.as_u64())
-            .ok_or_else(|| anyhow::anyhow!("Missing fee field in meta"))?;
         
-        let logs = meta.get("logMessages").and_then(|l| {
-            if l.is_array() {
-                Some(l.as_array().unwrap()
-                    .iter()
-                    .map(|entry| entry.as_str().unwrap_or("").to_string())
-                    .collect())
-            } else {
-                None
+        let manager = self.clone();
+        tokio::spawn(async move {
+            while let Some(signature) = signatures_rx.recv().await {
+                match manager.get_transaction(&signature, Commitment::Confirmed).await {
+                    Ok(tx) => {
+                        let _ = manager.update_sender.send(tx);
+                    }
+                    Err(e) => tracing::debug!("Failed to fetch live transaction {}: {}", signature, e),
+                }
             }
         });
+    }
+    
+    /// Get a transaction at `commitment`, from cache or Helius
+    pub async fn get_transaction(&self, signature: &str, commitment: Commitment) -> Result<TransactionData> {
+        let helius_client = self.helius_client.clone();
+        let owned_signature = signature.to_string();
+        let key = format!("{}:{}", signature, commitment.as_str());
+        let (tx, source) = self.cache.get_or_load(&key, move || async move {
+            let response = helius_client.get_transaction(&owned_signature, commitment).await?;
+            tracing::debug!("Helius transaction response: {:?}", response);
+            parse_transaction(&owned_signature, &response)
+        }).await?;
+        let tx = tx.ok_or_else(|| anyhow::anyhow!("Transaction not found"))?;
+        
+        if source == Source::Upstream {
+            self.record(&tx).await;
+        }
         
-        let transaction = result.get("transaction").ok_or_else(|| anyhow::anyhow!("Missing transaction field in result"))?;
-        let message = transaction.get("message").ok_or_else(|| anyhow::anyhow!("Missing message field in transaction"))?;
-        
-        let recent_blockhash = message.get("recentBlockhash")
-            .and_then(|b| b.as_str())
-            .unwrap_or("")
-            .to_string();
-        
-        let account_keys = message.get("accountKeys")
-            .and_then(|a| a.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .map(|key| key.as_str().unwrap_or("").to_string())
-                    .collect()
-            })
-            .unwrap_or_else(Vec::new);
-        
-        let program_ids = message.get("instructions")
-            .and_then(|i| i.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|inst| {
-                        inst.get("programId").and_then(|p| p.as_str()).map(|s| s.to_string())
-                    })
-                    .collect::<Vec<String>>()
-            })
-            .unwrap_or_else(|| {
-                account_keys.clone()
-            });
-        
-        let instructions = message.get("instructions")
-            .and_then(|i| i.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|inst| {
-                        let program_id = inst.get("programId").and_then(|p| p.as_str())?;
-                        let accounts = inst.get("accounts")
-                            .and_then(|a| a.as_array())
-                            .map(|arr| {
-                                arr.iter()
-                                    .filter_map(|idx| {
-                                        idx.as_u64().and_then(|i| account_keys.get(i as usize)).cloned()
-                                    })
-                                    .collect()
-                            })
-                            .unwrap_or_default();
-                        
-                        let data = inst.get("data").and_then(|d| d.as_str()).unwrap_or("").to_string();
-                        
-                        Some(crate::transaction_endpoints::InstructionData {
-                            program_id: program_id.to_string(),
-                            accounts,
-                            data,
-                        })
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-        
-        let tx = TransactionData {
-            signature: signature.to_string(),
-            slot,
-            block_time,
-            err: err.clone(),
-            fee,
-            recent_blockhash,
-            program_ids,
-            accounts: account_keys,
-            logs,
-            instructions,
-            success: err.is_none(),
-        };
-        
+        Ok(tx)
+    }
+    
+    /// Adds a newly fetched transaction to the recent, program and account lists
+    async fn record(&self, tx: &TransactionData) {
+        let signature = &tx.signature;
         {
-            let mut cache = self.cache.write().await;
-            cache.insert(signature.to_string(), tx.clone());
-            
             let mut recent = self.recent_transactions.write().await;
             recent.push_back(signature.to_string());
             
             if recent.len() > self.max_recent_transactions {
                 recent.pop_front();
             }
-            
+        }
+        
+        {
+            let mut program_txs = self.program_transactions.write().await;
             for program_id in &tx.program_ids {
-                let mut program_txs = self.program_transactions.write().await;
                 let queue = program_txs.entry(program_id.clone()).or_insert_with(VecDeque::new);
                 queue.push_back(signature.to_string());
                 
@@ -236,19 +189,17 @@ impl TransactionDataManager {
                     queue.pop_front();
                 }
             }
+        }
+        
+        let mut account_txs = self.account_transactions.write().await;
+        for account in &tx.accounts {
+            let queue = account_txs.entry(account.clone()).or_insert_with(VecDeque::new);
+            queue.push_back(signature.to_string());
             
-            for account in &tx.accounts {
-                let mut account_txs = self.account_transactions.write().await;
-                let queue = account_txs.entry(account.clone()).or_insert_with(VecDeque::new);
-                queue.push_back(signature.to_string());
-                
-                if queue.len() > self.max_recent_transactions {
-                    queue.pop_front();
-                }
+            if queue.len() > self.max_recent_transactions {
+                queue.pop_front();
             }
         }
-        
-        Ok(tx)
     }
     
     pub async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
@@ -260,7 +211,7 @@ impl TransactionDataManager {
         };
         
         for signature in signatures {
-            if let Ok(tx) = self.get_transaction(&signature).await {
+            if let Ok(tx) = self.get_transaction(&signature, Commitment::Confirmed).await {
                 txs.push(tx);
             }
         }
@@ -281,7 +232,7 @@ impl TransactionDataManager {
         };
         
         for signature in signatures {
-            if let Ok(tx) = self.get_transaction(&signature).await {
+            if let Ok(tx) = self.get_transaction(&signature, Commitment::Confirmed).await {
                 txs.push(tx);
             }
         }
@@ -302,7 +253,7 @@ impl TransactionDataManager {
         };
         
         for signature in signatures {
-            if let Ok(tx) = self.get_transaction(&signature).await {
+            if let Ok(tx) = self.get_transaction(&signature, Commitment::Confirmed).await {
                 txs.push(tx);
             }
         }
@@ -313,4 +264,76 @@ impl TransactionDataManager {
     pub fn subscribe(&self) -> broadcast::Receiver<TransactionData> {
         self.update_sender.subscribe()
     }
+
+    pub async fn cache_stats(&self) -> CacheStats {
+        self.cache.stats().await
+    }
+}
+
+/// Parses a `getTransaction` response; `None` if Helius does not have the transaction
+fn parse_transaction(signature: &str, response: &Value) -> Result<Option<TransactionData>> {
+    if let Some(error) = response.get("error") {
+        return Err(anyhow::anyhow!("Helius API error: {}", error));
+    }
+    
+    let result = response.get("result").ok_or_else(|| anyhow::anyhow!("Missing result field in response"))?;
+    
+    if result.is_null() {
+        return Ok(None);
+    }
+    
+    let slot = result.get("slot").and_then(|s| s.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing slot field in result"))?;
+    
+    let block_time = result.get("blockTime").and_then(|b| b.as_i64());
+    
+    let meta = result.get("meta").ok_or_else(|| anyhow::anyhow!("Missing meta field in result"))?;
+    let err = meta.get("err").and_then(|e| {
+        if e.is_null() {
+            None
+        } else {
+            Some(e.clone())
+        }
+    });
+    let fee = meta.get("fee").and_then(|f| f.as_u64())
+        .ok_or_else(|| anyhow::anyhow!("Missing fee field in meta"))?;
+    
+    let logs = meta.get("logMessages").and_then(|l| {
+        if l.is_array() {
+            Some(l.as_array().unwrap()
+                .iter()
+                .map(|entry| entry.as_str().unwrap_or("").to_string())
+                .collect())
+        } else {
+            None
+        }
+    });
+    
+    let transaction = result.get("transaction").ok_or_else(|| anyhow::anyhow!("Missing transaction field in result"))?;
+    let message = transaction.get("message").ok_or_else(|| anyhow::anyhow!("Missing message field in transaction"))?;
+    
+    let recent_blockhash = message.get("recentBlockhash")
+        .and_then(|b| b.as_str())
+        .unwrap_or("")
+        .to_string();
+    
+    // Includes the addresses loaded from lookup tables
+    let account_keys = lookup_tables::json_account_keys(transaction, meta);
+    
+    let instructions = json_instructions(message, meta, &account_keys);
+    let program_ids = program_ids(&instructions);
+    
+    Ok(Some(TransactionData {
+        signature: signature.to_string(),
+        slot,
+        block_time,
+        err: err.clone(),
+        fee,
+        recent_blockhash,
+        program_ids,
+        accounts: account_keys,
+        logs,
+        instructions,
+        success: err.is_none(),
+    }))
 }
\ No newline at end of file
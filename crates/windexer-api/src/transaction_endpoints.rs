@@ -5,13 +5,30 @@ use axum::{
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
 
+use windexer_common::{
+    feed::FeedEvent,
+    filter::{Filter, Predicate, Subject},
+    lookup_tables,
+    types::{ApiScope, Invocation, Page},
+};
+
+use crate::auth::{scoped, scoped_router, RouteScope};
 use crate::rest::AppState;
-use crate::types::{ApiResponse, ApiError};
+use crate::subscriptions::{
+    parse_filter, parse_pubkeys, stream_to_sse, stream_to_websocket, subscription_hub, Commitment, Subscription,
+    SubscriptionHub,
+};
+use crate::types::{recent_page, ApiResponse, ApiError, CommitmentParams};
 use crate::transaction_data_manager::TransactionDataManager;
+use crate::tenant::TenantContext;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TransactionData {
     pub signature: String,
     pub slot: u64,
@@ -26,39 +43,166 @@ pub struct TransactionData {
     pub success: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TransactionQueryParams {
     pub limit: Option<usize>,
     pub before: Option<String>,
     pub after: Option<String>,
     pub program: Option<String>,
     pub account: Option<String>,
+    pub cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TransactionUpdateParams {
     pub program: Option<String>,
     pub account: Option<String>,
+    pub commitment: Option<Commitment>,
+    /// Filter expression, e.g. `program:{program_id}; !vote; status:success`,
+    /// required along with `program` and `account`
+    pub filter: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct InstructionData {
     pub program_id: String,
     pub accounts: Vec<String>,
     pub data: String,
+    /// Invocation depth, 1 for top-level instructions
+    #[serde(default = "top_level")]
+    pub depth: u32,
+    /// Instructions invoked by this one, in execution order
+    #[serde(default)]
+    #[schema(no_recursion)]
+    pub inner_instructions: Vec<InstructionData>,
+}
+
+fn top_level() -> u32 {
+    1
+}
+
+impl From<&Invocation> for InstructionData {
+    fn from(call: &Invocation) -> Self {
+        Self {
+            program_id: call.program_id.to_string(),
+            accounts: call.accounts.iter().map(|key| key.to_string()).collect(),
+            data: bs58::encode(&call.data).into_string(),
+            depth: call.depth,
+            inner_instructions: call.calls.iter().map(Self::from).collect(),
+        }
+    }
+}
+
+/// Top-level instructions of a `json` or `jsonParsed` encoded transaction
+/// with the calls they made, nested from the `innerInstructions` of its
+/// meta. `account_keys` must include any addresses loaded from lookup
+/// tables. Instructions the node parsed carry no data.
+pub(crate) fn json_instructions(message: &Value, meta: &Value, account_keys: &[String]) -> Vec<InstructionData> {
+    let key = |value: &Value| -> Option<Pubkey> {
+        let key = match value.as_u64() {
+            Some(index) => account_keys.get(index as usize)?.as_str(),
+            None => value.as_str()?,
+        };
+        Pubkey::from_str(key).ok()
+    };
+    let instruction = |value: &Value, stack_height: Option<u32>| -> Option<windexer_common::types::InstructionData> {
+        Some(windexer_common::types::InstructionData {
+            program_id: key(&value["programIdIndex"]).or_else(|| key(&value["programId"]))?,
+            accounts: value["accounts"].as_array().into_iter().flatten().filter_map(key).collect(),
+            data: value["data"].as_str().and_then(|data| bs58::decode(data).into_vec().ok()).unwrap_or_default(),
+            stack_height,
+            inner_instructions: Vec::new(),
+        })
+    };
+
+    let mut instructions: Vec<_> = message["instructions"].as_array().into_iter().flatten()
+        .filter_map(|value| instruction(value, Some(1)))
+        .collect();
+    for inner in meta["innerInstructions"].as_array().into_iter().flatten() {
+        let Some(parent) = inner["index"].as_u64().and_then(|index| instructions.get_mut(index as usize)) else {
+            continue;
+        };
+        parent.inner_instructions = inner["instructions"].as_array().into_iter().flatten()
+            .filter_map(|value| instruction(value, value["stackHeight"].as_u64().map(|height| height as u32)))
+            .collect();
+    }
+    instructions.iter().map(|instruction| InstructionData::from(&instruction.call_tree())).collect()
+}
+
+/// Programs of the top-level instructions, in order of first use
+pub(crate) fn program_ids(instructions: &[InstructionData]) -> Vec<String> {
+    let mut program_ids: Vec<String> = Vec::new();
+    for instruction in instructions {
+        if !program_ids.contains(&instruction.program_id) {
+            program_ids.push(instruction.program_id.clone());
+        }
+    }
+    program_ids
+}
+
+impl From<&windexer_common::types::TransactionData> for TransactionData {
+    fn from(transaction: &windexer_common::types::TransactionData) -> Self {
+        let keys = &transaction.message.account_keys;
+        let status = transaction.serializable_meta.status;
+
+        // Records written before instructions were resolved carry none
+        let parsed;
+        let resolved = if transaction.instructions.is_empty() {
+            parsed = windexer_common::types::InstructionData::parse(
+                &transaction.message,
+                transaction.serializable_meta.inner_instructions().as_deref(),
+            );
+            &parsed
+        } else {
+            &transaction.instructions
+        };
+        let instructions: Vec<InstructionData> = resolved.iter()
+            .map(|instruction| InstructionData::from(&instruction.call_tree()))
+            .collect();
+        let program_ids = program_ids(&instructions);
+
+        Self {
+            signature: transaction.signature.to_string(),
+            slot: transaction.slot,
+            block_time: None,
+            err: status.filter(|&code| code != 0).map(serde_json::Value::from),
+            fee: transaction.serializable_meta.fee,
+            recent_blockhash: transaction.message.recent_blockhash.to_string(),
+            program_ids,
+            accounts: keys.iter().map(|key| key.to_string()).collect(),
+            logs: transaction.serializable_meta.log_messages.clone(),
+            instructions,
+            success: status == Some(0),
+        }
+    }
 }
 
+#[utoipa::path(
+    get,
+    path = "/transaction/{signature}",
+    tag = "transactions",
+    params(("signature" = String, Path, description = "Transaction signature"), CommitmentParams),
+    responses(
+        (status = 200, description = "Transaction", body = ApiResponse<TransactionData>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_transaction(
     State(state): State<AppState>,
     Path(signature): Path<String>,
+    Query(params): Query<CommitmentParams>,
 ) -> Result<Json<ApiResponse<TransactionData>>, ApiError> {
+    let commitment = params.level();
     let helius_client = state.helius_client.as_ref().ok_or_else(|| {
         ApiError::Internal("Helius client not initialized".to_string())
     })?;
     
     // Try to get transaction from manager first if available
     if let Some(tx_manager) = &state.transaction_data_manager {
-        match tx_manager.get_transaction(&signature).await {
+        match tx_manager.get_transaction(&signature, commitment).await {
             Ok(tx) => return Ok(Json(ApiResponse::success(tx))),
             Err(e) => {
                 tracing::warn!("Error getting transaction from manager, falling back to direct API call: {}", e);
@@ -67,7 +211,7 @@ pub async fn get_transaction(
         }
     }
     
-    match helius_client.get_transaction(&signature).await {
+    match helius_client.get_transaction(&signature, commitment).await {
         Ok(response) => {
             tracing::debug!("Helius transaction response: {}", response);
             
@@ -116,56 +260,11 @@ pub async fn get_transaction(
                                 .unwrap_or("")
                                 .to_string();
                             
-                            let account_keys = message.get("accountKeys")
-                                .and_then(|a| a.as_array())
-                                .map(|arr| {
-                                    arr.iter()
-                                        .map(|key| key.as_str().unwrap_or("").to_string())
-                                        .collect()
-                                })
-                                .unwrap_or_else(Vec::new);
+                            // Includes the addresses loaded from lookup tables
+                            let account_keys = lookup_tables::json_account_keys(transaction, meta);
                             
-                            let program_ids = message.get("instructions")
-                                .and_then(|i| i.as_array())
-                                .map(|arr| {
-                                    arr.iter()
-                                        .filter_map(|inst| {
-                                            inst.get("programId").and_then(|p| p.as_str()).map(|s| s.to_string())
-                                        })
-                                        .collect::<Vec<String>>()
-                                })
-                                .unwrap_or_else(|| {
-                                    account_keys.first().cloned().into_iter().collect()
-                                });
-                            
-                            let instructions = message.get("instructions")
-                                .and_then(|i| i.as_array())
-                                .map(|arr| {
-                                    arr.iter()
-                                        .filter_map(|inst| {
-                                            let program_id = inst.get("programId").and_then(|p| p.as_str())?;
-                                            let accounts = inst.get("accounts")
-                                                .and_then(|a| a.as_array())
-                                                .map(|arr| {
-                                                    arr.iter()
-                                                        .filter_map(|idx| {
-                                                            idx.as_u64().and_then(|i| account_keys.get(i as usize)).cloned()
-                                                        })
-                                                        .collect()
-                                                })
-                                                .unwrap_or_default();
-                                            
-                                            let data = inst.get("data").and_then(|d| d.as_str()).unwrap_or("").to_string();
-                                            
-                                            Some(InstructionData {
-                                                program_id: program_id.to_string(),
-                                                accounts,
-                                                data,
-                                            })
-                                        })
-                                        .collect()
-                                })
-                                .unwrap_or_default();
+                            let instructions = json_instructions(message, meta, &account_keys);
+                            let program_ids = program_ids(&instructions);
                             
                             let tx = TransactionData {
                                 signature: signature.clone(),
@@ -196,191 +295,221 @@ pub async fn get_transaction(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/transactions/recent",
+    tag = "transactions",
+    params(TransactionQueryParams),
+    responses(
+        (status = 200, description = "Most recent transactions", body = ApiResponse<Page<TransactionData>>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_recent_transactions(
     State(state): State<AppState>,
     Query(params): Query<TransactionQueryParams>,
-) -> Result<Json<ApiResponse<Vec<TransactionData>>>, ApiError> {
-    let tx_manager = state.transaction_data_manager.ok_or_else(|| {
+) -> Result<Json<ApiResponse<Page<TransactionData>>>, ApiError> {
+    let tx_manager = state.transaction_data_manager.as_ref().ok_or_else(|| {
         ApiError::Internal("Transaction data manager not initialized".to_string())
     })?;
     
-    // Get limit from query params
+    // Get limit and cursor from query params
     let limit = params.limit.unwrap_or(10);
     
     // Fetch recent transactions
-    match tx_manager.get_recent_transactions(limit).await {
-        Ok(txs) => Ok(Json(ApiResponse::success(txs))),
-        Err(e) => Err(ApiError::Internal(format!("Failed to fetch recent transactions: {}", e)))
-    }
+    let page = recent_page(params.cursor.as_deref(), limit, |tx: &TransactionData| tx.slot, |_, n| async move {
+        tx_manager.get_recent_transactions(n).await
+            .map_err(|e| ApiError::Internal(format!("Failed to fetch recent transactions: {}", e)))
+    }).await?;
+    Ok(Json(ApiResponse::success(page)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/transactions/program/{program_id}",
+    tag = "transactions",
+    params(("program_id" = String, Path, description = "Invoked program"), TransactionQueryParams),
+    responses(
+        (status = 200, description = "Transactions invoking the program", body = ApiResponse<Page<TransactionData>>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_transactions_by_program(
     State(state): State<AppState>,
     Path(program_id): Path<String>,
     Query(params): Query<TransactionQueryParams>,
-) -> Result<Json<ApiResponse<Vec<TransactionData>>>, ApiError> {
-    let tx_manager = state.transaction_data_manager.ok_or_else(|| {
-        ApiError::Internal("Transaction data manager not initialized".to_string())
-    })?;
-    
+) -> Result<Json<ApiResponse<Page<TransactionData>>>, ApiError> {
     let limit = params.limit.unwrap_or(10);
     
-    match tx_manager.get_transactions_by_program(&program_id, limit).await {
-        Ok(txs) => Ok(Json(ApiResponse::success(txs))),
-        Err(e) => Err(ApiError::Internal(format!("Failed to fetch transactions by program: {}", e)))
-    }
+    let (state, program_id) = (&state, &program_id);
+    let page = recent_page(params.cursor.as_deref(), limit, |tx: &TransactionData| tx.slot, |_, n| async move {
+        transactions_by_program(state, program_id, n).await
+    }).await?;
+    Ok(Json(ApiResponse::success(page)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/transactions/account/{account}",
+    tag = "transactions",
+    params(("account" = String, Path, description = "Mentioned account"), TransactionQueryParams),
+    responses(
+        (status = 200, description = "Transactions mentioning the account", body = ApiResponse<Page<TransactionData>>),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Not found", body = ApiError)
+    )
+)]
 pub async fn get_transactions_by_account(
     State(state): State<AppState>,
     Path(account): Path<String>,
     Query(params): Query<TransactionQueryParams>,
-) -> Result<Json<ApiResponse<Vec<TransactionData>>>, ApiError> {
-    let tx_manager = state.transaction_data_manager.ok_or_else(|| {
-        ApiError::Internal("Transaction data manager not initialized".to_string())
-    })?;
-    
+) -> Result<Json<ApiResponse<Page<TransactionData>>>, ApiError> {
     let limit = params.limit.unwrap_or(10);
     
-    match tx_manager.get_transactions_by_account(&account, limit).await {
-        Ok(txs) => Ok(Json(ApiResponse::success(txs))),
-        Err(e) => Err(ApiError::Internal(format!("Failed to fetch transactions by account: {}", e)))
+    let (state, account) = (&state, &account);
+    let page = recent_page(params.cursor.as_deref(), limit, |tx: &TransactionData| tx.slot, |_, n| async move {
+        transactions_by_account(state, account, n).await
+    }).await?;
+    Ok(Json(ApiResponse::success(page)))
+}
+
+fn transaction_manager(state: &AppState) -> Result<&std::sync::Arc<TransactionDataManager>, ApiError> {
+    state.transaction_data_manager.as_ref().ok_or_else(|| {
+        ApiError::Internal("Transaction data manager not initialized".to_string())
+    })
+}
+
+/// Whether transactions can be listed, from the store or the data manager
+fn lists_transactions(state: &AppState) -> bool {
+    #[cfg(feature = "store")]
+    {
+        if state.storage.is_some() {
+            return true;
+        }
     }
+    state.transaction_data_manager.is_some()
 }
 
+/// Latest transactions invoking `program_id`, newest first
+///
+/// The store's index answers when there is a store; the data manager's
+/// cache of recently seen transactions only stands in without one.
+pub(crate) async fn transactions_by_program(state: &AppState, program_id: &str, limit: usize) -> Result<Vec<TransactionData>, ApiError> {
+    #[cfg(feature = "store")]
+    {
+        if let Some(storage) = state.store() {
+            let transactions = storage.get_transactions_by_program(program_id, limit).await.map_err(ApiError::storage)?;
+            return Ok(transactions.iter().map(TransactionData::from).collect());
+        }
+    }
+    
+    transaction_manager(state)?.get_transactions_by_program(program_id, limit).await
+        .map_err(|e| ApiError::Internal(format!("Failed to fetch transactions by program: {}", e)))
+}
+
+/// Latest transactions mentioning `account`, newest first, read like
+/// [`transactions_by_program`]
+pub(crate) async fn transactions_by_account(state: &AppState, account: &str, limit: usize) -> Result<Vec<TransactionData>, ApiError> {
+    #[cfg(feature = "store")]
+    {
+        if let Some(storage) = state.store() {
+            let transactions = storage.get_transactions_by_account(account, limit).await.map_err(ApiError::storage)?;
+            return Ok(transactions.iter().map(TransactionData::from).collect());
+        }
+    }
+    
+    transaction_manager(state)?.get_transactions_by_account(account, limit).await
+        .map_err(|e| ApiError::Internal(format!("Failed to fetch transactions by account: {}", e)))
+}
+
+fn transaction_filter(params: &TransactionUpdateParams) -> Result<Filter, ApiError> {
+    Ok(parse_filter(params.filter.as_deref(), Subject::Transactions).map_err(ApiError::BadRequest)?
+        .and_any_of(Predicate::Accounts, parse_pubkeys(params.account.as_deref(), "account").map_err(ApiError::BadRequest)?)
+        .and_any_of(Predicate::Programs, parse_pubkeys(params.program.as_deref(), "program").map_err(ApiError::BadRequest)?))
+}
+
+fn subscribe_transactions(
+    hub: &std::sync::Arc<SubscriptionHub>,
+    commitment: Commitment,
+    filter: Filter,
+) -> Subscription<TransactionData> {
+    hub.subscribe(commitment, move |event| match event {
+        FeedEvent::Transaction(transaction) if filter.matches_transaction_data(transaction) => {
+            Some((transaction.slot, TransactionData::from(&**transaction)))
+        }
+        _ => None,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/ws/transactions",
+    tag = "streams",
+    params(TransactionUpdateParams),
+    responses((status = 101, description = "WebSocket of `TransactionData` updates"))
+)]
 pub async fn transaction_stream(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
     Query(params): Query<TransactionUpdateParams>,
-) -> impl IntoResponse {
-    let program = params.program;
-    let account = params.account;
+) -> Result<impl IntoResponse, ApiError> {
+    let hub = subscription_hub(&state)?;
+    let filter = transaction_filter(&params)?;
+    let commitment = params.commitment.unwrap_or_default();
 
-    ws.on_upgrade(move |socket| async move {
-        handle_transaction_websocket(socket, state, program, account).await
-    })
+    // The connection outlives the request and its tenant scope
+    let tenant = TenantContext::current();
+    Ok(ws.on_upgrade(move |socket| tenant.scope(async move {
+        let subscription = subscribe_transactions(&hub, commitment, filter);
+        state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+
+        stream_to_websocket(socket, subscription).await;
+        state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+    })))
 }
 
-async fn handle_transaction_websocket(
-    socket: axum::extract::ws::WebSocket,
-    state: AppState,
-    program: Option<String>,
-    account: Option<String>,
-) {
-    use axum::extract::ws::Message;
-    use futures::{SinkExt, StreamExt};
-    use std::time::Duration;
-    
-    state.metrics.set_metric("active_transaction_streams", serde_json::json!(1)).await;
-    
-    let (sender, receiver) = socket.split();
-    
-    let (tx, rx) = broadcast::channel::<TransactionData>(1000);
-    
-    let tx_clone = tx.clone();
-    let program_clone = program.clone();
-    let account_clone = account.clone();
-    
-    let mut simulation_task = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
-        
-        loop {
-            interval.tick().await;
-            
-            let signature = format!("signature{}", fastrand::u64(..1000000));
-            
-            let program_ids = if let Some(ref p) = program_clone {
-                vec![p.clone()]
-            } else {
-                vec![format!("program{}", fastrand::u64(..10))]
-            };
-            
-            let accounts = if let Some(ref a) = account_clone {
-                vec![a.clone()]
-            } else {
-                vec![format!("account{}", fastrand::u64(..10))]
-            };
-            
-            let transaction = TransactionData {
-                signature,
-                slot: fastrand::u64(..1000000),
-                block_time: Some(chrono::Utc::now().timestamp()),
-                err: None,
-                fee: fastrand::u64(..10000),
-                recent_blockhash: format!("blockhash{}", fastrand::u64(..1000)),
-                program_ids,
-                accounts,
-                logs: Some(vec!["Program log: Simulated transaction".to_string()]),
-                instructions: Vec::new(),
-                success: true,
-            };
-            
-            let _ = tx_clone.send(transaction);
-        }
-    });
-    
-    tokio::spawn(async move {
-        let mut sender = sender;
-        let mut receiver = receiver;
-        let mut rx = rx;
-        
-        loop {
-            tokio::select! {
-                result = receiver.next() => {
-                    match result {
-                        Some(Ok(Message::Text(text))) => {
-                            if text == "ping" {
-                                if sender.send(Message::Text("pong".to_string())).await.is_err() {
-                                    break;
-                                }
-                            }
-                        },
-                        Some(Ok(Message::Close(_))) | None => break,
-                        _ => {}
-                    }
-                },
-                
-                result = rx.recv() => {
-                    if let Ok(transaction) = result {
-                        let matches_program = program.is_none() || 
-                            transaction.program_ids.iter().any(|p| Some(p) == program.as_ref());
-                            
-                        let matches_account = account.is_none() || 
-                            transaction.accounts.iter().any(|a| Some(a) == account.as_ref());
-                        
-                        if matches_program && matches_account {
-                            if let Ok(json) = serde_json::to_string(&transaction) {
-                                if sender.send(Message::Text(json)).await.is_err() {
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        simulation_task.abort();
-        
-        state.metrics.set_metric("active_transaction_streams", serde_json::json!(0)).await;
-    });
+/// Transactions as Server-Sent Events, for clients that cannot use WebSockets
+#[utoipa::path(
+    get,
+    path = "/stream/transactions",
+    tag = "streams",
+    params(TransactionUpdateParams),
+    responses((status = 200, description = "`transaction` events carrying `TransactionData`", content_type = "text/event-stream"))
+)]
+pub async fn transaction_events(
+    State(state): State<AppState>,
+    Query(params): Query<TransactionUpdateParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let hub = subscription_hub(&state)?;
+    let filter = transaction_filter(&params)?;
+
+    let subscription = subscribe_transactions(&hub, params.commitment.unwrap_or_default(), filter);
+    state.metrics.set_metric("active_subscriptions", serde_json::json!(hub.active_subscriptions())).await;
+    Ok(stream_to_sse(subscription, "transaction"))
 }
 
-pub fn create_transaction_router() -> Router<AppState> {
-    Router::new()
-        .route("/transaction/:signature", get(get_transaction))
-        .route("/transactions/recent", get(get_recent_transactions))
-        .route("/transactions/program/:program_id", get(get_transactions_by_program))
-        .route("/transactions/account/:account", get(get_transactions_by_account))
-        .route("/ws/transactions", get(transaction_stream))
+pub fn create_transaction_router() -> OpenApiRouter<AppState> {
+    let reads = OpenApiRouter::new()
+        .routes(routes!(get_transaction))
+        .routes(routes!(get_recent_transactions))
+        .routes(routes!(get_transactions_by_program))
+        .routes(routes!(get_transactions_by_account));
+    let streams = OpenApiRouter::new()
+        .routes(routes!(transaction_stream))
+        .routes(routes!(transaction_events));
+
+    scoped(reads, RouteScope::ReadWrite).merge(scoped(streams, RouteScope::All(ApiScope::Stream)))
 }
 
 pub fn create_jito_compat_transaction_router() -> Router<AppState> {
-    Router::new()
+    let router = Router::new()
         .route("/transactions/recent", get(get_recent_transactions_jito_compat))
         .route("/transaction/:signature", get(get_transaction_by_signature_jito_compat))
         .route("/transactions/program/:pubkey", get(get_transactions_by_program_jito_compat))
-        .route("/transactions/account/:pubkey", get(get_transactions_by_account_jito_compat))
+        .route("/transactions/account/:pubkey", get(get_transactions_by_account_jito_compat));
+    scoped_router(router, RouteScope::ReadWrite)
 }
 
 async fn get_recent_transactions_jito_compat(
@@ -437,7 +566,7 @@ async fn get_transaction_by_signature_internal(
 ) -> Result<TransactionData, ApiError> {
     if let Some(manager) = &state.transaction_data_manager {
         // Get transaction from manager
-        match manager.get_transaction(&signature).await {
+        match manager.get_transaction(&signature, Commitment::Confirmed).await {
             Ok(tx) => Ok(tx),
             Err(e) => Err(ApiError::InternalError(format!("Failed to fetch transaction: {}", e)))
         }
@@ -456,11 +585,8 @@ async fn get_transactions_by_program_internal(
 ) -> Result<Vec<TransactionData>, ApiError> {
     let limit = params.limit.unwrap_or(10).min(100);
     
-    if let Some(manager) = &state.transaction_data_manager {
-        let transactions = manager.get_transactions_by_program(&pubkey, limit).await
-            .map_err(|e| ApiError::InternalError(format!("Failed to fetch transactions: {}", e)))?;
-            
-        Ok(transactions)
+    if lists_transactions(&state) {
+        transactions_by_program(&state, &pubkey, limit).await
     } else {
         let mut transactions = Vec::new();
         for i in 0..limit {
@@ -472,6 +598,8 @@ async fn get_transactions_by_program_internal(
                 program_id: pubkey.clone(),
                 accounts: vec!["11111111111111111111111111111111".to_string()],
                 data: format!("instruction data {}", i),
+                depth: 1,
+                inner_instructions: Vec::new(),
             });
             
             transactions.push(tx);
@@ -487,11 +615,8 @@ async fn get_transactions_by_account_internal(
 ) -> Result<Vec<TransactionData>, ApiError> {
     let limit = params.limit.unwrap_or(10).min(100);
     
-    if let Some(manager) = &state.transaction_data_manager {
-        let transactions = manager.get_transactions_by_account(&pubkey, limit).await
-            .map_err(|e| ApiError::InternalError(format!("Failed to fetch transactions: {}", e)))?;
-            
-        Ok(transactions)
+    if lists_transactions(&state) {
+        transactions_by_account(&state, &pubkey, limit).await
     } else {
         let mut transactions = Vec::new();
         for i in 0..limit {
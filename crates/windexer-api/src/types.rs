@@ -1,9 +1,11 @@
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use axum::{response::IntoResponse, http::StatusCode, Json};
+use utoipa::ToSchema;
+use windexer_common::{ErrorCode, types::{Cursor, Page, MAX_PAGE_WINDOW}};
 
 /// API response wrapper
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 #[serde(untagged)]
 pub enum ApiResponse<T> {
     /// Successful response with data
@@ -44,7 +46,7 @@ impl<T> ApiResponse<T> {
 }
 
 /// API error types
-#[derive(Debug, thiserror::Error, Clone, Serialize, Deserialize)]
+#[derive(Debug, thiserror::Error, Clone, Serialize, Deserialize, ToSchema)]
 pub enum ApiError {
     #[error("Not found: {0}")]
     NotFound(String),
@@ -61,23 +63,77 @@ pub enum ApiError {
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
+    #[error("Rate limited: {0}")]
+    RateLimited(String),
+
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+
     // Add Internal Error variant for compatibility
     #[error("Internal server error: {0}")]
     InternalError(String),
 }
 
+impl ApiError {
+    /// Error of the given class, for the errors of other crates
+    pub fn from_code(code: ErrorCode, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match code {
+            ErrorCode::NotFound => ApiError::NotFound(message),
+            ErrorCode::InvalidArgument | ErrorCode::FailedPrecondition | ErrorCode::AlreadyExists => ApiError::BadRequest(message),
+            ErrorCode::Unauthenticated => ApiError::Unauthorized(message),
+            ErrorCode::PermissionDenied => ApiError::Forbidden(message),
+            ErrorCode::ResourceExhausted => ApiError::RateLimited(message),
+            ErrorCode::Unavailable | ErrorCode::Unimplemented => ApiError::Unavailable(message),
+            ErrorCode::Internal => ApiError::Internal(message),
+        }
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::BadRequest(_) => ErrorCode::InvalidArgument,
+            ApiError::Unauthorized(_) => ErrorCode::Unauthenticated,
+            ApiError::Forbidden(_) => ErrorCode::PermissionDenied,
+            ApiError::RateLimited(_) => ErrorCode::ResourceExhausted,
+            ApiError::Unavailable(_) => ErrorCode::Unavailable,
+            ApiError::Internal(_) | ApiError::InternalError(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// Error for a failed storage call, classed by the
+    /// [`StorageError`](windexer_store::StorageError) it carries
+    #[cfg(feature = "store")]
+    pub fn storage(error: anyhow::Error) -> Self {
+        Self::from_code(windexer_store::StorageError::code_of(&error), error.to_string())
+    }
+}
+
+impl From<windexer_common::Error> for ApiError {
+    fn from(error: windexer_common::Error) -> Self {
+        Self::from_code(error.code(), error.to_string())
+    }
+}
+
+#[cfg(feature = "store")]
+impl From<windexer_store::StorageError> for ApiError {
+    fn from(error: windexer_store::StorageError) -> Self {
+        Self::from_code(error.code(), error.to_string())
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl From<ApiError> for tonic::Status {
+    fn from(error: ApiError) -> Self {
+        tonic::Status::new(tonic::Code::from(error.code().grpc_code()), error.to_string())
+    }
+}
+
 /// Convert ApiError to HTTP response
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
-        let (status, error_message) = match &self {
-            ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-            ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
-            ApiError::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-        };
-        
+        let status = StatusCode::from_u16(self.code().http_status())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
         let body = Json(ApiResponse::<()>::error(self));
         
         (status, body).into_response()
@@ -85,7 +141,7 @@ impl IntoResponse for ApiError {
 }
 
 /// Status response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct StatusResponse {
     /// Service name
     pub name: String,
@@ -101,7 +157,7 @@ pub struct StatusResponse {
 }
 
 /// Health check response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HealthResponse {
     /// Overall health status
     pub status: HealthStatus,
@@ -112,7 +168,7 @@ pub struct HealthResponse {
 }
 
 /// Health status enum
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum HealthStatus {
     /// All systems operational
@@ -124,7 +180,7 @@ pub enum HealthStatus {
 }
 
 /// Individual health check result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HealthCheckResult {
     /// Check status
     pub status: HealthStatus,
@@ -137,7 +193,7 @@ pub struct HealthCheckResult {
 }
 
 /// Node information for status responses
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NodeInfo {
     /// Node ID
     pub node_id: String,
@@ -149,4 +205,53 @@ pub struct NodeInfo {
     pub peer_count: usize,
     /// Whether this node is a bootstrap node
     pub is_bootstrap: bool,
-}
\ No newline at end of file
+}
+/// A page of the newest items, resuming after an optional pagination cursor
+///
+/// `fetch(cursor, n)` returns up to `n` items newest first, starting at the
+/// newest item or at any item in a slot at least `cursor.slot`. The window is
+/// widened while items that arrived since the cursor was handed out crowd
+/// out the page, up to [`MAX_PAGE_WINDOW`] items.
+pub async fn recent_page<T, F, Fut>(
+    cursor: Option<&str>,
+    limit: usize,
+    slot_of: fn(&T) -> u64,
+    fetch: F,
+) -> Result<Page<T>, ApiError>
+where
+    F: Fn(Cursor, usize) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<T>, ApiError>>,
+{
+    let cursor = Cursor::decode_opt(cursor).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let mut window = usize::try_from(cursor.offset).ok()
+        .and_then(|offset| offset.checked_add(limit))
+        .and_then(|window| window.checked_add(1))
+        .filter(|window| *window <= MAX_PAGE_WINDOW)
+        .ok_or_else(|| ApiError::BadRequest(format!("Pages reach at most {} items back", MAX_PAGE_WINDOW)))?;
+
+    loop {
+        let mut items = fetch(cursor, window).await?;
+        items.sort_by_key(|item| std::cmp::Reverse(slot_of(item)));
+        let skip = cursor.recency_skip(&items, slot_of);
+        if items.len() < window || items.len() - skip > limit {
+            return Ok(Page::from_recency(items, &cursor, limit, slot_of));
+        }
+        if window == MAX_PAGE_WINDOW {
+            return Err(ApiError::BadRequest("Cursor is too far behind the newest items".to_string()));
+        }
+        window = window.saturating_mul(2).min(MAX_PAGE_WINDOW);
+    }
+}
+
+/// `?commitment=` for point lookups; finalized when omitted, as in Solana RPC
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CommitmentParams {
+    pub commitment: Option<windexer_common::types::Commitment>,
+}
+
+impl CommitmentParams {
+    pub fn level(&self) -> windexer_common::types::Commitment {
+        self.commitment.unwrap_or(windexer_common::types::Commitment::Finalized)
+    }
+}
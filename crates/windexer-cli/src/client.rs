@@ -0,0 +1,90 @@
+// crates/windexer-cli/src/client.rs
+
+//! Calls to a node's REST API
+//!
+//! Every route answers `{"success": true, "data": ...}` or
+//! `{"success": false, "error": {"<Kind>": "<message>"}}`; [`ApiClient`]
+//! returns the data or turns the error into one.
+
+use {
+    anyhow::{anyhow, Context, Result},
+    reqwest::{Method, RequestBuilder},
+    serde::Serialize,
+    serde_json::Value,
+};
+
+pub struct ApiClient {
+    http: reqwest::Client,
+    /// Base URL with the API prefix, like `http://localhost:3000/api`
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(key) => request.header("X-API-Key", key),
+            None => request,
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Value> {
+        self.send(self.request(Method::GET, path), path).await
+    }
+
+    pub async fn post<T: Serialize + ?Sized>(&self, path: &str, body: &T) -> Result<Value> {
+        self.send(self.request(Method::POST, path).json(body), path).await
+    }
+
+    async fn send(&self, request: RequestBuilder, path: &str) -> Result<Value> {
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}{}", self.base_url, path))?;
+        let status = response.status();
+        let body = response.text().await?;
+        let reply: Value = serde_json::from_str(&body)
+            .map_err(|_| anyhow!("{} answered {} with: {}", path, status, body.trim()))?;
+        unwrap_reply(reply).map_err(|e| anyhow!("{} answered {}: {}", path, status, e))
+    }
+}
+
+/// The data of a reply, or its error as a message
+fn unwrap_reply(mut reply: Value) -> std::result::Result<Value, String> {
+    if reply["success"].as_bool() == Some(true) {
+        return Ok(reply["data"].take());
+    }
+    match reply.get("error") {
+        // Errors are serialized as `{"NotFound": "message"}`
+        Some(Value::Object(error)) => Err(error
+            .iter()
+            .map(|(kind, message)| format!("{}: {}", kind, message.as_str().unwrap_or(&message.to_string())))
+            .collect::<Vec<_>>()
+            .join(", ")),
+        Some(error) => Err(error.to_string()),
+        None => Err(reply.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn unwraps_replies() {
+        assert_eq!(unwrap_reply(json!({"success": true, "data": {"slot": 1}})), Ok(json!({"slot": 1})));
+        assert_eq!(
+            unwrap_reply(json!({"success": false, "error": {"NotFound": "Account not found"}})),
+            Err("NotFound: Account not found".to_string())
+        );
+    }
+}
@@ -0,0 +1,209 @@
+// crates/windexer-cli/src/main.rs
+
+//! Command line client for a wIndexer node
+//!
+//! Queries a node and runs its admin tasks through its REST API, so routine
+//! operations don't need curl and jq. Admin commands need an API key with
+//! the `admin` scope, passed with `--api-key` or `WINDEXER_API_KEY`.
+
+mod client;
+mod output;
+
+use {
+    anyhow::Result,
+    clap::{Parser, Subcommand, ValueEnum},
+    client::ApiClient,
+    output::{print_json, print_list, Column},
+    serde_json::{json, Value},
+};
+
+#[derive(Parser, Debug)]
+#[command(name = "windexer-cli", version, about = "Query and administer a wIndexer node")]
+struct Args {
+    /// Base URL of the node's API, with its path prefix
+    #[arg(long, env = "WINDEXER_URL", default_value = "http://localhost:3000/api", global = true)]
+    url: String,
+
+    /// API key, sent in the `X-API-Key` header
+    #[arg(long, env = "WINDEXER_API_KEY", hide_env_values = true, global = true)]
+    api_key: Option<String>,
+
+    /// Print replies as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Look up indexed data
+    #[command(subcommand)]
+    Query(QueryCommand),
+    /// Blocks
+    #[command(subcommand)]
+    Blocks(BlocksCommand),
+    /// Peers of the node's network
+    #[command(subcommand)]
+    Peers(PeersCommand),
+    /// Backfills of historical slots; needs the `admin` scope
+    #[command(subcommand)]
+    Backfill(BackfillCommand),
+    /// The node's store; needs the `admin` scope
+    #[command(subcommand)]
+    Store(StoreCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum QueryCommand {
+    /// An account's latest state
+    Account {
+        pubkey: String,
+        #[arg(long, value_enum)]
+        commitment: Option<Commitment>,
+    },
+    /// A transaction
+    Tx {
+        signature: String,
+        #[arg(long, value_enum)]
+        commitment: Option<Commitment>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BlocksCommand {
+    /// The latest blocks, newest first
+    Recent {
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PeersCommand {
+    /// Known peers, most recently seen first
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+enum BackfillCommand {
+    /// Start backfilling a slot range
+    Start {
+        /// First slot; defaults to the slot after the store's last processed slot
+        #[arg(long)]
+        from: Option<u64>,
+        /// Last slot; defaults to the latest finalized slot
+        #[arg(long)]
+        to: Option<u64>,
+        /// Leave vote transactions out
+        #[arg(long)]
+        skip_votes: bool,
+    },
+    /// Backfills started since the node started
+    List,
+    /// Stop a backfill after its current batch
+    Stop { id: u64 },
+}
+
+#[derive(Subcommand, Debug)]
+enum StoreCommand {
+    /// Indexing progress and the latest slot per commitment
+    Stats,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    fn query(commitment: Option<Self>) -> String {
+        match commitment {
+            Some(commitment) => format!("?commitment={}", commitment.to_possible_value().unwrap().get_name()),
+            None => String::new(),
+        }
+    }
+}
+
+const BLOCK_COLUMNS: &[Column] = &[
+    ("SLOT", "/slot"),
+    ("STATUS", "/status"),
+    ("TXS", "/transaction_count"),
+    ("TIME", "/timestamp"),
+    ("BLOCKHASH", "/blockhash"),
+];
+
+const PEER_COLUMNS: &[Column] = &[
+    ("PEER", "/peer_id"),
+    ("CONNECTED", "/connected"),
+    ("LAST SEEN", "/last_seen"),
+    ("LATENCY MS", "/latency_ms"),
+    ("STAKE", "/stake"),
+    ("VERSION", "/protocol_version"),
+];
+
+const BACKFILL_COLUMNS: &[Column] = &[
+    ("ID", "/id"),
+    ("STATE", "/state"),
+    ("FROM", "/start_slot"),
+    ("TO", "/end_slot"),
+    ("STARTED", "/started_at"),
+    ("ERROR", "/error"),
+];
+
+fn items(value: &Value) -> &[Value] {
+    // Paged routes answer `{"items": [...], "next_cursor": ...}`
+    value
+        .get("items")
+        .unwrap_or(value)
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+async fn run(args: Args) -> Result<()> {
+    let client = ApiClient::new(&args.url, args.api_key);
+    let json = args.json;
+    match args.command {
+        Command::Query(QueryCommand::Account { pubkey, commitment }) => {
+            print_json(&client.get(&format!("/account/{}{}", pubkey, Commitment::query(commitment))).await?);
+        }
+        Command::Query(QueryCommand::Tx { signature, commitment }) => {
+            print_json(&client.get(&format!("/transaction/{}{}", signature, Commitment::query(commitment))).await?);
+        }
+        Command::Blocks(BlocksCommand::Recent { limit }) => {
+            let page = client.get(&format!("/blocks?limit={}", limit)).await?;
+            print_list(items(&page), BLOCK_COLUMNS, json);
+        }
+        Command::Peers(PeersCommand::List) => {
+            let peers = client.get("/peers").await?;
+            print_list(items(&peers), PEER_COLUMNS, json);
+        }
+        Command::Backfill(BackfillCommand::Start { from, to, skip_votes }) => {
+            let request = json!({"start_slot": from, "end_slot": to, "skip_votes": skip_votes});
+            print_json(&client.post("/admin/backfills", &request).await?);
+        }
+        Command::Backfill(BackfillCommand::List) => {
+            let jobs = client.get("/admin/backfills").await?;
+            print_list(items(&jobs), BACKFILL_COLUMNS, json);
+        }
+        Command::Backfill(BackfillCommand::Stop { id }) => {
+            print_json(&client.post(&format!("/admin/backfills/{}/stop", id), &json!({})).await?);
+        }
+        Command::Store(StoreCommand::Stats) => {
+            print_json(&client.get("/admin/store").await?);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Args::parse()).await {
+        eprintln!("Error: {:#}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,71 @@
+// crates/windexer-cli/src/output.rs
+
+//! Printing replies
+//!
+//! Lists print as tables of their main fields and single records as
+//! indented JSON; `--json` prints every reply as JSON for scripts.
+
+use serde_json::Value;
+
+/// A table column: its header and the JSON pointer of its field in a row
+pub type Column = (&'static str, &'static str);
+
+pub fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string()));
+}
+
+/// `rows` as a table of `columns`, or as JSON when `json` is set
+pub fn print_list(rows: &[Value], columns: &[Column], json: bool) {
+    if json {
+        print_json(&Value::Array(rows.to_vec()));
+        return;
+    }
+    if rows.is_empty() {
+        println!("(none)");
+        return;
+    }
+    print!("{}", table(rows, columns));
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "-".to_string(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(items)) => items.iter().map(|item| cell(Some(item))).collect::<Vec<_>>().join(","),
+        Some(other) => other.to_string(),
+    }
+}
+
+fn table(rows: &[Value], columns: &[Column]) -> String {
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|(_, pointer)| cell(row.pointer(pointer))).collect())
+        .collect();
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (header, _))| cells.iter().map(|row| row[i].len()).max().unwrap_or(0).max(header.len()))
+        .collect();
+
+    let line = |values: Vec<&str>| {
+        let padded: Vec<String> = values.iter().zip(&widths).map(|(value, width)| format!("{:<width$}", value)).collect();
+        format!("{}\n", padded.join("  ").trim_end())
+    };
+    let mut out = line(columns.iter().map(|(header, _)| *header).collect());
+    for row in &cells {
+        out.push_str(&line(row.iter().map(String::as_str).collect()));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn aligns_columns() {
+        let rows = [json!({"slot": 12, "hash": "abc"}), json!({"slot": 7, "hash": null})];
+        let out = table(&rows, &[("SLOT", "/slot"), ("HASH", "/hash")]);
+        assert_eq!(out, "SLOT  HASH\n12    abc\n7     -\n");
+    }
+}
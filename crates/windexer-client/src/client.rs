@@ -0,0 +1,187 @@
+// crates/windexer-client/src/client.rs
+
+//! Typed calls to a node's REST API
+//!
+//! Every route answers `{"success": true, "data": ...}` or
+//! `{"success": false, "error": {"<Kind>": "<message>"}}`; the data is
+//! decoded into the route's type and errors become [`ClientError::Api`].
+
+use {
+    crate::{
+        error::{ClientError, Result},
+        types::{Account, Block, Commitment, Page, Peer, StoreStats, Transaction},
+    },
+    reqwest::{Method, RequestBuilder, Url},
+    serde::de::DeserializeOwned,
+    serde_json::Value,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::types::{EpochInfo, OperatorDetail, OperatorStatus, PerformanceRecord, RewardBalance};
+
+#[derive(Clone)]
+pub struct WindexerClient {
+    http: reqwest::Client,
+    /// Base URL with the API prefix, like `http://localhost:3000/api`
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl WindexerClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: None,
+        }
+    }
+
+    /// Send `key` with every request, for nodes that require API keys
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    pub async fn account(&self, pubkey: &str, commitment: Option<Commitment>) -> Result<Account> {
+        self.get(&format!("/account/{}", pubkey), &commitment_query(commitment)).await
+    }
+
+    pub async fn transaction(&self, signature: &str, commitment: Option<Commitment>) -> Result<Transaction> {
+        self.get(&format!("/transaction/{}", signature), &commitment_query(commitment)).await
+    }
+
+    /// Most recent blocks first; pass a page's `next_cursor` to continue it
+    pub async fn recent_blocks(&self, limit: usize, cursor: Option<&str>) -> Result<Page<Block>> {
+        let limit = limit.to_string();
+        let mut query = vec![("limit", limit.as_str())];
+        query.extend(cursor.map(|cursor| ("cursor", cursor)));
+        self.get("/blocks", &query).await
+    }
+
+    pub async fn block(&self, slot: u64) -> Result<Block> {
+        self.get(&format!("/blocks/{}", slot), &[]).await
+    }
+
+    /// Peers of the node's network, most recently seen first
+    pub async fn peers(&self) -> Result<Vec<Peer>> {
+        self.get("/peers", &[]).await
+    }
+
+    /// Needs a key with the `admin` scope
+    pub async fn store_stats(&self) -> Result<StoreStats> {
+        self.get("/admin/store", &[]).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.api_key {
+            Some(key) => request.header("X-API-Key", key),
+            None => request,
+        }
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T> {
+        self.send(self.request(Method::GET, path).query(query), path).await
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder, path: &str) -> Result<T> {
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        decode_reply(path, status, &body)
+    }
+
+    /// WebSocket URL of `path` with `query`. Browsers can't set headers on
+    /// a WebSocket, so the API key goes in the query.
+    pub(crate) fn websocket_url(&self, path: &str, query: &[(&str, &str)]) -> Result<Url> {
+        let invalid = |e: String| ClientError::InvalidUrl(format!("{}{}: {}", self.base_url, path, e));
+        let mut url = Url::parse(&format!("{}{}", self.base_url, path)).map_err(|e| invalid(e.to_string()))?;
+        let scheme = if url.scheme() == "https" { "wss" } else { "ws" };
+        url.set_scheme(scheme).map_err(|_| invalid(format!("cannot switch to {}", scheme)))?;
+
+        let mut pairs: Vec<(&str, &str)> = query.to_vec();
+        pairs.extend(self.api_key.as_deref().map(|key| ("api_key", key)));
+        if !pairs.is_empty() {
+            url.query_pairs_mut().extend_pairs(pairs);
+        }
+        Ok(url)
+    }
+}
+
+/// Staking routes; their types come from `windexer-common`, which doesn't
+/// build for `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+impl WindexerClient {
+    /// Operators, largest stake first
+    pub async fn operators(&self) -> Result<Vec<OperatorStatus>> {
+        self.get("/staking/operators", &[]).await
+    }
+
+    pub async fn operator(&self, pubkey: &str) -> Result<OperatorDetail> {
+        self.get(&format!("/staking/operators/{}", pubkey), &[]).await
+    }
+
+    /// Performance in every epoch closed within `window`, like `7d`, oldest
+    /// first; the node's default window when `None`
+    pub async fn operator_performance(&self, pubkey: &str, window: Option<&str>) -> Result<Vec<PerformanceRecord>> {
+        let query: Vec<_> = window.map(|window| ("window", window)).into_iter().collect();
+        self.get(&format!("/staking/operators/{}/performance", pubkey), &query).await
+    }
+
+    pub async fn rewards(&self, pubkey: &str) -> Result<RewardBalance> {
+        self.get(&format!("/staking/rewards/{}", pubkey), &[]).await
+    }
+
+    pub async fn epoch(&self) -> Result<EpochInfo> {
+        self.get("/staking/epoch", &[]).await
+    }
+}
+
+fn commitment_query(commitment: Option<Commitment>) -> Vec<(&'static str, &'static str)> {
+    commitment.map(|commitment| ("commitment", commitment.as_str())).into_iter().collect()
+}
+
+/// The data of a reply, decoded, or its error
+fn decode_reply<T: DeserializeOwned>(path: &str, status: u16, body: &str) -> Result<T> {
+    let mut reply: Value = serde_json::from_str(body)
+        .map_err(|_| ClientError::Decode(format!("{} answered {} with: {}", path, status, body.trim())))?;
+    if reply["success"].as_bool() == Some(true) {
+        return serde_json::from_value(reply["data"].take())
+            .map_err(|e| ClientError::Decode(format!("{}: {}", path, e)));
+    }
+
+    // Errors are serialized as `{"NotFound": "message"}`
+    let (kind, message) = match reply.get("error") {
+        Some(Value::Object(error)) if !error.is_empty() => {
+            let (kind, message) = error.iter().next().unwrap();
+            (kind.clone(), message.as_str().map(str::to_string).unwrap_or_else(|| message.to_string()))
+        }
+        Some(error) => ("Error".to_string(), error.to_string()),
+        None => ("Error".to_string(), reply.to_string()),
+    };
+    Err(ClientError::Api { path: path.to_string(), status, kind, message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_replies() {
+        let peers: Vec<String> = decode_reply("/x", 200, r#"{"success": true, "data": ["a"]}"#).unwrap();
+        assert_eq!(peers, ["a"]);
+
+        let error = decode_reply::<Account>("/account/a", 404, r#"{"success": false, "error": {"NotFound": "Account not found"}}"#);
+        assert!(matches!(
+            error,
+            Err(ClientError::Api { status: 404, ref kind, ref message, .. }) if kind == "NotFound" && message == "Account not found"
+        ));
+    }
+
+    #[test]
+    fn builds_websocket_urls() {
+        let client = WindexerClient::new("https://indexer.example.com/api/").with_api_key("k");
+        let url = client.websocket_url("/ws/accounts", &[("pubkeys", "a,b")]).unwrap();
+        assert_eq!(url.as_str(), "wss://indexer.example.com/api/ws/accounts?pubkeys=a%2Cb&api_key=k");
+    }
+}
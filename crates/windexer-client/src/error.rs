@@ -0,0 +1,30 @@
+// crates/windexer-client/src/error.rs
+
+//! Client errors
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Invalid URL {0}")]
+    InvalidUrl(String),
+
+    /// The node answered with an error, like `NotFound` or `BadRequest`
+    #[error("{path} answered {status}: {kind}: {message}")]
+    Api { path: String, status: u16, kind: String, message: String },
+
+    #[error("Unexpected reply: {0}")]
+    Decode(String),
+
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
+    /// The node closed a subscription, like when it fell behind the feed
+    #[error("Subscription ended by the node: {0}")]
+    Subscription(String),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
@@ -0,0 +1,23 @@
+// crates/windexer-client/src/lib.rs
+
+//! Rust client for a wIndexer node
+//!
+//! [`WindexerClient`] wraps the node's REST API with typed methods and its
+//! WebSockets with [`Subscription`] streams of accounts, transactions and
+//! blocks. It builds for native targets and for `wasm32`, where requests
+//! go through the browser's `fetch` and WebSocket; the staking methods are
+//! native only, see [`types`].
+//!
+//! The gRPC API isn't wrapped here; clients of it can generate stubs from
+//! `crates/windexer-api/proto/geyser.proto`.
+
+mod client;
+mod error;
+mod stream;
+pub mod types;
+
+pub use {
+    client::WindexerClient,
+    error::{ClientError, Result},
+    stream::Subscription,
+};
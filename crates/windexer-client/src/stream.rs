@@ -0,0 +1,251 @@
+// crates/windexer-client/src/stream.rs
+
+//! Subscriptions over the API's WebSockets
+//!
+//! The node sends every update as a JSON text message. A subscriber that
+//! falls behind the feed is sent `{"error": "..."}` and disconnected, which
+//! ends the [`Subscription`] after a [`ClientError::Subscription`]; open a
+//! new one to resume.
+//!
+//! With [`AccountFilter::diff`] the node sends large accounts as patches of
+//! the version it sent before; the subscription keeps the last version of
+//! those accounts and yields them patched, in full.
+
+use {
+    crate::{
+        error::{ClientError, Result},
+        types::{Account, AccountFilter, Block, Transaction, TransactionFilter},
+        WindexerClient,
+    },
+    futures::{ready, Stream, StreamExt},
+    reqwest::Url,
+    serde::{de::DeserializeOwned, Deserialize},
+    serde_json::Value,
+    std::{
+        collections::HashMap,
+        pin::Pin,
+        task::{Context, Poll},
+    },
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+type Messages = futures::stream::BoxStream<'static, Result<String>>;
+#[cfg(not(target_arch = "wasm32"))]
+type Decode<T> = Box<dyn FnMut(&str) -> Result<T> + Send>;
+// Browser WebSockets aren't `Send`
+#[cfg(target_arch = "wasm32")]
+type Messages = futures::stream::LocalBoxStream<'static, Result<String>>;
+#[cfg(target_arch = "wasm32")]
+type Decode<T> = Box<dyn FnMut(&str) -> Result<T>>;
+
+/// Updates of one subscription, until the node or the connection ends it
+pub struct Subscription<T> {
+    messages: Messages,
+    decode: Decode<T>,
+    ended: bool,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.ended {
+            return Poll::Ready(None);
+        }
+        let update = match ready!(this.messages.poll_next_unpin(cx)) {
+            Some(Ok(text)) => (this.decode)(&text),
+            Some(Err(e)) => Err(e),
+            None => return Poll::Ready(None),
+        };
+        this.ended = matches!(update, Err(ClientError::Subscription(_) | ClientError::WebSocket(_)));
+        Poll::Ready(Some(update))
+    }
+}
+
+impl WindexerClient {
+    /// Account updates matching `filter`
+    pub async fn subscribe_accounts(&self, filter: &AccountFilter) -> Result<Subscription<Account>> {
+        let pubkeys = filter.pubkeys.join(",");
+        let mut query = Vec::new();
+        query.extend(filter.program.as_deref().map(|program| ("program", program)));
+        if !pubkeys.is_empty() {
+            query.push(("pubkeys", pubkeys.as_str()));
+        }
+        query.extend(filter.filter.as_deref().map(|expression| ("filter", expression)));
+        query.extend(filter.commitment.map(|commitment| ("commitment", commitment.as_str())));
+        if !filter.diff {
+            return self.subscribe("/ws/accounts", &query, Box::new(parse_update)).await;
+        }
+
+        query.push(("diff", "true"));
+        let mut accounts = HashMap::new();
+        self.subscribe("/ws/accounts", &query, Box::new(move |text: &str| assemble(&mut accounts, parse_update(text)?))).await
+    }
+
+    /// Transactions matching `filter`
+    pub async fn subscribe_transactions(&self, filter: &TransactionFilter) -> Result<Subscription<Transaction>> {
+        let mut query = Vec::new();
+        query.extend(filter.program.as_deref().map(|program| ("program", program)));
+        query.extend(filter.account.as_deref().map(|account| ("account", account)));
+        query.extend(filter.filter.as_deref().map(|expression| ("filter", expression)));
+        query.extend(filter.commitment.map(|commitment| ("commitment", commitment.as_str())));
+        self.subscribe("/ws/transactions", &query, Box::new(parse_update)).await
+    }
+
+    /// Every new block
+    pub async fn subscribe_blocks(&self) -> Result<Subscription<Block>> {
+        self.subscribe("/ws/blocks", &[], Box::new(parse_update)).await
+    }
+
+    async fn subscribe<T>(&self, path: &str, query: &[(&str, &str)], decode: Decode<T>) -> Result<Subscription<T>> {
+        Ok(Subscription {
+            messages: connect(self.websocket_url(path, query)?).await?,
+            decode,
+            ended: false,
+        })
+    }
+}
+
+/// Message of a `diff` account subscription
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AccountUpdate {
+    /// Sent in full and not patched
+    Account(Account),
+    /// Sent in full, and patched by later messages
+    Snapshot(Account),
+    Patch(AccountPatch),
+}
+
+#[derive(Deserialize)]
+struct AccountPatch {
+    pubkey: String,
+    lamports: u64,
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+    slot: u64,
+    updated_at: i64,
+    data_len: usize,
+    patches: Vec<DataPatch>,
+}
+
+#[derive(Deserialize)]
+struct DataPatch {
+    offset: usize,
+    data: Vec<u8>,
+}
+
+/// The account `update` brings to its latest version, given the versions of
+/// `accounts` the node sends patches of
+fn assemble(accounts: &mut HashMap<String, Account>, update: AccountUpdate) -> Result<Account> {
+    let patch = match update {
+        AccountUpdate::Account(account) => {
+            accounts.remove(&account.pubkey);
+            return Ok(account);
+        }
+        AccountUpdate::Snapshot(account) => {
+            accounts.insert(account.pubkey.clone(), account.clone());
+            return Ok(account);
+        }
+        AccountUpdate::Patch(patch) => patch,
+    };
+
+    let account = accounts
+        .get_mut(&patch.pubkey)
+        .ok_or_else(|| ClientError::Decode(format!("Patch of {} before its snapshot", patch.pubkey)))?;
+    account.data.resize(patch.data_len, 0);
+    for DataPatch { offset, data } in patch.patches {
+        let range = account.data.get_mut(offset..offset + data.len()).ok_or_else(|| {
+            ClientError::Decode(format!("Patch of {} is past the end of its data", patch.pubkey))
+        })?;
+        range.copy_from_slice(&data);
+    }
+    account.lamports = patch.lamports;
+    account.owner = patch.owner;
+    account.executable = patch.executable;
+    account.rent_epoch = patch.rent_epoch;
+    account.slot = patch.slot;
+    account.updated_at = patch.updated_at;
+    // Only sent with snapshots, and out of date once patched
+    account.data_base64 = None;
+    Ok(account.clone())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn connect(url: Url) -> Result<Messages> {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let (socket, _) = tokio_tungstenite::connect_async(url.as_str())
+        .await
+        .map_err(|e| ClientError::WebSocket(format!("Failed to connect to {}: {}", url, e)))?;
+    Ok(socket
+        .filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(Ok(text)),
+                Ok(_) => None,
+                Err(e) => Some(Err(ClientError::WebSocket(e.to_string()))),
+            }
+        })
+        .boxed())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn connect(url: Url) -> Result<Messages> {
+    use gloo_net::websocket::{futures::WebSocket, Message};
+
+    let socket = WebSocket::open(url.as_str())
+        .map_err(|e| ClientError::WebSocket(format!("Failed to connect to {}: {}", url, e)))?;
+    Ok(socket
+        .filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(Ok(text)),
+                Ok(Message::Bytes(_)) => None,
+                Err(e) => Some(Err(ClientError::WebSocket(e.to_string()))),
+            }
+        })
+        .boxed_local())
+}
+
+/// An update, or the error the node closed the subscription with
+fn parse_update<T: DeserializeOwned>(text: &str) -> Result<T> {
+    let update: Value = serde_json::from_str(text).map_err(|e| ClientError::Decode(format!("{}: {}", e, text)))?;
+    let error = update.as_object().filter(|update| update.len() == 1).and_then(|update| update.get("error"));
+    if let Some(error) = error {
+        return Err(ClientError::Subscription(error.as_str().map(str::to_string).unwrap_or_else(|| error.to_string())));
+    }
+    serde_json::from_value(update).map_err(|e| ClientError::Decode(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_updates_and_errors() {
+        let block: Block = parse_update(
+            r#"{"slot": 2, "parent_slot": 1, "blockhash": "b", "previous_blockhash": "a", "block_time": null,
+                "block_height": 2, "transaction_count": 3, "leader": "", "rewards": null}"#,
+        )
+        .unwrap();
+        assert_eq!((block.slot, block.transaction_count), (2, 3));
+
+        let lagged = parse_update::<Block>(r#"{"error": "Subscriber fell behind and missed 4 updates"}"#);
+        assert!(matches!(lagged, Err(ClientError::Subscription(message)) if message.contains("missed 4")));
+    }
+
+    #[test]
+    fn assembles_patched_accounts() {
+        let mut accounts = HashMap::new();
+        let snapshot = r#"{"type": "snapshot", "pubkey": "a", "lamports": 1, "owner": "o", "executable": false,
+            "rent_epoch": 0, "data": [0, 0, 0, 0], "data_base64": "AAAAAA==", "slot": 1, "updated_at": 10}"#;
+        let patch = r#"{"type": "patch", "pubkey": "a", "lamports": 2, "owner": "o", "executable": false,
+            "rent_epoch": 0, "slot": 2, "updated_at": 20, "data_len": 5, "patches": [{"offset": 1, "data": [7]}, {"offset": 4, "data": [9]}]}"#;
+
+        assert!(assemble(&mut accounts, parse_update(patch).unwrap()).is_err());
+        assemble(&mut accounts, parse_update(snapshot).unwrap()).unwrap();
+        let account = assemble(&mut accounts, parse_update(patch).unwrap()).unwrap();
+        assert_eq!((account.data, account.lamports, account.data_base64), (vec![0, 7, 0, 0, 9], 2, None));
+    }
+}
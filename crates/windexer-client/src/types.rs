@@ -0,0 +1,162 @@
+// crates/windexer-client/src/types.rs
+
+//! Records served by the API
+//!
+//! Accounts, transactions, blocks and peers mirror the API's JSON, with keys
+//! and hashes as base58 strings. Types the API shares with the rest of
+//! wIndexer are re-exported from `windexer-common` on native targets; it
+//! doesn't build for `wasm32`, so [`Commitment`] and [`Page`] are declared
+//! here with the same JSON there, and the staking types are native only.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use windexer_common::{
+    control::{EpochInfo, OperatorDetail, OperatorStatus, RewardBalance, StakeDelegation},
+    types::{Commitment, Page, PerformanceRecord, SlashRecord, SlotAttestation},
+};
+
+#[cfg(target_arch = "wasm32")]
+pub use self::wasm::{Commitment, Page};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: Vec<u8>,
+    pub data_base64: Option<String>,
+    pub slot: u64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Instruction {
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    /// Base58 encoded
+    pub data: String,
+    /// Invocation depth, 1 for top-level instructions
+    #[serde(default)]
+    pub depth: u32,
+    /// Instructions invoked by this one, in execution order
+    #[serde(default)]
+    pub inner_instructions: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub err: Option<serde_json::Value>,
+    pub fee: u64,
+    pub recent_blockhash: String,
+    pub program_ids: Vec<String>,
+    pub accounts: Vec<String>,
+    pub logs: Option<Vec<String>>,
+    pub instructions: Vec<Instruction>,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reward {
+    pub pubkey: String,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub blockhash: String,
+    pub previous_blockhash: String,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub transaction_count: u64,
+    pub leader: String,
+    pub rewards: Option<Vec<Reward>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Peer {
+    pub peer_id: String,
+    pub connected: bool,
+    /// Unix timestamp in seconds
+    pub last_seen: i64,
+    pub topics: Vec<String>,
+    pub protocol_version: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub stake: Option<u64>,
+}
+
+/// Progress of the node's store, served at `/admin/store`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreStats {
+    pub last_processed_slot: u64,
+    pub total_accounts: u64,
+    pub total_transactions: u64,
+    pub processed_slot: Option<u64>,
+    pub confirmed_slot: Option<u64>,
+    pub finalized_slot: Option<u64>,
+}
+
+/// Accounts to stream; with neither set every account update is sent
+#[derive(Debug, Clone, Default)]
+pub struct AccountFilter {
+    /// Accounts owned by this program
+    pub program: Option<String>,
+    pub pubkeys: Vec<String>,
+    /// Filter expression, e.g. `owner:{program_id}; size:165`
+    pub filter: Option<String>,
+    pub commitment: Option<Commitment>,
+    /// Have the node send large accounts as patches of their changed bytes;
+    /// the subscription still yields them in full
+    pub diff: bool,
+}
+
+/// Transactions to stream; with neither set every transaction is sent
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    /// Transactions invoking this program
+    pub program: Option<String>,
+    /// Transactions referencing this account
+    pub account: Option<String>,
+    /// Filter expression, e.g. `!vote; status:success`
+    pub filter: Option<String>,
+    pub commitment: Option<Commitment>,
+}
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum Commitment {
+        #[default]
+        Processed,
+        Confirmed,
+        Finalized,
+    }
+
+    impl Commitment {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Commitment::Processed => "processed",
+                Commitment::Confirmed => "confirmed",
+                Commitment::Finalized => "finalized",
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Page<T> {
+        pub items: Vec<T>,
+        pub next_cursor: Option<String>,
+    }
+}
@@ -0,0 +1,152 @@
+//! Operational alerts
+//!
+//! Monitors publish [`Alert`]s to an [`AlertBus`], which the API streams to
+//! clients and [`spawn_webhooks`] posts as JSON to operator endpoints. Like
+//! the [`DataFeed`](crate::feed::DataFeed), publishing never blocks and
+//! subscribers that fall behind miss alerts.
+//!
+//! Every attempt at delivering an alert to a webhook carries the same
+//! `Idempotency-Key` header, so an endpoint that got the alert but failed to
+//! answer can drop the retries.
+
+use {
+    crate::{shutdown::{self, ShutdownToken}, utils::current_timestamp},
+    serde::{Deserialize, Serialize},
+    std::time::Duration,
+    tokio::{sync::broadcast, task::JoinHandle},
+    tracing::warn,
+};
+
+/// Alerts buffered for slow subscribers
+pub const ALERT_CAPACITY: usize = 256;
+
+/// Attempts to deliver an alert to one webhook
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// What an alert is about
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertKind {
+    /// The indexed data trails the chain tip by more than the threshold
+    SlotLag { lag: u64, local_slot: u64, tip_slot: u64, threshold: u64 },
+    /// The indexed data caught up again
+    SlotLagRecovered { lag: u64, local_slot: u64, tip_slot: u64, threshold: u64 },
+    /// An operator's locally tracked stake differs from its on-chain stake
+    StakeMismatch { operator: String, local_stake: u64, onchain_stake: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alert {
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub kind: AlertKind,
+    pub message: String,
+    /// Unix time the alert was raised
+    pub timestamp: i64,
+}
+
+impl Alert {
+    pub fn new(severity: Severity, kind: AlertKind, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            kind,
+            message: message.into(),
+            timestamp: current_timestamp(),
+        }
+    }
+
+    /// Hash of the alert's contents, the same for every delivery of it
+    pub fn idempotency_key(&self) -> String {
+        let contents = serde_json::to_vec(self).unwrap_or_default();
+        blake3::hash(&contents).to_hex().to_string()
+    }
+}
+
+/// Broadcast bus of alerts
+#[derive(Debug, Clone)]
+pub struct AlertBus {
+    sender: broadcast::Sender<Alert>,
+}
+
+impl Default for AlertBus {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(ALERT_CAPACITY).0,
+        }
+    }
+}
+
+impl AlertBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish an alert, logging it as well; dropped when nobody is subscribed
+    pub fn publish(&self, alert: Alert) {
+        match alert.severity {
+            Severity::Info => tracing::info!("{}", alert.message),
+            Severity::Warning | Severity::Critical => warn!("{}", alert.message),
+        }
+        let _ = self.sender.send(alert);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Alert> {
+        self.sender.subscribe()
+    }
+}
+
+/// Post every alert of `bus` as JSON to each of `urls`, retrying failed
+/// deliveries with backoff, until `shutdown` is cancelled
+pub fn spawn_webhooks(bus: &AlertBus, urls: Vec<String>, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+    let mut alerts = bus.subscribe();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+        .unwrap_or_default();
+
+    tokio::spawn(async move {
+        loop {
+            let alert = tokio::select! {
+                alert = alerts.recv() => alert,
+                _ = shutdown::cancelled(shutdown.as_ref()) => return,
+            };
+            let alert = match alert {
+                Ok(alert) => alert,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Alert webhooks fell behind and dropped {} alerts", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            for url in &urls {
+                deliver(&client, url, &alert).await;
+            }
+        }
+    })
+}
+
+async fn deliver(client: &reqwest::Client, url: &str, alert: &Alert) {
+    let mut backoff = Duration::from_millis(500);
+    let key = alert.idempotency_key();
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        match client.post(url).header(IDEMPOTENCY_KEY_HEADER, &key).json(alert).send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => warn!("Alert webhook {} answered {} (attempt {})", url, response.status(), attempt),
+            Err(e) => warn!("Alert webhook {} failed: {} (attempt {})", url, e, attempt),
+        }
+        if attempt < WEBHOOK_ATTEMPTS {
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
@@ -0,0 +1,330 @@
+// crates/windexer-common/src/config/loader.rs
+
+//! Layered configuration loading
+//!
+//! Every binary builds its settings the same way, each layer overriding the
+//! one before:
+//!
+//! 1. the settings type's `Default`
+//! 2. a configuration file, TOML, JSON or YAML by extension
+//! 3. environment variables named `WINDEXER_<KEY>`, with `__` between
+//!    nested keys, e.g. `WINDEXER_CONNECTIONS__MAX_INBOUND=50`
+//! 4. command line flags
+//!
+//! Environment values are parsed as JSON where the setting is not a string,
+//! and comma separated lists fill list settings. Variables naming no known
+//! setting are ignored. The merged result is deserialized and checked with
+//! [`Validate`].
+
+use {
+    crate::errors::{Error, Result},
+    serde::{de::DeserializeOwned, Serialize},
+    serde_json::{Map, Value},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+pub const ENV_PREFIX: &str = "WINDEXER";
+
+/// Checks run on settings once every layer is applied
+pub trait Validate {
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::Config(message.into())
+}
+
+/// Reads a configuration file into a JSON tree
+pub fn read_file(path: &Path) -> Result<Value> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| invalid(format!("Failed to read {}: {}", path.display(), e)))?;
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    match extension {
+        "toml" => toml::from_str(&contents).map_err(|e| invalid(format!("Invalid TOML in {}: {}", path.display(), e))),
+        "json" => serde_json::from_str(&contents).map_err(|e| invalid(format!("Invalid JSON in {}: {}", path.display(), e))),
+        "yaml" | "yml" => serde_yaml::from_str(&contents).map_err(|e| invalid(format!("Invalid YAML in {}: {}", path.display(), e))),
+        _ => Err(invalid(format!("Unknown configuration format of {}; expected .toml, .json or .yaml", path.display()))),
+    }
+}
+
+/// Merges `overlay` into `base`, table by table
+pub fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Parses a raw environment or flag value the way the setting it replaces
+/// is typed
+fn parse_value(raw: &str, current: Option<&Value>) -> Value {
+    match current {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        Some(Value::Array(_)) if !raw.trim_start().starts_with('[') => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| serde_json::from_str(item).unwrap_or_else(|_| Value::String(item.to_string())))
+                .collect(),
+        ),
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+fn lookup<'a>(tree: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(tree, |value, key| value.get(key))
+}
+
+fn set(tree: &mut Value, path: &[String], value: Value) {
+    let mut node = tree;
+    for key in path {
+        if !node.is_object() {
+            *node = Value::Object(Map::new());
+        }
+        node = node.as_object_mut().unwrap().entry(key.clone()).or_insert(Value::Null);
+    }
+    *node = value;
+}
+
+/// Hides values of keys that look like credentials, for printing settings
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let secret = ["key", "secret", "password", "token"].iter().any(|word| key.contains(word));
+                if secret && value.is_string() {
+                    *value = Value::String("<redacted>".to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Builder for the layers of one binary's settings
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    file: Option<PathBuf>,
+    env_prefix: String,
+    /// Older variable names, applied before the prefixed ones
+    aliases: Vec<(String, String)>,
+    overrides: Vec<(String, Value)>,
+    raw_overrides: Vec<(String, String)>,
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self {
+            file: None,
+            env_prefix: ENV_PREFIX.to_string(),
+            aliases: Vec::new(),
+            overrides: Vec::new(),
+            raw_overrides: Vec::new(),
+        }
+    }
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, path: Option<impl Into<PathBuf>>) -> Self {
+        self.file = path.map(Into::into);
+        self
+    }
+
+    /// Also read `key` from the environment variable `var`
+    pub fn env_alias(mut self, var: &str, key: &str) -> Self {
+        self.aliases.push((var.to_string(), key.to_string()));
+        self
+    }
+
+    /// Override `key`, a dotted path, with a command line value
+    pub fn set(mut self, key: &str, value: impl Serialize) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.overrides.push((key.to_string(), value));
+        }
+        self
+    }
+
+    /// Override `key` when the flag was given
+    pub fn set_some(self, key: &str, value: Option<impl Serialize>) -> Self {
+        match value {
+            Some(value) => self.set(key, value),
+            None => self,
+        }
+    }
+
+    /// Override keys with `KEY=VALUE` assignments, parsed like environment
+    /// values
+    pub fn assign<'a>(mut self, assignments: impl IntoIterator<Item = &'a String>) -> Result<Self> {
+        for assignment in assignments {
+            let (key, value) = assignment.split_once('=')
+                .ok_or_else(|| invalid(format!("Expected KEY=VALUE, got {}", assignment)))?;
+            self.raw_overrides.push((key.trim().to_string(), value.to_string()));
+        }
+        Ok(self)
+    }
+
+    /// Loads the settings from the process environment
+    pub fn load<T>(&self) -> Result<T>
+    where
+        T: Default + Serialize + DeserializeOwned + Validate,
+    {
+        self.load_with_env(std::env::vars())
+    }
+
+    pub fn load_with_env<T>(&self, env: impl IntoIterator<Item = (String, String)>) -> Result<T>
+    where
+        T: Default + Serialize + DeserializeOwned + Validate,
+    {
+        let settings: T = serde_json::from_value(self.merged::<T>(env)?)
+            .map_err(|e| invalid(format!("Invalid settings: {}", e)))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn merged<T: Default + Serialize>(&self, env: impl IntoIterator<Item = (String, String)>) -> Result<Value> {
+        let mut tree = serde_json::to_value(T::default())?;
+        if let Some(path) = &self.file {
+            merge(&mut tree, read_file(path)?);
+        }
+
+        let env: Vec<(String, String)> = env.into_iter().collect();
+        let prefix = format!("{}_", self.env_prefix);
+        let aliased = self.aliases.iter().filter_map(|(var, key)| {
+            env.iter()
+                .find(|(name, _)| name == var)
+                .map(|(_, value)| (key.split('.').map(str::to_string).collect::<Vec<_>>(), value))
+        });
+        let prefixed = env.iter().filter_map(|(name, value)| {
+            let key = name.strip_prefix(&prefix)?;
+            Some((key.to_lowercase().split("__").map(str::to_string).collect::<Vec<_>>(), value))
+        });
+        for (path, raw) in aliased.chain(prefixed).collect::<Vec<_>>() {
+            // Only settings the type knows, so unrelated variables pass
+            if tree.get(&path[0]).is_none() {
+                continue;
+            }
+            let value = parse_value(raw, lookup(&tree, &path));
+            set(&mut tree, &path, value);
+        }
+
+        for (key, raw) in &self.raw_overrides {
+            let path: Vec<String> = key.split('.').map(str::to_string).collect();
+            if tree.get(&path[0]).is_none() {
+                return Err(invalid(format!("Unknown setting {}", key)));
+            }
+            let value = parse_value(raw, lookup(&tree, &path));
+            set(&mut tree, &path, value);
+        }
+        for (key, value) in &self.overrides {
+            let path: Vec<String> = key.split('.').map(str::to_string).collect();
+            set(&mut tree, &path, value.clone());
+        }
+        Ok(tree)
+    }
+
+    /// Loads and validates the settings, returning them as pretty JSON with
+    /// credentials hidden, for `config validate`
+    pub fn check<T>(&self) -> Result<String>
+    where
+        T: Default + Serialize + DeserializeOwned + Validate,
+    {
+        let settings: T = self.load()?;
+        let mut value = serde_json::to_value(settings)?;
+        redact(&mut value);
+        Ok(serde_json::to_string_pretty(&value)?)
+    }
+}
+
+/// Flags every binary takes for its configuration
+#[cfg(feature = "cli")]
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ConfigArgs {
+    /// Configuration file, TOML, JSON or YAML
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Override a setting, e.g. `--set connections.max_inbound=50`
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    pub overrides: Vec<String>,
+}
+
+#[cfg(feature = "cli")]
+impl ConfigArgs {
+    /// Loader reading the file and `--set` overrides given on the command line
+    pub fn loader(&self) -> Result<ConfigLoader> {
+        ConfigLoader::new().file(self.config.clone()).assign(&self.overrides)
+    }
+}
+
+#[cfg(feature = "cli")]
+#[derive(clap::Subcommand, Debug, Clone)]
+pub enum ConfigCommand {
+    /// Check the configuration and print the settings it resolves to
+    Validate,
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde::Deserialize};
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    struct Settings {
+        name: String,
+        port: u16,
+        peers: Vec<String>,
+        limits: Limits,
+    }
+
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    struct Limits {
+        max_inbound: Option<u32>,
+    }
+
+    impl Validate for Settings {}
+
+    #[test]
+    fn layers_override_in_order() {
+        let dir = std::env::temp_dir().join(format!("windexer-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("settings.toml");
+        fs::write(&file, "name = \"file\"\nport = 9000\n[limits]\nmax_inbound = 10\n").unwrap();
+
+        let env = [
+            ("WINDEXER_PORT", "9100"),
+            ("WINDEXER_NAME", "123"),
+            ("WINDEXER_PEERS", "a,b"),
+            ("WINDEXER_LIMITS__MAX_INBOUND", "20"),
+            ("WINDEXER_UNRELATED", "1"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+        let settings: Settings = ConfigLoader::new()
+            .file(Some(&file))
+            .set("port", 9200)
+            .load_with_env(env)
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(settings.name, "123");
+        assert_eq!(settings.port, 9200);
+        assert_eq!(settings.peers, ["a", "b"]);
+        assert_eq!(settings.limits.max_inbound, Some(20));
+    }
+}
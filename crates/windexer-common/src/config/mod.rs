@@ -2,12 +2,17 @@
 
 mod network;
 mod store;
+pub mod loader;
 pub mod node;
+pub mod storage;
 
 // Comment out these imports to resolve duplicates
 // pub use network::NetworkConfig;
 // pub use store::StoreConfig;
-pub use node::NodeConfig;
+pub use loader::{ConfigLoader, Validate};
+#[cfg(feature = "cli")]
+pub use loader::{ConfigArgs, ConfigCommand};
+pub use node::{ConnectionConfig, NodeConfig, TransportKind};
 
 use {
     std::{fs, path::{Path, PathBuf}},
@@ -2,10 +2,61 @@
 
 use {
     serde::{Deserialize, Serialize},
-    std::net::SocketAddr,
+    std::{net::SocketAddr, str::FromStr},
     crate::crypto::SerializableKeypair,
 };
 
+/// Transports a node listens on and dials with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransportKind {
+    /// TCP with noise and yamux
+    #[default]
+    Tcp,
+    /// QUIC over UDP, on the same port number as TCP would use
+    Quic,
+    /// Both, so peers on either transport can connect
+    Both,
+}
+
+impl TransportKind {
+    pub fn tcp(&self) -> bool {
+        matches!(self, TransportKind::Tcp | TransportKind::Both)
+    }
+
+    pub fn quic(&self) -> bool {
+        matches!(self, TransportKind::Quic | TransportKind::Both)
+    }
+}
+
+impl FromStr for TransportKind {
+    type Err = String;
+
+    fn from_str(transport: &str) -> Result<Self, Self::Err> {
+        match transport {
+            "tcp" => Ok(TransportKind::Tcp),
+            "quic" => Ok(TransportKind::Quic),
+            "both" => Ok(TransportKind::Both),
+            _ => Err(format!("Unknown transport {}; expected tcp, quic or both", transport)),
+        }
+    }
+}
+
+/// Which peers may connect and how many connections are kept; every limit
+/// is off when unset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionConfig {
+    pub max_inbound: Option<u32>,
+    pub max_outbound: Option<u32>,
+    /// Connections from or to any one IP address
+    pub max_per_ip: Option<u32>,
+    /// Peer ids allowed to connect; any peer when empty
+    pub allowlist: Vec<String>,
+    /// Peer ids refused outright, as if banned
+    pub denylist: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeConfig {
     pub node_id: String,
@@ -17,6 +68,13 @@ pub struct NodeConfig {
     pub geyser_plugin_config: Option<String>,
     pub keypair: SerializableKeypair,
     pub metrics_addr: Option<SocketAddr>,
+    /// Programs whose account updates the node subscribes to; all accounts when empty
+    #[serde(default)]
+    pub account_programs: Vec<String>,
+    #[serde(default)]
+    pub transport: TransportKind,
+    #[serde(default)]
+    pub connections: ConnectionConfig,
 }
 
 impl NodeConfig {
@@ -36,6 +94,9 @@ impl NodeConfig {
             geyser_plugin_config: None,
             keypair: SerializableKeypair::default(),
             metrics_addr: None,
+            account_programs: Vec::new(),
+            transport: TransportKind::default(),
+            connections: ConnectionConfig::default(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,156 @@
+//! Storage backend configuration
+//!
+//! The `storage` section shared by the Geyser plugin config and the store
+//! factory, kept here so neither crate has to depend on the other for it.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub enum StorageType {
+    #[serde(rename = "rocksdb")]
+    RocksDB,
+    #[serde(rename = "parquet")]
+    Parquet,
+    #[serde(rename = "postgres")]
+    Postgres,
+}
+
+impl Default for StorageType {
+    fn default() -> Self {
+        StorageType::RocksDB
+    }
+}
+
+/// Codec used for account data payloads
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionCodec {
+    #[default]
+    None,
+    Lz4,
+    Zstd,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CompressionConfig {
+    #[serde(default)]
+    pub codec: CompressionCodec,
+    /// Codec-specific level; only used by zstd
+    #[serde(default = "default_compression_level")]
+    pub level: i32,
+    /// Payloads smaller than this are stored uncompressed
+    #[serde(default = "default_compression_min_size")]
+    pub min_size_bytes: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            codec: CompressionCodec::None,
+            level: default_compression_level(),
+            min_size_bytes: default_compression_min_size(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ParquetConfig {
+    pub directory: String,
+    #[serde(default = "default_parquet_file_size_mb")]
+    pub max_file_size_mb: usize,
+    #[serde(default = "default_true")]
+    pub compression_enabled: bool,
+    #[serde(default = "default_parquet_partition_by_slot")]
+    pub partition_by_slot: bool,
+    #[serde(default = "default_parquet_slots_per_partition")]
+    pub slots_per_partition: u64,
+    #[serde(default = "default_parquet_file_age_secs")]
+    pub max_file_age_secs: u64,
+    /// Rows buffered per table before they are written as one row group
+    #[serde(default = "default_parquet_row_group_size")]
+    pub row_group_size: usize,
+    /// Column compression for the account `data` column
+    #[serde(default)]
+    pub account_data_compression: CompressionConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PostgresConfig {
+    pub connection_string: String,
+    #[serde(default = "default_true")]
+    pub create_tables: bool,
+    #[serde(default = "default_postgres_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_postgres_max_connections")]
+    pub max_connections: usize,
+    /// `synchronous_commit` level for ingestion sessions (on, off, local,
+    /// remote_write, remote_apply); server default when unset
+    #[serde(default)]
+    pub synchronous_commit: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub storage_type: StorageType,
+    #[serde(default)]
+    pub parquet: Option<ParquetConfig>,
+    #[serde(default)]
+    pub postgres: Option<PostgresConfig>,
+    #[serde(default)]
+    pub rocksdb_path: Option<String>,
+    #[serde(default = "default_true")]
+    pub hot_cold_separation: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            storage_type: StorageType::RocksDB,
+            parquet: None,
+            postgres: None,
+            rocksdb_path: None,
+            hot_cold_separation: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_parquet_file_size_mb() -> usize {
+    128 // 128 MB per file is a good balance for Parquet
+}
+
+fn default_parquet_partition_by_slot() -> bool {
+    true // Partitioning by slot is efficient for blockchain data
+}
+
+fn default_parquet_slots_per_partition() -> u64 {
+    432_000 // One epoch worth of slots per partition
+}
+
+fn default_parquet_file_age_secs() -> u64 {
+    300 // Roll files at least every five minutes so they become readable
+}
+
+fn default_parquet_row_group_size() -> usize {
+    50_000
+}
+
+fn default_compression_level() -> i32 {
+    3
+}
+
+fn default_compression_min_size() -> usize {
+    256
+}
+
+fn default_postgres_batch_size() -> usize {
+    1000 // Default batch size for PostgreSQL inserts
+}
+
+fn default_postgres_max_connections() -> usize {
+    20 // Default connection pool size for PostgreSQL
+}
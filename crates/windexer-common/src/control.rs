@@ -0,0 +1,282 @@
+//! Runtime control hooks
+//!
+//! Components that can be managed while running implement these traits, so a
+//! binary embedding several of them can hand them to the admin API without
+//! the API depending on each component's crate.
+
+use {
+    crate::{filter::Filter, logging::Directives, types::{PerformanceRecord, SlashRecord}},
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+};
+
+/// A peer known to the network node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerInfo {
+    pub peer_id: String,
+    pub connected: bool,
+    pub banned: bool,
+    /// On the node's allowlist; while the allowlist is empty every unbanned peer may connect
+    pub allowed: bool,
+}
+
+/// What the node knows about a peer it has seen; kept across restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    pub peer_id: String,
+    pub connected: bool,
+    /// Unix timestamp in seconds
+    pub last_seen: i64,
+    /// Topics the peer advertised
+    pub topics: Vec<String>,
+    pub protocol_version: Option<String>,
+    /// Round-trip time of the latest ping
+    pub latency_ms: Option<u64>,
+    /// Stake of the peer's operator, when known
+    pub stake: Option<u64>,
+}
+
+/// Lists, bans and allowlists network peers
+#[async_trait]
+pub trait PeerControl: Send + Sync {
+    async fn peers(&self) -> Result<Vec<PeerInfo>>;
+
+    /// Every peer in the node's registry, connected or not
+    async fn registry(&self) -> Result<Vec<PeerRecord>>;
+
+    /// Disconnect the peer and refuse it until unbanned
+    async fn ban_peer(&self, peer_id: &str) -> Result<()>;
+
+    async fn unban_peer(&self, peer_id: &str) -> Result<()>;
+
+    /// Peers on the allowlist
+    async fn allowlist(&self) -> Result<Vec<String>>;
+
+    /// Add the peer to the allowlist; once it has any entry, peers not on it are disconnected and refused
+    async fn allow_peer(&self, peer_id: &str) -> Result<()>;
+
+    async fn disallow_peer(&self, peer_id: &str) -> Result<()>;
+}
+
+/// A gossip topic of the network node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopicInfo {
+    pub topic: String,
+    pub description: String,
+    pub subscribed: bool,
+}
+
+/// Lists the node's gossip topics and changes its subscriptions
+#[async_trait]
+pub trait TopicControl: Send + Sync {
+    /// The well-known topics and any other topic the node is subscribed to
+    async fn topics(&self) -> Result<Vec<TopicInfo>>;
+
+    async fn subscribe(&self, topic: &str) -> Result<()>;
+
+    async fn unsubscribe(&self, topic: &str) -> Result<()>;
+
+    /// Subscribe to the topics carrying the updates `filter` can match,
+    /// returning their names
+    async fn subscribe_filter(&self, filter: &Filter) -> Result<Vec<String>>;
+}
+
+/// Reloads a component's data filters without restarting it
+#[async_trait]
+pub trait FilterControl: Send + Sync {
+    /// The filters in effect
+    async fn filters(&self) -> Result<Value>;
+
+    /// Re-read the filters from their source and apply them, returning the new filters
+    async fn reload_filters(&self) -> Result<Value>;
+}
+
+/// Changes a component's log levels while it runs
+pub trait LogControl: Send + Sync {
+    /// The directives in effect, such as `info,windexer_network=debug`
+    fn directives(&self) -> String;
+
+    /// Replace every directive
+    fn set_directives(&self, directives: &str) -> Result<()>;
+
+    /// Change the level of `module`, or the default level without one,
+    /// keeping the other directives
+    fn set_level(&self, module: Option<&str>, level: &str) -> Result<()> {
+        let mut directives: Directives = self.directives().parse()?;
+        directives.set(module, level.parse().map_err(|_| anyhow!("Invalid log level '{}'", level))?);
+        self.set_directives(&directives.to_string())
+    }
+}
+
+/// Change of log levels: either every directive, or one level keeping the rest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LogLevelRequest {
+    /// Every directive, e.g. `info,windexer_network::node=debug`
+    #[serde(default)]
+    pub directives: Option<String>,
+    /// Level to set: `off`, `error`, `warn`, `info`, `debug` or `trace`
+    #[serde(default)]
+    pub level: Option<String>,
+    /// Module `level` applies to, e.g. `windexer_network::node`; the default level when unset
+    #[serde(default)]
+    pub module: Option<String>,
+}
+
+impl LogLevelRequest {
+    pub fn apply(&self, control: &dyn LogControl) -> Result<()> {
+        match (&self.directives, &self.level) {
+            (Some(directives), None) if self.module.is_none() => control.set_directives(directives),
+            (None, Some(level)) => control.set_level(self.module.as_deref(), level),
+            _ => Err(anyhow!("Set either directives, or a level and an optional module")),
+        }
+    }
+}
+
+/// A batch that could not be delivered, without its records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeadLetterInfo {
+    pub id: u64,
+    /// What failed to deliver it, such as `publisher` or `store`
+    pub source: String,
+    /// Record kind of the batch, such as `accounts` or `blocks`
+    pub kind: String,
+    pub records: usize,
+    pub bytes: usize,
+    /// The last error
+    pub error: String,
+    /// Unix timestamp of the first failure
+    pub failed_at: i64,
+    /// Retries that failed since
+    pub attempts: u32,
+}
+
+/// Which dead letters to retry or purge; every one when `ids` is unset
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DeadLetterRequest {
+    #[serde(default)]
+    pub ids: Option<Vec<u64>>,
+}
+
+/// Outcome of retrying dead letters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RetryReport {
+    /// Delivered and removed from the queue
+    pub delivered: usize,
+    /// Failed again and kept
+    pub failed: usize,
+}
+
+/// Lists, retries and purges a component's dead letters
+#[async_trait]
+pub trait DeadLetterControl: Send + Sync {
+    /// Up to `limit` dead letters, oldest first
+    async fn dead_letters(&self, limit: usize) -> Result<Vec<DeadLetterInfo>>;
+
+    async fn retry(&self, ids: Option<&[u64]>) -> Result<RetryReport>;
+
+    /// Drop dead letters without delivering them, returning how many were
+    async fn purge(&self, ids: Option<&[u64]>) -> Result<usize>;
+}
+
+/// Where the cluster is in its epoch schedule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct EpochInfo {
+    pub epoch: u64,
+    /// Latest slot seen
+    pub slot: u64,
+    /// Position of `slot` in the epoch
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    pub first_slot: u64,
+    pub last_slot: u64,
+}
+
+/// Reports the Solana epoch a component follows
+pub trait EpochStatus: Send + Sync {
+    /// `None` until the first slot was seen
+    fn current_epoch(&self) -> Option<EpochInfo>;
+}
+
+/// An operator's stake and accounting in the staking service
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OperatorStatus {
+    pub pubkey: String,
+    pub total_stake: u64,
+    /// Stakers delegating to the operator
+    pub delegators: usize,
+    /// Performance score of the last closed epoch, between 0 and 1
+    pub performance_score: f64,
+    /// Unix timestamp in seconds of its last counted vote
+    pub last_active: Option<i64>,
+    /// Rewards earned by the operator and not yet paid out
+    pub reward_balance: u64,
+    /// Unstaked stake still cooling down
+    pub pending_withdrawals: u64,
+}
+
+/// Stake one staker delegates to one operator
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct StakeDelegation {
+    pub staker: String,
+    pub operator: String,
+    pub amount: u64,
+    /// Rewards accrued through the delegation and not yet paid out
+    pub rewards: u64,
+}
+
+/// An operator with its delegations
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct OperatorDetail {
+    pub operator: OperatorStatus,
+    /// Largest first
+    pub delegations: Vec<StakeDelegation>,
+    /// Performance score in the current epoch so far
+    pub current_score: f64,
+}
+
+/// Rewards accrued to an account, as an operator and as a staker
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RewardBalance {
+    pub pubkey: String,
+    /// Earned as an operator
+    pub operator_rewards: u64,
+    /// Delegations of the account, with what each earned
+    pub delegations: Vec<StakeDelegation>,
+    /// Everything not yet paid out
+    pub total: u64,
+}
+
+/// Reads and registers the operators of a staking service
+#[async_trait]
+pub trait StakingControl: Send + Sync {
+    /// Every operator, by stake, largest first
+    async fn operators(&self) -> Result<Vec<OperatorStatus>>;
+
+    /// `None` when `pubkey` is not an operator
+    async fn operator(&self, pubkey: &str) -> Result<Option<OperatorDetail>>;
+
+    async fn rewards(&self, pubkey: &str) -> Result<RewardBalance>;
+
+    /// Up to `limit` of the most recent slashes of `operator`, or of every
+    /// operator, newest first
+    async fn slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>>;
+
+    /// Performance of `pubkey` in every retained epoch closed at or after
+    /// the unix timestamp `since`, oldest first; `None` when `pubkey` is not
+    /// an operator
+    async fn performance(&self, pubkey: &str, since: i64) -> Result<Option<Vec<PerformanceRecord>>>;
+
+    /// Start keeping the accounting of a new operator
+    async fn register_operator(&self, pubkey: &str) -> Result<OperatorStatus>;
+}
@@ -0,0 +1,308 @@
+//! Dead-letter queue for batches that could not be delivered
+//!
+//! A batch a publisher failed to send, or a store failed to write, goes to a
+//! [`DeadLetterQueue`] instead of being dropped once the error is logged. An
+//! entry keeps the batch as a wire frame, see [`crate::types::wire`], with
+//! the error and the component that failed to deliver it.
+//!
+//! Entries are appended to a file, one JSON line each, so they survive a
+//! restart; retrying or purging entries rewrites it. [`DeadLetters`] pairs a
+//! queue with the [`Redeliver`] that retries its entries and implements
+//! [`DeadLetterControl`] for the admin routes.
+
+use {
+    crate::{
+        control::{DeadLetterControl, DeadLetterInfo, RetryReport},
+        types::wire::WireFrame,
+    },
+    anyhow::{Context, Result},
+    async_trait::async_trait,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::BTreeMap,
+        fs::{self, OpenOptions},
+        io::Write,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    },
+    tracing::warn,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterConfig {
+    /// File the entries are kept in
+    pub path: String,
+    /// Entries kept; later failures are only logged until some are retried
+    /// or purged
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_max_entries() -> usize {
+    10_000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DeadLetter {
+    id: u64,
+    source: String,
+    error: String,
+    failed_at: i64,
+    attempts: u32,
+    /// Base64-encoded wire frame
+    #[serde(with = "base64_payload")]
+    payload: Vec<u8>,
+}
+
+mod base64_payload {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(payload: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(payload))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        base64::decode(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+impl DeadLetter {
+    fn info(&self) -> DeadLetterInfo {
+        let frame = WireFrame::parse(&self.payload).ok();
+        DeadLetterInfo {
+            id: self.id,
+            source: self.source.clone(),
+            kind: frame.map_or("unknown", |frame| frame.kind.name()).to_string(),
+            records: frame.map_or(0, |frame| frame.len()),
+            bytes: self.payload.len(),
+            error: self.error.clone(),
+            failed_at: self.failed_at,
+            attempts: self.attempts,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    entries: BTreeMap<u64, DeadLetter>,
+    next: u64,
+}
+
+#[derive(Debug)]
+pub struct DeadLetterQueue {
+    path: PathBuf,
+    max_entries: usize,
+    state: Mutex<QueueState>,
+}
+
+impl DeadLetterQueue {
+    /// Opens the queue file, picking up the entries left by a previous run
+    pub fn open(config: &DeadLetterConfig) -> Result<Self> {
+        let path = PathBuf::from(&config.path);
+        if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)
+                .with_context(|| format!("Failed to create dead-letter directory {}", directory.display()))?;
+        }
+
+        let mut state = QueueState::default();
+        if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read dead letters from {}", path.display()))?;
+            for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+                // A crash while appending leaves a partial last line
+                match serde_json::from_str::<DeadLetter>(line) {
+                    Ok(entry) => {
+                        state.next = state.next.max(entry.id + 1);
+                        state.entries.insert(entry.id, entry);
+                    }
+                    Err(e) => warn!("Skipping unreadable dead letter in {}: {}", path.display(), e),
+                }
+            }
+        }
+
+        Ok(Self {
+            path,
+            max_entries: config.max_entries,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Keeps `payload`, a wire frame `source` failed to deliver with `error`;
+    /// returns its id, or `None` if the queue is full
+    pub fn push(&self, source: &str, payload: Vec<u8>, error: &str) -> Result<Option<u64>> {
+        let mut state = self.state.lock().unwrap();
+        if state.entries.len() >= self.max_entries {
+            return Ok(None);
+        }
+        let entry = DeadLetter {
+            id: state.next,
+            source: source.to_string(),
+            error: error.to_string(),
+            failed_at: chrono::Utc::now().timestamp(),
+            attempts: 0,
+            payload,
+        };
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)
+            .with_context(|| format!("Failed to open dead letters {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+
+        state.next += 1;
+        state.entries.insert(entry.id, entry);
+        Ok(Some(state.next - 1))
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Up to `limit` entries, oldest first
+    pub fn list(&self, limit: usize) -> Vec<DeadLetterInfo> {
+        self.state.lock().unwrap().entries.values().take(limit).map(DeadLetter::info).collect()
+    }
+
+    fn selected(&self, ids: Option<&[u64]>) -> Vec<DeadLetter> {
+        let state = self.state.lock().unwrap();
+        match ids {
+            Some(ids) => ids.iter().filter_map(|id| state.entries.get(id)).cloned().collect(),
+            None => state.entries.values().cloned().collect(),
+        }
+    }
+
+    /// Removes the entries, or every entry without `ids`, returning how many were
+    pub fn purge(&self, ids: Option<&[u64]>) -> Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.entries.len();
+        match ids {
+            Some(ids) => {
+                for id in ids {
+                    state.entries.remove(id);
+                }
+            }
+            None => state.entries.clear(),
+        }
+        self.rewrite(&state)?;
+        Ok(before - state.entries.len())
+    }
+
+    /// Removes the `delivered` entries and records the new error of the `failed` ones
+    fn settle(&self, delivered: &[u64], failed: Vec<(u64, String)>) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        for id in delivered {
+            state.entries.remove(id);
+        }
+        for (id, error) in failed {
+            if let Some(entry) = state.entries.get_mut(&id) {
+                entry.attempts += 1;
+                entry.error = error;
+            }
+        }
+        self.rewrite(&state)
+    }
+
+    /// Writes to a temporary file first so a crash never loses the queue
+    fn rewrite(&self, state: &QueueState) -> Result<()> {
+        let mut contents = String::new();
+        for entry in state.entries.values() {
+            contents.push_str(&serde_json::to_string(entry)?);
+            contents.push('\n');
+        }
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)
+            .with_context(|| format!("Failed to rewrite dead letters {}", self.path.display()))
+    }
+}
+
+/// Delivers a dead letter again, where `source` first failed to
+#[async_trait]
+pub trait Redeliver: Send + Sync {
+    async fn redeliver(&self, source: &str, payload: &[u8]) -> Result<()>;
+}
+
+/// A queue with the means to retry its entries
+pub struct DeadLetters {
+    queue: Arc<DeadLetterQueue>,
+    redeliver: Arc<dyn Redeliver>,
+}
+
+impl DeadLetters {
+    pub fn new(queue: Arc<DeadLetterQueue>, redeliver: Arc<dyn Redeliver>) -> Self {
+        Self { queue, redeliver }
+    }
+}
+
+#[async_trait]
+impl DeadLetterControl for DeadLetters {
+    async fn dead_letters(&self, limit: usize) -> Result<Vec<DeadLetterInfo>> {
+        Ok(self.queue.list(limit))
+    }
+
+    async fn retry(&self, ids: Option<&[u64]>) -> Result<RetryReport> {
+        let mut delivered = Vec::new();
+        let mut failed = Vec::new();
+        for entry in self.queue.selected(ids) {
+            match self.redeliver.redeliver(&entry.source, &entry.payload).await {
+                Ok(()) => delivered.push(entry.id),
+                Err(e) => failed.push((entry.id, e.to_string())),
+            }
+        }
+        let report = RetryReport { delivered: delivered.len(), failed: failed.len() };
+        self.queue.settle(&delivered, failed)?;
+        Ok(report)
+    }
+
+    async fn purge(&self, ids: Option<&[u64]>) -> Result<usize> {
+        self.queue.purge(ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::types::{wire, BlockData},
+    };
+
+    struct FailingStore;
+
+    #[async_trait]
+    impl Redeliver for FailingStore {
+        async fn redeliver(&self, source: &str, _payload: &[u8]) -> Result<()> {
+            match source {
+                "store" => Err(anyhow::anyhow!("still down")),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_entries_across_reopen_and_retries() {
+        let path = std::env::temp_dir().join(format!("windexer-dlq-{}.jsonl", std::process::id()));
+        let config = DeadLetterConfig { path: path.to_string_lossy().to_string(), max_entries: 2 };
+        let frame = wire::encode(None, &[BlockData::default()]).unwrap();
+
+        let queue = DeadLetterQueue::open(&config).unwrap();
+        assert_eq!(queue.push("publisher", frame.clone(), "timed out").unwrap(), Some(0));
+        assert_eq!(queue.push("store", frame.clone(), "disk full").unwrap(), Some(1));
+        assert_eq!(queue.push("store", frame, "disk full").unwrap(), None);
+        drop(queue);
+
+        let queue = Arc::new(DeadLetterQueue::open(&config).unwrap());
+        let listed = queue.list(10);
+        assert_eq!((listed.len(), listed[0].kind.as_str(), listed[0].records), (2, "blocks", 1));
+
+        let dead_letters = DeadLetters::new(queue.clone(), Arc::new(FailingStore));
+        assert_eq!(dead_letters.retry(None).await.unwrap(), RetryReport { delivered: 1, failed: 1 });
+        let listed = DeadLetterQueue::open(&config).unwrap().list(10);
+        assert_eq!((listed.len(), listed[0].id, listed[0].attempts), (1, 1, 1));
+
+        assert_eq!(dead_letters.purge(Some(&[1])).await.unwrap(), 1);
+        assert!(queue.is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+}
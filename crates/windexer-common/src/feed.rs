@@ -0,0 +1,94 @@
+//! Live data feed
+//!
+//! A [`DataFeed`] fans out every account, transaction, block and slot status
+//! the node ingests, and the slot gaps it detects, to any number of
+//! in-process subscribers, such as the streaming APIs and gap backfills. Publishers never block: subscribers that fall more than the
+//! channel capacity behind miss events and are told how many they skipped.
+
+use {
+    crate::{
+        types::{AccountData, BlockData, SlotStatusData, TransactionData},
+        utils::SlotGap,
+    },
+    std::sync::Arc,
+    tokio::sync::broadcast,
+};
+
+/// Default number of events buffered for slow subscribers
+pub const DEFAULT_FEED_CAPACITY: usize = 16_384;
+
+/// One ingested update
+#[derive(Debug, Clone)]
+pub enum FeedEvent {
+    Account(Arc<AccountData>),
+    Transaction(Arc<TransactionData>),
+    Block(Arc<BlockData>),
+    SlotStatus(SlotStatusData),
+    /// Rooted slots the node never received
+    SlotGap(SlotGap),
+}
+
+impl FeedEvent {
+    pub fn slot(&self) -> u64 {
+        match self {
+            FeedEvent::Account(account) => account.slot,
+            FeedEvent::Transaction(transaction) => transaction.slot,
+            FeedEvent::Block(block) => block.slot,
+            FeedEvent::SlotStatus(status) => status.slot,
+            FeedEvent::SlotGap(gap) => gap.first_slot,
+        }
+    }
+}
+
+/// Broadcast bus of ingested updates
+#[derive(Debug, Clone)]
+pub struct DataFeed {
+    sender: broadcast::Sender<FeedEvent>,
+}
+
+impl Default for DataFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_FEED_CAPACITY)
+    }
+}
+
+impl DataFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Publish an event; dropped when nobody is subscribed
+    pub fn publish(&self, event: FeedEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn publish_account(&self, account: AccountData) {
+        self.publish(FeedEvent::Account(Arc::new(account)));
+    }
+
+    pub fn publish_transaction(&self, transaction: TransactionData) {
+        self.publish(FeedEvent::Transaction(Arc::new(transaction)));
+    }
+
+    pub fn publish_block(&self, block: BlockData) {
+        self.publish(FeedEvent::Block(Arc::new(block)));
+    }
+
+    pub fn publish_slot_status(&self, status: SlotStatusData) {
+        self.publish(FeedEvent::SlotStatus(status));
+    }
+
+    pub fn publish_slot_gap(&self, gap: SlotGap) {
+        self.publish(FeedEvent::SlotGap(gap));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FeedEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
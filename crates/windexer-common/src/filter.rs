@@ -0,0 +1,479 @@
+//! Filter expressions over accounts and transactions
+//!
+//! One [`Filter`] type selects updates everywhere they are filtered: the
+//! geyser plugin's `accounts_filter` and `transactions_filter`, gossip topic
+//! subscriptions, and the `filter` parameter of the WebSocket and SSE
+//! streams. Filters are written, and serialized, as text:
+//!
+//! ```text
+//! owner:TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA; size:165; memcmp:0:<base58>
+//! account:<a>,<b> | program:<p>; !vote; status:success; slot:250000000..
+//! ```
+//!
+//! A filter is a list of groups separated by `;`, every one of which must
+//! match; a group is a list of terms separated by `|`, any one of which must
+//! match. A term negated with `!` matches when its predicate doesn't. The
+//! empty filter matches everything.
+//!
+//! | Term | Matches |
+//! |------|---------|
+//! | `account:<pubkey>,...` | the account, or transactions mentioning it |
+//! | `owner:<pubkey>,...` | accounts owned by the program |
+//! | `token_owner:<pubkey>,...` | SPL Token and Token-2022 accounts held by the wallet |
+//! | `program:<pubkey>,...` | transactions whose top-level instructions invoke the program |
+//! | `memcmp:<offset>:<base58>` | accounts whose data holds the bytes at the offset |
+//! | `size:<min>..<max>` | accounts with a data length within the bounds |
+//! | `status:success` or `status:failed` | transactions that succeeded or failed |
+//! | `vote`, `vote:true` or `vote:false` | vote transactions, or others |
+//! | `slot:<first>..<last>` | updates within the slots |
+//!
+//! Bounds are inclusive and either can be left out; a single number is an
+//! exact size or slot. Terms that only apply to accounts never match
+//! transactions and the other way round; [`Filter::check`] rejects them.
+
+use {
+    crate::types::{AccountData, TransactionData},
+    anyhow::{anyhow, Result},
+    serde::{Deserialize, Deserializer, Serialize, Serializer},
+    solana_sdk::{pubkey, pubkey::Pubkey},
+    std::{
+        collections::BTreeSet,
+        fmt::{self, Display, Formatter},
+        str::FromStr,
+    },
+};
+
+const TOKEN_PROGRAM: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const TOKEN_2022_PROGRAM: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Length of a token account without extensions
+const TOKEN_ACCOUNT_LEN: usize = 165;
+/// Offset of the wallet that holds a token account
+const TOKEN_OWNER_OFFSET: usize = 32;
+/// Token-2022 account type stored after the base layout, for accounts with extensions
+const TOKEN_2022_ACCOUNT_TYPE: u8 = 2;
+
+/// What a filter is applied to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subject {
+    Accounts,
+    Transactions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Success,
+    Failed,
+}
+
+/// Inclusive bounds, either of which can be open
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bounds<T> {
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T: Copy + PartialOrd> Bounds<T> {
+    pub fn contains(&self, value: T) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    Accounts(BTreeSet<Pubkey>),
+    Owners(BTreeSet<Pubkey>),
+    TokenOwners(BTreeSet<Pubkey>),
+    Programs(BTreeSet<Pubkey>),
+    Memcmp { offset: usize, bytes: Vec<u8> },
+    DataSize(Bounds<usize>),
+    Status(TransactionStatus),
+    Vote(bool),
+    Slots(Bounds<u64>),
+}
+
+impl Predicate {
+    pub fn applies_to(&self, subject: Subject) -> bool {
+        match self {
+            Predicate::Accounts(_) | Predicate::Slots(_) => true,
+            Predicate::Owners(_) | Predicate::TokenOwners(_) | Predicate::Memcmp { .. } | Predicate::DataSize(_) => {
+                subject == Subject::Accounts
+            }
+            Predicate::Programs(_) | Predicate::Status(_) | Predicate::Vote(_) => subject == Subject::Transactions,
+        }
+    }
+
+    fn matches_account(&self, account: &AccountRef) -> bool {
+        match self {
+            Predicate::Accounts(accounts) => accounts.contains(account.pubkey),
+            Predicate::Owners(owners) => owners.contains(account.owner),
+            Predicate::TokenOwners(wallets) => token_account_holder(account.owner, account.data)
+                .is_some_and(|wallet| wallets.contains(&wallet)),
+            Predicate::Memcmp { offset, bytes } => offset.checked_add(bytes.len())
+                .and_then(|end| account.data.get(*offset..end))
+                .is_some_and(|data| data == bytes.as_slice()),
+            Predicate::DataSize(bounds) => bounds.contains(account.data.len()),
+            Predicate::Slots(bounds) => bounds.contains(account.slot),
+            Predicate::Programs(_) | Predicate::Status(_) | Predicate::Vote(_) => false,
+        }
+    }
+
+    fn matches_transaction(&self, transaction: &TransactionRef) -> bool {
+        match self {
+            Predicate::Accounts(accounts) => transaction.account_keys.iter().any(|key| accounts.contains(key)),
+            Predicate::Programs(programs) => transaction.programs.iter().any(|program| programs.contains(program)),
+            Predicate::Status(status) => transaction.failed == (*status == TransactionStatus::Failed),
+            Predicate::Vote(vote) => transaction.is_vote == *vote,
+            Predicate::Slots(bounds) => bounds.contains(transaction.slot),
+            Predicate::Owners(_) | Predicate::TokenOwners(_) | Predicate::Memcmp { .. } | Predicate::DataSize(_) => false,
+        }
+    }
+}
+
+/// Wallet holding a token account, if `data` is one
+fn token_account_holder(owner: &Pubkey, data: &[u8]) -> Option<Pubkey> {
+    if data.len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    let is_token_account = match *owner {
+        TOKEN_PROGRAM => data.len() == TOKEN_ACCOUNT_LEN,
+        // Mints with extensions are padded to the same length, so the
+        // account type tells them apart
+        TOKEN_2022_PROGRAM => data.len() == TOKEN_ACCOUNT_LEN || data[TOKEN_ACCOUNT_LEN] == TOKEN_2022_ACCOUNT_TYPE,
+        _ => false,
+    };
+    is_token_account
+        .then(|| Pubkey::try_from(&data[TOKEN_OWNER_OFFSET..TOKEN_OWNER_OFFSET + 32]).ok())
+        .flatten()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub negated: bool,
+    pub predicate: Predicate,
+}
+
+impl From<Predicate> for Term {
+    fn from(predicate: Predicate) -> Self {
+        Self { negated: false, predicate }
+    }
+}
+
+/// The fields of an account update a filter looks at
+#[derive(Debug, Clone, Copy)]
+pub struct AccountRef<'a> {
+    pub pubkey: &'a Pubkey,
+    pub owner: &'a Pubkey,
+    pub data: &'a [u8],
+    pub slot: u64,
+}
+
+impl<'a> From<&'a AccountData> for AccountRef<'a> {
+    fn from(account: &'a AccountData) -> Self {
+        Self {
+            pubkey: &account.pubkey,
+            owner: &account.owner,
+            data: &account.data,
+            slot: account.slot,
+        }
+    }
+}
+
+/// The fields of a transaction a filter looks at
+#[derive(Debug, Clone, Copy)]
+pub struct TransactionRef<'a> {
+    pub slot: u64,
+    pub is_vote: bool,
+    pub failed: bool,
+    pub account_keys: &'a [Pubkey],
+    /// Programs of the top-level instructions
+    pub programs: &'a [Pubkey],
+}
+
+/// Every group must match; a group matches when any of its terms does
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    groups: Vec<Vec<Term>>,
+}
+
+impl Filter {
+    /// The filter matching everything
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Also require any of `terms` to match
+    pub fn and_any(mut self, terms: impl IntoIterator<Item = Term>) -> Self {
+        self.groups.push(terms.into_iter().collect());
+        self
+    }
+
+    /// Also require `predicate` to match
+    pub fn and(self, predicate: Predicate) -> Self {
+        self.and_any([Term::from(predicate)])
+    }
+
+    /// Also require `predicate` over any of `pubkeys`, unless there are none
+    pub fn and_any_of(self, predicate: fn(BTreeSet<Pubkey>) -> Predicate, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        let pubkeys: BTreeSet<Pubkey> = pubkeys.into_iter().collect();
+        if pubkeys.is_empty() {
+            return self;
+        }
+        self.and(predicate(pubkeys))
+    }
+
+    /// Fails when a term can never match `subject`
+    pub fn check(&self, subject: Subject) -> Result<()> {
+        match self.terms().find(|term| !term.predicate.applies_to(subject)) {
+            Some(term) => Err(anyhow!("{} doesn't apply to {:?}", term, subject)),
+            None => Ok(()),
+        }
+    }
+
+    fn terms(&self) -> impl Iterator<Item = &Term> {
+        self.groups.iter().flatten()
+    }
+
+    /// Programs every matching account is owned by, when a group only lists owners
+    pub fn required_owners(&self) -> Option<&BTreeSet<Pubkey>> {
+        self.groups.iter().find_map(|group| match group.as_slice() {
+            [Term { negated: false, predicate: Predicate::Owners(owners) }] => Some(owners),
+            _ => None,
+        })
+    }
+
+    pub fn matches_account(&self, account: &AccountRef) -> bool {
+        self.groups.iter().all(|group| {
+            group.iter().any(|term| term.predicate.matches_account(account) != term.negated)
+        })
+    }
+
+    pub fn matches_transaction(&self, transaction: &TransactionRef) -> bool {
+        self.groups.iter().all(|group| {
+            group.iter().any(|term| term.predicate.matches_transaction(transaction) != term.negated)
+        })
+    }
+
+    pub fn matches_account_data(&self, account: &AccountData) -> bool {
+        self.matches_account(&AccountRef::from(account))
+    }
+
+    pub fn matches_transaction_data(&self, transaction: &TransactionData) -> bool {
+        if self.is_all() {
+            return true;
+        }
+        let keys = &transaction.message.account_keys;
+        let programs: Vec<Pubkey> = transaction.message.instructions.iter()
+            .filter_map(|instruction| keys.get(instruction.program_id_index as usize).copied())
+            .collect();
+        self.matches_transaction(&TransactionRef {
+            slot: transaction.slot,
+            is_vote: transaction.is_vote,
+            failed: transaction.serializable_meta.status != Some(0),
+            account_keys: keys,
+            programs: &programs,
+        })
+    }
+}
+
+fn parse_pubkeys(values: &str) -> Result<BTreeSet<Pubkey>> {
+    let pubkeys = values.split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| Pubkey::from_str(value).map_err(|_| anyhow!("Invalid pubkey {}", value)))
+        .collect::<Result<BTreeSet<_>>>()?;
+    if pubkeys.is_empty() {
+        return Err(anyhow!("Expected at least one pubkey"));
+    }
+    Ok(pubkeys)
+}
+
+fn parse_bounds<T: FromStr + Copy>(value: &str) -> Result<Bounds<T>> {
+    let bound = |value: &str| match value.trim() {
+        "" => Ok(None),
+        value => value.parse().map(Some).map_err(|_| anyhow!("Invalid bound {}", value)),
+    };
+    match value.split_once("..") {
+        Some((min, max)) => Ok(Bounds { min: bound(min)?, max: bound(max)? }),
+        None => {
+            let exact = bound(value)?.ok_or_else(|| anyhow!("Expected a number or a range like 1..10"))?;
+            Ok(Bounds { min: Some(exact), max: Some(exact) })
+        }
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = anyhow::Error;
+
+    fn from_str(term: &str) -> Result<Self> {
+        let (key, value) = match term.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (term.trim(), ""),
+        };
+        let predicate = match key {
+            "account" => Predicate::Accounts(parse_pubkeys(value)?),
+            "owner" => Predicate::Owners(parse_pubkeys(value)?),
+            "token_owner" => Predicate::TokenOwners(parse_pubkeys(value)?),
+            "program" => Predicate::Programs(parse_pubkeys(value)?),
+            "memcmp" => {
+                let (offset, bytes) = value.split_once(':')
+                    .ok_or_else(|| anyhow!("Expected memcmp:<offset>:<base58 bytes>"))?;
+                Predicate::Memcmp {
+                    offset: offset.trim().parse().map_err(|_| anyhow!("Invalid memcmp offset {}", offset))?,
+                    bytes: bs58::decode(bytes.trim()).into_vec().map_err(|e| anyhow!("Invalid memcmp bytes {}: {}", bytes, e))?,
+                }
+            }
+            "size" => Predicate::DataSize(parse_bounds(value)?),
+            "slot" => Predicate::Slots(parse_bounds(value)?),
+            "status" => Predicate::Status(match value {
+                "success" => TransactionStatus::Success,
+                "failed" => TransactionStatus::Failed,
+                _ => return Err(anyhow!("Expected status:success or status:failed")),
+            }),
+            "vote" => Predicate::Vote(match value {
+                "" | "true" => true,
+                "false" => false,
+                _ => return Err(anyhow!("Expected vote:true or vote:false")),
+            }),
+            _ => return Err(anyhow!("Unknown filter term {}", key)),
+        };
+        Ok(predicate)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(filter: &str) -> Result<Self> {
+        let groups = filter.split(';')
+            .filter(|group| !group.trim().is_empty())
+            .map(|group| {
+                group.split('|')
+                    .map(|term| {
+                        let term = term.trim();
+                        let (negated, predicate) = match term.strip_prefix('!') {
+                            Some(predicate) => (true, predicate),
+                            None => (false, term),
+                        };
+                        let predicate = predicate.parse::<Predicate>().map_err(|e| anyhow!("Invalid filter term '{}': {}", term, e))?;
+                        Ok(Term { negated, predicate })
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { groups })
+    }
+}
+
+fn write_pubkeys(f: &mut Formatter<'_>, key: &str, pubkeys: &BTreeSet<Pubkey>) -> fmt::Result {
+    let pubkeys: Vec<String> = pubkeys.iter().map(Pubkey::to_string).collect();
+    write!(f, "{}:{}", key, pubkeys.join(","))
+}
+
+fn write_bounds<T: Display>(f: &mut Formatter<'_>, key: &str, bounds: &Bounds<T>) -> fmt::Result {
+    let bound = |value: &Option<T>| value.as_ref().map(T::to_string).unwrap_or_default();
+    write!(f, "{}:{}..{}", key, bound(&bounds.min), bound(&bounds.max))
+}
+
+impl Display for Predicate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Accounts(accounts) => write_pubkeys(f, "account", accounts),
+            Predicate::Owners(owners) => write_pubkeys(f, "owner", owners),
+            Predicate::TokenOwners(wallets) => write_pubkeys(f, "token_owner", wallets),
+            Predicate::Programs(programs) => write_pubkeys(f, "program", programs),
+            Predicate::Memcmp { offset, bytes } => write!(f, "memcmp:{}:{}", offset, bs58::encode(bytes).into_string()),
+            Predicate::DataSize(bounds) => write_bounds(f, "size", bounds),
+            Predicate::Slots(bounds) => write_bounds(f, "slot", bounds),
+            Predicate::Status(TransactionStatus::Success) => f.write_str("status:success"),
+            Predicate::Status(TransactionStatus::Failed) => f.write_str("status:failed"),
+            Predicate::Vote(vote) => write!(f, "vote:{}", vote),
+        }
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.negated {
+            f.write_str("!")?;
+        }
+        self.predicate.fmt(f)
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, group) in self.groups.iter().enumerate() {
+            if index > 0 {
+                f.write_str("; ")?;
+            }
+            for (index, term) in group.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(" | ")?;
+                }
+                term.fmt(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Filter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Filter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let filter = String::deserialize(deserializer)?;
+        filter.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_matches_filters() {
+        let (owner, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let filter: Filter = format!("owner:{} | account:{}; size:4..; !memcmp:0:{}", owner, other, bs58::encode([9u8]).into_string())
+            .parse()
+            .unwrap();
+        assert_eq!(filter.to_string().parse::<Filter>().unwrap(), filter);
+        assert!(filter.check(Subject::Accounts).is_ok() && filter.check(Subject::Transactions).is_err());
+
+        let account = |owner: &Pubkey, data: &[u8]| {
+            filter.matches_account(&AccountRef { pubkey: &Pubkey::new_unique(), owner, data, slot: 1 })
+        };
+        assert!(account(&owner, &[0, 0, 0, 0]));
+        assert!(!account(&owner, &[9, 0, 0, 0]));
+        assert!(!account(&owner, &[0]));
+        assert!(!account(&Pubkey::new_unique(), &[0, 0, 0, 0]));
+
+        assert!("slot:5;vote:maybe".parse::<Filter>().is_err());
+        assert!("".parse::<Filter>().unwrap().is_all());
+    }
+
+    #[test]
+    fn matches_transactions() {
+        let program = Pubkey::new_unique();
+        let filter: Filter = format!("program:{}; !vote; status:success; slot:10..20", program).parse().unwrap();
+        let transaction = |slot, is_vote, failed| TransactionRef {
+            slot,
+            is_vote,
+            failed,
+            account_keys: &[],
+            programs: std::slice::from_ref(&program),
+        };
+        assert!(filter.matches_transaction(&transaction(15, false, false)));
+        assert!(!filter.matches_transaction(&transaction(15, true, false)));
+        assert!(!filter.matches_transaction(&transaction(15, false, true)));
+        assert!(!filter.matches_transaction(&transaction(21, false, false)));
+    }
+}
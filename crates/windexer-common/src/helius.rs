@@ -1,9 +1,13 @@
 use std::sync::Arc;
-use tokio::sync::{RwLock, broadcast};
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock, broadcast, mpsc};
+use tokio::task::JoinHandle;
 use serde::{Serialize, Deserialize};
 use anyhow::{Result, anyhow};
 use futures_util::{SinkExt, StreamExt};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use crate::lookup_tables;
+use crate::rpc_provider::{HeliusProvider, JsonRpcProvider, RpcProvider};
 use crate::types::helius::{
     AccountData,
     BlockData,
@@ -34,15 +38,56 @@ impl Default for HeliusConfig {
     }
 }
 
+/// Delay before the first reconnect attempt; doubled after each failure
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(500);
+/// Longest delay between reconnect attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// State of the WebSocket connection, broadcast on every change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum ConnectionState {
+    Connected,
+    /// The connection dropped; the next attempt starts after `delay_ms`
+    Reconnecting { attempt: u32, delay_ms: u64 },
+    /// Closed with [`HeliusClient::disconnect_websocket`]
+    Disconnected,
+}
+
+/// A subscription request, replayed after every reconnect
+#[derive(Debug, Clone, PartialEq)]
+struct Subscription {
+    method: String,
+    params: Vec<serde_json::Value>,
+}
+
+/// Update channels the reader task publishes to
+#[derive(Clone)]
+struct UpdateChannels {
+    accounts: broadcast::Sender<AccountData>,
+    transactions: broadcast::Sender<TransactionData>,
+    blocks: broadcast::Sender<BlockData>,
+}
+
 /// Helius API client for Solana blockchain data
+///
+/// The WebSocket connection reconnects with exponential backoff when it
+/// drops, and every subscription made through the client is sent again once
+/// it is back. [`HeliusClient::connection_states`] reports each change.
 #[derive(Debug, Clone)]
 pub struct HeliusClient {
     /// Configuration
     config: HeliusConfig,
-    /// HTTP client
-    client: reqwest::Client,
+    /// Upstream serving HTTP requests
+    provider: Arc<dyn RpcProvider>,
     /// WebSocket connection (if established)
-    ws_connection: Arc<RwLock<Option<tokio::sync::mpsc::Sender<Message>>>>,
+    ws_connection: Arc<RwLock<Option<mpsc::Sender<Message>>>>,
+    /// Task keeping the WebSocket connected
+    ws_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    /// Subscriptions to restore after a reconnect
+    subscriptions: Arc<RwLock<Vec<Subscription>>>,
+    /// Connection state channel
+    connection_states: broadcast::Sender<ConnectionState>,
     /// Account update channel
     account_updates: broadcast::Sender<AccountData>,
     /// Transaction update channel
@@ -54,14 +99,29 @@ pub struct HeliusClient {
 impl HeliusClient {
     /// Create a new Helius client
     pub fn new(config: HeliusConfig) -> Self {
+        let provider: Arc<dyn RpcProvider> = match &config.http_endpoint {
+            Some(endpoint) => Arc::new(JsonRpcProvider::new(format!("{}?api-key={}", endpoint, config.api_key))),
+            None => Arc::new(HeliusProvider::new(&config.api_key, &config.network)),
+        };
+        Self::with_provider(config, provider)
+    }
+    
+    /// Create a client sending HTTP requests to `provider`, which may be any
+    /// RPC provider; WebSocket updates use `config.ws_endpoint` if set, then
+    /// the provider's WebSocket endpoint
+    pub fn with_provider(config: HeliusConfig, provider: Arc<dyn RpcProvider>) -> Self {
         let (account_tx, _) = broadcast::channel(1000);
         let (tx_tx, _) = broadcast::channel(1000);
         let (block_tx, _) = broadcast::channel(1000);
+        let (state_tx, _) = broadcast::channel(100);
         
         Self {
             config,
-            client: reqwest::Client::new(),
+            provider,
             ws_connection: Arc::new(RwLock::new(None)),
+            ws_task: Arc::new(Mutex::new(None)),
+            subscriptions: Arc::new(RwLock::new(Vec::new())),
+            connection_states: state_tx,
             account_updates: account_tx,
             transaction_updates: tx_tx,
             block_updates: block_tx,
@@ -76,21 +136,12 @@ impl HeliusClient {
         })
     }
     
-    /// Get the base URL for HTTP requests
-    fn get_base_url(&self) -> String {
-        if let Some(endpoint) = &self.config.http_endpoint {
-            format!("{}?api-key={}", endpoint, self.config.api_key)
-        } else {
-            format!("https://{}.helius-rpc.com/?api-key={}", 
-                self.config.network, 
-                self.config.api_key)
-        }
-    }
-    
     /// Get the WebSocket URL
     fn get_ws_url(&self) -> String {
         if let Some(endpoint) = &self.config.ws_endpoint {
             format!("{}?api-key={}", endpoint, self.config.api_key)
+        } else if let Some(url) = self.provider.ws_url() {
+            url
         } else {
             format!("wss://{}.helius-rpc.com/v0/ws?api-key={}", 
                 self.config.network, 
@@ -98,113 +149,96 @@ impl HeliusClient {
         }
     }
     
+    fn update_channels(&self) -> UpdateChannels {
+        UpdateChannels {
+            accounts: self.account_updates.clone(),
+            transactions: self.transaction_updates.clone(),
+            blocks: self.block_updates.clone(),
+        }
+    }
+    
     /// Connect to the Helius WebSocket endpoint
+    ///
+    /// Fails if the first connection attempt fails; after that the
+    /// connection is kept up in the background until
+    /// [`HeliusClient::disconnect_websocket`] is called.
     pub async fn connect_websocket(&self) -> Result<()> {
         let ws_url = self.get_ws_url();
         
-        tracing::info!("Connecting to Helius WebSocket at {}", ws_url);
+        tracing::info!("Connecting to Helius WebSocket");
         
-        let (ws_stream, _) = connect_async(ws_url).await
+        let (ws_stream, _) = connect_async(ws_url.as_str()).await
             .map_err(|e| anyhow!("Failed to connect to WebSocket: {}", e))?;
         
         tracing::info!("Connected to Helius WebSocket");
         
-        // Split the WebSocket stream
-        let (mut write, mut read) = ws_stream.split();
-        
-        // Create a channel for sending messages to the WebSocket
-        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
-        
-        // Store the sender
-        {
-            let mut connection = self.ws_connection.write().await;
-            *connection = Some(tx);
+        let mut task = self.ws_task.lock().await;
+        if let Some(previous) = task.take() {
+            previous.abort();
         }
         
-        // Create channel clones for the task
-        let account_updates = self.account_updates.clone();
-        let transaction_updates = self.transaction_updates.clone();
-        let block_updates = self.block_updates.clone();
+        let ws_connection = self.ws_connection.clone();
+        let subscriptions = self.subscriptions.clone();
+        let states = self.connection_states.clone();
+        let channels = self.update_channels();
         
-        // Spawn a task to forward messages from the channel to the WebSocket
-        tokio::spawn(async move {
-            while let Some(message) = rx.recv().await {
-                if let Err(e) = write.send(message).await {
-                    tracing::error!("Failed to send WebSocket message: {}", e);
-                    break;
+        *task = Some(tokio::spawn(async move {
+            let mut stream = ws_stream;
+            let mut attempt = 0;
+            loop {
+                // Replace the sender before replaying, so new subscriptions
+                // made meanwhile are not lost either
+                let (tx, rx) = mpsc::channel(100);
+                *ws_connection.write().await = Some(tx.clone());
+                for subscription in subscriptions.read().await.iter() {
+                    let _ = tx.send(Message::Text(subscription_request(subscription).to_string())).await;
                 }
-            }
-            tracing::info!("WebSocket sender task terminated");
-        });
-        
-        // Spawn a task to process incoming WebSocket messages
-        tokio::spawn(async move {
-            while let Some(message_result) = read.next().await {
-                match message_result {
-                    Ok(message) => {
-                        if let Message::Text(text) = message {
-                            match serde_json::from_str::<serde_json::Value>(&text) {
-                                Ok(json) => {
-                                    if let Some(method) = json.get("method").and_then(|m| m.as_str()) {
-                                        match method {
-                                            "accountNotification" => {
-                                                // Process account update
-                                                if let Some(account) = parse_account_update(&json) {
-                                                    let _ = account_updates.send(account);
-                                                }
-                                            },
-                                            "signatureNotification" => {
-                                                // Process transaction update
-                                                if let Some(tx) = parse_transaction_update(&json) {
-                                                    let _ = transaction_updates.send(tx);
-                                                }
-                                            },
-                                            "slotNotification" => {
-                                                // Process block update
-                                                if let Some(block) = parse_block_update(&json) {
-                                                    let _ = block_updates.send(block);
-                                                }
-                                            },
-                                            _ => {
-                                                tracing::debug!("Received unhandled WebSocket message: {}", method);
-                                            }
-                                        }
-                                    }
-                                },
-                                Err(e) => {
-                                    tracing::error!("Failed to parse WebSocket message: {}", e);
-                                }
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        tracing::error!("WebSocket error: {}", e);
-                        break;
+                let _ = states.send(ConnectionState::Connected);
+                
+                run_session(stream, rx, &channels).await;
+                *ws_connection.write().await = None;
+                
+                // Reconnect with exponential backoff
+                stream = loop {
+                    let delay = RECONNECT_INITIAL_DELAY
+                        .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+                        .min(RECONNECT_MAX_DELAY);
+                    attempt += 1;
+                    tracing::warn!("Helius WebSocket disconnected; reconnecting in {:?} (attempt {})", delay, attempt);
+                    let _ = states.send(ConnectionState::Reconnecting { attempt, delay_ms: delay.as_millis() as u64 });
+                    tokio::time::sleep(delay).await;
+                    
+                    match connect_async(ws_url.as_str()).await {
+                        Ok((stream, _)) => break stream,
+                        Err(e) => tracing::warn!("Failed to reconnect to Helius WebSocket: {}", e),
                     }
-                }
+                };
+                tracing::info!("Reconnected to Helius WebSocket after {} attempt(s)", attempt);
+                attempt = 0;
             }
-            tracing::info!("WebSocket receiver task terminated");
-        });
+        }));
         
         Ok(())
     }
     
-    /// Send a JSON-RPC request to Helius
-    pub async fn send_rpc_request<T: Serialize>(&self, request: &T) -> Result<serde_json::Value> {
-        let url = self.get_base_url();
-        
-        let response = self.client.post(&url)
-            .json(request)
-            .send()
-            .await
-            .map_err(|e| anyhow!("HTTP request failed: {}", e))?;
-        
-        if !response.status().is_success() {
-            return Err(anyhow!("HTTP error: {}", response.status()));
+    /// Close the WebSocket connection and stop reconnecting
+    pub async fn disconnect_websocket(&self) {
+        if let Some(task) = self.ws_task.lock().await.take() {
+            task.abort();
+            *self.ws_connection.write().await = None;
+            let _ = self.connection_states.send(ConnectionState::Disconnected);
         }
-        
-        let json = response.json::<serde_json::Value>().await
-            .map_err(|e| anyhow!("Failed to parse JSON response: {}", e))?;
+    }
+    
+    /// Get a subscription to WebSocket connection state changes
+    pub fn connection_states(&self) -> broadcast::Receiver<ConnectionState> {
+        self.connection_states.subscribe()
+    }
+    
+    /// Send a JSON-RPC request to the upstream provider
+    pub async fn send_rpc_request<T: Serialize>(&self, request: &T) -> Result<serde_json::Value> {
+        let request = serde_json::to_value(request)?;
+        let json = self.provider.send(&request).await?;
         
         if let Some(error) = json.get("error") {
             return Err(anyhow!("RPC error: {}", error));
@@ -214,13 +248,19 @@ impl HeliusClient {
     }
     
     /// Send a WebSocket subscription request
+    ///
+    /// The subscription is remembered and sent again after every reconnect,
+    /// including when the connection is down right now.
     pub async fn send_subscription(&self, method: &str, params: Vec<serde_json::Value>) -> Result<()> {
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": method,
-            "params": params
-        });
+        let subscription = Subscription { method: method.to_string(), params };
+        let request = subscription_request(&subscription);
+        
+        {
+            let mut subscriptions = self.subscriptions.write().await;
+            if !subscriptions.contains(&subscription) {
+                subscriptions.push(subscription);
+            }
+        }
         
         let connection = self.ws_connection.read().await;
         if let Some(sender) = &*connection {
@@ -370,6 +410,78 @@ impl HeliusClient {
     }
 }
 
+fn subscription_request(subscription: &Subscription) -> serde_json::Value {
+    serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": subscription.method,
+        "params": subscription.params
+    })
+}
+
+/// Forwards outgoing messages and dispatches notifications until the
+/// connection drops
+async fn run_session<S>(stream: S, mut outgoing: mpsc::Receiver<Message>, channels: &UpdateChannels)
+where
+    S: futures_util::Stream<Item = std::result::Result<Message, tokio_tungstenite::tungstenite::Error>>
+        + futures_util::Sink<Message, Error = tokio_tungstenite::tungstenite::Error>
+        + Unpin,
+{
+    let (mut write, mut read) = stream.split();
+    loop {
+        tokio::select! {
+            message = outgoing.recv() => {
+                let Some(message) = message else { break };
+                if let Err(e) = write.send(message).await {
+                    tracing::error!("Failed to send WebSocket message: {}", e);
+                    break;
+                }
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => dispatch(&text, channels),
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    tracing::error!("WebSocket error: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Publishes a notification to the matching update channel
+fn dispatch(text: &str, channels: &UpdateChannels) {
+    let json = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(json) => json,
+        Err(e) => {
+            tracing::error!("Failed to parse WebSocket message: {}", e);
+            return;
+        }
+    };
+    
+    match json.get("method").and_then(|m| m.as_str()) {
+        Some("accountNotification") => {
+            if let Some(account) = parse_account_update(&json) {
+                let _ = channels.accounts.send(account);
+            }
+        }
+        Some("signatureNotification") => {
+            if let Some(tx) = parse_transaction_update(&json) {
+                let _ = channels.transactions.send(tx);
+            }
+        }
+        Some("slotNotification") => {
+            if let Some(block) = parse_block_update(&json) {
+                let _ = channels.blocks.send(block);
+            }
+        }
+        Some(method) => tracing::debug!("Received unhandled WebSocket message: {}", method),
+        // Subscription confirmations
+        None => {}
+    }
+}
+
 /// Parse an account update from a WebSocket notification
 fn parse_account_update(json: &serde_json::Value) -> Option<AccountData> {
     if let Some(params) = json.get("params")?.as_array() {
@@ -506,17 +618,19 @@ fn parse_transaction_response(signature: &str, json: &serde_json::Value) -> Opti
             })
         }).unwrap_or_default();
         
-        // In a real implementation, we would parse the complete transaction
-        // For now, return a simplified version
+        // Includes the addresses loaded from lookup tables
+        let transaction = result.get("transaction")?;
+        let accounts = lookup_tables::json_account_keys(transaction, meta);
+
         return Some(TransactionData {
             signature: signature.to_string(),
             slot,
             err: err.is_some(),
             status: if err.is_none() { 1 } else { 0 },
             fee,
-            fee_payer: "11111111111111111111111111111111".to_string(), // Placeholder
-            recent_blockhash: "11111111111111111111111111111111".to_string(), // Placeholder
-            accounts: vec![], // Placeholder
+            fee_payer: accounts.first().cloned().unwrap_or_default(),
+            recent_blockhash: transaction["message"]["recentBlockhash"].as_str().unwrap_or_default().to_string(),
+            accounts,
             log_messages,
             block_time,
         });
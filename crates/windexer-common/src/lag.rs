@@ -0,0 +1,218 @@
+//! Chain tip lag monitoring
+//!
+//! [`LagMonitor`] compares the last slot the indexer processed with the
+//! network tip. Local slots come from the [`DataFeed`]; the tip is polled
+//! from an RPC provider with `getSlot` and can also be reported by gossip
+//! through [`LagMonitor::observe_tip`]. Both only move forward.
+//!
+//! The lag is exported as the `windexer_slot_lag` gauge, next to
+//! `windexer_chain_tip_slot` and `windexer_indexed_slot`. Once it exceeds
+//! the threshold a [`AlertKind::SlotLag`] warning is published, and once it
+//! falls back to half the threshold a [`AlertKind::SlotLagRecovered`], so a
+//! lag hovering around the threshold does not flood the alert bus.
+
+use {
+    crate::{
+        alerts::{Alert, AlertBus, AlertKind, Severity},
+        feed::DataFeed,
+        metrics::{self, Collector, IntGauge, MetricSet},
+        rpc_provider::RpcProvider,
+        shutdown::{self, ShutdownToken},
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::json,
+    std::{
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::{sync::broadcast::error::RecvError, task::JoinHandle},
+    tracing::warn,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LagConfig {
+    /// Slots the indexer may trail the tip before a warning is raised
+    pub threshold_slots: u64,
+    /// How often the tip is polled from the RPC provider
+    pub poll_interval_secs: u64,
+    /// URLs every alert is posted to
+    pub webhooks: Vec<String>,
+}
+
+impl Default for LagConfig {
+    fn default() -> Self {
+        Self {
+            threshold_slots: 150,
+            poll_interval_secs: 5,
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+pub struct LagMonitor {
+    config: LagConfig,
+    local_slot: AtomicU64,
+    tip_slot: AtomicU64,
+    lagging: AtomicBool,
+    lag_gauge: IntGauge,
+    tip_gauge: IntGauge,
+    local_gauge: IntGauge,
+    alerts: AlertBus,
+}
+
+impl LagMonitor {
+    pub fn new(config: LagConfig, alerts: AlertBus) -> Self {
+        Self {
+            config,
+            local_slot: AtomicU64::new(0),
+            tip_slot: AtomicU64::new(0),
+            lagging: AtomicBool::new(false),
+            lag_gauge: metrics::gauge("", "slot_lag", "Slots the indexer trails the chain tip"),
+            tip_gauge: metrics::gauge("", "chain_tip_slot", "Latest slot of the network"),
+            local_gauge: metrics::gauge("", "indexed_slot", "Latest slot the indexer processed"),
+            alerts,
+        }
+    }
+
+    pub fn config(&self) -> &LagConfig {
+        &self.config
+    }
+
+    pub fn alerts(&self) -> &AlertBus {
+        &self.alerts
+    }
+
+    /// A slot the indexer processed
+    pub fn observe_local(&self, slot: u64) {
+        if self.local_slot.fetch_max(slot, Ordering::Relaxed) < slot {
+            self.evaluate();
+        }
+    }
+
+    /// A slot the network reached, from RPC or gossip
+    pub fn observe_tip(&self, slot: u64) {
+        if self.tip_slot.fetch_max(slot, Ordering::Relaxed) < slot {
+            self.evaluate();
+        }
+    }
+
+    pub fn local_slot(&self) -> Option<u64> {
+        Some(self.local_slot.load(Ordering::Relaxed)).filter(|slot| *slot > 0)
+    }
+
+    pub fn tip_slot(&self) -> Option<u64> {
+        Some(self.tip_slot.load(Ordering::Relaxed)).filter(|slot| *slot > 0)
+    }
+
+    /// Slots the indexer trails the tip, once both are known
+    pub fn lag(&self) -> Option<u64> {
+        Some(self.tip_slot()?.saturating_sub(self.local_slot()?))
+    }
+
+    pub fn is_lagging(&self) -> bool {
+        self.lagging.load(Ordering::Relaxed)
+    }
+
+    fn evaluate(&self) {
+        let (Some(local_slot), Some(tip_slot)) = (self.local_slot(), self.tip_slot()) else {
+            return;
+        };
+        let lag = tip_slot.saturating_sub(local_slot);
+        self.lag_gauge.set(lag as i64);
+        self.tip_gauge.set(tip_slot as i64);
+        self.local_gauge.set(local_slot as i64);
+
+        let threshold = self.config.threshold_slots;
+        if lag > threshold && !self.lagging.swap(true, Ordering::Relaxed) {
+            self.alerts.publish(Alert::new(
+                Severity::Warning,
+                AlertKind::SlotLag { lag, local_slot, tip_slot, threshold },
+                format!("Indexer is {} slots behind the chain tip at {}, over the threshold of {}", lag, tip_slot, threshold),
+            ));
+        } else if lag <= threshold / 2 && self.lagging.swap(false, Ordering::Relaxed) {
+            self.alerts.publish(Alert::new(
+                Severity::Info,
+                AlertKind::SlotLagRecovered { lag, local_slot, tip_slot, threshold },
+                format!("Indexer caught up to {} slots behind the chain tip", lag),
+            ));
+        }
+    }
+
+    /// Follow the slots of everything published to `feed`
+    pub fn follow_feed(self: &Arc<Self>, feed: &DataFeed, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let monitor = self.clone();
+        let mut events = feed.subscribe();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => monitor.observe_local(event.slot()),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = shutdown::cancelled(shutdown.as_ref()) => return,
+                }
+            }
+        })
+    }
+
+    /// Poll the tip from `provider` every `poll_interval_secs`
+    pub fn poll_tip(self: &Arc<Self>, provider: Arc<dyn RpcProvider>, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let monitor = self.clone();
+        let period = Duration::from_secs(self.config.poll_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown::cancelled(shutdown.as_ref()) => return,
+                }
+                match provider.call("getSlot", json!([{"commitment": "processed"}])).await {
+                    Ok(reply) => match reply.get("result").and_then(|slot| slot.as_u64()) {
+                        Some(slot) => monitor.observe_tip(slot),
+                        None => warn!("{} returned no slot for the chain tip: {}", provider.name(), reply),
+                    },
+                    Err(e) => warn!("Failed to poll the chain tip from {}: {}", provider.name(), e),
+                }
+            }
+        })
+    }
+}
+
+impl MetricSet for LagMonitor {
+    fn collectors(&self) -> Vec<&dyn Collector> {
+        vec![&self.lag_gauge, &self.tip_gauge, &self.local_gauge]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_once_per_episode() {
+        let bus = AlertBus::new();
+        let mut alerts = bus.subscribe();
+        let monitor = LagMonitor::new(LagConfig { threshold_slots: 100, ..LagConfig::default() }, bus);
+
+        monitor.observe_local(1_000);
+        monitor.observe_tip(1_050);
+        assert_eq!(monitor.lag(), Some(50));
+        assert!(alerts.try_recv().is_err());
+
+        monitor.observe_tip(1_200);
+        monitor.observe_tip(1_250);
+        assert!(matches!(alerts.try_recv().unwrap().kind, AlertKind::SlotLag { lag: 200, .. }));
+        assert!(alerts.try_recv().is_err());
+
+        // Back under the threshold, but not yet under half of it
+        monitor.observe_local(1_180);
+        assert!(alerts.try_recv().is_err());
+        monitor.observe_local(1_220);
+        assert!(matches!(alerts.try_recv().unwrap().kind, AlertKind::SlotLagRecovered { lag: 30, .. }));
+    }
+}
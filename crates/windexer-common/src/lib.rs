@@ -1,11 +1,23 @@
+pub mod alerts;
 pub mod config;
+pub mod control;
 pub mod crypto;
+pub mod dlq;
 pub mod errors;
+pub mod feed;
+pub mod filter;
+pub mod lag;
+pub mod logging;
+pub mod logs;
+pub mod lookup_tables;
+pub mod metrics;
+pub mod shutdown;
 pub mod types;
 pub mod utils;
 pub mod helius;
+pub mod rpc_provider;
 
 pub use config::{IndexerConfig, NetworkConfig, StoreConfig};
-pub use errors::{Error, Result};
+pub use errors::{Error, ErrorCode, Result};
 pub use types::*;
 pub use crypto::SerializableKeypair;
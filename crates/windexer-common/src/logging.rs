@@ -0,0 +1,165 @@
+//! Runtime log levels
+//!
+//! Log filters are written as comma separated directives: a bare level sets
+//! the default, and `target=level` the level of a module and everything under
+//! it, like `info,windexer_network::node=debug`. The most specific target
+//! wins.
+//!
+//! [`init`] installs a `tracing` subscriber whose filter sits behind a reload
+//! layer; the returned [`LogFilter`] swaps it while the process runs, which
+//! the admin API exposes as `PUT /admin/log-level`. Components logging through
+//! the `log` crate, like the Geyser plugin, apply the same [`Directives`]
+//! themselves.
+
+use {
+    crate::control::LogControl,
+    anyhow::{anyhow, Context, Result},
+    log::LevelFilter,
+    std::{
+        collections::BTreeMap,
+        fmt,
+        str::FromStr,
+        sync::{Arc, RwLock},
+    },
+    tracing_subscriber::{fmt as fmt_layer, prelude::*, reload, EnvFilter, Registry},
+};
+
+/// Default level and per-module levels of a log filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directives {
+    default: LevelFilter,
+    modules: BTreeMap<String, LevelFilter>,
+}
+
+impl Default for Directives {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+impl Directives {
+    /// Set the level of `module`, or the default level without one
+    pub fn set(&mut self, module: Option<&str>, level: LevelFilter) {
+        match module {
+            Some(module) => {
+                self.modules.insert(module.to_string(), level);
+            }
+            None => self.default = level,
+        }
+    }
+
+    /// Level of the most specific directive matching `target`
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    /// Most verbose level of any directive
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules.values().copied().chain([self.default]).max().unwrap_or(self.default)
+    }
+}
+
+impl FromStr for Directives {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut directives = Directives::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let (module, level) = match directive.split_once('=') {
+                Some((module, level)) => (Some(module.trim()), level.trim()),
+                None => (None, directive),
+            };
+            let level = level.parse().map_err(|_| anyhow!("Invalid log level in '{}'", directive))?;
+            directives.set(module, level);
+        }
+        Ok(directives)
+    }
+}
+
+impl fmt::Display for Directives {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.to_string().to_lowercase())?;
+        for (module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.to_string().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// Filter of the global `tracing` subscriber, changeable at runtime
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    directives: RwLock<Directives>,
+}
+
+impl fmt::Debug for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogFilter").field("directives", &self.directives()).finish()
+    }
+}
+
+impl LogControl for LogFilter {
+    fn directives(&self) -> String {
+        self.directives.read().map(|d| d.to_string()).unwrap_or_default()
+    }
+
+    fn set_directives(&self, directives: &str) -> Result<()> {
+        let parsed: Directives = directives.parse()?;
+        let filter = EnvFilter::try_new(parsed.to_string())?;
+        self.handle.reload(filter).context("Failed to reload the log filter")?;
+        if let Ok(mut current) = self.directives.write() {
+            *current = parsed;
+        }
+        Ok(())
+    }
+}
+
+/// Install the global `tracing` subscriber, logging with `RUST_LOG` or else
+/// `default` until the returned filter is changed
+pub fn init(default: &str) -> Result<Arc<LogFilter>> {
+    let directives: Directives = std::env::var("RUST_LOG")
+        .unwrap_or_else(|_| default.to_string())
+        .parse()?;
+    let (filter, handle) = reload::Layer::new(EnvFilter::try_new(directives.to_string())?);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer::layer())
+        .try_init()
+        .context("Failed to install the tracing subscriber")?;
+
+    Ok(Arc::new(LogFilter {
+        handle,
+        directives: RwLock::new(directives),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_directive_wins() {
+        let mut directives: Directives = "warn, windexer_network=info".parse().unwrap();
+        directives.set(Some("windexer_network::node"), LevelFilter::Debug);
+
+        assert_eq!(directives.level_for("windexer_api::rest"), LevelFilter::Warn);
+        assert_eq!(directives.level_for("windexer_network::gossip"), LevelFilter::Info);
+        assert_eq!(directives.level_for("windexer_network::node::registry"), LevelFilter::Debug);
+        assert_eq!(directives.level_for("windexer_networking"), LevelFilter::Warn);
+        assert_eq!(directives.max_level(), LevelFilter::Debug);
+        assert_eq!(directives.to_string(), "warn,windexer_network=info,windexer_network::node=debug");
+        assert!("info,windexer_api=loud".parse::<Directives>().is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Program log parsing
+//!
+//! The runtime frames the logs of every program invocation: a
+//! `Program <id> invoke [<depth>]` line when it starts and a
+//! `Program <id> success` or `Program <id> failed: <error>` line when it
+//! returns. Lines in between belong to the innermost open invocation, such
+//! as `Program log:` messages, `Program data:` lines with the base64 chunks a
+//! program passed to `sol_log_data`, and compute unit usage. Anchor programs
+//! emit events as one such chunk: an 8 byte discriminator followed by the
+//! Borsh encoded event.
+//!
+//! Logs are cut off past the validator's size limit with a `Log truncated`
+//! line; the invocations still open at that point never see their end.
+
+use {solana_sdk::pubkey::Pubkey, std::str::FromStr};
+
+/// What a log line records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogKind {
+    Invoke,
+    Success,
+    Failure(String),
+    /// A `Program log:` message
+    Log(String),
+    /// Chunks of a `Program data:` line, base64 decoded
+    Data(Vec<Vec<u8>>),
+    /// Data set with `sol_set_return_data`
+    Return(Vec<u8>),
+    /// Compute units the invocation consumed and its budget at the start
+    Consumed { units: u64, budget: u64 },
+    Truncated,
+    /// Any other line
+    Other(String),
+}
+
+/// A log line with the invocation that wrote it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramLog {
+    /// Position of the line in the transaction's logs
+    pub line: usize,
+    /// Program of the innermost open invocation; `None` outside invocations
+    pub program_id: Option<Pubkey>,
+    /// Invocation depth, 1 for top-level instructions and 0 outside
+    /// invocations
+    pub depth: u32,
+    pub kind: LogKind,
+}
+
+/// `Program <id> <rest>`, for lines naming a program
+fn program_line(line: &str) -> Option<(Pubkey, &str)> {
+    let (program_id, rest) = line.strip_prefix("Program ")?.split_once(' ')?;
+    Some((Pubkey::from_str(program_id).ok()?, rest))
+}
+
+fn consumed(rest: &str) -> Option<LogKind> {
+    let (units, budget) = rest.strip_prefix("consumed ")?.strip_suffix(" compute units")?.split_once(" of ")?;
+    Some(LogKind::Consumed { units: units.parse().ok()?, budget: budget.parse().ok()? })
+}
+
+/// Attribute every line of a transaction's logs to the invocation that wrote
+/// it. Lines the runtime did not frame as expected are kept as
+/// [`LogKind::Other`] rather than failing the transaction.
+pub fn parse_logs(logs: &[String]) -> Vec<ProgramLog> {
+    let mut stack: Vec<Pubkey> = Vec::new();
+    let mut parsed = Vec::with_capacity(logs.len());
+
+    for (line, log) in logs.iter().enumerate() {
+        let mut program_id = stack.last().copied();
+        let mut depth = stack.len() as u32;
+
+        let kind = if let Some(message) = log.strip_prefix("Program log: ") {
+            LogKind::Log(message.to_string())
+        } else if let Some(data) = log.strip_prefix("Program data: ") {
+            LogKind::Data(data.split(' ').filter_map(|chunk| base64::decode(chunk).ok()).collect())
+        } else if let Some(rest) = log.strip_prefix("Program return: ") {
+            rest.split_once(' ')
+                .and_then(|(_, data)| base64::decode(data).ok())
+                .map_or_else(|| LogKind::Other(log.clone()), LogKind::Return)
+        } else if log == "Log truncated" {
+            LogKind::Truncated
+        } else if let Some((program, rest)) = program_line(log) {
+            if let Some(invoked) = rest.strip_prefix("invoke [").and_then(|rest| rest.strip_suffix(']')) {
+                stack.push(program);
+                program_id = Some(program);
+                depth = invoked.parse().unwrap_or(stack.len() as u32);
+                LogKind::Invoke
+            } else if rest == "success" || rest.starts_with("failed") {
+                // The returning invocation is the innermost one with this program
+                if let Some(position) = stack.iter().rposition(|open| *open == program) {
+                    depth = position as u32 + 1;
+                    stack.truncate(position);
+                }
+                program_id = Some(program);
+                match rest.strip_prefix("failed: ") {
+                    Some(error) => LogKind::Failure(error.to_string()),
+                    None if rest == "success" => LogKind::Success,
+                    None => LogKind::Failure(rest.to_string()),
+                }
+            } else {
+                program_id = Some(program);
+                consumed(rest).unwrap_or_else(|| LogKind::Other(log.clone()))
+            }
+        } else {
+            LogKind::Other(log.clone())
+        };
+
+        let truncated = kind == LogKind::Truncated;
+        parsed.push(ProgramLog { line, program_id, depth, kind });
+        if truncated {
+            break;
+        }
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_lines_to_invocations() {
+        let (outer, inner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", outer),
+            "Program log: Instruction: Swap".to_string(),
+            format!("Program {} invoke [2]", inner),
+            format!("Program {} consumed 120 of 190000 compute units", inner),
+            format!("Program {} success", inner),
+            format!("Program data: {} {}", base64::encode([1, 2]), base64::encode([3])),
+            format!("Program {} failed: custom program error: 0x1", outer),
+        ]
+        .into();
+
+        let parsed = parse_logs(&logs);
+        let summary: Vec<_> = parsed.iter().map(|log| (log.program_id, log.depth)).collect();
+        assert_eq!(summary, vec![
+            (Some(outer), 1),
+            (Some(outer), 1),
+            (Some(inner), 2),
+            (Some(inner), 2),
+            (Some(inner), 2),
+            (Some(outer), 1),
+            (Some(outer), 1),
+        ]);
+        assert_eq!(parsed[3].kind, LogKind::Consumed { units: 120, budget: 190_000 });
+        assert_eq!(parsed[5].kind, LogKind::Data(vec![vec![1, 2], vec![3]]));
+        assert_eq!(parsed[6].kind, LogKind::Failure("custom program error: 0x1".to_string()));
+    }
+}
@@ -0,0 +1,223 @@
+//! Address lookup tables
+//!
+//! A version 0 message lists only its static account keys; each of its
+//! address table lookups names a lookup table and the indexes of the
+//! addresses loaded from it. The full account list, which instruction
+//! account indexes resolve against, is the static keys, then every writable
+//! loaded address, then every readonly one.
+//!
+//! Validators hand geyser plugins messages with the lookups resolved, and
+//! RPC nodes return the loaded addresses in the `loadedAddresses` field of
+//! the transaction meta. Where neither is available, lookups are resolved
+//! with [`LookupTables`], which keeps the contents of the tables seen as
+//! account updates or fetched on demand. Tables only grow until they are
+//! closed, so their current contents resolve lookups of any transaction
+//! since the table was last extended.
+
+use {
+    crate::types::AccountData,
+    serde_json::Value,
+    solana_sdk::{
+        address_lookup_table::state::AddressLookupTable,
+        message::v0::{LoadedAddresses, MessageAddressTableLookup},
+        pubkey::Pubkey,
+    },
+    std::{collections::HashMap, str::FromStr, sync::RwLock},
+};
+
+pub use solana_sdk::address_lookup_table::program::ID as ADDRESS_LOOKUP_TABLE_PROGRAM_ID;
+
+/// Addresses held by a lookup table account
+pub fn parse_lookup_table(data: &[u8]) -> Option<Vec<Pubkey>> {
+    AddressLookupTable::deserialize(data).ok().map(|table| table.addresses.to_vec())
+}
+
+/// Static keys followed by the loaded addresses, in the order the runtime
+/// resolves instruction account indexes against
+pub fn resolved_account_keys(static_keys: &[Pubkey], loaded: &LoadedAddresses) -> Vec<Pubkey> {
+    static_keys.iter().chain(&loaded.writable).chain(&loaded.readonly).copied().collect()
+}
+
+fn pubkeys(value: &Value) -> Option<Vec<Pubkey>> {
+    value.as_array()?.iter().map(|key| Pubkey::from_str(key.as_str()?).ok()).collect()
+}
+
+/// The `loadedAddresses` of an RPC transaction meta, if the node returned them
+pub fn loaded_addresses_from_json(meta: &Value) -> Option<LoadedAddresses> {
+    let loaded = meta.get("loadedAddresses")?;
+    Some(LoadedAddresses {
+        writable: pubkeys(&loaded["writable"])?,
+        readonly: pubkeys(&loaded["readonly"])?,
+    })
+}
+
+/// The `addressTableLookups` of an RPC message, empty for legacy messages
+pub fn lookups_from_json(message: &Value) -> Vec<MessageAddressTableLookup> {
+    let indexes = |value: &Value| -> Option<Vec<u8>> {
+        value.as_array()?.iter().map(|index| u8::try_from(index.as_u64()?).ok()).collect()
+    };
+    message["addressTableLookups"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|lookup| {
+            Some(MessageAddressTableLookup {
+                account_key: Pubkey::from_str(lookup["accountKey"].as_str()?).ok()?,
+                writable_indexes: indexes(&lookup["writableIndexes"])?,
+                readonly_indexes: indexes(&lookup["readonlyIndexes"])?,
+            })
+        })
+        .collect()
+}
+
+/// Contents of lookup tables by address
+#[derive(Debug, Default)]
+pub struct LookupTables {
+    tables: RwLock<HashMap<Pubkey, Vec<Pubkey>>>,
+}
+
+impl LookupTables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps the contents of a lookup table account, or forgets a closed
+    /// one; returns whether the account is a lookup table
+    pub fn apply_account(&self, account: &AccountData) -> bool {
+        if account.owner != ADDRESS_LOOKUP_TABLE_PROGRAM_ID {
+            return false;
+        }
+        match parse_lookup_table(&account.data).filter(|_| account.lamports > 0) {
+            Some(addresses) => self.insert(account.pubkey, addresses),
+            None => {
+                if let Ok(mut tables) = self.tables.write() {
+                    tables.remove(&account.pubkey);
+                }
+            }
+        }
+        true
+    }
+
+    /// Keeps a table unless a longer version of it is already known
+    pub fn insert(&self, table: Pubkey, addresses: Vec<Pubkey>) {
+        let Ok(mut tables) = self.tables.write() else {
+            return;
+        };
+        let known = tables.entry(table).or_default();
+        if addresses.len() >= known.len() {
+            *known = addresses;
+        }
+    }
+
+    pub fn get(&self, table: &Pubkey) -> Option<Vec<Pubkey>> {
+        self.tables.read().ok()?.get(table).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.read().map(|tables| tables.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Tables named by `lookups` whose contents are not known
+    pub fn missing(&self, lookups: &[MessageAddressTableLookup]) -> Vec<Pubkey> {
+        let Ok(tables) = self.tables.read() else {
+            return Vec::new();
+        };
+        let mut missing: Vec<Pubkey> = lookups
+            .iter()
+            .map(|lookup| lookup.account_key)
+            .filter(|table| !tables.contains_key(table))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+
+    /// Addresses loaded by `lookups`; `None` if a table is unknown or too
+    /// short for an index
+    pub fn resolve(&self, lookups: &[MessageAddressTableLookup]) -> Option<LoadedAddresses> {
+        let tables = self.tables.read().ok()?;
+        let mut loaded = LoadedAddresses::default();
+        for lookup in lookups {
+            let table = tables.get(&lookup.account_key)?;
+            let load = |indexes: &[u8]| -> Option<Vec<Pubkey>> {
+                indexes.iter().map(|index| table.get(*index as usize).copied()).collect()
+            };
+            loaded.writable.extend(load(&lookup.writable_indexes)?);
+            loaded.readonly.extend(load(&lookup.readonly_indexes)?);
+        }
+        Some(loaded)
+    }
+
+    /// Sets the `loadedAddresses` of an RPC transaction meta that lacks
+    /// them from the lookups of its `json` encoded transaction; returns
+    /// whether the meta has them afterwards
+    pub fn fill_loaded_addresses(&self, transaction: &Value, meta: &mut Value) -> bool {
+        if meta.get("loadedAddresses").is_some() {
+            return true;
+        }
+        let (Some(meta), Some(loaded)) = (meta.as_object_mut(), self.resolve(&lookups_from_json(&transaction["message"]))) else {
+            return false;
+        };
+        let encode = |keys: &[Pubkey]| keys.iter().map(Pubkey::to_string).collect::<Vec<_>>();
+        meta.insert("loadedAddresses".to_string(), serde_json::json!({
+            "writable": encode(&loaded.writable),
+            "readonly": encode(&loaded.readonly),
+        }));
+        true
+    }
+}
+
+/// Account keys of a transaction returned by `getTransaction` or `getBlock`.
+/// The `accountKeys` of a `jsonParsed` message already include the loaded
+/// addresses; those of a `json` one are followed by the meta's
+/// `loadedAddresses`, if it has them.
+pub fn json_account_keys(transaction: &Value, meta: &Value) -> Vec<String> {
+    let keys = transaction["message"]["accountKeys"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let mut account_keys: Vec<String> = keys
+        .iter()
+        .filter_map(|key| key.as_str().or_else(|| key["pubkey"].as_str()))
+        .map(str::to_string)
+        .collect();
+    if keys.iter().all(Value::is_string) {
+        if let Some(loaded) = loaded_addresses_from_json(meta) {
+            account_keys.extend(loaded.writable.iter().chain(&loaded.readonly).map(Pubkey::to_string));
+        }
+    }
+    account_keys
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, serde_json::json};
+
+    #[test]
+    fn resolves_lookups_from_meta_or_known_tables() {
+        let table = Pubkey::new_unique();
+        let addresses: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let payer = Pubkey::new_unique();
+        let transaction = json!({
+            "message": {
+                "accountKeys": [payer.to_string()],
+                "addressTableLookups": [{ "accountKey": table.to_string(), "writableIndexes": [2], "readonlyIndexes": [0] }],
+            }
+        });
+        let expected = vec![payer.to_string(), addresses[2].to_string(), addresses[0].to_string()];
+
+        let tables = LookupTables::new();
+        let mut meta = json!({});
+        assert!(!tables.fill_loaded_addresses(&transaction, &mut meta));
+        assert_eq!(json_account_keys(&transaction, &meta), vec![payer.to_string()]);
+        assert_eq!(tables.missing(&lookups_from_json(&transaction["message"])), vec![table]);
+
+        tables.insert(table, addresses.clone());
+        assert!(tables.fill_loaded_addresses(&transaction, &mut meta));
+        assert_eq!(json_account_keys(&transaction, &meta), expected);
+
+        tables.insert(table, addresses[..1].to_vec());
+        assert_eq!(tables.get(&table), Some(addresses));
+    }
+}
@@ -0,0 +1,209 @@
+//! Prometheus metrics
+//!
+//! Every component defines its metrics with the constructors here, which name
+//! them `windexer_<subsystem>_<name>`, and groups them in a [`MetricSet`].
+//! The process creates one [`MetricsRegistry`] and hands it to each component
+//! to register its set, so a single `/metrics` endpoint renders everything:
+//! the API serves it over axum, the Geyser plugin over warp.
+//!
+//! Gauges that are cheaper to read than to keep current, such as queue
+//! depths, are refreshed by [`MetricSet::sample`] whenever the registry is
+//! scraped.
+//!
+//! Labels whose values come from the data, such as program ids, go through a
+//! [`LabelLimit`] so a busy chain cannot create an unbounded number of series.
+
+use {
+    prometheus::{core::Desc, exponential_buckets, proto::MetricFamily, Encoder, HistogramOpts, Opts, Registry, TextEncoder},
+    std::{
+        collections::HashSet,
+        sync::{Arc, RwLock},
+    },
+};
+
+pub use prometheus::{
+    core::Collector, Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Result,
+};
+
+/// Prefix of every metric name
+pub const NAMESPACE: &str = "windexer";
+
+/// Content type of [`MetricsRegistry::render`]
+pub const TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Metrics of one component, registered together
+pub trait MetricSet: Send + Sync + 'static {
+    fn collectors(&self) -> Vec<&dyn Collector>;
+
+    /// Refresh gauges sampled at scrape time
+    fn sample(&self) {}
+}
+
+fn opts(subsystem: &str, name: &str, help: &str) -> Opts {
+    Opts::new(name, help).namespace(NAMESPACE).subsystem(subsystem)
+}
+
+/// Counter named `windexer_<subsystem>_<name>`; `name` should end in `_total`
+pub fn counter(subsystem: &str, name: &str, help: &str) -> IntCounter {
+    IntCounter::with_opts(opts(subsystem, name, help)).expect("valid counter definition")
+}
+
+pub fn counter_vec(subsystem: &str, name: &str, help: &str, labels: &[&str]) -> IntCounterVec {
+    IntCounterVec::new(opts(subsystem, name, help), labels).expect("valid counter definition")
+}
+
+pub fn gauge(subsystem: &str, name: &str, help: &str) -> IntGauge {
+    IntGauge::with_opts(opts(subsystem, name, help)).expect("valid gauge definition")
+}
+
+pub fn gauge_vec(subsystem: &str, name: &str, help: &str, labels: &[&str]) -> IntGaugeVec {
+    IntGaugeVec::new(opts(subsystem, name, help), labels).expect("valid gauge definition")
+}
+
+pub fn histogram_vec(subsystem: &str, name: &str, help: &str, labels: &[&str], buckets: Vec<f64>) -> HistogramVec {
+    let opts = HistogramOpts::new(name, help)
+        .namespace(NAMESPACE)
+        .subsystem(subsystem)
+        .buckets(buckets);
+    HistogramVec::new(opts, labels).expect("valid histogram definition")
+}
+
+/// Latency buckets from 100µs to about 3s, in seconds
+pub fn latency_buckets() -> Vec<f64> {
+    exponential_buckets(0.0001, 2.0, 16).expect("valid latency buckets")
+}
+
+/// Value of a limited label once its limit is reached
+pub const OTHER_LABEL: &str = "other";
+
+/// Caps the distinct values of a label: the first `limit` values are kept
+/// and every later one is reported as [`OTHER_LABEL`]
+#[derive(Debug)]
+pub struct LabelLimit {
+    limit: usize,
+    seen: RwLock<HashSet<String>>,
+}
+
+impl LabelLimit {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            seen: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// The label to report `value` under
+    pub fn label<'a>(&self, value: &'a str) -> &'a str {
+        if self.seen.read().map(|seen| seen.contains(value)).unwrap_or(false) {
+            return value;
+        }
+        match self.seen.write() {
+            Ok(mut seen) if seen.len() < self.limit => {
+                seen.insert(value.to_string());
+                value
+            }
+            Ok(seen) if seen.contains(value) => value,
+            _ => OTHER_LABEL,
+        }
+    }
+}
+
+/// Registry every component of a process registers its metrics with
+#[derive(Clone, Default)]
+pub struct MetricsRegistry {
+    registry: Registry,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a component's metrics; fails when a name is taken
+    pub fn register<M: MetricSet>(&self, metrics: Arc<M>) -> Result<()> {
+        self.registry.register(Box::new(SetCollector(metrics)))
+    }
+
+    /// The underlying registry, for collectors outside a [`MetricSet`]
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub fn gather(&self) -> Vec<MetricFamily> {
+        self.registry.gather()
+    }
+
+    /// Every registered metric in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.gather(), &mut out) {
+            tracing::warn!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(out).unwrap_or_default()
+    }
+}
+
+impl std::fmt::Debug for MetricsRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsRegistry").finish_non_exhaustive()
+    }
+}
+
+/// Samples a set whenever the registry is gathered
+struct SetCollector<M>(Arc<M>);
+
+impl<M: MetricSet> Collector for SetCollector<M> {
+    fn desc(&self) -> Vec<&Desc> {
+        self.0.collectors().into_iter().flat_map(|c| c.desc()).collect()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        self.0.sample();
+        self.0.collectors().into_iter().flat_map(|c| c.collect()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Requests {
+        served: IntCounter,
+        open: IntGauge,
+    }
+
+    impl MetricSet for Requests {
+        fn collectors(&self) -> Vec<&dyn Collector> {
+            vec![&self.served, &self.open]
+        }
+
+        fn sample(&self) {
+            self.open.set(3);
+        }
+    }
+
+    #[test]
+    fn renders_registered_sets() {
+        let registry = MetricsRegistry::new();
+        let requests = Arc::new(Requests {
+            served: counter("test", "requests_total", "Requests served"),
+            open: gauge("test", "open_connections", "Open connections"),
+        });
+        requests.served.inc_by(2);
+        registry.register(requests.clone()).unwrap();
+        assert!(registry.register(requests).is_err());
+
+        let text = registry.render();
+        assert!(text.contains("windexer_test_requests_total 2\n"));
+        assert!(text.contains("windexer_test_open_connections 3\n"));
+    }
+
+    #[test]
+    fn limits_label_values() {
+        let limit = LabelLimit::new(2);
+        assert_eq!(limit.label("a"), "a");
+        assert_eq!(limit.label("b"), "b");
+        assert_eq!(limit.label("c"), OTHER_LABEL);
+        assert_eq!(limit.label("a"), "a");
+    }
+}
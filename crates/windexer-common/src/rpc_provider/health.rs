@@ -0,0 +1,186 @@
+//! Upstream health tracking
+//!
+//! Each provider behind a [`MultiplexProvider`](super::MultiplexProvider) has
+//! a circuit breaker fed by both real requests and periodic `getHealth`
+//! probes. After `failure_threshold` failures in a row the circuit opens and
+//! requests skip the provider; once `cooldown_secs` have passed it is tried
+//! again, and the next success closes it.
+
+use {
+    serde::{Deserialize, Serialize},
+    std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    },
+};
+
+/// Weight of the newest sample in the latency and error rate averages
+const EWMA_ALPHA: f64 = 0.2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Consecutive failures that open a provider's circuit
+    pub failure_threshold: u32,
+    /// Seconds an open circuit is skipped before the provider is retried
+    pub cooldown_secs: u64,
+    /// Seconds between `getHealth` probes; 0 disables probing
+    pub probe_interval_secs: u64,
+    /// Seconds a probe may take before it counts as a failure
+    pub probe_timeout_secs: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 3,
+            cooldown_secs: 30,
+            probe_interval_secs: 10,
+            probe_timeout_secs: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Serving requests
+    Closed,
+    /// Skipped until the cooldown ends
+    Open,
+    /// Cooldown over; the next result decides whether it closes again
+    HalfOpen,
+}
+
+/// Health of one upstream provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamHealth {
+    pub name: String,
+    pub state: CircuitState,
+    /// Moving average of successful request latency
+    pub latency_ms: Option<f64>,
+    /// Moving average of the share of failed requests
+    pub error_rate: f64,
+    pub requests: u64,
+    pub errors: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct State {
+    open_until: Option<Instant>,
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    requests: u64,
+    errors: u64,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+}
+
+/// Circuit breaker and statistics for one provider
+#[derive(Debug)]
+pub(super) struct EndpointHealth {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl EndpointHealth {
+    pub(super) fn new(config: &HealthConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            state: Mutex::new(State {
+                open_until: None,
+                latency_ms: None,
+                error_rate: 0.0,
+                requests: 0,
+                errors: 0,
+                consecutive_failures: 0,
+                last_error: None,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("upstream health lock poisoned")
+    }
+
+    fn circuit(state: &State, now: Instant) -> CircuitState {
+        match state.open_until {
+            None => CircuitState::Closed,
+            Some(until) if now < until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether requests should be sent to the provider
+    pub(super) fn available(&self) -> bool {
+        Self::circuit(&self.lock(), Instant::now()) != CircuitState::Open
+    }
+
+    pub(super) fn record_success(&self, latency: Duration) {
+        let mut state = self.lock();
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        state.requests += 1;
+        state.latency_ms = Some(match state.latency_ms {
+            Some(average) => average + EWMA_ALPHA * (latency_ms - average),
+            None => latency_ms,
+        });
+        state.error_rate *= 1.0 - EWMA_ALPHA;
+        state.consecutive_failures = 0;
+        state.open_until = None;
+    }
+
+    pub(super) fn record_failure(&self, error: &anyhow::Error) {
+        let mut state = self.lock();
+        let now = Instant::now();
+        state.requests += 1;
+        state.errors += 1;
+        state.error_rate += EWMA_ALPHA * (1.0 - state.error_rate);
+        state.consecutive_failures += 1;
+        state.last_error = Some(format!("{:#}", error));
+
+        // A failed retry after the cooldown reopens the circuit straight away
+        let retrying = Self::circuit(&state, now) == CircuitState::HalfOpen;
+        if retrying || state.consecutive_failures >= self.failure_threshold {
+            state.open_until = Some(now + self.cooldown);
+        }
+    }
+
+    pub(super) fn snapshot(&self, name: &str) -> UpstreamHealth {
+        let state = self.lock();
+        UpstreamHealth {
+            name: name.to_string(),
+            state: Self::circuit(&state, Instant::now()),
+            latency_ms: state.latency_ms,
+            error_rate: state.error_rate,
+            requests: state.requests,
+            errors: state.errors,
+            consecutive_failures: state.consecutive_failures,
+            last_error: state.last_error.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, anyhow::anyhow};
+
+    #[test]
+    fn opens_after_threshold_and_closes_on_success() {
+        let health = EndpointHealth::new(&HealthConfig { failure_threshold: 2, cooldown_secs: 0, ..Default::default() });
+        health.record_failure(&anyhow!("timeout"));
+        assert_eq!(health.snapshot("a").state, CircuitState::Closed);
+
+        health.record_failure(&anyhow!("timeout"));
+        // A zero cooldown ends immediately, so the circuit is ready for a retry
+        assert_eq!(health.snapshot("a").state, CircuitState::HalfOpen);
+        assert!(health.available());
+
+        health.record_success(Duration::from_millis(40));
+        let snapshot = health.snapshot("a");
+        assert_eq!((snapshot.state, snapshot.consecutive_failures, snapshot.errors), (CircuitState::Closed, 0, 2));
+    }
+}
@@ -0,0 +1,421 @@
+//! Upstream RPC providers
+//!
+//! Everything that reads chain data from an RPC node goes through
+//! [`RpcProvider`], so a deployment can use Helius, Triton, QuickNode or its
+//! own validator without code changes. [`RpcProviderConfig`] selects one:
+//!
+//! ```json
+//! { "provider": "multi", "strategy": "failover", "providers": [
+//!     { "provider": "rpc", "url": "https://example.rpcpool.com/<token>" },
+//!     { "provider": "helius", "api_key": "..." }
+//! ] }
+//! ```
+//!
+//! Providers return the whole JSON-RPC response, so callers still see error
+//! objects the node answered with. Only transport failures are errors, and
+//! those are what [`MultiplexProvider`] moves on to the next provider for.
+//! It also tracks each provider's health and stops sending requests to
+//! failing ones for a while; see [`health`].
+
+pub mod health;
+
+pub use health::{CircuitState, HealthConfig, UpstreamHealth};
+
+use {
+    anyhow::{anyhow, bail, Context, Result},
+    async_trait::async_trait,
+    serde::{Deserialize, Serialize},
+    serde_json::{json, Value},
+    std::{
+        fmt,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Weak,
+        },
+        time::{Duration, Instant},
+    },
+    tracing::{info, warn},
+    health::EndpointHealth,
+};
+
+/// A source of Solana JSON-RPC answers
+#[async_trait]
+pub trait RpcProvider: Send + Sync + fmt::Debug {
+    /// Name for logs; never contains credentials
+    fn name(&self) -> &str;
+
+    /// Sends one JSON-RPC request and returns the whole response
+    async fn send(&self, request: &Value) -> Result<Value>;
+
+    /// Calls `method`, returning the whole response including any error object
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        self.send(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }))
+        .await
+    }
+
+    /// WebSocket endpoint for subscriptions, if the provider has one
+    fn ws_url(&self) -> Option<String> {
+        None
+    }
+
+    /// Health of the endpoints behind this provider, if it tracks any
+    fn health(&self) -> Vec<UpstreamHealth> {
+        Vec::new()
+    }
+}
+
+/// Any Solana JSON-RPC endpoint, such as Triton, QuickNode or a private node
+pub struct JsonRpcProvider {
+    name: String,
+    url: String,
+    ws_url: Option<String>,
+    client: reqwest::Client,
+}
+
+impl JsonRpcProvider {
+    pub fn new(url: impl Into<String>) -> Self {
+        let url = url.into();
+        Self {
+            name: redact(&url),
+            url,
+            ws_url: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_ws_url(mut self, ws_url: impl Into<String>) -> Self {
+        self.ws_url = Some(ws_url.into());
+        self
+    }
+}
+
+impl fmt::Debug for JsonRpcProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonRpcProvider").field("name", &self.name).finish()
+    }
+}
+
+#[async_trait]
+impl RpcProvider for JsonRpcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value> {
+        let response = self.client.post(&self.url)
+            .json(request)
+            .send()
+            .await
+            .with_context(|| format!("Request to {} failed", self.name))?;
+
+        // JSON-RPC errors come back with 200; anything else is the endpoint failing
+        let status = response.status();
+        if !status.is_success() {
+            bail!("{} answered HTTP {}", self.name, status);
+        }
+
+        response.json().await
+            .with_context(|| format!("Invalid JSON from {}", self.name))
+    }
+
+    fn ws_url(&self) -> Option<String> {
+        self.ws_url.clone()
+    }
+}
+
+/// Helius, addressed by API key and network
+pub struct HeliusProvider {
+    inner: JsonRpcProvider,
+}
+
+impl HeliusProvider {
+    pub fn new(api_key: &str, network: &str) -> Self {
+        let mut inner = JsonRpcProvider::new(format!("https://{}.helius-rpc.com/?api-key={}", network, api_key))
+            .with_ws_url(format!("wss://{}.helius-rpc.com/?api-key={}", network, api_key));
+        inner.name = format!("helius-{}", network);
+        Self { inner }
+    }
+}
+
+impl fmt::Debug for HeliusProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeliusProvider").field("name", &self.inner.name).finish()
+    }
+}
+
+#[async_trait]
+impl RpcProvider for HeliusProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value> {
+        self.inner.send(request).await
+    }
+
+    fn ws_url(&self) -> Option<String> {
+        self.inner.ws_url()
+    }
+}
+
+/// How [`MultiplexProvider`] picks the provider to try first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultiplexStrategy {
+    /// Always start with the first provider; later ones are fallbacks
+    #[default]
+    Failover,
+    /// Rotate the first provider per request to spread load
+    RoundRobin,
+}
+
+struct Endpoint {
+    provider: Arc<dyn RpcProvider>,
+    health: EndpointHealth,
+}
+
+/// Spreads requests over several providers, skipping unhealthy ones and
+/// retrying the next one when a provider cannot be reached
+pub struct MultiplexProvider {
+    endpoints: Vec<Endpoint>,
+    strategy: MultiplexStrategy,
+    config: HealthConfig,
+    next: AtomicUsize,
+}
+
+impl fmt::Debug for MultiplexProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiplexProvider")
+            .field("providers", &self.endpoints.iter().map(|endpoint| endpoint.provider.name()).collect::<Vec<_>>())
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
+
+impl MultiplexProvider {
+    pub fn new(providers: Vec<Arc<dyn RpcProvider>>, strategy: MultiplexStrategy) -> Result<Self> {
+        Self::with_health_config(providers, strategy, HealthConfig::default())
+    }
+
+    pub fn with_health_config(providers: Vec<Arc<dyn RpcProvider>>, strategy: MultiplexStrategy, config: HealthConfig) -> Result<Self> {
+        if providers.is_empty() {
+            bail!("A multiplexed provider needs at least one provider");
+        }
+        let endpoints = providers.into_iter()
+            .map(|provider| Endpoint { provider, health: EndpointHealth::new(&config) })
+            .collect();
+        Ok(Self { endpoints, strategy, config, next: AtomicUsize::new(0) })
+    }
+
+    /// Endpoints in the order this request should try them; when every
+    /// circuit is open, all of them, since a stale answer beats none
+    fn order(&self) -> Vec<&Endpoint> {
+        let start = match self.strategy {
+            MultiplexStrategy::Failover => 0,
+            MultiplexStrategy::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.endpoints.len(),
+        };
+        let rotated = self.endpoints.iter().cycle().skip(start).take(self.endpoints.len());
+        let available: Vec<_> = rotated.clone().filter(|endpoint| endpoint.health.available()).collect();
+        if available.is_empty() {
+            rotated.collect()
+        } else {
+            available
+        }
+    }
+
+    /// Probes every provider with `getHealth` every `probe_interval_secs`
+    /// until the provider is dropped
+    pub fn spawn_health_checks(self: &Arc<Self>) {
+        if self.config.probe_interval_secs == 0 {
+            return;
+        }
+        let interval = Duration::from_secs(self.config.probe_interval_secs);
+        let timeout = Duration::from_secs(self.config.probe_timeout_secs.max(1));
+        let multiplex: Weak<Self> = Arc::downgrade(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(multiplex) = multiplex.upgrade() else { break };
+                let probes = multiplex.endpoints.iter().map(|endpoint| probe(endpoint, timeout));
+                futures::future::join_all(probes).await;
+            }
+        });
+    }
+}
+
+async fn probe(endpoint: &Endpoint, timeout: Duration) {
+    let was_open = !endpoint.health.available();
+    let started = Instant::now();
+    let result = match tokio::time::timeout(timeout, endpoint.provider.call("getHealth", json!([]))).await {
+        // A node that is behind answers getHealth with an error object
+        Ok(Ok(response)) => match response.get("error") {
+            Some(error) => Err(anyhow!("Unhealthy: {}", error)),
+            None => Ok(()),
+        },
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err(anyhow!("Health probe timed out after {:?}", timeout)),
+    };
+
+    match result {
+        Ok(()) => {
+            endpoint.health.record_success(started.elapsed());
+            if was_open {
+                info!("Upstream {} is healthy again", endpoint.provider.name());
+            }
+        }
+        Err(e) => {
+            endpoint.health.record_failure(&e);
+            if !was_open && !endpoint.health.available() {
+                warn!("Upstream {} is unhealthy, routing around it: {:#}", endpoint.provider.name(), e);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RpcProvider for MultiplexProvider {
+    fn name(&self) -> &str {
+        "multiplex"
+    }
+
+    async fn send(&self, request: &Value) -> Result<Value> {
+        let mut last_error = None;
+        for endpoint in self.order() {
+            let started = Instant::now();
+            match endpoint.provider.send(request).await {
+                Ok(response) => {
+                    endpoint.health.record_success(started.elapsed());
+                    return Ok(response);
+                }
+                Err(e) => {
+                    warn!("Upstream {} failed, trying the next provider: {:#}", endpoint.provider.name(), e);
+                    endpoint.health.record_failure(&e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow!("No upstream providers")))
+    }
+
+    fn ws_url(&self) -> Option<String> {
+        self.endpoints.iter().find_map(|endpoint| endpoint.provider.ws_url())
+    }
+
+    fn health(&self) -> Vec<UpstreamHealth> {
+        self.endpoints.iter()
+            .map(|endpoint| endpoint.health.snapshot(endpoint.provider.name()))
+            .collect()
+    }
+}
+
+/// Upstream provider selection, tagged by `provider`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum RpcProviderConfig {
+    Helius {
+        api_key: String,
+        #[serde(default = "default_network")]
+        network: String,
+    },
+    /// Plain JSON-RPC; Triton and QuickNode endpoints embed their token in the URL
+    #[serde(alias = "triton", alias = "quicknode")]
+    Rpc {
+        url: String,
+        #[serde(default)]
+        ws_url: Option<String>,
+    },
+    Multi {
+        #[serde(default)]
+        strategy: MultiplexStrategy,
+        providers: Vec<RpcProviderConfig>,
+        #[serde(default)]
+        health: HealthConfig,
+    },
+}
+
+fn default_network() -> String {
+    "mainnet".to_string()
+}
+
+impl RpcProviderConfig {
+    /// Builds the provider; inside a Tokio runtime, multiplexed providers
+    /// also start probing their providers' health
+    pub fn build(&self) -> Result<Arc<dyn RpcProvider>> {
+        Ok(match self {
+            Self::Helius { api_key, network } => Arc::new(HeliusProvider::new(api_key, network)),
+            Self::Rpc { url, ws_url } => {
+                let provider = JsonRpcProvider::new(url.clone());
+                Arc::new(match ws_url {
+                    Some(ws_url) => provider.with_ws_url(ws_url.clone()),
+                    None => provider,
+                })
+            }
+            Self::Multi { strategy, providers, health } => {
+                let providers = providers.iter().map(Self::build).collect::<Result<_>>()?;
+                let multiplex = Arc::new(MultiplexProvider::with_health_config(providers, *strategy, health.clone())?);
+                if tokio::runtime::Handle::try_current().is_ok() {
+                    multiplex.spawn_health_checks();
+                }
+                multiplex
+            }
+        })
+    }
+}
+
+/// Scheme and host of `url`, dropping paths and query strings that often carry tokens
+fn redact(url: &str) -> String {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let host = rest.split(['/', '?']).next().unwrap_or(rest);
+    if scheme.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}://{}", scheme, host)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Fixed(&'static str, bool);
+
+    #[async_trait]
+    impl RpcProvider for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        async fn send(&self, _request: &Value) -> Result<Value> {
+            if self.1 {
+                Ok(json!({ "result": self.0 }))
+            } else {
+                Err(anyhow!("{} is down", self.0))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn round_robin_rotates_and_skips_failed_providers() {
+        let multiplex = MultiplexProvider::new(
+            vec![Arc::new(Fixed("a", true)), Arc::new(Fixed("b", false)), Arc::new(Fixed("c", true))],
+            MultiplexStrategy::RoundRobin,
+        )
+        .unwrap();
+
+        let mut answered = Vec::new();
+        for _ in 0..3 {
+            let response = multiplex.call("getSlot", json!([])).await.unwrap();
+            answered.push(response["result"].as_str().unwrap().to_string());
+        }
+        assert_eq!(answered, ["a", "c", "c"]);
+        assert_eq!(redact("https://example.quiknode.pro/token/"), "https://example.quiknode.pro");
+    }
+}
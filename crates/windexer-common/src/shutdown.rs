@@ -0,0 +1,218 @@
+//! Coordinated shutdown
+//!
+//! A process hands each subsystem a [`ShutdownToken`] for the [`Stage`] it
+//! belongs to. On Ctrl+C or SIGTERM, [`Shutdown::shutdown`] stops the stages
+//! one after another: the tokens of a stage are cancelled, and the next
+//! stage is only cancelled once every token of the previous one has been
+//! dropped. Servers stop taking requests before the node stops gossiping,
+//! and pending writes are flushed after both.
+//!
+//! One timeout bounds the whole drain. Stages still running when it expires
+//! are cancelled anyway and left behind.
+
+use {
+    std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+        time::Duration,
+    },
+    tokio::time::Instant,
+    tokio_util::{
+        sync::{CancellationToken, WaitForCancellationFutureOwned},
+        task::{task_tracker::TaskTrackerToken, TaskTracker},
+    },
+    tracing::{info, warn},
+};
+
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Subsystem groups, stopped in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Servers taking requests from clients
+    Ingress,
+    /// The peer-to-peer node
+    Network,
+    /// Background services such as staking
+    Services,
+    /// Storage, flushing pending writes
+    Storage,
+    /// Metrics, last so the drain itself can be observed
+    Telemetry,
+}
+
+#[derive(Debug, Clone, Default)]
+struct StageState {
+    cancel: CancellationToken,
+    tracker: TaskTracker,
+}
+
+/// Stops the stages of one process in order
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    stages: Arc<Mutex<BTreeMap<Stage, StageState>>>,
+    started: CancellationToken,
+    timeout: Duration,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new(DEFAULT_SHUTDOWN_TIMEOUT)
+    }
+}
+
+impl Shutdown {
+    /// Coordinator giving the drain `timeout` in total
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            stages: Arc::new(Mutex::new(BTreeMap::new())),
+            started: CancellationToken::new(),
+            timeout,
+        }
+    }
+
+    /// Register a subsystem of `stage`, which holds the stage open until the
+    /// token and all its clones are dropped
+    pub fn token(&self, stage: Stage) -> ShutdownToken {
+        let mut stages = self.stages.lock().unwrap();
+        let state = stages.entry(stage).or_default();
+        ShutdownToken {
+            stage,
+            cancel: state.cancel.clone(),
+            _tracked: state.tracker.token(),
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.started.is_cancelled()
+    }
+
+    /// Stop every stage in order, returning whether all of them drained
+    /// before the timeout
+    pub async fn shutdown(&self) -> bool {
+        self.started.cancel();
+        let deadline = Instant::now() + self.timeout;
+        let stages: Vec<(Stage, StageState)> = self.stages.lock().unwrap()
+            .iter()
+            .map(|(stage, state)| (*stage, state.clone()))
+            .collect();
+
+        let mut drained = true;
+        for (stage, state) in stages {
+            state.cancel.cancel();
+            state.tracker.close();
+            if !drained {
+                continue;
+            }
+            match tokio::time::timeout_at(deadline, state.tracker.wait()).await {
+                Ok(()) => info!("{:?} stage stopped", stage),
+                Err(_) => {
+                    warn!(
+                        "Shutdown timed out after {:?} with {} {:?} subsystems still running",
+                        self.timeout,
+                        state.tracker.len(),
+                        stage,
+                    );
+                    drained = false;
+                }
+            }
+        }
+        drained
+    }
+
+    /// Wait for Ctrl+C or SIGTERM, then stop every stage
+    pub async fn signalled(&self) -> bool {
+        wait_for_signal().await;
+        info!("Shutting down");
+        self.shutdown().await
+    }
+}
+
+/// A subsystem's part in the shutdown; dropping every clone tells the
+/// coordinator the subsystem has stopped
+#[derive(Debug, Clone)]
+pub struct ShutdownToken {
+    stage: Stage,
+    cancel: CancellationToken,
+    _tracked: TaskTrackerToken,
+}
+
+impl ShutdownToken {
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Completes once the subsystem's stage is stopped
+    pub async fn cancelled(&self) {
+        self.cancel.cancelled().await
+    }
+
+    /// Like [`ShutdownToken::cancelled`], but owning no part of the token,
+    /// for graceful shutdown hooks that drop their signal before draining
+    pub fn cancelled_owned(&self) -> WaitForCancellationFutureOwned {
+        self.cancel.clone().cancelled_owned()
+    }
+}
+
+/// Completes when `shutdown` is cancelled, never without one, for
+/// subsystems that may run outside a coordinated process
+pub async fn cancelled(shutdown: Option<&ShutdownToken>) {
+    match shutdown {
+        Some(shutdown) => shutdown.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Completes on Ctrl+C, or on SIGTERM on unix
+pub async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Failed to listen for Ctrl+C: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tokio::sync::mpsc};
+
+    #[tokio::test]
+    async fn stages_stop_in_order() {
+        let shutdown = Shutdown::new(Duration::from_secs(5));
+        let (stopped_tx, mut stopped_rx) = mpsc::unbounded_channel();
+        for stage in [Stage::Storage, Stage::Ingress, Stage::Network] {
+            let token = shutdown.token(stage);
+            let stopped_tx = stopped_tx.clone();
+            tokio::spawn(async move {
+                token.cancelled().await;
+                // Later stages must still be running
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                stopped_tx.send(token.stage()).unwrap();
+            });
+        }
+
+        assert!(shutdown.shutdown().await);
+        drop(stopped_tx);
+        let mut order = Vec::new();
+        while let Some(stage) = stopped_rx.recv().await {
+            order.push(stage);
+        }
+        assert_eq!(order, [Stage::Ingress, Stage::Network, Stage::Storage]);
+    }
+}
@@ -0,0 +1,89 @@
+//! API key records
+//!
+//! Keys are issued by the API server and persisted by the store. Only a hash
+//! of each key's secret is kept, so a leaked record cannot be used to
+//! authenticate.
+
+use {
+    serde::{Deserialize, Serialize},
+    std::{fmt, str::FromStr},
+};
+
+/// What a credential may access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    /// Queries over indexed data
+    ReadOnly,
+    /// Live subscriptions
+    Stream,
+    /// Everything, including key management
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::ReadOnly => "read_only",
+            ApiScope::Stream => "stream",
+            ApiScope::Admin => "admin",
+        }
+    }
+
+    /// Whether holding this scope grants `required`
+    pub fn grants(&self, required: ApiScope) -> bool {
+        *self == ApiScope::Admin || *self == required
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_only" => Ok(ApiScope::ReadOnly),
+            "stream" => Ok(ApiScope::Stream),
+            "admin" => Ok(ApiScope::Admin),
+            _ => Err(format!("Unknown API scope {}", s)),
+        }
+    }
+}
+
+/// A stored API key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    /// Public identifier, embedded in the key itself
+    pub id: String,
+    pub name: String,
+    /// Hex-encoded SHA-256 of the key's secret
+    pub secret_hash: String,
+    pub scopes: Vec<ApiScope>,
+    /// Requests allowed per minute; `None` uses the server default
+    pub rate_limit_per_minute: Option<u32>,
+    /// Unix timestamp in seconds
+    pub created_at: i64,
+    /// Unix timestamp in seconds after which the key is rejected
+    pub expires_at: Option<i64>,
+    pub revoked: bool,
+    /// Tenant whose data the key is limited to; `None` for every tenant
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+impl ApiKeyRecord {
+    /// Whether the key may be used at unix time `now`
+    pub fn is_active(&self, now: i64) -> bool {
+        !self.revoked && self.expires_at.map_or(true, |expires_at| now < expires_at)
+    }
+
+    pub fn grants(&self, required: ApiScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+}
@@ -0,0 +1,76 @@
+//! Network attestations of indexed slots
+//!
+//! Once a node has indexed a rooted slot it signs a [`ConsensusVote`] for
+//! the content hash of the slot's data. When operators holding enough stake
+//! have voted for the same hash the slot is network-finalized, and the
+//! votes are kept as its [`SlotAttestation`] so anyone can check them.
+
+use {
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+    },
+};
+
+/// Domain separator, so a vote signature can't be passed off as anything else
+const VOTE_DOMAIN: &[u8] = b"windexer-consensus-vote";
+
+/// An operator's signed claim that `slot` has content hash `content_hash`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsensusVote {
+    pub slot: u64,
+    pub content_hash: Hash,
+    pub voter: Pubkey,
+    pub signature: Signature,
+}
+
+fn vote_bytes(slot: u64, content_hash: &Hash) -> Vec<u8> {
+    [VOTE_DOMAIN, &slot.to_le_bytes(), content_hash.as_ref()].concat()
+}
+
+impl ConsensusVote {
+    pub fn sign(keypair: &Keypair, slot: u64, content_hash: Hash) -> Self {
+        Self {
+            slot,
+            content_hash,
+            voter: keypair.pubkey(),
+            signature: keypair.sign_message(&vote_bytes(slot, &content_hash)),
+        }
+    }
+
+    pub fn verify(&self) -> bool {
+        self.signature.verify(self.voter.as_ref(), &vote_bytes(self.slot, &self.content_hash))
+    }
+}
+
+/// The votes that network-finalized a slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotAttestation {
+    pub slot: u64,
+    pub content_hash: Hash,
+    pub votes: Vec<ConsensusVote>,
+    /// Stake behind `votes`
+    pub stake: u64,
+    /// Stake of every operator at the time
+    pub total_stake: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::hash::hash};
+
+    #[test]
+    fn votes_verify_only_unchanged() {
+        let keypair = Keypair::new();
+        let vote = ConsensusVote::sign(&keypair, 42, hash(b"bundle"));
+        assert!(vote.verify());
+
+        let moved = ConsensusVote { slot: 43, ..vote.clone() };
+        assert!(!moved.verify());
+        let changed = ConsensusVote { content_hash: hash(b"other"), ..vote };
+        assert!(!changed.verify());
+    }
+}
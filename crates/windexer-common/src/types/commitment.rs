@@ -0,0 +1,48 @@
+//! Commitment levels
+//!
+//! How settled data must be before a query or subscription returns it,
+//! mirroring Solana RPC: `processed` data may still be rolled back,
+//! `confirmed` data was voted on by a supermajority and `finalized` data is
+//! rooted.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    #[default]
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    /// Commitment reached by a slot status, as named by `SlotStatus::as_str`
+    pub fn from_slot_status(status: &str) -> Option<Self> {
+        match status {
+            "processed" => Some(Commitment::Processed),
+            "confirmed" => Some(Commitment::Confirmed),
+            "rooted" => Some(Commitment::Finalized),
+            _ => None,
+        }
+    }
+
+    /// Parses a Solana RPC commitment, including the deprecated aliases
+    pub fn from_rpc(commitment: &str) -> Option<Self> {
+        match commitment {
+            "processed" | "recent" => Some(Commitment::Processed),
+            "confirmed" | "single" | "singleGossip" => Some(Commitment::Confirmed),
+            "finalized" | "max" | "root" => Some(Commitment::Finalized),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Content hashes of indexed data
+//!
+//! Nodes that index the same rooted slot must arrive at the same hash for
+//! it, so records are hashed from a canonical encoding of only the fields
+//! every node sees alike. Fields that depend on the observer are left out:
+//! an account's `write_version` and `is_startup`, a block's commitment
+//! status and its entries, which only nodes with entry notifications have.
+//!
+//! A slot's content hash is the root of a blake3 merkle tree over its
+//! block, its transactions in block order and its account updates in hash
+//! order, so a single record can later be proven part of the slot.
+
+use {
+    super::{AccountData, BlockData, TransactionData},
+    serde::{Deserialize, Serialize},
+    solana_sdk::hash::Hash,
+};
+
+/// Prefixes keeping leaves, inner nodes and record kinds from colliding
+const LEAF: u8 = 0;
+const NODE: u8 = 1;
+const BLOCK: u8 = 0;
+const TRANSACTION: u8 = 1;
+const ACCOUNT: u8 = 2;
+
+/// Deterministic encoding of a record for hashing
+pub trait CanonicalEncode {
+    /// Bytes every node produces for the same record
+    fn canonical_bytes(&self) -> Vec<u8>;
+
+    /// Leaf hash of the record in a slot's merkle tree
+    fn content_hash(&self) -> Hash;
+}
+
+fn encode(value: &impl Serialize) -> Vec<u8> {
+    // bincode writes fixed-width little-endian integers and length-prefixed
+    // sequences, so the same values always give the same bytes
+    bincode::serialize(value).expect("serializing to memory cannot fail")
+}
+
+fn leaf(kind: u8, bytes: &[u8]) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[LEAF, kind]);
+    hasher.update(bytes);
+    Hash::new_from_array(*hasher.finalize().as_bytes())
+}
+
+fn node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[NODE]);
+    hasher.update(left.as_ref());
+    hasher.update(right.as_ref());
+    Hash::new_from_array(*hasher.finalize().as_bytes())
+}
+
+impl CanonicalEncode for AccountData {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        encode(&(
+            &self.pubkey,
+            self.lamports,
+            &self.owner,
+            self.executable,
+            self.rent_epoch,
+            &self.data,
+            self.slot,
+            &self.transaction_signature,
+        ))
+    }
+
+    fn content_hash(&self) -> Hash {
+        leaf(ACCOUNT, &self.canonical_bytes())
+    }
+}
+
+impl CanonicalEncode for TransactionData {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        encode(&(
+            &self.signature,
+            self.slot,
+            self.index as u64,
+            self.is_vote,
+            &self.message,
+            &self.signatures,
+            &self.serializable_meta,
+        ))
+    }
+
+    fn content_hash(&self) -> Hash {
+        leaf(TRANSACTION, &self.canonical_bytes())
+    }
+}
+
+impl CanonicalEncode for BlockData {
+    fn canonical_bytes(&self) -> Vec<u8> {
+        encode(&(
+            self.slot,
+            self.parent_slot,
+            &self.blockhash,
+            &self.parent_blockhash,
+            self.block_height,
+            self.timestamp,
+            self.transaction_count,
+            &self.rewards,
+        ))
+    }
+
+    fn content_hash(&self) -> Hash {
+        leaf(BLOCK, &self.canonical_bytes())
+    }
+}
+
+/// The leaf hashes of a slot, in canonical order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotContent {
+    pub block: Hash,
+    /// Ordered by position in the block
+    pub transactions: Vec<Hash>,
+    /// Ordered by hash, since nodes may see a slot's updates in any order
+    pub accounts: Vec<Hash>,
+}
+
+impl SlotContent {
+    pub fn new(block: &BlockData, transactions: &[TransactionData], accounts: &[AccountData]) -> Self {
+        let mut ordered: Vec<&TransactionData> = transactions.iter().collect();
+        ordered.sort_by_key(|tx| (tx.index, tx.signature));
+        let mut accounts: Vec<Hash> = accounts.iter().map(CanonicalEncode::content_hash).collect();
+        accounts.sort();
+        // Replays of the same update are one update
+        accounts.dedup();
+
+        Self {
+            block: block.content_hash(),
+            transactions: ordered.into_iter().map(CanonicalEncode::content_hash).collect(),
+            accounts,
+        }
+    }
+
+    /// Every leaf: the block, then transactions, then accounts
+    pub fn leaves(&self) -> Vec<Hash> {
+        std::iter::once(self.block)
+            .chain(self.transactions.iter().copied())
+            .chain(self.accounts.iter().copied())
+            .collect()
+    }
+
+    /// The slot's content hash
+    pub fn root(&self) -> Hash {
+        merkle_root(self.leaves())
+    }
+
+    /// Proof that `leaf` is part of the slot, if it is
+    pub fn proof(&self, leaf: &Hash) -> Option<MerkleProof> {
+        let leaves = self.leaves();
+        let index = leaves.iter().position(|candidate| candidate == leaf)?;
+        MerkleProof::new(&leaves, index)
+    }
+}
+
+/// Root of the tree over `leaves`; a level with an odd count carries its
+/// last hash up unpaired
+pub fn merkle_root(mut level: Vec<Hash>) -> Hash {
+    if level.is_empty() {
+        return Hash::default();
+    }
+    while level.len() > 1 {
+        level = level.chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// The sibling hashes on the path from a leaf to the root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub index: usize,
+    pub leaf_count: usize,
+    /// Bottom up; levels where the path's hash is carried up unpaired have none
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+    pub fn new(leaves: &[Hash], index: usize) -> Option<Self> {
+        if index >= leaves.len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        let mut level = leaves.to_vec();
+        let mut position = index;
+        while level.len() > 1 {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                siblings.push(level[sibling]);
+            }
+            level = level.chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            position /= 2;
+        }
+        Some(Self { index, leaf_count: leaves.len(), siblings })
+    }
+
+    /// Whether `leaf` at this proof's index leads up to `root`
+    pub fn verify(&self, leaf: &Hash, root: &Hash) -> bool {
+        if self.index >= self.leaf_count {
+            return false;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut hash = *leaf;
+        let (mut position, mut len) = (self.index, self.leaf_count);
+        while len > 1 {
+            if position % 2 == 1 {
+                let Some(left) = siblings.next() else { return false };
+                hash = node(left, &hash);
+            } else if position + 1 < len {
+                let Some(right) = siblings.next() else { return false };
+                hash = node(&hash, right);
+            }
+            position /= 2;
+            len = len.div_ceil(2);
+        }
+        siblings.next().is_none() && hash == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    fn account(lamports: u64, write_version: u64) -> AccountData {
+        AccountData {
+            pubkey: Pubkey::new_from_array([1; 32]),
+            lamports,
+            owner: Pubkey::new_from_array([2; 32]),
+            executable: false,
+            rent_epoch: 0,
+            data: vec![1, 2, 3],
+            write_version,
+            slot: 10,
+            is_startup: false,
+            transaction_signature: None,
+        }
+    }
+
+    #[test]
+    fn content_hash_ignores_observer_fields_and_order() {
+        let block = BlockData { slot: 10, blockhash: Some("hash".to_string()), ..Default::default() };
+        let ours = SlotContent::new(&block, &[], &[account(5, 1), account(6, 2)]);
+        let theirs = SlotContent::new(
+            &BlockData { status: agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus::Rooted, ..block.clone() },
+            &[],
+            &[account(6, 900), account(5, 899)],
+        );
+        assert_eq!(ours.root(), theirs.root());
+
+        let corrupted = SlotContent::new(&block, &[], &[account(5, 1), account(7, 2)]);
+        assert_ne!(ours.root(), corrupted.root());
+    }
+
+    #[test]
+    fn proofs_verify_every_leaf_and_nothing_else() {
+        let leaves: Vec<Hash> = (0..7u8).map(|i| leaf(ACCOUNT, &[i])).collect();
+        let root = merkle_root(leaves.clone());
+        for (index, leaf) in leaves.iter().enumerate() {
+            let proof = MerkleProof::new(&leaves, index).unwrap();
+            assert!(proof.verify(leaf, &root));
+            assert!(!proof.verify(&leaves[(index + 1) % leaves.len()], &root));
+        }
+        assert!(MerkleProof::new(&leaves, 7).is_none());
+    }
+}
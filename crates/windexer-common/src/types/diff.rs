@@ -0,0 +1,290 @@
+//! Account data diffs
+//!
+//! Large accounts that change a few bytes at a time, such as orderbooks,
+//! don't need their whole data sent on every update. A [`DataDiffer`] keeps
+//! the last version sent of each account and turns the next one into
+//! [`DataPatch`]es of the byte ranges that changed; consumers apply them to
+//! the version they hold. Every `snapshot_interval` patches, and whenever a
+//! patch wouldn't be much smaller, the account is sent in full again, so a
+//! consumer that missed a patch catches up at the next snapshot.
+
+use {
+    crate::types::account::AccountData,
+    anyhow::{anyhow, Result},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature, system_instruction::MAX_PERMITTED_DATA_LENGTH},
+    std::{collections::HashMap, hash::Hash},
+};
+
+/// Unchanged bytes between two changed ranges below which they are sent as
+/// one patch, as each patch costs about as much in framing
+const MERGE_GAP: usize = 16;
+
+/// Bytes of an account's data starting at `offset`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataPatch {
+    pub offset: u32,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
+/// Byte ranges of `current` that differ from `previous`, including any
+/// bytes past the end of `previous`
+pub fn diff_data(previous: &[u8], current: &[u8]) -> Vec<DataPatch> {
+    let common = previous.len().min(current.len());
+    let mut patches: Vec<DataPatch> = Vec::new();
+
+    let mut index = 0;
+    while index < common {
+        if previous[index] == current[index] {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        let mut end = index + 1;
+        let mut scan = end;
+        while scan < common && scan - end < MERGE_GAP {
+            if previous[scan] != current[scan] {
+                end = scan + 1;
+            }
+            scan += 1;
+        }
+        patches.push(DataPatch { offset: start as u32, data: current[start..end].to_vec() });
+        index = scan;
+    }
+
+    if current.len() > common {
+        match patches.last_mut() {
+            Some(last) if last.offset as usize + last.data.len() + MERGE_GAP >= common => {
+                last.data = current[last.offset as usize..].to_vec();
+            }
+            _ => patches.push(DataPatch { offset: common as u32, data: current[common..].to_vec() }),
+        }
+    }
+    patches
+}
+
+/// `base` resized to `data_len` with `patches` applied. No account holds
+/// more than `MAX_PERMITTED_DATA_LENGTH` bytes, so a larger `data_len` is
+/// refused before anything is allocated.
+pub fn apply_patches(base: &[u8], data_len: usize, patches: &[DataPatch]) -> Result<Vec<u8>> {
+    if data_len as u64 > MAX_PERMITTED_DATA_LENGTH {
+        return Err(anyhow!("Data length of {} bytes exceeds the {} bytes an account can hold", data_len, MAX_PERMITTED_DATA_LENGTH));
+    }
+    let mut data = base.to_vec();
+    data.resize(data_len, 0);
+    for patch in patches {
+        let start = patch.offset as usize;
+        let end = start + patch.data.len();
+        if end > data_len {
+            return Err(anyhow!("Patch of {}..{} is past the end of {} bytes of data", start, end, data_len));
+        }
+        data[start..end].copy_from_slice(&patch.data);
+    }
+    Ok(data)
+}
+
+/// When account updates are sent as patches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDiffConfig {
+    /// Accounts with less data are always sent in full
+    #[serde(default = "default_min_data_len")]
+    pub min_data_len: usize,
+    /// Patches sent for an account before it is sent in full again
+    #[serde(default = "default_snapshot_interval")]
+    pub snapshot_interval: u32,
+    /// Accounts whose last version is kept; others are sent in full
+    #[serde(default = "default_max_accounts")]
+    pub max_accounts: usize,
+}
+
+impl Default for AccountDiffConfig {
+    fn default() -> Self {
+        Self {
+            min_data_len: default_min_data_len(),
+            snapshot_interval: default_snapshot_interval(),
+            max_accounts: default_max_accounts(),
+        }
+    }
+}
+
+fn default_min_data_len() -> usize {
+    4 * 1024
+}
+
+fn default_snapshot_interval() -> u32 {
+    64
+}
+
+fn default_max_accounts() -> usize {
+    10_000
+}
+
+/// Patches from the version of an account the consumer holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDiff {
+    /// Version the patches apply to
+    pub base_version: u64,
+    pub patches: Vec<DataPatch>,
+}
+
+#[derive(Debug)]
+struct Tracked {
+    version: u64,
+    data: Vec<u8>,
+    /// Patches sent since the account was last sent in full
+    patches: u32,
+}
+
+/// Last version sent of each account, by `K`
+#[derive(Debug)]
+pub struct DataDiffer<K> {
+    config: AccountDiffConfig,
+    tracked: HashMap<K, Tracked>,
+}
+
+impl<K: Hash + Eq> DataDiffer<K> {
+    pub fn new(config: AccountDiffConfig) -> Self {
+        Self {
+            config,
+            tracked: HashMap::new(),
+        }
+    }
+
+    /// Records `data` as `version` of `key`, returning the patches to send
+    /// for it, or `None` when it is to be sent in full
+    pub fn update(&mut self, key: K, version: u64, data: &[u8]) -> Option<DataDiff> {
+        if data.len() < self.config.min_data_len {
+            self.tracked.remove(&key);
+            return None;
+        }
+        if !self.tracked.contains_key(&key) && self.tracked.len() >= self.config.max_accounts {
+            return None;
+        }
+
+        let tracked = self.tracked.entry(key).or_insert_with(|| Tracked {
+            version,
+            data: Vec::new(),
+            patches: u32::MAX,
+        });
+        let diff = (tracked.patches < self.config.snapshot_interval)
+            .then(|| diff_data(&tracked.data, data))
+            // Not worth it when half the data changed
+            .filter(|patches| patches.iter().map(|patch| patch.data.len()).sum::<usize>() * 2 < data.len())
+            .map(|patches| DataDiff { base_version: tracked.version, patches });
+
+        tracked.patches = if diff.is_some() { tracked.patches + 1 } else { 0 };
+        tracked.version = version;
+        tracked.data.clear();
+        tracked.data.extend_from_slice(data);
+        diff
+    }
+
+    /// Whether patches of `key` will follow, so consumers should keep it
+    pub fn is_tracked(&self, key: &K) -> bool {
+        self.tracked.contains_key(key)
+    }
+}
+
+/// An account update sent as patches of its data, with its other fields in
+/// full
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountPatch {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub write_version: u64,
+    pub slot: Slot,
+    pub is_startup: bool,
+    pub transaction_signature: Option<Signature>,
+    /// Write version of the account the patches apply to
+    pub base_write_version: u64,
+    pub data_len: u32,
+    pub patches: Vec<DataPatch>,
+}
+
+impl AccountPatch {
+    pub fn new(account: &AccountData, diff: DataDiff) -> Self {
+        Self {
+            pubkey: account.pubkey,
+            lamports: account.lamports,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            write_version: account.write_version,
+            slot: account.slot,
+            is_startup: account.is_startup,
+            transaction_signature: account.transaction_signature,
+            base_write_version: diff.base_version,
+            data_len: account.data.len() as u32,
+            patches: diff.patches,
+        }
+    }
+
+    /// The account, patched onto `base`, which must be the version the
+    /// patch was taken from
+    pub fn apply(&self, base: &AccountData) -> Result<AccountData> {
+        if base.pubkey != self.pubkey || base.write_version != self.base_write_version {
+            return Err(anyhow!(
+                "Patch of {} applies to write version {}, not {} of {}",
+                self.pubkey, self.base_write_version, base.write_version, base.pubkey
+            ));
+        }
+        Ok(AccountData {
+            pubkey: self.pubkey,
+            lamports: self.lamports,
+            owner: self.owner,
+            executable: self.executable,
+            rent_epoch: self.rent_epoch,
+            data: apply_patches(&base.data, self.data_len as usize, &self.patches)?,
+            write_version: self.write_version,
+            slot: self.slot,
+            is_startup: self.is_startup,
+            transaction_signature: self.transaction_signature,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patches_rebuild_the_data() {
+        let previous: Vec<u8> = (0..200).map(|byte| byte as u8).collect();
+        let mut current = previous.clone();
+        current[10] = 0;
+        current[20] = 0;
+        current[150] = 0;
+        current.extend_from_slice(&[1, 2, 3]);
+
+        let patches = diff_data(&previous, &current);
+        // 10 and 20 are close enough to share a patch
+        assert_eq!(patches.iter().map(|patch| patch.offset).collect::<Vec<_>>(), [10, 150, 200]);
+        assert_eq!(apply_patches(&previous, current.len(), &patches).unwrap(), current);
+        assert_eq!(apply_patches(&current, 100, &diff_data(&current, &previous[..100])).unwrap(), &previous[..100]);
+        assert!(apply_patches(&current, MAX_PERMITTED_DATA_LENGTH as usize + 1, &[]).is_err());
+    }
+
+    #[test]
+    fn sends_snapshots_between_patches() {
+        let mut differ = DataDiffer::new(AccountDiffConfig { min_data_len: 8, snapshot_interval: 2, max_accounts: 1 });
+        let mut data = vec![0u8; 64];
+
+        assert_eq!(differ.update("a", 1, &data), None);
+        data[3] = 1;
+        assert_eq!(differ.update("a", 2, &data).map(|diff| diff.base_version), Some(1));
+        data[4] = 1;
+        assert!(differ.update("a", 3, &data).is_some());
+        data[5] = 1;
+        assert_eq!(differ.update("a", 4, &data), None);
+        data[6] = 1;
+        assert!(differ.update("a", 5, &data).is_some());
+        // Too many accounts to track another
+        assert_eq!(differ.update("b", 1, &data), None);
+        assert_eq!(differ.update("b", 2, &data), None);
+        assert!(differ.is_tracked(&"a") && !differ.is_tracked(&"b"));
+    }
+}
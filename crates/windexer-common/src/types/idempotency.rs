@@ -0,0 +1,131 @@
+//! Idempotency keys of indexed data
+//!
+//! Every account update, transaction and block has a key that names it
+//! across retries, redeliveries and ingestion sources:
+//!
+//! | record         | key                                          |
+//! |----------------|----------------------------------------------|
+//! | account update | `account:<pubkey>:<slot>:<write_version>`    |
+//! | transaction    | `transaction:<signature>`                    |
+//! | block          | `block:<slot>`                               |
+//!
+//! Keys travel with the data: each wire record carries the fields of its
+//! key, and [`WireFrame::idempotency_keys`](super::wire::WireFrame::idempotency_keys)
+//! reads them without decoding the batch. Writing a record whose key is
+//! already stored leaves the store as it was, except that a block moves on
+//! to a later commitment. Delivery is at-least-once almost everywhere, so
+//! the keys are what makes the effect exactly-once:
+//!
+//! - Geyser to a node over the network, or to the `store` publisher: a batch
+//!   is sent again after a spill, a dead-letter retry or a restart, and the
+//!   store upserts on the keys. Parquet files are appended to and keep
+//!   duplicates; readers dedupe on the key.
+//! - Kafka: each message carries its key in the `idempotency-key` header and
+//!   the producer is idempotent, so broker retries don't duplicate; messages
+//!   sent again after a restart do, and consumers dedupe on the header.
+//! - NATS JetStream: the key is the `Nats-Msg-Id`, so the stream drops
+//!   duplicates within its duplicate window.
+//! - Alert webhooks: every attempt at delivering an alert carries the same
+//!   `Idempotency-Key` header.
+//! - Live streams over WebSocket, SSE and gRPC are at-most-once: an update
+//!   missed while disconnected is not replayed.
+
+use {
+    super::{AccountData, AccountPatch, AccountView, BlockData, TransactionData},
+    anyhow::{anyhow, Result},
+    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
+    std::{fmt, str::FromStr},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum IdempotencyKey {
+    Account { pubkey: Pubkey, slot: Slot, write_version: u64 },
+    Transaction(Signature),
+    Block(Slot),
+}
+
+impl fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdempotencyKey::Account { pubkey, slot, write_version } => {
+                write!(f, "account:{}:{}:{}", pubkey, slot, write_version)
+            }
+            IdempotencyKey::Transaction(signature) => write!(f, "transaction:{}", signature),
+            IdempotencyKey::Block(slot) => write!(f, "block:{}", slot),
+        }
+    }
+}
+
+impl FromStr for IdempotencyKey {
+    type Err = anyhow::Error;
+
+    fn from_str(key: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid idempotency key {:?}", key);
+        let parts: Vec<&str> = key.split(':').collect();
+        Ok(match parts.as_slice() {
+            ["account", pubkey, slot, write_version] => IdempotencyKey::Account {
+                pubkey: pubkey.parse().map_err(|_| invalid())?,
+                slot: slot.parse().map_err(|_| invalid())?,
+                write_version: write_version.parse().map_err(|_| invalid())?,
+            },
+            ["transaction", signature] => IdempotencyKey::Transaction(signature.parse().map_err(|_| invalid())?),
+            ["block", slot] => IdempotencyKey::Block(slot.parse().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        })
+    }
+}
+
+/// A record with an idempotency key
+pub trait Idempotent {
+    fn idempotency_key(&self) -> IdempotencyKey;
+}
+
+impl Idempotent for AccountData {
+    fn idempotency_key(&self) -> IdempotencyKey {
+        IdempotencyKey::Account { pubkey: self.pubkey, slot: self.slot, write_version: self.write_version }
+    }
+}
+
+impl Idempotent for AccountView<'_> {
+    fn idempotency_key(&self) -> IdempotencyKey {
+        IdempotencyKey::Account { pubkey: self.pubkey(), slot: self.slot(), write_version: self.write_version() }
+    }
+}
+
+/// The key of the update the patch rebuilds
+impl Idempotent for AccountPatch {
+    fn idempotency_key(&self) -> IdempotencyKey {
+        IdempotencyKey::Account { pubkey: self.pubkey, slot: self.slot, write_version: self.write_version }
+    }
+}
+
+impl Idempotent for TransactionData {
+    fn idempotency_key(&self) -> IdempotencyKey {
+        IdempotencyKey::Transaction(self.signature)
+    }
+}
+
+impl Idempotent for BlockData {
+    fn idempotency_key(&self) -> IdempotencyKey {
+        IdempotencyKey::Block(self.slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_round_trip_through_strings() {
+        let keys = [
+            IdempotencyKey::Account { pubkey: Pubkey::new_unique(), slot: 42, write_version: 7 },
+            IdempotencyKey::Transaction(Signature::from([3; 64])),
+            IdempotencyKey::Block(42),
+        ];
+        for key in keys {
+            assert_eq!(key.to_string().parse::<IdempotencyKey>().unwrap(), key);
+        }
+        assert!("block:x".parse::<IdempotencyKey>().is_err());
+        assert!("slot:42".parse::<IdempotencyKey>().is_err());
+    }
+}
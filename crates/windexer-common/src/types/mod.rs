@@ -1,20 +1,81 @@
 //! Common data types used throughout the windexer system
 
 pub mod account;
+pub mod api_key;
+pub mod attestation;
 pub mod block;
+pub mod commitment;
+pub mod content_hash;
+pub mod diff;
 pub mod message;
 pub mod transaction;
 pub mod helius;
+pub mod idempotency;
+pub mod page;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+pub mod staking;
+pub mod wire;
 
 pub use account::AccountData;
+pub use api_key::{ApiKeyRecord, ApiScope};
+pub use attestation::{ConsensusVote, SlotAttestation};
 pub use block::{BlockData, EntryData, SlotStatusData};
-pub use transaction::TransactionData;
+pub use commitment::Commitment;
+pub use content_hash::{CanonicalEncode, MerkleProof, SlotContent};
+pub use idempotency::{IdempotencyKey, Idempotent};
+pub use diff::{AccountDiffConfig, AccountPatch, DataDiffer, DataPatch};
+pub use transaction::{InstructionData, Invocation, TransactionData};
+pub use page::{Cursor, Page, MAX_PAGE_WINDOW};
+pub use staking::{DelegationRecord, OperatorRecord, PerformanceRecord, SlashRecord, WithdrawalRecord};
+pub use wire::{AccountView, WireFrame, WireKind, WireRecord};
 
-use serde::{Deserialize, Serialize};
+use {
+    anyhow::{Context, Result},
+    serde::{Deserialize, Serialize},
+    std::{fs, io::ErrorKind, path::Path},
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Indexing progress, persisted so indexing resumes where it stopped
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IndexerState {
+    /// Latest rooted slot whose data has been fully written
     pub last_processed_slot: u64,
     pub total_accounts: u64,
     pub total_transactions: u64,
+}
+
+impl IndexerState {
+    /// Record a rooted slot; progress never moves backwards
+    pub fn record_rooted_slot(&mut self, slot: u64) -> bool {
+        if slot <= self.last_processed_slot {
+            return false;
+        }
+        self.last_processed_slot = slot;
+        true
+    }
+
+    /// Whether data for `slot` was already processed before a restart
+    pub fn is_processed(&self, slot: u64) -> bool {
+        self.last_processed_slot > 0 && slot <= self.last_processed_slot
+    }
+
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .with_context(|| format!("Invalid indexer state in {}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read indexer state from {}", path.display())),
+        }
+    }
+
+    /// Write via a temporary file so a crash never leaves a torn state file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec(self)?)?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to write indexer state to {}", path.display()))
+    }
 }
\ No newline at end of file
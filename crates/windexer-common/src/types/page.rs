@@ -0,0 +1,212 @@
+//! Cursor-based pagination types
+//!
+//! Paged queries return a [`Page`] holding the items and an opaque
+//! `next_cursor`. Clients pass the cursor back verbatim to fetch the next page;
+//! they should never construct or inspect it themselves.
+
+use {
+    crate::errors::{Error, Result},
+    serde::{Deserialize, Serialize},
+};
+
+/// Most items a page may be cut from; cursors and limits reaching further
+/// back are rejected
+pub const MAX_PAGE_WINDOW: usize = 10_000;
+
+/// A single page of query results
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Create a page with no continuation
+    pub fn last(items: Vec<T>) -> Self {
+        Self { items, next_cursor: None }
+    }
+
+    /// Build a page from results ordered by recency
+    ///
+    /// `items` must start at the first element of the full result set and
+    /// contain at least `offset + limit + 1` elements if another page exists.
+    pub fn from_offset(items: Vec<T>, offset: usize, limit: usize) -> Self {
+        let end = offset.saturating_add(limit);
+        let has_more = items.len() > end;
+        let items: Vec<T> = items.into_iter().skip(offset).take(limit).collect();
+
+        let next_cursor = has_more.then(|| {
+            Cursor { slot: 0, offset: end as u64 }.encode()
+        });
+
+        Self { items, next_cursor }
+    }
+
+    /// Build a page from results ordered by ascending slot
+    ///
+    /// `items` must start at `cursor.slot` (or the range start for the first
+    /// page) and contain at least `cursor.offset + limit + 1` elements if
+    /// another page exists. Resuming is stable across inserts in later slots.
+    pub fn from_slot_range<F>(items: Vec<T>, cursor: &Cursor, limit: usize, slot_of: F) -> Self
+    where
+        F: Fn(&T) -> u64,
+    {
+        let skip = cursor.offset as usize;
+        let has_more = items.len().saturating_sub(skip) > limit;
+        let items: Vec<T> = items.into_iter().skip(skip).take(limit).collect();
+
+        let next_cursor = match (has_more, items.last()) {
+            (true, Some(last)) => {
+                let last_slot = slot_of(last);
+                let in_last_slot = items.iter().filter(|item| slot_of(item) == last_slot).count() as u64;
+                let offset = if last_slot == cursor.slot {
+                    cursor.offset + in_last_slot
+                } else {
+                    in_last_slot
+                };
+                Some(Cursor { slot: last_slot, offset }.encode())
+            }
+            _ => None,
+        };
+
+        Self { items, next_cursor }
+    }
+
+    /// Build a page from results ordered by descending slot, newest first
+    ///
+    /// `items` must start at the newest item, or at any item in a slot at
+    /// least `cursor.slot` when resuming, and contain at least `limit + 1`
+    /// items past the cursor if another page exists. Like
+    /// [`Page::from_slot_range`] the cursor is anchored on the slot of the
+    /// last item returned, so items arriving in newer slots between requests
+    /// do not shift later pages.
+    pub fn from_recency<F>(items: Vec<T>, cursor: &Cursor, limit: usize, slot_of: F) -> Self
+    where
+        F: Fn(&T) -> u64,
+    {
+        let skip = cursor.recency_skip(&items, &slot_of);
+        let has_more = items.len() - skip > limit;
+        let items: Vec<T> = items.into_iter().skip(skip).take(limit).collect();
+
+        let next_cursor = match (has_more, items.last()) {
+            (true, Some(last)) => {
+                let last_slot = slot_of(last);
+                let in_last_slot = items.iter().filter(|item| slot_of(item) == last_slot).count() as u64;
+                let offset = if last_slot == cursor.slot {
+                    cursor.offset + in_last_slot
+                } else {
+                    in_last_slot
+                };
+                Some(Cursor { slot: last_slot, offset }.encode())
+            }
+            _ => None,
+        };
+
+        Self { items, next_cursor }
+    }
+}
+
+/// Position within a paged result set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cursor {
+    /// Slot to resume from (unused for offset-paged queries)
+    pub slot: u64,
+    /// Number of items to skip at the resume position; 0 on the first page
+    pub offset: u64,
+}
+
+impl Cursor {
+    /// Encode the cursor as an opaque string
+    pub fn encode(&self) -> String {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.slot.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.offset.to_be_bytes());
+        bs58::encode(bytes).into_string()
+    }
+
+    /// Decode a cursor previously produced by [`Cursor::encode`]
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let bytes = bs58::decode(cursor)
+            .into_vec()
+            .map_err(|e| Error::Serialization(format!("Invalid cursor: {}", e)))?;
+
+        if bytes.len() != 16 {
+            return Err(Error::Serialization("Invalid cursor length".to_string()));
+        }
+
+        let mut slot = [0u8; 8];
+        let mut offset = [0u8; 8];
+        slot.copy_from_slice(&bytes[..8]);
+        offset.copy_from_slice(&bytes[8..]);
+
+        Ok(Self {
+            slot: u64::from_be_bytes(slot),
+            offset: u64::from_be_bytes(offset),
+        })
+    }
+
+    /// Decode an optional cursor, defaulting to the start of the result set
+    pub fn decode_opt(cursor: Option<&str>) -> Result<Self> {
+        cursor.map(Self::decode).transpose().map(Option::unwrap_or_default)
+    }
+
+    /// Number of leading `items`, ordered by descending slot, that were
+    /// either returned before this cursor or arrived in newer slots since
+    pub fn recency_skip<T>(&self, items: &[T], slot_of: impl Fn(&T) -> u64) -> usize {
+        if self.offset == 0 {
+            return 0;
+        }
+        let newer = items.iter().take_while(|item| slot_of(item) > self.slot).count();
+        let returned = items[newer..]
+            .iter()
+            .take_while(|item| slot_of(item) == self.slot)
+            .count()
+            .min(self.offset as usize);
+        newer + returned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = Cursor { slot: 42, offset: 7 };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert_eq!(Cursor::decode_opt(None).unwrap(), Cursor::default());
+        assert!(Cursor::decode("not-a-cursor").is_err());
+    }
+
+    #[test]
+    fn test_slot_range_paging() {
+        let slots = vec![1u64, 1, 1, 2, 3];
+
+        let first = Page::from_slot_range(slots.clone(), &Cursor::default(), 2, |s| *s);
+        assert_eq!(first.items, vec![1, 1]);
+        let cursor = Cursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(cursor, Cursor { slot: 1, offset: 2 });
+
+        let remaining: Vec<u64> = slots.into_iter().filter(|s| *s >= cursor.slot).collect();
+        let second = Page::from_slot_range(remaining, &cursor, 2, |s| *s);
+        assert_eq!(second.items, vec![1, 2]);
+        assert!(second.next_cursor.is_some());
+    }
+
+    #[test]
+    fn test_recency_paging_survives_new_items() {
+        let slots = vec![9u64, 8, 8, 7, 6];
+
+        let first = Page::from_recency(slots.clone(), &Cursor::default(), 2, |s| *s);
+        assert_eq!(first.items, vec![9, 8]);
+        let cursor = Cursor::decode(first.next_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(cursor, Cursor { slot: 8, offset: 1 });
+
+        // Items landing in newer slots are not returned again or skipped over
+        let newer: Vec<u64> = [11, 10].into_iter().chain(slots).collect();
+        let second = Page::from_recency(newer, &cursor, 2, |s| *s);
+        assert_eq!(second.items, vec![8, 7]);
+        assert!(second.next_cursor.is_some());
+    }
+}
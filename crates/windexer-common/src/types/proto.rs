@@ -0,0 +1,209 @@
+//! Protobuf and Borsh encodings of the exported data types
+//!
+//! [`AccountData`], [`TransactionData`], [`BlockData`] and [`EntryData`] hold
+//! Solana types that neither prost nor Borsh can encode, so they are exported
+//! through the messages here, converted with `From`. The messages follow the
+//! update messages of the Yellowstone gRPC `geyser` package and are defined in
+//! `proto/windexer.proto`, the schema for consumers outside Rust. With the
+//! `borsh` feature the same messages also derive Borsh, whose layout is the
+//! field order below.
+//!
+//! Fields are only added under new tags, and removed tags are reserved, so
+//! both schemas stay readable by older consumers.
+
+use {
+    crate::types::{
+        account::AccountData,
+        block::{BlockData, EntryData},
+        transaction::TransactionData,
+    },
+    prost::Message,
+};
+
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct SubscribeUpdateAccount {
+    #[prost(message, optional, tag = "1")]
+    pub account: Option<SubscribeUpdateAccountInfo>,
+    #[prost(uint64, tag = "2")]
+    pub slot: u64,
+    #[prost(bool, tag = "3")]
+    pub is_startup: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct SubscribeUpdateAccountInfo {
+    #[prost(bytes = "vec", tag = "1")]
+    pub pubkey: Vec<u8>,
+    #[prost(uint64, tag = "2")]
+    pub lamports: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub owner: Vec<u8>,
+    #[prost(bool, tag = "4")]
+    pub executable: bool,
+    #[prost(uint64, tag = "5")]
+    pub rent_epoch: u64,
+    #[prost(bytes = "vec", tag = "6")]
+    pub data: Vec<u8>,
+    #[prost(uint64, tag = "7")]
+    pub write_version: u64,
+    #[prost(bytes = "vec", optional, tag = "8")]
+    pub txn_signature: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct SubscribeUpdateTransaction {
+    #[prost(message, optional, tag = "1")]
+    pub transaction: Option<SubscribeUpdateTransactionInfo>,
+    #[prost(uint64, tag = "2")]
+    pub slot: u64,
+}
+
+/// Yellowstone's `transaction` and `meta` (tags 3 and 4) use the
+/// solana-storage types and are left out
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct SubscribeUpdateTransactionInfo {
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: Vec<u8>,
+    #[prost(bool, tag = "2")]
+    pub is_vote: bool,
+    #[prost(uint64, tag = "5")]
+    pub index: u64,
+    #[prost(bool, tag = "6")]
+    pub success: bool,
+    #[prost(uint64, tag = "7")]
+    pub fee: u64,
+    #[prost(bytes = "vec", repeated, tag = "8")]
+    pub account_keys: Vec<Vec<u8>>,
+    /// bincode encoded `solana_sdk::message::Message`
+    #[prost(bytes = "vec", tag = "9")]
+    pub message: Vec<u8>,
+    #[prost(bytes = "vec", repeated, tag = "10")]
+    pub signatures: Vec<Vec<u8>>,
+    #[prost(string, repeated, tag = "11")]
+    pub log_messages: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct UnixTimestamp {
+    #[prost(int64, tag = "1")]
+    pub timestamp: i64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct BlockHeight {
+    #[prost(uint64, tag = "1")]
+    pub block_height: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct SubscribeUpdateBlockMeta {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    #[prost(string, tag = "2")]
+    pub blockhash: String,
+    #[prost(message, optional, tag = "4")]
+    pub block_time: Option<UnixTimestamp>,
+    #[prost(message, optional, tag = "5")]
+    pub block_height: Option<BlockHeight>,
+    #[prost(uint64, tag = "6")]
+    pub parent_slot: u64,
+    #[prost(string, tag = "7")]
+    pub parent_blockhash: String,
+    #[prost(uint64, tag = "8")]
+    pub executed_transaction_count: u64,
+    #[prost(uint64, tag = "9")]
+    pub entries_count: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+#[cfg_attr(feature = "borsh", derive(BorshSerialize, BorshDeserialize))]
+pub struct SubscribeUpdateEntry {
+    #[prost(uint64, tag = "1")]
+    pub slot: u64,
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+    #[prost(uint64, tag = "3")]
+    pub num_hashes: u64,
+    #[prost(bytes = "vec", tag = "4")]
+    pub hash: Vec<u8>,
+    #[prost(uint64, tag = "5")]
+    pub executed_transaction_count: u64,
+    #[prost(uint64, tag = "6")]
+    pub starting_transaction_index: u64,
+}
+
+impl From<&AccountData> for SubscribeUpdateAccount {
+    fn from(account: &AccountData) -> Self {
+        Self {
+            account: Some(SubscribeUpdateAccountInfo {
+                pubkey: account.pubkey.to_bytes().to_vec(),
+                lamports: account.lamports,
+                owner: account.owner.to_bytes().to_vec(),
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+                data: account.data.clone(),
+                write_version: account.write_version,
+                txn_signature: account.transaction_signature.map(|signature| signature.as_ref().to_vec()),
+            }),
+            slot: account.slot,
+            is_startup: account.is_startup,
+        }
+    }
+}
+
+impl From<&TransactionData> for SubscribeUpdateTransaction {
+    fn from(transaction: &TransactionData) -> Self {
+        Self {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                signature: transaction.signature.as_ref().to_vec(),
+                is_vote: transaction.is_vote,
+                index: transaction.index as u64,
+                success: transaction.serializable_meta.status == Some(0),
+                fee: transaction.serializable_meta.fee,
+                account_keys: transaction.message.account_keys.iter().map(|key| key.to_bytes().to_vec()).collect(),
+                message: bincode::serialize(&transaction.message).unwrap_or_default(),
+                signatures: transaction.signatures.iter().map(|signature| signature.as_ref().to_vec()).collect(),
+                log_messages: transaction.serializable_meta.log_messages.clone().unwrap_or_default(),
+            }),
+            slot: transaction.slot,
+        }
+    }
+}
+
+impl From<&BlockData> for SubscribeUpdateBlockMeta {
+    fn from(block: &BlockData) -> Self {
+        Self {
+            slot: block.slot,
+            blockhash: block.blockhash.clone().unwrap_or_default(),
+            block_time: block.timestamp.map(|timestamp| UnixTimestamp { timestamp }),
+            block_height: block.block_height.map(|block_height| BlockHeight { block_height }),
+            parent_slot: block.parent_slot.unwrap_or_default(),
+            parent_blockhash: block.parent_blockhash.clone().unwrap_or_default(),
+            executed_transaction_count: block.transaction_count.unwrap_or_default(),
+            entries_count: block.entry_count,
+        }
+    }
+}
+
+impl From<&EntryData> for SubscribeUpdateEntry {
+    fn from(entry: &EntryData) -> Self {
+        Self {
+            slot: entry.slot,
+            index: entry.index as u64,
+            num_hashes: entry.num_hashes,
+            hash: entry.hash.clone(),
+            executed_transaction_count: entry.executed_transaction_count,
+            starting_transaction_index: entry.starting_transaction_index as u64,
+        }
+    }
+}
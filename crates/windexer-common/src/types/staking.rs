@@ -0,0 +1,87 @@
+//! Staking records
+//!
+//! The staking service keeps its operator and delegation accounting in the
+//! store, writing through on every change and loading it back on startup.
+//! Amounts are in lamports and keys are base58 pubkeys.
+
+use serde::{Deserialize, Serialize};
+
+/// Accounting of one operator
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperatorRecord {
+    pub operator: String,
+    /// Stake delegated to the operator
+    pub total_stake: u64,
+    pub performance_score: f64,
+    /// Unix timestamp in seconds the operator was last seen active
+    pub last_active: Option<i64>,
+    /// Rewards earned by the operator and not yet paid out
+    pub reward_balance: u64,
+    /// Unix timestamp in seconds of the last change
+    pub updated_at: i64,
+}
+
+/// Stake one staker delegated to one operator
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegationRecord {
+    pub operator: String,
+    pub staker: String,
+    pub amount: u64,
+    /// Rewards accrued to the staker through this delegation and not yet paid out
+    pub reward_balance: u64,
+    /// Unstaked amounts waiting out their cooldown
+    #[serde(default)]
+    pub pending_withdrawals: Vec<WithdrawalRecord>,
+    /// Unix timestamp in seconds of the last change
+    pub updated_at: i64,
+}
+
+/// Stake unstaked from a delegation, released once its cooldown ends
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalRecord {
+    pub amount: u64,
+    /// Epoch the unstake was requested in
+    pub requested_epoch: u64,
+    /// First epoch the stake can be withdrawn in
+    pub withdrawable_epoch: u64,
+}
+
+/// Stake taken from an operator for a violation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashRecord {
+    pub operator: String,
+    /// Kind of violation, like `double_vote`
+    pub violation: String,
+    /// Stake deducted
+    pub amount: u64,
+    /// Stake the operator held before the slash
+    pub stake_before: u64,
+    /// Staking epoch the slash happened in
+    pub epoch: u64,
+    /// Unix timestamp in seconds of the slash
+    pub timestamp: i64,
+}
+
+/// An operator's performance over one staking epoch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct PerformanceRecord {
+    pub operator: String,
+    pub epoch: u64,
+    /// Share of the consensus rounds it was expected to vote in that it voted in
+    pub uptime: f64,
+    /// Consensus rounds it was expected to vote in and did not
+    pub missed_rounds: u64,
+    /// Consensus rounds it was expected to vote in
+    pub rounds: u64,
+    /// Mean seconds its votes trailed the first vote of their round
+    pub response_time: f64,
+    /// Share of its consensus messages that were valid, if it sent any
+    pub message_success_rate: Option<f64>,
+    /// Slashes it suffered during the epoch
+    pub slashes: u64,
+    /// Performance score the epoch's rewards were computed with
+    pub performance_score: f64,
+    /// Unix timestamp in seconds the epoch closed
+    pub timestamp: i64,
+}
@@ -8,8 +8,9 @@ use {
         signature::Signature,
         clock::Slot,
         message::Message,
+        pubkey::Pubkey,
     },
-    solana_transaction_status::TransactionStatusMeta,
+    solana_transaction_status::{InnerInstructions, TransactionStatusMeta},
     serde::{Deserialize, Serialize},
     std::fmt::{Debug, Formatter, Result as FmtResult},
     crate::utils::SerializableTransactionMeta,
@@ -27,6 +28,112 @@ pub struct TransactionData {
     #[serde(rename = "meta")]
     pub serializable_meta: SerializableTransactionMeta,
     pub index: usize,
+    /// Top-level instructions with the inner instructions they invoked
+    #[serde(default)]
+    pub instructions: Vec<InstructionData>,
+}
+
+/// An instruction with its program and accounts resolved
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructionData {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+    /// Invocation depth, 1 for top-level instructions; validators before
+    /// 1.16 did not record it for inner instructions
+    pub stack_height: Option<u32>,
+    /// Instructions invoked by this one in execution order, only set on
+    /// top-level instructions
+    #[serde(default)]
+    pub inner_instructions: Vec<InstructionData>,
+}
+
+impl InstructionData {
+    /// Resolves the instructions of `message`, whose account keys include
+    /// any loaded from lookup tables, and attaches the inner instructions
+    /// recorded in the transaction meta. Indexes outside the account keys
+    /// resolve to the default pubkey rather than failing the transaction.
+    pub fn parse(message: &Message, inner_instructions: Option<&[InnerInstructions]>) -> Vec<Self> {
+        let key = |index: u8| message.account_keys.get(index as usize).copied().unwrap_or_default();
+        let mut instructions: Vec<Self> = message.instructions.iter()
+            .map(|instruction| Self {
+                program_id: key(instruction.program_id_index),
+                accounts: instruction.accounts.iter().map(|&index| key(index)).collect(),
+                data: instruction.data.clone(),
+                stack_height: Some(1),
+                inner_instructions: Vec::new(),
+            })
+            .collect();
+
+        for inner in inner_instructions.unwrap_or_default() {
+            let Some(parent) = instructions.get_mut(inner.index as usize) else {
+                continue;
+            };
+            parent.inner_instructions = inner.instructions.iter()
+                .map(|inner| Self {
+                    program_id: key(inner.instruction.program_id_index),
+                    accounts: inner.instruction.accounts.iter().map(|&index| key(index)).collect(),
+                    data: inner.instruction.data.clone(),
+                    stack_height: inner.stack_height,
+                    inner_instructions: Vec::new(),
+                })
+                .collect();
+        }
+        instructions
+    }
+
+    /// The instruction with its inner instructions nested under the
+    /// instruction that invoked them. Inner instructions without a stack
+    /// height are taken as invoked by the top-level instruction.
+    pub fn call_tree(&self) -> Invocation {
+        let mut root = Invocation::new(self, 1);
+        // Position of the innermost open call at each depth below the root
+        let mut path: Vec<usize> = Vec::new();
+
+        for inner in &self.inner_instructions {
+            let depth = inner.stack_height.unwrap_or(2).max(2);
+            path.truncate(depth as usize - 2);
+            let mut parent = &mut root;
+            for &index in &path {
+                parent = &mut parent.calls[index];
+            }
+            parent.calls.push(Invocation::new(inner, parent.depth + 1));
+            path.push(parent.calls.len() - 1);
+        }
+        root
+    }
+}
+
+/// An instruction in the call tree of a transaction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invocation {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+    /// 1 for top-level instructions, 2 for the instructions they invoke and
+    /// so on
+    pub depth: u32,
+    /// Instructions invoked by this one, in execution order
+    pub calls: Vec<Invocation>,
+}
+
+impl Invocation {
+    fn new(instruction: &InstructionData, depth: u32) -> Self {
+        Self {
+            program_id: instruction.program_id,
+            accounts: instruction.accounts.clone(),
+            data: instruction.data.clone(),
+            depth,
+            calls: Vec::new(),
+        }
+    }
+
+    /// This call followed by every call below it, depth first
+    pub fn walk(&self) -> Box<dyn Iterator<Item = &Invocation> + '_> {
+        Box::new(std::iter::once(self).chain(self.calls.iter().flat_map(Invocation::walk)))
+    }
 }
 
 impl Debug for TransactionData {
@@ -39,6 +146,63 @@ impl Debug for TransactionData {
             .field("signatures_count", &self.signatures.len())
             .field("meta", &"[TransactionStatusMeta]")
             .field("index", &self.index)
+            .field("instructions_count", &self.instructions.len())
             .finish()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{hash::Hash, instruction::CompiledInstruction, message::MessageHeader},
+        solana_transaction_status::InnerInstruction,
+    };
+
+    #[test]
+    fn parse_attaches_inner_instructions() {
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let message = Message {
+            header: MessageHeader::default(),
+            account_keys: keys.clone(),
+            recent_blockhash: Hash::default(),
+            instructions: vec![
+                CompiledInstruction::new_from_raw_parts(2, vec![1], vec![0, 1]),
+                CompiledInstruction::new_from_raw_parts(3, vec![2], vec![1]),
+            ],
+        };
+        let inner = [InnerInstructions {
+            index: 1,
+            instructions: vec![InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(2, vec![3], vec![1, 0]),
+                stack_height: Some(2),
+            }],
+        }];
+
+        let instructions = InstructionData::parse(&message, Some(&inner));
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id, keys[2]);
+        assert_eq!(instructions[0].accounts, vec![keys[0], keys[1]]);
+        assert!(instructions[0].inner_instructions.is_empty());
+        assert_eq!(instructions[1].inner_instructions.len(), 1);
+        assert_eq!(instructions[1].inner_instructions[0].accounts, vec![keys[1], keys[0]]);
+        assert_eq!(instructions[1].inner_instructions[0].stack_height, Some(2));
+    }
+
+    #[test]
+    fn call_tree_nests_by_stack_height() {
+        let call = |stack_height: Option<u32>| InstructionData {
+            program_id: Pubkey::new_unique(),
+            accounts: Vec::new(),
+            data: Vec::new(),
+            stack_height,
+            inner_instructions: Vec::new(),
+        };
+        let mut instruction = call(Some(1));
+        instruction.inner_instructions = vec![call(Some(2)), call(Some(3)), call(Some(3)), call(Some(2)), call(None)];
+
+        let tree = instruction.call_tree();
+        assert_eq!(tree.calls.iter().map(|call| call.calls.len()).collect::<Vec<_>>(), vec![2, 0, 0]);
+        assert_eq!(tree.calls[0].calls[1].program_id, instruction.inner_instructions[2].program_id);
+        assert_eq!(tree.walk().map(|call| call.depth).collect::<Vec<_>>(), vec![1, 2, 3, 3, 2, 2]);
+    }
+}
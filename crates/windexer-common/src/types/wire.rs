@@ -0,0 +1,459 @@
+//! Wire format of published data
+//!
+//! Every batch the geyser plugin publishes to the network travels as one
+//! frame: a short header naming the format version, the record kind and the
+//! publishing validator, followed by length-prefixed records. All integers
+//! are little-endian.
+//!
+//! Account records have a fixed layout that [`AccountView`] reads in place,
+//! so nodes can route and filter account updates on their pubkey and owner,
+//! and hand out their data, without decoding or copying the batch. The other
+//! kinds are bincode encoded, including [`AccountPatch`]es, which carry only
+//! the changed bytes of large accounts, see [`crate::types::diff`].
+//!
+//! Fields are only ever appended to the account layout, and readers skip
+//! record bytes past the fields they know, so frames from newer publishers
+//! stay readable. Any other change, including to the bincode encoded types,
+//! bumps [`WIRE_VERSION`]; frames of an unknown version are rejected. New
+//! record kinds don't, as readers reject frames of kinds they don't know.
+//!
+//! Transaction and block records lead with the field their
+//! [`IdempotencyKey`] is made of, the signature and the slot, so the keys of
+//! a frame are read without decoding its records; see
+//! [`crate::types::idempotency`].
+
+use {
+    crate::types::{
+        account::AccountData,
+        block::{BlockData, EntryData},
+        diff::AccountPatch,
+        idempotency::{IdempotencyKey, Idempotent},
+        transaction::TransactionData,
+    },
+    anyhow::{anyhow, Result},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+};
+
+pub const WIRE_VERSION: u8 = 2;
+
+const MAGIC: [u8; 2] = *b"wx";
+
+/// Offsets of the account record fields
+mod layout {
+    pub const PUBKEY: usize = 0;
+    pub const OWNER: usize = 32;
+    pub const LAMPORTS: usize = 64;
+    pub const RENT_EPOCH: usize = 72;
+    pub const WRITE_VERSION: usize = 80;
+    pub const SLOT: usize = 88;
+    pub const FLAGS: usize = 96;
+    /// Zeroed when the update has no transaction
+    pub const SIGNATURE: usize = 97;
+    pub const DATA_LEN: usize = 161;
+    pub const DATA: usize = 165;
+}
+
+const FLAG_EXECUTABLE: u8 = 1;
+const FLAG_STARTUP: u8 = 1 << 1;
+const FLAG_SIGNATURE: u8 = 1 << 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WireKind {
+    Accounts = 1,
+    Transactions = 2,
+    Blocks = 3,
+    Entries = 4,
+    AccountPatches = 5,
+}
+
+impl WireKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WireKind::Accounts => "accounts",
+            WireKind::Transactions => "transactions",
+            WireKind::Blocks => "blocks",
+            WireKind::Entries => "entries",
+            WireKind::AccountPatches => "account_patches",
+        }
+    }
+}
+
+impl TryFrom<u8> for WireKind {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            1 => WireKind::Accounts,
+            2 => WireKind::Transactions,
+            3 => WireKind::Blocks,
+            4 => WireKind::Entries,
+            5 => WireKind::AccountPatches,
+            kind => return Err(anyhow!("Unknown wire record kind {}", kind)),
+        })
+    }
+}
+
+/// A type sent as the records of a frame
+pub trait WireRecord: Sized {
+    const KIND: WireKind;
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<()>;
+
+    fn read(bytes: &[u8]) -> Result<Self>;
+}
+
+impl WireRecord for AccountData {
+    const KIND: WireKind = WireKind::Accounts;
+
+    fn write(&self, out: &mut Vec<u8>) -> Result<()> {
+        let mut flags = 0;
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.is_startup {
+            flags |= FLAG_STARTUP;
+        }
+        if self.transaction_signature.is_some() {
+            flags |= FLAG_SIGNATURE;
+        }
+        out.reserve(layout::DATA + self.data.len());
+        out.extend_from_slice(self.pubkey.as_ref());
+        out.extend_from_slice(self.owner.as_ref());
+        out.extend_from_slice(&self.lamports.to_le_bytes());
+        out.extend_from_slice(&self.rent_epoch.to_le_bytes());
+        out.extend_from_slice(&self.write_version.to_le_bytes());
+        out.extend_from_slice(&self.slot.to_le_bytes());
+        out.push(flags);
+        match &self.transaction_signature {
+            Some(signature) => out.extend_from_slice(signature.as_ref()),
+            None => out.extend_from_slice(&[0; 64]),
+        }
+        let data_len = u32::try_from(self.data.len())
+            .map_err(|_| anyhow!("Account {} data is too large for a frame", self.pubkey))?;
+        out.extend_from_slice(&data_len.to_le_bytes());
+        out.extend_from_slice(&self.data);
+        Ok(())
+    }
+
+    fn read(bytes: &[u8]) -> Result<Self> {
+        Ok(AccountView::new(bytes)?.to_account_data())
+    }
+}
+
+macro_rules! bincode_record {
+    ($type:ty, $kind:expr) => {
+        impl WireRecord for $type {
+            const KIND: WireKind = $kind;
+
+            fn write(&self, out: &mut Vec<u8>) -> Result<()> {
+                Ok(bincode::serialize_into(out, self)?)
+            }
+
+            fn read(bytes: &[u8]) -> Result<Self> {
+                Ok(bincode::deserialize(bytes)?)
+            }
+        }
+    };
+}
+
+bincode_record!(TransactionData, WireKind::Transactions);
+bincode_record!(BlockData, WireKind::Blocks);
+bincode_record!(EntryData, WireKind::Entries);
+bincode_record!(AccountPatch, WireKind::AccountPatches);
+
+/// Encodes `records` as one frame
+pub fn encode<T: WireRecord>(validator_id: Option<&str>, records: &[T]) -> Result<Vec<u8>> {
+    let validator_id = validator_id.unwrap_or_default().as_bytes();
+    let validator_len = u16::try_from(validator_id.len())
+        .map_err(|_| anyhow!("Validator id is too long for a frame"))?;
+    let count = u32::try_from(records.len())
+        .map_err(|_| anyhow!("Too many records for a frame"))?;
+
+    let mut out = Vec::with_capacity(64 + validator_id.len());
+    out.extend_from_slice(&MAGIC);
+    out.push(WIRE_VERSION);
+    out.push(T::KIND as u8);
+    out.extend_from_slice(&validator_len.to_le_bytes());
+    out.extend_from_slice(validator_id);
+    out.extend_from_slice(&count.to_le_bytes());
+    for record in records {
+        let start = out.len();
+        out.extend_from_slice(&[0; 4]);
+        record.write(&mut out)?;
+        let len = u32::try_from(out.len() - start - 4)
+            .map_err(|_| anyhow!("Record is too large for a frame"))?;
+        out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    }
+    Ok(out)
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(anyhow!("Frame is truncated"));
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Result<u32> {
+    Ok(u32::from_le_bytes(take(bytes, 4)?.try_into()?))
+}
+
+/// A received frame, borrowing its records from the payload
+#[derive(Debug, Clone, Copy)]
+pub struct WireFrame<'a> {
+    pub version: u8,
+    pub kind: WireKind,
+    pub validator_id: Option<&'a str>,
+    count: u32,
+    records: &'a [u8],
+}
+
+impl<'a> WireFrame<'a> {
+    pub fn parse(payload: &'a [u8]) -> Result<Self> {
+        let mut bytes = payload;
+        let header = take(&mut bytes, 4)?;
+        if header[..2] != MAGIC {
+            return Err(anyhow!("Not a wIndexer frame"));
+        }
+        let version = header[2];
+        if version != WIRE_VERSION {
+            return Err(anyhow!("Unsupported wire version {}", version));
+        }
+        let kind = WireKind::try_from(header[3])?;
+        let validator_len = u16::from_le_bytes(take(&mut bytes, 2)?.try_into()?) as usize;
+        let validator_id = std::str::from_utf8(take(&mut bytes, validator_len)?)
+            .map_err(|_| anyhow!("Validator id is not UTF-8"))?;
+        let count = take_u32(&mut bytes)?;
+
+        Ok(Self {
+            version,
+            kind,
+            validator_id: (!validator_id.is_empty()).then_some(validator_id),
+            count,
+            records: bytes,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The raw records, in order
+    pub fn records(&self) -> Records<'a> {
+        Records { rest: self.records, remaining: self.count }
+    }
+
+    /// Account records, read in place
+    pub fn accounts(&self) -> Result<impl Iterator<Item = Result<AccountView<'a>>> + 'a> {
+        self.expect(WireKind::Accounts)?;
+        Ok(self.records().map(|record| AccountView::new(record?)))
+    }
+
+    /// Decodes every record into its owned type
+    pub fn decode<T: WireRecord>(&self) -> Result<Vec<T>> {
+        self.expect(T::KIND)?;
+        self.records().map(|record| T::read(record?)).collect()
+    }
+
+    /// The idempotency key of every record, in order; entries have none
+    pub fn idempotency_keys(&self) -> Result<Vec<IdempotencyKey>> {
+        // bincode leaves the bytes after the leading field unread
+        self.records()
+            .map(|record| {
+                let record = record?;
+                Ok(match self.kind {
+                    WireKind::Accounts => AccountView::new(record)?.idempotency_key(),
+                    WireKind::Transactions => IdempotencyKey::Transaction(bincode::deserialize::<Signature>(record)?),
+                    WireKind::Blocks => IdempotencyKey::Block(bincode::deserialize::<u64>(record)?),
+                    WireKind::AccountPatches => AccountPatch::read(record)?.idempotency_key(),
+                    WireKind::Entries => return Err(anyhow!("Entries have no idempotency keys")),
+                })
+            })
+            .collect()
+    }
+
+    fn expect(&self, kind: WireKind) -> Result<()> {
+        if self.kind != kind {
+            return Err(anyhow!("Frame holds {:?}, not {:?}", self.kind, kind));
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the records of a frame
+#[derive(Debug, Clone)]
+pub struct Records<'a> {
+    rest: &'a [u8],
+    remaining: u32,
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let record = take_u32(&mut self.rest).and_then(|len| take(&mut self.rest, len as usize));
+        if record.is_err() {
+            self.remaining = 0;
+        }
+        Some(record)
+    }
+}
+
+/// An account record read in place
+#[derive(Clone, Copy)]
+pub struct AccountView<'a> {
+    bytes: &'a [u8],
+}
+
+impl std::fmt::Debug for AccountView<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccountView")
+            .field("pubkey", &self.pubkey())
+            .field("owner", &self.owner())
+            .field("slot", &self.slot())
+            .field("data_len", &self.data().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> AccountView<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < layout::DATA {
+            return Err(anyhow!("Account record is truncated"));
+        }
+        let view = Self { bytes };
+        if bytes.len() < layout::DATA + view.u32_at(layout::DATA_LEN) as usize {
+            return Err(anyhow!("Account data is truncated"));
+        }
+        Ok(view)
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn u64_at(&self, offset: usize) -> u64 {
+        u64::from_le_bytes(self.bytes[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn flag(&self, flag: u8) -> bool {
+        self.bytes[layout::FLAGS] & flag != 0
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.bytes[layout::PUBKEY..layout::OWNER].try_into().unwrap())
+    }
+
+    pub fn owner(&self) -> Pubkey {
+        Pubkey::new_from_array(self.bytes[layout::OWNER..layout::LAMPORTS].try_into().unwrap())
+    }
+
+    pub fn lamports(&self) -> u64 {
+        self.u64_at(layout::LAMPORTS)
+    }
+
+    pub fn rent_epoch(&self) -> u64 {
+        self.u64_at(layout::RENT_EPOCH)
+    }
+
+    pub fn write_version(&self) -> u64 {
+        self.u64_at(layout::WRITE_VERSION)
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.u64_at(layout::SLOT)
+    }
+
+    pub fn executable(&self) -> bool {
+        self.flag(FLAG_EXECUTABLE)
+    }
+
+    pub fn is_startup(&self) -> bool {
+        self.flag(FLAG_STARTUP)
+    }
+
+    pub fn transaction_signature(&self) -> Option<Signature> {
+        let bytes: [u8; 64] = self.bytes[layout::SIGNATURE..layout::DATA_LEN].try_into().unwrap();
+        self.flag(FLAG_SIGNATURE).then(|| Signature::from(bytes))
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        &self.bytes[layout::DATA..layout::DATA + self.u32_at(layout::DATA_LEN) as usize]
+    }
+
+    pub fn to_account_data(&self) -> AccountData {
+        AccountData {
+            pubkey: self.pubkey(),
+            lamports: self.lamports(),
+            owner: self.owner(),
+            executable: self.executable(),
+            rent_epoch: self.rent_epoch(),
+            data: self.data().to_vec(),
+            write_version: self.write_version(),
+            slot: self.slot(),
+            is_startup: self.is_startup(),
+            transaction_signature: self.transaction_signature(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_are_read_in_place() {
+        let account = AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1_000,
+            owner: Pubkey::new_unique(),
+            executable: true,
+            rent_epoch: u64::MAX,
+            data: vec![9; 100],
+            write_version: 7,
+            slot: 42,
+            is_startup: false,
+            transaction_signature: Some(Signature::from([5; 64])),
+        };
+        let mut payload = encode(Some("validator"), std::slice::from_ref(&account)).unwrap();
+
+        let frame = WireFrame::parse(&payload).unwrap();
+        assert_eq!(frame.validator_id, Some("validator"));
+        let view = frame.accounts().unwrap().next().unwrap().unwrap();
+        assert_eq!(view.owner(), account.owner);
+        assert_eq!(view.data().as_ptr(), payload[payload.len() - 100..].as_ptr());
+        assert_eq!(frame.decode::<AccountData>().unwrap()[0].pubkey, account.pubkey);
+        assert!(frame.decode::<BlockData>().is_err());
+
+        // A newer publisher appended a field to the record
+        let len_at = payload.len() - layout::DATA - 100 - 4;
+        let len = u32::from_le_bytes(payload[len_at..len_at + 4].try_into().unwrap());
+        payload[len_at..len_at + 4].copy_from_slice(&(len + 8).to_le_bytes());
+        payload.extend_from_slice(&[1; 8]);
+        let frame = WireFrame::parse(&payload).unwrap();
+        assert_eq!(frame.decode::<AccountData>().unwrap()[0].data, account.data);
+        assert_eq!(frame.idempotency_keys().unwrap(), vec![account.idempotency_key()]);
+    }
+
+    #[test]
+    fn block_keys_are_read_without_decoding() {
+        let blocks: Vec<BlockData> = [41, 42]
+            .into_iter()
+            .map(|slot| BlockData { slot, blockhash: Some("hash".to_string()), ..BlockData::default() })
+            .collect();
+        let payload = encode(None, &blocks).unwrap();
+        let keys = WireFrame::parse(&payload).unwrap().idempotency_keys().unwrap();
+        assert_eq!(keys, vec![IdempotencyKey::Block(41), IdempotencyKey::Block(42)]);
+    }
+}
@@ -2,6 +2,7 @@
 
 mod crypto;
 mod time;
+pub mod slot_audit;
 pub mod slot_status;
 pub mod transaction_status;
 
@@ -10,8 +11,9 @@ use solana_sdk::pubkey::Pubkey;
 
 pub use crypto::{hash_message, verify_signature};
 pub use time::{current_timestamp, duration_since};
+pub use slot_audit::{SlotAudit, SlotGap, SlotRecord};
 pub use slot_status::SerializableSlotStatus;
-pub use transaction_status::SerializableTransactionMeta;
+pub use transaction_status::{SerializableInnerInstructions, SerializableTransactionMeta};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexerState {
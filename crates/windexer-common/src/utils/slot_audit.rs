@@ -0,0 +1,141 @@
+//! Slot ingestion audit
+//!
+//! [`SlotAudit`] records the status transitions of every slot an ingestion
+//! path observes and checks the rooted chain for holes. Each rooted slot
+//! names its parent; when that parent lies past the last root seen, the
+//! roots in between never arrived and the range is reported as a
+//! [`SlotGap`] for the backfill to fill.
+//!
+//! A gap covers every slot between the two roots, including slots the
+//! leader skipped, which the backfill finds empty. Roots without a known
+//! parent cannot be checked and only advance the last root.
+
+use {
+    crate::utils::current_timestamp,
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    serde::{Deserialize, Serialize},
+    solana_sdk::clock::Slot,
+    std::collections::BTreeMap,
+};
+
+/// Slots whose transitions are kept by default
+pub const DEFAULT_AUDIT_HISTORY: usize = 1024;
+
+/// Range of slots whose roots were never observed, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotGap {
+    pub first_slot: Slot,
+    pub last_slot: Slot,
+}
+
+impl SlotGap {
+    pub fn slot_count(&self) -> u64 {
+        self.last_slot - self.first_slot + 1
+    }
+}
+
+/// What was observed of one slot
+#[derive(Debug, Clone, Default)]
+pub struct SlotRecord {
+    pub parent: Option<Slot>,
+    /// Statuses in the order they arrived, with the unix time they did
+    pub transitions: Vec<(SlotStatus, i64)>,
+}
+
+/// Tracks slot statuses and detects holes in the rooted chain
+#[derive(Debug)]
+pub struct SlotAudit {
+    slots: BTreeMap<Slot, SlotRecord>,
+    history: usize,
+    last_rooted: Option<Slot>,
+    missed_slots: u64,
+}
+
+impl Default for SlotAudit {
+    fn default() -> Self {
+        Self::new(DEFAULT_AUDIT_HISTORY)
+    }
+}
+
+impl SlotAudit {
+    /// Keep the transitions of the latest `history` slots
+    pub fn new(history: usize) -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            history: history.max(1),
+            last_rooted: None,
+            missed_slots: 0,
+        }
+    }
+
+    /// Record a status update, returning the gap it reveals if any
+    pub fn observe(&mut self, slot: Slot, parent: Option<Slot>, status: &SlotStatus) -> Option<SlotGap> {
+        let record = self.slots.entry(slot).or_default();
+        if parent.is_some() {
+            record.parent = parent;
+        }
+        record.transitions.push((status.clone(), current_timestamp()));
+        let parent = record.parent;
+
+        while self.slots.len() > self.history {
+            self.slots.pop_first();
+        }
+
+        if !matches!(status, SlotStatus::Rooted) {
+            return None;
+        }
+        let gap = match (self.last_rooted, parent) {
+            // A late or repeated root
+            (Some(root), _) if slot <= root => return None,
+            (Some(root), Some(parent)) if parent > root => Some(SlotGap {
+                first_slot: root + 1,
+                last_slot: parent,
+            }),
+            _ => None,
+        };
+
+        self.last_rooted = Some(slot);
+        if let Some(gap) = gap {
+            self.missed_slots += gap.slot_count();
+        }
+        gap
+    }
+
+    /// Transitions of a recent slot
+    pub fn record(&self, slot: Slot) -> Option<&SlotRecord> {
+        self.slots.get(&slot)
+    }
+
+    pub fn last_rooted(&self) -> Option<Slot> {
+        self.last_rooted
+    }
+
+    /// Slots covered by the gaps reported so far
+    pub fn missed_slots(&self) -> u64 {
+        self.missed_slots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_roots_missing_from_the_chain() {
+        let mut audit = SlotAudit::new(16);
+        assert_eq!(audit.observe(10, Some(9), &SlotStatus::Rooted), None);
+        audit.observe(11, Some(10), &SlotStatus::Processed);
+        // 12 was skipped by its leader, so 13 builds on 11
+        assert_eq!(audit.observe(11, None, &SlotStatus::Rooted), None);
+        assert_eq!(audit.observe(13, Some(11), &SlotStatus::Rooted), None);
+
+        // The roots of 14 to 16 never arrived
+        assert_eq!(
+            audit.observe(17, Some(16), &SlotStatus::Rooted),
+            Some(SlotGap { first_slot: 14, last_slot: 16 })
+        );
+        assert_eq!(audit.observe(15, Some(14), &SlotStatus::Rooted), None);
+        assert_eq!(audit.missed_slots(), 3);
+        assert_eq!(audit.record(11).unwrap().transitions.len(), 2);
+    }
+}
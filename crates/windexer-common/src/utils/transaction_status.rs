@@ -1,5 +1,7 @@
-use solana_transaction_status::TransactionStatusMeta;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
 use serde::{Serialize, Deserialize};
+use serde_json::Value;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SerializableTransactionMeta {
@@ -25,6 +27,32 @@ pub struct SerializableInstruction {
     pub program_id_index: u8,
     pub accounts: Vec<u8>,
     pub data: Vec<u8>,
+    /// Invocation depth, `None` when the validator did not record it
+    #[serde(default)]
+    pub stack_height: Option<u32>,
+}
+
+impl SerializableInnerInstructions {
+    /// The `innerInstructions` of a `json` or `base64` encoded RPC
+    /// transaction meta, `None` if the node left them out
+    pub fn from_json(meta: &Value) -> Option<Vec<Self>> {
+        let inner_instructions = meta.get("innerInstructions")?.as_array()?;
+        Some(inner_instructions.iter()
+            .filter_map(|inner| Some(Self {
+                index: u8::try_from(inner["index"].as_u64()?).ok()?,
+                instructions: inner["instructions"].as_array()?.iter()
+                    .filter_map(|instruction| Some(SerializableInstruction {
+                        program_id_index: u8::try_from(instruction["programIdIndex"].as_u64()?).ok()?,
+                        accounts: instruction["accounts"].as_array()?.iter()
+                            .map(|index| index.as_u64().and_then(|index| u8::try_from(index).ok()))
+                            .collect::<Option<_>>()?,
+                        data: bs58::decode(instruction["data"].as_str()?).into_vec().ok()?,
+                        stack_height: instruction["stackHeight"].as_u64().and_then(|height| u32::try_from(height).ok()),
+                    }))
+                    .collect(),
+            }))
+            .collect())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -52,6 +80,29 @@ pub struct SerializableReward {
     pub commission: Option<u8>,
 }
 
+impl SerializableTransactionMeta {
+    /// Inner instructions in the validator's form
+    pub fn inner_instructions(&self) -> Option<Vec<InnerInstructions>> {
+        self.inner_instructions.as_ref().map(|inner_instructions| {
+            inner_instructions.iter()
+                .map(|inner| InnerInstructions {
+                    index: inner.index,
+                    instructions: inner.instructions.iter()
+                        .map(|instruction| InnerInstruction {
+                            instruction: CompiledInstruction {
+                                program_id_index: instruction.program_id_index,
+                                accounts: instruction.accounts.clone(),
+                                data: instruction.data.clone(),
+                            },
+                            stack_height: instruction.stack_height,
+                        })
+                        .collect(),
+                })
+                .collect()
+        })
+    }
+}
+
 impl From<&TransactionStatusMeta> for SerializableTransactionMeta {
     fn from(meta: &TransactionStatusMeta) -> Self {
         let status = if meta.status.is_ok() {
@@ -65,7 +116,21 @@ impl From<&TransactionStatusMeta> for SerializableTransactionMeta {
             fee: meta.fee,
             pre_balances: meta.pre_balances.clone(),
             post_balances: meta.post_balances.clone(),
-            inner_instructions: None,
+            inner_instructions: meta.inner_instructions.as_ref().map(|inner_instructions| {
+                inner_instructions.iter()
+                    .map(|inner| SerializableInnerInstructions {
+                        index: inner.index,
+                        instructions: inner.instructions.iter()
+                            .map(|inner| SerializableInstruction {
+                                program_id_index: inner.instruction.program_id_index,
+                                accounts: inner.instruction.accounts.clone(),
+                                data: inner.instruction.data.clone(),
+                                stack_height: inner.stack_height,
+                            })
+                            .collect(),
+                    })
+                    .collect()
+            }),
             log_messages: meta.log_messages.clone(),
             pre_token_balances: None,
             post_token_balances: None,
@@ -0,0 +1,89 @@
+use {
+    anyhow::{Context, Result},
+    clap::Parser,
+    std::{
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    },
+    tracing::info,
+    tracing_subscriber::EnvFilter,
+    windexer_store::{
+        backfill::{read_gap_log, Backfill, BackfillConfig},
+        config::StoreConfig,
+        factory::WindexerStorageFactory,
+        traits::StorageFactory,
+    },
+};
+
+#[derive(Parser, Debug)]
+#[clap(
+    version,
+    about = "Backfill historical slots into a wIndexer store",
+    long_about = "Replays finalized blocks from an RPC endpoint into the configured store, resuming from its checkpoint"
+)]
+struct Args {
+    /// Storage backend config (TOML or JSON)
+    #[clap(long)]
+    store_config: PathBuf,
+
+    /// Backfill config (TOML)
+    #[clap(long)]
+    config: PathBuf,
+
+    /// Override the first slot to backfill
+    #[clap(long)]
+    start_slot: Option<u64>,
+
+    /// Override the last slot to backfill
+    #[clap(long)]
+    end_slot: Option<u64>,
+
+    /// Backfill the gaps in a Geyser plugin gap log instead of one range
+    #[clap(long)]
+    gaps: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .init();
+
+    let mut config = BackfillConfig::from_file(&args.config)?;
+    if let Some(start_slot) = args.start_slot {
+        config.start_slot = Some(start_slot);
+    }
+    if let Some(end_slot) = args.end_slot {
+        config.end_slot = Some(end_slot);
+    }
+
+    let factory = WindexerStorageFactory::new(StoreConfig::from_file(&args.store_config)?);
+    let storage = factory.create_storage().await.context("Failed to create storage")?;
+    let configs = match &args.gaps {
+        Some(path) => read_gap_log(path)?.iter().map(|gap| config.for_gap(gap)).collect(),
+        None => vec![config],
+    };
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopping = stop.clone();
+    ctrlc::set_handler(move || {
+        info!("Stopping backfill after the current batch");
+        stopping.store(true, Ordering::Relaxed);
+    })?;
+
+    for config in configs {
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let backfill = Backfill::new(config, storage.clone())?.with_stop_handle(stop.clone());
+        let checkpoint = backfill.run().await?;
+        info!("Next slot to backfill: {}", checkpoint.next_slot);
+    }
+
+    storage.close().await
+}
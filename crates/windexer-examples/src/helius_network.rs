@@ -63,6 +63,9 @@ async fn main() -> Result<()> {
         keypair: SerializableKeypair::new(&Keypair::new()),
         geyser_plugin_config: None,
         metrics_addr: Some(format!("127.0.0.1:{}", args.port + 2000).parse()?),
+        account_programs: Vec::new(),
+        transport: Default::default(),
+        connections: Default::default(),
     };
     
     // Create the node
@@ -233,6 +233,9 @@ async fn main() -> Result<()> {
         keypair: SerializableKeypair::new(&Keypair::new()),
         geyser_plugin_config: None,
         metrics_addr: Some(format!("127.0.0.1:{}", metrics_port).parse()?),
+        account_programs: Vec::new(),
+        transport: Default::default(),
+        connections: Default::default(),
     };
 
     info!("🚀 Starting wIndexer node");
@@ -165,6 +165,9 @@ async fn main() -> Result<()> {
         keypair: SerializableKeypair::new(&Keypair::new()),
         geyser_plugin_config: None,
         metrics_addr: Some(format!("127.0.0.1:{}", metrics_port).parse()?),
+        account_programs: Vec::new(),
+        transport: Default::default(),
+        connections: Default::default(),
     };
 
     info!("🚀 Starting local data generator");
@@ -278,6 +281,7 @@ async fn main() -> Result<()> {
                     compute_units_consumed: None,
                 }).into(),
                 index: i,
+                instructions: Vec::new(),
             };
             
             if let Err(e) = tx_tx.send(tx).await {
@@ -82,6 +82,9 @@ async fn main() -> Result<()> {
         keypair: SerializableKeypair::new(&Keypair::new()),
         geyser_plugin_config: None,
         metrics_addr: Some(format!("127.0.0.1:{}", metrics_port).parse()?),
+        account_programs: Vec::new(),
+        transport: Default::default(),
+        connections: Default::default(),
     };
 
     let staking_config = StakingConfig {
@@ -92,6 +95,8 @@ async fn main() -> Result<()> {
         distribution_interval: Duration::from_secs(60),
         slash_threshold: 0.90,
         min_uptime: 0.95,
+        cooldown_epochs: 1,
+        performance_retention_epochs: 30,
     };
 
     info!("🚀 Starting Jito-integrated node {} on port {}", args.index, port);
@@ -0,0 +1,51 @@
+// crates/windexer-geyser/benches/encoding.rs
+
+//! Encoding throughput of account updates, the bulk of what external
+//! publishers send, per message format and account size
+//!
+//! Run with `cargo bench -p windexer-geyser --bench encoding`.
+
+use {
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::hint::black_box,
+    windexer_common::types::account::AccountData,
+    windexer_geyser::{Encode, MessageFormat},
+};
+
+/// A token account, a typical program account and a large one
+const DATA_SIZES: [usize; 3] = [165, 4 * 1024, 1024 * 1024];
+
+fn account(data_len: usize) -> AccountData {
+    AccountData {
+        pubkey: Pubkey::new_unique(),
+        lamports: 2_039_280,
+        owner: Pubkey::new_unique(),
+        executable: false,
+        rent_epoch: u64::MAX,
+        data: (0..data_len).map(|i| i as u8).collect(),
+        write_version: 1,
+        slot: 300_000_000,
+        is_startup: false,
+        transaction_signature: Some(Signature::from([1; 64])),
+    }
+}
+
+fn encode_accounts(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_account");
+    for data_len in DATA_SIZES {
+        let account = account(data_len);
+        group.throughput(Throughput::Bytes(data_len as u64));
+        for format in [MessageFormat::Json, MessageFormat::Protobuf, MessageFormat::Borsh] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", format).to_lowercase(), data_len),
+                &account,
+                |b, account| b.iter(|| black_box(account).encode(format).unwrap()),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, encode_accounts);
+criterion_main!(benches);
@@ -48,6 +48,18 @@ fn main() {
         }
     }
     
+    // The sidecar service streams the export messages of windexer-common
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/sidecar.proto");
+        println!("cargo:rerun-if-changed=../windexer-common/proto/windexer.proto");
+        tonic_build::configure()
+            .build_client(false)
+            .extern_path(".windexer", "::windexer_common::types::proto")
+            .compile_protos(&["proto/sidecar.proto"], &["proto", "../windexer-common/proto"])
+            .expect("Failed to compile sidecar protos");
+    }
+
     println!("Building wIndexer Geyser plugin with Rust {}", version);
     
     println!("cargo:rerun-if-changed=build.rs");
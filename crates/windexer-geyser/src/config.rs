@@ -11,6 +11,7 @@ use {
     serde::{Deserialize, Serialize},
     anyhow::{anyhow, Result},
     std::{
+        collections::HashMap,
         fs::File,
         io::Read,
         net::SocketAddr,
@@ -21,21 +22,64 @@ use {
         pubkey::Pubkey,
         signature::Keypair,
     },
-    windexer_common,
+    windexer_common::{
+        self,
+        config::{ConnectionConfig, TransportKind},
+        dlq::DeadLetterConfig,
+        filter::{Filter, Subject},
+        types::AccountDiffConfig,
+    },
+};
+
+pub use windexer_common::config::storage::{
+    CompressionCodec, CompressionConfig, ParquetConfig, PostgresConfig, StorageConfig, StorageType,
 };
 
+/// Accounts to index: those listed in `accounts`, owned by a program in
+/// `owners` or token accounts held by a wallet in `token_owners`, narrowed
+/// by `data_size` and `memcmp` like RPC `getProgramAccounts` filters
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AccountsSelector {
     pub accounts: Vec<String>,
     #[serde(default)]
     pub owners: Option<Vec<String>>,
+    /// Wallets whose SPL Token and Token-2022 accounts are indexed
+    #[serde(default)]
+    pub token_owners: Option<Vec<String>>,
+    #[serde(default)]
+    pub data_size: Option<DataSizeFilter>,
+    /// Every pattern must match
+    #[serde(default)]
+    pub memcmp: Vec<MemcmpFilter>,
+}
+
+/// Inclusive bounds on the length of account data
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DataSizeFilter {
+    #[serde(default)]
+    pub min: Option<usize>,
+    #[serde(default)]
+    pub max: Option<usize>,
+}
+
+/// Account data must hold `bytes` at `offset`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct MemcmpFilter {
+    pub offset: usize,
+    /// Base58-encoded
+    pub bytes: String,
 }
 
+/// Transactions to index: those mentioning an account in `mentions` (`*`
+/// for all) or invoking a program in `programs`
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TransactionSelector {
     pub mentions: Vec<String>,
     #[serde(default)]
     pub include_votes: bool,
+    /// Programs whose top-level instructions select the transaction
+    #[serde(default)]
+    pub programs: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -50,80 +94,275 @@ pub struct NetworkConfig {
     pub geyser_plugin_config: Option<String>,
     #[serde(default)]
     pub metrics_addr: Option<SocketAddr>,
+    /// `tcp`, `quic` or `both`
+    #[serde(default)]
+    pub transport: TransportKind,
+    /// Connection limits and peer allow/deny lists
+    #[serde(default)]
+    pub connections: ConnectionConfig,
+}
+
+/// What a full spill queue does with another batch
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    /// Discard the oldest spilled batches to make room
+    #[default]
+    DropOldest,
+    /// Discard the batch being spilled
+    DropNewest,
 }
 
+/// On-disk queue for batches published while the node has no peers
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct MetricsConfig {
-    #[serde(default = "default_true")]
-    pub enabled: bool,
-    #[serde(default = "default_metrics_interval")]
-    pub interval_seconds: u64,
+pub struct SpillConfig {
+    pub directory: String,
+    #[serde(default = "default_spill_max_size_mb")]
+    pub max_size_mb: u64,
+    #[serde(default)]
+    pub drop_policy: DropPolicy,
+}
+
+/// What a processor does with a message when the worker it is dispatched
+/// to has a full queue
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait for the worker, holding up the validator's notifications
+    Block,
+    /// Discard the oldest queued message to make room
+    DropOldest,
+    /// Discard the message
+    #[default]
+    DropNewest,
+    /// Write overflowing messages to disk and feed them back in order once
+    /// the worker catches up
+    SpillToDisk,
+}
+
+/// Backpressure between the plugin callbacks and the processor workers
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct OverflowConfig {
+    #[serde(default)]
+    pub policy: OverflowPolicy,
+    /// Directory for `spill_to_disk`, one subdirectory per worker
+    #[serde(default)]
+    pub spill_directory: Option<String>,
+    /// Cap per worker; messages that do not fit are dropped
+    #[serde(default = "default_spill_max_size_mb")]
+    pub spill_max_size_mb: u64,
+}
+
+/// How external publishers encode each record
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageFormat {
+    #[default]
+    Json,
+    /// The Yellowstone gRPC update messages
+    Protobuf,
+    /// Borsh encoding of the same messages as `Protobuf`
+    Borsh,
 }
 
+/// Kafka topics or NATS subjects records are sent to, one per data type
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub enum StorageType {
-    #[serde(rename = "rocksdb")]
-    RocksDB,
-    #[serde(rename = "parquet")]
-    Parquet,
-    #[serde(rename = "postgres")]
-    Postgres,
+pub struct Topics {
+    #[serde(default = "default_accounts_topic")]
+    pub accounts: String,
+    #[serde(default = "default_transactions_topic")]
+    pub transactions: String,
+    #[serde(default = "default_blocks_topic")]
+    pub blocks: String,
+    #[serde(default = "default_entries_topic")]
+    pub entries: String,
 }
 
-impl Default for StorageType {
+impl Default for Topics {
     fn default() -> Self {
-        StorageType::RocksDB
+        Self {
+            accounts: default_accounts_topic(),
+            transactions: default_transactions_topic(),
+            blocks: default_blocks_topic(),
+            entries: default_entries_topic(),
+        }
     }
 }
 
+/// Kafka cluster the `kafka` publisher produces to
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct ParquetConfig {
-    pub directory: String,
-    #[serde(default = "default_parquet_file_size_mb")]
-    pub max_file_size_mb: usize,
-    #[serde(default = "default_true")]
-    pub compression_enabled: bool,
-    #[serde(default = "default_parquet_partition_by_slot")]
-    pub partition_by_slot: bool,
+pub struct KafkaConfig {
+    /// Comma-separated `host:port` list
+    pub brokers: String,
+    #[serde(default)]
+    pub topics: Topics,
+    #[serde(default)]
+    pub format: MessageFormat,
+    /// How long the producer waits to fill a batch
+    #[serde(default = "default_kafka_linger_ms")]
+    pub linger_ms: u64,
+    /// Extra librdkafka properties, e.g. `security.protocol` or `compression.type`
+    #[serde(default)]
+    pub options: HashMap<String, String>,
 }
 
+/// NATS server the `nats` publisher sends to, through JetStream
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct PostgresConfig {
-    pub connection_string: String,
-    #[serde(default = "default_true")]
-    pub create_tables: bool,
-    #[serde(default = "default_postgres_batch_size")]
-    pub batch_size: usize,
-    #[serde(default = "default_postgres_max_connections")]
-    pub max_connections: usize,
+pub struct NatsConfig {
+    /// Comma-separated server URLs, e.g. `nats://127.0.0.1:4222`
+    pub url: String,
+    /// Subjects must be bound to a stream on the server
+    #[serde(default)]
+    pub subjects: Topics,
+    #[serde(default)]
+    pub format: MessageFormat,
+    #[serde(default)]
+    pub credentials_file: Option<String>,
+    /// Messages published but not yet acknowledged by the stream; publishing
+    /// waits once this many are pending
+    #[serde(default = "default_nats_max_pending_acks")]
+    pub max_pending_acks: usize,
+    /// Reconnect attempts before giving up on the server, unlimited when unset
+    #[serde(default)]
+    pub max_reconnects: Option<usize>,
 }
 
+/// Local endpoint the `grpc` publisher streams to sidecar processes on
 #[derive(Debug, Deserialize, Serialize, Clone)]
-pub struct StorageConfig {
+pub struct GrpcConfig {
+    /// Unix socket to listen on; `addr` is used when unset
     #[serde(default)]
-    pub storage_type: StorageType,
+    pub socket_path: Option<String>,
+    /// Must be a loopback address
+    #[serde(default = "default_grpc_addr")]
+    pub addr: SocketAddr,
+    /// Updates held for subscribers; one that falls further behind is
+    /// disconnected
+    #[serde(default = "default_grpc_buffer")]
+    pub buffer: usize,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            socket_path: None,
+            addr: default_grpc_addr(),
+            buffer: default_grpc_buffer(),
+        }
+    }
+}
+
+/// Handling of the account snapshot the validator streams before
+/// `notify_end_of_startup`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StartupConfig {
+    /// Ignore snapshot accounts entirely and only index live updates
     #[serde(default)]
-    pub parquet: Option<ParquetConfig>,
+    pub skip: bool,
+    /// Accounts per published batch while the snapshot loads
+    #[serde(default = "default_startup_batch_size")]
+    pub batch_size: usize,
+    /// Hold selected snapshot accounts in memory until the end of startup
+    /// and publish them then; only sensible with a narrow accounts selector
     #[serde(default)]
-    pub postgres: Option<PostgresConfig>,
+    pub defer_publish: bool,
+    /// Accounts the snapshot is expected to hold, for progress reporting;
+    /// the count seen is logged at the end of every startup
     #[serde(default)]
-    pub rocksdb_path: Option<String>,
-    #[serde(default = "default_true")]
-    pub hot_cold_separation: bool,
+    pub expected_accounts: Option<u64>,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            skip: false,
+            batch_size: default_startup_batch_size(),
+            defer_publish: false,
+            expected_accounts: None,
+        }
+    }
+}
+
+/// Replay protection for live account updates
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AccountOrderingConfig {
+    /// Drop updates older than one already processed for the account;
+    /// when false they are published anyway and only counted. Off by
+    /// default: slots are compared regardless of fork, so after a switch to
+    /// a fork with lower slots its updates would be dropped as stale.
+    #[serde(default)]
+    pub drop_stale: bool,
+    /// Slots an account's latest version is remembered after its last update
+    #[serde(default = "default_ordering_window_slots")]
+    pub window_slots: u64,
+}
+
+impl Default for AccountOrderingConfig {
+    fn default() -> Self {
+        Self {
+            drop_stale: false,
+            window_slots: default_ordering_window_slots(),
+        }
+    }
+}
+
+/// Auditing of the slot statuses the validator reports
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SlotAuditConfig {
+    /// File every detected gap is appended to as a JSON line, for the
+    /// backfill to fill
+    #[serde(default)]
+    pub gap_log: Option<String>,
+    /// Slots whose status transitions are kept
+    #[serde(default = "default_audit_history")]
+    pub history_slots: usize,
 }
 
-impl Default for StorageConfig {
+impl Default for SlotAuditConfig {
     fn default() -> Self {
         Self {
-            storage_type: StorageType::RocksDB,
-            parquet: None,
-            postgres: None,
-            rocksdb_path: None,
-            hot_cold_separation: true,
+            gap_log: None,
+            history_slots: default_audit_history(),
         }
     }
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MetricsConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_metrics_interval")]
+    pub interval_seconds: u64,
+    /// Serve Prometheus metrics at `http://<bind_addr>/metrics`
+    #[serde(default)]
+    pub bind_addr: Option<SocketAddr>,
+    /// Also serve `GET`/`PUT /admin/log-level` and the `/admin/dead-letters`
+    /// routes on `bind_addr`; they are not authenticated, so only enable it on
+    /// a private address
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// Where processed data goes
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PublisherKind {
+    /// Gossiped to the wIndexer network through an embedded node
+    #[default]
+    Network,
+    /// Written to the backend in the `storage` section, without starting a
+    /// node; needs the `store` feature
+    Store,
+    /// Produced to the cluster in the `kafka` section; needs the `kafka` feature
+    Kafka,
+    /// Published to the JetStream server in the `nats` section; needs the
+    /// `nats` feature
+    Nats,
+    /// Streamed to local subscribers on the endpoint in the `grpc` section;
+    /// needs the `grpc` feature
+    Grpc,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct GeyserPluginConfig {
     pub libpath: String,
@@ -135,20 +374,76 @@ pub struct GeyserPluginConfig {
     pub accounts_selector: Option<AccountsSelector>,
     #[serde(default)]
     pub transaction_selector: Option<TransactionSelector>,
+    /// Filter expression selecting accounts, used instead of
+    /// `accounts_selector` when set
+    #[serde(default)]
+    pub accounts_filter: Option<Filter>,
+    /// Filter expression selecting transactions, used instead of
+    /// `transaction_selector` when set
+    #[serde(default)]
+    pub transactions_filter: Option<Filter>,
+    /// Drop vote transactions, most of the transaction stream, even if selected
+    #[serde(default)]
+    pub skip_vote_transactions: bool,
+    /// Drop transactions that failed, even if selected
+    #[serde(default)]
+    pub skip_failed_transactions: bool,
+    #[serde(default)]
+    pub startup: StartupConfig,
+    #[serde(default)]
+    pub account_ordering: AccountOrderingConfig,
+    #[serde(default)]
+    pub slot_audit: SlotAuditConfig,
+    #[serde(default)]
+    pub publisher: PublisherKind,
     #[serde(default = "default_thread_count")]
     pub thread_count: usize,
     #[serde(default = "default_batch_size")]
     pub batch_size: usize,
     #[serde(default)]
+    pub overflow: OverflowConfig,
+    /// Seconds unloading waits for queued messages to be published
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+    #[serde(default)]
     pub node_pubkey: Option<String>,
     #[serde(default)]
     pub panic_on_error: bool,
+    /// Log directives of the plugin, such as `info,windexer_geyser::processor=debug`;
+    /// the validator's level when unset
+    #[serde(default)]
+    pub log_level: Option<String>,
     #[serde(default = "default_true")]
     pub use_mmap: bool,
     #[serde(default)]
     pub metrics: MetricsConfig,
     #[serde(default)]
     pub storage: StorageConfig,
+    /// File where indexing progress is persisted; slots at or before the last
+    /// recorded rooted slot are not republished after a restart
+    #[serde(default)]
+    pub state_file: Option<String>,
+    /// Buffer outgoing batches on disk during network outages; they are
+    /// dropped when unset
+    #[serde(default)]
+    pub spill: Option<SpillConfig>,
+    /// Keep batches that failed to publish, or to be written by the
+    /// `store` publisher's backend, for a retry; they are dropped when unset
+    #[serde(default)]
+    pub dead_letters: Option<DeadLetterConfig>,
+    /// Publish updates of large accounts as patches of their changed bytes,
+    /// with periodic full snapshots; always in full when unset
+    #[serde(default)]
+    pub account_diffs: Option<AccountDiffConfig>,
+    /// Required by the `kafka` publisher
+    #[serde(default)]
+    pub kafka: Option<KafkaConfig>,
+    /// Required by the `nats` publisher
+    #[serde(default)]
+    pub nats: Option<NatsConfig>,
+    /// Endpoint of the `grpc` publisher, `127.0.0.1:10001` when unset
+    #[serde(default)]
+    pub grpc: Option<GrpcConfig>,
 }
 
 // Simplified SerializableKeypair - only implements what we need
@@ -208,6 +503,15 @@ impl GeyserPluginConfig {
         if self.keypair.is_empty() {
             return Err("keypair cannot be empty".to_string());
         }
+        if self.overflow.policy == OverflowPolicy::SpillToDisk && self.overflow.spill_directory.is_none() {
+            return Err("overflow policy spill_to_disk needs a spill_directory".to_string());
+        }
+        if let Some(filter) = &self.accounts_filter {
+            filter.check(Subject::Accounts).map_err(|e| format!("accounts_filter: {}", e))?;
+        }
+        if let Some(filter) = &self.transactions_filter {
+            filter.check(Subject::Transactions).map_err(|e| format!("transactions_filter: {}", e))?;
+        }
         Ok(())
     }
     
@@ -216,6 +520,9 @@ impl GeyserPluginConfig {
         self.accounts_selector.clone().unwrap_or_else(|| AccountsSelector {
             accounts: vec!["*".to_string()],
             owners: None,
+            token_owners: None,
+            data_size: None,
+            memcmp: Vec::new(),
         })
     }
     
@@ -224,6 +531,7 @@ impl GeyserPluginConfig {
         self.transaction_selector.clone().unwrap_or_else(|| TransactionSelector {
             mentions: vec!["*".to_string()],
             include_votes: false,
+            programs: Vec::new(),
         })
     }
     
@@ -251,6 +559,19 @@ fn default_batch_size() -> usize {
     100
 }
 
+fn default_drain_timeout_secs() -> u64 {
+    10
+}
+
+fn default_ordering_window_slots() -> u64 {
+    // About a minute of slots, far longer than updates are reordered by
+    150
+}
+
+fn default_audit_history() -> usize {
+    windexer_common::utils::slot_audit::DEFAULT_AUDIT_HISTORY
+}
+
 fn default_true() -> bool {
     true
 }
@@ -259,20 +580,44 @@ fn default_metrics_interval() -> u64 {
     15
 }
 
-fn default_parquet_file_size_mb() -> usize {
-    128 // 128 MB per file is a good balance for Parquet
+fn default_spill_max_size_mb() -> u64 {
+    1024
+}
+
+fn default_accounts_topic() -> String {
+    "windexer.accounts".to_string()
+}
+
+fn default_transactions_topic() -> String {
+    "windexer.transactions".to_string()
+}
+
+fn default_blocks_topic() -> String {
+    "windexer.blocks".to_string()
+}
+
+fn default_entries_topic() -> String {
+    "windexer.entries".to_string()
+}
+
+fn default_kafka_linger_ms() -> u64 {
+    5
+}
+
+fn default_nats_max_pending_acks() -> usize {
+    10_000
 }
 
-fn default_parquet_partition_by_slot() -> bool {
-    true // Partitioning by slot is efficient for blockchain data
+fn default_startup_batch_size() -> usize {
+    10_000
 }
 
-fn default_postgres_batch_size() -> usize {
-    1000 // Default batch size for PostgreSQL inserts
+fn default_grpc_addr() -> SocketAddr {
+    "127.0.0.1:10001".parse().unwrap()
 }
 
-fn default_postgres_max_connections() -> usize {
-    20 // Default connection pool size for PostgreSQL
+fn default_grpc_buffer() -> usize {
+    4096
 }
 
 impl Default for MetricsConfig {
@@ -280,6 +625,8 @@ impl Default for MetricsConfig {
         Self {
             enabled: default_true(),
             interval_seconds: default_metrics_interval(),
+            bind_addr: None,
+            admin: false,
         }
     }
 }
@@ -299,16 +646,36 @@ impl Default for GeyserPluginConfig {
                 solana_rpc_url: "http://127.0.0.1:8899".to_string(),
                 geyser_plugin_config: None,
                 metrics_addr: None,
+                transport: TransportKind::default(),
+                connections: ConnectionConfig::default(),
             },
             accounts_selector: None,
             transaction_selector: None,
+            accounts_filter: None,
+            transactions_filter: None,
+            skip_vote_transactions: false,
+            skip_failed_transactions: false,
+            startup: StartupConfig::default(),
+            account_ordering: AccountOrderingConfig::default(),
+            slot_audit: SlotAuditConfig::default(),
+            publisher: PublisherKind::default(),
             thread_count: 4,
             batch_size: 100,
+            overflow: OverflowConfig::default(),
+            drain_timeout_secs: default_drain_timeout_secs(),
             node_pubkey: None,
             panic_on_error: false,
+            log_level: None,
             use_mmap: true,
             metrics: MetricsConfig::default(),
             storage: StorageConfig::default(),
+            state_file: None,
+            spill: None,
+            dead_letters: None,
+            account_diffs: None,
+            kafka: None,
+            nats: None,
+            grpc: None,
         }
     }
 }
\ No newline at end of file
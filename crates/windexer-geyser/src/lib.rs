@@ -33,6 +33,7 @@ use {
 };
 
 mod config;
+mod logging;
 mod plugin;
 mod processor;
 mod publisher;
@@ -41,8 +42,9 @@ mod metrics;
 mod tests;
 
 // Public exports
-pub use config::GeyserPluginConfig;
+pub use config::{GeyserPluginConfig, MessageFormat};
 pub use metrics::Metrics;
+pub use publisher::Encode;
 pub use processor::{AccountHandler, TransactionHandler, BlockHandler};
 
 #[no_mangle]
@@ -0,0 +1,85 @@
+// crates/windexer-geyser/src/logging.rs
+
+//! Plugin log levels
+//!
+//! The validator hands the plugin its logger in `setup_logger`. The plugin
+//! installs it behind a [`PluginLogger`], which drops records its own
+//! [`Directives`] filter out before the validator's filter sees them. The
+//! directives start from `log_level` in the plugin config and can be changed
+//! at `/admin/log-level` on the metrics endpoint when `metrics.admin` is set.
+//!
+//! The validator's filter still applies after the plugin's: a module only
+//! logs at `debug` if the validator's `RUST_LOG` lets it through as well.
+
+use {
+    anyhow::{anyhow, Result},
+    log::{LevelFilter, Log, Metadata, Record},
+    std::sync::{OnceLock, RwLock},
+    windexer_common::{control::LogControl, logging::Directives},
+};
+
+static LOGGER: OnceLock<PluginLogger> = OnceLock::new();
+
+pub struct PluginLogger {
+    inner: &'static dyn Log,
+    directives: RwLock<Directives>,
+}
+
+impl PluginLogger {
+    /// The installed logger, once `setup_logger` ran
+    pub fn get() -> Option<&'static PluginLogger> {
+        LOGGER.get()
+    }
+
+    /// Install `inner` as the plugin's logger, filtered at `level` until
+    /// the directives are changed
+    pub fn install(inner: &'static dyn Log, level: LevelFilter) -> Result<&'static PluginLogger> {
+        let mut directives = Directives::default();
+        directives.set(None, level);
+        LOGGER
+            .set(PluginLogger {
+                inner,
+                directives: RwLock::new(directives),
+            })
+            .map_err(|_| anyhow!("The plugin logger is already installed"))?;
+        let logger = LOGGER.get().expect("plugin logger was just installed");
+        log::set_logger(logger).map_err(|e| anyhow!("Failed to set the plugin logger: {}", e))?;
+        log::set_max_level(level);
+        Ok(logger)
+    }
+}
+
+impl Log for PluginLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.directives
+            .read()
+            .map(|directives| metadata.level() <= directives.level_for(metadata.target()))
+            .unwrap_or(true)
+            && self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+impl LogControl for PluginLogger {
+    fn directives(&self) -> String {
+        self.directives.read().map(|d| d.to_string()).unwrap_or_default()
+    }
+
+    fn set_directives(&self, directives: &str) -> Result<()> {
+        let directives: Directives = directives.parse()?;
+        log::set_max_level(directives.max_level());
+        if let Ok(mut current) = self.directives.write() {
+            *current = directives;
+        }
+        Ok(())
+    }
+}
@@ -2,78 +2,482 @@
 
 //! Plugin metrics
 //!
-//! This module contains the metrics for the wIndexer Geyser plugin.
+//! This module contains the metrics for the wIndexer Geyser plugin. They form
+//! a [`MetricSet`] registered with the plugin's [`MetricsRegistry`], next to
+//! the metrics of the embedded network node or store, and are rendered in the
+//! Prometheus text format by the endpoint [`serve`] starts on the plugin
+//! runtime:
+//!
+//! | metric                                       | type      | labels               |
+//! |----------------------------------------------|-----------|----------------------|
+//! | `windexer_geyser_<counter>_total`            | counter   |                      |
+//! | `windexer_geyser_startup_accounts_expected`  | gauge     |                      |
+//! | `windexer_geyser_program_accounts_total`     | counter   | `program`            |
+//! | `windexer_geyser_program_transactions_total` | counter   | `program`            |
+//! | `windexer_geyser_queue_depth`                | gauge     | `processor`, `queue` |
+//! | `windexer_geyser_publish_duration_seconds`   | histogram | `kind`               |
+//!
+//! Queue depths are sampled at scrape time from the queues registered with
+//! [`Metrics::watch_queue`]. Accounts are counted under their owner program
+//! and transactions under each program they invoke; past
+//! [`MAX_PROGRAM_LABELS`] programs the rest are counted as `other`.
 
 use {
+    anyhow::{anyhow, Result},
+    log::info,
+    solana_sdk::pubkey::Pubkey,
     std::{
+        collections::HashSet,
         fmt::{Debug, Formatter, Result as FmtResult},
-        sync::atomic::{AtomicU64, Ordering},
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+    },
+    serde::Deserialize,
+    serde_json::json,
+    warp::{http::StatusCode, Filter},
+    windexer_common::control::{DeadLetterControl, DeadLetterRequest, LogControl, LogLevelRequest},
+    windexer_common::metrics::{
+        self, Collector, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, LabelLimit,
+        MetricSet, MetricsRegistry, TEXT_CONTENT_TYPE,
     },
 };
 
+const SUBSYSTEM: &str = "geyser";
+
+/// Distinct programs labeled before the rest are counted as `other`
+pub const MAX_PROGRAM_LABELS: usize = 200;
+
+/// Upper bounds, in seconds, of the publish latency buckets
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 1.0,
+];
+
+type QueueDepth = Box<dyn Fn() -> usize + Send + Sync>;
+
 /// Plugin metrics
 pub struct Metrics {
-    pub account_updates: AtomicU64,
-    pub account_update_errors: AtomicU64,
-    pub transaction_updates: AtomicU64,
-    pub transaction_update_errors: AtomicU64,
-    pub block_updates: AtomicU64,
-    pub block_update_errors: AtomicU64,
-    pub entry_updates: AtomicU64,
-    pub entry_updates_errors: AtomicU64,
-    pub account_batches_published: AtomicU64,
-    pub account_publish_errors: AtomicU64,
-    pub transaction_batches_published: AtomicU64,
-    pub transaction_publish_errors: AtomicU64,
-    pub blocks_published: AtomicU64,
-    pub block_publish_errors: AtomicU64,
-    pub entry_batches_published: AtomicU64,
-    pub entry_publish_errors: AtomicU64,
+    pub account_updates: IntCounter,
+    pub account_update_errors: IntCounter,
+    pub transaction_updates: IntCounter,
+    pub transaction_update_errors: IntCounter,
+    /// Vote and failed transactions dropped by the skip flags
+    pub transactions_skipped: IntCounter,
+    pub block_updates: IntCounter,
+    pub block_update_errors: IntCounter,
+    pub entry_updates: IntCounter,
+    pub entry_updates_errors: IntCounter,
+    pub account_batches_published: IntCounter,
+    pub account_publish_errors: IntCounter,
+    /// Account updates published as patches of their data
+    pub account_patches_published: IntCounter,
+    pub transaction_batches_published: IntCounter,
+    pub transaction_publish_errors: IntCounter,
+    pub blocks_published: IntCounter,
+    pub block_publish_errors: IntCounter,
+    pub entry_batches_published: IntCounter,
+    pub entry_publish_errors: IntCounter,
+    /// Snapshot accounts received before the end of startup
+    pub startup_accounts_seen: IntCounter,
+    /// Snapshot size from the config, zero when unknown
+    pub startup_accounts_expected: IntGauge,
+    /// Snapshot accounts ignored because `startup.skip` is set
+    pub startup_accounts_skipped: IntCounter,
+    /// Batches written to the spill queue while the node had no peers
+    pub batches_spilled: IntCounter,
+    /// Batches discarded because the spill queue was full
+    pub spilled_batches_dropped: IntCounter,
+    /// Spilled batches published once peers returned
+    pub spilled_batches_drained: IntCounter,
+    /// Batches that failed to publish, kept in the dead-letter queue
+    pub dead_letters: IntCounter,
+    /// Messages lost because an account worker's queue was full
+    pub account_messages_dropped: IntCounter,
+    /// Messages lost because a transaction worker's queue was full
+    pub transaction_messages_dropped: IntCounter,
+    /// Messages lost because a block worker's queue was full
+    pub block_messages_dropped: IntCounter,
+    /// Account updates not newer than one already processed for the account
+    pub account_updates_out_of_order: IntCounter,
+    /// Slots in gaps of the rooted chain the validator reported
+    pub missed_slots: IntCounter,
+    /// Messages written to disk by the `spill_to_disk` overflow policy
+    pub messages_overflowed: IntCounter,
+    /// Warnings logged about workers falling behind
+    pub slow_consumer_warnings: IntCounter,
+    /// Messages an external broker acknowledged
+    pub messages_delivered: IntCounter,
+    /// Messages an external broker failed to take
+    pub delivery_failures: IntCounter,
+    pub account_publish_latency: Histogram,
+    pub transaction_publish_latency: Histogram,
+    pub block_publish_latency: Histogram,
+    pub entry_publish_latency: Histogram,
+    publish_duration: HistogramVec,
+    queue_depth: IntGaugeVec,
+    program_accounts: IntCounterVec,
+    program_transactions: IntCounterVec,
+    program_labels: LabelLimit,
+    /// `(processor, queue)` names and depths of the processor queues
+    queues: Mutex<Vec<(&'static str, String, QueueDepth)>>,
+}
+
+fn counter(name: &str, help: &str) -> IntCounter {
+    metrics::counter(SUBSYSTEM, &format!("{}_total", name), help)
 }
 
 impl Metrics {
     /// Create new metrics
     pub fn new() -> Self {
+        let publish_duration = metrics::histogram_vec(
+            SUBSYSTEM,
+            "publish_duration_seconds",
+            "Publisher call latency",
+            &["kind"],
+            LATENCY_BUCKETS.to_vec(),
+        );
+
         Self {
-            account_updates: AtomicU64::new(0),
-            account_update_errors: AtomicU64::new(0),
-            transaction_updates: AtomicU64::new(0),
-            transaction_update_errors: AtomicU64::new(0),
-            block_updates: AtomicU64::new(0),
-            block_update_errors: AtomicU64::new(0),
-            entry_updates: AtomicU64::new(0),
-            entry_updates_errors: AtomicU64::new(0),
-            account_batches_published: AtomicU64::new(0),
-            account_publish_errors: AtomicU64::new(0),
-            transaction_batches_published: AtomicU64::new(0),
-            transaction_publish_errors: AtomicU64::new(0),
-            blocks_published: AtomicU64::new(0),
-            block_publish_errors: AtomicU64::new(0),
-            entry_batches_published: AtomicU64::new(0),
-            entry_publish_errors: AtomicU64::new(0),
+            account_updates: counter("account_updates", "Account updates received"),
+            account_update_errors: counter("account_update_errors", "Account updates that failed to queue"),
+            transaction_updates: counter("transaction_updates", "Transactions received"),
+            transaction_update_errors: counter("transaction_update_errors", "Transactions that failed to queue"),
+            transactions_skipped: counter("transactions_skipped", "Vote and failed transactions dropped by the skip flags"),
+            block_updates: counter("block_updates", "Block and slot notifications received"),
+            block_update_errors: counter("block_update_errors", "Block and slot notifications that failed to queue"),
+            entry_updates: counter("entry_updates", "Entries received"),
+            entry_updates_errors: counter("entry_update_errors", "Entries that failed to queue"),
+            account_batches_published: counter("accounts_published", "Accounts published"),
+            account_publish_errors: counter("account_publish_errors", "Failed account publishes"),
+            account_patches_published: counter("account_patches_published", "Account updates published as patches of their data"),
+            transaction_batches_published: counter("transactions_published", "Transactions published"),
+            transaction_publish_errors: counter("transaction_publish_errors", "Failed transaction publishes"),
+            blocks_published: counter("blocks_published", "Blocks published"),
+            block_publish_errors: counter("block_publish_errors", "Failed block publishes"),
+            entry_batches_published: counter("entries_published", "Entries published"),
+            entry_publish_errors: counter("entry_publish_errors", "Failed entry publishes"),
+            startup_accounts_seen: counter("startup_accounts_seen", "Snapshot accounts received before the end of startup"),
+            startup_accounts_expected: metrics::gauge(
+                SUBSYSTEM,
+                "startup_accounts_expected",
+                "Snapshot size from the config, zero when unknown",
+            ),
+            startup_accounts_skipped: counter("startup_accounts_skipped", "Snapshot accounts ignored because startup.skip is set"),
+            batches_spilled: counter("batches_spilled", "Batches written to the spill queue"),
+            spilled_batches_dropped: counter("spilled_batches_dropped", "Batches discarded because the spill queue was full"),
+            spilled_batches_drained: counter("spilled_batches_drained", "Spilled batches published once peers returned"),
+            dead_letters: counter("dead_letters", "Batches that failed to publish, kept in the dead-letter queue"),
+            account_messages_dropped: counter("account_messages_dropped", "Messages lost because an account worker's queue was full"),
+            transaction_messages_dropped: counter("transaction_messages_dropped", "Messages lost because a transaction worker's queue was full"),
+            block_messages_dropped: counter("block_messages_dropped", "Messages lost because a block worker's queue was full"),
+            account_updates_out_of_order: counter("account_updates_out_of_order", "Stale or replayed account updates"),
+            missed_slots: counter("missed_slots", "Slots in gaps of the rooted chain"),
+            messages_overflowed: counter("messages_overflowed", "Messages written to disk by the spill_to_disk overflow policy"),
+            slow_consumer_warnings: counter("slow_consumer_warnings", "Warnings logged about workers falling behind"),
+            messages_delivered: counter("messages_delivered", "Messages an external broker acknowledged"),
+            delivery_failures: counter("delivery_failures", "Messages an external broker failed to take"),
+            account_publish_latency: publish_duration.with_label_values(&["account"]),
+            transaction_publish_latency: publish_duration.with_label_values(&["transaction"]),
+            block_publish_latency: publish_duration.with_label_values(&["block"]),
+            entry_publish_latency: publish_duration.with_label_values(&["entry"]),
+            publish_duration,
+            queue_depth: metrics::gauge_vec(
+                SUBSYSTEM,
+                "queue_depth",
+                "Messages waiting in a processor queue",
+                &["processor", "queue"],
+            ),
+            program_accounts: metrics::counter_vec(
+                SUBSYSTEM,
+                "program_accounts_total",
+                "Account updates processed per owner program",
+                &["program"],
+            ),
+            program_transactions: metrics::counter_vec(
+                SUBSYSTEM,
+                "program_transactions_total",
+                "Transactions processed per invoked program",
+                &["program"],
+            ),
+            program_labels: LabelLimit::new(MAX_PROGRAM_LABELS),
+            queues: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Count an account update under its owner program
+    pub fn count_account(&self, owner: &Pubkey) {
+        let owner = owner.to_string();
+        self.program_accounts
+            .with_label_values(&[self.program_labels.label(&owner)])
+            .inc();
+    }
+
+    /// Count a transaction once under each program it invokes
+    pub fn count_transaction(&self, programs: &[Pubkey]) {
+        let programs: HashSet<&Pubkey> = programs.iter().collect();
+        for program in programs {
+            let program = program.to_string();
+            self.program_transactions
+                .with_label_values(&[self.program_labels.label(&program)])
+                .inc();
+        }
+    }
+
+    /// Report the depth of a processor queue when scraped
+    pub fn watch_queue(&self, processor: &'static str, queue: String, depth: impl Fn() -> usize + Send + Sync + 'static) {
+        if let Ok(mut queues) = self.queues.lock() {
+            queues.push((processor, queue, Box::new(depth)));
+        }
+    }
+
+    /// Forget the watched queues, which hold on to their channels
+    pub fn unwatch_queues(&self) {
+        if let Ok(mut queues) = self.queues.lock() {
+            queues.clear();
+        }
+        self.queue_depth.reset();
+    }
+}
+
+impl MetricSet for Metrics {
+    fn collectors(&self) -> Vec<&dyn Collector> {
+        vec![
+            &self.account_updates,
+            &self.account_update_errors,
+            &self.transaction_updates,
+            &self.transaction_update_errors,
+            &self.transactions_skipped,
+            &self.block_updates,
+            &self.block_update_errors,
+            &self.entry_updates,
+            &self.entry_updates_errors,
+            &self.account_batches_published,
+            &self.account_publish_errors,
+            &self.account_patches_published,
+            &self.transaction_batches_published,
+            &self.transaction_publish_errors,
+            &self.blocks_published,
+            &self.block_publish_errors,
+            &self.entry_batches_published,
+            &self.entry_publish_errors,
+            &self.startup_accounts_seen,
+            &self.startup_accounts_expected,
+            &self.startup_accounts_skipped,
+            &self.batches_spilled,
+            &self.spilled_batches_dropped,
+            &self.spilled_batches_drained,
+            &self.dead_letters,
+            &self.account_messages_dropped,
+            &self.transaction_messages_dropped,
+            &self.block_messages_dropped,
+            &self.account_updates_out_of_order,
+            &self.missed_slots,
+            &self.messages_overflowed,
+            &self.slow_consumer_warnings,
+            &self.messages_delivered,
+            &self.delivery_failures,
+            &self.publish_duration,
+            &self.queue_depth,
+            &self.program_accounts,
+            &self.program_transactions,
+        ]
+    }
+
+    fn sample(&self) {
+        if let Ok(queues) = self.queues.lock() {
+            for (processor, queue, depth) in queues.iter() {
+                self.queue_depth
+                    .with_label_values(&[*processor, queue.as_str()])
+                    .set(depth() as i64);
+            }
         }
     }
 }
 
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dead letters listed when the request sets no limit
+const DEFAULT_DEAD_LETTER_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct DeadLetterQuery {
+    limit: Option<usize>,
+}
+
+/// Serves `GET /metrics` with everything in `registry` on `addr` on the
+/// current runtime, `GET`/`PUT /admin/log-level` with `log_control`, and
+/// `GET /admin/dead-letters` with `POST /admin/dead-letters/{retry,purge}`
+/// with `dead_letter_control`
+pub fn serve(
+    registry: MetricsRegistry,
+    addr: SocketAddr,
+    log_control: Option<&'static dyn LogControl>,
+    dead_letter_control: Option<Arc<dyn DeadLetterControl>>,
+) -> Result<()> {
+    let metrics = warp::path("metrics")
+        .and(warp::path::end())
+        .and(warp::get())
+        .map(move || {
+            warp::reply::with_header(registry.render(), "content-type", TEXT_CONTENT_TYPE)
+        });
+
+    let log_level = warp::path!("admin" / "log-level");
+    let get_log_level = log_level
+        .and(warp::get())
+        .map(move || match log_control {
+            Some(control) => reply(StatusCode::OK, json!({ "directives": control.directives() })),
+            None => reply(StatusCode::NOT_FOUND, json!({ "error": "metrics.admin is not enabled" })),
+        });
+    let set_log_level = log_level
+        .and(warp::put())
+        .and(warp::body::json())
+        .map(move |request: LogLevelRequest| {
+            let Some(control) = log_control else {
+                return reply(StatusCode::NOT_FOUND, json!({ "error": "metrics.admin is not enabled" }));
+            };
+            match request.apply(control) {
+                Ok(()) => {
+                    info!("Log directives changed to {}", control.directives());
+                    reply(StatusCode::OK, json!({ "directives": control.directives() }))
+                }
+                Err(e) => reply(StatusCode::BAD_REQUEST, json!({ "error": e.to_string() })),
+            }
+        });
+
+    let dead_letter_control = warp::any().map(move || dead_letter_control.clone());
+    let list_dead_letters = warp::path!("admin" / "dead-letters")
+        .and(warp::get())
+        .and(warp::query::<DeadLetterQuery>())
+        .and(dead_letter_control.clone())
+        .then(|query: DeadLetterQuery, control: Option<Arc<dyn DeadLetterControl>>| async move {
+            let Some(control) = control else {
+                return reply(StatusCode::NOT_FOUND, json!({ "error": "dead letters are not enabled" }));
+            };
+            match control.dead_letters(query.limit.unwrap_or(DEFAULT_DEAD_LETTER_LIMIT)).await {
+                Ok(dead_letters) => reply(StatusCode::OK, json!(dead_letters)),
+                Err(e) => reply(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": e.to_string() })),
+            }
+        });
+    let retry_dead_letters = warp::path!("admin" / "dead-letters" / "retry")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(dead_letter_control.clone())
+        .then(|request: DeadLetterRequest, control: Option<Arc<dyn DeadLetterControl>>| async move {
+            let Some(control) = control else {
+                return reply(StatusCode::NOT_FOUND, json!({ "error": "dead letters are not enabled" }));
+            };
+            match control.retry(request.ids.as_deref()).await {
+                Ok(report) => {
+                    info!("Retried dead letters: {} delivered, {} failed again", report.delivered, report.failed);
+                    reply(StatusCode::OK, json!(report))
+                }
+                Err(e) => reply(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": e.to_string() })),
+            }
+        });
+    let purge_dead_letters = warp::path!("admin" / "dead-letters" / "purge")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(dead_letter_control)
+        .then(|request: DeadLetterRequest, control: Option<Arc<dyn DeadLetterControl>>| async move {
+            let Some(control) = control else {
+                return reply(StatusCode::NOT_FOUND, json!({ "error": "dead letters are not enabled" }));
+            };
+            match control.purge(request.ids.as_deref()).await {
+                Ok(purged) => {
+                    info!("Purged {} dead letters", purged);
+                    reply(StatusCode::OK, json!({ "purged": purged }))
+                }
+                Err(e) => reply(StatusCode::INTERNAL_SERVER_ERROR, json!({ "error": e.to_string() })),
+            }
+        });
+
+    let route = metrics
+        .or(get_log_level)
+        .or(set_log_level)
+        .or(list_dead_letters)
+        .or(retry_dead_letters)
+        .or(purge_dead_letters);
+
+    let (addr, server) = warp::serve(route)
+        .try_bind_ephemeral(addr)
+        .map_err(|e| anyhow!("Failed to bind metrics endpoint {}: {}", addr, e))?;
+    info!("Serving metrics on http://{}/metrics", addr);
+    tokio::spawn(server);
+    Ok(())
+}
+
+fn reply(status: StatusCode, body: serde_json::Value) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(warp::reply::json(&body), status)
+}
+
 impl Debug for Metrics {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         f.debug_struct("Metrics")
-            .field("account_updates", &self.account_updates.load(Ordering::Relaxed))
-            .field("account_update_errors", &self.account_update_errors.load(Ordering::Relaxed))
-            .field("transaction_updates", &self.transaction_updates.load(Ordering::Relaxed))
-            .field("transaction_update_errors", &self.transaction_update_errors.load(Ordering::Relaxed))
-            .field("block_updates", &self.block_updates.load(Ordering::Relaxed))
-            .field("block_update_errors", &self.block_update_errors.load(Ordering::Relaxed))
-            .field("entry_updates", &self.entry_updates.load(Ordering::Relaxed))
-            .field("entry_updates_errors", &self.entry_updates_errors.load(Ordering::Relaxed))
-            .field("account_batches_published", &self.account_batches_published.load(Ordering::Relaxed))
-            .field("account_publish_errors", &self.account_publish_errors.load(Ordering::Relaxed))
-            .field("transaction_batches_published", &self.transaction_batches_published.load(Ordering::Relaxed))
-            .field("transaction_publish_errors", &self.transaction_publish_errors.load(Ordering::Relaxed))
-            .field("blocks_published", &self.blocks_published.load(Ordering::Relaxed))
-            .field("block_publish_errors", &self.block_publish_errors.load(Ordering::Relaxed))
-            .field("entry_batches_published", &self.entry_batches_published.load(Ordering::Relaxed))
-            .field("entry_publish_errors", &self.entry_publish_errors.load(Ordering::Relaxed))
-            .finish()
+            .field("account_updates", &self.account_updates.get())
+            .field("account_update_errors", &self.account_update_errors.get())
+            .field("transaction_updates", &self.transaction_updates.get())
+            .field("transaction_update_errors", &self.transaction_update_errors.get())
+            .field("transactions_skipped", &self.transactions_skipped.get())
+            .field("block_updates", &self.block_updates.get())
+            .field("block_update_errors", &self.block_update_errors.get())
+            .field("entry_updates", &self.entry_updates.get())
+            .field("entry_updates_errors", &self.entry_updates_errors.get())
+            .field("account_batches_published", &self.account_batches_published.get())
+            .field("account_publish_errors", &self.account_publish_errors.get())
+            .field("account_patches_published", &self.account_patches_published.get())
+            .field("transaction_batches_published", &self.transaction_batches_published.get())
+            .field("transaction_publish_errors", &self.transaction_publish_errors.get())
+            .field("blocks_published", &self.blocks_published.get())
+            .field("block_publish_errors", &self.block_publish_errors.get())
+            .field("entry_batches_published", &self.entry_batches_published.get())
+            .field("entry_publish_errors", &self.entry_publish_errors.get())
+            .field("startup_accounts_seen", &self.startup_accounts_seen.get())
+            .field("startup_accounts_expected", &self.startup_accounts_expected.get())
+            .field("startup_accounts_skipped", &self.startup_accounts_skipped.get())
+            .field("batches_spilled", &self.batches_spilled.get())
+            .field("spilled_batches_dropped", &self.spilled_batches_dropped.get())
+            .field("spilled_batches_drained", &self.spilled_batches_drained.get())
+            .field("dead_letters", &self.dead_letters.get())
+            .field("account_messages_dropped", &self.account_messages_dropped.get())
+            .field("transaction_messages_dropped", &self.transaction_messages_dropped.get())
+            .field("block_messages_dropped", &self.block_messages_dropped.get())
+            .field("account_updates_out_of_order", &self.account_updates_out_of_order.get())
+            .field("missed_slots", &self.missed_slots.get())
+            .field("messages_overflowed", &self.messages_overflowed.get())
+            .field("slow_consumer_warnings", &self.slow_consumer_warnings.get())
+            .field("messages_delivered", &self.messages_delivered.get())
+            .field("delivery_failures", &self.delivery_failures.get())
+            .field("account_publishes", &self.account_publish_latency.get_sample_count())
+            .field("transaction_publishes", &self.transaction_publish_latency.get_sample_count())
+            .field("block_publishes", &self.block_publish_latency.get_sample_count())
+            .field("entry_publishes", &self.entry_publish_latency.get_sample_count())
+            .finish_non_exhaustive()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use {super::*, std::sync::Arc};
+
+    #[test]
+    fn renders_counters_queues_and_latencies() {
+        let metrics = Arc::new(Metrics::new());
+        metrics.account_updates.inc_by(3);
+        metrics.watch_queue("account", "input".to_string(), || 7);
+        metrics.block_publish_latency.observe(0.003);
+        let program = Pubkey::new_unique();
+        metrics.count_transaction(&[program, program]);
+        let registry = MetricsRegistry::new();
+        registry.register(metrics).unwrap();
+
+        let text = registry.render();
+        assert!(text.contains("windexer_geyser_account_updates_total 3\n"));
+        assert!(text.contains("windexer_geyser_queue_depth{processor=\"account\",queue=\"input\"} 7\n"));
+        assert!(text.contains("windexer_geyser_publish_duration_seconds_bucket{kind=\"block\",le=\"0.0025\"} 0\n"));
+        assert!(text.contains("windexer_geyser_publish_duration_seconds_bucket{kind=\"block\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("windexer_geyser_publish_duration_seconds_count{kind=\"block\"} 1\n"));
+        assert!(text.contains(&format!("windexer_geyser_program_transactions_total{{program=\"{}\"}} 1\n", program)));
+    }
+}
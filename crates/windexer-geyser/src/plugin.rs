@@ -6,13 +6,16 @@
 
 use {
     crate::{
-        config::GeyserPluginConfig,
+        config::{GeyserPluginConfig, PublisherKind},
         processor::{
-            AccountProcessor, BlockProcessor, TransactionProcessor,
+            AccountFilter, AccountProcessor, BlockProcessor, TransactionProcessor, TransactionFilter,
             ProcessorHandle, ProcessorConfig,
         },
-        publisher::{Publisher, NetworkPublisher, PublisherConfig, NullPublisher},
-        metrics::Metrics,
+        publisher::{
+            Publisher, DeadLetterPublisher, InstrumentedPublisher, NetworkPublisher, PublisherConfig, NullPublisher,
+        },
+        logging::PluginLogger,
+        metrics::{self, Metrics},
         ShutdownFlag, PluginVersion,
     },
     agave_geyser_plugin_interface::{
@@ -26,16 +29,87 @@ use {
     solana_sdk::clock::Slot,
     std::{
         fmt::{Debug, Formatter, Result as FmtResult},
+        path::PathBuf,
         sync::{Arc, Mutex, RwLock},
         str::FromStr,
+        time::{Duration, Instant},
     },
     tokio::runtime::Runtime,
     anyhow::{anyhow, Result},
     windexer_network::Node as NetworkNode,
     windexer_common::config::NodeConfig,
+    windexer_common::control::{DeadLetterControl, LogControl},
+    windexer_common::dlq::{DeadLetterQueue, DeadLetters},
+    windexer_common::metrics::MetricsRegistry,
     windexer_common::SerializableKeypair,
+    windexer_common::types::IndexerState,
 };
 
+#[cfg(feature = "store")]
+use crate::publisher::StorePublisher;
+#[cfg(feature = "kafka")]
+use crate::publisher::KafkaPublisher;
+#[cfg(feature = "nats")]
+use crate::publisher::NatsPublisher;
+#[cfg(feature = "grpc")]
+use crate::publisher::GrpcPublisher;
+
+fn block_slot(block_info: &ReplicaBlockInfoVersions) -> Slot {
+    match block_info {
+        ReplicaBlockInfoVersions::V0_0_1(info) => info.slot,
+        ReplicaBlockInfoVersions::V0_0_2(info) => info.slot,
+        ReplicaBlockInfoVersions::V0_0_3(info) => info.slot,
+        ReplicaBlockInfoVersions::V0_0_4(info) => info.slot,
+    }
+}
+
+/// Indexing progress persisted in the configured state file
+#[derive(Debug)]
+struct ProgressTracker {
+    path: PathBuf,
+    state: Mutex<IndexerState>,
+}
+
+impl ProgressTracker {
+    fn load(path: PathBuf) -> Self {
+        let state = match IndexerState::load_from_file(&path) {
+            Ok(Some(state)) => {
+                info!("Resuming after slot {} from {}", state.last_processed_slot, path.display());
+                state
+            }
+            Ok(None) => IndexerState::default(),
+            Err(e) => {
+                warn!("Ignoring indexer state: {}", e);
+                IndexerState::default()
+            }
+        };
+
+        Self {
+            path,
+            state: Mutex::new(state),
+        }
+    }
+
+    fn is_processed(&self, slot: Slot) -> bool {
+        self.state.lock().unwrap().is_processed(slot)
+    }
+
+    fn record_writes(&self, accounts: u64, transactions: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.total_accounts += accounts;
+        state.total_transactions += transactions;
+    }
+
+    fn record_rooted_slot(&self, slot: Slot) {
+        let mut state = self.state.lock().unwrap();
+        if state.record_rooted_slot(slot) {
+            if let Err(e) = state.save_to_file(&self.path) {
+                error!("Failed to persist indexer state: {}", e);
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct PluginState {
     config: GeyserPluginConfig,
@@ -56,6 +130,7 @@ pub struct WindexerGeyserPlugin {
     version: PluginVersion,
     initialized: Arc<std::sync::atomic::AtomicBool>,
     plugin_state: Arc<RwLock<Option<PluginState>>>,
+    progress: Arc<RwLock<Option<ProgressTracker>>>,
 }
 
 impl WindexerGeyserPlugin {
@@ -76,42 +151,33 @@ impl WindexerGeyserPlugin {
             version: PluginVersion::new(),
             initialized: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             plugin_state: Arc::new(RwLock::new(None)),
+            progress: Arc::new(RwLock::new(None)),
         }
     }
 
-    fn initialize(&mut self, config_path: &str) -> Result<(), GeyserPluginError> {
-        let config = match GeyserPluginConfig::load_from_file(config_path) {
-            Ok(config) => config,
-            Err(e) => {
-                return Err(GeyserPluginError::ConfigFileReadError {
-                    msg: format!("Failed to load config: {}", e),
-                });
-            }
-        };
-        
-        config.validate()
-            .map_err(|e| GeyserPluginError::ConfigFileReadError {
-                msg: format!("Invalid config: {}", e),
-            })?;
-        
-        let runtime = Runtime::new()
-            .map_err(|e| GeyserPluginError::Custom(
-                Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Error message: {}", e)))
-            ))?;
-        
-        let _node_pubkey = if let Some(pubkey_str) = config.node_pubkey.clone() {
-            let pubkey = solana_sdk::pubkey::Pubkey::from_str(&pubkey_str)
-                .map_err(|e| {
-                    GeyserPluginError::Custom(Box::new(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        format!("Invalid node pubkey: {}", e)
-                    )))
-                })?;
-            Some(pubkey)
-        } else {
-            None
-        };
-        
+    fn load_progress(&self, config: &GeyserPluginConfig) {
+        *self.progress.write().unwrap() = config.state_file
+            .as_ref()
+            .map(|path| ProgressTracker::load(PathBuf::from(path)));
+    }
+
+    /// Whether `slot` was already published before the last restart
+    fn already_processed(&self, slot: Slot) -> bool {
+        self.progress.read().unwrap().as_ref().is_some_and(|progress| progress.is_processed(slot))
+    }
+
+    fn record_writes(&self, accounts: u64, transactions: u64) {
+        if let Some(progress) = self.progress.read().unwrap().as_ref() {
+            progress.record_writes(accounts, transactions);
+        }
+    }
+
+    /// Creates the embedded network node and a publisher gossiping through it
+    fn network_publisher(
+        &self,
+        config: &GeyserPluginConfig,
+        runtime: &Runtime,
+    ) -> Result<(NetworkPublisher, NetworkNode), GeyserPluginError> {
         let (network_node, _shutdown_sender) = runtime.block_on(async {
             let node_config = NodeConfig {
                 node_id: config.network.node_id.clone(),
@@ -123,6 +189,9 @@ impl WindexerGeyserPlugin {
                 metrics_addr: config.network.metrics_addr,
                 geyser_plugin_config: config.network.geyser_plugin_config.clone(),
                 solana_rpc_url: config.network.solana_rpc_url.clone(),
+                account_programs: Vec::new(),
+                transport: config.network.transport,
+                connections: config.network.connections.clone(),
             };
             
             NetworkNode::create_simple(node_config)
@@ -143,7 +212,16 @@ impl WindexerGeyserPlugin {
             config.batch_size,
             self.metrics.clone(),
             Some(config.network.node_id.clone()),
-        );
+        )
+        .with_gossip(network_node.gossip());
+        let publisher_config = match &config.spill {
+            Some(spill) => publisher_config.with_spill(spill.clone()),
+            None => publisher_config,
+        };
+        let publisher_config = match &config.account_diffs {
+            Some(account_diffs) => publisher_config.with_account_diffs(account_diffs.clone()),
+            None => publisher_config,
+        };
 
         let publisher = runtime.block_on(async {
             NetworkPublisher::new(publisher_config, self.shutdown_flag.clone())
@@ -156,38 +234,239 @@ impl WindexerGeyserPlugin {
                     )))
                 })
         })?;
+
+        Ok((publisher, network_node))
+    }
+
+    /// Opens the configured storage backend to publish into directly
+    #[cfg(feature = "store")]
+    fn store_publisher(
+        config: &GeyserPluginConfig,
+        runtime: &Runtime,
+        registry: &MetricsRegistry,
+        dead_letters: Option<Arc<DeadLetterQueue>>,
+    ) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        let publisher = runtime.block_on(StorePublisher::open(&config.storage, registry, dead_letters))
+            .map_err(|e| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("Failed to open storage: {}", e),
+            ))))?;
+        info!("Publishing directly to {:?} storage", config.storage.storage_type);
+        Ok(Arc::new(publisher))
+    }
+
+    #[cfg(not(feature = "store"))]
+    fn store_publisher(
+        _config: &GeyserPluginConfig,
+        _runtime: &Runtime,
+        _registry: &MetricsRegistry,
+        _dead_letters: Option<Arc<DeadLetterQueue>>,
+    ) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        Err(GeyserPluginError::ConfigFileReadError {
+            msg: "publisher is 'store' but the plugin was built without the store feature".to_string(),
+        })
+    }
+
+    /// Connects a producer to the configured Kafka cluster
+    #[cfg(feature = "kafka")]
+    fn kafka_publisher(&self, config: &GeyserPluginConfig) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        let kafka = config.kafka.clone().ok_or_else(|| GeyserPluginError::ConfigFileReadError {
+            msg: "publisher is 'kafka' but the kafka section is missing".to_string(),
+        })?;
+        let publisher = KafkaPublisher::new(kafka, self.metrics.clone())
+            .map_err(|e| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                e.to_string(),
+            ))))?;
+        Ok(Arc::new(publisher))
+    }
+
+    #[cfg(not(feature = "kafka"))]
+    fn kafka_publisher(&self, _config: &GeyserPluginConfig) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        Err(GeyserPluginError::ConfigFileReadError {
+            msg: "publisher is 'kafka' but the plugin was built without the kafka feature".to_string(),
+        })
+    }
+
+    /// Connects to the configured JetStream server on the plugin runtime,
+    /// which then awaits the acknowledgements
+    #[cfg(feature = "nats")]
+    fn nats_publisher(&self, config: &GeyserPluginConfig, runtime: &Runtime) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        let nats = config.nats.clone().ok_or_else(|| GeyserPluginError::ConfigFileReadError {
+            msg: "publisher is 'nats' but the nats section is missing".to_string(),
+        })?;
+        let publisher = runtime.block_on(NatsPublisher::connect(nats, self.metrics.clone()))
+            .map_err(|e| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                e.to_string(),
+            ))))?;
+        Ok(Arc::new(publisher))
+    }
+
+    #[cfg(not(feature = "nats"))]
+    fn nats_publisher(&self, _config: &GeyserPluginConfig, _runtime: &Runtime) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        Err(GeyserPluginError::ConfigFileReadError {
+            msg: "publisher is 'nats' but the plugin was built without the nats feature".to_string(),
+        })
+    }
+
+    /// Serves the sidecar stream on the plugin runtime
+    #[cfg(feature = "grpc")]
+    fn grpc_publisher(&self, config: &GeyserPluginConfig, runtime: &Runtime) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        let grpc = config.grpc.clone().unwrap_or_default();
+        let publisher = runtime.block_on(GrpcPublisher::serve(grpc, self.metrics.clone()))
+            .map_err(|e| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                e.to_string(),
+            ))))?;
+        Ok(Arc::new(publisher))
+    }
+
+    #[cfg(not(feature = "grpc"))]
+    fn grpc_publisher(&self, _config: &GeyserPluginConfig, _runtime: &Runtime) -> Result<Arc<dyn Publisher>, GeyserPluginError> {
+        Err(GeyserPluginError::ConfigFileReadError {
+            msg: "publisher is 'grpc' but the plugin was built without the grpc feature".to_string(),
+        })
+    }
+
+    fn initialize(&mut self, config_path: &str) -> Result<(), GeyserPluginError> {
+        let config = match GeyserPluginConfig::load_from_file(config_path) {
+            Ok(config) => config,
+            Err(e) => {
+                return Err(GeyserPluginError::ConfigFileReadError {
+                    msg: format!("Failed to load config: {}", e),
+                });
+            }
+        };
+        
+        config.validate()
+            .map_err(|e| GeyserPluginError::ConfigFileReadError {
+                msg: format!("Invalid config: {}", e),
+            })?;
+        
+        if let (Some(directives), Some(logger)) = (&config.log_level, PluginLogger::get()) {
+            logger.set_directives(directives)
+                .map_err(|e| GeyserPluginError::ConfigFileReadError {
+                    msg: format!("Invalid log_level: {}", e),
+                })?;
+        }
+        
+        let runtime = Runtime::new()
+            .map_err(|e| GeyserPluginError::Custom(
+                Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("Error message: {}", e)))
+            ))?;
+        
+        let _node_pubkey = if let Some(pubkey_str) = config.node_pubkey.clone() {
+            let pubkey = solana_sdk::pubkey::Pubkey::from_str(&pubkey_str)
+                .map_err(|e| {
+                    GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("Invalid node pubkey: {}", e)
+                    )))
+                })?;
+            Some(pubkey)
+        } else {
+            None
+        };
+        
+        // The plugin, its node or store, all exported on one endpoint
+        let registry = MetricsRegistry::new();
+        if let Err(e) = registry.register(self.metrics.clone()) {
+            warn!("Failed to register plugin metrics: {}", e);
+        }
+
+        let dead_letters = config.dead_letters.as_ref()
+            .map(|dead_letters| DeadLetterQueue::open(dead_letters).map(Arc::new))
+            .transpose()
+            .map_err(|e| GeyserPluginError::ConfigFileReadError {
+                msg: format!("Failed to open dead letters: {}", e),
+            })?;
+
+        let (publisher, network_node): (Arc<dyn Publisher>, Option<NetworkNode>) = match config.publisher {
+            PublisherKind::Network => {
+                let (publisher, network_node) = self.network_publisher(&config, &runtime)?;
+                (Arc::new(publisher), Some(network_node))
+            }
+            PublisherKind::Store => (Self::store_publisher(&config, &runtime, &registry, dead_letters.clone())?, None),
+            PublisherKind::Kafka => (self.kafka_publisher(&config)?, None),
+            PublisherKind::Nats => (self.nats_publisher(&config, &runtime)?, None),
+            PublisherKind::Grpc => (self.grpc_publisher(&config, &runtime)?, None),
+        };
+        // Failed batches are kept before the instrumented publisher counts the error
+        let (publisher, dead_letter_control): (Arc<dyn Publisher>, Option<Arc<dyn DeadLetterControl>>) = match &dead_letters {
+            Some(queue) => {
+                let publisher = Arc::new(DeadLetterPublisher::new(
+                    publisher,
+                    queue.clone(),
+                    self.metrics.clone(),
+                    Some(config.network.node_id.clone()),
+                ));
+                info!("Keeping failed batches as dead letters, {} kept so far", queue.len());
+                (publisher.clone(), Some(Arc::new(DeadLetters::new(queue.clone(), publisher))))
+            }
+            None => (publisher, None),
+        };
+        let publisher: Arc<dyn Publisher> = Arc::new(InstrumentedPublisher::new(publisher, self.metrics.clone()));
+        if let Some(network_node) = &network_node {
+            if let Err(e) = registry.register(network_node.metrics()) {
+                warn!("Failed to register network node metrics: {}", e);
+            }
+        }
+        
+        if let Some(addr) = config.metrics.bind_addr.filter(|_| config.metrics.enabled) {
+            let _guard = runtime.enter();
+            let log_control = PluginLogger::get()
+                .filter(|_| config.metrics.admin)
+                .map(|logger| logger as &'static dyn LogControl);
+            let dead_letter_control = dead_letter_control.filter(|_| config.metrics.admin);
+            metrics::serve(registry, addr, log_control, dead_letter_control)
+                .map_err(|e| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    e.to_string(),
+                ))))?;
+        }
         
         let processor_config = ProcessorConfig {
             thread_count: config.thread_count,
             batch_size: config.batch_size,
+            overflow: config.overflow.clone(),
             metrics: self.metrics.clone(),
             shutdown_flag: self.shutdown_flag.clone(),
         };
+        let processor_error = |e: anyhow::Error| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            format!("Failed to start processors: {}", e),
+        )));
         
         let account_processor = AccountProcessor::new(
             processor_config.clone(),
-            Arc::new(publisher.clone()),
-            config.accounts_selector.clone(),
-        );
+            publisher.clone(),
+            AccountFilter::from_config(&config),
+            config.startup.clone(),
+            config.account_ordering.clone(),
+        ).map_err(processor_error)?;
         
         let transaction_processor = TransactionProcessor::new(
             processor_config.clone(),
-            Arc::new(publisher.clone()),
-            config.transaction_selector.clone(),
-        );
+            publisher.clone(),
+            TransactionFilter::from_config(&config)
+                .skipping(config.skip_vote_transactions, config.skip_failed_transactions),
+        ).map_err(processor_error)?;
         
         let block_processor = BlockProcessor::new(
             processor_config.clone(),
-            Arc::new(publisher.clone()),
-        );
+            publisher.clone(),
+            config.slot_audit.clone(),
+        ).map_err(processor_error)?;
         
         // Store all components
         *self.runtime.lock().unwrap() = Some(runtime);
-        *self.network_node.lock().unwrap() = Some(network_node);
-        *self.publisher.lock().unwrap() = Arc::new(publisher);
+        *self.network_node.lock().unwrap() = network_node;
+        *self.publisher.lock().unwrap() = publisher;
         *self.account_processor.lock().unwrap() = Some(account_processor);
         *self.transaction_processor.lock().unwrap() = Some(transaction_processor);
         *self.block_processor.lock().unwrap() = Some(block_processor);
+        self.load_progress(&config);
         self.config = config;
         
         let runtime_handle = self.runtime.lock().unwrap();
@@ -210,7 +489,40 @@ impl WindexerGeyserPlugin {
         Ok(())
     }
 
+    /// Publishes what the processors have queued, giving up at the deadline
+    fn drain_processors(&self) {
+        let deadline = Instant::now() + Duration::from_secs(self.config.drain_timeout_secs);
+        let account_processor = self.account_processor.lock().unwrap().take();
+        let transaction_processor = self.transaction_processor.lock().unwrap().take();
+        let block_processor = self.block_processor.lock().unwrap().take();
+        
+        let closed = [
+            account_processor.as_ref().map(|processor| processor.close(deadline)),
+            transaction_processor.as_ref().map(|processor| processor.close(deadline)),
+            block_processor.as_ref().map(|processor| processor.close(deadline)),
+        ];
+        for error in closed.into_iter().flatten().filter_map(Result::err) {
+            warn!("{}", error);
+        }
+        
+        let drained = [
+            account_processor.is_none_or(|processor| processor.finish(deadline)),
+            transaction_processor.is_none_or(|processor| processor.finish(deadline)),
+            block_processor.is_none_or(|processor| processor.finish(deadline)),
+        ];
+        if drained.contains(&false) {
+            warn!(
+                "Processors did not drain within {}s, queued messages are dropped",
+                self.config.drain_timeout_secs
+            );
+        } else {
+            info!("Processors drained");
+        }
+    }
+
     fn cleanup(&mut self) {
+        // Workers publish through the node, so they finish before it stops
+        self.drain_processors();
         self.shutdown_flag.shutdown();
 
         if let Some(runtime) = self.runtime.lock().unwrap().as_ref() {
@@ -223,17 +535,7 @@ impl WindexerGeyserPlugin {
             });
         }
         
-        if let Some(processor) = self.account_processor.lock().unwrap().take() {
-            processor.join();
-        }
-        
-        if let Some(processor) = self.transaction_processor.lock().unwrap().take() {
-            processor.join();
-        }
-        
-        if let Some(processor) = self.block_processor.lock().unwrap().take() {
-            processor.join();
-        }
+        self.metrics.unwatch_queues();
         
         {
             let mut publisher_guard = self.publisher.lock().unwrap();
@@ -280,6 +582,8 @@ impl WindexerGeyserPlugin {
         
         self.debug_plugin_init("STATE", "Setting up plugin state");
         
+        self.load_progress(&config);
+        
         let plugin_state = PluginState {
             config,
             publisher,
@@ -308,11 +612,12 @@ impl GeyserPlugin for WindexerGeyserPlugin {
     }
 
     fn setup_logger(&self, logger: &'static dyn Log, level: LevelFilter) -> std::result::Result<(), GeyserPluginError> {
-        log::set_max_level(level);
-        if let Err(e) = log::set_logger(logger) {
-            return Err(GeyserPluginError::Custom(Box::new(e)));
-        }
-        Ok(())
+        PluginLogger::install(logger, level)
+            .map(|_| ())
+            .map_err(|e| GeyserPluginError::Custom(Box::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                e.to_string(),
+            ))))
     }
 
     fn on_load(&mut self, config_file: &str, is_reload: bool) -> std::result::Result<(), GeyserPluginError> {
@@ -348,11 +653,15 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return Ok(());
         }
         
-        self.metrics.account_updates.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.metrics.account_updates.inc();
+        
+        if !is_startup && self.already_processed(slot) {
+            return Ok(());
+        }
         
         if let Some(processor) = self.account_processor.lock().unwrap().as_ref() {
             if let Err(err) = processor.process_account(account, slot, is_startup) {
-                self.metrics.account_update_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.metrics.account_update_errors.inc();
                 let err_msg = format!("Failed to process account update: {}", err);
                 
                 if self.config.panic_on_error {
@@ -360,6 +669,8 @@ impl GeyserPlugin for WindexerGeyserPlugin {
                 } else {
                     error!("{}", err_msg);
                 }
+            } else {
+                self.record_writes(1, 0);
             }
         }
         
@@ -393,12 +704,12 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return Ok(());
         }
         
-        self.metrics.block_updates.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        self.metrics.block_update_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.metrics.block_updates.inc();
+        self.metrics.block_update_errors.inc();
         
         if let Some(processor) = self.block_processor.lock().unwrap().as_ref() {
             if let Err(err) = processor.update_slot_status(slot, parent, status.clone()) {
-                self.metrics.block_update_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.metrics.block_update_errors.inc();
                 let err_msg = format!("Failed to process slot status update: {}", err);
                 
                 if self.config.panic_on_error {
@@ -406,6 +717,10 @@ impl GeyserPlugin for WindexerGeyserPlugin {
                 } else {
                     error!("{}", err_msg);
                 }
+            } else if let SlotStatus::Rooted = status {
+                if let Some(progress) = self.progress.read().unwrap().as_ref() {
+                    progress.record_rooted_slot(slot);
+                }
             }
         }
         
@@ -417,16 +732,21 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return Ok(());
         }
         
-        self.metrics.transaction_updates.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.metrics.transaction_updates.inc();
+        
+        if self.already_processed(slot) {
+            return Ok(());
+        }
         
         if let Some(processor) = self.transaction_processor.lock().unwrap().as_ref() {
             if let Err(err) = processor.process_transaction(transaction, slot) {
-                self.metrics.transaction_update_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.metrics.transaction_update_errors.inc();
                 let _err_msg = format!("Failed to process transaction: {}", err);
                 
                 let boxed_error = Box::new(std::io::Error::new(std::io::ErrorKind::Other, format!("{}", err)));
                 return Err(GeyserPluginError::Custom(boxed_error));
             }
+            self.record_writes(0, 1);
         }
         
         Ok(())
@@ -437,12 +757,16 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return Ok(());
         }
         
-        self.metrics.block_updates.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        self.metrics.block_update_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.metrics.block_updates.inc();
+        self.metrics.block_update_errors.inc();
+        
+        if self.already_processed(block_slot(&block_info)) {
+            return Ok(());
+        }
         
         if let Some(processor) = self.block_processor.lock().unwrap().as_ref() {
             if let Err(err) = processor.process_block_metadata(block_info) {
-                self.metrics.block_update_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.metrics.block_update_errors.inc();
                 let err_msg = format!("Failed to process block metadata: {}", err);
                 
                 if self.config.panic_on_error {
@@ -461,12 +785,12 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return Ok(());
         }
         
-        self.metrics.entry_updates.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        self.metrics.entry_updates_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.metrics.entry_updates.inc();
+        self.metrics.entry_updates_errors.inc();
         
         if let Some(processor) = self.block_processor.lock().unwrap().as_ref() {
             if let Err(err) = processor.process_entry(entry_info) {
-                self.metrics.entry_updates_errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.metrics.entry_updates_errors.inc();
                 let err_msg = format!("Failed to process entry: {}", err);
                 
                 if self.config.panic_on_error {
@@ -485,11 +809,7 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return false;
         }
         
-        if let Some(_selector) = &self.config.accounts_selector {
-            true
-        } else {
-            false
-        }
+        self.config.accounts_selector.is_some() || self.config.accounts_filter.is_some()
     }
 
     fn transaction_notifications_enabled(&self) -> bool {
@@ -497,11 +817,7 @@ impl GeyserPlugin for WindexerGeyserPlugin {
             return false;
         }
         
-        if let Some(_selector) = &self.config.transaction_selector {
-            true
-        } else {
-            false
-        }
+        self.config.transaction_selector.is_some() || self.config.transactions_filter.is_some()
     }
 
     fn entry_notifications_enabled(&self) -> bool {
@@ -6,9 +6,14 @@
 
 use {
     crate::{
-        config::AccountsSelector,
+        config::{AccountOrderingConfig, StartupConfig},
         metrics::Metrics,
-        processor::{ProcessorConfig, AccountHandler, ProcessorHandle},
+        processor::{
+            dispatch::{Channels, Dispatch},
+            filter::AccountFilter,
+            versions::AccountVersions,
+            ProcessorConfig, AccountHandler, Drain, ProcessorHandle,
+        },
         publisher::Publisher,
         ShutdownFlag,
     },
@@ -21,22 +26,24 @@ use {
         GeyserPluginError, ReplicaAccountInfo, ReplicaAccountInfoV2, ReplicaAccountInfoV3
     },
     anyhow::{anyhow, Result},
-    crossbeam_channel::{Sender, Receiver, bounded},
-    log::{debug, error, info, trace, warn},
+    crossbeam_channel::{Sender, Receiver},
+    log::{debug, error, info, trace},
+    serde::{Deserialize, Serialize},
     std::{
-        collections::HashSet,
         sync::{
             Arc,
             atomic::{AtomicBool, Ordering},
-            Mutex, RwLock,
         },
         thread::{self, JoinHandle},
-        time::Duration,
-        str::FromStr,
+        time::{Duration, Instant},
     },
     windexer_common::types::account::AccountData,
 };
 
+/// Snapshot accounts between progress log lines
+const STARTUP_PROGRESS_INTERVAL: u64 = 1_000_000;
+
+#[derive(Serialize, Deserialize)]
 enum AccountMessage {
     ProcessAccount {
         pubkey: Pubkey,
@@ -55,14 +62,34 @@ enum AccountMessage {
     Shutdown,
 }
 
+impl Dispatch for AccountMessage {
+    fn must_deliver(&self) -> bool {
+        // The snapshot is only streamed once, so startup accounts wait for
+        // the worker too
+        matches!(
+            self,
+            AccountMessage::ProcessAccount { is_startup: true, .. }
+                | AccountMessage::EndOfStartup
+                | AccountMessage::Shutdown
+        )
+    }
+
+    fn is_shutdown(&self) -> bool {
+        matches!(self, AccountMessage::Shutdown)
+    }
+}
+
 pub struct AccountProcessor {
     config: ProcessorConfig,
     publisher: Arc<dyn Publisher>,
-    selector: Option<AccountsSelector>,
-    included_accounts: Arc<RwLock<Option<HashSet<Pubkey>>>>,
-    included_owners: Arc<RwLock<Option<HashSet<Pubkey>>>>,
-    include_all_accounts: Arc<AtomicBool>,
-    sender: Sender<AccountMessage>,
+    filter: Arc<AccountFilter>,
+    startup: StartupConfig,
+    ordering: AccountOrderingConfig,
+    /// One queue per worker; an account always goes to the same one, so its
+    /// updates are published in the order they arrive
+    shards: Vec<Sender<AccountMessage>>,
+    /// Direct line to every worker, for notifications all of them need
+    worker_senders: Vec<Sender<AccountMessage>>,
     receivers: Vec<Receiver<AccountMessage>>,
     startup_complete: Arc<AtomicBool>,
 }
@@ -71,106 +98,31 @@ impl AccountProcessor {
     pub fn new(
         config: ProcessorConfig,
         publisher: Arc<dyn Publisher>,
-        selector: Option<AccountsSelector>,
-    ) -> ProcessorHandle<Self> {
-        let (included_accounts, included_owners, include_all_accounts) = 
-            Self::parse_selectors(&selector);
+        filter: AccountFilter,
+        startup: StartupConfig,
+        ordering: AccountOrderingConfig,
+    ) -> Result<ProcessorHandle<Self>> {
+        config.metrics.startup_accounts_expected
+            .set(startup.expected_accounts.unwrap_or(0) as i64);
         
-        let (sender, receivers) = Self::create_channels(config.thread_count);
+        let Channels { queues: shards, worker_senders, receivers } =
+            Channels::sharded("account", &config, |metrics| &metrics.account_messages_dropped)?;
         
         let processor = Self {
             config: config.clone(),
             publisher,
-            selector,
-            included_accounts: Arc::new(RwLock::new(included_accounts)),
-            included_owners: Arc::new(RwLock::new(included_owners)),
-            include_all_accounts: Arc::new(AtomicBool::new(include_all_accounts)),
-            sender,
+            filter: Arc::new(filter),
+            startup,
+            ordering,
+            shards,
+            worker_senders,
             receivers,
             startup_complete: Arc::new(AtomicBool::new(false)),
         };
         
         let workers = processor.start_workers();
         
-        ProcessorHandle::new(processor, workers)
-    }
-    
-    fn parse_selectors(
-        selector: &Option<AccountsSelector>,
-    ) -> (Option<HashSet<Pubkey>>, Option<HashSet<Pubkey>>, bool) {
-        let mut included_accounts = None;
-        let mut included_owners = None;
-        let mut include_all_accounts = false;
-        
-        if let Some(selector) = selector {
-            let accounts = &selector.accounts;
-            if accounts.contains(&"*".to_string()) {
-                include_all_accounts = true;
-            } else {
-                let mut account_set = HashSet::new();
-                for account in accounts {
-                    if let Ok(pubkey) = Pubkey::from_str(account) {
-                        account_set.insert(pubkey);
-                    } else {
-                        warn!("Invalid account pubkey in selector: {}", account);
-                    }
-                }
-                included_accounts = Some(account_set);
-            }
-            
-            if let Some(owners) = &selector.owners {
-                let mut owner_set = HashSet::new();
-                for owner in owners {
-                    if let Ok(pubkey) = Pubkey::from_str(owner) {
-                        owner_set.insert(pubkey);
-                    } else {
-                        warn!("Invalid owner pubkey in selector: {}", owner);
-                    }
-                }
-                if !owner_set.is_empty() {
-                    included_owners = Some(owner_set);
-                }
-            }
-        } else {
-            included_accounts = Some(HashSet::new());
-        }
-        
-        (included_accounts, included_owners, include_all_accounts)
-    }
-    
-    /// Create channels for workers
-    fn create_channels(
-        thread_count: usize,
-    ) -> (Sender<AccountMessage>, Vec<Receiver<AccountMessage>>) {
-        let (sender, main_receiver) = bounded(10_000);
-        let mut receivers = Vec::with_capacity(thread_count);
-        
-        for _ in 0..thread_count {
-            let (worker_sender, worker_receiver) = bounded(1_000);
-            
-            let main_receiver_clone = main_receiver.clone();
-            thread::spawn(move || {
-                for message in main_receiver_clone.iter() {
-                    match &message {
-                        AccountMessage::Shutdown => {
-                            let _ = worker_sender.send(message);
-                            break;
-                        }
-                        _ => {
-                            if worker_sender.try_send(message).is_err() {
-                                // If the channel is full, just drop the message
-                                // The worker is probably busy and we don't want to block
-                                // the main thread
-                            }
-                        }
-                    }
-                }
-            });
-            
-            receivers.push(worker_receiver);
-        }
-        
-        (sender, receivers)
+        Ok(ProcessorHandle::new(processor, workers))
     }
     
     fn start_workers(&self) -> Vec<JoinHandle<()>> {
@@ -181,10 +133,10 @@ impl AccountProcessor {
             let publisher = self.publisher.clone();
             let metrics = self.config.metrics.clone();
             let shutdown_flag = self.config.shutdown_flag.clone();
-            let included_accounts = self.included_accounts.clone();
-            let included_owners = self.included_owners.clone();
-            let include_all_accounts = self.include_all_accounts.clone();
+            let filter = self.filter.clone();
+            let startup = self.startup.clone();
             let startup_complete = self.startup_complete.clone();
+            let ordering = self.ordering.clone();
             
             let worker = thread::Builder::new()
                 .name(format!("account-worker-{}", i))
@@ -194,10 +146,10 @@ impl AccountProcessor {
                         publisher,
                         metrics,
                         shutdown_flag,
-                        included_accounts,
-                        included_owners,
-                        include_all_accounts,
+                        filter,
+                        startup,
                         startup_complete,
+                        ordering,
                     );
                 })
                 .unwrap();
@@ -208,17 +160,32 @@ impl AccountProcessor {
         workers
     }
     
+    fn publish_batch(publisher: &dyn Publisher, metrics: &Metrics, batch: &[AccountData]) {
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = publisher.publish_accounts(batch) {
+            error!("Failed to publish accounts: {}", e);
+            metrics.account_publish_errors.inc();
+        } else {
+            metrics.account_batches_published.inc_by(batch.len() as u64);
+        }
+    }
+    
     fn worker_thread(
         receiver: Receiver<AccountMessage>,
         publisher: Arc<dyn Publisher>,
         metrics: Arc<Metrics>,
         shutdown_flag: Arc<ShutdownFlag>,
-        included_accounts: Arc<RwLock<Option<HashSet<Pubkey>>>>,
-        included_owners: Arc<RwLock<Option<HashSet<Pubkey>>>>,
-        include_all_accounts: Arc<AtomicBool>,
+        filter: Arc<AccountFilter>,
+        startup: StartupConfig,
         startup_complete: Arc<AtomicBool>,
+        ordering: AccountOrderingConfig,
     ) {
         let mut batch = Vec::new();
+        let mut versions = AccountVersions::new(ordering.window_slots);
+        // Snapshot accounts, batched separately and in larger batches
+        let mut startup_batch = Vec::new();
         let mut last_publish = std::time::Instant::now();
         
         for message in receiver.iter() {
@@ -228,51 +195,54 @@ impl AccountProcessor {
             
             match message {
                 AccountMessage::ProcessAccount { pubkey, lamports, owner, executable, rent_epoch, data, write_version, slot, is_startup } => {
-                    if !Self::should_process_account(
-                        &pubkey, 
-                        &included_accounts, 
-                        &included_owners,
-                        &include_all_accounts,
-                    ) {
+                    if !filter.matches(&pubkey, &owner, &data, slot) {
                         continue;
                     }
+                    metrics.count_account(&owner);
+                    
+                    // Snapshot accounts carry no ordering worth checking
+                    if !is_startup && !versions.observe(&pubkey, slot, write_version) {
+                        metrics.account_updates_out_of_order.inc();
+                        if ordering.drop_stale {
+                            trace!("Dropping stale update of {} at slot {} write version {}", pubkey, slot, write_version);
+                            continue;
+                        }
+                        debug!("Publishing out of order update of {} at slot {}", pubkey, slot);
+                    }
                     
                     match Self::convert_account(pubkey, lamports, owner, executable, rent_epoch, data, write_version, slot, is_startup) {
+                        Ok(account_data) if is_startup && !startup_complete.load(Ordering::Relaxed) => {
+                            startup_batch.push(account_data);
+                            
+                            if !startup.defer_publish && startup_batch.len() >= startup.batch_size {
+                                Self::publish_batch(publisher.as_ref(), &metrics, &startup_batch);
+                                startup_batch.clear();
+                            }
+                        }
                         Ok(account_data) => {
                             batch.push(account_data);
                             
                             if batch.len() >= 1000 || last_publish.elapsed() > Duration::from_millis(100) {
-                                if !batch.is_empty() {
-                                    if let Err(e) = publisher.publish_accounts(&batch) {
-                                        error!("Failed to publish accounts: {}", e);
-                                        metrics.account_publish_errors.fetch_add(1, Ordering::Relaxed);
-                                    } else {
-                                        metrics.account_batches_published.fetch_add(batch.len() as u64, Ordering::Relaxed);
-                                    }
-                                    batch.clear();
-                                    last_publish = std::time::Instant::now();
-                                }
+                                Self::publish_batch(publisher.as_ref(), &metrics, &batch);
+                                batch.clear();
+                                last_publish = std::time::Instant::now();
                             }
                         }
                         Err(e) => {
                             error!("Failed to convert account: {}", e);
-                            metrics.account_update_errors.fetch_add(1, Ordering::Relaxed);
+                            metrics.account_update_errors.inc();
                         }
                     }
                 }
                 AccountMessage::EndOfStartup => {
-                    info!("End of startup notification received by account worker");
-                    startup_complete.store(true, Ordering::SeqCst);
+                    debug!("End of startup notification received by account worker");
                     
-                    if !batch.is_empty() {
-                        if let Err(e) = publisher.publish_accounts(&batch) {
-                            error!("Failed to publish accounts: {}", e);
-                            metrics.account_publish_errors.fetch_add(1, Ordering::Relaxed);
-                        } else {
-                            metrics.account_batches_published.fetch_add(batch.len() as u64, Ordering::Relaxed);
-                        }
-                        batch.clear();
+                    for chunk in startup_batch.chunks(startup.batch_size.max(1)) {
+                        Self::publish_batch(publisher.as_ref(), &metrics, chunk);
                     }
+                    startup_batch = Vec::new();
+                    Self::publish_batch(publisher.as_ref(), &metrics, &batch);
+                    batch.clear();
                 }
                 AccountMessage::Shutdown => {
                     debug!("Account worker received shutdown message");
@@ -281,41 +251,33 @@ impl AccountProcessor {
             }
         }
         
-        if !batch.is_empty() {
-            if let Err(e) = publisher.publish_accounts(&batch) {
-                error!("Failed to publish accounts: {}", e);
-                metrics.account_publish_errors.fetch_add(1, Ordering::Relaxed);
-            } else {
-                metrics.account_batches_published.fetch_add(batch.len() as u64, Ordering::Relaxed);
-            }
-        }
+        Self::publish_batch(publisher.as_ref(), &metrics, &startup_batch);
+        Self::publish_batch(publisher.as_ref(), &metrics, &batch);
         
         debug!("Account worker thread exiting");
     }
     
-    fn should_process_account(
-        pubkey: &Pubkey,
-        included_accounts: &Arc<RwLock<Option<HashSet<Pubkey>>>>,
-        included_owners: &Arc<RwLock<Option<HashSet<Pubkey>>>>,
-        include_all_accounts: &Arc<AtomicBool>,
-    ) -> bool {
-        if include_all_accounts.load(Ordering::Relaxed) {
-            return true;
-        }
-        
-        if let Some(included) = included_accounts.read().unwrap().as_ref() {
-            if included.contains(pubkey) {
-                return true;
-            }
+    fn shard(pubkey: &Pubkey, shards: usize) -> usize {
+        let bytes = pubkey.to_bytes();
+        u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize % shards
+    }
+    
+    /// Counts a snapshot account and logs progress towards the expected size
+    fn track_startup_progress(&self) {
+        self.config.metrics.startup_accounts_seen.inc();
+        let seen = self.config.metrics.startup_accounts_seen.get();
+        if seen % STARTUP_PROGRESS_INTERVAL != 0 {
+            return;
         }
-        
-        if let Some(included) = included_owners.read().unwrap().as_ref() {
-            if included.contains(pubkey) {
-                return true;
-            }
+        match self.startup.expected_accounts {
+            Some(expected) if expected > 0 => info!(
+                "Snapshot loading: {} of ~{} accounts ({:.1}%)",
+                seen,
+                expected,
+                seen as f64 * 100.0 / expected as f64
+            ),
+            _ => info!("Snapshot loading: {} accounts", seen),
         }
-        
-        false
     }
     
     fn convert_account(
@@ -351,6 +313,14 @@ impl AccountHandler for AccountProcessor {
         slot: Slot,
         is_startup: bool,
     ) -> Result<()> {
+        if is_startup {
+            if self.startup.skip {
+                self.config.metrics.startup_accounts_skipped.inc();
+                return Ok(());
+            }
+            self.track_startup_progress();
+        }
+        
         // Extract data from the account reference
         let (pubkey, lamports, owner, executable, rent_epoch, data, write_version) = 
             match &account {
@@ -393,7 +363,7 @@ impl AccountHandler for AccountProcessor {
                 },
             };
         
-        self.sender.send(AccountMessage::ProcessAccount {
+        self.shards[Self::shard(&pubkey, self.shards.len())].send(AccountMessage::ProcessAccount {
             pubkey,
             lamports,
             owner,
@@ -409,7 +379,101 @@ impl AccountHandler for AccountProcessor {
     }
     
     fn notify_end_of_startup(&self) -> Result<()> {
-        self.sender.send(AccountMessage::EndOfStartup)
-            .map_err(|e| anyhow!("Failed to send end of startup notification: {}", e))
+        let seen = self.config.metrics.startup_accounts_seen.get();
+        let skipped = self.config.metrics.startup_accounts_skipped.get();
+        info!("Snapshot loaded: {} accounts, {} skipped", seen, skipped);
+        
+        // Snapshot accounts still queued are published as they arrive
+        self.startup_complete.store(true, Ordering::SeqCst);
+        for sender in &self.worker_senders {
+            sender.send(AccountMessage::EndOfStartup)
+                .map_err(|e| anyhow!("Failed to send end of startup notification: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drain for AccountProcessor {
+    fn close(&self, deadline: Instant) -> Result<()> {
+        for shard in &self.shards {
+            shard.send_deadline(AccountMessage::Shutdown, deadline)
+                .map_err(|e| anyhow!("Failed to queue account worker shutdown: {}", e))?;
+        }
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::config::OverflowConfig,
+        std::sync::Mutex,
+        windexer_common::{
+            filter::Filter,
+            types::{block::{BlockData, EntryData}, transaction::TransactionData},
+        },
+    };
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<AccountData>>);
+
+    impl Publisher for Recorder {
+        fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+            self.0.lock().unwrap().extend_from_slice(accounts);
+            Ok(())
+        }
+
+        fn publish_transactions(&self, _transactions: &[TransactionData]) -> Result<()> {
+            Ok(())
+        }
+
+        fn publish_block(&self, _block: BlockData) -> Result<()> {
+            Ok(())
+        }
+
+        fn publish_entries(&self, _entries: &[EntryData]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn closing_publishes_every_queued_update() {
+        let config = ProcessorConfig {
+            thread_count: 2,
+            batch_size: 100,
+            overflow: OverflowConfig::default(),
+            metrics: Arc::new(Metrics::new()),
+            shutdown_flag: Arc::new(ShutdownFlag::new()),
+        };
+        let recorder = Arc::new(Recorder::default());
+        let handle = AccountProcessor::new(
+            config,
+            recorder.clone(),
+            AccountFilter::new(Some(Filter::all())),
+            StartupConfig::default(),
+            AccountOrderingConfig::default(),
+        ).unwrap();
+
+        let owner = Pubkey::new_unique();
+        for write_version in 0..500 {
+            let pubkey = Pubkey::new_unique();
+            let info = ReplicaAccountInfoV3 {
+                pubkey: pubkey.as_ref(),
+                lamports: 1,
+                owner: owner.as_ref(),
+                executable: false,
+                rent_epoch: 0,
+                data: &[],
+                write_version,
+                txn: None,
+            };
+            handle.process_account(ReplicaAccountInfoVersions::V0_0_3(&info), 1, false).unwrap();
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        handle.close(deadline).unwrap();
+        assert!(handle.finish(deadline));
+        assert_eq!(recorder.0.lock().unwrap().len(), 500);
+    }
+}
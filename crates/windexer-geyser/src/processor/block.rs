@@ -3,11 +3,14 @@
 //! Block data processor
 //!
 //! This module contains the implementation of the block data processor.
+//! Slot status updates are audited as they arrive, before they are queued,
+//! so gaps in the rooted chain are detected in the validator's order.
 
 use {
     crate::{
+        config::SlotAuditConfig,
         metrics::Metrics,
-        processor::{ProcessorConfig, BlockHandler, ProcessorHandle},
+        processor::{dispatch::{Channels, Dispatch}, ProcessorConfig, BlockHandler, Drain, ProcessorHandle},
         publisher::Publisher,
         ShutdownFlag,
     },
@@ -23,28 +26,37 @@ use {
     },
     solana_transaction_status::Reward,
     anyhow::{anyhow, Result},
-    crossbeam_channel::{Sender, Receiver, bounded},
+    crossbeam_channel::{Sender, Receiver},
     log::{debug, error, info, trace, warn},
+    serde::{Deserialize, Serialize},
     std::{
         collections::HashMap,
+        fs::OpenOptions,
+        io::Write,
         sync::{
             Arc,
-            atomic::{AtomicBool, Ordering},
+            atomic::AtomicBool,
             Mutex, RwLock,
         },
         thread::{self, JoinHandle},
-        time::Duration,
+        time::{Duration, Instant},
     },
-    windexer_common::types::{
-        block::BlockData,
-        block::EntryData,
+    windexer_common::{
+        types::{
+            block::BlockData,
+            block::EntryData,
+            block::slot_status_serde,
+        },
+        utils::{SlotAudit, SlotGap},
     },
 };
 
+#[derive(Serialize, Deserialize)]
 enum BlockMessage {
     UpdateSlotStatus {
         slot: Slot,
         parent: Option<Slot>,
+        #[serde(with = "slot_status_serde")]
         status: SlotStatus,
     },
     
@@ -71,20 +83,35 @@ enum BlockMessage {
     Shutdown,
 }
 
+impl Dispatch for BlockMessage {
+    fn must_deliver(&self) -> bool {
+        self.is_shutdown()
+    }
+
+    fn is_shutdown(&self) -> bool {
+        matches!(self, BlockMessage::Shutdown)
+    }
+}
+
 pub struct BlockProcessor {
     config: ProcessorConfig,
     publisher: Arc<dyn Publisher>,
     sender: Sender<BlockMessage>,    
     receivers: Vec<Receiver<BlockMessage>>,
     tracked_slots: Arc<RwLock<HashMap<Slot, BlockData>>>,
+    audit: Mutex<SlotAudit>,
+    gap_log: Option<String>,
 }
 
 impl BlockProcessor {
     pub fn new(
         config: ProcessorConfig,
         publisher: Arc<dyn Publisher>,
-    ) -> ProcessorHandle<Self> {
-        let (sender, receivers) = Self::create_channels(config.thread_count);
+        slot_audit: SlotAuditConfig,
+    ) -> Result<ProcessorHandle<Self>> {
+        let Channels { mut queues, receivers, .. } =
+            Channels::new("block", &config, |metrics| &metrics.block_messages_dropped)?;
+        let sender = queues.remove(0);
         
         let processor = Self {
             config: config.clone(),
@@ -92,45 +119,13 @@ impl BlockProcessor {
             sender,
             receivers,
             tracked_slots: Arc::new(RwLock::new(HashMap::new())),
+            audit: Mutex::new(SlotAudit::new(slot_audit.history_slots)),
+            gap_log: slot_audit.gap_log,
         };
         
         let workers = processor.start_workers();
         
-        ProcessorHandle::new(processor, workers)
-    }
-    
-    fn create_channels(
-        thread_count: usize,
-    ) -> (Sender<BlockMessage>, Vec<Receiver<BlockMessage>>) {
-        let (sender, main_receiver) = bounded(10_000);
-        let mut receivers = Vec::with_capacity(thread_count);
-        
-        for _ in 0..thread_count {
-            let (worker_sender, worker_receiver) = bounded(1_000);
-            
-            let main_receiver_clone = main_receiver.clone();
-            thread::spawn(move || {
-                for message in main_receiver_clone.iter() {
-                    match &message {
-                        BlockMessage::Shutdown => {
-                            let _ = worker_sender.send(message);
-                            break;
-                        }
-                        _ => {
-                            if worker_sender.try_send(message).is_err() {
-                                // If the channel is full, just drop the message
-                                // The worker is probably busy and we don't want to block
-                                // the main thread
-                            }
-                        }
-                    }
-                }
-            });
-            
-            receivers.push(worker_receiver);
-        }
-        
-        (sender, receivers)
+        Ok(ProcessorHandle::new(processor, workers))
     }
     
     fn start_workers(&self) -> Vec<JoinHandle<()>> {
@@ -203,9 +198,9 @@ impl BlockProcessor {
                     if matches!(status, SlotStatus::Rooted) {
                         if let Err(e) = publisher.publish_block(block_data.clone()) {
                             error!("Failed to publish block: {}", e);
-                            metrics.block_publish_errors.fetch_add(1, Ordering::Relaxed);
+                            metrics.block_publish_errors.inc();
                         } else {
-                            metrics.blocks_published.fetch_add(1, Ordering::Relaxed);
+                            metrics.blocks_published.inc();
                         }
                     }
                 }
@@ -262,9 +257,9 @@ impl BlockProcessor {
                     if matches!(existing.status, SlotStatus::Rooted) {
                         if let Err(e) = publisher.publish_block(existing.clone()) {
                             error!("Failed to publish block: {}", e);
-                            metrics.block_publish_errors.fetch_add(1, Ordering::Relaxed);
+                            metrics.block_publish_errors.inc();
                         } else {
-                            metrics.blocks_published.fetch_add(1, Ordering::Relaxed);
+                            metrics.blocks_published.inc();
                         }
                     }
                 }
@@ -302,9 +297,9 @@ impl BlockProcessor {
                         if !entry_batch.is_empty() {
                             if let Err(e) = publisher.publish_entries(&entry_batch) {
                                 error!("Failed to publish entries: {}", e);
-                                metrics.entry_publish_errors.fetch_add(1, Ordering::Relaxed);
+                                metrics.entry_publish_errors.inc();
                             } else {
-                                metrics.entry_batches_published.fetch_add(entry_batch.len() as u64, Ordering::Relaxed);
+                                metrics.entry_batches_published.inc_by(entry_batch.len() as u64);
                             }
                             entry_batch.clear();
                             last_publish = std::time::Instant::now();
@@ -326,15 +321,32 @@ impl BlockProcessor {
         if !entry_batch.is_empty() {
             if let Err(e) = publisher.publish_entries(&entry_batch) {
                 error!("Failed to publish entries: {}", e);
-                metrics.entry_publish_errors.fetch_add(1, Ordering::Relaxed);
+                metrics.entry_publish_errors.inc();
             } else {
-                metrics.entry_batches_published.fetch_add(entry_batch.len() as u64, Ordering::Relaxed);
+                metrics.entry_batches_published.inc_by(entry_batch.len() as u64);
             }
         }
         
         debug!("Block worker thread exiting");
     }
     
+    fn report_gap(&self, gap: SlotGap) {
+        warn!("Rooted slots {}..={} were never reported by the validator", gap.first_slot, gap.last_slot);
+        self.config.metrics.missed_slots.inc_by(gap.slot_count());
+        
+        let Some(path) = &self.gap_log else {
+            return;
+        };
+        let appended = serde_json::to_string(&gap).map_err(anyhow::Error::from).and_then(|line| {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", line)?;
+            Ok(())
+        });
+        if let Err(e) = appended {
+            error!("Failed to record slot gap in {}: {}", path, e);
+        }
+    }
+    
     fn cleanup_old_slots(tracked_slots: &Arc<RwLock<HashMap<Slot, BlockData>>>) {
         let mut slots_to_remove = Vec::new();
         let _now = std::time::Instant::now();
@@ -364,6 +376,11 @@ impl BlockHandler for BlockProcessor {
         parent: Option<Slot>,
         status: SlotStatus,
     ) -> Result<()> {
+        let gap = self.audit.lock().unwrap().observe(slot, parent, &status);
+        if let Some(gap) = gap {
+            self.report_gap(gap);
+        }
+        
         self.sender.send(BlockMessage::UpdateSlotStatus {
             slot,
             parent,
@@ -478,4 +495,15 @@ impl BlockHandler for BlockProcessor {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl Drain for BlockProcessor {
+    fn close(&self, deadline: Instant) -> Result<()> {
+        // Every dispatcher takes one from the shared queue and stops
+        for _ in 0..self.receivers.len() {
+            self.sender.send_deadline(BlockMessage::Shutdown, deadline)
+                .map_err(|e| anyhow!("Failed to queue block worker shutdown: {}", e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,408 @@
+// crates/windexer-geyser/src/processor/dispatch.rs
+
+//! Dispatch from a processor's queue to its workers
+//!
+//! Every processor queues incoming messages on one channel, or on one per
+//! worker when messages must stay in order per key, and a dispatcher thread
+//! per worker moves them onto that worker's bounded channel. When the
+//! worker's channel is full the configured [`OverflowPolicy`] decides what
+//! happens: wait, drop a message, or spill to disk until the worker catches
+//! up. Lost messages are counted per processor, and a dispatcher whose
+//! worker falls behind logs a slow consumer warning at most every
+//! [`WARN_INTERVAL`].
+
+use {
+    crate::{
+        config::{DropPolicy, OverflowPolicy, SpillConfig},
+        metrics::Metrics,
+        processor::ProcessorConfig,
+        publisher::SpillQueue,
+    },
+    anyhow::{anyhow, Result},
+    crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError},
+    log::warn,
+    serde::{de::DeserializeOwned, Serialize},
+    std::{
+        collections::VecDeque,
+        path::Path,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    },
+    windexer_common::metrics::IntCounter,
+};
+
+const QUEUE_CAPACITY: usize = 10_000;
+const WORKER_CAPACITY: usize = 1_000;
+/// Messages written to disk together
+const SPILL_CHUNK: usize = 1_000;
+const SPILL_TAG: &str = "overflow";
+/// How often a dispatcher with spilled messages checks for room while idle
+const SPILL_POLL: Duration = Duration::from_millis(50);
+const WARN_INTERVAL: Duration = Duration::from_secs(10);
+
+pub trait Dispatch: Serialize + DeserializeOwned + Send + 'static {
+    /// Whether the message reaches its worker whatever the policy, like
+    /// shutdown and other control messages
+    fn must_deliver(&self) -> bool;
+
+    fn is_shutdown(&self) -> bool;
+}
+
+pub struct Channels<M> {
+    /// The queue every dispatcher reads from, or one queue per dispatcher
+    /// when sharded
+    pub queues: Vec<Sender<M>>,
+    /// Direct line to every worker, for messages all of them need
+    pub worker_senders: Vec<Sender<M>>,
+    pub receivers: Vec<Receiver<M>>,
+}
+
+impl<M: Dispatch> Channels<M> {
+    /// `kind` names the processor in logs and spill directories, and
+    /// `dropped` picks its counter of lost messages
+    pub fn new(kind: &'static str, config: &ProcessorConfig, dropped: fn(&Metrics) -> &IntCounter) -> Result<Self> {
+        Self::create(kind, config, dropped, false)
+    }
+
+    /// Gives every dispatcher its own queue, so messages sent to the same
+    /// queue reach the same worker in order
+    pub fn sharded(kind: &'static str, config: &ProcessorConfig, dropped: fn(&Metrics) -> &IntCounter) -> Result<Self> {
+        Self::create(kind, config, dropped, true)
+    }
+
+    fn create(kind: &'static str, config: &ProcessorConfig, dropped: fn(&Metrics) -> &IntCounter, sharded: bool) -> Result<Self> {
+        if config.thread_count == 0 {
+            return Err(anyhow!("thread_count must be at least 1"));
+        }
+        let mut queues = Vec::new();
+        let mut worker_senders = Vec::with_capacity(config.thread_count);
+        let mut receivers = Vec::with_capacity(config.thread_count);
+        let shared = (!sharded).then(|| bounded(QUEUE_CAPACITY));
+        if let Some((sender, receiver)) = &shared {
+            queues.push(sender.clone());
+            let receiver = receiver.clone();
+            config.metrics.watch_queue(kind, "input".to_string(), move || receiver.len());
+        }
+
+        for i in 0..config.thread_count {
+            let main_receiver = match &shared {
+                Some((_, receiver)) => receiver.clone(),
+                None => {
+                    let (sender, receiver) = bounded((QUEUE_CAPACITY / config.thread_count).max(WORKER_CAPACITY));
+                    queues.push(sender);
+                    let depth = receiver.clone();
+                    config.metrics.watch_queue(kind, format!("input-{}", i), move || depth.len());
+                    receiver
+                }
+            };
+            let (worker_sender, worker_receiver) = bounded(WORKER_CAPACITY);
+            let depth = worker_receiver.clone();
+            config.metrics.watch_queue(kind, format!("worker-{}", i), move || depth.len());
+            let spill = match config.overflow.policy {
+                OverflowPolicy::SpillToDisk => {
+                    let directory = config.overflow.spill_directory.as_ref()
+                        .ok_or_else(|| anyhow!("overflow policy spill_to_disk needs a spill_directory"))?;
+                    Some(Spill::open(&Path::new(directory).join(format!("{}-{}", kind, i)), config.overflow.spill_max_size_mb)?)
+                }
+                _ => None,
+            };
+
+            let dispatcher = Dispatcher {
+                name: format!("{} worker {}", kind, i),
+                policy: config.overflow.policy,
+                main: main_receiver,
+                worker: worker_sender.clone(),
+                worker_receiver: worker_receiver.clone(),
+                spill,
+                metrics: config.metrics.clone(),
+                dropped,
+                lost_since_warning: 0,
+                last_warning: None,
+            };
+            thread::Builder::new()
+                .name(format!("{}-dispatch-{}", kind, i))
+                .spawn(move || dispatcher.run())?;
+
+            worker_senders.push(worker_sender);
+            receivers.push(worker_receiver);
+        }
+
+        Ok(Self { queues, worker_senders, receivers })
+    }
+}
+
+struct Dispatcher<M> {
+    name: String,
+    policy: OverflowPolicy,
+    main: Receiver<M>,
+    worker: Sender<M>,
+    /// For taking the oldest message back under `drop_oldest`
+    worker_receiver: Receiver<M>,
+    spill: Option<Spill<M>>,
+    metrics: Arc<Metrics>,
+    dropped: fn(&Metrics) -> &IntCounter,
+    lost_since_warning: u64,
+    last_warning: Option<Instant>,
+}
+
+impl<M: Dispatch> Dispatcher<M> {
+    fn run(mut self) {
+        loop {
+            let spilling = self.spill.as_ref().is_some_and(|spill| !spill.is_empty());
+            let message = if spilling {
+                match self.main.recv_timeout(SPILL_POLL) {
+                    Ok(message) => Some(message),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            } else {
+                match self.main.recv() {
+                    Ok(message) => Some(message),
+                    Err(_) => break,
+                }
+            };
+
+            if let Some(spill) = &mut self.spill {
+                spill.refill(&self.worker);
+            }
+            let Some(message) = message else { continue };
+            let shutdown = message.is_shutdown();
+            self.dispatch(message);
+            if shutdown {
+                break;
+            }
+        }
+    }
+
+    fn dispatch(&mut self, message: M) {
+        if message.must_deliver() {
+            // Spilled messages were queued first
+            if let Some(spill) = &mut self.spill {
+                spill.flush(&self.worker);
+            }
+            let _ = self.worker.send(message);
+            return;
+        }
+
+        if let Some(spill) = &mut self.spill {
+            if !spill.is_empty() {
+                let lost = spill.push(message, &self.metrics);
+                self.behind(lost);
+                return;
+            }
+        }
+        let message = match self.worker.try_send(message) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+            Err(TrySendError::Full(message)) => message,
+        };
+
+        match self.policy {
+            OverflowPolicy::Block => {
+                self.behind(0);
+                let _ = self.worker.send(message);
+            }
+            OverflowPolicy::DropNewest => self.behind(1),
+            OverflowPolicy::DropOldest => {
+                let mut message = message;
+                loop {
+                    match self.worker_receiver.try_recv() {
+                        // Never dropped, so it goes back and the new one goes instead
+                        Ok(oldest) if oldest.must_deliver() => {
+                            let _ = self.worker.send(oldest);
+                            self.behind(1);
+                            return;
+                        }
+                        Ok(_) => self.behind(1),
+                        // The worker took it first
+                        Err(_) => {}
+                    }
+                    match self.worker.try_send(message) {
+                        Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+                        Err(TrySendError::Full(returned)) => message = returned,
+                    }
+                }
+            }
+            OverflowPolicy::SpillToDisk => {
+                let lost = match &mut self.spill {
+                    Some(spill) => spill.push(message, &self.metrics),
+                    None => 1,
+                };
+                self.behind(lost);
+            }
+        }
+    }
+
+    /// Records that the worker fell behind, losing `lost` messages
+    fn behind(&mut self, lost: u64) {
+        (self.dropped)(&self.metrics).inc_by(lost);
+        self.lost_since_warning += lost;
+        if self.last_warning.is_some_and(|last| last.elapsed() < WARN_INTERVAL) {
+            return;
+        }
+        warn!(
+            "Slow consumer: {} is falling behind ({:?}), {} messages lost since the last warning",
+            self.name, self.policy, self.lost_since_warning
+        );
+        self.metrics.slow_consumer_warnings.inc();
+        self.lost_since_warning = 0;
+        self.last_warning = Some(Instant::now());
+    }
+}
+
+/// Messages a dispatcher spilled, oldest first: those read back from disk,
+/// those on disk, then those not yet written
+struct Spill<M> {
+    queue: SpillQueue,
+    backlog: VecDeque<M>,
+    pending: Vec<M>,
+}
+
+impl<M: Dispatch> Spill<M> {
+    fn open(directory: &Path, max_size_mb: u64) -> Result<Self> {
+        let queue = SpillQueue::open(&SpillConfig {
+            directory: directory.to_string_lossy().to_string(),
+            max_size_mb,
+            drop_policy: DropPolicy::DropNewest,
+        })?;
+        Ok(Self { queue, backlog: VecDeque::new(), pending: Vec::new() })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.backlog.is_empty() && self.pending.is_empty() && self.queue.is_empty()
+    }
+
+    /// Returns how many messages were lost to a full spill directory
+    fn push(&mut self, message: M, metrics: &Metrics) -> u64 {
+        self.pending.push(message);
+        if self.pending.len() < SPILL_CHUNK {
+            return 0;
+        }
+
+        let count = self.pending.len() as u64;
+        let written = bincode::serialize(&self.pending)
+            .map_err(Into::into)
+            .and_then(|data| self.queue.push_tagged(SPILL_TAG, data));
+        self.pending.clear();
+        match written {
+            Ok((true, _)) => {
+                metrics.messages_overflowed.inc_by(count);
+                0
+            }
+            Ok((false, _)) => count,
+            Err(e) => {
+                warn!("Failed to spill messages: {}", e);
+                count
+            }
+        }
+    }
+
+    /// Moves the oldest messages into `backlog`, returning `false` when
+    /// nothing is spilled
+    fn load(&mut self) -> bool {
+        if self.queue.is_empty() {
+            if self.pending.is_empty() {
+                return false;
+            }
+            self.backlog.extend(self.pending.drain(..));
+            return true;
+        }
+
+        let backlog = &mut self.backlog;
+        let loaded = self.queue.drain_tagged(1, |_, data| {
+            match bincode::deserialize::<Vec<M>>(&data) {
+                Ok(messages) => backlog.extend(messages),
+                Err(e) => warn!("Discarding unreadable spilled messages: {}", e),
+            }
+            Ok(())
+        });
+        if let Err(e) = loaded {
+            warn!("Failed to read spilled messages: {}", e);
+            return false;
+        }
+        true
+    }
+
+    /// Hands spilled messages to the worker while it has room
+    fn refill(&mut self, worker: &Sender<M>) {
+        loop {
+            while let Some(message) = self.backlog.pop_front() {
+                match worker.try_send(message) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(message)) => {
+                        self.backlog.push_front(message);
+                        return;
+                    }
+                    Err(TrySendError::Disconnected(_)) => return,
+                }
+            }
+            if !self.load() {
+                return;
+            }
+        }
+    }
+
+    /// Waits until the worker has every spilled message
+    fn flush(&mut self, worker: &Sender<M>) {
+        loop {
+            while let Some(message) = self.backlog.pop_front() {
+                if worker.send(message).is_err() {
+                    return;
+                }
+            }
+            if !self.load() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{config::OverflowConfig, ShutdownFlag},
+        serde::Deserialize,
+    };
+
+    #[derive(Serialize, Deserialize)]
+    struct Message(u64);
+
+    impl Dispatch for Message {
+        fn must_deliver(&self) -> bool {
+            false
+        }
+
+        fn is_shutdown(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn spilled_messages_arrive_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ProcessorConfig {
+            thread_count: 1,
+            batch_size: 100,
+            overflow: OverflowConfig {
+                policy: OverflowPolicy::SpillToDisk,
+                spill_directory: Some(dir.path().to_string_lossy().to_string()),
+                spill_max_size_mb: 16,
+            },
+            metrics: Arc::new(Metrics::new()),
+            shutdown_flag: Arc::new(ShutdownFlag::new()),
+        };
+        let channels = Channels::new("test", &config, |metrics| &metrics.account_messages_dropped).unwrap();
+
+        let count = (WORKER_CAPACITY + 2 * SPILL_CHUNK + 10) as u64;
+        for i in 0..count {
+            channels.queues[0].send(Message(i)).unwrap();
+        }
+        for i in 0..count {
+            let Message(received) = channels.receivers[0].recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(received, i);
+        }
+        assert_eq!(config.metrics.account_messages_dropped.get(), 0);
+        assert!(config.metrics.messages_overflowed.get() > 0);
+    }
+}
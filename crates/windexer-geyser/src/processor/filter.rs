@@ -0,0 +1,188 @@
+// crates/windexer-geyser/src/processor/filter.rs
+
+//! Account and transaction selection
+//!
+//! The filter expressions, or else the selectors, from the plugin config
+//! are parsed once into filters that the processor workers evaluate against
+//! every update before it is converted and batched for the publisher.
+
+use {
+    crate::config::{AccountsSelector, GeyserPluginConfig, TransactionSelector},
+    log::warn,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::BTreeSet, str::FromStr},
+    windexer_common::filter::{AccountRef, Bounds, Filter, Predicate, Term, TransactionRef},
+};
+
+fn parse_pubkeys<'a>(keys: impl IntoIterator<Item = &'a String>, kind: &str) -> BTreeSet<Pubkey> {
+    keys.into_iter()
+        .filter_map(|key| match Pubkey::from_str(key) {
+            Ok(pubkey) => Some(pubkey),
+            Err(_) => {
+                warn!("Invalid {} pubkey in selector: {}", kind, key);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Terms for the non-empty sets, any of which selects an update
+fn any_of<const N: usize>(sets: [(BTreeSet<Pubkey>, fn(BTreeSet<Pubkey>) -> Predicate); N]) -> Vec<Term> {
+    sets.into_iter()
+        .filter(|(pubkeys, _)| !pubkeys.is_empty())
+        .map(|(pubkeys, predicate)| predicate(pubkeys).into())
+        .collect()
+}
+
+/// The filter an accounts selector stands for
+pub fn accounts_selector_filter(selector: &AccountsSelector) -> Filter {
+    let mut filter = Filter::all();
+    if !selector.accounts.iter().any(|account| account == "*") {
+        filter = filter.and_any(any_of([
+            (parse_pubkeys(selector.accounts.iter(), "account"), Predicate::Accounts),
+            (parse_pubkeys(selector.owners.iter().flatten(), "owner"), Predicate::Owners),
+            (parse_pubkeys(selector.token_owners.iter().flatten(), "token owner"), Predicate::TokenOwners),
+        ]));
+    }
+    if let Some(data_size) = selector.data_size.filter(|size| size.min.is_some() || size.max.is_some()) {
+        filter = filter.and(Predicate::DataSize(Bounds { min: data_size.min, max: data_size.max }));
+    }
+    for memcmp in &selector.memcmp {
+        match bs58::decode(&memcmp.bytes).into_vec() {
+            Ok(bytes) => filter = filter.and(Predicate::Memcmp { offset: memcmp.offset, bytes }),
+            Err(e) => warn!("Invalid memcmp bytes {} in selector: {}", memcmp.bytes, e),
+        }
+    }
+    filter
+}
+
+/// The filter a transaction selector stands for; vote transactions are
+/// only selected by `*`, `all_votes` or `include_votes`
+pub fn transaction_selector_filter(selector: &TransactionSelector) -> Filter {
+    if selector.mentions.iter().any(|mention| mention == "*") {
+        return Filter::all();
+    }
+    let include_votes = selector.include_votes || selector.mentions.iter().any(|mention| mention == "all_votes");
+    let mentions = selector.mentions.iter().filter(|mention| *mention != "all_votes");
+
+    let mut selected = any_of([
+        (parse_pubkeys(mentions, "mention"), Predicate::Accounts),
+        (parse_pubkeys(&selector.programs, "program"), Predicate::Programs),
+    ]);
+    if include_votes {
+        selected.push(Predicate::Vote(true).into());
+    }
+    let filter = Filter::all().and_any(selected);
+    if include_votes {
+        filter
+    } else {
+        filter.and(Predicate::Vote(false))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AccountFilter {
+    /// Selects nothing when unset
+    filter: Option<Filter>,
+}
+
+impl AccountFilter {
+    pub fn new(filter: Option<Filter>) -> Self {
+        Self { filter }
+    }
+
+    /// `accounts_filter`, or else the filter of `accounts_selector`
+    pub fn from_config(config: &GeyserPluginConfig) -> Self {
+        Self::new(config.accounts_filter.clone().or_else(|| config.accounts_selector.as_ref().map(accounts_selector_filter)))
+    }
+
+    pub fn matches(&self, pubkey: &Pubkey, owner: &Pubkey, data: &[u8], slot: u64) -> bool {
+        self.filter.as_ref()
+            .is_some_and(|filter| filter.matches_account(&AccountRef { pubkey, owner, data, slot }))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TransactionFilter {
+    /// Selects nothing when unset
+    filter: Option<Filter>,
+    skip_votes: bool,
+    skip_failed: bool,
+}
+
+impl TransactionFilter {
+    pub fn new(filter: Option<Filter>) -> Self {
+        Self { filter, ..Self::default() }
+    }
+
+    /// `transactions_filter`, or else the filter of `transaction_selector`
+    pub fn from_config(config: &GeyserPluginConfig) -> Self {
+        Self::new(config.transactions_filter.clone().or_else(|| config.transaction_selector.as_ref().map(transaction_selector_filter)))
+    }
+
+    /// Drops vote and failed transactions whatever the filter says
+    pub fn skipping(mut self, votes: bool, failed: bool) -> Self {
+        self.skip_votes = votes;
+        self.skip_failed = failed;
+        self
+    }
+
+    /// Whether the transaction is dropped by the skip flags, which is
+    /// checked before it is queued for the workers
+    pub fn skips(&self, is_vote: bool, failed: bool) -> bool {
+        (is_vote && self.skip_votes) || (failed && self.skip_failed)
+    }
+
+    /// `transaction.account_keys` includes addresses loaded from lookup
+    /// tables, and `transaction.programs` the programs of the top-level
+    /// instructions
+    pub fn matches(&self, transaction: &TransactionRef) -> bool {
+        self.filter.as_ref().is_some_and(|filter| filter.matches_transaction(transaction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::config::{DataSizeFilter, MemcmpFilter}, solana_sdk::pubkey};
+
+    const TOKEN_PROGRAM: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+    #[test]
+    fn token_owner_and_memcmp_filters() {
+        let wallet = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut data = vec![0u8; 165];
+        data[..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(wallet.as_ref());
+
+        let filter = AccountFilter::new(Some(accounts_selector_filter(&AccountsSelector {
+            accounts: vec![],
+            owners: None,
+            token_owners: Some(vec![wallet.to_string()]),
+            data_size: Some(DataSizeFilter { min: Some(165), max: Some(165) }),
+            memcmp: vec![MemcmpFilter { offset: 0, bytes: mint.to_string() }],
+        })));
+        let account = Pubkey::new_unique();
+        assert!(filter.matches(&account, &TOKEN_PROGRAM, &data, 1));
+        assert!(!filter.matches(&account, &Pubkey::new_unique(), &data, 1));
+
+        data[0] ^= 1;
+        assert!(!filter.matches(&account, &TOKEN_PROGRAM, &data, 1));
+    }
+
+    #[test]
+    fn skip_flags_drop_vote_and_failed_transactions() {
+        let filter = TransactionFilter::new(Some(Filter::all()));
+        assert!(!filter.skips(true, false));
+        assert!(!filter.skips(false, true));
+
+        let filter = filter.skipping(true, true);
+        assert!(filter.skips(true, false));
+        assert!(filter.skips(false, true));
+        assert!(!filter.skips(false, false));
+
+        let filter = filter.skipping(false, true);
+        assert!(!filter.skips(true, false));
+        assert!(filter.skips(true, true));
+    }
+}
@@ -6,16 +6,20 @@
 //! and blocks from the Geyser plugin interface.
 
 mod account;
+mod dispatch;
+mod filter;
 mod transaction;
 mod block;
+mod versions;
 
 pub use account::AccountProcessor;
 pub use transaction::TransactionProcessor;
 pub use block::BlockProcessor;
+pub use filter::{AccountFilter, TransactionFilter};
 
 use {
     crate::{
-        config::{AccountsSelector, TransactionSelector},
+        config::{AccountsSelector, OverflowConfig, TransactionSelector},
         metrics::Metrics,
         ShutdownFlag,
     },
@@ -29,6 +33,7 @@ use {
     std::{
         sync::{Arc, atomic::{AtomicBool, Ordering}},
         thread::{self, JoinHandle},
+        time::{Duration, Instant},
     },
     crate::publisher::Publisher,
 };
@@ -39,6 +44,8 @@ pub struct ProcessorConfig {
     
     pub batch_size: usize,
     
+    pub overflow: OverflowConfig,
+    
     pub metrics: Arc<Metrics>,
     
     pub shutdown_flag: Arc<ShutdownFlag>,
@@ -82,6 +89,16 @@ pub trait BlockHandler: Send + Sync {
     ) -> Result<()>;
 }
 
+/// How often draining checks whether the workers are done
+const DRAIN_POLL: Duration = Duration::from_millis(10);
+
+/// Lets a processor's workers finish what was queued before unloading
+pub trait Drain {
+    /// Queue a shutdown message for every worker behind the messages
+    /// already queued, waiting for room until `deadline`
+    fn close(&self, deadline: Instant) -> Result<()>;
+}
+
 pub struct ProcessorHandle<T> {
     processor: Arc<T>,
     workers: Vec<JoinHandle<()>>,
@@ -100,6 +117,25 @@ impl<T> ProcessorHandle<T> {
             let _ = worker.join();
         }
     }
+    
+    /// Wait until `deadline` for the workers to exit, joining them if they
+    /// do; workers still running are left to stop on the shutdown flag
+    pub fn finish(self, deadline: Instant) -> bool {
+        while !self.workers.iter().all(JoinHandle::is_finished) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(DRAIN_POLL);
+        }
+        self.join();
+        true
+    }
+}
+
+impl<T: Drain> ProcessorHandle<T> {
+    pub fn close(&self, deadline: Instant) -> Result<()> {
+        self.processor.close(deadline)
+    }
 }
 
 impl<T: AccountHandler> ProcessorHandle<T> {
@@ -6,9 +6,12 @@
 
 use {
     crate::{
-        config::TransactionSelector,
         metrics::Metrics,
-        processor::{ProcessorConfig, TransactionHandler, ProcessorHandle},
+        processor::{
+            dispatch::{Channels, Dispatch},
+            filter::TransactionFilter,
+            ProcessorConfig, TransactionHandler, Drain, ProcessorHandle,
+        },
         publisher::Publisher,
         ShutdownFlag,
     },
@@ -30,42 +33,57 @@ use {
         address_lookup_table::state::AddressLookupTable,
         transaction::VersionedTransaction,
         message::v0::LoadedAddresses,
-        message::Message,
+        message::{Message, SanitizedMessage},
     },
     anyhow::{anyhow, Result},
-    crossbeam_channel::{Sender, Receiver, bounded},
-    log::{debug, error, info, trace, warn},
+    crossbeam_channel::{Sender, Receiver},
+    log::{debug, error, info, trace},
+    serde::{Deserialize, Serialize},
     std::{
-        collections::HashSet,
-        sync::{
-            Arc,
-            atomic::{AtomicBool, Ordering},
-            RwLock,
-        },
+        sync::Arc,
         thread::{self, JoinHandle},
-        time::Duration,
-        str::FromStr,
+        time::{Duration, Instant},
+    },
+    windexer_common::{
+        filter::TransactionRef,
+        types::transaction::{InstructionData, TransactionData},
+        utils::SerializableTransactionMeta,
     },
-    windexer_common::types::transaction::TransactionData,
 };
 
+#[derive(Serialize, Deserialize)]
 enum TransactionMessage {
     ProcessTransaction {
-        signature: [u8; 64],
+        signatures: Vec<Signature>,
         slot: Slot,
         is_vote: bool,
+        index: usize,
+        /// Includes the addresses loaded from lookup tables
+        message: Message,
+        /// Not serializable, so a spilled transaction keeps only
+        /// `serializable_meta`
+        #[serde(skip)]
+        meta: TransactionStatusMeta,
+        serializable_meta: SerializableTransactionMeta,
     },
     
     Shutdown,
 }
 
+impl Dispatch for TransactionMessage {
+    fn must_deliver(&self) -> bool {
+        self.is_shutdown()
+    }
+
+    fn is_shutdown(&self) -> bool {
+        matches!(self, TransactionMessage::Shutdown)
+    }
+}
+
 pub struct TransactionProcessor {
     config: ProcessorConfig,
     publisher: Arc<dyn Publisher>,
-    selector: Option<TransactionSelector>,
-    mentioned_accounts: Arc<RwLock<Option<HashSet<Pubkey>>>>,
-    include_all_transactions: Arc<AtomicBool>,
-    include_votes: Arc<AtomicBool>,
+    filter: Arc<TransactionFilter>,
     sender: Sender<TransactionMessage>,
     receivers: Vec<Receiver<TransactionMessage>>,
 }
@@ -74,93 +92,23 @@ impl TransactionProcessor {
     pub fn new(
         config: ProcessorConfig,
         publisher: Arc<dyn Publisher>,
-        selector: Option<TransactionSelector>,
-    ) -> ProcessorHandle<Self> {
-        let (mentioned_accounts, include_all_transactions, include_votes) = 
-            Self::parse_selectors(&selector);
-        
-        let (sender, receivers) = Self::create_channels(config.thread_count);
+        filter: TransactionFilter,
+    ) -> Result<ProcessorHandle<Self>> {
+        let Channels { mut queues, receivers, .. } =
+            Channels::new("transaction", &config, |metrics| &metrics.transaction_messages_dropped)?;
+        let sender = queues.remove(0);
         
         let processor = Self {
             config: config.clone(),
             publisher,
-            selector,
-            mentioned_accounts: Arc::new(RwLock::new(mentioned_accounts)),
-            include_all_transactions: Arc::new(AtomicBool::new(include_all_transactions)),
-            include_votes: Arc::new(AtomicBool::new(include_votes)),
+            filter: Arc::new(filter),
             sender,
             receivers,
         };
         
         let workers = processor.start_workers();
         
-        ProcessorHandle::new(processor, workers)
-    }
-    
-    fn parse_selectors(
-        selector: &Option<TransactionSelector>,
-    ) -> (Option<HashSet<Pubkey>>, bool, bool) {
-        let mut mentioned_accounts = None;
-        let mut include_all_transactions = false;
-        let mut include_votes = false;
-        
-        if let Some(selector) = selector {
-            if selector.mentions.contains(&"*".to_string()) {
-                include_all_transactions = true;
-            } else if selector.mentions.contains(&"all_votes".to_string()) {
-                include_votes = true;
-            } else {
-                let mut account_set = HashSet::new();
-                for mention in &selector.mentions {
-                    if let Ok(pubkey) = Pubkey::from_str(mention) {
-                        account_set.insert(pubkey);
-                    } else {
-                        warn!("Invalid mention pubkey in selector: {}", mention);
-                    }
-                }
-                mentioned_accounts = Some(account_set);
-            }
-            
-            if selector.include_votes {
-                include_votes = true;
-            }
-        }
-        
-        (mentioned_accounts, include_all_transactions, include_votes)
-    }
-    
-    fn create_channels(
-        thread_count: usize,
-    ) -> (Sender<TransactionMessage>, Vec<Receiver<TransactionMessage>>) {
-        let (sender, main_receiver) = bounded(10_000);
-        let mut receivers = Vec::with_capacity(thread_count);
-        
-        for _ in 0..thread_count {
-            let (worker_sender, worker_receiver) = bounded(1_000);
-            
-            let main_receiver_clone = main_receiver.clone();
-            thread::spawn(move || {
-                for message in main_receiver_clone.iter() {
-                    match &message {
-                        TransactionMessage::Shutdown => {
-                            let _ = worker_sender.send(message);
-                            break;
-                        }
-                        _ => {
-                            if worker_sender.try_send(message).is_err() {
-                                // If the channel is full, just drop the message
-                                // The worker is probably busy and we don't want to block
-                                // the main thread
-                            }
-                        }
-                    }
-                }
-            });
-            
-            receivers.push(worker_receiver);
-        }
-        
-        (sender, receivers)
+        Ok(ProcessorHandle::new(processor, workers))
     }
     
     /// Start worker threads
@@ -172,9 +120,7 @@ impl TransactionProcessor {
             let publisher = self.publisher.clone();
             let metrics = self.config.metrics.clone();
             let shutdown_flag = self.config.shutdown_flag.clone();
-            let mentioned_accounts = self.mentioned_accounts.clone();
-            let include_all_transactions = self.include_all_transactions.clone();
-            let include_votes = self.include_votes.clone();
+            let filter = self.filter.clone();
             
             let worker = thread::Builder::new()
                 .name(format!("transaction-worker-{}", i))
@@ -184,9 +130,7 @@ impl TransactionProcessor {
                         publisher,
                         metrics,
                         shutdown_flag,
-                        mentioned_accounts,
-                        include_all_transactions,
-                        include_votes,
+                        filter,
                     );
                 })
                 .unwrap();
@@ -202,9 +146,7 @@ impl TransactionProcessor {
         publisher: Arc<dyn Publisher>,
         metrics: Arc<Metrics>,
         shutdown_flag: Arc<ShutdownFlag>,
-        mentioned_accounts: Arc<RwLock<Option<HashSet<Pubkey>>>>,
-        include_all_transactions: Arc<AtomicBool>,
-        include_votes: Arc<AtomicBool>,
+        filter: Arc<TransactionFilter>,
     ) {
         let mut batch = Vec::new();
         let mut last_publish = std::time::Instant::now();
@@ -215,18 +157,22 @@ impl TransactionProcessor {
             }
             
             match message {
-                TransactionMessage::ProcessTransaction { signature, slot, is_vote } => {
-                    if !Self::should_process_transaction(
-                        &signature, 
-                        &is_vote,
-                        &mentioned_accounts, 
-                        &include_all_transactions,
-                        &include_votes,
-                    ) {
+                TransactionMessage::ProcessTransaction { signatures, slot, is_vote, index, message, meta, serializable_meta } => {
+                    let programs: Vec<Pubkey> = message.instructions.iter()
+                        .filter_map(|instruction| message.account_keys.get(instruction.program_id_index as usize).copied())
+                        .collect();
+                    if !filter.matches(&TransactionRef {
+                        slot,
+                        is_vote,
+                        failed: serializable_meta.status != Some(0),
+                        account_keys: &message.account_keys,
+                        programs: &programs,
+                    }) {
                         continue;
                     }
+                    metrics.count_transaction(&programs);
                     
-                    match Self::convert_transaction(signature, slot, is_vote) {
+                    match Self::convert_transaction(signatures, slot, is_vote, index, message, meta, serializable_meta) {
                         Ok(transaction_data) => {
                             batch.push(transaction_data);
                             
@@ -234,9 +180,9 @@ impl TransactionProcessor {
                                 if !batch.is_empty() {
                                     if let Err(e) = publisher.publish_transactions(&batch) {
                                         error!("Failed to publish transactions: {}", e);
-                                        metrics.transaction_publish_errors.fetch_add(1, Ordering::Relaxed);
+                                        metrics.transaction_publish_errors.inc();
                                     } else {
-                                        metrics.transaction_batches_published.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                                        metrics.transaction_batches_published.inc_by(batch.len() as u64);
                                     }
                                     batch.clear();
                                     last_publish = std::time::Instant::now();
@@ -245,7 +191,7 @@ impl TransactionProcessor {
                         }
                         Err(e) => {
                             error!("Failed to convert transaction: {}", e);
-                            metrics.transaction_update_errors.fetch_add(1, Ordering::Relaxed);
+                            metrics.transaction_update_errors.inc();
                         }
                     }
                 }
@@ -259,85 +205,54 @@ impl TransactionProcessor {
         if !batch.is_empty() {
             if let Err(e) = publisher.publish_transactions(&batch) {
                 error!("Failed to publish transactions: {}", e);
-                metrics.transaction_publish_errors.fetch_add(1, Ordering::Relaxed);
+                metrics.transaction_publish_errors.inc();
             } else {
-                metrics.transaction_batches_published.fetch_add(batch.len() as u64, Ordering::Relaxed);
+                metrics.transaction_batches_published.inc_by(batch.len() as u64);
             }
         }
         
         debug!("Transaction worker thread exiting");
     }
     
-    fn should_process_transaction(
-        signature: &[u8; 64],
-        is_vote: &bool,
-        mentioned_accounts: &Arc<RwLock<Option<HashSet<Pubkey>>>>,
-        include_all_transactions: &Arc<AtomicBool>,
-        include_votes: &Arc<AtomicBool>,
-    ) -> bool {
-        if include_all_transactions.load(Ordering::Relaxed) {
-            return true;
-        }
-        
-        if *is_vote && include_votes.load(Ordering::Relaxed) {
-            return true;
-        }
-        
-        if let Some(_accounts) = mentioned_accounts.read().unwrap().as_ref() {
-            for _account_key in signature.iter() {
-                // ...
-            }
-        }
-        
-        false
-    }
-
     fn convert_transaction(
-        signature: [u8; 64],
+        signatures: Vec<Signature>,
         slot: Slot,
         is_vote: bool,
+        index: usize,
+        message: Message,
+        meta: TransactionStatusMeta,
+        serializable_meta: SerializableTransactionMeta,
     ) -> Result<TransactionData> {
+        // A spilled transaction only kept the serializable meta
+        let inner_instructions = meta.inner_instructions.clone()
+            .or_else(|| serializable_meta.inner_instructions());
+        let instructions = InstructionData::parse(&message, inner_instructions.as_deref());
+        
         Ok(TransactionData {
-            signature: Signature::default(),
+            signature: signatures.first().copied().unwrap_or_default(),
             slot,
             is_vote,
-            message: Message::new_with_blockhash(
-                &[],
-                None,
-                &Blockhash::default(),
-            ),
-            signatures: vec![Signature::from(signature)],
-            meta: TransactionStatusMeta {
-                status: Ok(()),
-                fee: 0,
-                pre_balances: vec![],
-                post_balances: vec![],
-                inner_instructions: None,
-                log_messages: None,
-                pre_token_balances: None,
-                post_token_balances: None,
-                rewards: None,
-                loaded_addresses: LoadedAddresses::default(),
-                return_data: None,
-                compute_units_consumed: None,
-            },
-            serializable_meta: (&TransactionStatusMeta {
-                status: Ok(()),
-                fee: 0,
-                pre_balances: vec![],
-                post_balances: vec![],
-                inner_instructions: None,
-                log_messages: None,
-                pre_token_balances: None,
-                post_token_balances: None,
-                rewards: None,
-                loaded_addresses: LoadedAddresses::default(),
-                return_data: None,
-                compute_units_consumed: None,
-            }).into(),
-            index: 0, // Unknown in V1
+            message,
+            signatures,
+            meta,
+            serializable_meta,
+            index,
+            instructions,
         })
     }
+    
+    /// Versioned messages are flattened into a legacy message with the
+    /// addresses loaded from lookup tables appended to the account keys, so
+    /// instruction indexes resolve against them; the header only describes
+    /// the static keys
+    fn flatten_message(message: &SanitizedMessage) -> Message {
+        Message {
+            header: *message.header(),
+            account_keys: message.account_keys().iter().copied().collect(),
+            recent_blockhash: *message.recent_blockhash(),
+            instructions: message.instructions().to_vec(),
+        }
+    }
 }
 
 impl TransactionHandler for TransactionProcessor {
@@ -346,28 +261,34 @@ impl TransactionHandler for TransactionProcessor {
         transaction: ReplicaTransactionInfoVersions,
         slot: Slot,
     ) -> Result<()> {
-        let signature_bytes = match &transaction {
-            ReplicaTransactionInfoVersions::V0_0_1(info) => {
-                let mut bytes = [0u8; 64];
-                bytes.copy_from_slice(info.signature.as_ref());
-                bytes
-            },
-            ReplicaTransactionInfoVersions::V0_0_2(info) => {
-                let mut bytes = [0u8; 64];
-                bytes.copy_from_slice(info.signature.as_ref());
-                bytes
-            },
-        };
-        
-        let is_vote = match &transaction {
-            ReplicaTransactionInfoVersions::V0_0_1(info) => info.is_vote,
-            ReplicaTransactionInfoVersions::V0_0_2(info) => info.is_vote,
+        let (is_vote, sanitized, meta, index) = match &transaction {
+            ReplicaTransactionInfoVersions::V0_0_1(info) => (info.is_vote, info.transaction, info.transaction_status_meta, 0),
+            ReplicaTransactionInfoVersions::V0_0_2(info) => (info.is_vote, info.transaction, info.transaction_status_meta, info.index),
         };
+        if self.filter.skips(is_vote, meta.status.is_err()) {
+            self.config.metrics.transactions_skipped.inc();
+            return Ok(());
+        }
         
         self.sender.send(TransactionMessage::ProcessTransaction {
-            signature: signature_bytes,
+            signatures: sanitized.signatures().to_vec(),
             slot,
             is_vote,
+            index,
+            message: Self::flatten_message(sanitized.message()),
+            meta: meta.clone(),
+            serializable_meta: meta.into(),
         }).map_err(|e| anyhow!("Failed to send transaction to processor: {}", e))
     }
-}
\ No newline at end of file
+}
+
+impl Drain for TransactionProcessor {
+    fn close(&self, deadline: Instant) -> Result<()> {
+        // Every dispatcher takes one from the shared queue and stops
+        for _ in 0..self.receivers.len() {
+            self.sender.send_deadline(TransactionMessage::Shutdown, deadline)
+                .map_err(|e| anyhow!("Failed to queue transaction worker shutdown: {}", e))?;
+        }
+        Ok(())
+    }
+}
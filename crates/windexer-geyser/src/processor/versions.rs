@@ -0,0 +1,99 @@
+// crates/windexer-geyser/src/processor/versions.rs
+
+//! Per-account replay protection
+//!
+//! The validator can deliver account updates out of order, and a stale
+//! state published after a newer one would overwrite it downstream. Each
+//! account worker remembers the newest `(slot, write_version)` it processed
+//! per account and reports updates that are not newer. Accounts are sharded
+//! across workers by pubkey, so one worker sees every update of an account.
+//!
+//! Accounts not updated within `window_slots` of the newest slot are
+//! forgotten, which bounds memory; an update delayed longer than that is
+//! not caught.
+//!
+//! Versions are compared without regard to forks. An update on a fork whose
+//! slots are below those of one the account was updated on before is
+//! reported as stale even when that other fork was abandoned, which is why
+//! dropping reported updates is off by default.
+
+use {
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::collections::HashMap,
+};
+
+#[derive(Debug)]
+pub struct AccountVersions {
+    latest: HashMap<Pubkey, (Slot, u64)>,
+    window_slots: u64,
+    newest_slot: Slot,
+    pruned_at: Slot,
+}
+
+impl AccountVersions {
+    pub fn new(window_slots: u64) -> Self {
+        Self {
+            latest: HashMap::new(),
+            window_slots,
+            newest_slot: 0,
+            pruned_at: 0,
+        }
+    }
+
+    /// Records the update and returns whether it is newer than every update
+    /// of the account seen before; a replay of the same version is not
+    pub fn observe(&mut self, pubkey: &Pubkey, slot: Slot, write_version: u64) -> bool {
+        let version = (slot, write_version);
+        let fresh = match self.latest.get_mut(pubkey) {
+            Some(latest) if *latest >= version => false,
+            Some(latest) => {
+                *latest = version;
+                true
+            }
+            None => {
+                self.latest.insert(*pubkey, version);
+                true
+            }
+        };
+
+        if slot > self.newest_slot {
+            self.newest_slot = slot;
+            if self.newest_slot >= self.pruned_at + self.window_slots {
+                self.prune();
+            }
+        }
+        fresh
+    }
+
+    fn prune(&mut self) {
+        let oldest = self.newest_slot.saturating_sub(self.window_slots);
+        self.latest.retain(|_, (slot, _)| *slot >= oldest);
+        self.pruned_at = self.newest_slot;
+    }
+
+    pub fn len(&self) -> usize {
+        self.latest.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_stale_and_replayed_updates() {
+        let mut versions = AccountVersions::new(100);
+        let account = Pubkey::new_unique();
+
+        assert!(versions.observe(&account, 10, 5));
+        assert!(!versions.observe(&account, 10, 5));
+        assert!(!versions.observe(&account, 10, 4));
+        assert!(!versions.observe(&account, 9, 50));
+        assert!(versions.observe(&account, 10, 6));
+        assert!(versions.observe(&account, 11, 1));
+
+        versions.observe(&Pubkey::new_unique(), 200, 1);
+        assert_eq!(versions.len(), 1);
+        assert!(versions.observe(&account, 5, 1));
+    }
+}
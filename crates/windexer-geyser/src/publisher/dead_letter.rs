@@ -0,0 +1,103 @@
+// crates/windexer-geyser/src/publisher/dead_letter.rs
+
+//! Dead letters of failed publishes
+//!
+//! [`DeadLetterPublisher`] wraps the configured publisher and keeps every
+//! batch it fails to publish in the plugin's dead-letter queue, encoded as a
+//! wire frame, before passing the error on. Retrying an entry publishes its
+//! batch through the wrapped publisher again.
+
+use {
+    super::Publisher,
+    crate::metrics::Metrics,
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    log::{error, warn},
+    std::sync::Arc,
+    windexer_common::{
+        dlq::{DeadLetterQueue, Redeliver},
+        types::{
+            account::AccountData,
+            block::{BlockData, EntryData},
+            transaction::TransactionData,
+            wire::{self, WireFrame, WireKind, WireRecord},
+        },
+    },
+};
+
+/// Source of the dead letters kept by the publisher
+const SOURCE: &str = "publisher";
+
+#[derive(Debug)]
+pub struct DeadLetterPublisher {
+    inner: Arc<dyn Publisher>,
+    queue: Arc<DeadLetterQueue>,
+    metrics: Arc<Metrics>,
+    validator_id: Option<String>,
+}
+
+impl DeadLetterPublisher {
+    pub fn new(inner: Arc<dyn Publisher>, queue: Arc<DeadLetterQueue>, metrics: Arc<Metrics>, validator_id: Option<String>) -> Self {
+        Self { inner, queue, metrics, validator_id }
+    }
+
+    /// Keeps `records` as a dead letter if `result` is an error, which is
+    /// returned either way
+    fn keep<T: WireRecord>(&self, records: &[T], result: Result<()>) -> Result<()> {
+        let Err(e) = &result else {
+            return result;
+        };
+        let kept = wire::encode(self.validator_id.as_deref(), records)
+            .and_then(|frame| self.queue.push(SOURCE, frame, &e.to_string()));
+        match kept {
+            Ok(Some(id)) => {
+                self.metrics.dead_letters.inc();
+                warn!("Kept {} failed {} as dead letter {}", records.len(), T::KIND.name(), id);
+            }
+            Ok(None) => warn!("Dead-letter queue is full, dropping {} failed {}", records.len(), T::KIND.name()),
+            Err(e) => error!("Failed to keep {} failed {} as a dead letter: {}", records.len(), T::KIND.name(), e),
+        }
+        result
+    }
+}
+
+impl Publisher for DeadLetterPublisher {
+    fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        self.keep(accounts, self.inner.publish_accounts(accounts))
+    }
+
+    fn publish_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        self.keep(transactions, self.inner.publish_transactions(transactions))
+    }
+
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        let result = self.inner.publish_block(block.clone());
+        self.keep(&[block], result)
+    }
+
+    fn publish_entries(&self, entries: &[EntryData]) -> Result<()> {
+        self.keep(entries, self.inner.publish_entries(entries))
+    }
+}
+
+/// Publishes the batch of a dead letter with `publisher`
+fn republish(publisher: &dyn Publisher, payload: &[u8]) -> Result<()> {
+    let frame = WireFrame::parse(payload)?;
+    match frame.kind {
+        WireKind::Accounts => publisher.publish_accounts(&frame.decode::<AccountData>()?),
+        WireKind::Transactions => publisher.publish_transactions(&frame.decode::<TransactionData>()?),
+        WireKind::Blocks => frame.decode::<BlockData>()?.into_iter().try_for_each(|block| publisher.publish_block(block)),
+        WireKind::Entries => publisher.publish_entries(&frame.decode::<EntryData>()?),
+        WireKind::AccountPatches => Err(anyhow!("Account patches are never published as they are")),
+    }
+}
+
+#[async_trait]
+impl Redeliver for DeadLetterPublisher {
+    async fn redeliver(&self, _source: &str, payload: &[u8]) -> Result<()> {
+        // Publishers block, some of them on the plugin's runtime
+        let inner = self.inner.clone();
+        let payload = payload.to_vec();
+        tokio::task::spawn_blocking(move || republish(inner.as_ref(), &payload)).await?
+    }
+}
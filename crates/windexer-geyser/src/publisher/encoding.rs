@@ -0,0 +1,207 @@
+// crates/windexer-geyser/src/publisher/encoding.rs
+
+//! Message encoding for external publishers
+//!
+//! Publishers that hand data to other systems encode each record on its own,
+//! as JSON, protobuf or Borsh. The protobuf messages are the export messages
+//! of [`windexer_common::types::proto`], which follow the update messages of
+//! the Yellowstone gRPC `geyser` package, so existing decoders read them
+//! as-is. Borsh encodes the same messages.
+//!
+//! Accounts are the bulk of the traffic and their data can run to megabytes,
+//! so their protobuf message is written straight from the record instead of
+//! copying the data into a [`SubscribeUpdateAccount`] first.
+
+use {
+    crate::config::MessageFormat,
+    anyhow::Result,
+    borsh::BorshSerialize,
+    prost::{
+        encoding::{self, encode_key, encode_varint, encoded_len_varint, key_len, WireType},
+        Message,
+    },
+    serde::Serialize,
+    windexer_common::types::{
+        account::AccountData,
+        transaction::TransactionData,
+        block::BlockData,
+        block::EntryData,
+        idempotency::{IdempotencyKey, Idempotent},
+        proto::{SubscribeUpdateAccount, SubscribeUpdateBlockMeta, SubscribeUpdateEntry, SubscribeUpdateTransaction},
+    },
+};
+
+// Proto3 leaves out scalars and bytes holding their default value, except
+// in `optional` fields
+
+fn bytes_len(tag: u32, value: &[u8]) -> usize {
+    key_len(tag) + encoded_len_varint(value.len() as u64) + value.len()
+}
+
+fn uint64_len(tag: u32, value: u64) -> usize {
+    if value == 0 { 0 } else { encoding::uint64::encoded_len(tag, &value) }
+}
+
+fn bool_len(tag: u32, value: bool) -> usize {
+    if value { encoding::bool::encoded_len(tag, &value) } else { 0 }
+}
+
+fn put_bytes(tag: u32, value: &[u8], buf: &mut Vec<u8>) {
+    encode_key(tag, WireType::LengthDelimited, buf);
+    encode_varint(value.len() as u64, buf);
+    buf.extend_from_slice(value);
+}
+
+fn put_uint64(tag: u32, value: u64, buf: &mut Vec<u8>) {
+    if value != 0 {
+        encoding::uint64::encode(tag, &value, buf);
+    }
+}
+
+fn put_bool(tag: u32, value: bool, buf: &mut Vec<u8>) {
+    if value {
+        encoding::bool::encode(tag, &value, buf);
+    }
+}
+
+/// Same bytes as encoding `SubscribeUpdateAccount::from(account)`
+fn encode_account(account: &AccountData) -> Vec<u8> {
+    let signature = account.transaction_signature.as_ref().map(|signature| signature.as_ref());
+    let data_len = if account.data.is_empty() { 0 } else { bytes_len(6, &account.data) };
+    let info_len = bytes_len(1, account.pubkey.as_ref())
+        + uint64_len(2, account.lamports)
+        + bytes_len(3, account.owner.as_ref())
+        + bool_len(4, account.executable)
+        + uint64_len(5, account.rent_epoch)
+        + data_len
+        + uint64_len(7, account.write_version)
+        + signature.map_or(0, |signature| bytes_len(8, signature));
+    let len = key_len(1) + encoded_len_varint(info_len as u64) + info_len
+        + uint64_len(2, account.slot)
+        + bool_len(3, account.is_startup);
+
+    let mut buf = Vec::with_capacity(len);
+    encode_key(1, WireType::LengthDelimited, &mut buf);
+    encode_varint(info_len as u64, &mut buf);
+    put_bytes(1, account.pubkey.as_ref(), &mut buf);
+    put_uint64(2, account.lamports, &mut buf);
+    put_bytes(3, account.owner.as_ref(), &mut buf);
+    put_bool(4, account.executable, &mut buf);
+    put_uint64(5, account.rent_epoch, &mut buf);
+    if !account.data.is_empty() {
+        put_bytes(6, &account.data, &mut buf);
+    }
+    put_uint64(7, account.write_version, &mut buf);
+    if let Some(signature) = signature {
+        put_bytes(8, signature, &mut buf);
+    }
+    put_uint64(2, account.slot, &mut buf);
+    put_bool(3, account.is_startup, &mut buf);
+    buf
+}
+
+/// A record an external publisher sends as one message
+pub trait Encode: Serialize {
+    type Proto: Message + BorshSerialize + for<'a> From<&'a Self>;
+
+    /// Key the record is partitioned or routed by
+    fn key(&self) -> String;
+
+    /// Key consumers dedupe the record by; entries have none
+    fn idempotency_key(&self) -> Option<IdempotencyKey> {
+        None
+    }
+
+    fn encode(&self, format: MessageFormat) -> Result<Vec<u8>>
+    where
+        Self: Sized,
+    {
+        Ok(match format {
+            MessageFormat::Json => serde_json::to_vec(self)?,
+            MessageFormat::Protobuf => Self::Proto::from(self).encode_to_vec(),
+            MessageFormat::Borsh => borsh::to_vec(&Self::Proto::from(self))?,
+        })
+    }
+}
+
+impl Encode for AccountData {
+    type Proto = SubscribeUpdateAccount;
+
+    fn encode(&self, format: MessageFormat) -> Result<Vec<u8>> {
+        Ok(match format {
+            MessageFormat::Json => serde_json::to_vec(self)?,
+            MessageFormat::Protobuf => encode_account(self),
+            MessageFormat::Borsh => borsh::to_vec(&SubscribeUpdateAccount::from(self))?,
+        })
+    }
+
+    fn key(&self) -> String {
+        self.pubkey.to_string()
+    }
+
+    fn idempotency_key(&self) -> Option<IdempotencyKey> {
+        Some(Idempotent::idempotency_key(self))
+    }
+}
+
+impl Encode for TransactionData {
+    type Proto = SubscribeUpdateTransaction;
+
+    fn key(&self) -> String {
+        self.slot.to_string()
+    }
+
+    fn idempotency_key(&self) -> Option<IdempotencyKey> {
+        Some(Idempotent::idempotency_key(self))
+    }
+}
+
+impl Encode for BlockData {
+    type Proto = SubscribeUpdateBlockMeta;
+
+    fn key(&self) -> String {
+        self.slot.to_string()
+    }
+
+    fn idempotency_key(&self) -> Option<IdempotencyKey> {
+        Some(Idempotent::idempotency_key(self))
+    }
+}
+
+impl Encode for EntryData {
+    type Proto = SubscribeUpdateEntry;
+
+    fn key(&self) -> String {
+        self.slot.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::{pubkey::Pubkey, signature::Signature}};
+
+    #[test]
+    fn account_encoding_matches_the_message() {
+        let mut account = AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 2_039_280,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: u64::MAX,
+            data: vec![7; 165],
+            write_version: 42,
+            slot: 300_000_000,
+            is_startup: false,
+            transaction_signature: Some(Signature::from([3; 64])),
+        };
+        let expected = SubscribeUpdateAccount::from(&account).encode_to_vec();
+        assert_eq!(account.encode(MessageFormat::Protobuf).unwrap(), expected);
+
+        account.lamports = 0;
+        account.data.clear();
+        account.transaction_signature = None;
+        account.is_startup = true;
+        let expected = SubscribeUpdateAccount::from(&account).encode_to_vec();
+        assert_eq!(account.encode(MessageFormat::Protobuf).unwrap(), expected);
+    }
+}
@@ -0,0 +1,191 @@
+// crates/windexer-geyser/src/publisher/grpc.rs
+
+//! gRPC sidecar publisher
+//!
+//! This module contains the implementation of a publisher that streams
+//! processed data to processes on the validator host over the `Sidecar`
+//! service in `proto/sidecar.proto`, on a Unix socket or a loopback address.
+//! Heavy downstream work then runs outside the validator process without
+//! the p2p stack in between.
+//!
+//! Updates are only held for `buffer` records: there is no replay, and a
+//! subscriber that falls further behind gets a `DATA_LOSS` status.
+
+// tonic handlers return `Status` errors by design
+#![allow(clippy::result_large_err)]
+
+pub mod proto {
+    tonic::include_proto!("sidecar");
+}
+
+use {
+    super::Publisher,
+    crate::{config::GrpcConfig, metrics::Metrics},
+    anyhow::{anyhow, Result},
+    log::{info, warn},
+    proto::{
+        sidecar_server::{Sidecar, SidecarServer},
+        sidecar_update::Update,
+        SidecarUpdate, SubscribeRequest,
+    },
+    std::sync::Arc,
+    tokio::{
+        net::{TcpListener, UnixListener},
+        sync::{broadcast, broadcast::error::RecvError, mpsc, oneshot},
+    },
+    tokio_stream::wrappers::{ReceiverStream, TcpListenerStream, UnixListenerStream},
+    tonic::{transport::Server, Request, Response, Status},
+    windexer_common::types::{
+        account::AccountData,
+        transaction::TransactionData,
+        block::BlockData,
+        block::EntryData,
+    },
+};
+
+/// Updates buffered per subscriber between the broadcast and the connection
+const SUBSCRIBER_BUFFER: usize = 1024;
+
+impl SubscribeRequest {
+    fn selects(&self, update: &SidecarUpdate) -> bool {
+        if !(self.accounts || self.transactions || self.blocks || self.entries) {
+            return true;
+        }
+        match update.update {
+            Some(Update::Account(_)) => self.accounts,
+            Some(Update::Transaction(_)) => self.transactions,
+            Some(Update::Block(_)) => self.blocks,
+            Some(Update::Entry(_)) => self.entries,
+            None => false,
+        }
+    }
+}
+
+struct SidecarService {
+    updates: broadcast::Sender<Arc<SidecarUpdate>>,
+}
+
+#[tonic::async_trait]
+impl Sidecar for SidecarService {
+    type SubscribeStream = ReceiverStream<Result<SidecarUpdate, Status>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let request = request.into_inner();
+        let mut updates = self.updates.subscribe();
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_BUFFER);
+
+        tokio::spawn(async move {
+            loop {
+                let update = match updates.recv().await {
+                    Ok(update) => update,
+                    Err(RecvError::Lagged(missed)) => {
+                        let _ = tx.send(Err(Status::data_loss(format!(
+                            "Subscriber fell {} updates behind",
+                            missed
+                        )))).await;
+                        break;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if request.selects(&update) && tx.send(Ok((*update).clone())).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+}
+
+pub struct GrpcPublisher {
+    updates: broadcast::Sender<Arc<SidecarUpdate>>,
+    metrics: Arc<Metrics>,
+    /// Stops the server when the publisher is dropped
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl std::fmt::Debug for GrpcPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcPublisher")
+            .field("subscribers", &self.updates.receiver_count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl GrpcPublisher {
+    /// Binds the endpoint and serves it on the current runtime until the
+    /// publisher is dropped
+    pub async fn serve(config: GrpcConfig, metrics: Arc<Metrics>) -> Result<Self> {
+        let (updates, _) = broadcast::channel(config.buffer);
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let router = Server::builder()
+            .add_service(SidecarServer::new(SidecarService { updates: updates.clone() }));
+        let stopped = async move {
+            let _ = stopped.await;
+        };
+
+        match &config.socket_path {
+            Some(path) => {
+                // A socket left behind by an earlier run refuses the bind
+                let _ = std::fs::remove_file(path);
+                let listener = UnixListener::bind(path)
+                    .map_err(|e| anyhow!("Failed to bind sidecar socket {}: {}", path, e))?;
+                info!("Streaming to sidecars on {}", path);
+                tokio::spawn(async move {
+                    if let Err(e) = router.serve_with_incoming_shutdown(UnixListenerStream::new(listener), stopped).await {
+                        warn!("Sidecar stream stopped: {}", e);
+                    }
+                });
+            }
+            None => {
+                if !config.addr.ip().is_loopback() {
+                    return Err(anyhow!("Sidecar address {} is not a loopback address", config.addr));
+                }
+                let listener = TcpListener::bind(config.addr).await
+                    .map_err(|e| anyhow!("Failed to bind sidecar address {}: {}", config.addr, e))?;
+                info!("Streaming to sidecars on {}", config.addr);
+                tokio::spawn(async move {
+                    if let Err(e) = router.serve_with_incoming_shutdown(TcpListenerStream::new(listener), stopped).await {
+                        warn!("Sidecar stream stopped: {}", e);
+                    }
+                });
+            }
+        }
+
+        Ok(Self { updates, metrics, _shutdown: shutdown })
+    }
+
+    fn send(&self, updates: impl Iterator<Item = Update>) {
+        for update in updates {
+            // Without subscribers the update is gone, like a live stream
+            if self.updates.send(Arc::new(SidecarUpdate { update: Some(update) })).is_ok() {
+                self.metrics.messages_delivered.inc();
+            }
+        }
+    }
+}
+
+impl Publisher for GrpcPublisher {
+    fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        self.send(accounts.iter().map(|account| Update::Account(account.into())));
+        Ok(())
+    }
+
+    fn publish_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        self.send(transactions.iter().map(|transaction| Update::Transaction(transaction.into())));
+        Ok(())
+    }
+
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        self.send(std::iter::once(Update::Block((&block).into())));
+        Ok(())
+    }
+
+    fn publish_entries(&self, entries: &[EntryData]) -> Result<()> {
+        self.send(entries.iter().map(|entry| Update::Entry(entry.into())));
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+// crates/windexer-geyser/src/publisher/instrumented.rs
+
+//! Publish latency tracking
+//!
+//! [`InstrumentedPublisher`] times every call to the publisher it wraps and
+//! records the latency per data type in the plugin metrics. The plugin wraps
+//! whichever publisher it is configured with, so the figures cover the
+//! network, the store and the external brokers alike.
+
+use {
+    super::Publisher,
+    crate::metrics::Metrics,
+    anyhow::Result,
+    std::sync::Arc,
+    windexer_common::metrics::Histogram,
+    windexer_common::types::{
+        account::AccountData,
+        transaction::TransactionData,
+        block::BlockData,
+        block::EntryData,
+    },
+};
+
+#[derive(Debug)]
+pub struct InstrumentedPublisher {
+    inner: Arc<dyn Publisher>,
+    metrics: Arc<Metrics>,
+}
+
+impl InstrumentedPublisher {
+    pub fn new(inner: Arc<dyn Publisher>, metrics: Arc<Metrics>) -> Self {
+        Self { inner, metrics }
+    }
+
+    fn timed(&self, histogram: &Histogram, publish: impl FnOnce() -> Result<()>) -> Result<()> {
+        let timer = histogram.start_timer();
+        let result = publish();
+        timer.observe_duration();
+        result
+    }
+}
+
+impl Publisher for InstrumentedPublisher {
+    fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        self.timed(&self.metrics.account_publish_latency, || self.inner.publish_accounts(accounts))
+    }
+
+    fn publish_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        self.timed(&self.metrics.transaction_publish_latency, || self.inner.publish_transactions(transactions))
+    }
+
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        self.timed(&self.metrics.block_publish_latency, || self.inner.publish_block(block))
+    }
+
+    fn publish_entries(&self, entries: &[EntryData]) -> Result<()> {
+        self.timed(&self.metrics.entry_publish_latency, || self.inner.publish_entries(entries))
+    }
+}
@@ -0,0 +1,167 @@
+// crates/windexer-geyser/src/publisher/kafka.rs
+
+//! Kafka publisher
+//!
+//! This module contains the implementation of a publisher that produces
+//! processed data to Kafka, one message per record. Account updates are
+//! keyed by pubkey, so every update of an account lands on the same
+//! partition in order; transactions, blocks and entries are keyed by slot.
+//!
+//! librdkafka batches messages in the background (`linger_ms`) and reports
+//! each delivery back on its polling thread, where acknowledgements and
+//! failures are counted in the plugin metrics.
+//!
+//! The producer is idempotent unless `options` turn it off, so retries
+//! between it and the brokers don't duplicate messages. Messages of
+//! accounts, transactions and blocks carry their idempotency key in the
+//! `idempotency-key` header for consumers to dedupe what is sent again, see
+//! [`windexer_common::types::idempotency`].
+
+use {
+    super::{encoding::Encode, Publisher},
+    crate::{config::KafkaConfig, metrics::Metrics},
+    anyhow::{anyhow, Result},
+    log::{info, warn},
+    rdkafka::{
+        config::ClientConfig,
+        error::{KafkaError, RDKafkaErrorCode},
+        message::{Header, OwnedHeaders},
+        producer::{BaseRecord, DeliveryResult, Producer, ProducerContext, ThreadedProducer},
+        ClientContext,
+    },
+    std::{
+        sync::Arc,
+        thread,
+        time::Duration,
+    },
+    windexer_common::types::{
+        account::AccountData,
+        transaction::TransactionData,
+        block::BlockData,
+        block::EntryData,
+    },
+};
+
+/// Attempts at enqueueing a message while the producer queue is full
+const QUEUE_FULL_RETRIES: usize = 50;
+const QUEUE_FULL_BACKOFF: Duration = Duration::from_millis(20);
+/// How long unloading waits for queued messages to be delivered
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Header carrying a message's idempotency key
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+struct DeliveryReports {
+    metrics: Arc<Metrics>,
+}
+
+impl ClientContext for DeliveryReports {}
+
+impl ProducerContext for DeliveryReports {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        match result {
+            Ok(_) => {
+                self.metrics.messages_delivered.inc();
+            }
+            Err((e, _)) => {
+                warn!("Kafka delivery failed: {}", e);
+                self.metrics.delivery_failures.inc();
+            }
+        }
+    }
+}
+
+pub struct KafkaPublisher {
+    producer: ThreadedProducer<DeliveryReports>,
+    config: KafkaConfig,
+    metrics: Arc<Metrics>,
+}
+
+impl std::fmt::Debug for KafkaPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KafkaPublisher")
+            .field("brokers", &self.config.brokers)
+            .field("topics", &self.config.topics)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KafkaPublisher {
+    pub fn new(config: KafkaConfig, metrics: Arc<Metrics>) -> Result<Self> {
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &config.brokers)
+            .set("linger.ms", config.linger_ms.to_string())
+            .set("enable.idempotence", "true");
+        for (key, value) in &config.options {
+            client.set(key, value);
+        }
+        let producer = client
+            .create_with_context(DeliveryReports { metrics: metrics.clone() })
+            .map_err(|e| anyhow!("Failed to create Kafka producer: {}", e))?;
+        info!("Producing to Kafka at {} as {:?}", config.brokers, config.format);
+
+        Ok(Self { producer, config, metrics })
+    }
+
+    fn produce<T: Encode>(&self, topic: &str, records: &[T]) -> Result<()> {
+        for record in records {
+            let key = record.key();
+            let payload = record.encode(self.config.format)?;
+            let idempotency_key = record.idempotency_key().map(|key| key.to_string());
+            let mut message = BaseRecord::to(topic).key(&key).payload(&payload);
+            if let Some(idempotency_key) = &idempotency_key {
+                message = message.headers(OwnedHeaders::new().insert(Header {
+                    key: IDEMPOTENCY_KEY_HEADER,
+                    value: Some(idempotency_key),
+                }));
+            }
+            let mut retries = 0;
+            loop {
+                match self.producer.send(message) {
+                    Ok(()) => break,
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned))
+                        if retries < QUEUE_FULL_RETRIES =>
+                    {
+                        // The polling thread frees the queue as deliveries complete
+                        retries += 1;
+                        message = returned;
+                        thread::sleep(QUEUE_FULL_BACKOFF);
+                    }
+                    Err((e, _)) => {
+                        self.metrics.delivery_failures.inc();
+                        return Err(anyhow!("Failed to produce to {}: {}", topic, e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for KafkaPublisher {
+    fn drop(&mut self) {
+        if let Err(e) = self.producer.flush(FLUSH_TIMEOUT) {
+            warn!("{} Kafka messages undelivered at shutdown: {}", self.producer.in_flight_count(), e);
+        }
+    }
+}
+
+impl Publisher for KafkaPublisher {
+    fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        self.produce(&self.config.topics.accounts, accounts)
+    }
+
+    fn publish_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        self.produce(&self.config.topics.transactions, transactions)
+    }
+
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        self.produce(&self.config.topics.blocks, std::slice::from_ref(&block))
+    }
+
+    fn publish_entries(&self, entries: &[EntryData]) -> Result<()> {
+        self.produce(&self.config.topics.entries, entries)
+    }
+}
@@ -5,18 +5,44 @@
 //! This module contains the interfaces and implementations for publishing processed data
 //! to external consumers.
 
+mod dead_letter;
+mod encoding;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod instrumented;
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "nats")]
+mod nats;
 mod network;
 mod null;
+mod spill;
+#[cfg(feature = "store")]
+mod store;
 
+pub use dead_letter::DeadLetterPublisher;
+pub use encoding::Encode;
+#[cfg(feature = "grpc")]
+pub use grpc::GrpcPublisher;
+pub use instrumented::InstrumentedPublisher;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaPublisher;
+#[cfg(feature = "nats")]
+pub use nats::NatsPublisher;
 pub use network::NetworkPublisher;
 pub use null::NullPublisher;
+pub use spill::SpillQueue;
+#[cfg(feature = "store")]
+pub use store::StorePublisher;
 
 use {
-    crate::metrics::Metrics,
+    crate::{config::SpillConfig, metrics::Metrics},
     anyhow::Result,
     std::sync::Arc,
+    windexer_network::node::GossipHandle,
     windexer_common::types::{
         account::AccountData,
+        diff::AccountDiffConfig,
         transaction::TransactionData,
         block::BlockData,
         block::EntryData,
@@ -31,6 +57,13 @@ pub struct PublisherConfig {
     pub batch_size: usize,
     pub metrics: Arc<Metrics>,
     pub validator_id: Option<String>,
+    /// Node to publish through; without one batches are only counted
+    pub gossip: Option<GossipHandle>,
+    /// Where batches wait while the node has no peers; without it they are dropped
+    pub spill: Option<SpillConfig>,
+    /// Send updates of large accounts as patches of their data; they are
+    /// always sent in full when unset
+    pub account_diffs: Option<AccountDiffConfig>,
 }
 
 impl PublisherConfig {
@@ -49,8 +82,26 @@ impl PublisherConfig {
             batch_size,
             metrics,
             validator_id,
+            gossip: None,
+            spill: None,
+            account_diffs: None,
         }
     }
+
+    pub fn with_gossip(mut self, gossip: GossipHandle) -> Self {
+        self.gossip = Some(gossip);
+        self
+    }
+
+    pub fn with_spill(mut self, spill: SpillConfig) -> Self {
+        self.spill = Some(spill);
+        self
+    }
+
+    pub fn with_account_diffs(mut self, account_diffs: AccountDiffConfig) -> Self {
+        self.account_diffs = Some(account_diffs);
+        self
+    }
 }
 
 pub trait Publisher: Send + Sync + std::fmt::Debug + 'static {
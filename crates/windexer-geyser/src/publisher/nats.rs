@@ -0,0 +1,137 @@
+// crates/windexer-geyser/src/publisher/nats.rs
+
+//! NATS JetStream publisher
+//!
+//! This module contains the implementation of a publisher that sends
+//! processed data to NATS JetStream, one message per record, on a subject
+//! per data type. Publishing returns as soon as the client has buffered the
+//! message; the stream's acknowledgement is awaited on the plugin runtime and
+//! counted in the plugin metrics, with at most `max_pending_acks` of them
+//! outstanding at once.
+//!
+//! The client reconnects on its own after the connection drops. Messages
+//! published meanwhile wait in its buffer, and the connection state changes
+//! are logged.
+//!
+//! Messages of accounts, transactions and blocks are published with their
+//! idempotency key as the `Nats-Msg-Id`, so the stream drops the ones sent
+//! again within its duplicate window, see
+//! [`windexer_common::types::idempotency`].
+
+use {
+    super::{encoding::Encode, Publisher},
+    crate::{config::NatsConfig, metrics::Metrics},
+    anyhow::{anyhow, Result},
+    async_nats::{header::NATS_MESSAGE_ID, jetstream, ConnectOptions, Event, HeaderMap},
+    log::{info, warn},
+    std::sync::Arc,
+    tokio::{runtime::Handle, sync::Semaphore},
+    windexer_common::types::{
+        account::AccountData,
+        transaction::TransactionData,
+        block::BlockData,
+        block::EntryData,
+    },
+};
+
+pub struct NatsPublisher {
+    jetstream: jetstream::Context,
+    config: NatsConfig,
+    pending_acks: Arc<Semaphore>,
+    runtime: Handle,
+    metrics: Arc<Metrics>,
+}
+
+impl std::fmt::Debug for NatsPublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NatsPublisher")
+            .field("url", &self.config.url)
+            .field("subjects", &self.config.subjects)
+            .finish_non_exhaustive()
+    }
+}
+
+impl NatsPublisher {
+    /// Must be called on the runtime that awaits the acknowledgements
+    pub async fn connect(config: NatsConfig, metrics: Arc<Metrics>) -> Result<Self> {
+        let options = match &config.credentials_file {
+            Some(path) => ConnectOptions::with_credentials_file(path).await?,
+            None => ConnectOptions::new(),
+        };
+        let client = options
+            .max_reconnects(config.max_reconnects)
+            // Start even while the server is down, publishing once it is up
+            .retry_on_initial_connect()
+            .event_callback(|event| async move {
+                match event {
+                    Event::Connected => info!("Connected to NATS"),
+                    Event::Disconnected => warn!("Disconnected from NATS, reconnecting"),
+                    event => warn!("NATS: {}", event),
+                }
+            })
+            .connect(config.url.as_str())
+            .await
+            .map_err(|e| anyhow!("Failed to connect to NATS at {}: {}", config.url, e))?;
+        info!("Publishing to NATS JetStream at {} as {:?}", config.url, config.format);
+
+        Ok(Self {
+            jetstream: jetstream::new(client),
+            pending_acks: Arc::new(Semaphore::new(config.max_pending_acks)),
+            config,
+            runtime: Handle::current(),
+            metrics,
+        })
+    }
+
+    fn publish<T: Encode>(&self, subject: &str, records: &[T]) -> Result<()> {
+        for record in records {
+            let payload = record.encode(self.config.format)?;
+            let mut headers = HeaderMap::new();
+            if let Some(key) = record.idempotency_key() {
+                headers.insert(NATS_MESSAGE_ID, key.to_string().as_str());
+            }
+            let permit = self.runtime.block_on(self.pending_acks.clone().acquire_owned())?;
+            let published = self.jetstream.publish_with_headers(subject.to_string(), headers, payload.into());
+            let ack = match self.runtime.block_on(published) {
+                Ok(ack) => ack,
+                Err(e) => {
+                    self.metrics.delivery_failures.inc();
+                    return Err(anyhow!("Failed to publish to {}: {}", subject, e));
+                }
+            };
+
+            let metrics = self.metrics.clone();
+            self.runtime.spawn(async move {
+                match ack.await {
+                    Ok(_) => {
+                        metrics.messages_delivered.inc();
+                    }
+                    Err(e) => {
+                        warn!("NATS delivery failed: {}", e);
+                        metrics.delivery_failures.inc();
+                    }
+                }
+                drop(permit);
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Publisher for NatsPublisher {
+    fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        self.publish(&self.config.subjects.accounts, accounts)
+    }
+
+    fn publish_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        self.publish(&self.config.subjects.transactions, transactions)
+    }
+
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        self.publish(&self.config.subjects.blocks, std::slice::from_ref(&block))
+    }
+
+    fn publish_entries(&self, entries: &[EntryData]) -> Result<()> {
+        self.publish(&self.config.subjects.entries, entries)
+    }
+}
@@ -3,63 +3,84 @@
 //! Network data publisher
 //!
 //! This module contains the implementation of a publisher that sends data to the
-//! wIndexer network using libp2p gossipsub.
+//! wIndexer network using libp2p gossipsub. Each batch is sent as one frame
+//! of the common wire format, see [`windexer_common::types::wire`].
+//!
+//! Account batches go to the global accounts topic and, grouped by owner, to
+//! the `accounts/{program_id}` topic of each owning program, so nodes can
+//! follow single programs without the firehose.
+//!
+//! With a spill queue configured, batches published while the node has no
+//! peers are written to disk instead of being lost, and the next publish
+//! after peers return sends them first, oldest first. New batches keep
+//! going to the queue until it is empty so ordering is preserved.
 
 use {
     crate::{
         metrics::Metrics,
-        publisher::{Publisher, PublisherConfig},
+        publisher::{Publisher, PublisherConfig, SpillQueue},
         ShutdownFlag,
     },
     anyhow::Result,
     std::{
-        sync::{
-            Arc,
-            atomic::Ordering,
-        },
+        collections::HashMap,
+        sync::{Arc, Mutex, PoisonError},
     },
+    solana_sdk::pubkey::Pubkey,
     windexer_common::{
         types::{
             account::AccountData,
             transaction::TransactionData,
             block::BlockData,
             block::EntryData,
+            diff::{AccountPatch, DataDiffer},
+            wire::{self, WireRecord},
         },
         crypto::SerializableKeypair,
         config::NodeConfig,
     },
+    windexer_network::{node::GossipHandle, Topic},
     log::{error, warn},
-    serde::{Deserialize, Serialize},
 };
 
-const ACCOUNT_TOPIC: &str = "windexer.accounts";
-const TRANSACTION_TOPIC: &str = "windexer.transactions";
-const BLOCK_TOPIC: &str = "windexer.blocks";
-const ENTRY_TOPIC: &str = "windexer.entries";
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct NetworkMessage<T> {
-    pub validator_id: Option<String>,
-    pub data: T,
-}
-
 #[derive(Clone, Debug)]
 pub struct NetworkPublisher {
     batch_size: usize,
     metrics: Arc<Metrics>,
     validator_id: Option<String>,
+    gossip: Option<GossipHandle>,
+    spill: Option<Arc<SpillQueue>>,
+    /// Last published data of large accounts, when diffs are enabled
+    differ: Option<Arc<Mutex<DataDiffer<Pubkey>>>>,
     shutdown: Arc<ShutdownFlag>,
 }
 
+/// Spilled batches sent per publish, so draining a long outage does not
+/// stall the geyser thread
+const DRAIN_BATCHES: usize = 256;
+
 impl NetworkPublisher {
     pub async fn new(config: PublisherConfig, shutdown: Arc<ShutdownFlag>) -> Result<Self> {
         let env_var = std::env::var("WINDEXER_SKIP_NETWORK").unwrap_or_default();
         warn!("WINDEXER_SKIP_NETWORK value: '{}', is set: {}", env_var, env_var == "1");
         warn!("Creating network publisher with network disabled");
+        let spill = match &config.spill {
+            Some(spill) => {
+                let queue = SpillQueue::open(spill)?;
+                if !queue.is_empty() {
+                    warn!("{} batches spilled by a previous run are waiting in {}", queue.len(), spill.directory);
+                }
+                Some(Arc::new(queue))
+            }
+            None => None,
+        };
         Ok(Self {
             batch_size: config.batch_size,
             metrics: config.metrics,
             validator_id: config.validator_id,
+            gossip: config.gossip,
+            spill,
+            differ: config.account_diffs.map(|config| Arc::new(Mutex::new(DataDiffer::new(config)))),
             shutdown,
         })
     }
@@ -90,6 +111,93 @@ impl NetworkPublisher {
         
         result
     }
+
+    /// Groups account records by the program that owns them, keeping their order
+    fn group_by_owner<T: Clone>(records: &[T], owner_of: impl Fn(&T) -> Pubkey) -> Vec<(Pubkey, Vec<T>)> {
+        let mut groups: Vec<(Pubkey, Vec<T>)> = Vec::new();
+        let mut positions = HashMap::new();
+        for record in records {
+            let owner = owner_of(record);
+            let position = *positions.entry(owner).or_insert_with(|| {
+                groups.push((owner, Vec::new()));
+                groups.len() - 1
+            });
+            groups[position].1.push(record.clone());
+        }
+        groups
+    }
+
+    /// Publishes account records to the accounts topic and their owners' topics
+    fn send_accounts<T>(&self, records: &[T], owner_of: impl Fn(&T) -> Pubkey) -> Result<u64>
+    where
+        T: Clone + WireRecord,
+    {
+        if records.is_empty() {
+            return Ok(0);
+        }
+        let mut batches_count = self.send(Topic::Accounts, records)?;
+        for (owner, owned) in Self::group_by_owner(records, owner_of) {
+            batches_count += self.send(Topic::for_owner(&owner), &owned)?;
+        }
+        Ok(batches_count)
+    }
+
+    /// Splits accounts into those published in full and patches of the rest
+    fn diff_accounts(&self, differ: &Mutex<DataDiffer<Pubkey>>, accounts: &[AccountData]) -> (Vec<AccountData>, Vec<AccountPatch>) {
+        let mut differ = differ.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut full = Vec::new();
+        let mut patches = Vec::new();
+        for account in accounts {
+            match differ.update(account.pubkey, account.write_version, &account.data) {
+                Some(diff) => patches.push(AccountPatch::new(account, diff)),
+                None => full.push(account.clone()),
+            }
+        }
+        (full, patches)
+    }
+
+    /// Publishes `data` in batches to `topic`, returning the number of batches
+    fn send<T>(&self, topic: Topic, data: &[T]) -> Result<u64>
+    where
+        T: Clone + WireRecord,
+    {
+        let batches = Self::batch_data(data, self.batch_size);
+        let batches_count = batches.len() as u64;
+
+        if let Some(gossip) = &self.gossip {
+            for batch in batches {
+                self.deliver(gossip, topic, wire::encode(self.validator_id.as_deref(), &batch)?)?;
+            }
+        }
+        Ok(batches_count)
+    }
+
+    /// Publishes one encoded batch, or spills it while the node has no peers
+    /// or older batches are still waiting
+    fn deliver(&self, gossip: &GossipHandle, topic: Topic, data: Vec<u8>) -> Result<()> {
+        let Some(spill) = &self.spill else {
+            return gossip.publish(topic, data);
+        };
+
+        if gossip.is_connected() && !spill.is_empty() {
+            let drained = spill.drain(DRAIN_BATCHES, |topic, data| gossip.publish(topic, data))?;
+            self.metrics.spilled_batches_drained.inc_by(drained as u64);
+        }
+        if gossip.is_connected() && spill.is_empty() {
+            return gossip.publish(topic, data);
+        }
+
+        let (kept, dropped) = spill.push(topic, data)?;
+        if kept {
+            self.metrics.batches_spilled.inc();
+        }
+        let dropped = dropped + u64::from(!kept);
+        if dropped > 0 {
+            warn!("Spill queue is full; dropped {} batches", dropped);
+            self.metrics.spilled_batches_dropped.inc_by(dropped);
+        }
+        Ok(())
+    }
 }
 
 impl Publisher for NetworkPublisher {
@@ -98,10 +206,17 @@ impl Publisher for NetworkPublisher {
             return Ok(());
         }
         
-        let batches = Self::batch_data(accounts, self.batch_size);
-        let batches_count = batches.len() as u64;
-        
-        self.metrics.account_batches_published.fetch_add(batches_count, Ordering::Relaxed);
+        let Some(differ) = &self.differ else {
+            let batches_count = self.send_accounts(accounts, |account| account.owner)?;
+            self.metrics.account_batches_published.inc_by(batches_count);
+            return Ok(());
+        };
+
+        let (full, patches) = self.diff_accounts(differ, accounts);
+        let batches_count = self.send_accounts(&full, |account| account.owner)?
+            + self.send_accounts(&patches, |patch| patch.owner)?;
+        self.metrics.account_batches_published.inc_by(batches_count);
+        self.metrics.account_patches_published.inc_by(patches.len() as u64);
         Ok(())
     }
     
@@ -110,15 +225,15 @@ impl Publisher for NetworkPublisher {
             return Ok(());
         }
         
-        let batches = Self::batch_data(transactions, self.batch_size);
-        let batches_count = batches.len() as u64;
+        let batches_count = self.send(Topic::Transactions, transactions)?;
         
-        self.metrics.transaction_batches_published.fetch_add(batches_count, Ordering::Relaxed);
+        self.metrics.transaction_batches_published.inc_by(batches_count);
         Ok(())
     }
     
-    fn publish_block(&self, _block: BlockData) -> Result<()> {
-        self.metrics.blocks_published.fetch_add(1, Ordering::Relaxed);
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        self.send(Topic::Blocks, &[block])?;
+        self.metrics.blocks_published.inc();
         Ok(())
     }
     
@@ -127,10 +242,9 @@ impl Publisher for NetworkPublisher {
             return Ok(());
         }
         
-        let batches = Self::batch_data(entries, self.batch_size);
-        let batches_count = batches.len() as u64;
+        let batches_count = self.send(Topic::Entries, entries)?;
         
-        self.metrics.entry_batches_published.fetch_add(batches_count, Ordering::Relaxed);
+        self.metrics.entry_batches_published.inc_by(batches_count);
         Ok(())
     }
 }
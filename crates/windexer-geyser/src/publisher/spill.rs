@@ -0,0 +1,214 @@
+// crates/windexer-geyser/src/publisher/spill.rs
+
+//! On-disk spill queue for outgoing batches
+//!
+//! Each spilled batch is one file in the spill directory, named by its
+//! sequence number, so the queue survives a restart and is drained oldest
+//! first. The total size is capped; what gives way when it is full is the
+//! configured [`DropPolicy`].
+
+use {
+    crate::config::{DropPolicy, SpillConfig},
+    anyhow::{Context, Result},
+    log::warn,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::VecDeque,
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::Mutex,
+    },
+    windexer_network::Topic,
+};
+
+const EXTENSION: &str = "batch";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SpilledBatch {
+    topic: String,
+    data: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct SpillState {
+    /// Sequence number and size of each spilled batch, oldest first
+    batches: VecDeque<(u64, u64)>,
+    bytes: u64,
+    next: u64,
+}
+
+#[derive(Debug)]
+pub struct SpillQueue {
+    directory: PathBuf,
+    max_bytes: u64,
+    drop_policy: DropPolicy,
+    state: Mutex<SpillState>,
+}
+
+impl SpillQueue {
+    /// Opens the queue in the configured directory, picking up batches left
+    /// by a previous run
+    pub fn open(config: &SpillConfig) -> Result<Self> {
+        let directory = PathBuf::from(&config.directory);
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Failed to create spill directory {}", directory.display()))?;
+
+        let mut batches = Vec::new();
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            let Some(seq) = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| stem.parse().ok()) else {
+                continue;
+            };
+            batches.push((seq, fs::metadata(&path)?.len()));
+        }
+        batches.sort();
+
+        let state = SpillState {
+            bytes: batches.iter().map(|(_, len)| len).sum(),
+            next: batches.last().map(|(seq, _)| seq + 1).unwrap_or(0),
+            batches: batches.into(),
+        };
+        Ok(Self {
+            directory,
+            max_bytes: config.max_size_mb * 1024 * 1024,
+            drop_policy: config.drop_policy,
+            state: Mutex::new(state),
+        })
+    }
+
+    fn path(&self, seq: u64) -> PathBuf {
+        self.directory.join(format!("{:020}.{}", seq, EXTENSION))
+    }
+
+    fn remove(&self, seq: u64) {
+        if let Err(e) = fs::remove_file(self.path(seq)) {
+            warn!("Failed to remove spilled batch {}: {}", seq, e);
+        }
+    }
+
+    /// Spills a batch, returning `false` if it was dropped instead; with
+    /// [`DropPolicy::DropOldest`] the oldest batches are discarded to make
+    /// room and their count is returned alongside
+    pub fn push(&self, topic: Topic, data: Vec<u8>) -> Result<(bool, u64)> {
+        self.push_tagged(&topic.to_string(), data)
+    }
+
+    /// Like [`SpillQueue::push`], for batches that are not bound for a topic
+    pub fn push_tagged(&self, tag: &str, data: Vec<u8>) -> Result<(bool, u64)> {
+        let encoded = bincode::serialize(&SpilledBatch { topic: tag.to_string(), data })?;
+        let len = encoded.len() as u64;
+        let mut state = self.state.lock().unwrap();
+
+        if len > self.max_bytes {
+            return Ok((false, 0));
+        }
+        let mut dropped = 0;
+        while state.bytes + len > self.max_bytes {
+            match self.drop_policy {
+                DropPolicy::DropNewest => return Ok((false, dropped)),
+                DropPolicy::DropOldest => {
+                    let Some((seq, size)) = state.batches.pop_front() else { break };
+                    self.remove(seq);
+                    state.bytes -= size;
+                    dropped += 1;
+                }
+            }
+        }
+
+        let seq = state.next;
+        write_atomically(&self.path(seq), &encoded)?;
+        state.next += 1;
+        state.batches.push_back((seq, len));
+        state.bytes += len;
+        Ok((true, dropped))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state.lock().unwrap().batches.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().batches.len()
+    }
+
+    /// Hands up to `max` of the oldest batches to `send`, removing each once
+    /// sent, and returns how many were; an error from `send` stops the drain
+    /// and leaves that batch queued
+    pub fn drain(&self, max: usize, mut send: impl FnMut(Topic, Vec<u8>) -> Result<()>) -> Result<usize> {
+        self.drain_tagged(max, |tag, data| match Topic::from_str(&tag) {
+            Ok(topic) => send(topic, data),
+            Err(e) => {
+                warn!("Discarding spilled batch for unknown topic {}: {}", tag, e);
+                Ok(())
+            }
+        })
+    }
+
+    /// Like [`SpillQueue::drain`], handing over the tag each batch was pushed with
+    pub fn drain_tagged(&self, max: usize, mut send: impl FnMut(String, Vec<u8>) -> Result<()>) -> Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        let mut sent = 0;
+        while sent < max {
+            let Some(&(seq, size)) = state.batches.front() else { break };
+            let path = self.path(seq);
+            match read_batch(&path) {
+                Ok((tag, data)) => send(tag, data)?,
+                Err(e) => warn!("Discarding unreadable spilled batch {}: {}", path.display(), e),
+            }
+            state.batches.pop_front();
+            state.bytes -= size;
+            self.remove(seq);
+            sent += 1;
+        }
+        Ok(sent)
+    }
+}
+
+fn read_batch(path: &Path) -> Result<(String, Vec<u8>)> {
+    let batch: SpilledBatch = bincode::deserialize(&fs::read(path)?)?;
+    Ok((batch.topic, batch.data))
+}
+
+/// Writes to a temporary file first so a crash never leaves a partial batch
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_and_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SpillConfig {
+            directory: dir.path().to_string_lossy().to_string(),
+            max_size_mb: 1,
+            drop_policy: DropPolicy::DropOldest,
+        };
+        let queue = SpillQueue::open(&config).unwrap();
+        let batch = vec![0u8; 400 * 1024];
+        assert_eq!(queue.push(Topic::Accounts, batch.clone()).unwrap(), (true, 0));
+        assert_eq!(queue.push(Topic::Blocks, batch.clone()).unwrap(), (true, 0));
+        assert_eq!(queue.push(Topic::Entries, batch).unwrap(), (true, 1));
+        drop(queue);
+
+        let queue = SpillQueue::open(&config).unwrap();
+        assert_eq!(queue.len(), 2);
+        let mut topics = Vec::new();
+        let sent = queue.drain(usize::MAX, |topic, _| {
+            topics.push(topic);
+            Ok(())
+        }).unwrap();
+        assert_eq!(sent, 2);
+        assert_eq!(topics, vec![Topic::Blocks, Topic::Entries]);
+        assert!(queue.is_empty());
+    }
+}
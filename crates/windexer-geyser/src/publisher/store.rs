@@ -0,0 +1,84 @@
+// crates/windexer-geyser/src/publisher/store.rs
+
+//! Store publisher
+//!
+//! This module contains the implementation of a publisher that writes
+//! processed data straight into a local windexer-store backend, so a single
+//! node deployment can index without running the p2p stack.
+//!
+//! Publishers are called from the processor worker threads, which are not
+//! part of the plugin's runtime, so each write blocks its worker on the
+//! runtime until the backend has it.
+
+use {
+    super::Publisher,
+    anyhow::Result,
+    std::sync::Arc,
+    tokio::runtime::Handle,
+    windexer_common::{
+        config::storage::StorageConfig,
+        dlq::DeadLetterQueue,
+        metrics::MetricsRegistry,
+        types::{
+            account::AccountData,
+            transaction::TransactionData,
+            block::BlockData,
+            block::EntryData,
+        },
+    },
+    windexer_store::{factory::WindexerStorageFactory, traits::{Storage, StorageFactory}},
+};
+
+pub struct StorePublisher {
+    storage: Arc<dyn Storage>,
+    runtime: Handle,
+}
+
+impl std::fmt::Debug for StorePublisher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StorePublisher").finish_non_exhaustive()
+    }
+}
+
+impl StorePublisher {
+    pub fn new(storage: Arc<dyn Storage>, runtime: Handle) -> Self {
+        Self { storage, runtime }
+    }
+
+    /// Opens the backend selected in the plugin's `storage` section,
+    /// registering its metrics with `registry`; writes it fails to flush
+    /// are kept in `dead_letters`
+    pub async fn open(
+        config: &StorageConfig,
+        registry: &MetricsRegistry,
+        dead_letters: Option<Arc<DeadLetterQueue>>,
+    ) -> Result<Self> {
+        let factory = WindexerStorageFactory::from_geyser_config(config)?;
+        let factory = match dead_letters {
+            Some(queue) => factory.with_dead_letters(queue),
+            None => factory,
+        };
+        registry.register(factory.metrics())?;
+        let storage = factory.create_storage().await?;
+        Ok(Self::new(storage, Handle::current()))
+    }
+}
+
+impl Publisher for StorePublisher {
+    fn publish_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        self.runtime.block_on(self.storage.store_accounts(accounts.to_vec()))
+    }
+
+    fn publish_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        self.runtime.block_on(self.storage.store_transactions(transactions.to_vec()))
+    }
+
+    fn publish_block(&self, block: BlockData) -> Result<()> {
+        self.runtime.block_on(self.storage.store_block(block))
+    }
+
+    fn publish_entries(&self, _entries: &[EntryData]) -> Result<()> {
+        // Stores keep no entries of their own; blocks carry theirs
+        Ok(())
+    }
+}
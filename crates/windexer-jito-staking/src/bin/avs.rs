@@ -102,6 +102,7 @@ async fn main() -> Result<()> {
         storage_space: 1024 * 1024, // 1 MB
         consensus_threshold: 0.66,
         stake_threshold: 1_000_000_000, // 1 SOL in lamports
+        oracle: Default::default(),
     };
     
     // Create Cambrian service
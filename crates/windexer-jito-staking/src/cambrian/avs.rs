@@ -1,6 +1,6 @@
 //! AVS (Actively Validated Services) manager implementation using Cambrian CLI
 
-use super::{CambrianConfig, PoAState};
+use super::{CambrianConfig, PayloadManager, PoAState};
 use anyhow::{Result, anyhow};
 use solana_sdk::{
     pubkey::Pubkey,
@@ -9,6 +9,7 @@ use solana_sdk::{
 use std::{
     process::Command,
     str::FromStr,
+    sync::Arc,
 };
 use tracing::{info, warn, error};
 
@@ -68,47 +69,66 @@ pub async fn start_avs(&self) -> Result<()> {
         .expect("Failed to parse address");
     
     // Set up a simple status endpoint and payload execution endpoint
-    let make_service = hyper::service::make_service_fn(|_conn| async {
-        Ok::<_, hyper::Error>(hyper::service::service_fn(|req| async move {
-            let uri = req.uri().path();
+    let payloads = Arc::new(PayloadManager::new(self.config.clone()));
+    let make_service = hyper::service::make_service_fn(move |_conn| {
+        let payloads = payloads.clone();
+        async move {
+            Ok::<_, hyper::Error>(hyper::service::service_fn(move |req| {
+                let payloads = payloads.clone();
+                async move {
+                    let uri = req.uri().path();
             
-            match uri {
-                "/api/status" => {
-                    // Status endpoint
-                    Ok::<_, hyper::Error>(hyper::Response::builder()
-                        .status(200)
-                        .header("Content-Type", "application/json")
-                        .body(hyper::Body::from("{\"status\":\"ok\"}"))
-                        .unwrap())
-                },
-                "/api/payload/run" => {
-                    // Mock payload execution
-                    // Parse the request body
-                    let whole_body = hyper::body::to_bytes(req.into_body()).await?;
-                    let payload: serde_json::Value = serde_json::from_slice(&whole_body)
-                        .unwrap_or(serde_json::json!({}));
+                    match uri {
+                        "/api/status" => {
+                            // Status endpoint
+                            Ok::<_, hyper::Error>(hyper::Response::builder()
+                                .status(200)
+                                .header("Content-Type", "application/json")
+                                .body(hyper::Body::from("{\"status\":\"ok\"}"))
+                                .unwrap())
+                        },
+                        "/api/payload/run" => {
+                            // Run the payload for another operator verifying its result
+                            let whole_body = hyper::body::to_bytes(req.into_body()).await?;
+                            let payload: serde_json::Value = serde_json::from_slice(&whole_body)
+                                .unwrap_or(serde_json::json!({}));
                     
-                    // Log the payload request
-                    info!("Received payload execution request: {:?}", payload);
+                            // Log the payload request
+                            info!("Received payload execution request: {:?}", payload);
                     
-                    // Return a mock success response
-                    Ok(hyper::Response::builder()
-                        .status(200)
-                        .header("Content-Type", "application/json")
-                        .body(hyper::Body::from(
-                            "{\"status\":\"success\",\"signature\":\"mock-signature-12345\"}"
-                        ))
-                        .unwrap())
-                },
-                _ => {
-                    // 404 for any other path
-                    Ok(hyper::Response::builder()
-                        .status(404)
-                        .body(hyper::Body::from("Not found"))
-                        .unwrap())
+                            let run = match (payload["image"].as_str(), payload["poa"].as_str().map(Pubkey::from_str)) {
+                                (Some(image), Some(Ok(poa))) => payloads.run_payload(image, &poa).await,
+                                _ => Err(anyhow!("Expected an image and a PoA pubkey")),
+                            };
+                            let (status, body) = match run {
+                                Ok(result) => (200, serde_json::json!({
+                                    "status": "success",
+                                    "operator": result.operator.to_string(),
+                                    "proposal": result.proposal,
+                                    "hash": result.hash.to_string(),
+                                })),
+                                Err(e) => {
+                                    error!("Failed to run payload: {}", e);
+                                    (500, serde_json::json!({"status": "error", "error": e.to_string()}))
+                                }
+                            };
+                            Ok(hyper::Response::builder()
+                                .status(status)
+                                .header("Content-Type", "application/json")
+                                .body(hyper::Body::from(body.to_string()))
+                                .unwrap())
+                        },
+                        _ => {
+                            // 404 for any other path
+                            Ok(hyper::Response::builder()
+                                .status(404)
+                                .body(hyper::Body::from("Not found"))
+                                .unwrap())
+                        }
+                    }
                 }
-            }
-        }))
+            }))
+        }
     });
     
     let server = hyper::Server::bind(&addr).serve(make_service);
@@ -160,6 +180,23 @@ pub async fn start_avs(&self) -> Result<()> {
         info!("Proposal submitted with signature: {}", signature);
         Ok(signature)
     }
+
+    /// Submit `proposal` to the PoA program through a temporary file named
+    /// after `name`, which is removed afterwards
+    pub async fn submit_proposal_data(
+        &self,
+        name: &str,
+        proposal: &[u8],
+        poa_state: &PoAState,
+    ) -> Result<Signature> {
+        let proposal_file = std::env::temp_dir().join(format!("windexer-{}.json", name));
+        tokio::fs::write(&proposal_file, proposal).await?;
+        let submitted = self.submit_proposal(&proposal_file.to_string_lossy(), poa_state).await;
+        if let Err(e) = tokio::fs::remove_file(&proposal_file).await {
+            warn!("Failed to remove proposal file {:?}: {}", proposal_file, e);
+        }
+        submitted
+    }
 }
 
 // Helper function to extract PoA pubkey from Cambrian CLI output
@@ -2,6 +2,10 @@
 //! 
 //! This module implements the Cambrian Actively Validated Services (AVS)
 //! and Proof-of-Authority (PoA) functionality for wIndexer.
+//!
+//! A proposal is only submitted once a quorum of operators ran its payload
+//! and produced the same result, see [`PayloadVerifier`]. Slots operators
+//! reached consensus on are posted with [`CambrianService::post_attestation`].
 
 use solana_sdk::{
     commitment_config::CommitmentConfig,
@@ -16,21 +20,29 @@ use std::{
     sync::Arc,
     time::Duration,
 };
-use tokio::sync::RwLock;
+use tokio::{sync::RwLock, task::JoinHandle};
 use anyhow::Result;
 use tracing::{info, warn, error};
+use windexer_common::{shutdown::ShutdownToken, types::SlotAttestation};
 
 mod avs;
 mod poa;
 mod payload;
 mod operator;
 mod oracle;
+mod verification;
 
 pub use avs::AvsManager;
 pub use operator::OperatorManager;
 pub use poa::{PoAState, ProposalInstructionData};
-pub use payload::PayloadManager;
-pub use oracle::OracleManager;
+pub use payload::{PayloadManager, PayloadResult, PAYLOAD_TIMEOUT};
+pub use verification::{PayloadExecutor, PayloadVerifier, RemoteExecutor, VerifiedPayload};
+pub use oracle::{
+    median, HttpSource, OracleConfig, OracleFeed, OracleManager, OracleMetrics, OracleReport, OracleSource,
+    OracleUpdate, PoaProposer, UpdateProposer,
+};
+#[cfg(feature = "store")]
+pub use oracle::IndexedAccountSource;
 
 /// Configuration for Cambrian integration
 #[derive(Debug, Clone)]
@@ -57,6 +69,8 @@ pub struct CambrianConfig {
     pub consensus_threshold: f64,
     /// Stake threshold
     pub stake_threshold: u64,
+    /// Oracle rounds
+    pub oracle: OracleConfig,
 }
 
 impl Default for CambrianConfig {
@@ -73,6 +87,7 @@ impl Default for CambrianConfig {
             storage_space: 1024 * 1024, // 1 MB
             consensus_threshold: 0.66,
             stake_threshold: 1_000_000_000, // 1 SOL
+            oracle: OracleConfig::default(),
         }
     }
 }
@@ -85,6 +100,9 @@ pub struct CambrianService {
     payload_manager: Arc<PayloadManager>,
     oracle_manager: Arc<OracleManager>,
     poa_state: Arc<RwLock<Option<PoAState>>>,
+    /// Other operators that run each payload to verify its result
+    executors: RwLock<Vec<Arc<dyn PayloadExecutor>>>,
+    verifier: PayloadVerifier,
 }
 
 impl CambrianService {
@@ -95,6 +113,7 @@ impl CambrianService {
         let operator_manager = Arc::new(RwLock::new(OperatorManager::new(config.clone())));
         let payload_manager = Arc::new(PayloadManager::new(config.clone()));
         let oracle_manager = Arc::new(OracleManager::new(config.clone()));
+        let verifier = PayloadVerifier::new(config.consensus_threshold);
 
         Self {
             config,
@@ -103,6 +122,8 @@ impl CambrianService {
             payload_manager,
             oracle_manager,
             poa_state,
+            executors: RwLock::new(Vec::new()),
+            verifier,
         }
     }
 
@@ -144,7 +165,28 @@ impl CambrianService {
         Ok(())
     }
 
+    /// Oracle feeds, reports and metrics
+    pub fn oracle_manager(&self) -> Arc<OracleManager> {
+        self.oracle_manager.clone()
+    }
+
+    /// Propose the oracle feeds to the PoA program every round, reporting
+    /// this node's values as `operator`
+    pub fn start_oracle(&self, operator: Pubkey, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let proposer = Arc::new(PoaProposer::new(self.avs_manager.clone(), self.poa_state.clone()));
+        self.oracle_manager.spawn(operator, proposer, shutdown)
+    }
+
+    /// Have another operator run every payload before its proposal is
+    /// submitted
+    pub async fn add_executor(&self, executor: Arc<dyn PayloadExecutor>) {
+        self.executors.write().await.push(executor);
+    }
+
     /// Execute a proposal
+    ///
+    /// The payload runs here and on every added executor; the proposal is
+    /// only submitted if enough of them produced the same one.
     pub async fn execute_proposal(&self, payload_image: &str) -> Result<Signature> {
         info!("Executing proposal with payload: {}", payload_image);
         
@@ -153,15 +195,60 @@ impl CambrianService {
             anyhow::anyhow!("PoA state not initialized")
         })?;
         
-        // Run the payload
-        let proposal_file = self.payload_manager.run_payload(payload_image, &poa_state).await?;
-        
-        // Submit the proposal to PoA program
-        let signature = self.avs_manager.submit_proposal(&proposal_file, &poa_state).await?;
+        // Run the payload on every executor
+        let verified = self.run_verified(payload_image, &poa_state.pubkey).await?;
+        info!(
+            "{} operators agree on proposal {} of {}",
+            verified.agreeing.len(), verified.hash, payload_image
+        );
+        for operator in &verified.divergent {
+            warn!("Operator {} produced a divergent result for {}", operator, payload_image);
+        }
+
+        // Submit the agreed proposal to PoA program
+        let signature = self.avs_manager
+            .submit_proposal_data(&format!("proposal-{}", verified.hash), verified.proposal.as_bytes(), &poa_state)
+            .await?;
         
         info!("Proposal executed with signature: {}", signature);
         Ok(signature)
     }
+
+    /// Post the attestation of a slot operators reached consensus on to the
+    /// PoA program, with the signed votes behind it
+    pub async fn post_attestation(&self, attestation: &SlotAttestation) -> Result<Signature> {
+        let poa_state = self.poa_state.read().await.clone().ok_or_else(|| {
+            anyhow::anyhow!("PoA state not initialized")
+        })?;
+        let signature = self.avs_manager
+            .submit_proposal_data(
+                &format!("attestation-{}", attestation.slot),
+                &serde_json::to_vec(attestation)?,
+                &poa_state,
+            )
+            .await?;
+        info!(
+            "Posted attestation of slot {} with {} of {} stake: {}",
+            attestation.slot, attestation.stake, attestation.total_stake, signature
+        );
+        Ok(signature)
+    }
+
+    /// Run `payload_image` here and on every executor and verify the results
+    async fn run_verified(&self, payload_image: &str, poa: &Pubkey) -> Result<VerifiedPayload> {
+        let mut executors: Vec<Arc<dyn PayloadExecutor>> = vec![self.payload_manager.clone()];
+        executors.extend(self.executors.read().await.iter().cloned());
+
+        let runs = executors.iter().map(|executor| executor.execute(payload_image, poa));
+        let mut results = Vec::new();
+        for (executor, run) in executors.iter().zip(futures::future::join_all(runs).await) {
+            match run {
+                Ok(result) => results.push(result),
+                Err(e) => error!("{} failed to run payload {}: {}", executor.name(), payload_image, e),
+            }
+        }
+        self.verifier.verify(&results, executors.len())
+    }
 }
 
 // Helper to read a keypair from file
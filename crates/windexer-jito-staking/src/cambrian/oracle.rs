@@ -1,10 +1,39 @@
 //! Oracle manager for Cambrian integration
+//!
+//! Each [`OracleFeed`] reads its value from one or more [`OracleSource`]s:
+//! [`HttpSource`] fetches it from a JSON API and, with the `store` feature,
+//! `IndexedAccountSource` decodes it from an account the indexer stored.
+//! Every round this operator reports the median of its sources, other
+//! operators report theirs through [`OracleManager::submit_report`], and once
+//! `min_reports` operators reported, the median of the reports is proposed to
+//! the PoA program by an [`UpdateProposer`].
+//!
+//! | metric                                     | type      | labels           |
+//! |--------------------------------------------|-----------|------------------|
+//! | `windexer_oracle_updates_total`            | counter   | `feed`, `status` |
+//! | `windexer_oracle_update_latency_seconds`   | histogram | `feed`           |
+//! | `windexer_oracle_deviation_ratio`          | histogram | `feed`           |
+//! | `windexer_oracle_source_errors_total`      | counter   | `source`         |
 
-use super::CambrianConfig;
-use anyhow::Result;
-use std::collections::HashMap;
-use tokio::sync::RwLock;
-use tracing::info;
+use super::{AvsManager, CambrianConfig, PoAState};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{sync::RwLock, task::JoinHandle};
+use tracing::{info, warn, error};
+use windexer_common::{
+    metrics::{self, Collector, HistogramVec, IntCounterVec, MetricSet},
+    shutdown::{self, ShutdownToken},
+};
+
+const SUBSYSTEM: &str = "oracle";
 
 /// Oracle data
 #[derive(Debug, Clone)]
@@ -15,10 +44,254 @@ pub struct OracleData {
     pub timestamp: i64,
 }
 
+/// Oracle rounds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OracleConfig {
+    /// Seconds between rounds
+    pub update_interval_secs: u64,
+    /// Operator reports a feed needs before its median is proposed
+    pub min_reports: usize,
+    /// Relative distance from the median past which a report is an outlier
+    pub max_deviation: f64,
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            update_interval_secs: 60,
+            min_reports: 1,
+            max_deviation: 0.05,
+        }
+    }
+}
+
+/// Where a feed's value comes from
+#[async_trait]
+pub trait OracleSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn fetch(&self) -> Result<f64>;
+}
+
+/// Fetches a number from a JSON API
+pub struct HttpSource {
+    name: String,
+    url: String,
+    /// JSON pointer to the value, like `/data/price`
+    pointer: String,
+    client: reqwest::Client,
+}
+
+impl HttpSource {
+    pub fn new(name: impl Into<String>, url: impl Into<String>, pointer: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            url: url.into(),
+            pointer: pointer.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl OracleSource for HttpSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<f64> {
+        let body: Value = self.client
+            .get(&self.url)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let value = body
+            .pointer(&self.pointer)
+            .ok_or_else(|| anyhow!("{} has no value at {}", self.url, self.pointer))?;
+        // APIs quote prices to keep their precision
+        value
+            .as_f64()
+            .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+            .ok_or_else(|| anyhow!("{} at {} of {} is not a number", value, self.pointer, self.url))
+    }
+}
+
+/// Decodes a little-endian `u64` at `offset` of an account the indexer
+/// stored, scaled down by `decimals`
+#[cfg(feature = "store")]
+pub struct IndexedAccountSource {
+    name: String,
+    storage: Arc<dyn windexer_store::traits::Storage>,
+    account: Pubkey,
+    offset: usize,
+    decimals: u32,
+}
+
+#[cfg(feature = "store")]
+impl IndexedAccountSource {
+    pub fn new(
+        name: impl Into<String>,
+        storage: Arc<dyn windexer_store::traits::Storage>,
+        account: Pubkey,
+        offset: usize,
+        decimals: u32,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            storage,
+            account,
+            offset,
+            decimals,
+        }
+    }
+}
+
+#[cfg(feature = "store")]
+#[async_trait]
+impl OracleSource for IndexedAccountSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn fetch(&self) -> Result<f64> {
+        let account = self.storage
+            .get_account(&self.account.to_string())
+            .await?
+            .ok_or_else(|| anyhow!("Account {} is not indexed", self.account))?;
+        let bytes = account.data
+            .get(self.offset..self.offset + 8)
+            .ok_or_else(|| anyhow!("Account {} has no u64 at offset {}", self.account, self.offset))?;
+        let raw = u64::from_le_bytes(bytes.try_into()?);
+        Ok(raw as f64 / 10f64.powi(self.decimals as i32))
+    }
+}
+
+/// A value proposed under `key`, read from `sources`
+pub struct OracleFeed {
+    pub key: String,
+    pub sources: Vec<Arc<dyn OracleSource>>,
+}
+
+/// One operator's value for a feed in the current round
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OracleReport {
+    pub operator: Pubkey,
+    pub value: f64,
+    pub timestamp: i64,
+}
+
+/// The median of a round's reports, as proposed to the PoA program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OracleUpdate {
+    pub key: String,
+    pub value: f64,
+    pub reports: usize,
+    /// Largest distance of a report from the median, relative to it
+    pub deviation: f64,
+    /// Operators whose report was further than `max_deviation` from the median
+    pub outliers: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// Proposes aggregated updates on chain
+#[async_trait]
+pub trait UpdateProposer: Send + Sync {
+    async fn propose(&self, update: &OracleUpdate) -> Result<()>;
+}
+
+/// Submits each update as a proposal to the PoA program with Cambrian CLI
+pub struct PoaProposer {
+    avs: Arc<AvsManager>,
+    poa_state: Arc<RwLock<Option<PoAState>>>,
+}
+
+impl PoaProposer {
+    pub fn new(avs: Arc<AvsManager>, poa_state: Arc<RwLock<Option<PoAState>>>) -> Self {
+        Self { avs, poa_state }
+    }
+}
+
+#[async_trait]
+impl UpdateProposer for PoaProposer {
+    async fn propose(&self, update: &OracleUpdate) -> Result<()> {
+        let poa_state = self.poa_state.read().await.clone()
+            .ok_or_else(|| anyhow!("PoA state not initialized"))?;
+        let name = format!("oracle-{}-{}", update.key, update.timestamp);
+        self.avs.submit_proposal_data(&name, &serde_json::to_vec(update)?, &poa_state).await?;
+        Ok(())
+    }
+}
+
+/// Median of `values`, `None` when there are none
+pub fn median(values: &[f64]) -> Option<f64> {
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    match values.len() {
+        0 => None,
+        len if len % 2 == 0 => Some((values[middle - 1] + values[middle]) / 2.0),
+        _ => Some(values[middle]),
+    }
+}
+
+pub struct OracleMetrics {
+    updates: IntCounterVec,
+    latency: HistogramVec,
+    deviation: HistogramVec,
+    source_errors: IntCounterVec,
+}
+
+impl OracleMetrics {
+    fn new() -> Self {
+        Self {
+            updates: metrics::counter_vec(
+                SUBSYSTEM,
+                "updates_total",
+                "Oracle rounds per feed, by whether an update was proposed",
+                &["feed", "status"],
+            ),
+            latency: metrics::histogram_vec(
+                SUBSYSTEM,
+                "update_latency_seconds",
+                "Time from the start of a round to its update being proposed",
+                &["feed"],
+                vec![0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0],
+            ),
+            deviation: metrics::histogram_vec(
+                SUBSYSTEM,
+                "deviation_ratio",
+                "Largest distance of an operator report from the median, relative to it",
+                &["feed"],
+                vec![0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5],
+            ),
+            source_errors: metrics::counter_vec(
+                SUBSYSTEM,
+                "source_errors_total",
+                "Failed fetches per oracle source",
+                &["source"],
+            ),
+        }
+    }
+}
+
+impl MetricSet for OracleMetrics {
+    fn collectors(&self) -> Vec<&dyn Collector> {
+        vec![&self.updates, &self.latency, &self.deviation, &self.source_errors]
+    }
+}
+
 /// Oracle manager
 pub struct OracleManager {
     config: CambrianConfig,
     data: RwLock<HashMap<String, OracleData>>,
+    feeds: RwLock<Vec<Arc<OracleFeed>>>,
+    /// Reports of the current round, by feed and operator
+    reports: RwLock<HashMap<String, HashMap<Pubkey, OracleReport>>>,
+    metrics: Arc<OracleMetrics>,
 }
 
 impl OracleManager {
@@ -27,40 +300,201 @@ impl OracleManager {
         Self {
             config,
             data: RwLock::new(HashMap::new()),
+            feeds: RwLock::new(Vec::new()),
+            reports: RwLock::new(HashMap::new()),
+            metrics: Arc::new(OracleMetrics::new()),
         }
     }
-    
+
+    /// Metrics to register with the process's registry
+    pub fn metrics(&self) -> Arc<OracleMetrics> {
+        self.metrics.clone()
+    }
+
     /// Update oracle data
     pub async fn update_data(&self, key: &str, content: &str) -> Result<()> {
         info!("Updating oracle data for key: {}", key);
-        
+
         let oracle_data = OracleData {
             content: content.to_string(),
             timestamp: chrono::Utc::now().timestamp(),
         };
-        
+
         let mut data = self.data.write().await;
         data.insert(key.to_string(), oracle_data);
-        
+
         Ok(())
     }
-    
+
     /// Get oracle data
     pub async fn get_data(&self, key: &str) -> Option<OracleData> {
         let data = self.data.read().await;
         data.get(key).cloned()
     }
-    
+
+    /// Propose `feed` every round from now on
+    pub async fn add_feed(&self, feed: OracleFeed) {
+        info!("Added oracle feed {} with {} sources", feed.key, feed.sources.len());
+        self.feeds.write().await.push(Arc::new(feed));
+    }
+
+    /// Record an operator's value for `key` in the current round, replacing
+    /// any earlier one
+    pub async fn submit_report(&self, key: &str, report: OracleReport) {
+        self.reports
+            .write()
+            .await
+            .entry(key.to_string())
+            .or_default()
+            .insert(report.operator, report);
+    }
+
+    /// Report the median of each feed's sources as `operator`
+    pub async fn collect(&self, operator: Pubkey) {
+        let feeds = self.feeds.read().await.clone();
+        for feed in feeds {
+            let mut values = Vec::new();
+            for source in &feed.sources {
+                match source.fetch().await {
+                    Ok(value) => values.push(value),
+                    Err(e) => {
+                        warn!("Oracle source {} of {} failed: {}", source.name(), feed.key, e);
+                        self.metrics.source_errors.with_label_values(&[source.name()]).inc();
+                    }
+                }
+            }
+            match median(&values) {
+                Some(value) => {
+                    let report = OracleReport { operator, value, timestamp: chrono::Utc::now().timestamp() };
+                    self.submit_report(&feed.key, report).await;
+                }
+                None => warn!("No source of oracle feed {} answered", feed.key),
+            }
+        }
+    }
+
+    /// Close the round of `key`, returning the median of its reports if
+    /// enough operators reported; otherwise they are kept for the next round
+    pub async fn aggregate(&self, key: &str) -> Option<OracleUpdate> {
+        let oracle = &self.config.oracle;
+        let mut reports = self.reports.write().await;
+        if reports.get(key).map_or(0, HashMap::len) < oracle.min_reports.max(1) {
+            return None;
+        }
+        let round = reports.remove(key)?;
+        drop(reports);
+
+        let values: Vec<f64> = round.values().map(|report| report.value).collect();
+        let value = median(&values)?;
+        let distance = |v: f64| if value == 0.0 { (v - value).abs() } else { ((v - value) / value).abs() };
+        let mut outliers: Vec<Pubkey> = round
+            .values()
+            .filter(|report| distance(report.value) > oracle.max_deviation)
+            .map(|report| report.operator)
+            .collect();
+        outliers.sort();
+        let deviation = values.iter().map(|v| distance(*v)).fold(0.0, f64::max);
+        self.metrics.deviation.with_label_values(&[key]).observe(deviation);
+
+        Some(OracleUpdate {
+            key: key.to_string(),
+            value,
+            reports: round.len(),
+            deviation,
+            outliers,
+            timestamp: chrono::Utc::now().timestamp(),
+        })
+    }
+
+    /// Collect, aggregate and propose every feed once
+    pub async fn run_round(&self, operator: Pubkey, proposer: &dyn UpdateProposer) {
+        let started = Instant::now();
+        self.collect(operator).await;
+
+        let feeds = self.feeds.read().await.clone();
+        for feed in feeds {
+            let Some(update) = self.aggregate(&feed.key).await else {
+                self.metrics.updates.with_label_values(&[feed.key.as_str(), "insufficient"]).inc();
+                continue;
+            };
+            for outlier in &update.outliers {
+                warn!("Operator {} reported an outlier for {}", outlier, feed.key);
+            }
+            match proposer.propose(&update).await {
+                Ok(()) => {
+                    info!("Proposed {} = {} from {} reports", update.key, update.value, update.reports);
+                    self.metrics.updates.with_label_values(&[feed.key.as_str(), "proposed"]).inc();
+                    self.metrics.latency.with_label_values(&[feed.key.as_str()]).observe(started.elapsed().as_secs_f64());
+                    let _ = self.update_data(&update.key, &update.value.to_string()).await;
+                }
+                Err(e) => {
+                    error!("Failed to propose oracle update for {}: {}", feed.key, e);
+                    self.metrics.updates.with_label_values(&[feed.key.as_str(), "failed"]).inc();
+                }
+            }
+        }
+    }
+
+    /// Run a round every `update_interval_secs` until `shutdown` is cancelled
+    pub fn spawn(
+        self: &Arc<Self>,
+        operator: Pubkey,
+        proposer: Arc<dyn UpdateProposer>,
+        shutdown: Option<ShutdownToken>,
+    ) -> JoinHandle<()> {
+        let manager = self.clone();
+        let period = Duration::from_secs(self.config.oracle.update_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown::cancelled(shutdown.as_ref()) => {
+                        info!("Stopped oracle updates");
+                        return;
+                    }
+                }
+                manager.run_round(operator, proposer.as_ref()).await;
+            }
+        })
+    }
+
     /// Start oracle update container
     pub async fn start_update_container(&self, image: &str) -> Result<()> {
         info!("Starting oracle update container: {}", image);
-        
+
         // In a real implementation, we would start a Docker container
         // that periodically updates oracle data
-        
+
         // For now, we'll just update data directly
         self.update_data("windexer-status", r#"{"status":"healthy","timestamp":1681234567}"#).await?;
-        
+
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn aggregates_reports_by_median() {
+        let oracle = OracleManager::new(CambrianConfig {
+            oracle: OracleConfig { min_reports: 3, ..OracleConfig::default() },
+            ..CambrianConfig::default()
+        });
+        let report = |value| OracleReport { operator: Pubkey::new_unique(), value, timestamp: 0 };
+
+        oracle.submit_report("sol", report(100.0)).await;
+        oracle.submit_report("sol", report(101.0)).await;
+        assert!(oracle.aggregate("sol").await.is_none());
+
+        let outlier = report(150.0);
+        oracle.submit_report("sol", outlier).await;
+        let update = oracle.aggregate("sol").await.unwrap();
+        assert_eq!((update.value, update.reports), (101.0, 3));
+        assert_eq!(update.outliers, [outlier.operator]);
+        assert!(oracle.aggregate("sol").await.is_none());
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+}
@@ -1,13 +1,65 @@
 //! Payload manager for Cambrian integration using Cambrian CLI
+//!
+//! Each run gets its own working directory, which the CLI writes the
+//! proposal into and which is removed afterwards, and is killed if it does
+//! not finish within [`PAYLOAD_TIMEOUT`]. The proposal is returned as a
+//! [`PayloadResult`] whose hash operators compare before it is submitted.
 
-use super::{CambrianConfig, PoAState};
+use super::{read_keypair, CambrianConfig};
 use anyhow::{Result, anyhow};
+use serde_json::{Map, Value};
+use solana_sdk::{
+    hash::{hash, Hash},
+    pubkey::Pubkey,
+    signer::Signer,
+};
 use std::{
-    fs,
-    path::PathBuf,
-    process::Command,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
 };
-use tracing::{info, error};
+use tokio::process::Command;
+use tracing::{info, warn, error};
+
+/// Longest a payload may run before it is killed
+pub const PAYLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Numbers the working directories of runs
+static RUN_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Proposal an operator's run of a payload produced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadResult {
+    /// Operator that ran the payload
+    pub operator: Pubkey,
+    /// Proposal instructions as JSON
+    pub proposal: String,
+    /// Hash of the proposal with its keys sorted, so equal proposals hash
+    /// the same however they were serialized
+    pub hash: Hash,
+}
+
+impl PayloadResult {
+    pub fn new(operator: Pubkey, proposal: String) -> Result<Self> {
+        let value: Value = serde_json::from_str(&proposal)
+            .map_err(|e| anyhow!("Proposal of operator {} is not JSON: {}", operator, e))?;
+        let hash = hash(canonical(&value).to_string().as_bytes());
+        Ok(Self { operator, proposal, hash })
+    }
+}
+
+/// `value` with the keys of every object in sorted order
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Value::Object(keys.into_iter().map(|key| (key.clone(), canonical(&map[key]))).collect::<Map<_, _>>())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
 
 /// Payload manager using Cambrian CLI
 pub struct PayloadManager {
@@ -21,76 +73,105 @@ impl PayloadManager {
             config,
         }
     }
-    
-    /// Run a payload container using Cambrian CLI
+
+    /// Run a payload container using Cambrian CLI in its own working
+    /// directory, returning the proposal it produced
     pub async fn run_payload(
         &self,
         payload_image: &str,
-        poa_state: &PoAState,
-    ) -> Result<String> {
+        poa: &Pubkey,
+    ) -> Result<PayloadResult> {
         info!("Running payload container via Cambrian CLI: {}", payload_image);
-        
-        // Use Cambrian CLI to run payload
-        let output = Command::new("cambrian")
-            .args(&[
+
+        // The CLI runs in the sandbox, where a relative path would not resolve
+        let keypair_path = std::fs::canonicalize(&self.config.admin_keypair_path)?;
+        let operator = read_keypair(&keypair_path)?.pubkey();
+        let sandbox = std::env::temp_dir().join(format!(
+            "windexer-payload-{}-{}",
+            std::process::id(),
+            RUN_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::create_dir_all(&sandbox).await?;
+
+        let result = self.run_in(&sandbox, &keypair_path, payload_image, poa).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&sandbox).await {
+            warn!("Failed to remove payload directory {:?}: {}", sandbox, e);
+        }
+        let proposal = result?;
+
+        info!("Payload executed successfully, proposal created");
+        PayloadResult::new(operator, proposal)
+    }
+
+    async fn run_in(
+        &self,
+        sandbox: &Path,
+        keypair_path: &Path,
+        payload_image: &str,
+        poa: &Pubkey,
+    ) -> Result<String> {
+        let run = Command::new("cambrian")
+            .current_dir(sandbox)
+            .kill_on_drop(true)
+            .args([
                 "payload",
                 "run",
-                "--keypair", self.config.admin_keypair_path.to_str().unwrap(),
+                "--keypair", keypair_path.to_str().unwrap(),
                 "--image", payload_image,
-                "--poa", &poa_state.pubkey.to_string(),
+                "--poa", &poa.to_string(),
                 "--url", &self.config.solana_api_url,
                 "--output", "proposal.json",
             ])
-            .output()?;
-        
+            .output();
+        let output = tokio::time::timeout(PAYLOAD_TIMEOUT, run)
+            .await
+            .map_err(|_| anyhow!("Payload {} did not finish within {:?}", payload_image, PAYLOAD_TIMEOUT))??;
+
         if !output.status.success() {
             let error_message = String::from_utf8_lossy(&output.stderr);
             error!("Cambrian CLI error: {}", error_message);
             return Err(anyhow!("Failed to run payload: {}", error_message));
         }
-        
+
         // Log the output
         let stdout = String::from_utf8_lossy(&output.stdout);
         info!("Cambrian CLI output: {}", stdout);
-        
-        // Verify proposal.json was created
-        if !PathBuf::from("proposal.json").exists() {
-            return Err(anyhow!("Proposal file not created"));
-        }
-        
-        info!("Payload executed successfully, proposal file created");
-        Ok("proposal.json".to_string())
+
+        tokio::fs::read_to_string(sandbox.join("proposal.json"))
+            .await
+            .map_err(|e| anyhow!("Proposal file not created: {}", e))
     }
-    
+
     /// Build a payload container image using Cambrian CLI
     pub async fn build_payload_image(&self, path: &PathBuf) -> Result<String> {
         info!("Building payload image from path via Cambrian CLI: {:?}", path);
-        
+
         // Ensure path exists
         if !path.exists() {
             return Err(anyhow!("Path does not exist: {:?}", path));
         }
-        
+
         // Generate a unique tag for the image
         let tag = format!("windexer-payload-{}", chrono::Utc::now().timestamp());
-        
+
         // Use Cambrian CLI to build payload
         let output = Command::new("cambrian")
-            .args(&[
+            .args([
                 "payload",
                 "build",
                 "--path", path.to_str().unwrap(),
                 "--tag", &tag,
             ])
-            .output()?;
-        
+            .output()
+            .await?;
+
         if !output.status.success() {
             let error_message = String::from_utf8_lossy(&output.stderr);
             error!("Cambrian CLI error: {}", error_message);
             return Err(anyhow!("Failed to build payload: {}", error_message));
         }
-        
+
         info!("Payload image built with tag: {}", tag);
         Ok(tag)
     }
-} 
\ No newline at end of file
+}
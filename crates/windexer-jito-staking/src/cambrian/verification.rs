@@ -0,0 +1,153 @@
+//! Verification of payload results across operators
+//!
+//! Before a proposal is submitted, every [`PayloadExecutor`] runs the same
+//! payload. The proposal is only submitted if at least the consensus
+//! threshold's share of the executors produced the same proposal hash;
+//! operators that produced another one are reported as divergent.
+
+use super::payload::{PayloadManager, PayloadResult, PAYLOAD_TIMEOUT};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+use std::{collections::HashMap, str::FromStr};
+
+/// Something that runs a payload for an operator
+#[async_trait]
+pub trait PayloadExecutor: Send + Sync {
+    /// Who runs the payload, for logs
+    fn name(&self) -> String;
+
+    async fn execute(&self, payload_image: &str, poa: &Pubkey) -> Result<PayloadResult>;
+}
+
+#[async_trait]
+impl PayloadExecutor for PayloadManager {
+    fn name(&self) -> String {
+        "local".to_string()
+    }
+
+    async fn execute(&self, payload_image: &str, poa: &Pubkey) -> Result<PayloadResult> {
+        self.run_payload(payload_image, poa).await
+    }
+}
+
+/// Another operator's AVS, asked to run payloads at `/api/payload/run`
+pub struct RemoteExecutor {
+    operator: Pubkey,
+    url: String,
+}
+
+impl RemoteExecutor {
+    pub fn new(operator: Pubkey, url: impl Into<String>) -> Self {
+        Self {
+            operator,
+            url: url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl PayloadExecutor for RemoteExecutor {
+    fn name(&self) -> String {
+        self.operator.to_string()
+    }
+
+    async fn execute(&self, payload_image: &str, poa: &Pubkey) -> Result<PayloadResult> {
+        let request = hyper::Request::post(format!("{}/api/payload/run", self.url.trim_end_matches('/')))
+            .header("Content-Type", "application/json")
+            .body(hyper::Body::from(json!({"image": payload_image, "poa": poa.to_string()}).to_string()))?;
+        let response = tokio::time::timeout(PAYLOAD_TIMEOUT, hyper::Client::new().request(request))
+            .await
+            .map_err(|_| anyhow!("Operator {} did not run {} within {:?}", self.operator, payload_image, PAYLOAD_TIMEOUT))??;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        if !status.is_success() {
+            return Err(anyhow!("Operator {} failed to run {}: {} {}", self.operator, payload_image, status, String::from_utf8_lossy(&body)));
+        }
+
+        let reply: Value = serde_json::from_slice(&body)?;
+        let operator = reply["operator"].as_str().map(Pubkey::from_str).transpose()?;
+        if operator != Some(self.operator) {
+            return Err(anyhow!("{} answered for operator {:?} instead of {}", self.url, operator, self.operator));
+        }
+        let proposal = reply["proposal"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Operator {} returned no proposal", self.operator))?;
+        // The hash is taken here rather than trusted from the reply
+        PayloadResult::new(self.operator, proposal.to_string())
+    }
+}
+
+/// The proposal a quorum of operators agreed on
+#[derive(Debug, Clone)]
+pub struct VerifiedPayload {
+    pub proposal: String,
+    pub hash: Hash,
+    /// Operators that produced the proposal
+    pub agreeing: Vec<Pubkey>,
+    /// Operators that produced another proposal
+    pub divergent: Vec<Pubkey>,
+}
+
+pub struct PayloadVerifier {
+    /// Share of the executors that must agree on the proposal
+    threshold: f64,
+}
+
+impl PayloadVerifier {
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold }
+    }
+
+    /// Pick the proposal most of `results` agree on, out of `executors`
+    /// asked to run the payload, failing when fewer than the threshold did
+    pub fn verify(&self, results: &[PayloadResult], executors: usize) -> Result<VerifiedPayload> {
+        let mut by_hash: HashMap<Hash, Vec<&PayloadResult>> = HashMap::new();
+        for result in results {
+            by_hash.entry(result.hash).or_default().push(result);
+        }
+        let (hash, agreeing) = by_hash
+            .into_iter()
+            .max_by(|a, b| a.1.len().cmp(&b.1.len()).then(b.0.cmp(&a.0)))
+            .ok_or_else(|| anyhow!("No operator produced a proposal"))?;
+
+        let required = ((self.threshold * executors as f64).ceil() as usize).max(1);
+        if agreeing.len() < required {
+            return Err(anyhow!(
+                "Only {} of {} operators agree on proposal {}, {} required",
+                agreeing.len(),
+                executors,
+                hash,
+                required
+            ));
+        }
+
+        Ok(VerifiedPayload {
+            proposal: agreeing[0].proposal.clone(),
+            hash,
+            agreeing: agreeing.iter().map(|result| result.operator).collect(),
+            divergent: results.iter().filter(|result| result.hash != hash).map(|result| result.operator).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_divergent_results() {
+        let result = |proposal: &str| PayloadResult::new(Pubkey::new_unique(), proposal.to_string()).unwrap();
+        let honest = [result(r#"{"data":[1],"program":"a"}"#), result(r#"{"program":"a","data":[1]}"#)];
+        let divergent = result(r#"{"program":"a","data":[2]}"#);
+        let verifier = PayloadVerifier::new(0.66);
+
+        let results = [honest[0].clone(), honest[1].clone(), divergent.clone()];
+        let verified = verifier.verify(&results, 3).unwrap();
+        assert_eq!(verified.hash, honest[0].hash);
+        assert_eq!(verified.divergent, [divergent.operator]);
+
+        assert!(verifier.verify(&[honest[0].clone(), divergent], 3).is_err());
+    }
+}
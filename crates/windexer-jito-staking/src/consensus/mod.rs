@@ -1,21 +1,56 @@
 // crates/windexer-jito-staking/src/consensus/mod.rs
 
 //! Consensus module implementation
+//!
+//! Operators vote on the content of rooted slots NCN style: each signs a
+//! [`ConsensusVote`] over the slot and its content hash with the Solana key
+//! it operates under. [`ConsensusManager::cast_vote`] checks the signature
+//! and tallies the vote by stake; once operators holding the consensus
+//! threshold of the total stake agree on a hash, the slot is final and its
+//! votes are returned as a [`SlotAttestation`], which can be posted on chain
+//! through the Cambrian PoA program with
+//! [`CambrianService::post_attestation`](crate::cambrian::CambrianService::post_attestation).
 
 mod metrics;
 mod validator;
 
-use anyhow::Result;
-use solana_sdk::pubkey::Pubkey;
+use crate::error::{Result, StakingError};
+use solana_sdk::{hash::Hash, pubkey::Pubkey};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{info, warn};
-use std::time::Duration;
+use windexer_common::types::{ConsensusVote, SlotAttestation};
 
 pub struct ConsensusManager {
     min_validators: usize,
     consensus_threshold: f64,
     active_operators: Arc<RwLock<Vec<Pubkey>>>,
+    slot_votes: Arc<RwLock<BTreeMap<u64, SlotTally>>>,
+}
+
+/// Votes cast for one slot, by the content hash they attest to
+#[derive(Default)]
+struct SlotTally {
+    voters: HashSet<Pubkey>,
+    /// Stake and number of voters behind each hash
+    hashes: HashMap<[u8; 32], (u64, usize)>,
+    /// Signed votes, kept to attest the slot once it is final
+    votes: Vec<ConsensusVote>,
+    finalized: Option<[u8; 32]>,
+}
+
+impl SlotTally {
+    /// Count a vote, unless the slot is final or `operator` already voted
+    fn add(&mut self, operator: Pubkey, content_hash: [u8; 32], stake: u64) -> bool {
+        if self.finalized.is_some() || !self.voters.insert(operator) {
+            return false;
+        }
+        let (hash_stake, voters) = self.hashes.entry(content_hash).or_default();
+        *hash_stake += stake;
+        *voters += 1;
+        true
+    }
 }
 
 impl ConsensusManager {
@@ -24,6 +59,7 @@ impl ConsensusManager {
             min_validators,
             consensus_threshold,
             active_operators: Arc::new(RwLock::new(Vec::new())),
+            slot_votes: Arc::new(RwLock::new(BTreeMap::new())),
         }
     }
 
@@ -36,14 +72,168 @@ impl ConsensusManager {
         Ok(())
     }
 
-    pub async fn check_consensus_threshold(&self) -> Result<bool> {
-        let operators = self.active_operators.read().await;
-        if operators.len() < self.min_validators {
-            warn!("Not enough operators for consensus");
-            return Ok(false);
+    /// Whether `content_hash` has votes of at least `min_validators`
+    /// operators holding the consensus threshold of `total_stake`
+    fn reached(&self, votes: (u64, usize), total_stake: u64) -> bool {
+        let (stake, voters) = votes;
+        let share = if total_stake == 0 { 0.0 } else { stake as f64 / total_stake as f64 };
+        voters >= self.min_validators && share >= self.consensus_threshold
+    }
+
+    /// Whether any hash of `slot` reached the stake-weighted threshold
+    pub async fn check_consensus_threshold(&self, slot: u64, total_stake: u64) -> Result<bool> {
+        let slot_votes = self.slot_votes.read().await;
+        Ok(slot_votes.get(&slot).is_some_and(|tally| {
+            tally.finalized.is_some() || tally.hashes.values().any(|votes| self.reached(*votes, total_stake))
+        }))
+    }
+
+    /// Whether `attestation` holds valid votes for its hash from enough
+    /// operators, weighted by `stake_of` out of `total_stake`, to have
+    /// finalized its slot
+    pub fn verify_attestation(&self, attestation: &SlotAttestation, stake_of: impl Fn(&Pubkey) -> u64, total_stake: u64) -> bool {
+        let mut voters = HashSet::new();
+        let mut stake = 0u64;
+        for vote in &attestation.votes {
+            if vote.slot != attestation.slot || vote.content_hash != attestation.content_hash || !vote.verify() {
+                return false;
+            }
+            let weight = stake_of(&vote.voter);
+            if weight > 0 && voters.insert(vote.voter) {
+                stake = stake.saturating_add(weight);
+            }
+        }
+        self.reached((stake, voters.len()), total_stake)
+    }
+
+    /// Finalize `slot` with `content_hash` if it reached the threshold
+    fn try_finalize(&self, slot: u64, tally: &mut SlotTally, content_hash: [u8; 32], total_stake: u64) -> bool {
+        if !self.reached(tally.hashes[&content_hash], total_stake) {
+            return false;
+        }
+        tally.finalized = Some(content_hash);
+        if tally.hashes.len() > 1 {
+            warn!("Slot {} finalized with {} competing hashes", slot, tally.hashes.len());
+        }
+        true
+    }
+
+    /// Records an operator's vote that `slot` has `content_hash`, weighted
+    /// by its `stake` out of `total_stake`. Returns the hash the first time
+    /// a hash reaches the consensus threshold with at least `min_validators`
+    /// voters; an operator's later votes for the same slot are ignored.
+    pub async fn record_vote(
+        &self,
+        slot: u64,
+        content_hash: [u8; 32],
+        operator: Pubkey,
+        stake: u64,
+        total_stake: u64,
+    ) -> Option<[u8; 32]> {
+        let mut slot_votes = self.slot_votes.write().await;
+        let tally = slot_votes.entry(slot).or_default();
+        if !tally.add(operator, content_hash, stake) {
+            return None;
+        }
+        self.try_finalize(slot, tally, content_hash, total_stake).then_some(content_hash)
+    }
+
+    /// Like [`ConsensusManager::record_vote`] for a signed vote, which is
+    /// rejected unless its signature is valid; returns the slot's
+    /// attestation, with the votes for the winning hash, once it is final
+    pub async fn cast_vote(&self, vote: ConsensusVote, stake: u64, total_stake: u64) -> Result<Option<SlotAttestation>> {
+        if !vote.verify() {
+            return Err(StakingError::InvalidSignature(vote.voter));
+        }
+        let content_hash = vote.content_hash.to_bytes();
+        let slot = vote.slot;
+
+        let mut slot_votes = self.slot_votes.write().await;
+        let tally = slot_votes.entry(slot).or_default();
+        if !tally.add(vote.voter, content_hash, stake) {
+            return Ok(None);
+        }
+        tally.votes.push(vote);
+        if !self.try_finalize(slot, tally, content_hash, total_stake) {
+            return Ok(None);
         }
 
-        let active_ratio = operators.len() as f64 / self.min_validators as f64;
-        Ok(active_ratio >= self.consensus_threshold)
+        let winning = Hash::new_from_array(content_hash);
+        Ok(Some(SlotAttestation {
+            slot,
+            content_hash: winning,
+            stake: tally.hashes[&content_hash].0,
+            total_stake,
+            votes: tally.votes.iter().filter(|vote| vote.content_hash == winning).cloned().collect(),
+        }))
+    }
+
+    /// Forgets the votes for every slot before `slot`
+    pub async fn prune_votes(&self, slot: u64) {
+        let mut slot_votes = self.slot_votes.write().await;
+        *slot_votes = slot_votes.split_off(&slot);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn finalizes_once_threshold_is_met() {
+        let manager = ConsensusManager::new(2, 0.66);
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let hash = [1u8; 32];
+
+        assert_eq!(manager.record_vote(7, hash, a, 50, 100).await, None);
+        // A second vote from the same operator doesn't count twice
+        assert_eq!(manager.record_vote(7, hash, a, 50, 100).await, None);
+        assert_eq!(manager.record_vote(7, [2u8; 32], b, 10, 100).await, None);
+        assert_eq!(manager.record_vote(7, hash, c, 20, 100).await, Some(hash));
+        assert_eq!(manager.record_vote(7, hash, b, 10, 100).await, None);
+    }
+
+    #[tokio::test]
+    async fn attests_signed_votes() {
+        use solana_sdk::{signature::Keypair, signer::Signer};
+
+        let manager = ConsensusManager::new(2, 0.66);
+        let (a, b) = (Keypair::new(), Keypair::new());
+        let hash = Hash::new_from_array([3u8; 32]);
+
+        let mut forged = ConsensusVote::sign(&a, 9, hash);
+        forged.voter = b.pubkey();
+        assert!(matches!(manager.cast_vote(forged, 60, 100).await, Err(StakingError::InvalidSignature(_))));
+
+        assert!(manager.cast_vote(ConsensusVote::sign(&a, 9, hash), 40, 100).await.unwrap().is_none());
+        assert!(!manager.check_consensus_threshold(9, 100).await.unwrap());
+        let attestation = manager.cast_vote(ConsensusVote::sign(&b, 9, hash), 30, 100).await.unwrap().unwrap();
+        assert_eq!((attestation.stake, attestation.votes.len()), (70, 2));
+        assert!(attestation.votes.iter().all(ConsensusVote::verify));
+        assert!(manager.check_consensus_threshold(9, 100).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn verifies_attestations_against_stake() {
+        use solana_sdk::{signature::Keypair, signer::Signer};
+
+        let manager = ConsensusManager::new(2, 0.66);
+        let (a, b) = (Keypair::new(), Keypair::new());
+        let hash = Hash::new_from_array([4u8; 32]);
+        manager.cast_vote(ConsensusVote::sign(&a, 5, hash), 40, 100).await.unwrap();
+        let attestation = manager.cast_vote(ConsensusVote::sign(&b, 5, hash), 30, 100).await.unwrap().unwrap();
+
+        let stake_of = |operator: &Pubkey| if *operator == a.pubkey() { 40 } else if *operator == b.pubkey() { 30 } else { 0 };
+        assert!(manager.verify_attestation(&attestation, stake_of, 100));
+        // Not enough stake once the votes are weighed by this node
+        assert!(!manager.verify_attestation(&attestation, |_| 10, 100));
+
+        let mut duplicated = attestation.clone();
+        duplicated.votes[1] = duplicated.votes[0].clone();
+        assert!(!manager.verify_attestation(&duplicated, stake_of, 100));
+
+        let mut forged = attestation;
+        forged.content_hash = Hash::new_from_array([5u8; 32]);
+        assert!(!manager.verify_attestation(&forged, stake_of, 100));
+    }
+}
@@ -1,6 +1,6 @@
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
-use anyhow::Result;
+use crate::error::{Result, StakingError};
 
 pub struct ValidatorSet {
     validators: HashMap<Pubkey, ValidatorInfo>,
@@ -24,7 +24,10 @@ impl ValidatorSet {
 
     pub fn add_validator(&mut self, pubkey: Pubkey, stake: u64) -> Result<()> {
         if stake < self.min_stake {
-            return Err(anyhow::anyhow!("Validator stake below minimum threshold"));
+            return Err(StakingError::ValidatorBelowMinimum {
+                stake,
+                minimum: self.min_stake,
+            });
         }
 
         self.validators.insert(pubkey, ValidatorInfo {
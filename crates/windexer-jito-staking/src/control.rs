@@ -0,0 +1,146 @@
+// crates/windexer-jito-staking/src/control.rs
+
+//! The staking service as a [`StakingControl`] hook
+//!
+//! A binary that embeds both the staking service and windexer-api hands the
+//! service to `ApiServer::set_staking_control`, which serves its operators,
+//! rewards, performance history and slashes under `/api/staking` without the API depending on
+//! this crate.
+
+use {
+    crate::{staking::types::OperatorStats, JitoStakingService},
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    solana_sdk::pubkey::Pubkey,
+    std::str::FromStr,
+    windexer_common::{
+        control::{OperatorDetail, OperatorStatus, RewardBalance, StakeDelegation, StakingControl},
+        types::{PerformanceRecord, SlashRecord},
+    },
+};
+
+fn parse_pubkey(pubkey: &str) -> Result<Pubkey> {
+    Pubkey::from_str(pubkey).map_err(|e| anyhow!("Invalid pubkey {}: {}", pubkey, e))
+}
+
+fn operator_status(operator: &Pubkey, stats: &OperatorStats) -> OperatorStatus {
+    OperatorStatus {
+        pubkey: operator.to_string(),
+        total_stake: stats.total_stake,
+        delegators: stats.active_delegations.len(),
+        performance_score: stats.performance_score,
+        last_active: stats.last_active,
+        reward_balance: stats.reward_balance,
+        pending_withdrawals: stats.pending_withdrawals.iter().map(|w| w.amount).sum(),
+    }
+}
+
+#[async_trait]
+impl StakingControl for JitoStakingService {
+    async fn operators(&self) -> Result<Vec<OperatorStatus>> {
+        let mut operators: Vec<OperatorStatus> = self
+            .staking_manager()
+            .get_all_operators()
+            .await
+            .iter()
+            .map(|(operator, stats)| operator_status(operator, stats))
+            .collect();
+        operators.sort_by(|a, b| b.total_stake.cmp(&a.total_stake).then_with(|| a.pubkey.cmp(&b.pubkey)));
+        Ok(operators)
+    }
+
+    async fn operator(&self, pubkey: &str) -> Result<Option<OperatorDetail>> {
+        let operator = parse_pubkey(pubkey)?;
+        let staking = self.staking_manager();
+        let Some(stats) = staking.find_operator(&operator) else {
+            return Ok(None);
+        };
+        let delegations = staking
+            .get_delegators_for_operator(&operator)
+            .into_iter()
+            .map(|(staker, amount)| StakeDelegation {
+                staker: staker.to_string(),
+                operator: pubkey.to_string(),
+                amount,
+                rewards: stats.delegator_rewards.get(&staker).copied().unwrap_or_default(),
+            })
+            .collect();
+        Ok(Some(OperatorDetail {
+            operator: operator_status(&operator, &stats),
+            delegations,
+            current_score: self.performance_tracker().current(&operator).score(),
+        }))
+    }
+
+    async fn rewards(&self, pubkey: &str) -> Result<RewardBalance> {
+        let account = parse_pubkey(pubkey)?;
+        let staking = self.staking_manager();
+        let operator_rewards = staking.find_operator(&account).map_or(0, |stats| stats.reward_balance);
+        let delegations: Vec<StakeDelegation> = staking
+            .get_delegations_by_staker(&account)
+            .into_iter()
+            .map(|delegation| StakeDelegation {
+                staker: pubkey.to_string(),
+                operator: delegation.operator.to_string(),
+                amount: delegation.amount,
+                rewards: delegation.rewards,
+            })
+            .collect();
+        let total = operator_rewards + delegations.iter().map(|d| d.rewards).sum::<u64>();
+        Ok(RewardBalance {
+            pubkey: pubkey.to_string(),
+            operator_rewards,
+            delegations,
+            total,
+        })
+    }
+
+    async fn slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        let operator = operator.map(parse_pubkey).transpose()?;
+        Ok(self
+            .slash_history(operator.as_ref(), limit)
+            .iter()
+            .map(|slash| slash.to_record())
+            .collect())
+    }
+
+    async fn performance(&self, pubkey: &str, since: i64) -> Result<Option<Vec<PerformanceRecord>>> {
+        let operator = parse_pubkey(pubkey)?;
+        let staking = self.staking_manager();
+        if staking.find_operator(&operator).is_none() {
+            return Ok(None);
+        }
+        Ok(Some(staking.performance_history(&operator, since)))
+    }
+
+    async fn register_operator(&self, pubkey: &str) -> Result<OperatorStatus> {
+        let operator = parse_pubkey(pubkey)?;
+        let stats = JitoStakingService::register_operator(self, operator).await?;
+        Ok(operator_status(&operator, &stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::StakingConfig};
+
+    #[tokio::test]
+    async fn serves_registered_operators() {
+        let service = JitoStakingService::new(StakingConfig::default());
+        let (operator, staker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let control: &dyn StakingControl = &service;
+
+        let status = control.register_operator(&operator.to_string()).await.unwrap();
+        assert_eq!(status.total_stake, 0);
+        assert!(control.register_operator(&operator.to_string()).await.is_err());
+        assert!(control.operator(&staker.to_string()).await.unwrap().is_none());
+
+        service.process_stake(1_000, staker, operator).await.unwrap();
+        service.staking_manager().credit_rewards(operator, 10, &[(staker, 90)]).await.unwrap();
+        let detail = control.operator(&operator.to_string()).await.unwrap().unwrap();
+        assert_eq!((detail.operator.total_stake, detail.operator.delegators), (1_000, 1));
+        let rewards = control.rewards(&staker.to_string()).await.unwrap();
+        assert_eq!((rewards.operator_rewards, rewards.total), (0, 90));
+        assert_eq!(control.rewards(&operator.to_string()).await.unwrap().total, 10);
+    }
+}
@@ -0,0 +1,304 @@
+// crates/windexer-jito-staking/src/epoch.rs
+
+//! Solana epochs
+//!
+//! [`EpochManager`] follows the cluster's latest slot through a
+//! [`SlotSource`] and places it in the cluster's [`EpochSchedule`]. When the
+//! slot enters a new epoch, the epoch is handed to
+//! [`JitoStakingService::process_epoch`], which closes the performance
+//! window, distributes the epoch's rewards and releases the withdrawals
+//! whose cooldown ended. A boundary that fails to process is retried on the
+//! next poll.
+//!
+//! [`RpcSlotSource`] polls an RPC provider and reads the schedule from it;
+//! [`FeedSlotSource`] follows the slots the indexer ingests, with the
+//! schedule taken from `slots_per_epoch`.
+
+use {
+    crate::{
+        error::{Result, StakingError},
+        JitoStakingService,
+    },
+    async_trait::async_trait,
+    serde::{Deserialize, Serialize},
+    serde_json::{json, Value},
+    solana_sdk::epoch_schedule::EpochSchedule,
+    std::{
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, OnceLock, RwLock,
+        },
+        time::Duration,
+    },
+    tokio::{sync::broadcast::error::RecvError, task::JoinHandle},
+    tracing::{info, warn},
+    windexer_common::{
+        control::{EpochInfo, EpochStatus},
+        feed::DataFeed,
+        rpc_provider::RpcProvider,
+        shutdown::{self, ShutdownToken},
+    },
+};
+
+/// Slots in a mainnet epoch
+pub const DEFAULT_SLOTS_PER_EPOCH: u64 = 432_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpochConfig {
+    /// Seconds between polls of the slot source
+    pub poll_interval_secs: u64,
+    /// Slots per epoch, for sources that cannot tell the cluster's schedule
+    pub slots_per_epoch: u64,
+}
+
+impl Default for EpochConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 10,
+            slots_per_epoch: DEFAULT_SLOTS_PER_EPOCH,
+        }
+    }
+}
+
+/// Somewhere to read the cluster's latest slot from
+#[async_trait]
+pub trait SlotSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Latest slot, `None` until one is known
+    async fn current_slot(&self) -> Result<Option<u64>>;
+
+    /// The cluster's epoch schedule, if the source can tell
+    async fn epoch_schedule(&self) -> Result<Option<EpochSchedule>> {
+        Ok(None)
+    }
+}
+
+fn epoch_error(e: impl std::fmt::Display) -> StakingError {
+    StakingError::Epoch(e.to_string())
+}
+
+/// Polls `getSlot` and reads the schedule with `getEpochSchedule`
+pub struct RpcSlotSource {
+    provider: Arc<dyn RpcProvider>,
+}
+
+impl RpcSlotSource {
+    pub fn new(provider: Arc<dyn RpcProvider>) -> Self {
+        Self { provider }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let mut reply = self.provider.call(method, params).await.map_err(epoch_error)?;
+        if let Some(error) = reply.get("error") {
+            return Err(epoch_error(format!("{} answered {} with {}", self.provider.name(), method, error)));
+        }
+        match reply.get_mut("result").map(Value::take) {
+            Some(result) => Ok(result),
+            None => Err(epoch_error(format!("{} returned no result for {}: {}", self.provider.name(), method, reply))),
+        }
+    }
+}
+
+#[async_trait]
+impl SlotSource for RpcSlotSource {
+    fn name(&self) -> &str {
+        self.provider.name()
+    }
+
+    async fn current_slot(&self) -> Result<Option<u64>> {
+        let slot = self.call("getSlot", json!([{"commitment": "confirmed"}])).await?;
+        slot.as_u64()
+            .map(Some)
+            .ok_or_else(|| epoch_error(format!("Invalid slot {} from {}", slot, self.provider.name())))
+    }
+
+    async fn epoch_schedule(&self) -> Result<Option<EpochSchedule>> {
+        let schedule = self.call("getEpochSchedule", json!([])).await?;
+        let field = |name: &str| {
+            schedule[name]
+                .as_u64()
+                .ok_or_else(|| epoch_error(format!("Epoch schedule from {} lacks {}", self.provider.name(), name)))
+        };
+        Ok(Some(EpochSchedule {
+            slots_per_epoch: field("slotsPerEpoch")?,
+            leader_schedule_slot_offset: field("leaderScheduleSlotOffset")?,
+            warmup: schedule["warmup"].as_bool().unwrap_or(false),
+            first_normal_epoch: field("firstNormalEpoch")?,
+            first_normal_slot: field("firstNormalSlot")?,
+        }))
+    }
+}
+
+/// Keeps the highest slot the indexer ingested
+#[derive(Default)]
+pub struct FeedSlotSource {
+    /// Zero until the first event
+    slot: AtomicU64,
+}
+
+impl FeedSlotSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the slots of the events published to `feed`
+    pub fn follow(self: &Arc<Self>, feed: &DataFeed, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let source = self.clone();
+        let mut events = feed.subscribe();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => {
+                            source.slot.fetch_max(event.slot(), Ordering::Relaxed);
+                        }
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = shutdown::cancelled(shutdown.as_ref()) => return,
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl SlotSource for FeedSlotSource {
+    fn name(&self) -> &str {
+        "feed"
+    }
+
+    async fn current_slot(&self) -> Result<Option<u64>> {
+        Ok(Some(self.slot.load(Ordering::Relaxed)).filter(|slot| *slot > 0))
+    }
+}
+
+pub struct EpochManager {
+    config: EpochConfig,
+    service: Arc<JitoStakingService>,
+    source: Arc<dyn SlotSource>,
+    schedule: OnceLock<EpochSchedule>,
+    current: RwLock<Option<EpochInfo>>,
+}
+
+impl EpochManager {
+    pub fn new(config: EpochConfig, service: Arc<JitoStakingService>, source: Arc<dyn SlotSource>) -> Self {
+        Self {
+            config,
+            service,
+            source,
+            schedule: OnceLock::new(),
+            current: RwLock::new(None),
+        }
+    }
+
+    /// The epoch of the latest slot seen
+    pub fn current(&self) -> Option<EpochInfo> {
+        *self.current.read().unwrap()
+    }
+
+    /// The source's schedule, read once, or else one of `slots_per_epoch`
+    async fn schedule(&self) -> Result<&EpochSchedule> {
+        if let Some(schedule) = self.schedule.get() {
+            return Ok(schedule);
+        }
+        let schedule = match self.source.epoch_schedule().await? {
+            Some(schedule) => schedule,
+            None => {
+                let slots = self.config.slots_per_epoch;
+                EpochSchedule::custom(slots, slots, false)
+            }
+        };
+        info!(
+            "Following epochs of {} slots from {}",
+            schedule.slots_per_epoch,
+            self.source.name()
+        );
+        Ok(self.schedule.get_or_init(|| schedule))
+    }
+
+    /// Move to `slot`, returning the epoch it starts if it is the first slot
+    /// seen of a new epoch
+    pub async fn observe(&self, slot: u64) -> Result<Option<u64>> {
+        let current = self.current();
+        if current.is_some_and(|current| slot <= current.slot) {
+            return Ok(None);
+        }
+        let schedule = self.schedule().await?;
+        let (epoch, slot_index) = schedule.get_epoch_and_slot_index(slot);
+        let info = EpochInfo {
+            epoch,
+            slot,
+            slot_index,
+            slots_in_epoch: schedule.get_slots_in_epoch(epoch),
+            first_slot: schedule.get_first_slot_in_epoch(epoch),
+            last_slot: schedule.get_last_slot_in_epoch(epoch),
+        };
+
+        let started = current.map_or(true, |current| current.epoch != epoch);
+        if started {
+            info!("Epoch {} at slot {} ({} of {})", epoch, slot, slot_index, info.slots_in_epoch);
+            self.service.process_epoch(epoch).await?;
+        }
+        *self.current.write().unwrap() = Some(info);
+        Ok(started.then_some(epoch))
+    }
+
+    /// Read the latest slot from the source and move to it
+    pub async fn poll(&self) -> Result<Option<u64>> {
+        match self.source.current_slot().await? {
+            Some(slot) => self.observe(slot).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Poll every `poll_interval_secs` until `shutdown` is cancelled
+    pub fn spawn(self: &Arc<Self>, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let manager = self.clone();
+        let period = Duration::from_secs(self.config.poll_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown::cancelled(shutdown.as_ref()) => {
+                        info!("Stopped following epochs");
+                        return;
+                    }
+                }
+                if let Err(e) = manager.poll().await {
+                    warn!("Failed to follow the epoch from {}: {}", manager.source.name(), e);
+                }
+            }
+        })
+    }
+}
+
+impl EpochStatus for EpochManager {
+    fn current_epoch(&self) -> Option<EpochInfo> {
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::StakingConfig};
+
+    #[tokio::test]
+    async fn processes_epoch_boundaries() {
+        let service = Arc::new(JitoStakingService::new(StakingConfig::default()));
+        let config = EpochConfig { slots_per_epoch: 32, ..EpochConfig::default() };
+        let manager = EpochManager::new(config, service.clone(), Arc::new(FeedSlotSource::new()));
+
+        assert_eq!(manager.observe(100).await.unwrap(), Some(3));
+        assert_eq!(manager.observe(101).await.unwrap(), None);
+        assert_eq!(manager.observe(90).await.unwrap(), None);
+        assert_eq!(manager.observe(130).await.unwrap(), Some(4));
+
+        let info = manager.current_epoch().unwrap();
+        assert_eq!((info.slot_index, info.first_slot, info.last_slot), (2, 128, 159));
+        assert_eq!(service.staking_manager().current_epoch(), 4);
+    }
+}
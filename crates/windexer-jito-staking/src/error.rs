@@ -0,0 +1,76 @@
+// crates/windexer-jito-staking/src/error.rs
+
+//! Staking errors
+
+use {
+    solana_sdk::pubkey::Pubkey,
+    thiserror::Error,
+    windexer_common::errors::ErrorCode,
+};
+
+#[derive(Debug, Error)]
+pub enum StakingError {
+    #[error("Stake amount {amount} below minimum threshold {minimum}")]
+    BelowMinimumStake { amount: u64, minimum: u64 },
+
+    #[error("Operator {0} would exceed maximum stake")]
+    MaximumStakeExceeded(Pubkey),
+
+    #[error("Validator stake {stake} below minimum threshold {minimum}")]
+    ValidatorBelowMinimum { stake: u64, minimum: u64 },
+
+    #[error("Cannot unstake {requested} from {operator}, {staker} only has {available} staked")]
+    InsufficientStake { operator: Pubkey, staker: Pubkey, requested: u64, available: u64 },
+
+    #[error("Amount must be positive")]
+    ZeroAmount,
+
+    #[error("Cannot redelegate from operator {0} to itself")]
+    SameOperator(Pubkey),
+
+    #[error("Operator {0} is already registered")]
+    OperatorExists(Pubkey),
+
+    #[error("Delegation of {staker} to {operator} not found")]
+    DelegationNotFound { operator: Pubkey, staker: Pubkey },
+
+    #[error("Invalid signature on vote from {0}")]
+    InvalidSignature(Pubkey),
+
+    #[error("Vote from {0} who is not a staked operator")]
+    UnstakedVoter(Pubkey),
+
+    #[error("Invalid vault {0}")]
+    InvalidVault(Pubkey),
+
+    #[error("No violation history found for {0}")]
+    NoViolationHistory(Pubkey),
+
+    #[error("Staking state storage failed: {0}")]
+    Storage(String),
+
+    #[error("On-chain stake sync failed: {0}")]
+    ChainSync(String),
+
+    #[error("Epoch tracking failed: {0}")]
+    Epoch(String),
+}
+
+impl StakingError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::BelowMinimumStake { .. }
+            | Self::ValidatorBelowMinimum { .. }
+            | Self::ZeroAmount
+            | Self::SameOperator(_)
+            | Self::InvalidSignature(_) => ErrorCode::InvalidArgument,
+            Self::MaximumStakeExceeded(_) | Self::InsufficientStake { .. } => ErrorCode::FailedPrecondition,
+            Self::OperatorExists(_) => ErrorCode::AlreadyExists,
+            Self::UnstakedVoter(_) => ErrorCode::PermissionDenied,
+            Self::DelegationNotFound { .. } | Self::InvalidVault(_) | Self::NoViolationHistory(_) => ErrorCode::NotFound,
+            Self::Storage(_) | Self::ChainSync(_) | Self::Epoch(_) => ErrorCode::Unavailable,
+        }
+    }
+}
+
+pub type Result<T, E = StakingError> = std::result::Result<T, E>;
@@ -7,9 +7,12 @@
 
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
-use anyhow::Result;
 use tracing::{info, error};
 use tokio::sync::RwLock;
+use windexer_common::{
+    shutdown::{self, ShutdownToken},
+    types::{ConsensusVote, SlotAttestation},
+};
 
 pub mod staking;
 pub mod rewards;
@@ -17,24 +20,47 @@ pub mod slashing;
 pub mod consensus;
 pub mod utils;
 pub mod cambrian;
+pub mod error;
+pub mod epoch;
+pub mod control;
 
-pub use staking::types::{StakingConfig, OperatorStats};
+pub use staking::types::{StakingConfig, DEFAULT_PERFORMANCE_RETENTION_EPOCHS, DelegationInfo, OperatorStats, PendingWithdrawal, SlashEvent, StakingEvent};
 pub use staking::StakingManager;
+pub use staking::chain::{FeedStakeSource, RpcStakeSource, StakeSource, VaultDelegation};
+pub use staking::sync::{ChainSync, ChainSyncConfig, StakeMismatch};
 pub use consensus::ConsensusManager;
-pub use rewards::RewardsManager;
+pub use rewards::{PerformanceMetrics, PerformanceTracker, RewardsManager};
 pub use slashing::{SlashingManager, ViolationType};
 pub use cambrian::{CambrianConfig, CambrianService};
+pub use error::{Result, StakingError};
+pub use epoch::{EpochConfig, EpochManager, FeedSlotSource, RpcSlotSource, SlotSource};
 
 pub struct JitoStakingService {
     staking_manager: Arc<StakingManager>,
     consensus_manager: Arc<ConsensusManager>,
     rewards_manager: Arc<RewardsManager>,
     slashing_manager: Arc<SlashingManager>,
+    performance: Arc<PerformanceTracker>,
 }
 
 impl JitoStakingService {
     pub fn new(config: StakingConfig) -> Self {
-        let staking_manager = Arc::new(StakingManager::new(config.clone()));
+        Self::with_manager(StakingManager::new(config))
+    }
+
+    /// Like [`JitoStakingService::new`], loading operator and delegation
+    /// state from `storage` and writing every change through to it
+    #[cfg(feature = "store")]
+    pub async fn with_storage(
+        config: StakingConfig,
+        storage: Arc<dyn windexer_store::traits::Storage>,
+    ) -> Result<Self> {
+        Ok(Self::with_manager(StakingManager::with_storage(config, storage).await?))
+    }
+
+    fn with_manager(staking_manager: StakingManager) -> Self {
+        let config = staking_manager.config().clone();
+        let staking_manager = Arc::new(staking_manager);
         let consensus_manager = Arc::new(ConsensusManager::new(
             config.min_operators as usize,
             config.consensus_threshold,
@@ -46,6 +72,7 @@ impl JitoStakingService {
         let slashing_manager = Arc::new(SlashingManager::new(
             config.slash_threshold,
             config.min_uptime,
+            staking_manager.clone(),
         ));
 
         Self {
@@ -53,16 +80,34 @@ impl JitoStakingService {
             consensus_manager,
             rewards_manager,
             slashing_manager,
+            performance: Arc::new(PerformanceTracker::new()),
         }
     }
 
     pub async fn start(&self) -> Result<()> {
-        self.start_reward_distribution().await?;
+        self.start_tasks(None).await
+    }
+
+    /// Like [`JitoStakingService::start`], stopping the background tasks
+    /// when `shutdown` is cancelled
+    pub async fn start_with_shutdown(&self, shutdown: ShutdownToken) -> Result<()> {
+        self.start_tasks(Some(shutdown)).await
+    }
+
+    async fn start_tasks(&self, shutdown: Option<ShutdownToken>) -> Result<()> {
+        self.start_reward_distribution(shutdown).await?;
         self.start_consensus_monitoring().await?;
         self.start_performance_monitoring().await?;
         Ok(())
     }
 
+    /// Register `operator` so it is listed before anyone stakes with it
+    pub async fn register_operator(&self, operator: Pubkey) -> Result<OperatorStats> {
+        let stats = self.staking_manager.register_operator(operator).await?;
+        info!("Registered operator {}", operator);
+        Ok(stats)
+    }
+
     pub async fn process_stake(
         &self,
         amount: u64,
@@ -74,33 +119,150 @@ impl JitoStakingService {
         Ok(())
     }
 
+    /// Start cooling down `amount` of `staker`'s stake with `operator`
+    pub async fn process_unstake(
+        &self,
+        amount: u64,
+        staker: Pubkey,
+        operator: Pubkey,
+    ) -> Result<PendingWithdrawal> {
+        self.staking_manager.process_unstake(amount, staker, operator).await
+    }
+
+    /// Handle an epoch boundary: score every operator for the epoch that
+    /// ended, distribute its rewards, and release withdrawals whose
+    /// cooldown ended; [`EpochManager`] calls this as Solana epochs change
+    ///
+    /// The first call only sets the epoch, as the service cannot know how
+    /// much of the previous one it saw.
+    pub async fn process_epoch(&self, epoch: u64) -> Result<Vec<(Pubkey, PendingWithdrawal)>> {
+        let previous = self.staking_manager.current_epoch();
+        if previous != 0 && epoch > previous {
+            self.close_performance_window(previous).await?;
+            if let Err(e) = self.rewards_manager.distribute_epoch(&self.staking_manager).await {
+                error!("Failed to distribute the rewards of epoch {}: {}", previous, e);
+            }
+        }
+        let released = self.staking_manager.process_epoch(epoch).await?;
+        if !released.is_empty() {
+            info!("Released {} withdrawals at epoch {}", released.len(), epoch);
+        }
+        Ok(released)
+    }
+
+    /// Move `amount` of `staker`'s stake from operator `from` to `to`
+    pub async fn redelegate(&self, amount: u64, staker: Pubkey, from: Pubkey, to: Pubkey) -> Result<()> {
+        self.staking_manager.redelegate(amount, staker, from, to).await
+    }
+
+    /// Every delegation of `staker`
+    pub fn get_delegations_by_staker(&self, staker: &Pubkey) -> Vec<DelegationInfo> {
+        self.staking_manager.get_delegations_by_staker(staker)
+    }
+
+    /// Stakers delegating to `operator` with their stake
+    pub fn get_delegators_for_operator(&self, operator: &Pubkey) -> Vec<(Pubkey, u64)> {
+        self.staking_manager.get_delegators_for_operator(operator)
+    }
+
+    /// Stake changes from now on
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<StakingEvent> {
+        self.staking_manager.subscribe()
+    }
+
+    /// Score every operator on its consensus participation during `epoch`
+    /// and queue the epoch's reward for distribution
+    async fn close_performance_window(&self, epoch: u64) -> Result<()> {
+        let window = self.performance.roll_window();
+        let operators = self.staking_manager.get_all_operators().await;
+        let mut total = 0;
+        for (operator, stats) in operators {
+            let metrics = window.get(&operator).copied().unwrap_or_default();
+            let score = metrics.score();
+            let reward = self.rewards_manager
+                .calculate_operator_rewards(&operator, stats.total_stake, score)
+                .await?;
+            self.staking_manager
+                .record_epoch_performance(operator, epoch, &metrics)
+                .await?;
+            total += reward;
+        }
+        self.staking_manager.prune_performance(epoch).await?;
+        info!("Closed epoch {} with {} in operator rewards", epoch, total);
+        Ok(())
+    }
+
+    /// Count an operator's signed vote on a slot, weighted by the stake
+    /// delegated to it; returns the slot's attestation once operators
+    /// holding the consensus threshold of all stake agree, ready for
+    /// [`CambrianService::post_attestation`]
+    pub async fn cast_vote(&self, vote: ConsensusVote) -> Result<Option<SlotAttestation>> {
+        let operators = self.staking_manager.get_all_operators().await;
+        let stake = operators.get(&vote.voter).map_or(0, |stats| stats.total_stake);
+        if stake == 0 {
+            return Err(StakingError::UnstakedVoter(vote.voter));
+        }
+        let total_stake = operators.values().map(|stats| stats.total_stake).sum();
+
+        let (slot, voter) = (vote.slot, vote.voter);
+        let attestation = self.consensus_manager.cast_vote(vote, stake, total_stake).await?;
+        self.performance.record_vote(slot, voter);
+        if let Some(attestation) = &attestation {
+            for vote in &attestation.votes {
+                self.performance.record_message(vote.voter, true);
+            }
+            let expected: Vec<Pubkey> = operators
+                .iter()
+                .filter(|(_, stats)| stats.total_stake > 0)
+                .map(|(operator, _)| *operator)
+                .collect();
+            self.performance.close_slot(slot, &expected);
+        }
+        Ok(attestation)
+    }
+
+    /// Consensus participation is reported here to score operators
+    pub fn performance_tracker(&self) -> Arc<PerformanceTracker> {
+        self.performance.clone()
+    }
+
     pub async fn get_operator_info(&self, operator: &Pubkey) -> Result<OperatorInfo> {
         let stats = self.staking_manager.get_operator_stats(operator).await?;
+        let metrics = self.performance.current(operator);
         Ok(OperatorInfo {
+            performance: metrics.score(),
+            rewards: stats.reward_balance,
+            metrics,
             stats,
-            performance: 1.0,
-            rewards: 0,
         })
     }
 
     async fn validate_stake(&self, amount: u64, _operator: &Pubkey) -> Result<()> {
-        if amount < self.staking_manager.config().min_stake {
-            return Err(anyhow::anyhow!("Stake amount below minimum threshold"));
+        let minimum = self.staking_manager.config().min_stake;
+        if amount < minimum {
+            return Err(StakingError::BelowMinimumStake { amount, minimum });
         }
         Ok(())
     }
 
-    async fn start_reward_distribution(&self) -> Result<()> {
+    async fn start_reward_distribution(&self, shutdown: Option<ShutdownToken>) -> Result<()> {
         let rewards_manager = self.rewards_manager.clone();
+        let staking_manager = self.staking_manager.clone();
         let distribution_interval = self.rewards_manager.distribution_interval().await;
 
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(distribution_interval);
 
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown::cancelled(shutdown.as_ref()) => {
+                        info!("Stopped reward distribution");
+                        return;
+                    }
+                }
                 
-                match rewards_manager.distribute_rewards().await {
+                match rewards_manager.distribute_rewards(&staking_manager).await {
                     Ok(_) => {
                         info!("Successfully distributed rewards for epoch");
                     }
@@ -125,12 +287,31 @@ impl JitoStakingService {
     pub fn get_config(&self) -> &StakingConfig {
         self.staking_manager.config()
     }
+
+    pub fn staking_manager(&self) -> Arc<StakingManager> {
+        self.staking_manager.clone()
+    }
+
+    /// Violations are reported here to slash operators
+    pub fn slashing_manager(&self) -> Arc<SlashingManager> {
+        self.slashing_manager.clone()
+    }
+
+    /// Up to `limit` of the most recent slashes of `operator`, or of every
+    /// operator, newest first
+    pub fn slash_history(&self, operator: Option<&Pubkey>, limit: usize) -> Vec<SlashEvent> {
+        self.staking_manager.slash_history(operator, limit)
+    }
 }
 
 #[derive(Debug)]
 pub struct OperatorInfo {
+    /// Accounting, with the performance score of the last closed epoch
     pub stats: OperatorStats,
+    /// Performance score in the current epoch so far
     pub performance: f64,
+    pub metrics: PerformanceMetrics,
+    /// Rewards earned and not yet paid out
     pub rewards: u64,
 }
 
@@ -140,11 +321,3 @@ struct ConsensusState {
     consecutive_misses: u32,
     last_update: i64,
 }
-
-#[derive(Debug, Clone)]
-struct PerformanceMetrics {
-    uptime: f64,
-    response_time: f64,
-    message_success_rate: f64,
-    timestamp: i64,
-}
\ No newline at end of file
@@ -1,33 +1,186 @@
 // crates/windexer-jito-staking/src/main.rs
 
-use windexer_jito_staking::{JitoStakingService, StakingConfig};
+use windexer_jito_staking::{
+    ChainSync, ChainSyncConfig, EpochConfig, EpochManager, JitoStakingService, RpcSlotSource, RpcStakeSource,
+    StakingConfig, DEFAULT_PERFORMANCE_RETENTION_EPOCHS,
+};
+use windexer_common::{
+    alerts::{self, AlertBus},
+    config::{ConfigArgs, ConfigCommand, Validate},
+    errors::{Error, Result as ConfigResult},
+    rpc_provider::RpcProviderConfig,
+    shutdown::{Shutdown, Stage, DEFAULT_SHUTDOWN_TIMEOUT},
+};
+use std::sync::Arc;
 use anyhow::Result;
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(name = "windexer-jito-staking", version, about = "wIndexer staking service")]
+struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Configuration checks
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// Staking settings, from a `--config` file, `WINDEXER_*` variables and flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    min_stake: u64,
+    min_operators: u32,
+    consensus_threshold: f64,
+    reward_rate: f64,
+    distribution_interval_secs: u64,
+    slash_threshold: f64,
+    min_uptime: f64,
+    /// Epochs unstaked stake cools down before it can be withdrawn
+    cooldown_epochs: u64,
+    /// Epochs of operator performance history kept
+    performance_retention_epochs: u64,
+    /// Seconds to stop the background tasks after Ctrl+C or SIGTERM
+    shutdown_timeout_secs: u64,
+    /// RPC provider the on-chain stake is read from
+    rpc: Option<RpcProviderConfig>,
+    /// Reconcile local stake with Jito vault delegations; needs `rpc`
+    chain_sync: Option<ChainSyncConfig>,
+    /// Process epochs as the cluster's Solana epochs change; needs `rpc`
+    epochs: Option<EpochConfig>,
+    /// Store to persist operator and delegation state in; kept in memory
+    /// only when unset
+    #[cfg(feature = "store")]
+    storage: Option<windexer_store::config::StoreConfig>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            min_stake: 1_000_000_000,
+            min_operators: 3,
+            consensus_threshold: 0.66,
+            reward_rate: 0.10,
+            distribution_interval_secs: 86400,
+            slash_threshold: 0.95,
+            min_uptime: 0.98,
+            cooldown_epochs: 1,
+            performance_retention_epochs: DEFAULT_PERFORMANCE_RETENTION_EPOCHS,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT.as_secs(),
+            rpc: None,
+            chain_sync: None,
+            epochs: None,
+            #[cfg(feature = "store")]
+            storage: None,
+        }
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> ConfigResult<()> {
+        for (name, value) in [
+            ("consensus_threshold", self.consensus_threshold),
+            ("reward_rate", self.reward_rate),
+            ("slash_threshold", self.slash_threshold),
+            ("min_uptime", self.min_uptime),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(Error::Config(format!("{} must be between 0 and 1, got {}", name, value)));
+            }
+        }
+        if self.distribution_interval_secs == 0 {
+            return Err(Error::Config("distribution_interval_secs must be positive".to_string()));
+        }
+        if self.chain_sync.is_some() && self.rpc.is_none() {
+            return Err(Error::Config("chain_sync needs an rpc provider".to_string()));
+        }
+        if self.epochs.is_some() && self.rpc.is_none() {
+            return Err(Error::Config("epochs needs an rpc provider".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl From<Settings> for StakingConfig {
+    fn from(settings: Settings) -> Self {
+        Self {
+            min_stake: settings.min_stake,
+            min_operators: settings.min_operators,
+            consensus_threshold: settings.consensus_threshold,
+            reward_rate: settings.reward_rate,
+            distribution_interval: Duration::from_secs(settings.distribution_interval_secs),
+            slash_threshold: settings.slash_threshold,
+            min_uptime: settings.min_uptime,
+            cooldown_epochs: settings.cooldown_epochs,
+            performance_retention_epochs: settings.performance_retention_epochs,
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse();
+    let loader = args.config.loader()?;
+    if let Some(Command::Config(ConfigCommand::Validate)) = args.command {
+        println!("{}", loader.check::<Settings>()?);
+        return Ok(());
+    }
+
     // Setup minimal logging
     tracing_subscriber::fmt::init();
     
-    // Create default config
-    let config = StakingConfig {
-        min_stake: 1_000_000_000,
-        min_operators: 3,
-        consensus_threshold: 0.66,
-        reward_rate: 0.10,
-        distribution_interval: std::time::Duration::from_secs(86400),
-        slash_threshold: 0.95,
-        min_uptime: 0.98,
-    };
+    let settings: Settings = loader.load()?;
+    let shutdown = Shutdown::new(Duration::from_secs(settings.shutdown_timeout_secs));
+    #[cfg(feature = "store")]
+    let storage = settings.storage.clone();
+    let chain_sync = settings.chain_sync.clone().zip(settings.rpc.clone());
+    let epochs = settings.epochs.clone().zip(settings.rpc.clone());
+    let config = StakingConfig::from(settings);
     
     // Initialize service
+    #[cfg(feature = "store")]
+    let staking_service = match storage {
+        Some(storage) => {
+            use windexer_store::{factory::WindexerStorageFactory, traits::StorageFactory};
+            let storage = WindexerStorageFactory::new(storage).create_storage().await?;
+            JitoStakingService::with_storage(config, storage).await?
+        }
+        None => JitoStakingService::new(config),
+    };
+    #[cfg(not(feature = "store"))]
     let staking_service = JitoStakingService::new(config);
     
     // Start service
-    staking_service.start().await?;
+    staking_service.start_with_shutdown(shutdown.token(Stage::Services)).await?;
+    let staking_service = Arc::new(staking_service);
+
+    if let Some((sync_config, rpc)) = chain_sync {
+        let bus = AlertBus::new();
+        if !sync_config.webhooks.is_empty() {
+            alerts::spawn_webhooks(&bus, sync_config.webhooks.clone(), Some(shutdown.token(Stage::Services)));
+        }
+        let source = Arc::new(RpcStakeSource::new(rpc.build()?, sync_config.program_id.clone()));
+        let sync = Arc::new(ChainSync::new(sync_config, staking_service.staking_manager(), source, bus)?);
+        sync.spawn(Some(shutdown.token(Stage::Services)));
+    }
+
+    if let Some((epoch_config, rpc)) = epochs {
+        let source = Arc::new(RpcSlotSource::new(rpc.build()?));
+        let epochs = Arc::new(EpochManager::new(epoch_config, staking_service.clone(), source));
+        epochs.spawn(Some(shutdown.token(Stage::Services)));
+    }
     
-    // Keep running until Ctrl+C
-    tokio::signal::ctrl_c().await?;
-    println!("Shutting down...");
+    // Keep running until Ctrl+C or SIGTERM
+    shutdown.signalled().await;
     
     Ok(())
-}
\ No newline at end of file
+}
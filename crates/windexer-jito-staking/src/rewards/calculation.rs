@@ -2,7 +2,10 @@
 
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
-use anyhow::Result;
+use crate::error::Result;
+
+/// Solana epochs in a year, at about two days each
+pub const EPOCHS_PER_YEAR: f64 = 182.5;
 
 pub struct RewardCalculator {
     base_reward_rate: f64,
@@ -17,14 +20,18 @@ impl RewardCalculator {
         }
     }
 
-    pub async fn calculate_reward(&self, operator: &Pubkey, performance_score: f64) -> Result<u64> {
+    /// Reward for one epoch of `stake` at the annual base rate, scaled by
+    /// the operator's performance score and multiplier
+    pub async fn calculate_reward(&self, operator: &Pubkey, stake: u64, performance_score: f64) -> Result<u64> {
         let multiplier = self.performance_multipliers
             .get(operator)
             .copied()
             .unwrap_or(1.0);
-            
-        let reward = (self.base_reward_rate * performance_score * multiplier) as u64;
-        Ok(reward)
+
+        let reward = stake as f64 * self.base_reward_rate / EPOCHS_PER_YEAR
+            * performance_score.clamp(0.0, 1.0)
+            * multiplier;
+        Ok(reward as u64)
     }
 
     pub fn update_performance_multiplier(&mut self, operator: &Pubkey, multiplier: f64) {
@@ -1,12 +1,24 @@
 // crates/windexer-jito-staking/src/rewards/distribution.rs
 
+//! Reward payouts
+//!
+//! Each operator's reward is split into the operator's commission and a net
+//! amount shared among its delegators in proportion to their stake, as
+//! listed by [`StakingManager::get_delegators_for_operator`]. Both are
+//! credited to the unpaid reward balances the [`StakingManager`] keeps;
+//! rounding dust and the net amount of operators without delegators go to
+//! the operator.
+
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
-use anyhow::Result;
+use crate::{error::Result, staking::StakingManager};
 use tokio::time::Duration;
 use tracing::{info, warn};
 use std::sync::RwLock;
 
+/// Share of each reward the operator keeps
+const COMMISSION_RATE: f64 = 0.10;
+
 pub struct RewardDistributor {
     distribution_interval: Duration,
     last_distribution: RwLock<i64>,
@@ -22,28 +34,35 @@ impl RewardDistributor {
         }
     }
 
-    pub async fn distribute_epoch_rewards(&self, rewards: &HashMap<Pubkey, u64>) -> Result<()> {
+    /// Whether `distribution_interval` passed since the last distribution
+    pub fn is_due(&self) -> bool {
         let current_time = chrono::Utc::now().timestamp();
-        
-        {
-            let last_dist = *self.last_distribution.read().unwrap();
-            if current_time - last_dist < self.distribution_interval.as_secs() as i64 {
-                return Ok(());
-            }
+        current_time - *self.last_distribution.read().unwrap() >= self.distribution_interval.as_secs() as i64
+    }
+
+    /// Pay out `rewards` per operator, if a distribution is due
+    pub async fn distribute_epoch_rewards(&self, rewards: &HashMap<Pubkey, u64>, staking: &StakingManager) -> Result<()> {
+        if !self.is_due() {
+            return Ok(());
         }
+        self.distribute(rewards, staking).await
+    }
 
+    /// Pay out `rewards` per operator now; rewards that fail are retried
+    /// with the operator's next distribution
+    pub async fn distribute(&self, rewards: &HashMap<Pubkey, u64>, staking: &StakingManager) -> Result<()> {
         {
-            *self.last_distribution.write().unwrap() = current_time;
+            *self.last_distribution.write().unwrap() = chrono::Utc::now().timestamp();
         }
 
         for (operator, amount) in rewards {
-            match self.execute_distribution(operator, *amount).await {
+            match self.execute_distribution(operator, *amount, staking).await {
                 Ok(_) => {
                     info!("Distributed {} rewards to operator {}", amount, operator);
                 }
                 Err(e) => {
                     warn!("Failed to distribute rewards to {}: {}", operator, e);
-                    self.pending_distributions.write().unwrap().insert(*operator, *amount);
+                    *self.pending_distributions.write().unwrap().entry(*operator).or_default() += *amount;
                 }
             }
         }
@@ -51,7 +70,7 @@ impl RewardDistributor {
         Ok(())
     }
 
-    async fn execute_distribution(&self, operator: &Pubkey, amount: u64) -> Result<()> {
+    async fn execute_distribution(&self, operator: &Pubkey, amount: u64, staking: &StakingManager) -> Result<()> {
         // Handle pending distributions first
         let pending = {
             let mut pending_dist = self.pending_distributions.write().unwrap();
@@ -59,40 +78,46 @@ impl RewardDistributor {
         };
 
         if let Some(pending_amount) = pending {
-            self.process_distribution(operator, pending_amount).await?;
+            if let Err(e) = self.process_distribution(operator, pending_amount, staking).await {
+                *self.pending_distributions.write().unwrap().entry(*operator).or_default() += pending_amount;
+                return Err(e);
+            }
         }
 
         // Process current distribution
-        self.process_distribution(operator, amount).await?;
+        self.process_distribution(operator, amount, staking).await?;
 
         Ok(())
     }
 
-    async fn process_distribution(&self, operator: &Pubkey, amount: u64) -> Result<()> {
-        let commission_rate = 0.10;
-        let commission = (amount as f64 * commission_rate) as u64;
+    async fn process_distribution(&self, operator: &Pubkey, amount: u64, staking: &StakingManager) -> Result<()> {
+        let commission = (amount as f64 * COMMISSION_RATE) as u64;
         let net_amount = amount - commission;
 
-        self.transfer_commission(operator, commission).await?;
-
-        self.distribute_to_delegators(operator, net_amount).await?;
+        let shares = Self::pro_rata(net_amount, &staking.get_delegators_for_operator(operator));
+        let distributed: u64 = shares.iter().map(|(_, share)| share).sum();
+        staking.credit_rewards(*operator, amount - distributed, &shares).await?;
 
         info!(
-            "Processed distribution for operator {}: amount={}, commission={}, net={}",
-            operator, amount, commission, net_amount
+            "Processed distribution for operator {}: amount={}, commission={}, net={}, delegators={}",
+            operator, amount, commission, net_amount, shares.len()
         );
 
         Ok(())
     }
 
-    async fn transfer_commission(&self, operator: &Pubkey, amount: u64) -> Result<()> {
-        info!("Transferring commission {} to operator {}", amount, operator);
-        Ok(())
-    }
-
-    async fn distribute_to_delegators(&self, operator: &Pubkey, amount: u64) -> Result<()> {
-        info!("Distributing {} to delegators of operator {}", amount, operator);
-        Ok(())
+    /// `amount` split among `delegators` in proportion to their stake,
+    /// rounded down
+    fn pro_rata(amount: u64, delegators: &[(Pubkey, u64)]) -> Vec<(Pubkey, u64)> {
+        let total: u128 = delegators.iter().map(|(_, stake)| *stake as u128).sum();
+        if total == 0 {
+            return Vec::new();
+        }
+        delegators
+            .iter()
+            .map(|(staker, stake)| (*staker, (amount as u128 * *stake as u128 / total) as u64))
+            .filter(|(_, share)| *share > 0)
+            .collect()
     }
 
     pub fn distribution_interval(&self) -> Duration {
@@ -102,26 +127,37 @@ impl RewardDistributor {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, crate::staking::types::StakingConfig};
 
     #[tokio::test]
     async fn test_reward_distribution() {
         let distributor = RewardDistributor::new(Duration::from_secs(3600));
+        let staking = StakingManager::new(StakingConfig::default());
+        let (operator, large, small) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        staking.process_stake(3_000, large, operator).await.unwrap();
+        staking.process_stake(1_000, small, operator).await.unwrap();
         let mut rewards = HashMap::new();
-        rewards.insert(Pubkey::new_unique(), 1000);
-        
-        assert!(distributor.distribute_epoch_rewards(&rewards).await.is_ok());
+        rewards.insert(operator, 1000);
+
+        assert!(distributor.distribute_epoch_rewards(&rewards, &staking).await.is_ok());
+        let stats = staking.get_operator_stats(&operator).await.unwrap();
+        assert_eq!(stats.reward_balance, 100);
+        assert_eq!(stats.delegator_rewards[&large], 675);
+        assert_eq!(stats.delegator_rewards[&small], 225);
+        assert!(!distributor.is_due());
     }
 
     #[tokio::test]
     async fn test_pending_distributions() {
         let distributor = RewardDistributor::new(Duration::from_secs(3600));
+        let staking = StakingManager::new(StakingConfig::default());
         let operator = Pubkey::new_unique();
         
         distributor.pending_distributions.write().unwrap().insert(operator, 500);
         let amount = 1000;
         
-        assert!(distributor.execute_distribution(&operator, amount).await.is_ok());
+        assert!(distributor.execute_distribution(&operator, amount, &staking).await.is_ok());
         assert!(distributor.pending_distributions.read().unwrap().is_empty());
+        assert_eq!(staking.get_operator_stats(&operator).await.unwrap().reward_balance, 1500);
     }
-}
\ No newline at end of file
+}
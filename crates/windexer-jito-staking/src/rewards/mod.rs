@@ -3,10 +3,13 @@
 use solana_sdk::pubkey::Pubkey;
 use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::RwLock;
-use anyhow::Result;
+use crate::{error::Result, staking::StakingManager};
 
 pub mod calculation;
 pub mod distribution;
+pub mod performance;
+
+pub use performance::{PerformanceMetrics, PerformanceTracker};
 
 pub struct RewardsManager {
     reward_calculator: Arc<RwLock<calculation::RewardCalculator>>,
@@ -27,24 +30,43 @@ impl RewardsManager {
         }
     }
 
-    pub async fn calculate_operator_rewards(&self, operator: &Pubkey, performance_score: f64) -> Result<u64> {
+    /// Reward `operator` for an epoch of `stake` at `performance_score`,
+    /// adding it to the rewards awaiting distribution
+    pub async fn calculate_operator_rewards(&self, operator: &Pubkey, stake: u64, performance_score: f64) -> Result<u64> {
         let calculator = self.reward_calculator.read().await;
         let mut rewards = self.epoch_rewards.write().await;
         
-        let reward_amount = calculator.calculate_reward(operator, performance_score).await?;
+        let reward_amount = calculator.calculate_reward(operator, stake, performance_score).await?;
         *rewards.entry(*operator).or_default() += reward_amount;
         
         Ok(reward_amount)
     }
 
-    pub async fn distribute_rewards(&self) -> Result<()> {
+    /// Pay the rewards awaiting distribution out to operators and their
+    /// delegators in `staking`, once the distribution interval passed
+    pub async fn distribute_rewards(&self, staking: &StakingManager) -> Result<()> {
         let distributor = self.reward_distributor.read().await;
-        let rewards = self.epoch_rewards.read().await;
+        if !distributor.is_due() {
+            return Ok(());
+        }
+        let rewards = std::mem::take(&mut *self.epoch_rewards.write().await);
         
-        distributor.distribute_epoch_rewards(&rewards).await?;
+        distributor.distribute_epoch_rewards(&rewards, staking).await?;
         Ok(())
     }
 
+    /// Pay the rewards awaiting distribution out now, at the end of an epoch
+    pub async fn distribute_epoch(&self, staking: &StakingManager) -> Result<()> {
+        let distributor = self.reward_distributor.read().await;
+        let rewards = std::mem::take(&mut *self.epoch_rewards.write().await);
+        distributor.distribute(&rewards, staking).await
+    }
+
+    /// Rewards awaiting distribution per operator
+    pub async fn epoch_rewards(&self) -> HashMap<Pubkey, u64> {
+        self.epoch_rewards.read().await.clone()
+    }
+
     pub async fn distribution_interval(&self) -> Duration {
         self.distribution_interval
     }
@@ -0,0 +1,202 @@
+// crates/windexer-jito-staking/src/rewards/performance.rs
+
+//! Operator performance from consensus participation
+//!
+//! The consensus protocol reports every vote it counts with
+//! [`PerformanceTracker::record_vote`] and every slot it finalizes with
+//! [`PerformanceTracker::close_slot`]. Each closed slot counts once for every
+//! operator expected to vote: as attended if it voted before finalization,
+//! with its delay after the slot's first vote as its response time.
+//! [`PerformanceTracker::record_message`] counts whether each of its votes
+//! agreed with the hash the network finalized.
+//!
+//! The counts accumulate over a window, which the epoch rollover closes with
+//! [`PerformanceTracker::roll_window`] to score the epoch's rewards and keep
+//! the epoch's [`PerformanceRecord`](windexer_common::types::PerformanceRecord).
+
+use {
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::RwLock,
+        time::{Duration, Instant},
+    },
+    windexer_common::utils::current_timestamp,
+};
+
+/// Slots kept open for votes; older unfinalized slots are dropped uncounted
+const MAX_OPEN_SLOTS: u64 = 512;
+
+/// Response time at or under which an operator counts as fully responsive
+const TARGET_RESPONSE: Duration = Duration::from_secs(1);
+
+/// Response time at which an operator counts as not responsive at all
+const MAX_RESPONSE: Duration = Duration::from_secs(10);
+
+/// Share of the score that depends on uptime alone; the rest also scales
+/// with responsiveness
+const UPTIME_WEIGHT: f64 = 0.8;
+
+/// An operator's consensus participation over a window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    /// Share of the slots it was expected to vote on that it voted on
+    pub uptime: f64,
+    /// Mean seconds its votes trailed the first vote of their slot
+    pub response_time: f64,
+    /// Slots it was expected to vote on
+    pub slots: u64,
+    /// Slots it was expected to vote on and did not
+    #[serde(default)]
+    pub missed_rounds: u64,
+    /// Share of its votes that agreed with the finalized hash, if it sent any
+    #[serde(default)]
+    pub message_success_rate: Option<f64>,
+    /// Unix timestamp in seconds a slot it voted on last closed
+    pub last_vote: Option<i64>,
+}
+
+impl PerformanceMetrics {
+    /// Score between 0 and 1; operators without slots score 1 so new
+    /// operators are not penalized before they had a chance to vote
+    pub fn score(&self) -> f64 {
+        if self.slots == 0 {
+            return 1.0;
+        }
+        let target = TARGET_RESPONSE.as_secs_f64();
+        let max = MAX_RESPONSE.as_secs_f64();
+        let responsiveness = 1.0 - ((self.response_time - target) / (max - target)).clamp(0.0, 1.0);
+        self.uptime * (UPTIME_WEIGHT + (1.0 - UPTIME_WEIGHT) * responsiveness)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Participation {
+    slots: u64,
+    votes: u64,
+    response: Duration,
+    last_vote: Option<i64>,
+    messages: u64,
+    accepted_messages: u64,
+}
+
+impl Participation {
+    fn metrics(&self) -> PerformanceMetrics {
+        PerformanceMetrics {
+            uptime: if self.slots == 0 { 0.0 } else { self.votes as f64 / self.slots as f64 },
+            response_time: if self.votes == 0 { 0.0 } else { self.response.as_secs_f64() / self.votes as f64 },
+            slots: self.slots,
+            missed_rounds: self.slots - self.votes,
+            message_success_rate: (self.messages > 0).then(|| self.accepted_messages as f64 / self.messages as f64),
+            last_vote: self.last_vote,
+        }
+    }
+}
+
+/// Votes seen for one slot, with when they arrived
+struct OpenSlot {
+    first_vote: Instant,
+    votes: HashMap<Pubkey, Instant>,
+}
+
+#[derive(Default)]
+pub struct PerformanceTracker {
+    open: RwLock<BTreeMap<u64, OpenSlot>>,
+    window: RwLock<HashMap<Pubkey, Participation>>,
+}
+
+impl PerformanceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A vote of `operator` for `slot` was counted
+    pub fn record_vote(&self, slot: u64, operator: Pubkey) {
+        let now = Instant::now();
+        let mut open = self.open.write().unwrap();
+        open.entry(slot)
+            .or_insert_with(|| OpenSlot { first_vote: now, votes: HashMap::new() })
+            .votes
+            .entry(operator)
+            .or_insert(now);
+    }
+
+    /// `slot` was finalized; count it for every operator in `expected`
+    pub fn close_slot(&self, slot: u64, expected: &[Pubkey]) {
+        let mut open = self.open.write().unwrap();
+        let votes = open.remove(&slot);
+        if let Some(before) = slot.checked_sub(MAX_OPEN_SLOTS) {
+            *open = open.split_off(&before);
+        }
+        drop(open);
+
+        let now = current_timestamp();
+        let mut window = self.window.write().unwrap();
+        for operator in expected {
+            let participation = window.entry(*operator).or_default();
+            participation.slots += 1;
+            let voted = votes.as_ref().and_then(|slot| Some((slot.first_vote, *slot.votes.get(operator)?)));
+            if let Some((first_vote, voted_at)) = voted {
+                participation.votes += 1;
+                participation.response += voted_at.saturating_duration_since(first_vote);
+                participation.last_vote = Some(now);
+            }
+        }
+    }
+
+    /// A signed vote of `operator` was `accepted` as agreeing with the
+    /// finalized hash, or not
+    pub fn record_message(&self, operator: Pubkey, accepted: bool) {
+        let mut window = self.window.write().unwrap();
+        let participation = window.entry(operator).or_default();
+        participation.messages += 1;
+        if accepted {
+            participation.accepted_messages += 1;
+        }
+    }
+
+    /// Performance of `operator` in the open window
+    pub fn current(&self, operator: &Pubkey) -> PerformanceMetrics {
+        self.window
+            .read()
+            .unwrap()
+            .get(operator)
+            .map(Participation::metrics)
+            .unwrap_or_default()
+    }
+
+    /// Close the window, returning the performance of every operator in it
+    pub fn roll_window(&self) -> HashMap<Pubkey, PerformanceMetrics> {
+        std::mem::take(&mut *self.window.write().unwrap())
+            .into_iter()
+            .map(|(operator, participation)| (operator, participation.metrics()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_missed_slots() {
+        let tracker = PerformanceTracker::new();
+        let (steady, flaky) = (Pubkey::new_unique(), Pubkey::new_unique());
+        for slot in 0..4 {
+            tracker.record_vote(slot, steady);
+            if slot % 2 == 0 {
+                tracker.record_vote(slot, flaky);
+            }
+            tracker.close_slot(slot, &[steady, flaky]);
+        }
+
+        assert_eq!(tracker.current(&steady).uptime, 1.0);
+        assert_eq!(tracker.current(&flaky).uptime, 0.5);
+        assert_eq!(tracker.current(&flaky).missed_rounds, 2);
+        let window = tracker.roll_window();
+        assert!(window[&steady].score() > 0.99);
+        assert!((window[&flaky].score() - 0.5).abs() < 0.01);
+        assert_eq!(tracker.current(&steady).slots, 0);
+    }
+}
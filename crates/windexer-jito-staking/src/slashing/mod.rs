@@ -1,50 +1,124 @@
 // crates/windexer-jito-staking/src/slashing/mod.rs
 
-use solana_sdk::pubkey::Pubkey;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use anyhow::Result;
+//! Slashing of misbehaving operators
+//!
+//! Every violation reported to [`SlashingManager::process_violation`] adds to
+//! the operator's violation history. Once the history's total severity
+//! reaches `slash_threshold`, the violation's penalty is deducted from the
+//! operator's stake by [`StakingManager::slash`], which records and publishes
+//! the slash, and the history starts over.
+
+use {
+    crate::{
+        error::Result,
+        staking::{types::SlashEvent, StakingManager},
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{fmt, str::FromStr, sync::Arc},
+    tokio::sync::RwLock,
+    tracing::warn,
+};
 
 pub mod monitor;
 pub mod penalties;
 
 use monitor::SlashingMonitor;
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ViolationType {
     LowUptime,
     DoubleProposal,
     DoubleVote,
     MaliciousValidation,
+    /// Voted for slot content that differs from what the network finalized
+    DivergentData,
+}
+
+impl ViolationType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LowUptime => "low_uptime",
+            Self::DoubleProposal => "double_proposal",
+            Self::DoubleVote => "double_vote",
+            Self::MaliciousValidation => "malicious_validation",
+            Self::DivergentData => "divergent_data",
+        }
+    }
+}
+
+impl fmt::Display for ViolationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ViolationType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "low_uptime" => Ok(Self::LowUptime),
+            "double_proposal" => Ok(Self::DoubleProposal),
+            "double_vote" => Ok(Self::DoubleVote),
+            "malicious_validation" => Ok(Self::MaliciousValidation),
+            "divergent_data" => Ok(Self::DivergentData),
+            _ => Err(format!("Unknown violation type {}", s)),
+        }
+    }
 }
 
 pub struct SlashingManager {
     monitor: Arc<RwLock<SlashingMonitor>>,
     penalty_calculator: Arc<RwLock<penalties::PenaltyCalculator>>,
+    staking: Arc<StakingManager>,
 }
 
 impl SlashingManager {
-    pub fn new(slash_threshold: f64, min_uptime: f64) -> Self {
+    /// Slashes are deducted from the operator stake `staking` holds
+    pub fn new(slash_threshold: f64, min_uptime: f64, staking: Arc<StakingManager>) -> Self {
         Self {
             monitor: Arc::new(RwLock::new(SlashingMonitor::new(slash_threshold, min_uptime))),
             penalty_calculator: Arc::new(RwLock::new(penalties::PenaltyCalculator::new())),
+            staking,
         }
     }
 
-    pub async fn process_violation(&self, operator: &Pubkey, violation_type: ViolationType) -> Result<()> {
+    /// Record a violation of `operator`, returning the slash it triggered
+    pub async fn process_violation(
+        &self,
+        operator: &Pubkey,
+        violation_type: ViolationType,
+    ) -> Result<Option<SlashEvent>> {
         let mut monitor = self.monitor.write().await;
         let calculator = self.penalty_calculator.read().await;
-        
-        if monitor.should_slash(operator, &violation_type).await? {
-            let penalty = calculator.calculate_penalty(operator, &violation_type).await?;
-            self.execute_slash(operator, penalty).await?;
+
+        if !monitor.should_slash(operator, &violation_type).await? {
+            return Ok(None);
         }
-        
-        Ok(())
+        let penalty = calculator.calculate_penalty(operator, &violation_type).await?;
+        let slash = self.execute_slash(operator, penalty, violation_type).await?;
+        monitor.reset(operator);
+        Ok(Some(slash))
+    }
+
+    async fn execute_slash(
+        &self,
+        operator: &Pubkey,
+        penalty_amount: u64,
+        violation: ViolationType,
+    ) -> Result<SlashEvent> {
+        let slash = self.staking.slash(*operator, penalty_amount, violation).await?;
+        warn!(
+            "Slashed operator {} by {} of {} stake for {}",
+            operator, slash.amount, slash.stake_before, violation
+        );
+        Ok(slash)
     }
 
-    async fn execute_slash(&self, _operator: &Pubkey, _penalty_amount: u64) -> Result<()> {
-        // Implement slashing execution logic
-        Ok(())
+    /// Slashes of `operator`, or of every operator, newest first
+    pub fn slash_history(&self, operator: Option<&Pubkey>, limit: usize) -> Vec<SlashEvent> {
+        self.staking.slash_history(operator, limit)
     }
-}
\ No newline at end of file
+}
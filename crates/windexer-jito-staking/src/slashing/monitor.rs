@@ -2,7 +2,7 @@
 
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
-use anyhow::Result;
+use crate::error::{Result, StakingError};
 use crate::slashing::ViolationType;
 
 pub struct SlashingMonitor {
@@ -36,7 +36,7 @@ impl SlashingMonitor {
             
         let violation_record = ViolationRecord {
             timestamp: crate::utils::current_time(),
-            violation_type: violation.clone(),
+            violation_type: *violation,
             severity,
         };
         
@@ -50,12 +50,13 @@ impl SlashingMonitor {
             ViolationType::DoubleProposal => 0.7,
             ViolationType::DoubleVote => 0.8,
             ViolationType::MaliciousValidation => 1.0,
+            ViolationType::DivergentData => 1.0,
         }
     }
 
     fn check_slash_threshold(&self, operator: &Pubkey) -> Result<bool> {
         let records = self.violation_history.get(operator)
-            .ok_or_else(|| anyhow::anyhow!("No violation history found"))?;
+            .ok_or(StakingError::NoViolationHistory(*operator))?;
             
         let total_severity: f64 = records.iter()
             .map(|r| r.severity)
@@ -64,6 +65,11 @@ impl SlashingMonitor {
         Ok(total_severity >= self.slash_threshold)
     }
 
+    /// Forget the violations of `operator`, once they were slashed for
+    pub fn reset(&mut self, operator: &Pubkey) {
+        self.violation_history.remove(operator);
+    }
+
     pub async fn check_uptime(&self, uptime: f64) -> Result<bool> {
         Ok(uptime >= self.min_uptime)
     }
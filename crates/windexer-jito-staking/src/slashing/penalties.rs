@@ -2,7 +2,7 @@
 
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
-use anyhow::Result;
+use crate::error::Result;
 use crate::slashing::ViolationType;
 
 pub struct PenaltyCalculator {
@@ -18,6 +18,7 @@ impl PenaltyCalculator {
         base_penalties.insert(ViolationType::DoubleProposal, 5000);
         base_penalties.insert(ViolationType::DoubleVote, 7500);
         base_penalties.insert(ViolationType::MaliciousValidation, 10000);
+        base_penalties.insert(ViolationType::DivergentData, 10000);
         
         Self {
             base_penalties,
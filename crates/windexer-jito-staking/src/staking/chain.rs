@@ -0,0 +1,228 @@
+// crates/windexer-jito-staking/src/staking/chain.rs
+
+//! Jito vault delegations on chain
+//!
+//! Restaked stake reaches an operator through a `VaultOperatorDelegation`
+//! account of the Jito vault program, one per vault and operator. A
+//! [`StakeSource`] lists those accounts: [`RpcStakeSource`] asks an RPC
+//! provider with `getProgramAccounts`, [`FeedStakeSource`] keeps the ones the
+//! indexer ingests from its [`DataFeed`].
+
+use {
+    crate::error::{Result, StakingError},
+    async_trait::async_trait,
+    serde_json::{json, Value},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        str::FromStr,
+        sync::{Arc, RwLock},
+    },
+    tokio::{sync::broadcast::error::RecvError, task::JoinHandle},
+    tracing::warn,
+    windexer_common::{
+        feed::{DataFeed, FeedEvent},
+        rpc_provider::RpcProvider,
+        shutdown::{self, ShutdownToken},
+    },
+};
+
+/// The Jito vault program
+pub const JITO_VAULT_PROGRAM_ID: &str = "Vau1t6sLNxnzB7ZDsef8TLbPLfyZMYXH8WTNqUdm9g8";
+
+/// First byte of every `VaultOperatorDelegation` account
+const DELEGATION_DISCRIMINATOR: u8 = 4;
+/// [`DELEGATION_DISCRIMINATOR`] in base58, for `memcmp` filters
+const DELEGATION_DISCRIMINATOR_BS58: &str = "5";
+
+// Offsets after the 8 byte discriminator header
+const VAULT_OFFSET: usize = 8;
+const OPERATOR_OFFSET: usize = 40;
+const STAKED_OFFSET: usize = 72;
+const ENQUEUED_OFFSET: usize = 80;
+const COOLING_DOWN_OFFSET: usize = 88;
+const LAST_UPDATE_SLOT_OFFSET: usize = 352;
+
+/// A vault's delegation to an operator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultDelegation {
+    pub vault: Pubkey,
+    pub operator: Pubkey,
+    pub staked_amount: u64,
+    /// Stake that starts cooling down at the next epoch
+    pub enqueued_for_cooldown_amount: u64,
+    pub cooling_down_amount: u64,
+    pub last_update_slot: u64,
+}
+
+impl VaultDelegation {
+    /// Decode `VaultOperatorDelegation` account data
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < LAST_UPDATE_SLOT_OFFSET + 8 || data[0] != DELEGATION_DISCRIMINATOR {
+            return None;
+        }
+        let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).ok();
+        let amount = |offset: usize| data[offset..offset + 8].try_into().ok().map(u64::from_le_bytes);
+        Some(Self {
+            vault: pubkey(VAULT_OFFSET)?,
+            operator: pubkey(OPERATOR_OFFSET)?,
+            staked_amount: amount(STAKED_OFFSET)?,
+            enqueued_for_cooldown_amount: amount(ENQUEUED_OFFSET)?,
+            cooling_down_amount: amount(COOLING_DOWN_OFFSET)?,
+            last_update_slot: amount(LAST_UPDATE_SLOT_OFFSET)?,
+        })
+    }
+
+    /// Stake still securing the operator, including stake cooling down,
+    /// which can be slashed until it is withdrawn
+    pub fn total_security(&self) -> u64 {
+        self.staked_amount
+            .saturating_add(self.enqueued_for_cooldown_amount)
+            .saturating_add(self.cooling_down_amount)
+    }
+}
+
+/// Somewhere to read vault delegations from
+#[async_trait]
+pub trait StakeSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Current delegations of `vaults`, or of every vault when empty
+    async fn delegations(&self, vaults: &[Pubkey]) -> Result<Vec<VaultDelegation>>;
+}
+
+fn sync_error(e: impl std::fmt::Display) -> StakingError {
+    StakingError::ChainSync(e.to_string())
+}
+
+/// Reads delegations with `getProgramAccounts`
+pub struct RpcStakeSource {
+    provider: Arc<dyn RpcProvider>,
+    program_id: String,
+}
+
+impl RpcStakeSource {
+    pub fn new(provider: Arc<dyn RpcProvider>, program_id: impl Into<String>) -> Self {
+        Self {
+            provider,
+            program_id: program_id.into(),
+        }
+    }
+
+    async fn fetch(&self, vault: Option<&Pubkey>) -> Result<Vec<VaultDelegation>> {
+        let mut filters = vec![json!({"memcmp": {
+            "offset": 0,
+            "bytes": DELEGATION_DISCRIMINATOR_BS58,
+        }})];
+        if let Some(vault) = vault {
+            filters.push(json!({"memcmp": {"offset": VAULT_OFFSET, "bytes": vault.to_string()}}));
+        }
+        let reply = self
+            .provider
+            .call(
+                "getProgramAccounts",
+                json!([self.program_id, {"encoding": "base64", "commitment": "confirmed", "filters": filters}]),
+            )
+            .await
+            .map_err(sync_error)?;
+        if let Some(error) = reply.get("error") {
+            return Err(sync_error(format!("{} answered getProgramAccounts with {}", self.provider.name(), error)));
+        }
+        let accounts = reply
+            .get("result")
+            .and_then(Value::as_array)
+            .ok_or_else(|| sync_error(format!("{} returned no accounts: {}", self.provider.name(), reply)))?;
+
+        Ok(accounts
+            .iter()
+            .filter_map(|account| {
+                let data = account.pointer("/account/data/0")?.as_str()?;
+                let delegation = base64::decode(data).ok().as_deref().and_then(VaultDelegation::decode);
+                if delegation.is_none() {
+                    warn!("Skipping undecodable vault delegation {}", account["pubkey"]);
+                }
+                delegation
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl StakeSource for RpcStakeSource {
+    fn name(&self) -> &str {
+        self.provider.name()
+    }
+
+    async fn delegations(&self, vaults: &[Pubkey]) -> Result<Vec<VaultDelegation>> {
+        if vaults.is_empty() {
+            return self.fetch(None).await;
+        }
+        let mut delegations = Vec::new();
+        for vault in vaults {
+            delegations.extend(self.fetch(Some(vault)).await?);
+        }
+        Ok(delegations)
+    }
+}
+
+/// Keeps the latest delegation accounts the indexer ingested
+///
+/// Only accounts updated or snapshotted since [`FeedStakeSource::follow`]
+/// started are known, so pair it with a Geyser startup snapshot of the vault
+/// program.
+pub struct FeedStakeSource {
+    program_id: Pubkey,
+    delegations: RwLock<HashMap<Pubkey, VaultDelegation>>,
+}
+
+impl FeedStakeSource {
+    pub fn new(program_id: &str) -> Result<Self> {
+        Ok(Self {
+            program_id: Pubkey::from_str(program_id).map_err(sync_error)?,
+            delegations: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Keep the delegation accounts published to `feed`
+    pub fn follow(self: &Arc<Self>, feed: &DataFeed, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let source = self.clone();
+        let mut events = feed.subscribe();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(FeedEvent::Account(account)) if account.owner == source.program_id => {
+                            if let Some(delegation) = VaultDelegation::decode(&account.data) {
+                                source.delegations.write().unwrap().insert(account.pubkey, delegation);
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("Vault delegation tracking skipped {} feed events", skipped);
+                        }
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = shutdown::cancelled(shutdown.as_ref()) => return,
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl StakeSource for FeedStakeSource {
+    fn name(&self) -> &str {
+        "feed"
+    }
+
+    async fn delegations(&self, vaults: &[Pubkey]) -> Result<Vec<VaultDelegation>> {
+        Ok(self
+            .delegations
+            .read()
+            .unwrap()
+            .values()
+            .filter(|delegation| vaults.is_empty() || vaults.contains(&delegation.vault))
+            .cloned()
+            .collect())
+    }
+}
@@ -1,76 +1,64 @@
 // crates/windexer-jito-staking/src/staking/delegation.rs
 
-use solana_sdk::pubkey::Pubkey;
-use anyhow::Result;
-use std::collections::HashMap;
+//! Delegations by staker
+//!
+//! [`OperatorStats::active_delegations`] holds the stake each staker
+//! delegated to one operator. [`DelegationManager`] indexes the same
+//! delegations by staker, so the operators a staker delegated to are found
+//! without scanning every operator. [`StakingManager`](super::StakingManager)
+//! updates it with every change it commits.
 
+use {
+    crate::staking::types::OperatorStats,
+    solana_sdk::pubkey::Pubkey,
+    std::collections::HashMap,
+};
+
+#[derive(Debug, Default)]
 pub struct DelegationManager {
-    delegations: HashMap<Pubkey, Vec<(Pubkey, u64)>>, // operator -> [(staker, amount)]
+    /// staker -> operator -> amount
+    delegations: HashMap<Pubkey, HashMap<Pubkey, u64>>,
 }
 
 impl DelegationManager {
     pub fn new() -> Self {
-        Self {
-            delegations: HashMap::new()
-        }
+        Self::default()
     }
 
-    pub async fn add_delegation(
-        &mut self,
-        operator: Pubkey,
-        staker: Pubkey,
-        amount: u64
-    ) -> Result<()> {
-        let operator_delegations = self.delegations
-            .entry(operator)
-            .or_insert_with(Vec::new);
-            
-        if let Some(pos) = operator_delegations.iter().position(|(s, _)| s == &staker) {
-            operator_delegations[pos].1 += amount;
-        } else {
-            operator_delegations.push((staker, amount));
+    /// Index the delegations of `operators`
+    pub fn from_operators(operators: &HashMap<Pubkey, OperatorStats>) -> Self {
+        let mut manager = Self::new();
+        for (operator, stats) in operators {
+            for (staker, amount) in &stats.active_delegations {
+                manager.set_delegation(*operator, *staker, *amount);
+            }
         }
-            
-        Ok(())
+        manager
     }
 
-    pub fn get_operator_delegations(&self, operator: &Pubkey) -> Vec<(Pubkey, u64)> {
-        self.delegations
-            .get(operator)
-            .cloned()
-            .unwrap_or_default()
+    /// Record that `staker` delegates `amount` to `operator`, removing the
+    /// delegation when it is zero
+    pub fn set_delegation(&mut self, operator: Pubkey, staker: Pubkey, amount: u64) {
+        if amount > 0 {
+            self.delegations.entry(staker).or_default().insert(operator, amount);
+            return;
+        }
+        if let Some(operators) = self.delegations.get_mut(&staker) {
+            operators.remove(&operator);
+            if operators.is_empty() {
+                self.delegations.remove(&staker);
+            }
+        }
     }
 
+    /// Operators `staker` delegates to, with the amount delegated to each
     pub fn get_staker_delegations(&self, staker: &Pubkey) -> Vec<(Pubkey, u64)> {
-        self.delegations
-            .iter()
-            .flat_map(|(operator, delegations)| {
-                delegations
-                    .iter()
-                    .filter(|(s, _)| s == staker)
-                    .map(|(_, amount)| (*operator, *amount))
-            })
-            .collect()
+        let mut delegations: Vec<(Pubkey, u64)> = self
+            .delegations
+            .get(staker)
+            .map(|operators| operators.iter().map(|(operator, amount)| (*operator, *amount)).collect())
+            .unwrap_or_default();
+        delegations.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        delegations
     }
-
-    pub fn get_all_delegations(&self) -> Vec<(Pubkey, Vec<(Pubkey, u64)>)> {
-        self.delegations
-            .iter()
-            .map(|(operator, delegations)| (operator.clone(), delegations.clone()))
-            .collect()
-    }
-
-    pub fn remove_delegation(
-        &mut self,
-        operator: &Pubkey,
-        staker: &Pubkey
-    ) -> Result<u64> {
-        if let Some(delegations) = self.delegations.get_mut(operator) {
-            if let Some(pos) = delegations.iter().position(|(s, _)| s == staker) {
-                let (_, amount) = delegations.remove(pos);
-                return Ok(amount);
-            }
-        }
-        Err(anyhow::anyhow!("Delegation not found"))
-    }
-}
\ No newline at end of file
+}
@@ -1,25 +1,124 @@
 // crates/windexer-jito-staking/src/staking/mod.rs
 
 //! Staking management module
+//!
+//! [`StakingManager`] keeps the stake and reward accounting of every
+//! operator. Built with the `store` feature and
+//! [`StakingManager::with_storage`], it loads that accounting on startup and
+//! writes every change through to the store before applying it, so a
+//! restart loses nothing.
+//!
+//! Unstaked stake leaves the operator's `total_stake` at once but is only
+//! released to the staker after `cooldown_epochs`: it waits in the
+//! operator's pending withdrawals until [`StakingManager::process_epoch`]
+//! reaches its withdrawable epoch. Every change is published as a
+//! [`StakingEvent`] to [`StakingManager::subscribe`]rs.
+//!
+//! Stake can also move between operators at once with
+//! [`StakingManager::redelegate`]. Delegations are looked up per operator
+//! with [`StakingManager::get_delegators_for_operator`] and per staker with
+//! [`StakingManager::get_delegations_by_staker`], which a
+//! [`DelegationManager`] index keeps cheap.
+//!
+//! Slashes deduct from an operator's stake and from each delegation in
+//! proportion to its share. The most recent are kept for
+//! [`StakingManager::slash_history`] and, with a store, persisted.
+//!
+//! Each operator's performance over every closed epoch is kept as a
+//! [`PerformanceRecord`] for [`StakingManager::performance_history`], for
+//! the configured number of epochs, and with a store, persisted.
 
+pub mod chain;
+pub mod sync;
 pub mod types;
 
 use {
-    std::{collections::HashMap, sync::RwLock},
+    std::{
+        collections::{HashMap, VecDeque},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            RwLock,
+        },
+    },
     solana_sdk::pubkey::Pubkey,
-    anyhow::Result,
-    crate::staking::types::{StakingConfig, OperatorStats},
+    tokio::sync::broadcast,
+    crate::{
+        error::{Result, StakingError},
+        rewards::PerformanceMetrics,
+        slashing::ViolationType,
+        staking::types::{
+            DelegationInfo, StakingConfig, StakingEvent, OperatorStats, PendingWithdrawal, SlashEvent,
+        },
+    },
+    windexer_common::{types::PerformanceRecord, utils::current_timestamp},
+};
+
+#[cfg(feature = "store")]
+use {
+    std::sync::Arc,
+    tracing::info,
+    windexer_store::traits::Storage,
 };
 
 mod delegation;
+#[cfg(feature = "store")]
+mod persistence;
 mod vault;
 
 pub use delegation::DelegationManager;
 pub use vault::VaultManager;
 
+/// Most stake a single operator may hold
+pub const MAX_OPERATOR_STAKE: u64 = 1_000_000_000_000;
+
+/// Events buffered for slow subscribers
+const EVENT_CAPACITY: usize = 1_024;
+
+/// Slashes kept in memory for [`StakingManager::slash_history`]
+pub const MAX_SLASH_HISTORY: usize = 1_000;
+
+/// `amount` split across `delegations` in proportion to their size. Shares
+/// are rounded down and the units left over go to the largest remainders,
+/// so the shares add up to `amount`, or to everything delegated if that is
+/// less.
+fn slash_shares(delegations: &HashMap<Pubkey, u64>, amount: u64) -> Vec<(Pubkey, u64)> {
+    let total: u128 = delegations.values().map(|delegated| *delegated as u128).sum();
+    let amount = (amount as u128).min(total);
+    if amount == 0 {
+        return Vec::new();
+    }
+
+    let mut shares: Vec<(Pubkey, u64, u128)> = delegations
+        .iter()
+        .map(|(staker, delegated)| {
+            let exact = *delegated as u128 * amount;
+            (*staker, (exact / total) as u64, exact % total)
+        })
+        .collect();
+    let assigned: u128 = shares.iter().map(|(_, share, _)| *share as u128).sum();
+    shares.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(&b.0)));
+    for (_, share, _) in shares.iter_mut().take((amount - assigned) as usize) {
+        *share += 1;
+    }
+    shares.into_iter().map(|(staker, share, _)| (staker, share)).collect()
+}
+
 pub struct StakingManager {
     config: StakingConfig,
     operators: RwLock<HashMap<Pubkey, OperatorStats>>,
+    /// `operators`' delegations by staker
+    delegations: RwLock<DelegationManager>,
+    epoch: AtomicU64,
+    events: broadcast::Sender<StakingEvent>,
+    /// Held while a change is persisted, so changes are applied in the
+    /// order they were written
+    updates: tokio::sync::Mutex<()>,
+    /// Most recent slashes, oldest first
+    slashes: RwLock<VecDeque<SlashEvent>>,
+    /// Performance of every operator in the retained epochs, oldest first
+    performance: RwLock<VecDeque<PerformanceRecord>>,
+    #[cfg(feature = "store")]
+    storage: Option<Arc<dyn Storage>>,
 }
 
 impl StakingManager {
@@ -27,13 +126,65 @@ impl StakingManager {
         Self {
             config,
             operators: RwLock::new(HashMap::new()),
+            delegations: RwLock::new(DelegationManager::new()),
+            epoch: AtomicU64::new(0),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            updates: tokio::sync::Mutex::new(()),
+            slashes: RwLock::new(VecDeque::new()),
+            performance: RwLock::new(VecDeque::new()),
+            #[cfg(feature = "store")]
+            storage: None,
         }
     }
 
+    /// Load the accounting persisted in `storage` and write every later
+    /// change through to it
+    #[cfg(feature = "store")]
+    pub async fn with_storage(config: StakingConfig, storage: Arc<dyn Storage>) -> Result<Self> {
+        let operators = persistence::load(storage.as_ref()).await?;
+        let slashes = persistence::load_slashes(storage.as_ref(), MAX_SLASH_HISTORY).await?;
+        let performance = persistence::load_performance(storage.as_ref()).await?;
+        info!("Loaded the staking state of {} operators", operators.len());
+        Ok(Self {
+            config,
+            delegations: RwLock::new(DelegationManager::from_operators(&operators)),
+            operators: RwLock::new(operators),
+            epoch: AtomicU64::new(0),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            updates: tokio::sync::Mutex::new(()),
+            slashes: RwLock::new(slashes),
+            performance: RwLock::new(performance),
+            storage: Some(storage),
+        })
+    }
+
     pub fn config(&self) -> &StakingConfig {
         &self.config
     }
 
+    /// Stake changes from now on; subscribers that fall behind miss events
+    pub fn subscribe(&self) -> broadcast::Receiver<StakingEvent> {
+        self.events.subscribe()
+    }
+
+    /// The last epoch passed to [`StakingManager::process_epoch`]
+    pub fn current_epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Relaxed)
+    }
+
+    /// Start keeping the accounting of `operator` before anyone stakes
+    /// with it
+    pub async fn register_operator(&self, operator: Pubkey) -> Result<OperatorStats> {
+        let _update = self.updates.lock().await;
+        if self.operators.read().unwrap().contains_key(&operator) {
+            return Err(StakingError::OperatorExists(operator));
+        }
+        let stats = OperatorStats { pubkey: Some(operator), ..OperatorStats::default() };
+        self.commit(operator, stats.clone(), &[]).await?;
+        let _ = self.events.send(StakingEvent::OperatorRegistered { operator });
+        Ok(stats)
+    }
+
     pub async fn process_stake(
         &self,
         amount: u64,
@@ -41,24 +192,517 @@ impl StakingManager {
         operator: Pubkey,
     ) -> Result<()> {
         if amount < self.config.min_stake {
-            return Err(anyhow::anyhow!("Stake amount below minimum threshold"));
+            return Err(StakingError::BelowMinimumStake {
+                amount,
+                minimum: self.config.min_stake,
+            });
         }
 
-        let stats = self.get_operator_stats(&operator).await?;
-        if stats.total_stake + amount > 1_000_000_000_000 {
-            return Err(anyhow::anyhow!("Operator would exceed maximum stake"));
+        let _update = self.updates.lock().await;
+        let mut stats = self.get_operator_stats(&operator).await?;
+        if stats.total_stake + amount > MAX_OPERATOR_STAKE {
+            return Err(StakingError::MaximumStakeExceeded(operator));
         }
 
-        let mut operators = self.operators.write().unwrap();
-        let stats = operators.entry(operator).or_default();
+        stats.pubkey = Some(operator);
         stats.total_stake += amount;
+        *stats.active_delegations.entry(staker).or_default() += amount;
+        self.commit(operator, stats, &[staker]).await?;
+        let _ = self.events.send(StakingEvent::Staked { operator, staker, amount });
+        Ok(())
+    }
+
+    /// Take `amount` of `staker`'s stake off `operator`, to be withdrawn
+    /// once it has cooled down for `cooldown_epochs`
+    pub async fn process_unstake(
+        &self,
+        amount: u64,
+        staker: Pubkey,
+        operator: Pubkey,
+    ) -> Result<PendingWithdrawal> {
+        if amount == 0 {
+            return Err(StakingError::ZeroAmount);
+        }
+
+        let _update = self.updates.lock().await;
+        let mut stats = self.get_operator_stats(&operator).await?;
+        let available = stats
+            .active_delegations
+            .get(&staker)
+            .copied()
+            .ok_or(StakingError::DelegationNotFound { operator, staker })?;
+        if amount > available {
+            return Err(StakingError::InsufficientStake { operator, staker, requested: amount, available });
+        }
+
+        if amount == available {
+            stats.active_delegations.remove(&staker);
+        } else {
+            stats.active_delegations.insert(staker, available - amount);
+        }
+        stats.total_stake = stats.total_stake.saturating_sub(amount);
+        let epoch = self.current_epoch();
+        let withdrawal = PendingWithdrawal {
+            staker,
+            amount,
+            requested_epoch: epoch,
+            withdrawable_epoch: epoch + self.config.cooldown_epochs,
+        };
+        stats.pending_withdrawals.push(withdrawal.clone());
+        self.commit(operator, stats, &[staker]).await?;
+
+        let _ = self.events.send(StakingEvent::UnstakeRequested { operator, withdrawal: withdrawal.clone() });
+        Ok(withdrawal)
+    }
 
+    /// Move `amount` of `staker`'s stake from operator `from` to operator
+    /// `to`; unlike unstaking, the stake does not cool down
+    pub async fn redelegate(&self, amount: u64, staker: Pubkey, from: Pubkey, to: Pubkey) -> Result<()> {
+        if amount == 0 {
+            return Err(StakingError::ZeroAmount);
+        }
+        if from == to {
+            return Err(StakingError::SameOperator(from));
+        }
+
+        let _update = self.updates.lock().await;
+        let mut source = self.get_operator_stats(&from).await?;
+        let available = source
+            .active_delegations
+            .get(&staker)
+            .copied()
+            .ok_or(StakingError::DelegationNotFound { operator: from, staker })?;
+        if amount > available {
+            return Err(StakingError::InsufficientStake { operator: from, staker, requested: amount, available });
+        }
+        let mut target = self.get_operator_stats(&to).await?;
+        if target.total_stake + amount > MAX_OPERATOR_STAKE {
+            return Err(StakingError::MaximumStakeExceeded(to));
+        }
+
+        if amount == available {
+            source.active_delegations.remove(&staker);
+        } else {
+            source.active_delegations.insert(staker, available - amount);
+        }
+        source.total_stake = source.total_stake.saturating_sub(amount);
+        target.pubkey = Some(to);
+        target.total_stake += amount;
+        *target.active_delegations.entry(staker).or_default() += amount;
+        self.commit(from, source, &[staker]).await?;
+        self.commit(to, target, &[staker]).await?;
+
+        let _ = self.events.send(StakingEvent::Redelegated { staker, from, to, amount });
         Ok(())
     }
 
+    /// Move to `epoch` and release every withdrawal whose cooldown ended;
+    /// call on each epoch boundary
+    pub async fn process_epoch(&self, epoch: u64) -> Result<Vec<(Pubkey, PendingWithdrawal)>> {
+        let _update = self.updates.lock().await;
+        self.epoch.fetch_max(epoch, Ordering::Relaxed);
+
+        let due: Vec<(Pubkey, OperatorStats)> = self
+            .operators
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, stats)| stats.pending_withdrawals.iter().any(|w| w.withdrawable_epoch <= epoch))
+            .map(|(operator, stats)| (*operator, stats.clone()))
+            .collect();
+
+        let mut released = Vec::new();
+        for (operator, mut stats) in due {
+            let (ready, pending) = stats
+                .pending_withdrawals
+                .into_iter()
+                .partition::<Vec<_>, _>(|w| w.withdrawable_epoch <= epoch);
+            stats.pending_withdrawals = pending;
+            let stakers: Vec<Pubkey> = ready.iter().map(|w| w.staker).collect();
+            self.commit(operator, stats, &stakers).await?;
+
+            for withdrawal in ready {
+                let _ = self.events.send(StakingEvent::Withdrawn { operator, withdrawal: withdrawal.clone() });
+                released.push((operator, withdrawal));
+            }
+        }
+        Ok(released)
+    }
+
+    /// Credit rewards to an operator and to its delegators
+    pub async fn credit_rewards(
+        &self,
+        operator: Pubkey,
+        operator_reward: u64,
+        delegator_rewards: &[(Pubkey, u64)],
+    ) -> Result<()> {
+        let _update = self.updates.lock().await;
+        let mut stats = self.get_operator_stats(&operator).await?;
+        stats.pubkey = Some(operator);
+        stats.reward_balance += operator_reward;
+        for (staker, reward) in delegator_rewards {
+            *stats.delegator_rewards.entry(*staker).or_default() += reward;
+        }
+        let stakers: Vec<Pubkey> = delegator_rewards.iter().map(|(staker, _)| *staker).collect();
+        self.commit(operator, stats, &stakers).await
+    }
+
+    /// Record the performance `operator` closed `epoch` with, keeping it
+    /// in the operator's performance history
+    pub async fn record_epoch_performance(
+        &self,
+        operator: Pubkey,
+        epoch: u64,
+        metrics: &PerformanceMetrics,
+    ) -> Result<()> {
+        let _update = self.updates.lock().await;
+        let mut stats = self.get_operator_stats(&operator).await?;
+        let record = PerformanceRecord {
+            operator: operator.to_string(),
+            epoch,
+            uptime: metrics.uptime,
+            missed_rounds: metrics.missed_rounds,
+            rounds: metrics.slots,
+            response_time: metrics.response_time,
+            message_success_rate: metrics.message_success_rate,
+            slashes: self
+                .slashes
+                .read()
+                .unwrap()
+                .iter()
+                .filter(|slash| slash.operator == operator && slash.epoch == epoch)
+                .count() as u64,
+            performance_score: metrics.score(),
+            timestamp: current_timestamp(),
+        };
+        self.persist_performance(&record).await?;
+
+        stats.pubkey = Some(operator);
+        stats.performance_score = record.performance_score;
+        stats.last_active = metrics.last_vote.or(stats.last_active);
+        self.commit(operator, stats, &[]).await?;
+        self.performance.write().unwrap().push_back(record);
+        Ok(())
+    }
+
+    /// Drop the performance history of epochs that fell out of the
+    /// retention once `epoch` is recorded
+    pub async fn prune_performance(&self, epoch: u64) -> Result<()> {
+        let Some(oldest) = (epoch + 1).checked_sub(self.config.performance_retention()) else {
+            return Ok(());
+        };
+        self.prune_persisted_performance(oldest).await?;
+        let mut performance = self.performance.write().unwrap();
+        while performance.front().is_some_and(|record| record.epoch < oldest) {
+            performance.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Performance of `operator` in every retained epoch closed at or after
+    /// the unix timestamp `since`, oldest first
+    pub fn performance_history(&self, operator: &Pubkey, since: i64) -> Vec<PerformanceRecord> {
+        let operator = operator.to_string();
+        self.performance
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|record| record.operator == operator && record.timestamp >= since)
+            .cloned()
+            .collect()
+    }
+
+    /// Deduct up to `amount` from the stake of `operator` for `violation`,
+    /// taking from each delegation its share
+    pub async fn slash(&self, operator: Pubkey, amount: u64, violation: ViolationType) -> Result<SlashEvent> {
+        let _update = self.updates.lock().await;
+        let mut stats = self.get_operator_stats(&operator).await?;
+        let stake_before = stats.total_stake;
+        let amount = amount.min(stake_before);
+
+        let mut stakers = Vec::new();
+        for (staker, share) in slash_shares(&stats.active_delegations, amount) {
+            if let Some(delegated) = stats.active_delegations.get_mut(&staker) {
+                *delegated -= share;
+            }
+            stakers.push(staker);
+        }
+        stats.active_delegations.retain(|_, delegated| *delegated > 0);
+        stats.pubkey = Some(operator);
+        stats.total_stake = stake_before - amount;
+
+        let slash = SlashEvent {
+            operator,
+            violation,
+            amount,
+            stake_before,
+            epoch: self.current_epoch(),
+            timestamp: current_timestamp(),
+        };
+        self.persist_slash(&slash).await?;
+        self.commit(operator, stats, &stakers).await?;
+
+        let mut slashes = self.slashes.write().unwrap();
+        if slashes.len() == MAX_SLASH_HISTORY {
+            slashes.pop_front();
+        }
+        slashes.push_back(slash.clone());
+        drop(slashes);
+        let _ = self.events.send(StakingEvent::Slashed(slash.clone()));
+        Ok(slash)
+    }
+
+    /// Up to `limit` of the most recent slashes of `operator`, or of every
+    /// operator, newest first
+    pub fn slash_history(&self, operator: Option<&Pubkey>, limit: usize) -> Vec<SlashEvent> {
+        self.slashes
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|slash| operator.map_or(true, |operator| slash.operator == *operator))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Replace the stake of `operator` with the stake it holds on chain
+    pub async fn sync_stake(&self, operator: Pubkey, stake: u64) -> Result<()> {
+        let _update = self.updates.lock().await;
+        let mut stats = self.get_operator_stats(&operator).await?;
+        stats.pubkey = Some(operator);
+        stats.total_stake = stake;
+        self.commit(operator, stats, &[]).await
+    }
+
     pub async fn get_operator_stats(&self, operator: &Pubkey) -> Result<OperatorStats> {
         let operators = self.operators.read().unwrap();
         let stats = operators.get(operator).cloned().unwrap_or_default();
         Ok(stats)
     }
-}
\ No newline at end of file
+
+    /// The accounting of `operator`, `None` if it has none
+    pub fn find_operator(&self, operator: &Pubkey) -> Option<OperatorStats> {
+        self.operators.read().unwrap().get(operator).cloned()
+    }
+
+    /// Every operator with any accounting
+    pub async fn get_all_operators(&self) -> HashMap<Pubkey, OperatorStats> {
+        self.operators.read().unwrap().clone()
+    }
+
+    /// Stakers delegating to `operator` with the amount each delegates,
+    /// largest first
+    pub fn get_delegators_for_operator(&self, operator: &Pubkey) -> Vec<(Pubkey, u64)> {
+        let mut delegators: Vec<(Pubkey, u64)> = self
+            .operators
+            .read()
+            .unwrap()
+            .get(operator)
+            .map(|stats| stats.active_delegations.iter().map(|(staker, amount)| (*staker, *amount)).collect())
+            .unwrap_or_default();
+        delegators.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        delegators
+    }
+
+    /// Every delegation of `staker`, largest first
+    pub fn get_delegations_by_staker(&self, staker: &Pubkey) -> Vec<DelegationInfo> {
+        let delegations = self.delegations.read().unwrap().get_staker_delegations(staker);
+        let operators = self.operators.read().unwrap();
+        delegations
+            .into_iter()
+            .map(|(operator, amount)| DelegationInfo {
+                staker: *staker,
+                operator,
+                amount,
+                rewards: operators
+                    .get(&operator)
+                    .and_then(|stats| stats.delegator_rewards.get(staker))
+                    .copied()
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Persist the new `stats` of `operator` and its delegations from
+    /// `stakers`, then make them current
+    async fn commit(&self, operator: Pubkey, stats: OperatorStats, stakers: &[Pubkey]) -> Result<()> {
+        self.persist(&operator, &stats, stakers).await?;
+        let mut delegations = self.delegations.write().unwrap();
+        for staker in stakers {
+            let amount = stats.active_delegations.get(staker).copied().unwrap_or_default();
+            delegations.set_delegation(operator, *staker, amount);
+        }
+        drop(delegations);
+        self.operators.write().unwrap().insert(operator, stats);
+        Ok(())
+    }
+
+    #[cfg(feature = "store")]
+    async fn persist(&self, operator: &Pubkey, stats: &OperatorStats, stakers: &[Pubkey]) -> Result<()> {
+        match &self.storage {
+            Some(storage) => persistence::save(storage.as_ref(), operator, stats, stakers).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "store"))]
+    async fn persist(&self, _operator: &Pubkey, _stats: &OperatorStats, _stakers: &[Pubkey]) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "store")]
+    async fn persist_slash(&self, slash: &SlashEvent) -> Result<()> {
+        match &self.storage {
+            Some(storage) => persistence::save_slash(storage.as_ref(), slash).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "store"))]
+    async fn persist_slash(&self, _slash: &SlashEvent) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "store")]
+    async fn persist_performance(&self, record: &PerformanceRecord) -> Result<()> {
+        match &self.storage {
+            Some(storage) => persistence::save_performance(storage.as_ref(), record).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "store"))]
+    async fn persist_performance(&self, _record: &PerformanceRecord) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "store")]
+    async fn prune_persisted_performance(&self, epoch: u64) -> Result<()> {
+        match &self.storage {
+            Some(storage) => persistence::prune_performance(storage.as_ref(), epoch).await,
+            None => Ok(()),
+        }
+    }
+
+    #[cfg(not(feature = "store"))]
+    async fn prune_persisted_performance(&self, _epoch: u64) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn releases_unstake_after_cooldown() {
+        let config = StakingConfig { cooldown_epochs: 2, ..StakingConfig::default() };
+        let manager = StakingManager::new(config);
+        let (operator, staker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut events = manager.subscribe();
+
+        manager.process_epoch(10).await.unwrap();
+        manager.process_stake(1_000, staker, operator).await.unwrap();
+        assert!(matches!(
+            manager.process_unstake(1_500, staker, operator).await,
+            Err(StakingError::InsufficientStake { available: 1_000, .. })
+        ));
+        let withdrawal = manager.process_unstake(400, staker, operator).await.unwrap();
+        assert_eq!(withdrawal.withdrawable_epoch, 12);
+        assert_eq!(manager.get_operator_stats(&operator).await.unwrap().total_stake, 600);
+
+        assert!(manager.process_epoch(11).await.unwrap().is_empty());
+        assert_eq!(manager.process_epoch(12).await.unwrap(), vec![(operator, withdrawal.clone())]);
+        assert!(manager.get_operator_stats(&operator).await.unwrap().pending_withdrawals.is_empty());
+
+        assert!(matches!(events.try_recv().unwrap(), StakingEvent::Staked { amount: 1_000, .. }));
+        assert!(matches!(events.try_recv().unwrap(), StakingEvent::UnstakeRequested { .. }));
+        assert_eq!(events.try_recv().unwrap(), StakingEvent::Withdrawn { operator, withdrawal });
+    }
+
+    #[tokio::test]
+    async fn redelegates_between_operators() {
+        let manager = StakingManager::new(StakingConfig::default());
+        let (first, second, staker) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        manager.process_stake(1_000, staker, first).await.unwrap();
+        manager.process_stake(500, Pubkey::new_unique(), second).await.unwrap();
+
+        manager.redelegate(600, staker, first, second).await.unwrap();
+        assert_eq!(manager.get_operator_stats(&first).await.unwrap().total_stake, 400);
+        assert_eq!(manager.get_operator_stats(&second).await.unwrap().total_stake, 1_100);
+        assert_eq!(manager.get_delegators_for_operator(&second)[0], (staker, 600));
+        let delegations = manager.get_delegations_by_staker(&staker);
+        assert_eq!(
+            delegations.iter().map(|d| (d.operator, d.amount)).collect::<Vec<_>>(),
+            [(second, 600), (first, 400)]
+        );
+
+        manager.redelegate(400, staker, first, second).await.unwrap();
+        assert_eq!(manager.get_delegations_by_staker(&staker).len(), 1);
+        assert!(matches!(
+            manager.redelegate(1, staker, first, second).await,
+            Err(StakingError::DelegationNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn slashes_delegations_pro_rata() {
+        let manager = StakingManager::new(StakingConfig::default());
+        let (operator, large, small) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        manager.process_stake(3_000, large, operator).await.unwrap();
+        manager.process_stake(1_000, small, operator).await.unwrap();
+
+        let slash = manager.slash(operator, 400, ViolationType::DoubleVote).await.unwrap();
+        assert_eq!((slash.amount, slash.stake_before), (400, 4_000));
+        let stats = manager.get_operator_stats(&operator).await.unwrap();
+        assert_eq!(stats.total_stake, 3_600);
+        assert_eq!(stats.active_delegations[&large], 2_700);
+        assert_eq!(stats.active_delegations[&small], 900);
+
+        let slash = manager.slash(operator, 10_000, ViolationType::DivergentData).await.unwrap();
+        assert_eq!(slash.amount, 3_600);
+        assert!(manager.get_operator_stats(&operator).await.unwrap().active_delegations.is_empty());
+        let violations: Vec<ViolationType> = manager
+            .slash_history(Some(&operator), 10)
+            .iter()
+            .map(|slash| slash.violation)
+            .collect();
+        assert_eq!(violations, [ViolationType::DivergentData, ViolationType::DoubleVote]);
+    }
+
+    #[tokio::test]
+    async fn slash_shares_add_up_to_the_amount() {
+        let manager = StakingManager::new(StakingConfig::default());
+        let operator = Pubkey::new_unique();
+        for _ in 0..3 {
+            manager.process_stake(1, Pubkey::new_unique(), operator).await.unwrap();
+        }
+
+        manager.slash(operator, 2, ViolationType::DoubleVote).await.unwrap();
+        let stats = manager.get_operator_stats(&operator).await.unwrap();
+        assert_eq!(stats.total_stake, 1);
+        assert_eq!(stats.active_delegations.values().sum::<u64>(), stats.total_stake);
+    }
+
+    #[tokio::test]
+    async fn keeps_performance_for_retained_epochs() {
+        let config = StakingConfig { performance_retention_epochs: 2, ..StakingConfig::default() };
+        let manager = StakingManager::new(config);
+        let (operator, staker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        manager.process_stake(1_000, staker, operator).await.unwrap();
+        manager.slash(operator, 100, ViolationType::DoubleVote).await.unwrap();
+
+        let metrics = PerformanceMetrics { uptime: 0.5, slots: 4, missed_rounds: 2, ..Default::default() };
+        for epoch in 0..3 {
+            manager.record_epoch_performance(operator, epoch, &metrics).await.unwrap();
+            manager.prune_performance(epoch).await.unwrap();
+        }
+
+        let history = manager.performance_history(&operator, 0);
+        let epochs: Vec<u64> = history.iter().map(|record| record.epoch).collect();
+        assert_eq!(epochs, [1, 2]);
+        assert_eq!((history[0].missed_rounds, history[0].slashes), (2, 0));
+        assert!(manager.performance_history(&staker, 0).is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+// crates/windexer-jito-staking/src/staking/persistence.rs
+
+//! Staking state in windexer-store
+//!
+//! Each operator is one [`OperatorRecord`]; each staker with stake, unpaid
+//! rewards or pending withdrawals at an operator is one [`DelegationRecord`].
+//! Each slash is one [`SlashRecord`](windexer_common::types::SlashRecord),
+//! and each operator's performance over an epoch one [`PerformanceRecord`].
+
+use {
+    crate::{
+        error::{Result, StakingError},
+        slashing::ViolationType,
+        staking::types::{OperatorStats, PendingWithdrawal, SlashEvent},
+    },
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashMap, VecDeque},
+        str::FromStr,
+    },
+    tracing::warn,
+    windexer_common::{
+        types::{DelegationRecord, OperatorRecord, PerformanceRecord, WithdrawalRecord},
+        utils::current_timestamp,
+    },
+    windexer_store::traits::Storage,
+};
+
+fn storage_error(e: anyhow::Error) -> StakingError {
+    StakingError::Storage(e.to_string())
+}
+
+/// Every persisted operator with its delegations
+pub(crate) async fn load(storage: &dyn Storage) -> Result<HashMap<Pubkey, OperatorStats>> {
+    let mut operators = HashMap::new();
+    for record in storage.load_operator_records().await.map_err(storage_error)? {
+        let Ok(operator) = Pubkey::from_str(&record.operator) else {
+            warn!("Skipping stored operator with invalid pubkey {}", record.operator);
+            continue;
+        };
+        operators.insert(operator, OperatorStats {
+            pubkey: Some(operator),
+            total_stake: record.total_stake,
+            last_active: record.last_active,
+            performance_score: record.performance_score,
+            reward_balance: record.reward_balance,
+            ..Default::default()
+        });
+    }
+
+    for record in storage.load_delegations().await.map_err(storage_error)? {
+        let (Ok(operator), Ok(staker)) = (Pubkey::from_str(&record.operator), Pubkey::from_str(&record.staker)) else {
+            warn!("Skipping stored delegation of {} to {} with an invalid pubkey", record.staker, record.operator);
+            continue;
+        };
+        let stats = operators.entry(operator).or_insert_with(|| OperatorStats {
+            pubkey: Some(operator),
+            ..Default::default()
+        });
+        if record.amount > 0 {
+            stats.active_delegations.insert(staker, record.amount);
+        }
+        if record.reward_balance > 0 {
+            stats.delegator_rewards.insert(staker, record.reward_balance);
+        }
+        stats.pending_withdrawals.extend(record.pending_withdrawals.into_iter().map(|w| PendingWithdrawal {
+            staker,
+            amount: w.amount,
+            requested_epoch: w.requested_epoch,
+            withdrawable_epoch: w.withdrawable_epoch,
+        }));
+    }
+
+    Ok(operators)
+}
+
+/// Write `stats` of `operator` and the delegations of `stakers`, deleting
+/// those left without stake, rewards or pending withdrawals
+pub(crate) async fn save(storage: &dyn Storage, operator: &Pubkey, stats: &OperatorStats, stakers: &[Pubkey]) -> Result<()> {
+    let now = current_timestamp();
+    storage
+        .save_operator_record(&OperatorRecord {
+            operator: operator.to_string(),
+            total_stake: stats.total_stake,
+            performance_score: stats.performance_score,
+            last_active: stats.last_active,
+            reward_balance: stats.reward_balance,
+            updated_at: now,
+        })
+        .await
+        .map_err(storage_error)?;
+
+    for staker in stakers {
+        let amount = stats.active_delegations.get(staker).copied().unwrap_or_default();
+        let reward_balance = stats.delegator_rewards.get(staker).copied().unwrap_or_default();
+        let pending_withdrawals: Vec<WithdrawalRecord> = stats
+            .pending_withdrawals
+            .iter()
+            .filter(|w| w.staker == *staker)
+            .map(|w| WithdrawalRecord {
+                amount: w.amount,
+                requested_epoch: w.requested_epoch,
+                withdrawable_epoch: w.withdrawable_epoch,
+            })
+            .collect();
+        if amount == 0 && reward_balance == 0 && pending_withdrawals.is_empty() {
+            storage.delete_delegation(&operator.to_string(), &staker.to_string()).await.map_err(storage_error)?;
+            continue;
+        }
+        storage
+            .save_delegation(&DelegationRecord {
+                operator: operator.to_string(),
+                staker: staker.to_string(),
+                amount,
+                reward_balance,
+                pending_withdrawals,
+                updated_at: now,
+            })
+            .await
+            .map_err(storage_error)?;
+    }
+    Ok(())
+}
+
+/// Up to `limit` of the most recent slashes, oldest first
+pub(crate) async fn load_slashes(storage: &dyn Storage, limit: usize) -> Result<VecDeque<SlashEvent>> {
+    let mut slashes = VecDeque::new();
+    for record in storage.load_slash_events(None, limit).await.map_err(storage_error)? {
+        let (Ok(operator), Ok(violation)) = (Pubkey::from_str(&record.operator), record.violation.parse::<ViolationType>()) else {
+            warn!("Skipping stored {} slash of {} that cannot be read", record.violation, record.operator);
+            continue;
+        };
+        slashes.push_front(SlashEvent {
+            operator,
+            violation,
+            amount: record.amount,
+            stake_before: record.stake_before,
+            epoch: record.epoch,
+            timestamp: record.timestamp,
+        });
+    }
+    Ok(slashes)
+}
+
+pub(crate) async fn save_slash(storage: &dyn Storage, slash: &SlashEvent) -> Result<()> {
+    storage
+        .save_slash_event(&slash.to_record())
+        .await
+        .map_err(storage_error)
+}
+
+/// Every persisted performance record, oldest epoch first
+pub(crate) async fn load_performance(storage: &dyn Storage) -> Result<VecDeque<PerformanceRecord>> {
+    Ok(storage.load_performance_records(None, 0).await.map_err(storage_error)?.into())
+}
+
+pub(crate) async fn save_performance(storage: &dyn Storage, record: &PerformanceRecord) -> Result<()> {
+    storage
+        .save_performance_record(record)
+        .await
+        .map_err(storage_error)
+}
+
+/// Delete the performance records of epochs before `epoch`
+pub(crate) async fn prune_performance(storage: &dyn Storage, epoch: u64) -> Result<()> {
+    storage
+        .prune_performance_records(epoch)
+        .await
+        .map(|_| ())
+        .map_err(storage_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::staking::{types::StakingConfig, StakingManager},
+        std::sync::Arc,
+        windexer_store::memory_store::MemoryStore,
+    };
+
+    #[tokio::test]
+    async fn restores_state_after_restart() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStore::new());
+        let (operator, staker) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let manager = StakingManager::with_storage(StakingConfig::default(), storage.clone()).await.unwrap();
+        manager.process_stake(500, staker, operator).await.unwrap();
+        manager.credit_rewards(operator, 7, &[(staker, 3)]).await.unwrap();
+        manager.process_unstake(200, staker, operator).await.unwrap();
+
+        let restarted = StakingManager::with_storage(StakingConfig::default(), storage).await.unwrap();
+        let stats = restarted.get_operator_stats(&operator).await.unwrap();
+        assert_eq!(stats.total_stake, 300);
+        assert_eq!(stats.reward_balance, 7);
+        assert_eq!(stats.active_delegations.get(&staker), Some(&300));
+        assert_eq!(stats.pending_withdrawals.len(), 1);
+        assert_eq!(stats.delegator_rewards.get(&staker), Some(&3));
+    }
+}
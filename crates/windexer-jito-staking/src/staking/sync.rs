@@ -0,0 +1,252 @@
+// crates/windexer-jito-staking/src/staking/sync.rs
+
+//! On-chain stake reconciliation
+//!
+//! [`ChainSync`] compares the stake [`StakingManager`] holds for each
+//! operator with the stake Jito vaults delegate to it on chain, every
+//! `interval_secs`. Operators whose stakes differ by more than `tolerance`
+//! are flagged: they are listed by [`ChainSync::mismatches`], logged, and
+//! raised once as a [`AlertKind::StakeMismatch`] warning. With
+//! `correct_local` set the local stake is also replaced by the on-chain one.
+
+use {
+    crate::{
+        error::{Result, StakingError},
+        staking::{
+            chain::{StakeSource, JITO_VAULT_PROGRAM_ID},
+            StakingManager,
+        },
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeSet, HashMap},
+        str::FromStr,
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tokio::task::JoinHandle,
+    tracing::{info, warn},
+    windexer_common::{
+        alerts::{Alert, AlertBus, AlertKind, Severity},
+        shutdown::{self, ShutdownToken},
+        utils::current_timestamp,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainSyncConfig {
+    /// Seconds between reconciliations
+    pub interval_secs: u64,
+    /// Vault program the delegations are read from
+    pub program_id: String,
+    /// Vaults whose delegations count; every vault when empty
+    pub vaults: Vec<String>,
+    /// Difference in stake ignored, for rounding and in-flight updates
+    pub tolerance: u64,
+    /// Replace the local stake of a mismatched operator with the on-chain one
+    pub correct_local: bool,
+    /// URLs every mismatch alert is posted to
+    pub webhooks: Vec<String>,
+}
+
+impl Default for ChainSyncConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            program_id: JITO_VAULT_PROGRAM_ID.to_string(),
+            vaults: Vec::new(),
+            tolerance: 0,
+            correct_local: false,
+            webhooks: Vec::new(),
+        }
+    }
+}
+
+/// An operator whose local and on-chain stake differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StakeMismatch {
+    pub operator: Pubkey,
+    pub local_stake: u64,
+    pub onchain_stake: u64,
+    /// Unix time the mismatch was first seen
+    pub detected_at: i64,
+}
+
+pub struct ChainSync {
+    config: ChainSyncConfig,
+    vaults: Vec<Pubkey>,
+    manager: Arc<StakingManager>,
+    source: Arc<dyn StakeSource>,
+    alerts: AlertBus,
+    mismatches: RwLock<HashMap<Pubkey, StakeMismatch>>,
+}
+
+impl ChainSync {
+    pub fn new(
+        config: ChainSyncConfig,
+        manager: Arc<StakingManager>,
+        source: Arc<dyn StakeSource>,
+        alerts: AlertBus,
+    ) -> Result<Self> {
+        let vaults = config
+            .vaults
+            .iter()
+            .map(|vault| {
+                Pubkey::from_str(vault)
+                    .map_err(|e| StakingError::ChainSync(format!("Invalid vault {}: {}", vault, e)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            config,
+            vaults,
+            manager,
+            source,
+            alerts,
+            mismatches: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Operators currently flagged
+    pub fn mismatches(&self) -> Vec<StakeMismatch> {
+        self.mismatches.read().unwrap().values().cloned().collect()
+    }
+
+    /// Compare every operator known locally or delegated to on chain, and
+    /// return the mismatches found
+    pub async fn reconcile(&self) -> Result<Vec<StakeMismatch>> {
+        let mut onchain: HashMap<Pubkey, u64> = HashMap::new();
+        for delegation in self.source.delegations(&self.vaults).await? {
+            *onchain.entry(delegation.operator).or_default() += delegation.total_security();
+        }
+        let local: HashMap<Pubkey, u64> = self
+            .manager
+            .get_all_operators()
+            .await
+            .into_iter()
+            .map(|(operator, stats)| (operator, stats.total_stake))
+            .collect();
+
+        let operators: BTreeSet<Pubkey> = onchain.keys().chain(local.keys()).copied().collect();
+        let mut found = Vec::new();
+        for operator in operators {
+            let local_stake = local.get(&operator).copied().unwrap_or_default();
+            let onchain_stake = onchain.get(&operator).copied().unwrap_or_default();
+            if local_stake.abs_diff(onchain_stake) <= self.config.tolerance {
+                if self.mismatches.write().unwrap().remove(&operator).is_some() {
+                    info!("Stake of operator {} matches the chain again", operator);
+                }
+                continue;
+            }
+
+            let mismatch = self.flag(operator, local_stake, onchain_stake);
+            if self.config.correct_local {
+                self.manager.sync_stake(operator, onchain_stake).await?;
+            }
+            found.push(mismatch);
+        }
+        Ok(found)
+    }
+
+    fn flag(&self, operator: Pubkey, local_stake: u64, onchain_stake: u64) -> StakeMismatch {
+        let mut mismatches = self.mismatches.write().unwrap();
+        let previous = mismatches.get(&operator).cloned();
+        let mismatch = StakeMismatch {
+            operator,
+            local_stake,
+            onchain_stake,
+            detected_at: previous.as_ref().map_or_else(current_timestamp, |previous| previous.detected_at),
+        };
+        mismatches.insert(operator, mismatch.clone());
+        drop(mismatches);
+
+        if previous.is_none() {
+            self.alerts.publish(Alert::new(
+                Severity::Warning,
+                AlertKind::StakeMismatch {
+                    operator: operator.to_string(),
+                    local_stake,
+                    onchain_stake,
+                },
+                format!(
+                    "Operator {} holds {} stake locally but {} on chain",
+                    operator, local_stake, onchain_stake
+                ),
+            ));
+        }
+        mismatch
+    }
+
+    /// Reconcile every `interval_secs` until `shutdown` is cancelled
+    pub fn spawn(self: &Arc<Self>, shutdown: Option<ShutdownToken>) -> JoinHandle<()> {
+        let sync = self.clone();
+        let period = Duration::from_secs(self.config.interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown::cancelled(shutdown.as_ref()) => {
+                        info!("Stopped on-chain stake sync");
+                        return;
+                    }
+                }
+                if let Err(e) = sync.reconcile().await {
+                    warn!("Failed to reconcile stake with {}: {}", sync.source.name(), e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::staking::{chain::VaultDelegation, types::StakingConfig},
+        async_trait::async_trait,
+    };
+
+    struct Fixed(Vec<VaultDelegation>);
+
+    #[async_trait]
+    impl StakeSource for Fixed {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn delegations(&self, _vaults: &[Pubkey]) -> Result<Vec<VaultDelegation>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn flags_and_corrects_mismatches() {
+        let (operator, staker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let manager = Arc::new(StakingManager::new(StakingConfig::default()));
+        manager.process_stake(1_000, staker, operator).await.unwrap();
+
+        let source = Arc::new(Fixed(vec![VaultDelegation {
+            vault: Pubkey::new_unique(),
+            operator,
+            staked_amount: 1_200,
+            enqueued_for_cooldown_amount: 0,
+            cooling_down_amount: 300,
+            last_update_slot: 1,
+        }]));
+        let bus = AlertBus::new();
+        let mut alerts = bus.subscribe();
+        let config = ChainSyncConfig { correct_local: true, ..ChainSyncConfig::default() };
+        let sync = ChainSync::new(config, manager.clone(), source, bus).unwrap();
+
+        let found = sync.reconcile().await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].local_stake, found[0].onchain_stake), (1_000, 1_500));
+        assert!(matches!(alerts.try_recv().unwrap().kind, AlertKind::StakeMismatch { .. }));
+
+        assert!(sync.reconcile().await.unwrap().is_empty());
+        assert!(sync.mismatches().is_empty());
+        assert_eq!(manager.get_operator_stats(&operator).await.unwrap().total_stake, 1_500);
+    }
+}
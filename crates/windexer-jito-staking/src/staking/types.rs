@@ -1,9 +1,11 @@
 // crates/windexer-jito-staking/src/staking/types.rs
 
 use solana_sdk::pubkey::Pubkey;
+use crate::slashing::ViolationType;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use std::collections::HashMap;
+use windexer_common::types::SlashRecord;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct StakingConfig {
@@ -14,6 +16,26 @@ pub struct StakingConfig {
     pub distribution_interval: Duration,
     pub slash_threshold: f64,
     pub min_uptime: f64,
+    /// Epochs unstaked stake cools down before it can be withdrawn
+    #[serde(default)]
+    pub cooldown_epochs: u64,
+    /// Epochs of operator performance history kept; zero keeps
+    /// [`DEFAULT_PERFORMANCE_RETENTION_EPOCHS`]
+    #[serde(default)]
+    pub performance_retention_epochs: u64,
+}
+
+/// Epochs of operator performance history kept unless configured
+pub const DEFAULT_PERFORMANCE_RETENTION_EPOCHS: u64 = 30;
+
+impl StakingConfig {
+    /// Epochs of operator performance history kept
+    pub fn performance_retention(&self) -> u64 {
+        match self.performance_retention_epochs {
+            0 => DEFAULT_PERFORMANCE_RETENTION_EPOCHS,
+            epochs => epochs,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -23,12 +45,79 @@ pub struct OperatorStats {
     pub active_delegations: HashMap<Pubkey, u64>,
     pub last_active: Option<i64>,
     pub performance_score: f64,
+    /// Rewards earned by the operator and not yet paid out
+    #[serde(default)]
+    pub reward_balance: u64,
+    /// Rewards accrued to each delegator and not yet paid out
+    #[serde(default)]
+    pub delegator_rewards: HashMap<Pubkey, u64>,
+    /// Unstaked stake waiting out its cooldown, no longer in `total_stake`
+    #[serde(default)]
+    pub pending_withdrawals: Vec<PendingWithdrawal>,
 }
 
-#[derive(Debug)]
-pub struct DelegationInfo {
+/// Stake a staker unstaked from an operator
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingWithdrawal {
     pub staker: Pubkey,
+    pub amount: u64,
+    /// Epoch the unstake was requested in
+    pub requested_epoch: u64,
+    /// First epoch the stake can be withdrawn in
+    pub withdrawable_epoch: u64,
+}
+
+/// Changes to stake, published by [`StakingManager`](super::StakingManager)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StakingEvent {
+    /// An operator was registered without stake
+    OperatorRegistered { operator: Pubkey },
+    Staked { operator: Pubkey, staker: Pubkey, amount: u64 },
+    /// Stake left the operator and started cooling down
+    UnstakeRequested { operator: Pubkey, withdrawal: PendingWithdrawal },
+    /// Cooled down stake was released to its staker
+    Withdrawn { operator: Pubkey, withdrawal: PendingWithdrawal },
+    /// Stake moved from one operator to another without cooling down
+    Redelegated { staker: Pubkey, from: Pubkey, to: Pubkey, amount: u64 },
+    /// Stake was taken from an operator for a violation
+    Slashed(SlashEvent),
+}
+
+/// Stake taken from an operator for a violation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashEvent {
     pub operator: Pubkey,
+    pub violation: ViolationType,
+    /// Stake deducted, at most the stake the operator held
     pub amount: u64,
+    pub stake_before: u64,
+    /// Epoch the slash happened in
+    pub epoch: u64,
+    /// Unix timestamp in seconds of the slash
     pub timestamp: i64,
+}
+
+impl SlashEvent {
+    /// The slash as it is stored and served
+    pub fn to_record(&self) -> SlashRecord {
+        SlashRecord {
+            operator: self.operator.to_string(),
+            violation: self.violation.to_string(),
+            amount: self.amount,
+            stake_before: self.stake_before,
+            epoch: self.epoch,
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// Stake one staker delegates to one operator
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DelegationInfo {
+    pub staker: Pubkey,
+    pub operator: Pubkey,
+    pub amount: u64,
+    /// Rewards accrued to the staker through this delegation and not yet paid out
+    pub rewards: u64,
 }
\ No newline at end of file
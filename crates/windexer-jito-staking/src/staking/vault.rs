@@ -1,7 +1,7 @@
 // crates/windexer-jito-staking/src/staking/vault.rs
 
 use solana_sdk::pubkey::Pubkey;
-use anyhow::Result;
+use crate::error::{Result, StakingError};
 
 pub struct VaultManager {
     vaults: Vec<Pubkey>,
@@ -32,7 +32,7 @@ impl VaultManager {
         _amount: u64
     ) -> Result<()> {
         if !self.vaults.contains(&vault) {
-            return Err(anyhow::anyhow!("Invalid vault"));
+            return Err(StakingError::InvalidVault(vault));
         }
         Ok(())
     }
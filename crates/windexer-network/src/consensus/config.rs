@@ -3,6 +3,9 @@ pub struct ConsensusConfig {
     pub min_validators: usize,
     pub consensus_threshold: f64,
     pub block_time: u64,
+    /// How many slots behind the newest finalized one votes and
+    /// attestations are kept in memory
+    pub retained_slots: u64,
 }
 
 impl Default for ConsensusConfig {
@@ -11,6 +14,7 @@ impl Default for ConsensusConfig {
             min_validators: 4,
             consensus_threshold: 0.66,
             block_time: 400,
+            retained_slots: 10_000,
         }
     }
-} 
\ No newline at end of file
+}
@@ -1,10 +1,17 @@
 // crates/windexer-network/src/consensus/mod.rs
 
 //! Consensus module integrating with Jito staking for validator consensus
+//!
+//! After indexing a rooted slot each operator signs a vote for the content
+//! hash of the slot's data and gossips it on the `consensus` topic. Votes are
+//! weighted by the voter's stake in the [`ValidatorSet`](validator::ValidatorSet);
+//! once a hash has the configured share of stake behind it the slot is
+//! network-finalized and its votes are kept as a
+//! [`SlotAttestation`](windexer_common::types::SlotAttestation).
 
 pub mod config;
 pub mod protocol;
 pub mod state;
 pub mod validator;
 
-pub use protocol::{ConsensusProtocol, ConsensusMessage, BlockHash};
+pub use protocol::{ConsensusProtocol, ConsensusMessage, SlashNotice};
@@ -1,145 +1,350 @@
 // crates/windexer-network/src/consensus/protocol.rs
 
 use {
-    std::{sync::Arc, time::Duration},
-    tokio::sync::{mpsc, RwLock},
+    std::sync::Arc,
+    tokio::sync::{broadcast, RwLock},
     anyhow::{Result, anyhow},
-    tracing::{debug, error, info, warn},
-    windexer_common::types::block::BlockData,
-    solana_sdk::pubkey::Pubkey,
-    windexer_jito_staking::{
-        StakingManager,
-        OperatorStats,
+    serde::{Deserialize, Serialize},
+    tracing::{debug, info, warn},
+    solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Keypair, signer::Signer},
+    windexer_common::types::{ConsensusVote, SlotAttestation},
+    windexer_jito_staking::{ConsensusManager, PerformanceTracker, SlashEvent, SlashingManager, ViolationType},
+    crate::{
+        consensus::{
+            state::ConsensusState,
+            validator::ValidatorSet,
+            config::ConsensusConfig,
+        },
+        gossip::Topic,
+        node::GossipHandle,
     },
-    crate::consensus::{
-        state::ConsensusState,
-        validator::ValidatorSet,
-        config::ConsensusConfig,
-    },
-    crate::gossip::{GossipMessage, MessageType},
-    hex,
-    windexer_common::utils::slot_status::SlotStatus,
 };
 
-pub struct ConsensusProtocol {
-    state: Arc<RwLock<ConsensusState>>,
-    validator_set: Arc<RwLock<ValidatorSet>>,
-    message_tx: mpsc::Sender<ConsensusMessage>,
-    staking_manager: Arc<StakingManager>,
-}
+/// Application-specific gossipsub score a slash takes off the slashed
+/// operator's node; at gossipsub's default weight of 10, one slash stops
+/// peers publishing to it and two graylist it
+const SLASH_SCORE_PENALTY: f64 = 5.0;
 
-#[derive(Debug, Clone)]
+/// Messages gossiped on the consensus topic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConsensusMessage {
-    BlockProposal(BlockData),
-    BlockVote {
-        slot: u64,
-        proposer: String,
-        is_valid: bool,
-    },
-    BlockConfirmation(BlockData),
+    Vote(ConsensusVote),
+    /// An operator was slashed by the node that published this
+    Slash(SlashNotice),
 }
 
-pub type BlockHash = [u8; 32];
+/// Announces a slash so other nodes lower the slashed operator's score,
+/// with the votes that prove it so they don't have to trust the sender
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlashNotice {
+    pub violation: ViolationType,
+    pub amount: u64,
+    /// The slashed operator's signed vote against the finalized hash
+    pub vote: ConsensusVote,
+    /// The votes that finalized the slot with another hash
+    pub attestation: SlotAttestation,
+}
+
+/// Collects stake-weighted votes on the content of rooted slots and
+/// finalizes a slot once enough stake agrees on its hash
+pub struct ConsensusProtocol {
+    keypair: Arc<Keypair>,
+    validators: Arc<RwLock<ValidatorSet>>,
+    manager: ConsensusManager,
+    state: RwLock<ConsensusState>,
+    gossip: GossipHandle,
+    finalized: broadcast::Sender<SlotAttestation>,
+    slashing: Option<Arc<SlashingManager>>,
+    performance: Option<Arc<PerformanceTracker>>,
+    retained_slots: u64,
+}
 
 impl ConsensusProtocol {
+    /// Votes are signed with `keypair`, and counted only from operators in
+    /// `validators`, weighted by their stake
     pub fn new(
-        _config: ConsensusConfig,
-        state: Arc<RwLock<ConsensusState>>,
-        validator_set: Arc<RwLock<ValidatorSet>>,
-        staking_manager: Arc<StakingManager>,
+        config: ConsensusConfig,
+        keypair: Arc<Keypair>,
+        validators: Arc<RwLock<ValidatorSet>>,
+        gossip: GossipHandle,
     ) -> Self {
-        let (message_tx, _message_rx) = mpsc::channel(1000);
         Self {
-            state,
-            validator_set,
-            message_tx,
-            staking_manager,
+            keypair,
+            validators,
+            manager: ConsensusManager::new(config.min_validators, config.consensus_threshold),
+            state: RwLock::new(ConsensusState::new()),
+            gossip,
+            finalized: broadcast::channel(1024).0,
+            slashing: None,
+            performance: None,
+            retained_slots: config.retained_slots,
         }
     }
 
-    // Modify handle_block to check stake
-    async fn handle_block(&mut self, block: BlockData) -> Result<()> {
+    /// Reports operators that vote against finalized hashes as
+    /// [`ViolationType::DivergentData`] violations; slashes they trigger are
+    /// gossiped as [`SlashNotice`]s
+    pub fn with_slashing(mut self, slashing: Arc<SlashingManager>) -> Self {
+        self.slashing = Some(slashing);
+        self
+    }
+
+    /// Reports which operators voted on each finalized slot, and how
+    /// quickly, to score their performance
+    pub fn with_performance(mut self, performance: Arc<PerformanceTracker>) -> Self {
+        self.performance = Some(performance);
+        self
+    }
+
+    /// Votes for `content_hash` as the content of the rooted `slot`
+    pub async fn attest(&self, slot: u64, content_hash: Hash) -> Result<()> {
+        let vote = ConsensusVote::sign(&self.keypair, slot, content_hash);
+        self.state.write().await.record_local(slot, content_hash);
+        self.gossip.publish(Topic::Consensus, bincode::serialize(&ConsensusMessage::Vote(vote.clone()))?)?;
+        // Gossipsub doesn't deliver our own messages back to us
+        self.handle_vote(vote).await
+    }
+
+    /// Counts a vote, finalizing its slot if it reaches the threshold
+    pub async fn handle_vote(&self, vote: ConsensusVote) -> Result<()> {
+        if !vote.verify() {
+            return Err(anyhow!("Invalid signature on vote from {}", vote.voter));
+        }
+        let validators = self.validators.read().await;
+        let stake = validators.get_stake(&vote.voter);
+        if stake == 0 {
+            return Err(anyhow!("Vote from {} who is not a staked operator", vote.voter));
+        }
+
         let mut state = self.state.write().await;
-        
-        if block.block_height != Some(state.height + 1) {
+        if let Some(attestation) = state.finalized(vote.slot) {
+            if attestation.content_hash != vote.content_hash {
+                let attestation = attestation.clone();
+                self.flag_divergent(&mut state, &vote, &attestation).await;
+            } else if let Some(performance) = &self.performance {
+                performance.record_message(vote.voter, true);
+            }
             return Ok(());
         }
+        if !state.add_vote(vote.clone()) {
+            return Ok(());
+        }
+        if let Some(performance) = &self.performance {
+            performance.record_vote(vote.slot, vote.voter);
+        }
+        let Some(hash) = self.manager
+            .record_vote(vote.slot, vote.content_hash.to_bytes(), vote.voter, stake, validators.total_stake())
+            .await
+        else {
+            return Ok(());
+        };
 
-        // Create a 32-byte array for the pubkey
-        let mut bytes = [0u8; 32];
-        bytes[..8].copy_from_slice(&block.slot.to_le_bytes());
-        let proposer = Pubkey::new_from_array(bytes);
+        let content_hash = Hash::new_from_array(hash);
+        let (votes, divergent): (Vec<_>, Vec<_>) = state.take_votes(vote.slot)
+            .into_iter()
+            .partition(|vote| vote.content_hash == content_hash);
+        let attestation = SlotAttestation {
+            slot: vote.slot,
+            content_hash,
+            stake: votes.iter().map(|vote| validators.get_stake(&vote.voter)).sum(),
+            total_stake: validators.total_stake(),
+            votes,
+        };
+        match state.local_hash(vote.slot) {
+            Some(local) if local != content_hash => warn!(
+                "Slot {} finalized with hash {} but this node indexed {}",
+                vote.slot, content_hash, local
+            ),
+            _ => info!("Slot {} network-finalized with hash {}", vote.slot, content_hash),
+        }
+        state.finalize(attestation.clone());
+        if let Some(performance) = &self.performance {
+            let expected: Vec<Pubkey> = validators.get_validators().copied().collect();
+            performance.close_slot(vote.slot, &expected);
+            for vote in &attestation.votes {
+                performance.record_message(vote.voter, true);
+            }
+        }
+        for vote in &divergent {
+            self.flag_divergent(&mut state, vote, &attestation).await;
+        }
 
-        let operator_stats = self.staking_manager
-            .get_operator_stats(&proposer)
-            .await?;
+        if let Some(before) = vote.slot.checked_sub(self.retained_slots) {
+            state.prune(before);
+            self.manager.prune_votes(before).await;
+        }
+        // Fails only without receivers
+        let _ = self.finalized.send(attestation);
+        Ok(())
+    }
 
-        if !self.is_stake_sufficient(&operator_stats).await? {
-            return Err(anyhow!("Insufficient stake for block proposal"));
+    async fn flag_divergent(&self, state: &mut ConsensusState, vote: &ConsensusVote, attestation: &SlotAttestation) {
+        if !state.flag(vote.slot, vote.voter) {
+            return;
         }
+        warn!(
+            "Operator {} voted {} for slot {}, which the network finalized with another hash",
+            vote.voter, vote.content_hash, vote.slot
+        );
+        if let Some(performance) = &self.performance {
+            performance.record_message(vote.voter, false);
+        }
+        if let Some(slashing) = &self.slashing {
+            match slashing.process_violation(&vote.voter, ViolationType::DivergentData).await {
+                Ok(Some(slash)) => self.announce_slash(slash, vote, attestation),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to report divergent data from {}: {}", vote.voter, e),
+            }
+        }
+    }
 
-        state.current_block = Some(block.clone());
-        state.height += 1;
-        
-        self.message_tx.send(ConsensusMessage::BlockProposal(block)).await?;
+    fn announce_slash(&self, slash: SlashEvent, vote: &ConsensusVote, attestation: &SlotAttestation) {
+        let notice = SlashNotice {
+            violation: slash.violation,
+            amount: slash.amount,
+            vote: vote.clone(),
+            attestation: attestation.clone(),
+        };
+        let published = bincode::serialize(&ConsensusMessage::Slash(notice))
+            .map_err(anyhow::Error::from)
+            .and_then(|payload| self.gossip.publish(Topic::Consensus, payload))
+            .and_then(|_| self.gossip.lower_score(slash.operator, SLASH_SCORE_PENALTY));
+        if let Err(e) = published {
+            warn!("Failed to announce the slash of {}: {}", slash.operator, e);
+        }
+    }
 
-        Ok(())
+    /// Lowers the score of an operator another staked operator slashed,
+    /// once the notice's votes prove it voted against a finalized hash
+    async fn handle_slash_notice(&self, signer: Pubkey, notice: SlashNotice) -> Result<()> {
+        let validators = self.validators.read().await;
+        if validators.get_stake(&signer) == 0 {
+            return Err(anyhow!("Slash notice from {} who is not a staked operator", signer));
+        }
+        let (vote, attestation) = (&notice.vote, &notice.attestation);
+        if notice.violation != ViolationType::DivergentData {
+            return Err(anyhow!("Slash notice for {} carries no evidence", notice.violation));
+        }
+        if !vote.verify() || vote.slot != attestation.slot || vote.content_hash == attestation.content_hash {
+            return Err(anyhow!("Slash notice for {} has no divergent vote of it", vote.voter));
+        }
+        if !self.manager.verify_attestation(attestation, |voter| validators.get_stake(voter), validators.total_stake()) {
+            return Err(anyhow!("Slash notice for {} has no valid attestation of slot {}", vote.voter, vote.slot));
+        }
+        drop(validators);
+
+        if vote.voter == self.keypair.pubkey() {
+            warn!("Operator {} reports this node was slashed {} for {}", signer, notice.amount, notice.violation);
+            return Ok(());
+        }
+        // Counted once per slot, however many nodes announce it
+        if !self.state.write().await.flag(vote.slot, vote.voter) {
+            return Ok(());
+        }
+        info!(
+            "Operator {} slashed {} by {} for {} at slot {}",
+            signer, vote.voter, notice.amount, notice.violation, vote.slot
+        );
+        self.gossip.lower_score(vote.voter, SLASH_SCORE_PENALTY)
     }
 
-    async fn is_stake_sufficient(&self, stats: &OperatorStats) -> Result<bool> {
-        Ok(stats.total_stake >= self.staking_manager.config().min_stake)
+    /// Handles votes from the consensus topic until the node stops
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        let mut messages = self.gossip.messages();
+        self.gossip.subscribe(Topic::Consensus)?;
+        loop {
+            match messages.recv().await {
+                Ok(message) if message.topic == Topic::Consensus => {
+                    match bincode::deserialize(&message.payload) {
+                        Ok(ConsensusMessage::Vote(vote)) => {
+                            if let Err(e) = self.handle_vote(vote).await {
+                                debug!("Ignoring vote from {}: {}", message.signer, e);
+                            }
+                        }
+                        Ok(ConsensusMessage::Slash(notice)) => {
+                            if let Err(e) = self.handle_slash_notice(message.signer, notice).await {
+                                debug!("Ignoring slash notice from {}: {}", message.signer, e);
+                            }
+                        }
+                        Err(e) => warn!("Malformed consensus message from {}: {}", message.signer, e),
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Consensus fell behind and skipped {} messages", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            }
+        }
     }
 
-    // Modify check_consensus to use stake-weighted voting
-    async fn check_consensus(&mut self, block_hash: &BlockHash) -> Result<()> {
-        let validator_set = self.validator_set.read().await;
-
-        let total_stake = self.get_total_active_stake().await?;
-        let vote_stake = self.get_vote_stake(block_hash, &*validator_set).await?;
-
-        if vote_stake * 3 > total_stake * 2 {
-            self.message_tx
-                .send(ConsensusMessage::BlockConfirmation(BlockData {
-                    blockhash: Some(hex::encode(block_hash)),
-                    slot: 0,
-                    block_height: None,
-                    parent_slot: None,
-                    parent_blockhash: None,
-                    transaction_count: None,
-                    timestamp: None,
-                    rewards: None,
-                    entries: Vec::new(),
-                    entry_count: 0,
-                    status: SlotStatus::Processed,
-                }))
-                .await?;
-        }
-
-        Ok(())  
+    /// The attestation of `slot`, if it was finalized recently
+    pub async fn finalized(&self, slot: u64) -> Option<SlotAttestation> {
+        self.state.read().await.finalized(slot).cloned()
     }
 
-    async fn get_total_active_stake(&self) -> Result<u64> {
-        let mut total = 0;
-        let validators = self.validator_set.read().await;
-        
-        for validator in validators.get_validators() {
-            let stats = self.staking_manager.get_operator_stats(validator).await?;
-            total += stats.total_stake;
-        }
-        Ok(total)
+    /// Operators that voted against finalized hashes, with the number of
+    /// slots they did so for
+    pub async fn divergent_operators(&self) -> Vec<(Pubkey, u64)> {
+        let mut operators: Vec<(Pubkey, u64)> = self.state.read().await.divergent()
+            .iter()
+            .map(|(operator, slots)| (*operator, *slots))
+            .collect();
+        operators.sort_by(|a, b| b.1.cmp(&a.1));
+        operators
+    }
+
+    /// Attestations of slots finalized from now on, e.g. for marking them in
+    /// a store
+    pub fn finalized_slots(&self) -> broadcast::Receiver<SlotAttestation> {
+        self.finalized.subscribe()
     }
+}
 
-    async fn get_vote_stake(&self, block_hash: &BlockHash, _validator_set: &ValidatorSet) -> Result<u64> {
-        let state = self.state.read().await;
-        let mut vote_stake = 0;
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::testing::TestNetwork,
+        solana_sdk::{hash::hash, signer::Signer},
+        std::time::Duration,
+    };
 
-        if let Some(votes) = state.get_votes(block_hash) {
-            for validator in votes.keys() {
-                let info = self.staking_manager.get_operator_stats(validator).await?;
-                vote_stake += info.total_stake;
-            }
+    #[tokio::test]
+    async fn slots_finalize_across_nodes() {
+        let network = TestNetwork::start(3).await.unwrap();
+        let keypairs: Vec<Arc<Keypair>> = (0..3).map(|_| Arc::new(Keypair::new())).collect();
+        let mut validators = ValidatorSet::new();
+        for keypair in &keypairs {
+            validators.add_validator(keypair.pubkey(), 100);
+        }
+        let validators = Arc::new(RwLock::new(validators));
+        let config = ConsensusConfig { min_validators: 2, ..Default::default() };
+
+        let protocols: Vec<Arc<ConsensusProtocol>> = network.nodes().iter().zip(&keypairs)
+            .map(|(node, keypair)| Arc::new(ConsensusProtocol::new(
+                config.clone(),
+                keypair.clone(),
+                validators.clone(),
+                node.gossip.clone(),
+            )))
+            .collect();
+        let mut finalized: Vec<_> = protocols.iter().map(|protocol| protocol.finalized_slots()).collect();
+        for protocol in &protocols {
+            tokio::spawn(protocol.clone().run());
         }
+        network.wait_for_topic(Topic::Consensus, Duration::from_secs(10)).await.unwrap();
 
-        Ok(vote_stake)
+        let content_hash = hash(b"slot 42");
+        for protocol in &protocols {
+            protocol.attest(42, content_hash).await.unwrap();
+        }
+        for receiver in &mut finalized {
+            let attestation = tokio::time::timeout(Duration::from_secs(10), receiver.recv())
+                .await.unwrap().unwrap();
+            assert_eq!((attestation.slot, attestation.content_hash), (42, content_hash));
+            assert!(attestation.votes.iter().all(ConsensusVote::verify));
+        }
+        network.shutdown().await.unwrap();
     }
-}
\ No newline at end of file
+}
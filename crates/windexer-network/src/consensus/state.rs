@@ -1,37 +1,86 @@
 // crates/windexer-network/src/consensus/state.rs
 
 use {
-    solana_sdk::vote::state::Vote,
-    windexer_common::types::block::BlockData,
-    super::protocol::BlockHash,
-    solana_sdk::pubkey::Pubkey,
-    std::collections::HashMap,
-    std::sync::Arc,
-    tokio::sync::RwLock,
+    solana_sdk::{hash::Hash, pubkey::Pubkey},
+    std::collections::{BTreeMap, HashMap, HashSet},
+    windexer_common::types::{ConsensusVote, SlotAttestation},
 };
 
+/// Votes and attestations for recent slots
+#[derive(Debug, Default)]
 pub struct ConsensusState {
-    pub height: u64,
-    pub current_block: Option<BlockData>,
-    pub votes: HashMap<BlockHash, HashMap<Pubkey, Vote>>,
+    /// Hash this node attested to for each slot it indexed
+    local: BTreeMap<u64, Hash>,
+    /// Verified votes for slots that are not finalized yet
+    pending: BTreeMap<u64, Vec<ConsensusVote>>,
+    finalized: BTreeMap<u64, SlotAttestation>,
+    /// Operators flagged for voting against the finalized hash, per slot
+    flagged: BTreeMap<u64, HashSet<Pubkey>>,
+    /// Number of slots each operator was flagged for
+    divergent: HashMap<Pubkey, u64>,
 }
 
 impl ConsensusState {
     pub fn new() -> Self {
-        Self {
-            height: 0,
-            current_block: None,
-            votes: HashMap::new(),
+        Self::default()
+    }
+
+    pub fn record_local(&mut self, slot: u64, content_hash: Hash) {
+        self.local.insert(slot, content_hash);
+    }
+
+    pub fn local_hash(&self, slot: u64) -> Option<Hash> {
+        self.local.get(&slot).copied()
+    }
+
+    /// Keeps a vote, returning `false` if the slot is already finalized or
+    /// the voter already voted for it
+    pub fn add_vote(&mut self, vote: ConsensusVote) -> bool {
+        if self.finalized.contains_key(&vote.slot) {
+            return false;
+        }
+        let votes = self.pending.entry(vote.slot).or_default();
+        if votes.iter().any(|existing| existing.voter == vote.voter) {
+            return false;
         }
+        votes.push(vote);
+        true
+    }
+
+    /// Removes and returns the pending votes of `slot`
+    pub fn take_votes(&mut self, slot: u64) -> Vec<ConsensusVote> {
+        self.pending.remove(&slot).unwrap_or_default()
     }
 
-    pub fn get_votes(&self, block_hash: &BlockHash) -> Option<&HashMap<Pubkey, Vote>> {
-        self.votes.get(block_hash)
+    pub fn finalize(&mut self, attestation: SlotAttestation) {
+        self.finalized.insert(attestation.slot, attestation);
     }
 
-    pub fn add_vote(&mut self, block_hash: BlockHash, validator: Pubkey, vote: Vote) {
-        self.votes.entry(block_hash)
-            .or_default()
-            .insert(validator, vote);
+    pub fn finalized(&self, slot: u64) -> Option<&SlotAttestation> {
+        self.finalized.get(&slot)
     }
-}
\ No newline at end of file
+
+    /// Flags `voter` for divergent content in `slot`, returning `false` if
+    /// it already was, so a replayed vote is only counted once
+    pub fn flag(&mut self, slot: u64, voter: Pubkey) -> bool {
+        if !self.flagged.entry(slot).or_default().insert(voter) {
+            return false;
+        }
+        *self.divergent.entry(voter).or_default() += 1;
+        true
+    }
+
+    /// Operators that voted against finalized hashes, with their slot counts
+    pub fn divergent(&self) -> &HashMap<Pubkey, u64> {
+        &self.divergent
+    }
+
+    /// Forgets everything about slots before `slot`, except the counts of
+    /// divergent votes
+    pub fn prune(&mut self, slot: u64) {
+        self.local = self.local.split_off(&slot);
+        self.pending = self.pending.split_off(&slot);
+        self.finalized = self.finalized.split_off(&slot);
+        self.flagged = self.flagged.split_off(&slot);
+    }
+}
@@ -15,10 +15,14 @@ use {
 mod mesh_manager;
 mod message_handler;
 mod topic_handler;
+pub mod signing;
+pub mod topics;
 
 pub use mesh_manager::MeshManager;
 pub use message_handler::MessageHandler;
 pub use topic_handler::TopicHandler;
+pub use signing::{Rejection, SignedPayload};
+pub use topics::Topic;
 
 /// Main gossip subsystem that coordinates network message propagation
 /// with stake-weighted validation and peer scoring
@@ -0,0 +1,101 @@
+// crates/windexer-network/src/gossip/signing.rs
+
+//! Signed gossip payloads
+//!
+//! Every payload a node publishes is wrapped in a [`SignedPayload`] carrying
+//! the node's Solana pubkey and an ed25519 signature over the topic and the
+//! payload, so a message can't be replayed on another topic. Receivers
+//! verify the signature and that the signer is the libp2p peer that authored
+//! the message; the node identity is derived from the same key.
+
+use {
+    anyhow::{anyhow, Result},
+    libp2p::{identity, PeerId},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::{Keypair, Signature},
+        signer::Signer,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedPayload {
+    pub signer: Pubkey,
+    pub signature: Signature,
+    pub payload: Vec<u8>,
+}
+
+/// Why a received payload was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// Not a signed payload at all
+    Malformed,
+    /// The signature does not match the signer, topic and payload
+    BadSignature,
+    /// Validly signed, but by someone other than the message author
+    WrongSigner,
+}
+
+fn signed_bytes(topic: &str, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(topic.len() + 1 + payload.len());
+    bytes.extend_from_slice(topic.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// libp2p peer id of the node whose Solana identity is `pubkey`
+pub fn peer_id_of(pubkey: &Pubkey) -> Result<PeerId> {
+    let public = identity::ed25519::PublicKey::try_from_bytes(&pubkey.to_bytes())
+        .map_err(|e| anyhow!("Invalid ed25519 key {}: {}", pubkey, e))?;
+    Ok(PeerId::from(identity::PublicKey::from(public)))
+}
+
+impl SignedPayload {
+    pub fn sign(keypair: &Keypair, topic: &str, payload: Vec<u8>) -> Self {
+        let signature = keypair.sign_message(&signed_bytes(topic, &payload));
+        Self {
+            signer: keypair.pubkey(),
+            signature,
+            payload,
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Decodes and verifies a payload received on `topic` from `author`
+    pub fn verify(data: &[u8], topic: &str, author: Option<&PeerId>) -> Result<Self, Rejection> {
+        let signed: SignedPayload = bincode::deserialize(data).map_err(|_| Rejection::Malformed)?;
+        if !signed.signature.verify(signed.signer.as_ref(), &signed_bytes(topic, &signed.payload)) {
+            return Err(Rejection::BadSignature);
+        }
+        if let Some(author) = author {
+            if peer_id_of(&signed.signer).ok().as_ref() != Some(author) {
+                return Err(Rejection::WrongSigner);
+            }
+        }
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_tampered_and_replayed_payloads() {
+        let keypair = Keypair::new();
+        let author = peer_id_of(&keypair.pubkey()).unwrap();
+        let data = SignedPayload::sign(&keypair, "blocks", vec![1, 2, 3]).encode().unwrap();
+
+        let signed = SignedPayload::verify(&data, "blocks", Some(&author)).unwrap();
+        assert_eq!(signed.payload, vec![1, 2, 3]);
+
+        assert_eq!(SignedPayload::verify(&data, "entries", Some(&author)), Err(Rejection::BadSignature));
+        assert_eq!(SignedPayload::verify(&data, "blocks", Some(&PeerId::random())), Err(Rejection::WrongSigner));
+        assert_eq!(SignedPayload::verify(&[0xff], "blocks", None), Err(Rejection::Malformed));
+    }
+}
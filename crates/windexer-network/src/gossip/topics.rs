@@ -0,0 +1,173 @@
+// crates/windexer-network/src/gossip/topics.rs
+
+//! Gossip topic names
+//!
+//! Account updates are published twice: to the global `accounts` topic and
+//! to `accounts/{program_id}` for the program that owns the account. A node
+//! that only cares about a few programs subscribes to their topics instead of
+//! the firehose.
+//!
+//! [`Topic::catalog`] lists the well-known topics; per-program topics are
+//! any `accounts/{program_id}`.
+
+use {
+    anyhow::{anyhow, Result},
+    libp2p::gossipsub::{IdentTopic, TopicHash},
+    solana_sdk::pubkey::Pubkey,
+    std::{fmt, str::FromStr},
+    windexer_common::filter::{Filter, Subject},
+};
+
+const ACCOUNTS: &str = "accounts";
+const TRANSACTIONS: &str = "transactions";
+const BLOCKS: &str = "blocks";
+const SLOTS: &str = "slots";
+const ENTRIES: &str = "entries";
+const SHARDS: &str = "shards";
+const CONSENSUS: &str = "consensus";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Every account update
+    Accounts,
+    /// Updates of accounts owned by one program
+    ProgramAccounts(Pubkey),
+    Transactions,
+    Blocks,
+    /// Slot status changes
+    Slots,
+    Entries,
+    /// Erasure-coded slot bundle shards
+    Shards,
+    /// Operators' votes on the content of rooted slots
+    Consensus,
+}
+
+impl Topic {
+    /// Program-scoped topic an account owned by `owner` is published to
+    pub fn for_owner(owner: &Pubkey) -> Self {
+        Topic::ProgramAccounts(*owner)
+    }
+
+    /// Topics carrying every update `filter` can match: the topics of the
+    /// programs it requires accounts to be owned by, or else the accounts
+    /// and transactions topics it applies to
+    pub fn for_filter(filter: &Filter) -> Vec<Topic> {
+        if let Some(owners) = filter.required_owners() {
+            return owners.iter().map(Topic::for_owner).collect();
+        }
+        [(Subject::Accounts, Topic::Accounts), (Subject::Transactions, Topic::Transactions)]
+            .into_iter()
+            .filter(|(subject, _)| filter.check(*subject).is_ok())
+            .map(|(_, topic)| topic)
+            .collect()
+    }
+
+    /// Every well-known topic, without the per-program ones
+    pub fn catalog() -> [Topic; 7] {
+        [
+            Topic::Accounts,
+            Topic::Transactions,
+            Topic::Blocks,
+            Topic::Slots,
+            Topic::Entries,
+            Topic::Shards,
+            Topic::Consensus,
+        ]
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Topic::Accounts => "Every account update",
+            Topic::ProgramAccounts(_) => "Updates of accounts owned by one program",
+            Topic::Transactions => "Transactions",
+            Topic::Blocks => "Block metadata",
+            Topic::Slots => "Slot status changes",
+            Topic::Entries => "Ledger entries",
+            Topic::Shards => "Erasure-coded slot bundle shards",
+            Topic::Consensus => "Votes on the content of rooted slots",
+        }
+    }
+
+    pub fn ident(&self) -> IdentTopic {
+        IdentTopic::new(self.to_string())
+    }
+
+    pub fn hash(&self) -> TopicHash {
+        self.ident().hash()
+    }
+
+    /// Topics a node joins on start: the global accounts topic, or only the
+    /// topics of `programs` when any are given, plus the other data topics
+    pub fn subscriptions(programs: &[String]) -> Result<Vec<Self>> {
+        let mut topics = if programs.is_empty() {
+            vec![Topic::Accounts]
+        } else {
+            programs.iter()
+                .map(|program| {
+                    Pubkey::from_str(program)
+                        .map(Topic::ProgramAccounts)
+                        .map_err(|e| anyhow!("Invalid account program {}: {}", program, e))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+        topics.extend([Topic::Transactions, Topic::Blocks, Topic::Entries]);
+        Ok(topics)
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::Accounts => f.write_str(ACCOUNTS),
+            Topic::ProgramAccounts(program) => write!(f, "{}/{}", ACCOUNTS, program),
+            Topic::Transactions => f.write_str(TRANSACTIONS),
+            Topic::Blocks => f.write_str(BLOCKS),
+            Topic::Slots => f.write_str(SLOTS),
+            Topic::Entries => f.write_str(ENTRIES),
+            Topic::Shards => f.write_str(SHARDS),
+            Topic::Consensus => f.write_str(CONSENSUS),
+        }
+    }
+}
+
+impl FromStr for Topic {
+    type Err = anyhow::Error;
+
+    fn from_str(topic: &str) -> Result<Self> {
+        match topic.split_once('/') {
+            None => match topic {
+                ACCOUNTS => Ok(Topic::Accounts),
+                TRANSACTIONS => Ok(Topic::Transactions),
+                BLOCKS => Ok(Topic::Blocks),
+                SLOTS => Ok(Topic::Slots),
+                ENTRIES => Ok(Topic::Entries),
+                SHARDS => Ok(Topic::Shards),
+                CONSENSUS => Ok(Topic::Consensus),
+                _ => Err(anyhow!("Unknown topic {}", topic)),
+            },
+            Some((ACCOUNTS, program)) => Pubkey::from_str(program)
+                .map(Topic::ProgramAccounts)
+                .map_err(|e| anyhow!("Invalid program in topic {}: {}", topic, e)),
+            Some(_) => Err(anyhow!("Unknown topic {}", topic)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn program_topics_round_trip() {
+        let program = Pubkey::new_unique();
+        let topic = Topic::for_owner(&program);
+        assert_eq!(topic.to_string(), format!("accounts/{}", program));
+        assert_eq!(topic.to_string().parse::<Topic>().unwrap(), topic);
+        assert!("accounts/not-a-key".parse::<Topic>().is_err());
+
+        let topics = Topic::subscriptions(&[program.to_string()]).unwrap();
+        assert!(!topics.contains(&Topic::Accounts));
+        assert!(topics.contains(&topic));
+    }
+}
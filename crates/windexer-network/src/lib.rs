@@ -13,6 +13,10 @@ pub mod node;
 pub mod gossip;
 pub mod consensus;
 pub mod metrics;
+pub mod protocol;
+pub mod sharding;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[derive(Debug, Error)]
 pub enum NetworkError {
@@ -45,7 +49,7 @@ pub type Result<T> = std::result::Result<T, NetworkError>;
 
 pub use node::Node;
 pub use windexer_common::config::NodeConfig;
-pub use gossip::{GossipConfig, GossipMessage, MessageType};
+pub use gossip::{GossipConfig, GossipMessage, MessageType, Topic};
 pub use consensus::config::ConsensusConfig;
 
 pub fn init_logging() {
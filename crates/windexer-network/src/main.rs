@@ -1,24 +1,36 @@
 use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::{path::PathBuf, time::Duration};
 use tracing::info;
-use windexer_common::config::NodeConfig;
+use windexer_common::{
+    config::{ConfigArgs, ConfigCommand, ConfigLoader, ConnectionConfig, NodeConfig, TransportKind, Validate},
+    errors::{Error, Result as ConfigResult},
+    shutdown::{Shutdown, Stage, DEFAULT_SHUTDOWN_TIMEOUT},
+};
 use windexer_network::Node;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Node ID
-    #[arg(long, default_value = "windexer-node")]
-    node_id: String,
+    #[arg(long)]
+    node_id: Option<String>,
 
     /// Listen address port
-    #[arg(long, default_value = "9000")]
-    port: u16,
+    #[arg(long)]
+    port: Option<u16>,
 
     /// RPC port
-    #[arg(long, default_value = "8899")]
-    rpc_port: u16,
+    #[arg(long)]
+    rpc_port: Option<u16>,
 
     /// Bootstrap peers
     #[arg(long, value_delimiter = ',')]
@@ -27,33 +39,148 @@ struct Args {
     /// Data directory
     #[arg(long)]
     data_dir: Option<PathBuf>,
+
+    /// Only receive account updates of these programs
+    #[arg(long, value_delimiter = ',')]
+    account_programs: Vec<String>,
+
+    /// Transport to listen on and dial with: tcp, quic or both
+    #[arg(long)]
+    transport: Option<TransportKind>,
+
+    /// Maximum inbound connections
+    #[arg(long)]
+    max_inbound: Option<u32>,
+
+    /// Maximum outbound connections
+    #[arg(long)]
+    max_outbound: Option<u32>,
+
+    /// Maximum connections from or to one IP address
+    #[arg(long)]
+    max_per_ip: Option<u32>,
+
+    /// Only accept connections from these peer ids
+    #[arg(long, value_delimiter = ',')]
+    allow_peers: Vec<String>,
+
+    /// Refuse connections from these peer ids
+    #[arg(long, value_delimiter = ',')]
+    deny_peers: Vec<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Configuration checks
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+/// Node settings, from a `--config` file, `WINDEXER_*` variables and flags
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    node_id: String,
+    port: u16,
+    rpc_port: u16,
+    bootstrap_peers: Vec<String>,
+    data_dir: Option<String>,
+    account_programs: Vec<String>,
+    transport: TransportKind,
+    connections: ConnectionConfig,
+    /// Seconds to save state and leave the mesh after Ctrl+C or SIGTERM
+    shutdown_timeout_secs: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            node_id: "windexer-node".to_string(),
+            port: 9000,
+            rpc_port: 8899,
+            bootstrap_peers: Vec::new(),
+            data_dir: None,
+            account_programs: Vec::new(),
+            transport: TransportKind::default(),
+            connections: ConnectionConfig::default(),
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT.as_secs(),
+        }
+    }
+}
+
+impl Validate for Settings {
+    fn validate(&self) -> ConfigResult<()> {
+        if self.node_id.is_empty() {
+            return Err(Error::Config("node_id must not be empty".to_string()));
+        }
+        if self.port == self.rpc_port {
+            return Err(Error::Config(format!("port and rpc_port are both {}", self.port)));
+        }
+        for program in &self.account_programs {
+            program.parse::<Pubkey>()
+                .map_err(|_| Error::Config(format!("Invalid account program {}", program)))?;
+        }
+        Ok(())
+    }
+}
+
+impl Args {
+    /// Flags given on the command line override every other layer
+    fn loader(&self) -> Result<ConfigLoader> {
+        let list = |values: &Vec<String>| (!values.is_empty()).then(|| values.clone());
+        Ok(self.config.loader()?
+            .set_some("node_id", self.node_id.clone())
+            .set_some("port", self.port)
+            .set_some("rpc_port", self.rpc_port)
+            .set_some("bootstrap_peers", list(&self.bootstrap_peers))
+            .set_some("data_dir", self.data_dir.as_ref().map(|dir| dir.to_string_lossy().to_string()))
+            .set_some("account_programs", list(&self.account_programs))
+            .set_some("transport", self.transport)
+            .set_some("connections.max_inbound", self.max_inbound)
+            .set_some("connections.max_outbound", self.max_outbound)
+            .set_some("connections.max_per_ip", self.max_per_ip)
+            .set_some("connections.allowlist", list(&self.allow_peers))
+            .set_some("connections.denylist", list(&self.deny_peers)))
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args = Args::parse();
+    let loader = args.loader()?;
+    if let Some(Command::Config(ConfigCommand::Validate)) = args.command {
+        println!("{}", loader.check::<Settings>()?);
+        return Ok(());
+    }
+
     // Initialize logging
     windexer_network::init_logging();
 
-    // Parse command line arguments
-    let args = Args::parse();
-    
-    // Create the NodeConfig using the correct constructor
+    let settings: Settings = loader.load()?;
     let mut config = NodeConfig::new_local(
-        args.node_id,
-        args.port,
-        args.rpc_port,
-        args.bootstrap_peers,
+        settings.node_id,
+        settings.port,
+        settings.rpc_port,
+        settings.bootstrap_peers,
     );
     
-    if let Some(data_dir) = args.data_dir {
-        config.data_dir = data_dir.to_string_lossy().to_string();
+    config.account_programs = settings.account_programs;
+    config.transport = settings.transport;
+    config.connections = settings.connections;
+    if let Some(data_dir) = settings.data_dir {
+        config.data_dir = data_dir;
     }
 
     info!("Starting windexer network node with config: {:?}", config);
     
     // Create and start the node
+    let shutdown = Shutdown::new(Duration::from_secs(settings.shutdown_timeout_secs));
     let (mut node, _shutdown_tx) = Node::create_simple(config).await?;
-    node.start().await?;
-    
-    Ok(())
-} 
\ No newline at end of file
+    node.set_shutdown(shutdown.token(Stage::Network));
+    let mut running = tokio::spawn(async move { node.start().await });
+    tokio::select! {
+        result = &mut running => return result?,
+        _ = shutdown.signalled() => {}
+    }
+    running.await?
+}
@@ -1,32 +1,170 @@
 // crates/windexer-network/src/metrics.rs
 
-use std::sync::atomic::{AtomicU64, Ordering};
+//! Node metrics
+//!
+//! | metric                                        | type    | labels             |
+//! |-----------------------------------------------|---------|--------------------|
+//! | `windexer_network_connected_peers`            | gauge   |                    |
+//! | `windexer_network_valid_messages_total`       | counter |                    |
+//! | `windexer_network_invalid_messages_total`     | counter |                    |
+//! | `windexer_network_gossip_messages_total`      | counter | `topic`, `outcome` |
+//! | `windexer_network_rejected_signatures_total`  | counter |                    |
+//! | `windexer_network_rejected_connections_total` | counter |                    |
+//! | `windexer_network_incompatible_peers_total`   | counter |                    |
+//!
+//! Gossip messages are counted per topic with an `outcome` of `valid` or
+//! `invalid`; past [`MAX_TOPIC_LABELS`] topics, such as many per-program
+//! account topics, the rest are counted as `other`.
+//!
+//! Register them with the process's
+//! [`MetricsRegistry`](windexer_common::metrics::MetricsRegistry) through
+//! [`Node::metrics`](crate::Node::metrics).
+
+use {
+    crate::protocol::Capabilities,
+    libp2p::PeerId,
+    std::{
+        collections::HashMap,
+        sync::Mutex,
+    },
+    windexer_common::metrics::{self, Collector, IntCounter, IntCounterVec, IntGauge, LabelLimit, MetricSet},
+};
+
+const SUBSYSTEM: &str = "network";
+
+/// Distinct topics labeled before the rest are counted as `other`
+pub const MAX_TOPIC_LABELS: usize = 100;
 
 #[derive(Debug)]
 pub struct Metrics {
-    connected_peers: AtomicU64,
-    valid_messages: AtomicU64,
-    invalid_messages: AtomicU64,
+    connected_peers: IntGauge,
+    valid_messages: IntCounter,
+    invalid_messages: IntCounter,
+    gossip_messages: IntCounterVec,
+    topic_labels: LabelLimit,
+    rejected_signatures: IntCounter,
+    rejected_connections: IntCounter,
+    incompatible_peers: IntCounter,
+    /// Capabilities negotiated with each connected peer
+    peer_capabilities: Mutex<HashMap<PeerId, Capabilities>>,
 }
 
 impl Metrics {
     pub fn new() -> Self {
         Self {
-            connected_peers: AtomicU64::new(0),
-            valid_messages: AtomicU64::new(0),
-            invalid_messages: AtomicU64::new(0),
+            connected_peers: metrics::gauge(SUBSYSTEM, "connected_peers", "Peers the node is connected to"),
+            valid_messages: metrics::counter(SUBSYSTEM, "valid_messages_total", "Gossip messages accepted"),
+            invalid_messages: metrics::counter(SUBSYSTEM, "invalid_messages_total", "Gossip messages rejected"),
+            gossip_messages: metrics::counter_vec(
+                SUBSYSTEM,
+                "gossip_messages_total",
+                "Gossip messages received per topic and validation outcome",
+                &["topic", "outcome"],
+            ),
+            topic_labels: LabelLimit::new(MAX_TOPIC_LABELS),
+            rejected_signatures: metrics::counter(
+                SUBSYSTEM,
+                "rejected_signatures_total",
+                "Messages dropped for a missing, bad or foreign signature",
+            ),
+            rejected_connections: metrics::counter(
+                SUBSYSTEM,
+                "rejected_connections_total",
+                "Connections closed for a ban, the allowlist or a connection limit",
+            ),
+            incompatible_peers: metrics::counter(
+                SUBSYSTEM,
+                "incompatible_peers_total",
+                "Peers disconnected for speaking an incompatible protocol",
+            ),
+            peer_capabilities: Mutex::new(HashMap::new()),
         }
     }
 
-    pub fn increment_valid_messages(&self) {
-        self.valid_messages.fetch_add(1, Ordering::Relaxed);
+    pub fn increment_valid_messages(&self, topic: &str) {
+        self.valid_messages.inc();
+        self.count_message(topic, "valid");
+    }
+
+    pub fn increment_invalid_messages(&self, topic: &str) {
+        self.invalid_messages.inc();
+        self.count_message(topic, "invalid");
+    }
+
+    fn count_message(&self, topic: &str, outcome: &str) {
+        self.gossip_messages
+            .with_label_values(&[self.topic_labels.label(topic), outcome])
+            .inc();
+    }
+
+    /// Counts a message dropped for a missing, bad or foreign signature
+    pub fn increment_rejected_signatures(&self) {
+        self.rejected_signatures.inc();
+    }
+
+    /// Counts a connection closed for a ban, the allowlist or a connection limit
+    pub fn increment_rejected_connections(&self) {
+        self.rejected_connections.inc();
+    }
+
+    /// Counts a peer disconnected for speaking an incompatible protocol
+    pub fn increment_incompatible_peers(&self) {
+        self.incompatible_peers.inc();
+    }
+
+    pub fn set_peer_capabilities(&self, peer_id: PeerId, capabilities: Capabilities) {
+        self.peer_capabilities.lock().unwrap().insert(peer_id, capabilities);
+    }
+
+    pub fn remove_peer_capabilities(&self, peer_id: &PeerId) {
+        self.peer_capabilities.lock().unwrap().remove(peer_id);
     }
 
-    pub fn increment_invalid_messages(&self) {
-        self.invalid_messages.fetch_add(1, Ordering::Relaxed);
+    pub fn valid_messages(&self) -> u64 {
+        self.valid_messages.get()
+    }
+
+    pub fn invalid_messages(&self) -> u64 {
+        self.invalid_messages.get()
+    }
+
+    pub fn rejected_signatures(&self) -> u64 {
+        self.rejected_signatures.get()
+    }
+
+    pub fn rejected_connections(&self) -> u64 {
+        self.rejected_connections.get()
+    }
+
+    pub fn incompatible_peers(&self) -> u64 {
+        self.incompatible_peers.get()
+    }
+
+    pub fn peer_capabilities(&self) -> HashMap<PeerId, Capabilities> {
+        self.peer_capabilities.lock().unwrap().clone()
     }
 
     pub fn set_connected_peers(&self, count: u64) {
-        self.connected_peers.store(count, Ordering::Relaxed);
+        self.connected_peers.set(count as i64);
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
     }
-}
\ No newline at end of file
+}
+
+impl MetricSet for Metrics {
+    fn collectors(&self) -> Vec<&dyn Collector> {
+        vec![
+            &self.connected_peers,
+            &self.valid_messages,
+            &self.invalid_messages,
+            &self.gossip_messages,
+            &self.rejected_signatures,
+            &self.rejected_connections,
+            &self.incompatible_peers,
+        ]
+    }
+}
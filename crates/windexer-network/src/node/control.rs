@@ -0,0 +1,113 @@
+//! Peer management for a running node
+//!
+//! The node's swarm is owned by its event loop, so [`NodeControl`] does not
+//! touch it directly: bans and allowlist changes are sent to the loop as
+//! [`PeerCommand`]s and applied between swarm events. The peer registry is
+//! shared with the loop, which records what it observes of each peer.
+
+use {
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    libp2p::PeerId,
+    solana_sdk::pubkey::Pubkey,
+    std::{collections::HashSet, str::FromStr, sync::Arc},
+    tokio::sync::{mpsc, RwLock},
+    windexer_common::control::{PeerControl, PeerInfo, PeerRecord},
+    super::PeerRegistry,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerCommand {
+    Ban(PeerId),
+    Unban(PeerId),
+    Allow(PeerId),
+    Disallow(PeerId),
+}
+
+/// Cloneable handle for listing, banning and allowlisting the node's peers
+#[derive(Debug, Clone)]
+pub struct NodeControl {
+    pub(super) known_peers: Arc<RwLock<HashSet<PeerId>>>,
+    pub(super) banned_peers: Arc<RwLock<HashSet<PeerId>>>,
+    pub(super) allowed_peers: Arc<RwLock<HashSet<PeerId>>>,
+    pub(super) registry: Arc<RwLock<PeerRegistry>>,
+    pub(super) commands: mpsc::UnboundedSender<PeerCommand>,
+}
+
+impl NodeControl {
+    fn send(&self, command: PeerCommand) -> Result<()> {
+        self.commands.send(command).map_err(|_| anyhow!("Node is not running"))
+    }
+
+    /// Records the stake of `operator`, shown for the peer it runs
+    pub async fn set_operator_stake(&self, operator: Pubkey, stake: u64) {
+        self.registry.write().await.set_stake(operator, stake);
+    }
+}
+
+pub(super) fn parse_peer_id(peer_id: &str) -> Result<PeerId> {
+    PeerId::from_str(peer_id).map_err(|e| anyhow!("Invalid peer id {}: {}", peer_id, e))
+}
+
+#[async_trait]
+impl PeerControl for NodeControl {
+    async fn peers(&self) -> Result<Vec<PeerInfo>> {
+        let known = self.known_peers.read().await;
+        let banned = self.banned_peers.read().await;
+        let allowed = self.allowed_peers.read().await;
+
+        let listed: HashSet<PeerId> = known.iter().chain(banned.iter()).chain(allowed.iter()).copied().collect();
+        let mut peers: Vec<PeerInfo> = listed.iter()
+            .map(|peer_id| PeerInfo {
+                peer_id: peer_id.to_string(),
+                connected: known.contains(peer_id),
+                banned: banned.contains(peer_id),
+                allowed: allowed.contains(peer_id),
+            })
+            .collect();
+        peers.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+        Ok(peers)
+    }
+
+    async fn registry(&self) -> Result<Vec<PeerRecord>> {
+        let known = self.known_peers.read().await;
+        Ok(self.registry.read().await.records(&known))
+    }
+
+    async fn ban_peer(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        self.banned_peers.write().await.insert(peer_id);
+        self.send(PeerCommand::Ban(peer_id))
+    }
+
+    async fn unban_peer(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        if !self.banned_peers.write().await.remove(&peer_id) {
+            return Err(anyhow!("Peer {} is not banned", peer_id));
+        }
+        self.send(PeerCommand::Unban(peer_id))
+    }
+
+    async fn allowlist(&self) -> Result<Vec<String>> {
+        let mut peers: Vec<String> = self.allowed_peers.read().await
+            .iter()
+            .map(PeerId::to_string)
+            .collect();
+        peers.sort();
+        Ok(peers)
+    }
+
+    async fn allow_peer(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        self.allowed_peers.write().await.insert(peer_id);
+        self.send(PeerCommand::Allow(peer_id))
+    }
+
+    async fn disallow_peer(&self, peer_id: &str) -> Result<()> {
+        let peer_id = parse_peer_id(peer_id)?;
+        if !self.allowed_peers.write().await.remove(&peer_id) {
+            return Err(anyhow!("Peer {} is not on the allowlist", peer_id));
+        }
+        self.send(PeerCommand::Disallow(peer_id))
+    }
+}
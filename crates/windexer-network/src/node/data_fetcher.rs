@@ -3,6 +3,7 @@ use tokio::sync::RwLock;
 use anyhow::Result;
 use windexer_common::{
     helius::{HeliusClient, HeliusConfig},
+    rpc_provider::RpcProvider,
     types::helius::{AccountData, BlockData, TransactionData},
 };
 
@@ -46,6 +47,14 @@ impl HeliusDataFetcher {
         }
     }
     
+    /// Create a new data fetcher reading from any upstream RPC provider
+    pub fn new_with_provider(provider: Arc<dyn RpcProvider>) -> Self {
+        Self {
+            client: Arc::new(HeliusClient::with_provider(HeliusConfig::default(), provider)),
+            cache: Arc::new(RwLock::new(DataCache::default())),
+        }
+    }
+    
     /// Initialize the data fetcher
     pub async fn initialize(&self) -> Result<()> {
         // Connect to WebSocket to receive updates
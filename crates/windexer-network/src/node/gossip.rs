@@ -0,0 +1,148 @@
+//! Publishing and topic subscriptions for a running node
+//!
+//! Like [`NodeControl`](super::NodeControl), [`GossipHandle`] leaves the swarm
+//! to the node's event loop and sends it [`GossipCommand`]s instead, so it can
+//! be cloned into publishers on other tasks. It also implements
+//! [`TopicControl`] so the admin API can change subscriptions.
+
+use {
+    crate::gossip::Topic,
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    libp2p::PeerId,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+    tokio::sync::{broadcast, mpsc, RwLock},
+    windexer_common::{
+        control::{TopicControl, TopicInfo},
+        filter::Filter,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GossipCommand {
+    Subscribe(Topic),
+    Unsubscribe(Topic),
+    Publish(Topic, Vec<u8>),
+    /// Lower the application-specific gossipsub score of the node whose
+    /// Solana identity is the pubkey by the amount
+    LowerScore(Pubkey, f64),
+}
+
+/// A verified message received on a subscribed topic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedMessage {
+    pub topic: Topic,
+    /// Solana identity of the node that published it
+    pub signer: Pubkey,
+    pub payload: Vec<u8>,
+}
+
+/// Cloneable handle for publishing to and subscribing to gossip topics
+#[derive(Debug, Clone)]
+pub struct GossipHandle {
+    pub(super) topics: Arc<RwLock<HashSet<Topic>>>,
+    /// Peers known to be subscribed to each topic
+    pub(super) topic_peers: Arc<RwLock<HashMap<Topic, HashSet<PeerId>>>>,
+    pub(super) connected_peers: Arc<AtomicUsize>,
+    pub(super) commands: mpsc::UnboundedSender<GossipCommand>,
+    pub(super) received: broadcast::Sender<ReceivedMessage>,
+}
+
+impl GossipHandle {
+    fn send(&self, command: GossipCommand) -> Result<()> {
+        self.commands.send(command).map_err(|_| anyhow!("Node is not running"))
+    }
+
+    pub fn subscribe(&self, topic: Topic) -> Result<()> {
+        self.send(GossipCommand::Subscribe(topic))
+    }
+
+    pub fn unsubscribe(&self, topic: Topic) -> Result<()> {
+        self.send(GossipCommand::Unsubscribe(topic))
+    }
+
+    /// Queue `data` for publishing; it is sent once the event loop gets to it
+    pub fn publish(&self, topic: Topic, data: Vec<u8>) -> Result<()> {
+        self.send(GossipCommand::Publish(topic, data))
+    }
+
+    /// Lower the gossipsub score of the node of `operator` by `penalty`, in
+    /// application-specific score, e.g. after it was slashed; the node keeps
+    /// the lowered score for as long as it runs
+    pub fn lower_score(&self, operator: Pubkey, penalty: f64) -> Result<()> {
+        self.send(GossipCommand::LowerScore(operator, penalty))
+    }
+
+    /// Whether the node is running and has peers to publish to
+    pub fn is_connected(&self) -> bool {
+        !self.commands.is_closed() && self.connected_peers.load(Ordering::Relaxed) > 0
+    }
+
+    /// Number of connected peers subscribed to `topic`
+    pub async fn topic_peers(&self, topic: Topic) -> usize {
+        self.topic_peers.read().await.get(&topic).map_or(0, HashSet::len)
+    }
+
+    /// Messages received from now on, on every subscribed topic; a receiver
+    /// that falls too far behind skips the oldest
+    pub fn messages(&self) -> broadcast::Receiver<ReceivedMessage> {
+        self.received.subscribe()
+    }
+
+    /// Topics the node is subscribed to
+    pub async fn topics(&self) -> Vec<Topic> {
+        let mut topics: Vec<Topic> = self.topics.read().await.iter().copied().collect();
+        topics.sort_by_key(|topic| topic.to_string());
+        topics
+    }
+}
+
+#[async_trait]
+impl TopicControl for GossipHandle {
+    async fn topics(&self) -> Result<Vec<TopicInfo>> {
+        let subscribed = self.topics.read().await;
+        let mut topics: Vec<Topic> = Topic::catalog().into_iter()
+            .chain(subscribed.iter().copied())
+            .collect();
+        topics.sort_by_key(|topic| topic.to_string());
+        topics.dedup();
+
+        Ok(topics.into_iter()
+            .map(|topic| TopicInfo {
+                topic: topic.to_string(),
+                description: topic.description().to_string(),
+                subscribed: subscribed.contains(&topic),
+            })
+            .collect())
+    }
+
+    async fn subscribe(&self, topic: &str) -> Result<()> {
+        GossipHandle::subscribe(self, topic.parse()?)
+    }
+
+    async fn unsubscribe(&self, topic: &str) -> Result<()> {
+        let topic: Topic = topic.parse()?;
+        if !self.topics.read().await.contains(&topic) {
+            return Err(anyhow!("Not subscribed to {}", topic));
+        }
+        GossipHandle::unsubscribe(self, topic)
+    }
+
+    async fn subscribe_filter(&self, filter: &Filter) -> Result<Vec<String>> {
+        let topics = Topic::for_filter(filter);
+        if topics.is_empty() {
+            return Err(anyhow!("No topic carries both accounts and transactions matching {}", filter));
+        }
+        for topic in &topics {
+            GossipHandle::subscribe(self, *topic)?;
+        }
+        Ok(topics.iter().map(Topic::to_string).collect())
+    }
+}
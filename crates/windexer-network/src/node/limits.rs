@@ -0,0 +1,117 @@
+//! Connection limits
+//!
+//! A connection's peer id and direction are only known once it is
+//! established, so limits are checked then and a connection over any of
+//! them is closed straight away.
+
+use {
+    libp2p::{
+        multiaddr::Protocol,
+        swarm::ConnectionId,
+        Multiaddr,
+    },
+    std::{collections::HashMap, net::IpAddr},
+    windexer_common::config::ConnectionConfig,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Connection {
+    pub inbound: bool,
+    pub ip: Option<IpAddr>,
+}
+
+/// The node's open connections, counted against its limits
+#[derive(Debug, Default)]
+pub(super) struct Connections {
+    max_inbound: Option<u32>,
+    max_outbound: Option<u32>,
+    max_per_ip: Option<u32>,
+    open: HashMap<ConnectionId, Connection>,
+}
+
+impl Connections {
+    pub fn new(config: &ConnectionConfig) -> Self {
+        Self {
+            max_inbound: config.max_inbound,
+            max_outbound: config.max_outbound,
+            max_per_ip: config.max_per_ip,
+            open: HashMap::new(),
+        }
+    }
+
+    /// The limit one more connection would exceed, if any
+    pub fn exceeded(&self, inbound: bool, ip: Option<IpAddr>) -> Option<&'static str> {
+        let count = |matches: &dyn Fn(&Connection) -> bool| {
+            self.open.values().filter(|connection| matches(connection)).count() as u32
+        };
+
+        let (limit, name) = if inbound {
+            (self.max_inbound, "inbound")
+        } else {
+            (self.max_outbound, "outbound")
+        };
+        if limit.is_some_and(|max| count(&|c| c.inbound == inbound) >= max) {
+            return Some(name);
+        }
+        if let (Some(max), Some(ip)) = (self.max_per_ip, ip) {
+            if count(&|c| c.ip == Some(ip)) >= max {
+                return Some("per-IP");
+            }
+        }
+        None
+    }
+
+    pub fn insert(&mut self, id: ConnectionId, connection: Connection) {
+        self.open.insert(id, connection);
+    }
+
+    pub fn remove(&mut self, id: &ConnectionId) -> Option<Connection> {
+        self.open.remove(id)
+    }
+}
+
+/// IP address a multiaddr points at, if it has one
+pub(super) fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_count_direction_and_ip() {
+        let config = ConnectionConfig {
+            max_inbound: Some(2),
+            max_per_ip: Some(1),
+            ..Default::default()
+        };
+        let mut connections = Connections::new(&config);
+        let addr: Multiaddr = "/ip4/10.0.0.1/tcp/9000".parse().unwrap();
+        let ip = ip_of(&addr);
+        assert_eq!(ip, Some("10.0.0.1".parse().unwrap()));
+
+        assert_eq!(connections.exceeded(true, ip), None);
+        connections.insert(
+            ConnectionId::new_unchecked(1),
+            Connection { inbound: true, ip },
+        );
+        assert_eq!(connections.exceeded(true, ip), Some("per-IP"));
+
+        let other = Some("10.0.0.2".parse().unwrap());
+        connections.insert(
+            ConnectionId::new_unchecked(2),
+            Connection { inbound: true, ip: other },
+        );
+        assert_eq!(connections.exceeded(true, Some("10.0.0.3".parse().unwrap())), Some("inbound"));
+        // No outbound limit is set
+        assert_eq!(connections.exceeded(false, None), None);
+
+        connections.remove(&ConnectionId::new_unchecked(1));
+        assert_eq!(connections.exceeded(true, ip), None);
+    }
+}
@@ -2,26 +2,38 @@
 
 use {
     crate::{
+        gossip::{signing::peer_id_of, Rejection, SignedPayload, Topic},
         metrics::Metrics,
+        protocol::Capabilities,
+        sharding::{Shard, ShardRequest, ShardResponse, ShardStore, ShardingConfig, ShardingService},
         NetworkPeerId,
     },
     anyhow::{anyhow, Context, Result},
     futures::StreamExt,
+    futures::future::Either,
     libp2p::{
-        core::upgrade,
+        core::{muxing::StreamMuxerBox, transport::Boxed, upgrade},
         gossipsub::{
             self, 
             Behaviour as GossipsubBehaviour,
+            MessageAcceptance,
             MessageAuthenticity,
+            PeerScoreParams,
+            PeerScoreThresholds,
             ValidationMode,
         },
+        identify,
         mdns::{self, tokio::Behaviour as MdnsBehaviour},
         noise,
-        swarm::{NetworkBehaviour, SwarmEvent, Swarm, Config as SwarmConfig},
+        ping,
+        quic,
+        request_response::{self, cbor::Behaviour as ShardsBehaviour, OutboundRequestId, ProtocolSupport},
+        swarm::{behaviour::toggle::Toggle, NetworkBehaviour, SwarmEvent, Swarm, Config as SwarmConfig},
         tcp,
         yamux,
         Multiaddr,
         PeerId,
+        StreamProtocol,
         Transport,
         identity,
     },
@@ -30,23 +42,45 @@ use {
         signer::keypair::Keypair as agaveKeypair,
     },
     std::{
-        collections::HashSet,
-        sync::Arc,
+        collections::{HashMap, HashSet},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
         time::Duration,
     },
     tokio::{
-        sync::{mpsc, RwLock, Mutex},
+        sync::{broadcast, mpsc, oneshot, RwLock, Mutex},
         time,
     },
     tracing::{debug, info, warn},
-    windexer_common::config::NodeConfig,
+    windexer_common::{
+        config::{NodeConfig, TransportKind},
+        rpc_provider::RpcProvider,
+        shutdown::{self, ShutdownToken},
+    },
 };
 
+mod control;
 mod data_fetcher;
+mod gossip;
+mod limits;
+mod registry;
+mod sharding;
 
 use std::convert::TryInto;
 
+pub use control::{NodeControl, PeerCommand};
 pub use data_fetcher::HeliusDataFetcher;
+pub use gossip::{GossipCommand, GossipHandle, ReceivedMessage};
+pub use registry::PeerRegistry;
+pub use sharding::{ShardCommand, ShardHandle};
+
+/// Invalid messages a peer may relay before it is banned
+const MAX_INVALID_MESSAGES: u32 = 5;
+
+/// Request-response protocol for fetching shards from their owners
+const SHARDS_PROTOCOL: StreamProtocol = StreamProtocol::new("/windexer/shards/1");
 
 pub fn convert_keypair(solana_keypair: &agaveKeypair) -> identity::Keypair {
     let full_bytes = solana_keypair.to_bytes();
@@ -57,12 +91,59 @@ pub fn convert_keypair(solana_keypair: &agaveKeypair) -> identity::Keypair {
         .expect("Valid keypair conversion")
 }
 
+/// Transport for `kind`; with both, QUIC is tried first when an address could use either
+fn build_transport(keypair: &identity::Keypair, kind: TransportKind) -> Result<Boxed<(PeerId, StreamMuxerBox)>> {
+    let tcp = || -> Result<Boxed<(PeerId, StreamMuxerBox)>> {
+        let tcp_config = tcp::Config::default().nodelay(true);
+        Ok(tcp::tokio::Transport::new(tcp_config)
+            .upgrade(upgrade::Version::V1)
+            .authenticate(noise::Config::new(keypair).context("Invalid noise config")?)
+            .multiplex(yamux::Config::default())
+            .boxed())
+    };
+    let quic = || {
+        quic::tokio::Transport::new(quic::Config::new(keypair))
+            .map(|(peer_id, connection), _| (peer_id, StreamMuxerBox::new(connection)))
+            .boxed()
+    };
+
+    Ok(match kind {
+        TransportKind::Tcp => tcp()?,
+        TransportKind::Quic => quic(),
+        TransportKind::Both => quic()
+            .or_transport(tcp()?)
+            .map(|either, _| match either {
+                Either::Left(output) | Either::Right(output) => output,
+            })
+            .boxed(),
+    })
+}
+
+/// Addresses to listen on for the configured transport; TCP and QUIC share the port number
+fn listen_addrs(config: &NodeConfig) -> Result<Vec<Multiaddr>> {
+    let ip = config.listen_addr.ip();
+    let port = config.listen_addr.port();
+    let mut addrs = Vec::new();
+    if config.transport.tcp() {
+        addrs.push(format!("/ip4/{}/tcp/{}", ip, port).parse()?);
+    }
+    if config.transport.quic() {
+        addrs.push(format!("/ip4/{}/udp/{}/quic-v1", ip, port).parse()?);
+    }
+    Ok(addrs)
+}
+
 // Combined network behavior using both gossipsub and mDNS
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "NodeEvent")]
 struct NodeBehaviour {
     gossipsub: GossipsubBehaviour,
-    mdns: MdnsBehaviour,
+    /// Off on transports without real network interfaces
+    mdns: Toggle<MdnsBehaviour>,
+    shards: ShardsBehaviour<ShardRequest, ShardResponse>,
+    identify: identify::Behaviour,
+    /// Round trips for the peer registry's latency
+    ping: ping::Behaviour,
 }
 
 // Events that can be produced by our network behavior
@@ -70,6 +151,9 @@ struct NodeBehaviour {
 enum NodeEvent {
     Gossipsub(gossipsub::Event),
     Mdns(mdns::Event),
+    Shards(request_response::Event<ShardRequest, ShardResponse>),
+    Identify(identify::Event),
+    Ping(ping::Event),
 }
 
 impl From<gossipsub::Event> for NodeEvent {
@@ -84,13 +168,61 @@ impl From<mdns::Event> for NodeEvent {
     }
 }
 
+impl From<request_response::Event<ShardRequest, ShardResponse>> for NodeEvent {
+    fn from(event: request_response::Event<ShardRequest, ShardResponse>) -> Self {
+        NodeEvent::Shards(event)
+    }
+}
+
+impl From<identify::Event> for NodeEvent {
+    fn from(event: identify::Event) -> Self {
+        NodeEvent::Identify(event)
+    }
+}
+
+impl From<ping::Event> for NodeEvent {
+    fn from(event: ping::Event) -> Self {
+        NodeEvent::Ping(event)
+    }
+}
+
 // Add these derives to make Node thread-safe
 pub struct Node {
     pub config: NodeConfig,
     swarm: Arc<Mutex<Swarm<NodeBehaviour>>>,
-    metrics: Arc<RwLock<Metrics>>,
+    peer_id: PeerId,
+    /// Solana identity that signs published payloads
+    signer: Arc<agaveKeypair>,
+    /// Invalid messages relayed per peer
+    invalid_messages: HashMap<PeerId, u32>,
+    /// Application-specific gossipsub score of peers whose score was lowered
+    app_scores: HashMap<PeerId, f64>,
+    metrics: Arc<Metrics>,
     known_peers: Arc<RwLock<HashSet<PeerId>>>,
+    /// Size of `known_peers`, readable without the lock
+    connected_peers: Arc<AtomicUsize>,
+    banned_peers: Arc<RwLock<HashSet<PeerId>>>,
+    /// Peers allowed to connect; any peer when empty
+    allowed_peers: Arc<RwLock<HashSet<PeerId>>>,
+    connections: limits::Connections,
+    /// Every peer seen, persisted in the data directory
+    registry: Arc<RwLock<PeerRegistry>>,
+    command_tx: mpsc::UnboundedSender<PeerCommand>,
+    command_rx: mpsc::UnboundedReceiver<PeerCommand>,
+    topics: Arc<RwLock<HashSet<Topic>>>,
+    topic_peers: Arc<RwLock<HashMap<Topic, HashSet<PeerId>>>>,
+    /// Verified messages, for [`GossipHandle::messages`]
+    received: broadcast::Sender<ReceivedMessage>,
+    gossip_tx: mpsc::UnboundedSender<GossipCommand>,
+    gossip_rx: mpsc::UnboundedReceiver<GossipCommand>,
+    sharding: Option<Arc<ShardingService>>,
+    shard_tx: mpsc::UnboundedSender<ShardCommand>,
+    shard_rx: mpsc::UnboundedReceiver<ShardCommand>,
+    /// Shard fetches waiting for a response
+    pending_shards: HashMap<OutboundRequestId, oneshot::Sender<Option<Shard>>>,
     shutdown_rx: mpsc::Receiver<()>,
+    /// Stops the node with the rest of the process, see [`Node::set_shutdown`]
+    shutdown: Option<ShutdownToken>,
     helius_data_fetcher: Option<Arc<HeliusDataFetcher>>,
 }
 
@@ -101,6 +233,9 @@ impl std::fmt::Debug for Node {
             .field("config", &self.config)
             .field("metrics", &self.metrics)
             .field("known_peers", &self.known_peers)
+            .field("banned_peers", &self.banned_peers)
+            .field("allowed_peers", &self.allowed_peers)
+            .field("topics", &self.topics)
             .field("helius_data_fetcher", &self.helius_data_fetcher)
             .finish_non_exhaustive()
     }
@@ -108,74 +243,169 @@ impl std::fmt::Debug for Node {
 
 impl Node {
     pub async fn create_simple(config: NodeConfig) -> Result<(Self, tokio::sync::mpsc::Sender<()>)> {
+        let kind = config.transport;
+        Self::create(config, |keypair| build_transport(keypair, kind), true).await
+    }
+
+    /// Creates a node on the transport `transport` builds for its identity,
+    /// discovering local peers over mDNS only if `discovery` is set
+    pub(crate) async fn create(
+        config: NodeConfig,
+        transport: impl FnOnce(&identity::Keypair) -> Result<Boxed<(PeerId, StreamMuxerBox)>>,
+        discovery: bool,
+    ) -> Result<(Self, mpsc::Sender<()>)> {
         let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
         
         // Initialize libp2p keypair from Solana keypair
-        let keypair = match config.keypair.to_keypair() {
-            Ok(kp) => convert_keypair(&kp),
+        let signer = match config.keypair.to_keypair() {
+            Ok(kp) => Arc::new(kp),
             Err(e) => return Err(anyhow!("Failed to convert keypair: {}", e)),
         };
+        let keypair = convert_keypair(&signer);
         
         let peer_id = PeerId::from(keypair.public());
         info!("Local peer id: {}", peer_id);
         
         // Create transport
-        let tcp_config = tcp::Config::default().nodelay(true);
-        let transport = tcp::tokio::Transport::new(tcp_config)
-            .upgrade(upgrade::Version::V1)
-            .authenticate(noise::Config::new(&keypair).expect("Valid noise config"))
-            .multiplex(yamux::Config::default())
-            .boxed();
+        let transport = transport(&keypair)?;
         
         // Create gossipsub
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(10))
             .validation_mode(ValidationMode::Strict)
+            // Messages are only forwarded once their payload signature checks out
+            .validate_messages()
             .build()
             .expect("Valid gossipsub config");
             
-        let gossipsub = gossipsub::Behaviour::new(
+        let mut gossipsub = gossipsub::Behaviour::new(
             MessageAuthenticity::Signed(keypair.clone()),
             gossipsub_config,
         ).expect("Valid gossipsub behavior");
+        // Scores peers so slashed operators can be lowered, see `GossipCommand::LowerScore`
+        gossipsub
+            .with_peer_score(PeerScoreParams::default(), PeerScoreThresholds::default())
+            .map_err(|e| anyhow!("Invalid gossipsub peer scoring: {}", e))?;
+
+        // Denylisted peers are banned from the start
+        let banned_peers = config.connections.denylist.iter()
+            .map(|peer_id| control::parse_peer_id(peer_id))
+            .collect::<Result<HashSet<_>>>()?;
+        let allowed_peers = config.connections.allowlist.iter()
+            .map(|peer_id| control::parse_peer_id(peer_id))
+            .collect::<Result<HashSet<_>>>()?;
+        for peer_id in &banned_peers {
+            gossipsub.blacklist_peer(peer_id);
+        }
         
         // Create mDNS for local peer discovery
-        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id)
-            .expect("Valid mDNS config");
+        let mdns = Toggle::from(discovery.then(|| {
+            mdns::tokio::Behaviour::new(mdns::Config::default(), peer_id)
+                .expect("Valid mDNS config")
+        }));
+        
+        // Shard fetches between peers
+        let shards = ShardsBehaviour::new(
+            [(SHARDS_PROTOCOL, ProtocolSupport::Full)],
+            request_response::Config::default(),
+        );
+        
+        // Version and capability exchange
+        let capabilities = Capabilities::local(
+            &Topic::subscriptions(&config.account_programs)?,
+            &[SHARDS_PROTOCOL.as_ref()],
+        );
+        let identify = identify::Behaviour::new(
+            identify::Config::new(capabilities.protocol_version(), keypair.public())
+                .with_agent_version(capabilities.agent_version()),
+        );
         
         // Combine into node behavior
         let behaviour = NodeBehaviour {
             gossipsub,
             mdns,
+            shards,
+            identify,
+            ping: ping::Behaviour::new(ping::Config::new()),
         };
         
         // Create swarm with proper config method - using tokio executor
         let swarm_config = SwarmConfig::with_tokio_executor();
         let swarm = Swarm::new(transport, behaviour, peer_id, swarm_config);
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (gossip_tx, gossip_rx) = mpsc::unbounded_channel();
+        let (shard_tx, shard_rx) = mpsc::unbounded_channel();
+        let connections = limits::Connections::new(&config.connections);
+        let registry = PeerRegistry::open(&config.data_dir)?;
         
         let node = Self {
             config,
             swarm: Arc::new(Mutex::new(swarm)),
-            metrics: Arc::new(RwLock::new(Metrics::new())),
+            peer_id,
+            signer,
+            invalid_messages: HashMap::new(),
+            app_scores: HashMap::new(),
+            metrics: Arc::new(Metrics::new()),
             known_peers: Arc::new(RwLock::new(HashSet::new())),
+            connected_peers: Arc::new(AtomicUsize::new(0)),
+            banned_peers: Arc::new(RwLock::new(banned_peers)),
+            allowed_peers: Arc::new(RwLock::new(allowed_peers)),
+            connections,
+            registry: Arc::new(RwLock::new(registry)),
+            command_tx,
+            command_rx,
+            topics: Arc::new(RwLock::new(HashSet::new())),
+            topic_peers: Arc::new(RwLock::new(HashMap::new())),
+            received: broadcast::channel(1024).0,
+            gossip_tx,
+            gossip_rx,
+            sharding: None,
+            shard_tx,
+            shard_rx,
+            pending_shards: HashMap::new(),
             shutdown_rx,
+            shutdown: None,
             helius_data_fetcher: None,
         };
         
         Ok((node, shutdown_tx))
     }
 
+    /// Also stop when `shutdown` is cancelled, alongside the sender
+    /// returned on creation; the node holds the token until it is dropped
+    pub fn set_shutdown(&mut self, shutdown: ShutdownToken) {
+        self.shutdown = Some(shutdown);
+    }
+
     pub async fn start(&mut self) -> Result<()> {
-        info!("Starting node on {}", self.config.listen_addr);
+        info!("Starting node on {} ({:?})", self.config.listen_addr, self.config.transport);
+        let addrs = listen_addrs(&self.config)?;
+        self.start_on(addrs).await
+    }
+
+    /// Like [`Node::start`], but listening on `addrs` instead of the configured address
+    pub async fn start_on(&mut self, addrs: Vec<Multiaddr>) -> Result<()> {
+        self.open(addrs).await?;
+        self.run().await
+    }
 
-        let addr = format!("/ip4/{}/tcp/{}", 
-            self.config.listen_addr.ip(),
-            self.config.listen_addr.port()
-        ).parse::<Multiaddr>()?;
+    /// Joins the configured topics, listens on `addrs` and dials the
+    /// bootstrap peers, without running the event loop yet
+    pub(crate) async fn open(&mut self, addrs: Vec<Multiaddr>) -> Result<()> {
+        // Only the account topics of the configured programs, if any
+        let mut topics = Topic::subscriptions(&self.config.account_programs)?;
+        if self.sharding.is_some() {
+            topics.push(Topic::Shards);
+        }
+        for topic in topics {
+            self.handle_gossip_command(GossipCommand::Subscribe(topic)).await;
+        }
 
         {
             let mut swarm = self.swarm.lock().await;
-            swarm.listen_on(addr)?;
+            for addr in addrs {
+                swarm.listen_on(addr)?;
+            }
 
             for addr in &self.config.bootstrap_peers {
                 let remote: Multiaddr = addr.parse()?;
@@ -185,17 +415,23 @@ impl Node {
                 }
             }
         }
-
-        self.run().await
+        Ok(())
     }
 
-    async fn run(&mut self) -> Result<()> {
+    pub(crate) async fn run(&mut self) -> Result<()> {
         let mut heartbeat = time::interval(Duration::from_secs(30));
 
         loop {
             tokio::select! {
                 Some(_) = self.shutdown_rx.recv() => {
                     info!("Received shutdown signal");
+                    self.save_registry().await;
+                    break;
+                }
+
+                _ = shutdown::cancelled(self.shutdown.as_ref()) => {
+                    info!("Process is shutting down");
+                    self.save_registry().await;
                     break;
                 }
 
@@ -203,6 +439,18 @@ impl Node {
                     self.maintain_peers().await?;
                 }
 
+                Some(command) = self.command_rx.recv() => {
+                    self.handle_peer_command(command).await;
+                }
+
+                Some(command) = self.gossip_rx.recv() => {
+                    self.handle_gossip_command(command).await;
+                }
+
+                Some(command) = self.shard_rx.recv() => {
+                    self.handle_shard_command(command).await;
+                }
+
                 // Fix: Store swarm in a variable and use proper pinning
                 event = {
                     let mut swarm = self.swarm.lock().await;
@@ -227,11 +475,99 @@ impl Node {
             peers.len() as u64
         };
 
-        self.metrics.write().await.set_connected_peers(peer_count);
+        self.metrics.set_connected_peers(peer_count);
+        self.save_registry().await;
         
         Ok(())
     }
 
+    async fn save_registry(&self) {
+        if let Err(e) = self.registry.write().await.save() {
+            warn!("Failed to save peer registry: {}", e);
+        }
+    }
+
+    async fn handle_peer_command(&mut self, command: PeerCommand) {
+        let mut swarm = self.swarm.lock().await;
+        match command {
+            PeerCommand::Ban(peer_id) => {
+                info!("Banning peer {}", peer_id);
+                swarm.behaviour_mut().gossipsub.blacklist_peer(&peer_id);
+                let _ = swarm.disconnect_peer_id(peer_id);
+            }
+            PeerCommand::Unban(peer_id) => {
+                info!("Unbanning peer {}", peer_id);
+                swarm.behaviour_mut().gossipsub.remove_blacklisted_peer(&peer_id);
+            }
+            PeerCommand::Allow(peer_id) | PeerCommand::Disallow(peer_id) => {
+                info!("Allowlist changed for peer {}", peer_id);
+                // A first entry or a removal can leave connected peers off the list
+                let allowed = self.allowed_peers.read().await;
+                if allowed.is_empty() {
+                    return;
+                }
+                for peer_id in self.known_peers.read().await.iter() {
+                    if !allowed.contains(peer_id) {
+                        debug!("Disconnecting {}, which is not on the allowlist", peer_id);
+                        let _ = swarm.disconnect_peer_id(*peer_id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether the allowlist, if any, lets the peer connect
+    async fn is_allowed(&self, peer_id: &PeerId) -> bool {
+        let allowed = self.allowed_peers.read().await;
+        allowed.is_empty() || allowed.contains(peer_id)
+    }
+
+    async fn handle_gossip_command(&mut self, command: GossipCommand) {
+        let mut swarm = self.swarm.lock().await;
+        let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+        match command {
+            GossipCommand::Subscribe(topic) => match gossipsub.subscribe(&topic.ident()) {
+                Ok(_) => {
+                    info!("Subscribed to {}", topic);
+                    self.topics.write().await.insert(topic);
+                }
+                Err(e) => warn!("Failed to subscribe to {}: {}", topic, e),
+            },
+            GossipCommand::Unsubscribe(topic) => {
+                if gossipsub.unsubscribe(&topic.ident()) {
+                    info!("Unsubscribed from {}", topic);
+                }
+                self.topics.write().await.remove(&topic);
+            }
+            GossipCommand::Publish(topic, data) => {
+                let data = match SignedPayload::sign(&self.signer, &topic.to_string(), data).encode() {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!("Failed to encode payload for {}: {}", topic, e);
+                        return;
+                    }
+                };
+                // Fails without peers on the topic, which is routine for narrow program topics
+                if let Err(e) = gossipsub.publish(topic.ident(), data) {
+                    debug!("Failed to publish to {}: {}", topic, e);
+                }
+            }
+            GossipCommand::LowerScore(operator, penalty) => {
+                let peer_id = match peer_id_of(&operator) {
+                    Ok(peer_id) => peer_id,
+                    Err(e) => {
+                        warn!("Cannot lower the score of {}: {}", operator, e);
+                        return;
+                    }
+                };
+                let score = self.app_scores.entry(peer_id).or_default();
+                *score -= penalty;
+                gossipsub.set_application_score(&peer_id, *score);
+                info!("Lowered the gossip score of {} ({}) to {}", operator, peer_id, score);
+            }
+        }
+    }
+
     async fn handle_swarm_event(
         &mut self,
         event: SwarmEvent<NodeEvent>
@@ -243,18 +579,65 @@ impl Node {
             SwarmEvent::Behaviour(NodeEvent::Mdns(event)) => {
                 self.handle_mdns_event(event).await?;
             }
+            SwarmEvent::Behaviour(NodeEvent::Shards(event)) => {
+                self.handle_shard_event(event).await;
+            }
+            SwarmEvent::Behaviour(NodeEvent::Identify(event)) => {
+                self.handle_identify_event(event).await;
+            }
+            SwarmEvent::Behaviour(NodeEvent::Ping(ping::Event { peer, result, .. })) => match result {
+                Ok(rtt) => self.registry.write().await.pinged(peer, rtt),
+                Err(e) => debug!("Ping to {} failed: {}", peer, e),
+            },
             SwarmEvent::NewListenAddr { address, .. } => {
                 info!("Listening on {}", address);
             }
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, connection_id, endpoint, .. } => {
+                if self.banned_peers.read().await.contains(&peer_id) {
+                    debug!("Dropping connection from banned peer {}", peer_id);
+                    self.metrics.increment_rejected_connections();
+                    let _ = self.swarm.lock().await.disconnect_peer_id(peer_id);
+                    return Ok(());
+                }
+                if !self.is_allowed(&peer_id).await {
+                    debug!("Dropping connection from {}, which is not on the allowlist", peer_id);
+                    self.metrics.increment_rejected_connections();
+                    let _ = self.swarm.lock().await.disconnect_peer_id(peer_id);
+                    return Ok(());
+                }
+
+                let connection = limits::Connection {
+                    inbound: endpoint.is_listener(),
+                    ip: limits::ip_of(endpoint.get_remote_address()),
+                };
+                if let Some(limit) = self.connections.exceeded(connection.inbound, connection.ip) {
+                    debug!("Closing connection to {}: {} connection limit reached", peer_id, limit);
+                    self.metrics.increment_rejected_connections();
+                    self.swarm.lock().await.close_connection(connection_id);
+                    return Ok(());
+                }
+                self.connections.insert(connection_id, connection);
+                self.registry.write().await.seen(peer_id);
+
                 let mut peers = self.known_peers.write().await;
                 peers.insert(peer_id);
+                self.connected_peers.store(peers.len(), Ordering::Relaxed);
                 debug!("Connected to {}", peer_id);
             }
-            SwarmEvent::ConnectionClosed { peer_id, .. } => {
-                let mut peers = self.known_peers.write().await;
-                peers.remove(&peer_id);
-                debug!("Disconnected from {}", peer_id);
+            SwarmEvent::ConnectionClosed { peer_id, connection_id, num_established, .. } => {
+                self.connections.remove(&connection_id);
+                self.registry.write().await.seen(peer_id);
+                // The peer stays known while it has other connections
+                if num_established == 0 {
+                    let mut peers = self.known_peers.write().await;
+                    peers.remove(&peer_id);
+                    self.connected_peers.store(peers.len(), Ordering::Relaxed);
+                    self.metrics.remove_peer_capabilities(&peer_id);
+                    for peers in self.topic_peers.write().await.values_mut() {
+                        peers.remove(&peer_id);
+                    }
+                    debug!("Disconnected from {}", peer_id);
+                }
             }
             _ => {}
         }
@@ -269,14 +652,46 @@ impl Node {
                 propagation_source,
                 ..
             } => {
-                if self.validate_message(&message).await? {
-                    debug!("Valid message {} from {}", message_id, propagation_source);
-                    // Acquire write lock to update metrics
-                    self.metrics.write().await.increment_valid_messages();
-                } else {
-                    warn!("Invalid message {} from {}", message_id, propagation_source);
-                    // Acquire write lock to update metrics
-                    self.metrics.write().await.increment_invalid_messages();
+                let acceptance = match self.validate_message(&message) {
+                    Ok(signed) => {
+                        debug!("Valid message {} from {}", message_id, propagation_source);
+                        self.metrics.increment_valid_messages(message.topic.as_str());
+                        if message.topic == Topic::Shards.hash() {
+                            self.accept_shard(&signed.payload).await;
+                        }
+                        if let Ok(topic) = message.topic.as_str().parse() {
+                            // Fails only without receivers
+                            let _ = self.received.send(ReceivedMessage {
+                                topic,
+                                signer: signed.signer,
+                                payload: signed.payload,
+                            });
+                        }
+                        MessageAcceptance::Accept
+                    }
+                    Err(rejection) => {
+                        warn!("Invalid message {} from {}: {:?}", message_id, propagation_source, rejection);
+                        self.metrics.increment_invalid_messages(message.topic.as_str());
+                        self.metrics.increment_rejected_signatures();
+                        self.penalize(propagation_source).await;
+                        MessageAcceptance::Reject
+                    }
+                };
+                let _ = self.swarm.lock().await
+                    .behaviour_mut()
+                    .gossipsub
+                    .report_message_validation_result(&message_id, &propagation_source, acceptance);
+            }
+            gossipsub::Event::Subscribed { peer_id, topic } => {
+                if let Ok(topic) = topic.as_str().parse() {
+                    self.topic_peers.write().await.entry(topic).or_default().insert(peer_id);
+                }
+            }
+            gossipsub::Event::Unsubscribed { peer_id, topic } => {
+                if let Ok(topic) = topic.as_str().parse::<Topic>() {
+                    if let Some(peers) = self.topic_peers.write().await.get_mut(&topic) {
+                        peers.remove(&peer_id);
+                    }
                 }
             }
             _ => {}
@@ -284,11 +699,96 @@ impl Node {
         Ok(())
     }
 
+    async fn handle_shard_command(&mut self, command: ShardCommand) {
+        match command {
+            ShardCommand::Fetch { peer, slot, index, reply } => {
+                let request_id = self.swarm.lock().await
+                    .behaviour_mut()
+                    .shards
+                    .send_request(&peer, ShardRequest { slot, index });
+                self.pending_shards.insert(request_id, reply);
+            }
+        }
+    }
+
+    async fn handle_shard_event(&mut self, event: request_response::Event<ShardRequest, ShardResponse>) {
+        match event {
+            request_response::Event::Message { peer, message, .. } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    let shard = match &self.sharding {
+                        Some(service) => service.local_shard(request.slot, request.index).await.unwrap_or_else(|e| {
+                            warn!("Failed to read shard {}/{} for {}: {}", request.slot, request.index, peer, e);
+                            None
+                        }),
+                        None => None,
+                    };
+                    let _ = self.swarm.lock().await
+                        .behaviour_mut()
+                        .shards
+                        .send_response(channel, ShardResponse(shard));
+                }
+                request_response::Message::Response { request_id, response } => {
+                    if let Some(reply) = self.pending_shards.remove(&request_id) {
+                        let _ = reply.send(response.0);
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error, .. } => {
+                debug!("Shard request to {} failed: {}", peer, error);
+                if let Some(reply) = self.pending_shards.remove(&request_id) {
+                    let _ = reply.send(None);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Keeps a shard received on the shards topic if it is placed on this node
+    async fn accept_shard(&self, payload: &[u8]) {
+        let Some(service) = &self.sharding else { return };
+        let shard: Shard = match bincode::deserialize(payload) {
+            Ok(shard) => shard,
+            Err(e) => {
+                warn!("Dropping malformed shard: {}", e);
+                return;
+            }
+        };
+        let members = sharding::members(self.peer_id, &*self.known_peers.read().await);
+        if let Err(e) = service.accept(shard, &members).await {
+            warn!("Failed to store shard: {}", e);
+        }
+    }
+
+    /// Records a peer's capabilities, disconnecting it if it does not speak
+    /// a compatible protocol version
+    async fn handle_identify_event(&mut self, event: identify::Event) {
+        let identify::Event::Received { peer_id, info, .. } = event else {
+            return;
+        };
+        let reason = match Capabilities::from_identify(&info) {
+            Ok(capabilities) if capabilities.is_compatible() => {
+                debug!("Peer {} speaks {:?}", peer_id, capabilities);
+                self.registry.write().await.identified(peer_id, &capabilities);
+                self.metrics.set_peer_capabilities(peer_id, capabilities);
+                return;
+            }
+            Ok(capabilities) => format!("incompatible protocol version {}", capabilities.version),
+            Err(e) => e.to_string(),
+        };
+
+        warn!("Disconnecting {}: {}", peer_id, reason);
+        self.metrics.increment_incompatible_peers();
+        let _ = self.swarm.lock().await.disconnect_peer_id(peer_id);
+    }
+
     async fn handle_mdns_event(&mut self, event: mdns::Event) -> Result<()> {
         match event {
             mdns::Event::Discovered(peers) => {
                 for (peer_id, addr) in peers {
                     debug!("Discovered peer {} at {}", peer_id, addr);
+                    if self.banned_peers.read().await.contains(&peer_id) || !self.is_allowed(&peer_id).await {
+                        continue;
+                    }
                     let known_peers = self.known_peers.read().await;
                     if !known_peers.contains(&peer_id) {
                         drop(known_peers);
@@ -303,14 +803,31 @@ impl Node {
                     debug!("Lost peer {}", peer_id);
                     let mut known_peers = self.known_peers.write().await;
                     known_peers.remove(&peer_id);
+                    self.connected_peers.store(known_peers.len(), Ordering::Relaxed);
                 }
             }
         }
         Ok(())
     }
 
-    async fn validate_message(&self, _message: &gossipsub::Message) -> Result<bool> {
-        Ok(true)
+    /// Checks the payload signature and that it was signed by the message author
+    fn validate_message(&self, message: &gossipsub::Message) -> std::result::Result<SignedPayload, Rejection> {
+        SignedPayload::verify(&message.data, message.topic.as_str(), message.source.as_ref())
+    }
+
+    /// Counts an invalid message against the peer that relayed it, banning
+    /// the peer once it reaches `MAX_INVALID_MESSAGES`
+    async fn penalize(&mut self, peer_id: PeerId) {
+        let count = self.invalid_messages.entry(peer_id).or_default();
+        *count += 1;
+        if *count < MAX_INVALID_MESSAGES {
+            return;
+        }
+
+        self.invalid_messages.remove(&peer_id);
+        warn!("Banning {} after {} invalid messages", peer_id, MAX_INVALID_MESSAGES);
+        self.banned_peers.write().await.insert(peer_id);
+        self.handle_peer_command(PeerCommand::Ban(peer_id)).await;
     }
 
     pub async fn stop(&self) -> Result<()> {
@@ -321,7 +838,17 @@ impl Node {
     // Add a method to initialize Helius data fetcher
     pub async fn init_helius_data_fetcher(&mut self, api_key: &str) -> Result<()> {
         info!("Initializing Helius data fetcher");
-        let data_fetcher = Arc::new(HeliusDataFetcher::new(api_key));
+        self.start_data_fetcher(HeliusDataFetcher::new(api_key)).await
+    }
+    
+    /// Initialize the data fetcher against any upstream RPC provider
+    pub async fn init_data_fetcher(&mut self, provider: Arc<dyn RpcProvider>) -> Result<()> {
+        info!("Initializing data fetcher using {}", provider.name());
+        self.start_data_fetcher(HeliusDataFetcher::new_with_provider(provider)).await
+    }
+    
+    async fn start_data_fetcher(&mut self, data_fetcher: HeliusDataFetcher) -> Result<()> {
+        let data_fetcher = Arc::new(data_fetcher);
         
         // Initialize the data fetcher
         data_fetcher.initialize().await?;
@@ -336,4 +863,58 @@ impl Node {
     pub fn helius_data_fetcher(&self) -> Option<Arc<HeliusDataFetcher>> {
         self.helius_data_fetcher.clone()
     }
+
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// The node's metrics, to register with the process's `MetricsRegistry`
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Joins `topic` while the node runs
+    pub fn subscribe_topic(&self, topic: Topic) -> Result<()> {
+        self.gossip().subscribe(topic)
+    }
+
+    /// Leaves `topic` while the node runs
+    pub fn unsubscribe_topic(&self, topic: Topic) -> Result<()> {
+        self.gossip().unsubscribe(topic)
+    }
+
+    /// Handle for publishing and changing topic subscriptions while the node runs
+    pub fn gossip(&self) -> GossipHandle {
+        GossipHandle {
+            topics: self.topics.clone(),
+            topic_peers: self.topic_peers.clone(),
+            connected_peers: self.connected_peers.clone(),
+            commands: self.gossip_tx.clone(),
+            received: self.received.clone(),
+        }
+    }
+
+    /// Keep the shards of slot bundles placed on this node in `store`; call
+    /// before [`Node::start`] so the node joins the shards topic
+    pub fn enable_sharding(&mut self, config: ShardingConfig, store: Arc<dyn ShardStore>) -> ShardHandle {
+        let service = Arc::new(ShardingService::new(config, self.peer_id, store));
+        self.sharding = Some(service.clone());
+        ShardHandle {
+            service,
+            gossip: self.gossip(),
+            known_peers: self.known_peers.clone(),
+            commands: self.shard_tx.clone(),
+        }
+    }
+
+    /// Handle for listing, banning and allowlisting peers while the node runs
+    pub fn peer_control(&self) -> NodeControl {
+        NodeControl {
+            known_peers: self.known_peers.clone(),
+            banned_peers: self.banned_peers.clone(),
+            allowed_peers: self.allowed_peers.clone(),
+            registry: self.registry.clone(),
+            commands: self.command_tx.clone(),
+        }
+    }
 }
@@ -0,0 +1,189 @@
+//! Persistent registry of the peers a node has seen
+//!
+//! Each peer's last sighting, advertised topics and protocol version, and
+//! latest ping round trip are recorded by the event loop. The stake of a
+//! peer's operator is set by the embedder, which knows the staking state,
+//! and is keyed by the operator's Solana identity, from which the peer id
+//! is derived. The registry is written to `peers.json` in the node's data
+//! directory on every heartbeat and on shutdown, and read back on start.
+
+use {
+    crate::protocol::Capabilities,
+    anyhow::{Context, Result},
+    libp2p::{identity, PeerId},
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{HashMap, HashSet},
+        fs,
+        io::ErrorKind,
+        path::PathBuf,
+        str::FromStr,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    tracing::warn,
+    windexer_common::control::PeerRecord,
+};
+
+const FILE_NAME: &str = "peers.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Entry {
+    last_seen: i64,
+    topics: Vec<String>,
+    protocol_version: Option<String>,
+    latency_ms: Option<u64>,
+}
+
+/// On-disk layout, keyed by base58 peer ids and operator pubkeys
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RegistryFile {
+    peers: HashMap<String, Entry>,
+    stakes: HashMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+pub struct PeerRegistry {
+    path: Option<PathBuf>,
+    peers: HashMap<PeerId, Entry>,
+    stakes: HashMap<Pubkey, u64>,
+    /// Changed since the last save
+    dirty: bool,
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() as i64)
+}
+
+/// Solana identity a peer id was derived from, for ed25519 peers
+fn operator_of(peer_id: &PeerId) -> Option<Pubkey> {
+    let multihash = libp2p::multihash::Multihash::<64>::from(*peer_id);
+    // Ed25519 keys are short enough to be inlined with the identity hash
+    if multihash.code() != 0 {
+        return None;
+    }
+    let key = identity::PublicKey::try_decode_protobuf(multihash.digest()).ok()?;
+    Some(Pubkey::new_from_array(key.try_into_ed25519().ok()?.to_bytes()))
+}
+
+impl PeerRegistry {
+    /// Loads the registry kept in `data_dir`; with an empty `data_dir` it
+    /// lives in memory only
+    pub fn open(data_dir: &str) -> Result<Self> {
+        if data_dir.is_empty() {
+            return Ok(Self::default());
+        }
+        let path = PathBuf::from(data_dir).join(FILE_NAME);
+        let file: RegistryFile = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("Invalid peer registry {}", path.display()))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => RegistryFile::default(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read peer registry {}", path.display())),
+        };
+
+        let peers = file.peers.into_iter()
+            .filter_map(|(peer_id, entry)| match PeerId::from_str(&peer_id) {
+                Ok(peer_id) => Some((peer_id, entry)),
+                Err(e) => {
+                    warn!("Skipping invalid peer id {} in registry: {}", peer_id, e);
+                    None
+                }
+            })
+            .collect();
+        let stakes = file.stakes.into_iter()
+            .filter_map(|(operator, stake)| Some((Pubkey::from_str(&operator).ok()?, stake)))
+            .collect();
+        Ok(Self { path: Some(path), peers, stakes, dirty: false })
+    }
+
+    fn entry(&mut self, peer_id: PeerId) -> &mut Entry {
+        self.dirty = true;
+        self.peers.entry(peer_id).or_default()
+    }
+
+    pub fn seen(&mut self, peer_id: PeerId) {
+        self.entry(peer_id).last_seen = now();
+    }
+
+    pub fn identified(&mut self, peer_id: PeerId, capabilities: &Capabilities) {
+        let entry = self.entry(peer_id);
+        entry.last_seen = now();
+        entry.topics = capabilities.topics.clone();
+        entry.protocol_version = Some(capabilities.version.clone());
+    }
+
+    pub fn pinged(&mut self, peer_id: PeerId, rtt: Duration) {
+        let entry = self.entry(peer_id);
+        entry.last_seen = now();
+        entry.latency_ms = Some(rtt.as_millis() as u64);
+    }
+
+    pub fn set_stake(&mut self, operator: Pubkey, stake: u64) {
+        self.dirty = true;
+        self.stakes.insert(operator, stake);
+    }
+
+    /// Every known peer, most recently seen first
+    pub fn records(&self, connected: &HashSet<PeerId>) -> Vec<PeerRecord> {
+        let mut records: Vec<PeerRecord> = self.peers.iter()
+            .map(|(peer_id, entry)| PeerRecord {
+                peer_id: peer_id.to_string(),
+                connected: connected.contains(peer_id),
+                last_seen: entry.last_seen,
+                topics: entry.topics.clone(),
+                protocol_version: entry.protocol_version.clone(),
+                latency_ms: entry.latency_ms,
+                stake: operator_of(peer_id).and_then(|operator| self.stakes.get(&operator).copied()),
+            })
+            .collect();
+        records.sort_by(|a, b| b.last_seen.cmp(&a.last_seen).then_with(|| a.peer_id.cmp(&b.peer_id)));
+        records
+    }
+
+    /// Writes the registry if it changed since the last save
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        if !self.dirty {
+            return Ok(());
+        }
+        let file = RegistryFile {
+            peers: self.peers.iter().map(|(peer_id, entry)| (peer_id.to_string(), entry.clone())).collect(),
+            stakes: self.stakes.iter().map(|(operator, stake)| (operator.to_string(), *stake)).collect(),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Via a temporary file so a crash never leaves a torn registry
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&file)?)?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to write peer registry {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::signature::{Keypair, Signer}};
+
+    #[test]
+    fn survives_reopen_with_operator_stake() {
+        let dir = std::env::temp_dir().join(format!("windexer-registry-{}", std::process::id()));
+        let data_dir = dir.to_string_lossy().to_string();
+        let operator = Keypair::new();
+        let peer_id = PeerId::from(crate::node::convert_keypair(&operator).public());
+
+        let mut registry = PeerRegistry::open(&data_dir).unwrap();
+        registry.pinged(peer_id, Duration::from_millis(42));
+        registry.set_stake(operator.pubkey(), 1_000);
+        registry.save().unwrap();
+
+        let registry = PeerRegistry::open(&data_dir).unwrap();
+        let records = registry.records(&HashSet::new());
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].peer_id, peer_id.to_string());
+        assert_eq!((records[0].latency_ms, records[0].stake), (Some(42), Some(1_000)));
+        assert!(!records[0].connected);
+    }
+}
@@ -0,0 +1,119 @@
+//! Storing and reading erasure-coded slot bundles through a running node
+//!
+//! Shards are published on the `shards` topic and each node keeps the ones
+//! placed on it. Reads use local shards first and fetch the rest from their
+//! owners with the `/windexer/shards/1` request-response protocol, which the
+//! event loop drives on behalf of [`ShardHandle`].
+
+use {
+    super::GossipHandle,
+    crate::{
+        gossip::Topic,
+        sharding::{codec, Shard, ShardingService},
+    },
+    anyhow::{anyhow, Result},
+    libp2p::PeerId,
+    std::{
+        collections::HashSet,
+        sync::Arc,
+    },
+    tokio::sync::{mpsc, oneshot, RwLock},
+};
+
+#[derive(Debug)]
+pub enum ShardCommand {
+    /// Ask `peer` for a shard; the reply is `None` if it does not have it or the request failed
+    Fetch {
+        peer: PeerId,
+        slot: u64,
+        index: u16,
+        reply: oneshot::Sender<Option<Shard>>,
+    },
+}
+
+/// Cloneable handle for storing and reading slot bundles
+#[derive(Clone)]
+pub struct ShardHandle {
+    pub(super) service: Arc<ShardingService>,
+    pub(super) gossip: GossipHandle,
+    pub(super) known_peers: Arc<RwLock<HashSet<PeerId>>>,
+    pub(super) commands: mpsc::UnboundedSender<ShardCommand>,
+}
+
+/// The local node and its peers, sorted, as used for placement
+pub(super) fn members(local: PeerId, peers: &HashSet<PeerId>) -> Vec<PeerId> {
+    let mut members: Vec<PeerId> = peers.iter().copied().chain(std::iter::once(local)).collect();
+    members.sort();
+    members.dedup();
+    members
+}
+
+impl ShardHandle {
+    async fn members(&self) -> Vec<PeerId> {
+        members(self.service.local_peer_id(), &*self.known_peers.read().await)
+    }
+
+    /// Erasure codes the bundle of a rooted slot and publishes its shards,
+    /// keeping those placed on this node; returns the number of shards
+    pub async fn store_bundle(&self, slot: u64, data: &[u8]) -> Result<usize> {
+        let shards = self.service.encode(slot, data)?;
+        let members = self.members().await;
+        for shard in &shards {
+            self.service.accept(shard.clone(), &members).await?;
+            self.gossip.publish(Topic::Shards, bincode::serialize(shard)?)?;
+        }
+        Ok(shards.len())
+    }
+
+    /// Rebuilds the bundle of `slot` from local shards and, for the rest,
+    /// their owners; `None` if no shard of the slot can be found
+    pub async fn read_bundle(&self, slot: u64) -> Result<Option<Vec<u8>>> {
+        let config = self.service.config();
+        let mut shards = self.service.local_shards(slot).await?;
+        let mut found: HashSet<u16> = shards.iter().map(|shard| shard.index).collect();
+        let members = self.members().await;
+        let local = self.service.local_peer_id();
+
+        for index in 0..config.total_shards() as u16 {
+            if shards.len() >= config.data_shards {
+                break;
+            }
+            if found.contains(&index) {
+                continue;
+            }
+            for owner in self.service.owners(slot, index, &members) {
+                if owner == local {
+                    continue;
+                }
+                if let Some(shard) = self.fetch(owner, slot, index).await? {
+                    if shard.slot == slot && shard.index == index {
+                        found.insert(index);
+                        shards.push(shard);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if shards.is_empty() {
+            return Ok(None);
+        }
+        if shards.len() < config.data_shards {
+            return Err(anyhow!(
+                "Only {} of the {} shards needed for slot {} are reachable",
+                shards.len(),
+                config.data_shards,
+                slot
+            ));
+        }
+        codec::decode(shards).map(Some)
+    }
+
+    async fn fetch(&self, peer: PeerId, slot: u64, index: u16) -> Result<Option<Shard>> {
+        let (reply, response) = oneshot::channel();
+        self.commands
+            .send(ShardCommand::Fetch { peer, slot, index, reply })
+            .map_err(|_| anyhow!("Node is not running"))?;
+        Ok(response.await.unwrap_or(None))
+    }
+}
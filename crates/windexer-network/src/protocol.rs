@@ -0,0 +1,137 @@
+// crates/windexer-network/src/protocol.rs
+
+//! Protocol versioning and capability negotiation
+//!
+//! Nodes exchange versions and capabilities over libp2p identify. The
+//! identify protocol version is `/windexer/{PROTOCOL_VERSION}`, the agent
+//! version lists the topics a node subscribed to on start and the
+//! compression codecs it reads, and the sync protocols it serves come from
+//! the protocols identify advertises:
+//!
+//! ```text
+//! windexer-network/0.1.0 topics=accounts,transactions,blocks,entries compression=none
+//! ```
+//!
+//! Peers with another major version, or older than
+//! [`MINIMUM_PROTOCOL_VERSION`], are disconnected.
+
+use {
+    crate::{gossip::Topic, MINIMUM_PROTOCOL_VERSION, PROTOCOL_VERSION},
+    anyhow::{anyhow, Result},
+    libp2p::identify,
+    serde::{Deserialize, Serialize},
+};
+
+const PROTOCOL_PREFIX: &str = "/windexer/";
+const AGENT_PREFIX: &str = "windexer-network/";
+
+/// Codecs this node can read gossip payloads in
+pub const COMPRESSION_CODECS: &[&str] = &["none"];
+
+/// What a node speaks, as advertised over identify
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub version: String,
+    pub topics: Vec<String>,
+    /// Request-response protocols for syncing data, e.g. `/windexer/shards/1`
+    pub sync: Vec<String>,
+    pub compression: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn local(topics: &[Topic], sync: &[&str]) -> Self {
+        Self {
+            version: PROTOCOL_VERSION.to_string(),
+            topics: topics.iter().map(Topic::to_string).collect(),
+            sync: sync.iter().map(|protocol| protocol.to_string()).collect(),
+            compression: COMPRESSION_CODECS.iter().map(|codec| codec.to_string()).collect(),
+        }
+    }
+
+    pub fn protocol_version(&self) -> String {
+        format!("{}{}", PROTOCOL_PREFIX, self.version)
+    }
+
+    pub fn agent_version(&self) -> String {
+        format!(
+            "{}{} topics={} compression={}",
+            AGENT_PREFIX,
+            self.version,
+            self.topics.join(","),
+            self.compression.join(","),
+        )
+    }
+
+    /// Reads a peer's capabilities from its identify info; fails for peers
+    /// that are not windexer nodes
+    pub fn from_identify(info: &identify::Info) -> Result<Self> {
+        let version = info.protocol_version.strip_prefix(PROTOCOL_PREFIX)
+            .ok_or_else(|| anyhow!("Unknown protocol {}", info.protocol_version))?;
+
+        let mut fields = info.agent_version.split_whitespace();
+        if !fields.next().is_some_and(|agent| agent.starts_with(AGENT_PREFIX)) {
+            return Err(anyhow!("Unknown agent {}", info.agent_version));
+        }
+        let list = |value: &str| -> Vec<String> {
+            value.split(',').filter(|item| !item.is_empty()).map(str::to_string).collect()
+        };
+
+        let mut capabilities = Self {
+            version: version.to_string(),
+            sync: info.protocols.iter()
+                .map(|protocol| protocol.to_string())
+                .filter(|protocol| protocol.starts_with(PROTOCOL_PREFIX))
+                .collect(),
+            ..Default::default()
+        };
+        // Unknown fields are left for newer versions
+        for field in fields {
+            match field.split_once('=') {
+                Some(("topics", topics)) => capabilities.topics = list(topics),
+                Some(("compression", codecs)) => capabilities.compression = list(codecs),
+                _ => {}
+            }
+        }
+        Ok(capabilities)
+    }
+
+    /// Whether this node can talk to a peer with these capabilities
+    pub fn is_compatible(&self) -> bool {
+        match (parse_version(&self.version), parse_version(PROTOCOL_VERSION), parse_version(MINIMUM_PROTOCOL_VERSION)) {
+            (Some(peer), Some(local), Some(minimum)) => peer.0 == local.0 && peer >= minimum,
+            _ => false,
+        }
+    }
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.split('-').next()?.split('.').map(|part| part.parse().ok());
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, libp2p::identity::Keypair};
+
+    #[test]
+    fn capabilities_round_trip_through_identify() {
+        let local = Capabilities::local(&[Topic::Accounts, Topic::Blocks], &[]);
+        let info = identify::Info {
+            public_key: Keypair::generate_ed25519().public(),
+            protocol_version: local.protocol_version(),
+            agent_version: local.agent_version(),
+            listen_addrs: Vec::new(),
+            protocols: vec![libp2p::StreamProtocol::new("/windexer/shards/1")],
+            observed_addr: "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            signed_peer_record: None,
+        };
+
+        let peer = Capabilities::from_identify(&info).unwrap();
+        assert_eq!(peer.topics, vec!["accounts", "blocks"]);
+        assert_eq!(peer.sync, vec!["/windexer/shards/1"]);
+        assert!(peer.is_compatible());
+
+        let old = Capabilities { version: "0.0.9".to_string(), ..peer };
+        assert!(!old.is_compatible());
+    }
+}
@@ -0,0 +1,124 @@
+// crates/windexer-network/src/sharding/codec.rs
+
+//! Reed-Solomon encoding of slot bundles
+
+use {
+    super::{Shard, ShardingConfig},
+    anyhow::{anyhow, Result},
+    reed_solomon_erasure::galois_8::ReedSolomon,
+    solana_sdk::hash::{hash, Hash},
+    std::collections::HashMap,
+};
+
+fn codec(data_shards: usize, parity_shards: usize) -> Result<ReedSolomon> {
+    ReedSolomon::new(data_shards, parity_shards)
+        .map_err(|e| anyhow!("Invalid shard layout {}+{}: {:?}", data_shards, parity_shards, e))
+}
+
+/// Splits `data` into `data_shards` equal pieces, zero padded, and adds
+/// `parity_shards` parity pieces
+pub fn encode(config: &ShardingConfig, slot: u64, data: &[u8]) -> Result<Vec<Shard>> {
+    let rs = codec(config.data_shards, config.parity_shards)?;
+    let shard_len = data.len().div_ceil(config.data_shards).max(1);
+
+    let mut pieces: Vec<Vec<u8>> = (0..config.total_shards())
+        .map(|index| {
+            let start = (index * shard_len).min(data.len());
+            let end = (start + shard_len).min(data.len());
+            let mut piece = if index < config.data_shards { data[start..end].to_vec() } else { Vec::new() };
+            piece.resize(shard_len, 0);
+            piece
+        })
+        .collect();
+    rs.encode(&mut pieces).map_err(|e| anyhow!("Failed to encode slot {}: {:?}", slot, e))?;
+
+    let checksum = hash(data);
+    Ok(pieces.into_iter()
+        .enumerate()
+        .map(|(index, bytes)| Shard {
+            slot,
+            index: index as u16,
+            data_shards: config.data_shards as u16,
+            parity_shards: config.parity_shards as u16,
+            data_len: data.len() as u64,
+            checksum,
+            bytes,
+        })
+        .collect())
+}
+
+/// Rebuilds a bundle from any `data_shards` of its shards
+///
+/// Shards of different encodings of the slot, told apart by their checksum,
+/// can't be mixed; each encoding is tried in turn, the best supplied first.
+pub fn decode(shards: Vec<Shard>) -> Result<Vec<u8>> {
+    let slot = shards.first().ok_or_else(|| anyhow!("No shards to decode"))?.slot;
+
+    let mut encodings: HashMap<(Hash, u64, u16, u16), Vec<Shard>> = HashMap::new();
+    for shard in shards {
+        let layout = (shard.checksum, shard.data_len, shard.data_shards, shard.parity_shards);
+        encodings.entry(layout).or_default().push(shard);
+    }
+    let mut encodings: Vec<Vec<Shard>> = encodings.into_values().collect();
+    encodings.sort_by_key(|shards| std::cmp::Reverse(shards.len()));
+
+    let mut failure = None;
+    for shards in encodings {
+        match decode_encoding(shards) {
+            Ok(data) => return Ok(data),
+            Err(e) => failure = Some(e),
+        }
+    }
+    Err(failure.unwrap_or_else(|| anyhow!("No shards to decode slot {}", slot)))
+}
+
+/// Rebuilds a bundle from shards of a single encoding
+fn decode_encoding(shards: Vec<Shard>) -> Result<Vec<u8>> {
+    let first = shards.first().ok_or_else(|| anyhow!("No shards to decode"))?;
+    let (slot, data_shards, parity_shards) = (first.slot, first.data_shards as usize, first.parity_shards as usize);
+    let (data_len, checksum) = (first.data_len as usize, first.checksum);
+    let rs = codec(data_shards, parity_shards)?;
+
+    let mut pieces: Vec<Option<Vec<u8>>> = vec![None; data_shards + parity_shards];
+    for shard in shards {
+        if let Some(piece) = pieces.get_mut(shard.index as usize) {
+            *piece = Some(shard.bytes);
+        }
+    }
+    rs.reconstruct_data(&mut pieces)
+        .map_err(|e| anyhow!("Failed to reconstruct slot {}: {:?}", slot, e))?;
+
+    let mut data: Vec<u8> = pieces.into_iter().take(data_shards).flatten().flatten().collect();
+    data.truncate(data_len);
+    if hash(&data) != checksum {
+        return Err(anyhow!("Reconstructed slot {} does not match its checksum", slot));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconstructs_from_any_data_shards() {
+        let config = ShardingConfig { data_shards: 4, parity_shards: 2, replicas: 1 };
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7) as u8).collect();
+        let mut shards = encode(&config, 42, &data).unwrap();
+        assert_eq!(shards.len(), 6);
+
+        // Lose two data shards; the parity shards stand in for them
+        shards.remove(0);
+        shards.remove(1);
+        assert_eq!(decode(shards.clone()).unwrap(), data);
+
+        shards.remove(0);
+        assert!(decode(shards.clone()).is_err());
+
+        // A stray shard of another encoding of the slot doesn't spoil the rest
+        let other = encode(&config, 42, b"another bundle").unwrap();
+        let mut mixed = other[..1].to_vec();
+        mixed.extend(encode(&config, 42, &data).unwrap());
+        assert_eq!(decode(mixed).unwrap(), data);
+    }
+}
@@ -0,0 +1,123 @@
+// crates/windexer-network/src/sharding/mod.rs
+
+//! Erasure-coded storage of slot history across the network
+//!
+//! A rooted slot's bundle is Reed-Solomon encoded into `data_shards` data
+//! and `parity_shards` parity shards, and each shard is kept by the
+//! `replicas` peers [`placement`] assigns it to. No node needs the whole
+//! history: a bundle can be rebuilt from any `data_shards` of its shards,
+//! fetched from their owners on read.
+//!
+//! Placement depends on the membership each node sees, so nodes that
+//! disagree about who is connected may look for a shard in the wrong place;
+//! reads then try the owners of further shards until enough are found.
+
+pub mod codec;
+pub mod placement;
+mod store;
+
+pub use store::{FileShardStore, MemoryShardStore, ShardStore, DEFAULT_RETAINED_SLOTS};
+
+use {
+    anyhow::Result,
+    libp2p::PeerId,
+    serde::{Deserialize, Serialize},
+    solana_sdk::hash::Hash,
+    std::sync::Arc,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShardingConfig {
+    pub data_shards: usize,
+    pub parity_shards: usize,
+    /// Peers keeping each shard
+    pub replicas: usize,
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self {
+            data_shards: 10,
+            parity_shards: 4,
+            replicas: 2,
+        }
+    }
+}
+
+impl ShardingConfig {
+    pub fn total_shards(&self) -> usize {
+        self.data_shards + self.parity_shards
+    }
+}
+
+/// One piece of an encoded slot bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shard {
+    pub slot: u64,
+    pub index: u16,
+    pub data_shards: u16,
+    pub parity_shards: u16,
+    /// Length of the bundle before padding
+    pub data_len: u64,
+    /// Hash of the whole bundle, checked after reconstruction
+    pub checksum: Hash,
+    pub bytes: Vec<u8>,
+}
+
+/// Asks a peer for one shard it keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardRequest {
+    pub slot: u64,
+    pub index: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardResponse(pub Option<Shard>);
+
+/// Encodes bundles and keeps the shards placed on the local node
+pub struct ShardingService {
+    config: ShardingConfig,
+    local: PeerId,
+    store: Arc<dyn ShardStore>,
+}
+
+impl ShardingService {
+    pub fn new(config: ShardingConfig, local: PeerId, store: Arc<dyn ShardStore>) -> Self {
+        Self { config, local, store }
+    }
+
+    pub fn config(&self) -> &ShardingConfig {
+        &self.config
+    }
+
+    pub fn local_peer_id(&self) -> PeerId {
+        self.local
+    }
+
+    pub fn encode(&self, slot: u64, data: &[u8]) -> Result<Vec<Shard>> {
+        codec::encode(&self.config, slot, data)
+    }
+
+    /// Members that keep shard `index` of `slot`
+    pub fn owners(&self, slot: u64, index: u16, members: &[PeerId]) -> Vec<PeerId> {
+        placement::owners(slot, index, members, self.config.replicas.max(1))
+    }
+
+    /// Stores `shard` if it is placed on the local node, returning whether it was
+    pub async fn accept(&self, shard: Shard, members: &[PeerId]) -> Result<bool> {
+        if !self.owners(shard.slot, shard.index, members).contains(&self.local) {
+            return Ok(false);
+        }
+        self.store.put(shard).await?;
+        Ok(true)
+    }
+
+    pub async fn local_shard(&self, slot: u64, index: u16) -> Result<Option<Shard>> {
+        self.store.get(slot, index).await
+    }
+
+    pub async fn local_shards(&self, slot: u64) -> Result<Vec<Shard>> {
+        self.store.slot_shards(slot).await
+    }
+}
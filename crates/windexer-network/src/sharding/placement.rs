@@ -0,0 +1,23 @@
+// crates/windexer-network/src/sharding/placement.rs
+
+//! Deterministic shard placement
+//!
+//! Shards are placed by rendezvous hashing: every member scores
+//! `sha256(slot, index, peer_id)` and the highest scores keep the shard. Any
+//! node with the same membership computes the same owners without
+//! coordination, and a peer joining or leaving only moves the shards it
+//! would own or owned.
+
+use {
+    libp2p::PeerId,
+    solana_sdk::hash::hashv,
+};
+
+/// The `replicas` members that keep shard `index` of `slot`
+pub fn owners(slot: u64, index: u16, members: &[PeerId], replicas: usize) -> Vec<PeerId> {
+    let mut scored: Vec<_> = members.iter()
+        .map(|peer| (hashv(&[&slot.to_le_bytes(), &index.to_le_bytes(), &peer.to_bytes()]), *peer))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0));
+    scored.into_iter().take(replicas).map(|(_, peer)| peer).collect()
+}
@@ -0,0 +1,215 @@
+// crates/windexer-network/src/sharding/store.rs
+
+//! Local storage for the shards placed on this node
+//!
+//! Both stores keep the shards of the newest `retained_slots` slots and drop
+//! older slots as new ones arrive.
+
+use {
+    super::Shard,
+    anyhow::{Context, Result},
+    async_trait::async_trait,
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        io::ErrorKind,
+        path::PathBuf,
+    },
+    tokio::{fs, sync::{Mutex, RwLock}},
+    tracing::warn,
+};
+
+/// Slots kept unless another retention is given, about a day of slots
+pub const DEFAULT_RETAINED_SLOTS: usize = 216_000;
+
+#[async_trait]
+pub trait ShardStore: Send + Sync {
+    async fn put(&self, shard: Shard) -> Result<()>;
+
+    async fn get(&self, slot: u64, index: u16) -> Result<Option<Shard>>;
+
+    /// Shards of `slot` held locally
+    async fn slot_shards(&self, slot: u64) -> Result<Vec<Shard>>;
+}
+
+/// Keeps shards in memory; they are lost on restart
+pub struct MemoryShardStore {
+    retained_slots: usize,
+    /// Shards by slot and index
+    shards: RwLock<BTreeMap<u64, BTreeMap<u16, Shard>>>,
+}
+
+impl Default for MemoryShardStore {
+    fn default() -> Self {
+        Self::with_retention(DEFAULT_RETAINED_SLOTS)
+    }
+}
+
+impl MemoryShardStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep the shards of the newest `retained_slots` slots
+    pub fn with_retention(retained_slots: usize) -> Self {
+        Self {
+            retained_slots: retained_slots.max(1),
+            shards: RwLock::new(BTreeMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ShardStore for MemoryShardStore {
+    async fn put(&self, shard: Shard) -> Result<()> {
+        let mut shards = self.shards.write().await;
+        shards.entry(shard.slot).or_default().insert(shard.index, shard);
+        while shards.len() > self.retained_slots {
+            shards.pop_first();
+        }
+        Ok(())
+    }
+
+    async fn get(&self, slot: u64, index: u16) -> Result<Option<Shard>> {
+        Ok(self.shards.read().await.get(&slot).and_then(|shards| shards.get(&index)).cloned())
+    }
+
+    async fn slot_shards(&self, slot: u64) -> Result<Vec<Shard>> {
+        Ok(self.shards.read().await
+            .get(&slot)
+            .map(|shards| shards.values().cloned().collect())
+            .unwrap_or_default())
+    }
+}
+
+/// Keeps shards on disk, one file per shard in a directory per slot, so they
+/// survive restarts
+pub struct FileShardStore {
+    dir: PathBuf,
+    retained_slots: usize,
+    /// Slots with a directory under `dir`
+    slots: Mutex<BTreeSet<u64>>,
+}
+
+impl FileShardStore {
+    /// Open or create the store in `dir`, keeping the shards of the newest
+    /// `retained_slots` slots
+    pub async fn open(dir: impl Into<PathBuf>, retained_slots: usize) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).await
+            .with_context(|| format!("Failed to create shard directory {}", dir.display()))?;
+
+        let mut slots = BTreeSet::new();
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(slot) = entry.file_name().to_str().and_then(|name| name.parse().ok()) {
+                slots.insert(slot);
+            }
+        }
+
+        Ok(Self {
+            dir,
+            retained_slots: retained_slots.max(1),
+            slots: Mutex::new(slots),
+        })
+    }
+
+    fn slot_dir(&self, slot: u64) -> PathBuf {
+        self.dir.join(slot.to_string())
+    }
+
+    fn shard_path(&self, slot: u64, index: u16) -> PathBuf {
+        self.slot_dir(slot).join(format!("{}.shard", index))
+    }
+
+    async fn read(path: PathBuf) -> Result<Option<Shard>> {
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bincode::deserialize(&bytes)
+                .with_context(|| format!("Corrupt shard file {}", path.display()))?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl ShardStore for FileShardStore {
+    async fn put(&self, shard: Shard) -> Result<()> {
+        let bytes = bincode::serialize(&shard)?;
+        let path = self.shard_path(shard.slot, shard.index);
+
+        // Held until the slot is recorded so pruning never races the write
+        let mut slots = self.slots.lock().await;
+        fs::create_dir_all(self.slot_dir(shard.slot)).await?;
+        // Written aside and renamed so a crash never leaves half a shard
+        let partial = path.with_extension("partial");
+        fs::write(&partial, &bytes).await?;
+        fs::rename(&partial, &path).await?;
+        slots.insert(shard.slot);
+
+        while slots.len() > self.retained_slots {
+            let Some(oldest) = slots.pop_first() else {
+                break;
+            };
+            if let Err(e) = fs::remove_dir_all(self.slot_dir(oldest)).await {
+                warn!("Failed to drop the shards of slot {}: {}", oldest, e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(&self, slot: u64, index: u16) -> Result<Option<Shard>> {
+        Self::read(self.shard_path(slot, index)).await
+    }
+
+    async fn slot_shards(&self, slot: u64) -> Result<Vec<Shard>> {
+        let mut entries = match fs::read_dir(self.slot_dir(slot)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut shards = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().extension().is_some_and(|extension| extension == "shard") {
+                shards.extend(Self::read(entry.path()).await?);
+            }
+        }
+        shards.sort_by_key(|shard| shard.index);
+        Ok(shards)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::hash::Hash};
+
+    fn shard(slot: u64, index: u16) -> Shard {
+        Shard {
+            slot,
+            index,
+            data_shards: 1,
+            parity_shards: 0,
+            data_len: 1,
+            checksum: Hash::default(),
+            bytes: vec![index as u8],
+        }
+    }
+
+    #[tokio::test]
+    async fn file_store_survives_reopening_and_drops_old_slots() {
+        let dir = std::env::temp_dir().join(format!("windexer-shards-{}", std::process::id()));
+        let store = FileShardStore::open(&dir, 2).await.unwrap();
+        for slot in [1, 2] {
+            store.put(shard(slot, 0)).await.unwrap();
+            store.put(shard(slot, 1)).await.unwrap();
+        }
+
+        let reopened = FileShardStore::open(&dir, 2).await.unwrap();
+        assert_eq!(reopened.slot_shards(2).await.unwrap(), vec![shard(2, 0), shard(2, 1)]);
+        reopened.put(shard(3, 0)).await.unwrap();
+        assert!(reopened.get(1, 0).await.unwrap().is_none());
+        assert_eq!(reopened.get(3, 0).await.unwrap(), Some(shard(3, 0)));
+
+        fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+// crates/windexer-network/src/testing.rs
+
+//! In-memory networks for tests
+//!
+//! [`TestNetwork`] runs nodes over libp2p's memory transport, without sockets
+//! or mDNS, so multi-node behaviour such as gossip, consensus and sync can be
+//! tested deterministically. Each node listens on its own `/memory/{port}`
+//! address and dials the nodes started before it, giving a full mesh. A
+//! memory listener exists as soon as it is opened, so those dials cannot
+//! race the earlier nodes' startup.
+//!
+//! Built for this crate's tests and, with the `testing` feature, for other
+//! crates'.
+
+use {
+    crate::{
+        gossip::Topic,
+        node::{GossipHandle, NodeControl, ReceivedMessage},
+        Node,
+    },
+    anyhow::{anyhow, Context, Result},
+    libp2p::{
+        core::{muxing::StreamMuxerBox, transport::{Boxed, MemoryTransport}, upgrade},
+        identity,
+        noise,
+        yamux,
+        Multiaddr,
+        PeerId,
+        Transport,
+    },
+    std::{
+        sync::atomic::{AtomicU64, Ordering},
+        time::Duration,
+    },
+    tokio::{
+        sync::{broadcast, mpsc, Mutex},
+        task::JoinHandle,
+        time::{self, Instant},
+    },
+    windexer_common::config::NodeConfig,
+};
+
+/// Memory ports are process-wide, so concurrent tests draw from one counter
+static NEXT_PORT: AtomicU64 = AtomicU64::new(1);
+
+fn memory_transport(keypair: &identity::Keypair) -> Result<Boxed<(PeerId, StreamMuxerBox)>> {
+    Ok(MemoryTransport::new()
+        .upgrade(upgrade::Version::V1)
+        .authenticate(noise::Config::new(keypair).context("Invalid noise config")?)
+        .multiplex(yamux::Config::default())
+        .boxed())
+}
+
+/// A running node of a [`TestNetwork`]
+pub struct TestNode {
+    pub peer_id: PeerId,
+    pub address: Multiaddr,
+    pub gossip: GossipHandle,
+    pub control: NodeControl,
+    messages: Mutex<broadcast::Receiver<ReceivedMessage>>,
+    shutdown: mpsc::Sender<()>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl TestNode {
+    pub fn publish(&self, topic: Topic, data: Vec<u8>) -> Result<()> {
+        self.gossip.publish(topic, data)
+    }
+
+    /// Waits up to `timeout` for the next message received on `topic`,
+    /// skipping messages on other topics
+    pub async fn next_message(&self, topic: Topic, timeout: Duration) -> Result<ReceivedMessage> {
+        let mut messages = self.messages.lock().await;
+        time::timeout(timeout, async {
+            loop {
+                match messages.recv().await {
+                    Ok(message) if message.topic == topic => return Ok(message),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return Err(anyhow!("Node stopped")),
+                }
+            }
+        })
+        .await
+        .map_err(|_| anyhow!("No message on {} within {:?}", topic, timeout))?
+    }
+}
+
+/// Nodes connected to each other over the memory transport
+pub struct TestNetwork {
+    nodes: Vec<TestNode>,
+}
+
+impl TestNetwork {
+    /// Starts `count` nodes with default configs
+    pub async fn start(count: usize) -> Result<Self> {
+        Self::start_with(count, |_, _| {}).await
+    }
+
+    /// Starts `count` nodes, letting `configure` adjust each node's config by index
+    pub async fn start_with(count: usize, mut configure: impl FnMut(usize, &mut NodeConfig)) -> Result<Self> {
+        let mut nodes: Vec<TestNode> = Vec::with_capacity(count);
+        for index in 0..count {
+            let port = NEXT_PORT.fetch_add(1, Ordering::Relaxed);
+            let address: Multiaddr = format!("/memory/{}", port).parse()?;
+            let mut config = NodeConfig::new_local(
+                format!("test-node-{}", index),
+                0,
+                0,
+                nodes.iter().map(|node| node.address.to_string()).collect(),
+            );
+            // Test nodes keep their peer registry in memory
+            config.data_dir = String::new();
+            configure(index, &mut config);
+
+            let (mut node, shutdown) = Node::create(config, memory_transport, false).await?;
+            let gossip = node.gossip();
+            let peer_id = node.peer_id();
+            node.open(vec![address.clone()]).await?;
+            nodes.push(TestNode {
+                peer_id,
+                address,
+                messages: Mutex::new(gossip.messages()),
+                gossip,
+                control: node.peer_control(),
+                shutdown,
+                task: tokio::spawn(async move { node.run().await }),
+            });
+        }
+        Ok(Self { nodes })
+    }
+
+    pub fn nodes(&self) -> &[TestNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &TestNode {
+        &self.nodes[index]
+    }
+
+    /// Waits up to `timeout` until every node knows all the others to be
+    /// subscribed to `topic`, after which a publish reaches every node
+    pub async fn wait_for_topic(&self, topic: Topic, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let others = self.nodes.len().saturating_sub(1);
+        loop {
+            let mut ready = true;
+            for node in &self.nodes {
+                ready &= node.gossip.topic_peers(topic).await >= others;
+            }
+            if ready {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(anyhow!("Nodes did not all join {} within {:?}", topic, timeout));
+            }
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Stops every node and waits for their event loops to finish
+    pub async fn shutdown(self) -> Result<()> {
+        for node in self.nodes {
+            let _ = node.shutdown.send(()).await;
+            node.task.await??;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn gossip_reaches_every_node() {
+        let network = TestNetwork::start(3).await.unwrap();
+        network.wait_for_topic(Topic::Transactions, Duration::from_secs(10)).await.unwrap();
+
+        network.node(0).publish(Topic::Transactions, b"hello".to_vec()).unwrap();
+        for node in &network.nodes()[1..] {
+            let message = node.next_message(Topic::Transactions, Duration::from_secs(10)).await.unwrap();
+            assert_eq!(message.payload, b"hello");
+        }
+        network.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+-- Initial wIndexer schema
+
+CREATE TABLE IF NOT EXISTS accounts (
+    pubkey TEXT PRIMARY KEY,
+    owner TEXT NOT NULL,
+    lamports BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    executable BOOLEAN NOT NULL,
+    rent_epoch BIGINT NOT NULL,
+    data BYTEA,
+    write_version BIGINT NOT NULL,
+    is_startup BOOLEAN NOT NULL DEFAULT FALSE,
+    transaction_signature TEXT,
+    last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS accounts_slot_idx ON accounts(slot, write_version);
+CREATE INDEX IF NOT EXISTS accounts_owner_idx ON accounts(owner);
+CREATE INDEX IF NOT EXISTS accounts_last_updated_idx ON accounts(last_updated DESC);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    tx_index BIGINT NOT NULL,
+    is_vote BOOLEAN NOT NULL,
+    success BOOLEAN NOT NULL,
+    fee BIGINT NOT NULL,
+    message BYTEA NOT NULL,
+    signatures BYTEA NOT NULL,
+    meta JSONB,
+    last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS transactions_slot_idx ON transactions(slot, tx_index);
+
+CREATE TABLE IF NOT EXISTS blocks (
+    slot BIGINT PRIMARY KEY,
+    parent_slot BIGINT,
+    status TEXT NOT NULL,
+    blockhash TEXT,
+    parent_blockhash TEXT,
+    block_time BIGINT,
+    block_height BIGINT,
+    transaction_count BIGINT,
+    entry_count BIGINT NOT NULL DEFAULT 0,
+    rewards JSONB,
+    last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS transaction_mentions (
+    signature TEXT NOT NULL REFERENCES transactions(signature) ON DELETE CASCADE,
+    pubkey TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    is_signer BOOLEAN NOT NULL,
+    is_writable BOOLEAN NOT NULL,
+    PRIMARY KEY (signature, pubkey)
+);
+
+CREATE INDEX IF NOT EXISTS transaction_mentions_pubkey_idx ON transaction_mentions(pubkey, slot DESC);
+
+CREATE TABLE IF NOT EXISTS transaction_programs (
+    program_id TEXT NOT NULL,
+    slot BIGINT NOT NULL,
+    signature TEXT NOT NULL REFERENCES transactions(signature) ON DELETE CASCADE,
+    PRIMARY KEY (program_id, slot, signature)
+);
@@ -0,0 +1,17 @@
+-- Every account version, keyed by (pubkey, slot, write_version)
+
+CREATE TABLE IF NOT EXISTS account_history (
+    pubkey TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    lamports BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    executable BOOLEAN NOT NULL,
+    rent_epoch BIGINT NOT NULL,
+    data BYTEA,
+    write_version BIGINT NOT NULL,
+    is_startup BOOLEAN NOT NULL DEFAULT FALSE,
+    transaction_signature TEXT,
+    PRIMARY KEY (pubkey, slot, write_version)
+);
+
+CREATE INDEX IF NOT EXISTS account_history_slot_idx ON account_history(slot);
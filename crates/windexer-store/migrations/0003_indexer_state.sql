@@ -0,0 +1,9 @@
+-- Indexing progress, a single row updated on every rooted slot
+
+CREATE TABLE IF NOT EXISTS indexer_state (
+    id SMALLINT PRIMARY KEY CHECK (id = 1),
+    last_processed_slot BIGINT NOT NULL,
+    total_accounts BIGINT NOT NULL,
+    total_transactions BIGINT NOT NULL,
+    last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,12 @@
+-- API keys issued by the API server; only a hash of each secret is stored
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    secret_hash TEXT NOT NULL,
+    scopes TEXT[] NOT NULL,
+    rate_limit_per_minute BIGINT,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+);
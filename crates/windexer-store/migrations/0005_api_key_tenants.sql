@@ -0,0 +1,3 @@
+-- Keys issued for a tenant only see that tenant's data
+
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS tenant TEXT;
@@ -0,0 +1,21 @@
+-- Operator and delegation accounting of the staking service, in lamports
+
+CREATE TABLE IF NOT EXISTS staking_operators (
+    operator TEXT PRIMARY KEY,
+    total_stake BIGINT NOT NULL,
+    performance_score DOUBLE PRECISION NOT NULL,
+    last_active BIGINT,
+    reward_balance BIGINT NOT NULL DEFAULT 0,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS staking_delegations (
+    operator TEXT NOT NULL,
+    staker TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    reward_balance BIGINT NOT NULL DEFAULT 0,
+    updated_at BIGINT NOT NULL,
+    PRIMARY KEY (operator, staker)
+);
+
+CREATE INDEX IF NOT EXISTS staking_delegations_staker_idx ON staking_delegations (staker);
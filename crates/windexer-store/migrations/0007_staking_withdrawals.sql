@@ -0,0 +1,3 @@
+-- Unstaked amounts waiting out their cooldown, as a JSON array per delegation
+
+ALTER TABLE staking_delegations ADD COLUMN IF NOT EXISTS pending_withdrawals JSONB NOT NULL DEFAULT '[]';
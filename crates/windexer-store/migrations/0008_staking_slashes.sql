@@ -0,0 +1,13 @@
+-- Stake taken from operators for violations, in lamports
+
+CREATE TABLE IF NOT EXISTS staking_slashes (
+    id BIGSERIAL PRIMARY KEY,
+    operator TEXT NOT NULL,
+    violation TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    stake_before BIGINT NOT NULL,
+    epoch BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS staking_slashes_operator_idx ON staking_slashes (operator, id DESC);
@@ -0,0 +1,17 @@
+-- Operator performance per staking epoch, kept for a number of epochs
+
+CREATE TABLE IF NOT EXISTS staking_performance (
+    operator TEXT NOT NULL,
+    epoch BIGINT NOT NULL,
+    uptime DOUBLE PRECISION NOT NULL,
+    missed_rounds BIGINT NOT NULL,
+    rounds BIGINT NOT NULL,
+    response_time DOUBLE PRECISION NOT NULL,
+    message_success_rate DOUBLE PRECISION,
+    slashes BIGINT NOT NULL,
+    performance_score DOUBLE PRECISION NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (operator, epoch)
+);
+
+CREATE INDEX IF NOT EXISTS staking_performance_epoch_idx ON staking_performance (epoch);
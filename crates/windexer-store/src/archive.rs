@@ -0,0 +1,655 @@
+//! Object storage archival tier
+//!
+//! The [`Archiver`] periodically uploads sealed Parquet partitions to an
+//! object store (S3, GCS or a local directory, via the `object_store` crate)
+//! and deletes them from local disk. A partition is sealed once the newest
+//! partition of its table is at least `archive_after_slots` ahead of it and
+//! all of its files are older than `min_file_age_secs`, so files that may
+//! still be open are never moved.
+//!
+//! Archived files keep the local layout under the URL prefix:
+//!
+//! ```text
+//! s3://bucket/windexer/accounts/slot_start=312000000/part-1718000000-0.parquet
+//! ```
+//!
+//! [`ArchivedStore`] wraps the local backend and serves slot-range queries,
+//! block lookups and account history from the archive when the local store
+//! cannot fill them. Point lookups by pubkey or signature are not served from
+//! the archive because it is only indexed by slot. Transactions read back from
+//! the archive carry the reduced meta stored in Parquet (status, fee and logs).
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        parquet_store::{read_parquet, ParquetRecord},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::{anyhow, Context, Result},
+    async_trait::async_trait,
+    bytes::Bytes,
+    futures::stream::BoxStream,
+    object_store::{path::Path as ObjectPath, ObjectStore},
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        fmt::{Debug, Formatter, Result as FmtResult},
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+        str::FromStr,
+        time::Duration,
+    },
+    tokio::task::JoinHandle,
+    tracing::{debug, error, info, warn},
+    url::Url,
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+    windexer_common::config::storage::ParquetConfig,
+};
+
+const TABLES: [&str; 3] = ["accounts", "transactions", "blocks"];
+
+/// Archival configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    /// Archive location, e.g. `s3://bucket/prefix`, `gs://bucket/prefix` or
+    /// `file:///mnt/archive`
+    pub url: String,
+    /// Object store options such as `aws_region` or `google_service_account`;
+    /// credentials are otherwise read from the environment
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+    /// How far behind the newest partition a partition must be to be archived
+    #[serde(default = "default_archive_after_slots")]
+    pub archive_after_slots: u64,
+    #[serde(default = "default_archive_interval_secs")]
+    pub interval_seconds: u64,
+    /// Minimum time since a file was last modified before it is uploaded
+    #[serde(default = "default_min_file_age_secs")]
+    pub min_file_age_secs: u64,
+}
+
+fn default_archive_after_slots() -> u64 {
+    432_000 // one epoch
+}
+
+fn default_archive_interval_secs() -> u64 {
+    600
+}
+
+fn default_min_file_age_secs() -> u64 {
+    300
+}
+
+/// Archival statistics
+#[derive(Default)]
+pub struct ArchiveStats {
+    pub runs: AtomicU64,
+    pub run_errors: AtomicU64,
+    pub partitions_archived: AtomicU64,
+    pub files_archived: AtomicU64,
+    pub bytes_archived: AtomicU64,
+    /// Queries that fell through to the archive
+    pub archive_reads: AtomicU64,
+    pub archive_read_errors: AtomicU64,
+    pub rows_from_archive: AtomicU64,
+    pub last_run_timestamp: AtomicU64,
+}
+
+impl Debug for ArchiveStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ArchiveStats")
+            .field("runs", &self.runs.load(Ordering::Relaxed))
+            .field("run_errors", &self.run_errors.load(Ordering::Relaxed))
+            .field("partitions_archived", &self.partitions_archived.load(Ordering::Relaxed))
+            .field("files_archived", &self.files_archived.load(Ordering::Relaxed))
+            .field("bytes_archived", &self.bytes_archived.load(Ordering::Relaxed))
+            .field("archive_reads", &self.archive_reads.load(Ordering::Relaxed))
+            .field("archive_read_errors", &self.archive_read_errors.load(Ordering::Relaxed))
+            .field("rows_from_archive", &self.rows_from_archive.load(Ordering::Relaxed))
+            .field("last_run_timestamp", &self.last_run_timestamp.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Moves sealed Parquet partitions to object storage and reads them back
+pub struct Archiver {
+    config: ArchiveConfig,
+    store: Box<dyn ObjectStore>,
+    prefix: ObjectPath,
+    directory: PathBuf,
+    slots_per_partition: u64,
+    stats: Arc<ArchiveStats>,
+}
+
+impl Archiver {
+    /// Create an archiver for the files written by a slot-partitioned Parquet store
+    pub fn new(config: ArchiveConfig, parquet: &ParquetConfig) -> Result<Self> {
+        if !parquet.partition_by_slot {
+            return Err(anyhow!("archival requires a Parquet store with 'partition_by_slot' enabled"));
+        }
+
+        let url = Url::parse(&config.url)
+            .with_context(|| format!("Invalid archive URL '{}'", config.url))?;
+        let (store, prefix) = object_store::parse_url_opts(&url, config.options.iter())
+            .with_context(|| format!("Unsupported archive URL '{}'", config.url))?;
+
+        Ok(Self {
+            config,
+            store,
+            prefix,
+            directory: PathBuf::from(&parquet.directory),
+            slots_per_partition: parquet.slots_per_partition.max(1),
+            stats: Arc::new(ArchiveStats::default()),
+        })
+    }
+
+    /// Get the archival statistics
+    pub fn stats(&self) -> Arc<ArchiveStats> {
+        self.stats.clone()
+    }
+
+    /// Spawn the periodic archival task
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(self.config.interval_seconds.max(1));
+            let mut interval = tokio::time::interval(period);
+            info!("Archiver started (url={}, interval={:?})", self.config.url, period);
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_once().await {
+                    self.stats.run_errors.fetch_add(1, Ordering::Relaxed);
+                    error!("Archive run failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Archive every sealed partition of every table
+    pub async fn run_once(&self) -> Result<()> {
+        for table in TABLES {
+            let partitions = self.local_partitions(table).await?;
+            let Some(&(frontier, _)) = partitions.last() else {
+                continue;
+            };
+            let cutoff = frontier.saturating_sub(self.config.archive_after_slots);
+
+            for (slot_start, directory) in partitions {
+                if slot_start.saturating_add(self.slots_per_partition) > cutoff {
+                    break;
+                }
+                self.archive_partition(table, slot_start, &directory).await?;
+            }
+        }
+
+        self.stats.runs.fetch_add(1, Ordering::Relaxed);
+        self.stats.last_run_timestamp.store(
+            windexer_common::utils::current_timestamp() as u64,
+            Ordering::Relaxed,
+        );
+
+        Ok(())
+    }
+
+    /// Local partitions of a table, oldest first
+    async fn local_partitions(&self, table: &str) -> Result<Vec<(u64, PathBuf)>> {
+        let mut partitions = Vec::new();
+        let mut entries = match tokio::fs::read_dir(self.directory.join(table)).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(partitions),
+            Err(e) => return Err(e.into()),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(slot_start) = entry.file_name().to_str().and_then(parse_partition) {
+                partitions.push((slot_start, entry.path()));
+            }
+        }
+
+        partitions.sort_unstable_by_key(|(slot_start, _)| *slot_start);
+        Ok(partitions)
+    }
+
+    async fn archive_partition(&self, table: &str, slot_start: u64, directory: &Path) -> Result<()> {
+        let min_age = Duration::from_secs(self.config.min_file_age_secs);
+        let destination = self.partition_path(table, slot_start);
+        let mut remaining = 0usize;
+
+        let mut entries = tokio::fs::read_dir(directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("parquet") {
+                remaining += 1;
+                continue;
+            }
+
+            let age = entry.metadata().await?.modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age < min_age {
+                remaining += 1;
+                continue;
+            }
+
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                remaining += 1;
+                continue;
+            };
+
+            let contents = tokio::fs::read(&path).await?;
+            let size = contents.len() as u64;
+            self.store.put(&destination.child(file_name), Bytes::from(contents)).await
+                .with_context(|| format!("Failed to upload {}", path.display()))?;
+            // Only delete once the upload has succeeded
+            tokio::fs::remove_file(&path).await?;
+
+            self.stats.files_archived.fetch_add(1, Ordering::Relaxed);
+            self.stats.bytes_archived.fetch_add(size, Ordering::Relaxed);
+            debug!("Archived {} ({} bytes)", path.display(), size);
+        }
+
+        if remaining == 0 {
+            tokio::fs::remove_dir(directory).await?;
+            self.stats.partitions_archived.fetch_add(1, Ordering::Relaxed);
+            info!("Archived {} partition slot_start={}", table, slot_start);
+        }
+
+        Ok(())
+    }
+
+    fn partition_path(&self, table: &str, slot_start: u64) -> ObjectPath {
+        self.prefix.child(table).child(format!("slot_start={}", slot_start))
+    }
+
+    /// Archived partitions of a table, oldest first
+    async fn archived_partitions(&self, table: &str) -> Result<Vec<u64>> {
+        let listing = self.store.list_with_delimiter(Some(&self.prefix.child(table))).await?;
+
+        let mut partitions: Vec<u64> = listing.common_prefixes
+            .iter()
+            .filter_map(|prefix| prefix.filename().and_then(parse_partition))
+            .collect();
+        partitions.sort_unstable();
+        Ok(partitions)
+    }
+
+    /// Read up to `limit` archived rows in `[start_slot, end_slot]` that match
+    /// `filter`, ordered by slot
+    pub(crate) async fn read_range<T, F>(&self, start_slot: u64, end_slot: u64, limit: usize, filter: F) -> Result<Vec<T>>
+    where
+        T: ParquetRecord,
+        F: Fn(&T) -> bool + Send + Sync,
+    {
+        self.stats.archive_reads.fetch_add(1, Ordering::Relaxed);
+
+        let mut rows = Vec::new();
+        for slot_start in self.archived_partitions(T::TABLE).await? {
+            if slot_start > end_slot {
+                break;
+            }
+            if slot_start.saturating_add(self.slots_per_partition) <= start_slot {
+                continue;
+            }
+
+            let listing = self.store
+                .list_with_delimiter(Some(&self.partition_path(T::TABLE, slot_start)))
+                .await?;
+            for object in listing.objects {
+                let contents = self.store.get(&object.location).await?.bytes().await?;
+                let decoded = tokio::task::spawn_blocking(move || read_parquet::<T>(contents)).await??;
+                rows.extend(decoded.into_iter().filter(|row| {
+                    (start_slot..=end_slot).contains(&row.slot()) && filter(row)
+                }));
+            }
+
+            // Later partitions only hold later slots
+            if rows.len() >= limit {
+                break;
+            }
+        }
+
+        rows.sort_by_key(|row| row.slot());
+        rows.truncate(limit);
+        self.stats.rows_from_archive.fetch_add(rows.len() as u64, Ordering::Relaxed);
+        Ok(rows)
+    }
+}
+
+impl Debug for Archiver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Archiver")
+            .field("url", &self.config.url)
+            .field("directory", &self.directory)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+fn parse_partition(name: &str) -> Option<u64> {
+    name.strip_prefix("slot_start=")?.parse().ok()
+}
+
+/// Local backend with archived partitions behind it
+pub struct ArchivedStore {
+    local: Arc<dyn Storage>,
+    archiver: Arc<Archiver>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ArchivedStore {
+    /// Wrap a local backend and start the archival task
+    pub fn new(local: Arc<dyn Storage>, archiver: Arc<Archiver>) -> Self {
+        let task = archiver.clone().start();
+
+        Self {
+            local,
+            archiver,
+            task: Mutex::new(Some(task)),
+        }
+    }
+
+    /// Get the archival statistics
+    pub fn stats(&self) -> Arc<ArchiveStats> {
+        self.archiver.stats()
+    }
+
+    /// Fill up a local result from the archive when it came back short
+    async fn top_up<T, F>(&self, mut rows: Vec<T>, start_slot: u64, end_slot: u64, limit: usize, filter: F) -> Vec<T>
+    where
+        T: ParquetRecord,
+        F: Fn(&T) -> bool + Send + Sync,
+    {
+        if rows.len() >= limit {
+            return rows;
+        }
+
+        match self.archiver.read_range(start_slot, end_slot, limit, filter).await {
+            Ok(archived) => {
+                rows.extend(archived);
+                rows.sort_by_key(|row| row.slot());
+                rows.truncate(limit);
+            }
+            Err(e) => {
+                self.archiver.stats.archive_read_errors.fetch_add(1, Ordering::Relaxed);
+                warn!("Archive read for {} failed: {}", T::TABLE, e);
+            }
+        }
+        rows
+    }
+}
+
+impl Debug for ArchivedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ArchivedStore")
+            .field("archiver", &self.archiver)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for ArchivedStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.local.store_account(account).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.local.store_transaction(transaction).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.local.store_block(block).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        self.local.store_accounts(accounts).await
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        self.local.store_transactions(transactions).await
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        self.local.store_blocks(blocks).await
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        self.local.store_slot_bundle(bundle).await
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.local.update_slot_status(status).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.local.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.local.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.local.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.local.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.local.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.local.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.local.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.local.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.local.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.local.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.local.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.local.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.local.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.local.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.local.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.local.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.local.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.local.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.local.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.local.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.local.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        if let Some(block) = self.local.get_block(slot).await? {
+            return Ok(Some(block));
+        }
+        let blocks = self.top_up(Vec::new(), slot, slot, 1, |_: &BlockData| true).await;
+        Ok(blocks.into_iter().next())
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.local.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.local.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.local.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.local.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let local = match self.local.get_account_history(pubkey, start_slot, end_slot, limit).await {
+            Ok(local) => local,
+            // Write-only local stores such as Parquet keep no history index
+            Err(e) => {
+                debug!("Local account history unavailable: {}", e);
+                Vec::new()
+            }
+        };
+        let pubkey = Pubkey::from_str(pubkey)?;
+        Ok(self.top_up(local, start_slot, end_slot, limit, move |a: &AccountData| a.pubkey == pubkey).await)
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.local.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.local.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.local.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.local.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.local.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.local.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.local.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.local.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.local.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.local.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.local.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.local.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.local.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let local = self.local.get_accounts_by_slot_range(start_slot, end_slot, limit).await?;
+        Ok(self.top_up(local, start_slot, end_slot, limit, |_: &AccountData| true).await)
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        let local = self.local.get_transactions_by_slot_range(start_slot, end_slot, limit).await?;
+        Ok(self.top_up(local, start_slot, end_slot, limit, |_: &TransactionData| true).await)
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        let local = self.local.get_blocks_by_slot_range(start_slot, end_slot, limit).await?;
+        Ok(self.top_up(local, start_slot, end_slot, limit, |_: &BlockData| true).await)
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.local.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.local.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.local.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.local.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.local.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        if let Some(task) = self.task.lock().ok().and_then(|mut task| task.take()) {
+            task.abort();
+        }
+        self.local.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_partition() {
+        assert_eq!(parse_partition("slot_start=312000000"), Some(312_000_000));
+        assert_eq!(parse_partition("part-1718000000-0.parquet"), None);
+    }
+}
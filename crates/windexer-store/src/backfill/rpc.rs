@@ -0,0 +1,117 @@
+//! Minimal Solana JSON-RPC client for backfills
+//!
+//! Works against any RPC provider, including Helius, that serves historical
+//! blocks. Requests are retried with exponential backoff; slots the cluster
+//! skipped are reported as `None` rather than errors.
+
+use {
+    anyhow::{anyhow, Context, Result},
+    serde_json::{json, Value},
+    std::time::Duration,
+    tracing::warn,
+};
+
+/// Slot was skipped, or its block is not available in long-term storage
+const SKIPPED_SLOT_CODES: [i64; 2] = [-32007, -32009];
+
+#[derive(Debug, Clone)]
+pub struct BackfillRpc {
+    url: String,
+    client: reqwest::Client,
+    max_retries: u32,
+}
+
+impl BackfillRpc {
+    pub fn new(url: &str, timeout: Duration, max_retries: u32) -> Result<Self> {
+        Ok(Self {
+            url: url.to_string(),
+            client: reqwest::Client::builder().timeout(timeout).build()?,
+            max_retries,
+        })
+    }
+
+    /// Send a request, returning the RPC error object instead of failing on it
+    async fn send(&self, method: &str, params: &Value) -> Result<std::result::Result<Value, Value>> {
+        let mut backoff = Duration::from_millis(250);
+        let mut attempt = 0;
+
+        loop {
+            let response = self.client
+                .post(&self.url)
+                .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            let error = match response {
+                Ok(response) => {
+                    let mut body: Value = response.json().await.with_context(|| format!("Invalid {} response", method))?;
+                    match body.get_mut("error") {
+                        Some(error) if SKIPPED_SLOT_CODES.contains(&error["code"].as_i64().unwrap_or_default()) => {
+                            return Ok(Err(error.take()));
+                        }
+                        Some(error) => anyhow!("{} failed: {}", method, error),
+                        None => return Ok(Ok(body["result"].take())),
+                    }
+                }
+                Err(e) => anyhow!("{} request failed: {}", method, e),
+            };
+
+            attempt += 1;
+            if attempt > self.max_retries {
+                return Err(error);
+            }
+            warn!("{}, retrying in {:?} ({}/{})", error, backoff, attempt, self.max_retries);
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_secs(10));
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        self.send(method, &params).await?.map_err(|error| anyhow!("{} failed: {}", method, error))
+    }
+
+    /// Latest finalized slot
+    pub async fn get_slot(&self) -> Result<u64> {
+        let slot = self.call("getSlot", json!([{ "commitment": "finalized" }])).await?;
+        slot.as_u64().ok_or_else(|| anyhow!("Invalid getSlot result {}", slot))
+    }
+
+    /// Slots with a confirmed block in `start..=end`
+    pub async fn get_blocks(&self, start: u64, end: u64) -> Result<Vec<u64>> {
+        let slots = self.call("getBlocks", json!([start, end, { "commitment": "finalized" }])).await?;
+        serde_json::from_value(slots).context("Invalid getBlocks result")
+    }
+
+    /// Full block with base64 encoded transactions, or `None` if it was skipped
+    pub async fn get_block(&self, slot: u64) -> Result<Option<Value>> {
+        let params = json!([slot, {
+            "encoding": "base64",
+            "transactionDetails": "full",
+            "rewards": true,
+            "commitment": "finalized",
+            "maxSupportedTransactionVersion": 0,
+        }]);
+        match self.send("getBlock", &params).await? {
+            Ok(Value::Null) | Err(_) => Ok(None),
+            Ok(block) => Ok(Some(block)),
+        }
+    }
+
+    /// Current state of up to 100 accounts, with the slot it was read at
+    pub async fn get_multiple_accounts(&self, pubkeys: &[String]) -> Result<(u64, Vec<Option<Value>>)> {
+        let result = self.call("getMultipleAccounts", json!([pubkeys, { "encoding": "base64", "commitment": "finalized" }])).await?;
+        let slot = result["context"]["slot"].as_u64().unwrap_or_default();
+        let accounts = serde_json::from_value(result["value"].clone()).context("Invalid getMultipleAccounts result")?;
+        Ok((slot, accounts))
+    }
+
+    /// Current state of every account owned by a program, with the slot it was read at
+    pub async fn get_program_accounts(&self, program_id: &str) -> Result<(u64, Vec<Value>)> {
+        let params = json!([program_id, { "encoding": "base64", "commitment": "finalized", "withContext": true }]);
+        let result = self.call("getProgramAccounts", params).await?;
+        let slot = result["context"]["slot"].as_u64().unwrap_or_default();
+        let accounts = serde_json::from_value(result["value"].clone()).context("Invalid getProgramAccounts result")?;
+        Ok((slot, accounts))
+    }
+}
@@ -0,0 +1,597 @@
+//! ClickHouse storage backend
+//!
+//! Stores accounts, transactions and blocks in ClickHouse over its HTTP
+//! interface so analytics workloads (fees per program per day, account growth,
+//! ...) can run directly against indexed data. Every write is a single
+//! `INSERT ... FORMAT JSONEachRow` request; the factory puts a
+//! [`BatchingStore`](crate::pipeline::BatchingStore) in front of this backend
+//! so inserts arrive in large batches, and `async_insert` lets the server
+//! buffer further.
+//!
+//! Tables are `ReplacingMergeTree`s partitioned by epoch-sized slot ranges, so
+//! re-ingested rows collapse during merges. The `accounts` table keeps every
+//! account version, which also makes account history queries cheap. Binary
+//! payloads (account data, messages, signatures) are stored hex-encoded.
+
+use {
+    crate::{config::ClickHouseConfig, retention::DataKind, traits::Storage},
+    anyhow::{anyhow, Context, Result},
+    async_trait::async_trait,
+    reqwest::Client,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{str::FromStr, time::Duration},
+    tracing::{debug, info},
+    windexer_common::{
+        types::{AccountData, BlockData, IndexerState, InstructionData, TransactionData},
+        utils::{SerializableSlotStatus, SerializableTransactionMeta},
+    },
+};
+
+const CREATE_ACCOUNTS: &str = r#"
+CREATE TABLE IF NOT EXISTS accounts (
+    pubkey String,
+    owner LowCardinality(String),
+    lamports UInt64,
+    slot UInt64,
+    executable Bool,
+    rent_epoch UInt64,
+    data String,
+    write_version UInt64,
+    is_startup Bool,
+    transaction_signature Nullable(String)
+)
+ENGINE = ReplacingMergeTree
+PARTITION BY intDiv(slot, 432000)
+ORDER BY (pubkey, slot, write_version)
+"#;
+
+const CREATE_TRANSACTIONS: &str = r#"
+CREATE TABLE IF NOT EXISTS transactions (
+    signature String,
+    slot UInt64,
+    tx_index UInt64,
+    is_vote Bool,
+    success Bool,
+    fee UInt64,
+    account_keys Array(String),
+    program_ids Array(LowCardinality(String)),
+    message String,
+    signatures String,
+    meta String,
+    INDEX signature_idx signature TYPE bloom_filter GRANULARITY 4,
+    INDEX account_keys_idx account_keys TYPE bloom_filter GRANULARITY 4,
+    INDEX program_ids_idx program_ids TYPE bloom_filter GRANULARITY 4
+)
+ENGINE = ReplacingMergeTree
+PARTITION BY intDiv(slot, 432000)
+ORDER BY (slot, tx_index, signature)
+"#;
+
+const CREATE_BLOCKS: &str = r#"
+CREATE TABLE IF NOT EXISTS blocks (
+    slot UInt64,
+    parent_slot Nullable(UInt64),
+    status LowCardinality(String),
+    blockhash Nullable(String),
+    parent_blockhash Nullable(String),
+    block_time Nullable(Int64),
+    block_height Nullable(UInt64),
+    transaction_count Nullable(UInt64),
+    entry_count UInt64,
+    rewards Nullable(String)
+)
+ENGINE = ReplacingMergeTree
+PARTITION BY intDiv(slot, 432000)
+ORDER BY slot
+"#;
+
+const CREATE_INDEXER_STATE: &str = r#"
+CREATE TABLE IF NOT EXISTS indexer_state (
+    last_processed_slot UInt64,
+    total_accounts UInt64,
+    total_transactions UInt64
+)
+ENGINE = ReplacingMergeTree(last_processed_slot)
+ORDER BY tuple()
+"#;
+
+#[derive(Serialize, Deserialize)]
+struct AccountRow {
+    pubkey: String,
+    owner: String,
+    lamports: u64,
+    slot: u64,
+    executable: bool,
+    rent_epoch: u64,
+    data: String,
+    write_version: u64,
+    is_startup: bool,
+    transaction_signature: Option<String>,
+}
+
+impl From<&AccountData> for AccountRow {
+    fn from(account: &AccountData) -> Self {
+        Self {
+            pubkey: account.pubkey.to_string(),
+            owner: account.owner.to_string(),
+            lamports: account.lamports,
+            slot: account.slot,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: hex::encode(&account.data),
+            write_version: account.write_version,
+            is_startup: account.is_startup,
+            transaction_signature: account.transaction_signature.map(|s| s.to_string()),
+        }
+    }
+}
+
+impl TryFrom<AccountRow> for AccountData {
+    type Error = anyhow::Error;
+
+    fn try_from(row: AccountRow) -> Result<Self> {
+        Ok(Self {
+            pubkey: Pubkey::from_str(&row.pubkey)?,
+            owner: Pubkey::from_str(&row.owner)?,
+            lamports: row.lamports,
+            slot: row.slot,
+            executable: row.executable,
+            rent_epoch: row.rent_epoch,
+            data: hex::decode(&row.data)?,
+            write_version: row.write_version,
+            is_startup: row.is_startup,
+            transaction_signature: row.transaction_signature
+                .map(|s| Signature::from_str(&s))
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TransactionRow {
+    signature: String,
+    slot: u64,
+    tx_index: u64,
+    is_vote: bool,
+    success: bool,
+    fee: u64,
+    account_keys: Vec<String>,
+    program_ids: Vec<String>,
+    message: String,
+    signatures: String,
+    meta: String,
+}
+
+impl TryFrom<&TransactionData> for TransactionRow {
+    type Error = anyhow::Error;
+
+    fn try_from(transaction: &TransactionData) -> Result<Self> {
+        let message = &transaction.message;
+
+        Ok(Self {
+            signature: transaction.signature.to_string(),
+            slot: transaction.slot,
+            tx_index: transaction.index as u64,
+            is_vote: transaction.is_vote,
+            success: transaction.serializable_meta.status == Some(0),
+            fee: transaction.serializable_meta.fee,
+            account_keys: message.account_keys.iter().map(|k| k.to_string()).collect(),
+            program_ids: message.program_ids().iter().map(|k| k.to_string()).collect(),
+            message: hex::encode(bincode::serialize(message)?),
+            signatures: hex::encode(bincode::serialize(&transaction.signatures)?),
+            meta: serde_json::to_string(&transaction.serializable_meta)?,
+        })
+    }
+}
+
+impl TryFrom<TransactionRow> for TransactionData {
+    type Error = anyhow::Error;
+
+    fn try_from(row: TransactionRow) -> Result<Self> {
+        let serializable_meta: SerializableTransactionMeta = serde_json::from_str(&row.meta)?;
+
+        let message = bincode::deserialize(&hex::decode(&row.message)?)?;
+        let instructions = InstructionData::parse(&message, serializable_meta.inner_instructions().as_deref());
+
+        Ok(Self {
+            signature: Signature::from_str(&row.signature)?,
+            slot: row.slot,
+            is_vote: row.is_vote,
+            message,
+            signatures: bincode::deserialize(&hex::decode(&row.signatures)?)?,
+            meta: Default::default(),
+            serializable_meta,
+            index: row.tx_index as usize,
+            instructions,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockRow {
+    slot: u64,
+    parent_slot: Option<u64>,
+    status: String,
+    blockhash: Option<String>,
+    parent_blockhash: Option<String>,
+    block_time: Option<i64>,
+    block_height: Option<u64>,
+    transaction_count: Option<u64>,
+    entry_count: u64,
+    rewards: Option<String>,
+}
+
+impl TryFrom<&BlockData> for BlockRow {
+    type Error = anyhow::Error;
+
+    fn try_from(block: &BlockData) -> Result<Self> {
+        Ok(Self {
+            slot: block.slot,
+            parent_slot: block.parent_slot,
+            status: block.status.as_str().to_string(),
+            blockhash: block.blockhash.clone(),
+            parent_blockhash: block.parent_blockhash.clone(),
+            block_time: block.timestamp,
+            block_height: block.block_height,
+            transaction_count: block.transaction_count,
+            entry_count: block.entry_count,
+            rewards: block.rewards.as_ref().map(serde_json::to_string).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<BlockRow> for BlockData {
+    type Error = anyhow::Error;
+
+    fn try_from(row: BlockRow) -> Result<Self> {
+        let status: SerializableSlotStatus = serde_json::from_value(serde_json::Value::String(row.status))?;
+
+        Ok(Self {
+            slot: row.slot,
+            parent_slot: row.parent_slot,
+            status: status.into(),
+            blockhash: row.blockhash,
+            parent_blockhash: row.parent_blockhash,
+            timestamp: row.block_time,
+            block_height: row.block_height,
+            transaction_count: row.transaction_count,
+            entry_count: row.entry_count,
+            entries: Vec::new(),
+            rewards: row.rewards.as_deref().map(serde_json::from_str).transpose()?,
+        })
+    }
+}
+
+/// ClickHouse storage implementation
+pub struct ClickHouseStore {
+    client: Client,
+    config: ClickHouseConfig,
+}
+
+impl ClickHouseStore {
+    pub async fn new(config: ClickHouseConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()?;
+
+        let store = Self { client, config };
+
+        store.execute("SELECT 1", &[]).await
+            .with_context(|| format!("Failed to reach ClickHouse at {}", store.config.url))?;
+
+        if store.config.create_tables {
+            for ddl in [CREATE_ACCOUNTS, CREATE_TRANSACTIONS, CREATE_BLOCKS, CREATE_INDEXER_STATE] {
+                store.execute(ddl, &[]).await?;
+            }
+            info!("ClickHouse tables are ready in database {}", store.config.database);
+        }
+
+        Ok(store)
+    }
+
+    /// Get the store configuration
+    pub fn config(&self) -> &ClickHouseConfig {
+        &self.config
+    }
+
+    /// Build a request with the database, credentials and query parameters set
+    ///
+    /// `params` bind `{name:Type}` placeholders in the query.
+    fn request(&self, params: &[(&str, String)]) -> reqwest::RequestBuilder {
+        let mut query: Vec<(String, String)> = vec![
+            ("database".to_string(), self.config.database.clone()),
+            ("output_format_json_quote_64bit_integers".to_string(), "0".to_string()),
+        ];
+        query.extend(params.iter().map(|(name, value)| (format!("param_{}", name), value.clone())));
+
+        let mut request = self.client.post(&self.config.url).query(&query);
+        if let Some(user) = &self.config.user {
+            request = request.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &self.config.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+        request
+    }
+
+    /// Run a statement, returning the raw response body
+    async fn execute(&self, sql: &str, params: &[(&str, String)]) -> Result<String> {
+        let response = self.request(params)
+            .body(sql.to_string())
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("ClickHouse returned {}: {}", status, body.trim()));
+        }
+        Ok(body)
+    }
+
+    /// Run a query and decode each `JSONEachRow` line
+    async fn select<R: DeserializeOwned>(&self, sql: &str, params: &[(&str, String)]) -> Result<Vec<R>> {
+        let body = self.execute(&format!("{} FORMAT JSONEachRow", sql), params).await?;
+        body.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).map_err(Into::into))
+            .collect()
+    }
+
+    /// Insert rows with a single request
+    async fn insert<R: Serialize>(&self, table: &str, rows: &[R]) -> Result<()> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let mut body = String::new();
+        for row in rows {
+            body.push_str(&serde_json::to_string(row)?);
+            body.push('\n');
+        }
+
+        let mut request = self.request(&[])
+            .query(&[("query", format!("INSERT INTO {} FORMAT JSONEachRow", table))]);
+        if self.config.async_insert {
+            request = request.query(&[("async_insert", "1"), ("wait_for_async_insert", "1")]);
+        }
+
+        let response = request.body(body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("ClickHouse insert into {} failed with {}: {}", table, status, body.trim()));
+        }
+
+        debug!("Inserted {} rows into {}", rows.len(), table);
+        Ok(())
+    }
+
+    async fn select_accounts(&self, sql: &str, params: &[(&str, String)]) -> Result<Vec<AccountData>> {
+        self.select::<AccountRow>(sql, params).await?
+            .into_iter()
+            .map(AccountData::try_from)
+            .collect()
+    }
+
+    async fn select_transactions(&self, sql: &str, params: &[(&str, String)]) -> Result<Vec<TransactionData>> {
+        self.select::<TransactionRow>(sql, params).await?
+            .into_iter()
+            .map(TransactionData::try_from)
+            .collect()
+    }
+
+    async fn select_blocks(&self, sql: &str, params: &[(&str, String)]) -> Result<Vec<BlockData>> {
+        self.select::<BlockRow>(sql, params).await?
+            .into_iter()
+            .map(BlockData::try_from)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Storage for ClickHouseStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.store_accounts(vec![account]).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.store_transactions(vec![transaction]).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.store_blocks(vec![block]).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        let rows: Vec<AccountRow> = accounts.iter().map(AccountRow::from).collect();
+        self.insert("accounts", &rows).await
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        let rows = transactions.iter()
+            .map(TransactionRow::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        self.insert("transactions", &rows).await
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        let rows = blocks.iter()
+            .map(BlockRow::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        self.insert("blocks", &rows).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        let accounts = self.select_accounts(
+            "SELECT * FROM accounts WHERE pubkey = {pubkey:String} \
+             ORDER BY slot DESC, write_version DESC LIMIT 1",
+            &[("pubkey", pubkey.to_string())],
+        ).await?;
+        Ok(accounts.into_iter().next())
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        let transactions = self.select_transactions(
+            "SELECT * FROM transactions WHERE signature = {signature:String} LIMIT 1",
+            &[("signature", signature.to_string())],
+        ).await?;
+        Ok(transactions.into_iter().next())
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        let blocks = self.select_blocks(
+            "SELECT * FROM blocks FINAL WHERE slot = {slot:UInt64}",
+            &[("slot", slot.to_string())],
+        ).await?;
+        Ok(blocks.into_iter().next())
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.select_accounts(
+            "SELECT * FROM accounts ORDER BY slot DESC, write_version DESC LIMIT 1 BY pubkey LIMIT {limit:UInt64}",
+            &[("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.select_transactions(
+            "SELECT * FROM transactions ORDER BY slot DESC, tx_index DESC LIMIT {limit:UInt64}",
+            &[("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.select_blocks(
+            "SELECT * FROM blocks FINAL ORDER BY slot DESC LIMIT {limit:UInt64}",
+            &[("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        let accounts = self.select_accounts(
+            "SELECT * FROM accounts WHERE pubkey = {pubkey:String} AND slot <= {slot:UInt64} \
+             ORDER BY slot DESC, write_version DESC LIMIT 1",
+            &[("pubkey", pubkey.to_string()), ("slot", slot.to_string())],
+        ).await?;
+        Ok(accounts.into_iter().next())
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.select_accounts(
+            "SELECT * FROM accounts FINAL WHERE pubkey = {pubkey:String} \
+             AND slot BETWEEN {start:UInt64} AND {end:UInt64} \
+             ORDER BY slot, write_version LIMIT {limit:UInt64}",
+            &[
+                ("pubkey", pubkey.to_string()),
+                ("start", start_slot.to_string()),
+                ("end", end_slot.to_string()),
+                ("limit", limit.to_string()),
+            ],
+        ).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.select_transactions(
+            "SELECT * FROM transactions WHERE has(account_keys, {account:String}) \
+             ORDER BY slot DESC, tx_index DESC LIMIT {limit:UInt64}",
+            &[("account", account.to_string()), ("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.select_transactions(
+            "SELECT * FROM transactions WHERE has(program_ids, {program:String}) \
+             ORDER BY slot DESC, tx_index DESC LIMIT {limit:UInt64}",
+            &[("program", program_id.to_string()), ("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.select_accounts(
+            "SELECT * FROM accounts WHERE slot BETWEEN {start:UInt64} AND {end:UInt64} \
+             ORDER BY slot, write_version LIMIT {limit:UInt64}",
+            &[("start", start_slot.to_string()), ("end", end_slot.to_string()), ("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.select_transactions(
+            "SELECT * FROM transactions WHERE slot BETWEEN {start:UInt64} AND {end:UInt64} \
+             ORDER BY slot, tx_index LIMIT {limit:UInt64}",
+            &[("start", start_slot.to_string()), ("end", end_slot.to_string()), ("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.select_blocks(
+            "SELECT * FROM blocks FINAL WHERE slot BETWEEN {start:UInt64} AND {end:UInt64} \
+             ORDER BY slot LIMIT {limit:UInt64}",
+            &[("start", start_slot.to_string()), ("end", end_slot.to_string()), ("limit", limit.to_string())],
+        ).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        #[derive(Deserialize)]
+        struct Latest {
+            slot: Option<u64>,
+        }
+
+        let rows: Vec<Latest> = self.select(
+            "SELECT maxOrNull(slot) AS slot FROM blocks WHERE status = 'rooted'",
+            &[],
+        ).await?;
+        Ok(rows.into_iter().next().and_then(|row| row.slot))
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.insert("indexer_state", std::slice::from_ref(state)).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        // Older rows linger until merged away, so pick the furthest one
+        let rows: Vec<IndexerState> = self.select(
+            "SELECT last_processed_slot, total_accounts, total_transactions FROM indexer_state \
+             ORDER BY last_processed_slot DESC LIMIT 1",
+            &[],
+        ).await?;
+        Ok(rows.into_iter().next())
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        #[derive(Deserialize)]
+        struct Count {
+            count: u64,
+        }
+
+        let (table, condition) = match kind {
+            // Every version is a row, so only accounts updated since the
+            // cutoff lose theirs; the latest state of the others stays
+            DataKind::Accounts => (
+                "accounts",
+                "slot < {slot:UInt64} AND pubkey IN (SELECT pubkey FROM accounts WHERE slot >= {slot:UInt64})",
+            ),
+            DataKind::Transactions => ("transactions", "slot < {slot:UInt64}"),
+            DataKind::Blocks => ("blocks", "slot < {slot:UInt64}"),
+        };
+        let params = [("slot", slot.to_string())];
+
+        let rows: Vec<Count> = self.select(
+            &format!("SELECT count() AS count FROM {} WHERE {}", table, condition),
+            &params,
+        ).await?;
+        let pruned = rows.into_iter().next().map_or(0, |row| row.count);
+
+        if pruned > 0 {
+            // Lightweight delete; rows are removed physically during merges
+            self.execute(&format!("DELETE FROM {} WHERE {}", table, condition), &params).await?;
+        }
+
+        Ok(pruned)
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
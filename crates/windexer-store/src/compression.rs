@@ -0,0 +1,168 @@
+//! Account data compression
+//!
+//! Account `data` payloads can be hundreds of kilobytes. [`Compressor`]
+//! compresses them with the configured codec before they are written and
+//! decompresses them transparently on read.
+//!
+//! Every encoded payload starts with a one-byte codec tag, so stores can change
+//! codec without rewriting existing data and small payloads can be kept raw.
+
+use {
+    anyhow::{anyhow, Result},
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        sync::atomic::{AtomicU64, Ordering},
+    },
+    windexer_common::config::storage::{CompressionCodec, CompressionConfig},
+};
+
+const TAG_NONE: u8 = 0;
+const TAG_LZ4: u8 = 1;
+const TAG_ZSTD: u8 = 2;
+
+/// Compression metrics
+#[derive(Default)]
+pub struct CompressionMetrics {
+    /// Payload bytes before compression
+    pub bytes_in: AtomicU64,
+    /// Payload bytes after compression, including the codec tag
+    pub bytes_out: AtomicU64,
+    pub payloads_compressed: AtomicU64,
+    /// Payloads stored raw because they were small or did not shrink
+    pub payloads_skipped: AtomicU64,
+}
+
+impl CompressionMetrics {
+    /// Compressed size as a fraction of the original size
+    pub fn ratio(&self) -> f64 {
+        let bytes_in = self.bytes_in.load(Ordering::Relaxed);
+        if bytes_in == 0 {
+            1.0
+        } else {
+            self.bytes_out.load(Ordering::Relaxed) as f64 / bytes_in as f64
+        }
+    }
+
+    pub(crate) fn record(&self, bytes_in: u64, bytes_out: u64) {
+        self.bytes_in.fetch_add(bytes_in, Ordering::Relaxed);
+        self.bytes_out.fetch_add(bytes_out, Ordering::Relaxed);
+    }
+}
+
+impl Debug for CompressionMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CompressionMetrics")
+            .field("bytes_in", &self.bytes_in.load(Ordering::Relaxed))
+            .field("bytes_out", &self.bytes_out.load(Ordering::Relaxed))
+            .field("payloads_compressed", &self.payloads_compressed.load(Ordering::Relaxed))
+            .field("payloads_skipped", &self.payloads_skipped.load(Ordering::Relaxed))
+            .field("ratio", &self.ratio())
+            .finish()
+    }
+}
+
+/// Encodes and decodes account data payloads
+pub struct Compressor {
+    config: CompressionConfig,
+    metrics: CompressionMetrics,
+}
+
+impl Compressor {
+    pub fn new(config: CompressionConfig) -> Self {
+        Self {
+            config,
+            metrics: CompressionMetrics::default(),
+        }
+    }
+
+    pub fn metrics(&self) -> &CompressionMetrics {
+        &self.metrics
+    }
+
+    /// Encode a payload with the configured codec
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let compressed = if data.len() < self.config.min_size_bytes {
+            None
+        } else {
+            match self.config.codec {
+                CompressionCodec::None => None,
+                CompressionCodec::Lz4 => Some((TAG_LZ4, lz4_flex::compress_prepend_size(data))),
+                CompressionCodec::Zstd => Some((TAG_ZSTD, zstd::bulk::compress(data, self.config.level)?)),
+            }
+        };
+
+        let encoded = match compressed {
+            // Keep the raw bytes if compression did not help
+            Some((tag, body)) if body.len() < data.len() => {
+                self.metrics.payloads_compressed.fetch_add(1, Ordering::Relaxed);
+                tagged(tag, &body)
+            }
+            _ => {
+                self.metrics.payloads_skipped.fetch_add(1, Ordering::Relaxed);
+                tagged(TAG_NONE, data)
+            }
+        };
+
+        self.metrics.record(data.len() as u64, encoded.len() as u64);
+        Ok(encoded)
+    }
+
+    /// Decode a payload produced by [`Compressor::compress`] with any codec
+    pub fn decompress(&self, encoded: &[u8]) -> Result<Vec<u8>> {
+        decompress(encoded)
+    }
+}
+
+impl Debug for Compressor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("Compressor")
+            .field("codec", &self.config.codec)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+fn tagged(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 1);
+    out.push(tag);
+    out.extend_from_slice(body);
+    out
+}
+
+/// Decode a tagged payload
+pub fn decompress(encoded: &[u8]) -> Result<Vec<u8>> {
+    let (&tag, body) = encoded
+        .split_first()
+        .ok_or_else(|| anyhow!("Empty compressed payload"))?;
+
+    match tag {
+        TAG_NONE => Ok(body.to_vec()),
+        TAG_LZ4 => lz4_flex::decompress_size_prepended(body)
+            .map_err(|e| anyhow!("Invalid LZ4 payload: {}", e)),
+        TAG_ZSTD => zstd::stream::decode_all(body)
+            .map_err(|e| anyhow!("Invalid zstd payload: {}", e)),
+        other => Err(anyhow!("Unknown compression tag {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_all_codecs() {
+        let data = vec![7u8; 4096];
+
+        for codec in [CompressionCodec::None, CompressionCodec::Lz4, CompressionCodec::Zstd] {
+            let compressor = Compressor::new(CompressionConfig {
+                codec,
+                ..CompressionConfig::default()
+            });
+            let encoded = compressor.compress(&data).unwrap();
+            assert_eq!(compressor.decompress(&encoded).unwrap(), data);
+            if codec != CompressionCodec::None {
+                assert!(compressor.metrics().ratio() < 0.5);
+            }
+        }
+    }
+}
@@ -0,0 +1,607 @@
+//! Storage backend configuration
+//!
+//! [`StoreConfig`] selects and configures a storage backend at runtime. It is
+//! internally tagged by `backend`, so the same file format works in TOML:
+//!
+//! ```toml
+//! backend = "tiered"
+//! cache_capacity = 50000
+//!
+//! [cold]
+//! backend = "postgres"
+//! connection_string = "postgres://windexer@localhost/windexer"
+//! ```
+//!
+//! and JSON:
+//!
+//! ```json
+//! { "backend": "rocksdb", "path": "/var/lib/windexer/store" }
+//! ```
+//!
+//! The `archived` backend moves old Parquet partitions to object storage:
+//!
+//! ```toml
+//! backend = "archived"
+//!
+//! [archive]
+//! url = "s3://windexer-archive/mainnet"
+//!
+//! [local]
+//! backend = "parquet"
+//! directory = "/var/lib/windexer/parquet"
+//! ```
+//!
+//! The `tenants` backend gives each tenant a filtered copy of the data, by
+//! default under `<path>/<id>` of the shared backend:
+//!
+//! ```toml
+//! backend = "tenants"
+//!
+//! [[tenants]]
+//! id = "acme"
+//! filter = { programs = ["TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"] }
+//!
+//! [inner]
+//! backend = "rocksdb"
+//! path = "/var/lib/windexer/store"
+//! ```
+
+use {
+    crate::{
+        archive::ArchiveConfig,
+        decoders::DEFAULT_EVENT_CAPACITY,
+        forks::ForkConfig,
+        index::{DEFAULT_CPI_CALLS_PER_PROGRAM, DEFAULT_SEARCH_CAPACITY},
+        pipeline::PipelineConfig,
+        tenant::{validate_tenant_id, TenantFilter},
+        tiered::TieredConfig,
+    },
+    anyhow::{anyhow, Context, Result},
+    serde::{Deserialize, Serialize},
+    std::{path::Path, time::Duration},
+    windexer_common::config::storage::{CompressionConfig, ParquetConfig, PostgresConfig, StorageConfig, StorageType},
+};
+
+/// Storage backend selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum StoreConfig {
+    /// Process-local memory, lost on restart
+    Memory,
+    /// Embedded RocksDB database
+    Rocksdb(RocksDbConfig),
+    /// Slot-partitioned Parquet files
+    Parquet(ParquetConfig),
+    /// PostgreSQL database
+    Postgres(PostgresConfig),
+    /// ClickHouse over HTTP, for analytics workloads
+    Clickhouse(ClickHouseConfig),
+    /// LRU cache in front of another backend
+    Tiered(TieredStoreConfig),
+    /// Drops duplicate writes before another backend
+    Dedup(DedupStoreConfig),
+    /// Stages unrooted slots and rolls back abandoned forks before another backend
+    #[serde(rename = "fork_aware")]
+    ForkAware(ForkAwareStoreConfig),
+    /// Archives old Parquet partitions to object storage and reads them back
+    Archived(ArchivedStoreConfig),
+    /// Maintains derived indexes such as token balances over another backend
+    Indexed(IndexedStoreConfig),
+    /// Copies writes into isolated per-tenant backends
+    Tenants(TenantStoreConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RocksDbConfig {
+    pub path: String,
+    #[serde(default = "default_max_open_files")]
+    pub max_open_files: i32,
+    /// Block cache size in bytes
+    #[serde(default = "default_block_cache_bytes")]
+    pub cache_capacity: usize,
+    /// Compression for account data payloads
+    #[serde(default)]
+    pub account_data_compression: CompressionConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClickHouseConfig {
+    /// HTTP endpoint, e.g. `http://localhost:8123`
+    pub url: String,
+    #[serde(default = "default_clickhouse_database")]
+    pub database: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_true")]
+    pub create_tables: bool,
+    /// Let the server buffer inserts (`async_insert=1`)
+    #[serde(default = "default_true")]
+    pub async_insert: bool,
+    /// Rows per insert request
+    #[serde(default = "default_clickhouse_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_clickhouse_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    #[serde(default = "default_clickhouse_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl ClickHouseConfig {
+    pub(crate) fn pipeline_config(&self) -> PipelineConfig {
+        PipelineConfig {
+            batch_size: self.batch_size,
+            flush_interval: Duration::from_millis(self.flush_interval_ms),
+            ..PipelineConfig::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TieredStoreConfig {
+    /// Maximum number of entries cached per data type
+    #[serde(default = "default_cache_entries")]
+    pub cache_capacity: usize,
+    #[serde(default = "default_write_batch_size")]
+    pub write_batch_size: usize,
+    #[serde(default = "default_flush_interval_ms")]
+    pub flush_interval_ms: u64,
+    /// Backend the cache writes through to
+    pub cold: Box<StoreConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupStoreConfig {
+    /// Number of recent writes remembered for duplicate detection
+    #[serde(default = "default_dedup_window")]
+    pub window: usize,
+    /// Backend that receives the deduplicated writes
+    pub inner: Box<StoreConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkAwareStoreConfig {
+    /// Maximum number of unrooted slots held in staging
+    #[serde(default = "default_max_staged_slots")]
+    pub max_staged_slots: usize,
+    /// Backend that receives rooted data
+    pub inner: Box<StoreConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedStoreConfig {
+    pub archive: ArchiveConfig,
+    /// Backend whose Parquet files are archived; must be a slot-partitioned
+    /// Parquet store, optionally behind other wrappers
+    pub local: Box<StoreConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedStoreConfig {
+    /// Maintain `(owner, mint)` balances and mint holders from SPL token accounts
+    #[serde(default = "default_true")]
+    pub token_balances: bool,
+    /// Maintain Metaplex NFT metadata by mint and current holders by owner
+    #[serde(default = "default_true")]
+    pub nfts: bool,
+    /// Follow state compression trees and the compressed NFTs they hold
+    #[serde(default = "default_true")]
+    pub compressed_nfts: bool,
+    /// Keep the addresses of address lookup tables
+    #[serde(default = "default_true")]
+    pub lookup_tables: bool,
+    /// Keep the latest calls into each program made by other programs
+    #[serde(default = "default_true")]
+    pub cpi_calls: bool,
+    /// Calls into each program the CPI index keeps, oldest evicted first
+    #[serde(default = "default_cpi_calls_per_program")]
+    pub cpi_calls_per_program: usize,
+    /// Maintain a prefix index of pubkeys, signatures and blockhashes
+    #[serde(default = "default_true")]
+    pub search: bool,
+    /// Signatures and blockhashes the search index keeps, oldest evicted first
+    #[serde(default = "default_search_capacity")]
+    pub search_capacity: usize,
+    /// Decode accounts of programs with a registered decoder
+    #[serde(default = "default_true")]
+    pub decoded_accounts: bool,
+    /// Keep the events in the logs of written transactions, with the data
+    /// programs log decoded by their IDLs
+    #[serde(default = "default_true")]
+    pub events: bool,
+    /// Events the event index keeps, oldest evicted first
+    #[serde(default = "default_event_capacity")]
+    pub event_capacity: usize,
+    /// Directory of Anchor IDL JSON files to generate decoders from
+    #[serde(default)]
+    pub idl_directory: Option<String>,
+    /// Rebuild the indexes from stored accounts when the store is created
+    #[serde(default = "default_true")]
+    pub rebuild_on_start: bool,
+    /// Backend the indexed writes go to
+    pub inner: Box<StoreConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// Namespace of the tenant's data, also used to select it in the API
+    pub id: String,
+    /// Data the tenant receives; everything but votes when empty
+    #[serde(default)]
+    pub filter: TenantFilter,
+    /// Backend of the tenant; defaults to the shared backend namespaced by `id`
+    #[serde(default)]
+    pub store: Option<Box<StoreConfig>>,
+}
+
+impl TenantConfig {
+    pub(crate) fn store_config(&self, shared: &StoreConfig) -> Result<StoreConfig> {
+        match &self.store {
+            Some(store) => Ok(store.as_ref().clone()),
+            None => shared.namespaced(&self.id)
+                .with_context(|| format!("tenant {} needs an explicit 'store'", self.id)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantStoreConfig {
+    pub tenants: Vec<TenantConfig>,
+    /// Shared backend receiving every write
+    pub inner: Box<StoreConfig>,
+}
+
+impl ForkAwareStoreConfig {
+    pub(crate) fn fork_config(&self) -> ForkConfig {
+        ForkConfig {
+            max_staged_slots: self.max_staged_slots,
+        }
+    }
+}
+
+impl TieredStoreConfig {
+    pub(crate) fn tiered_config(&self) -> TieredConfig {
+        TieredConfig {
+            cache_capacity: self.cache_capacity,
+            pipeline: PipelineConfig {
+                batch_size: self.write_batch_size,
+                flush_interval: Duration::from_millis(self.flush_interval_ms),
+                ..PipelineConfig::default()
+            },
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_clickhouse_database() -> String {
+    "windexer".to_string()
+}
+
+fn default_clickhouse_batch_size() -> usize {
+    10_000
+}
+
+fn default_clickhouse_flush_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_clickhouse_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_open_files() -> i32 {
+    1000
+}
+
+fn default_block_cache_bytes() -> usize {
+    100 * 1024 * 1024 // 100 MB
+}
+
+fn default_cache_entries() -> usize {
+    100_000
+}
+
+fn default_search_capacity() -> usize {
+    DEFAULT_SEARCH_CAPACITY
+}
+
+fn default_cpi_calls_per_program() -> usize {
+    DEFAULT_CPI_CALLS_PER_PROGRAM
+}
+
+fn default_event_capacity() -> usize {
+    DEFAULT_EVENT_CAPACITY
+}
+
+fn default_dedup_window() -> usize {
+    1_000_000
+}
+
+fn default_max_staged_slots() -> usize {
+    ForkConfig::default().max_staged_slots
+}
+
+fn default_write_batch_size() -> usize {
+    1_000
+}
+
+fn default_flush_interval_ms() -> u64 {
+    100
+}
+
+impl StoreConfig {
+    /// Load a configuration file, choosing the format from its extension
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read store config {}", path.display()))?;
+
+        let config: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&contents)
+                .with_context(|| format!("Invalid TOML in {}", path.display()))?,
+            Some("json") | None => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid JSON in {}", path.display()))?,
+            Some(other) => return Err(anyhow!("Unsupported store config format '.{}'", other)),
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Convert the storage section of the Geyser plugin configuration
+    pub fn from_geyser(config: &StorageConfig) -> Result<Self> {
+        let store_config = match config.storage_type {
+            StorageType::RocksDB => StoreConfig::Rocksdb(RocksDbConfig {
+                path: config.rocksdb_path.clone()
+                    .ok_or_else(|| anyhow!("storage_type is 'rocksdb' but rocksdb_path is not set"))?,
+                max_open_files: default_max_open_files(),
+                cache_capacity: default_block_cache_bytes(),
+                account_data_compression: CompressionConfig::default(),
+            }),
+            StorageType::Parquet => StoreConfig::Parquet(config.parquet.clone()
+                .ok_or_else(|| anyhow!("storage_type is 'parquet' but the parquet section is missing"))?),
+            StorageType::Postgres => StoreConfig::Postgres(config.postgres.clone()
+                .ok_or_else(|| anyhow!("storage_type is 'postgres' but the postgres section is missing"))?),
+        };
+
+        store_config.validate()?;
+        Ok(store_config)
+    }
+
+    /// Name of the selected backend
+    pub fn backend_name(&self) -> &'static str {
+        match self {
+            StoreConfig::Memory => "memory",
+            StoreConfig::Rocksdb(_) => "rocksdb",
+            StoreConfig::Parquet(_) => "parquet",
+            StoreConfig::Postgres(_) => "postgres",
+            StoreConfig::Clickhouse(_) => "clickhouse",
+            StoreConfig::Tiered(_) => "tiered",
+            StoreConfig::Dedup(_) => "dedup",
+            StoreConfig::ForkAware(_) => "fork_aware",
+            StoreConfig::Archived(_) => "archived",
+            StoreConfig::Indexed(_) => "indexed",
+            StoreConfig::Tenants(_) => "tenants",
+        }
+    }
+
+    /// Parquet settings of this backend or the backend it wraps, if any
+    pub fn parquet_config(&self) -> Option<&ParquetConfig> {
+        match self {
+            StoreConfig::Parquet(config) => Some(config),
+            StoreConfig::Tiered(config) => config.cold.parquet_config(),
+            StoreConfig::Dedup(config) => config.inner.parquet_config(),
+            StoreConfig::ForkAware(config) => config.inner.parquet_config(),
+            StoreConfig::Archived(config) => config.local.parquet_config(),
+            StoreConfig::Indexed(config) => config.inner.parquet_config(),
+            StoreConfig::Tenants(config) => config.inner.parquet_config(),
+            _ => None,
+        }
+    }
+
+    /// The same backend with its data under `namespace`: a subdirectory for
+    /// RocksDB and Parquet, including archived Parquet, and a separate
+    /// instance for memory. Database backends have no namespaced form.
+    pub fn namespaced(&self, namespace: &str) -> Result<Self> {
+        let subdirectory = |path: &str| Path::new(path).join(namespace).to_string_lossy().into_owned();
+        let inner = |config: &StoreConfig| config.namespaced(namespace).map(Box::new);
+
+        Ok(match self {
+            StoreConfig::Memory => StoreConfig::Memory,
+            StoreConfig::Rocksdb(config) => StoreConfig::Rocksdb(RocksDbConfig {
+                path: subdirectory(&config.path),
+                ..config.clone()
+            }),
+            StoreConfig::Parquet(config) => StoreConfig::Parquet(ParquetConfig {
+                directory: subdirectory(&config.directory),
+                ..config.clone()
+            }),
+            StoreConfig::Postgres(_) | StoreConfig::Clickhouse(_) => {
+                return Err(anyhow!("{} backend cannot be namespaced", self.backend_name()));
+            }
+            StoreConfig::Tiered(config) => StoreConfig::Tiered(TieredStoreConfig {
+                cold: inner(&config.cold)?,
+                ..config.clone()
+            }),
+            StoreConfig::Dedup(config) => StoreConfig::Dedup(DedupStoreConfig {
+                inner: inner(&config.inner)?,
+                ..config.clone()
+            }),
+            StoreConfig::ForkAware(config) => StoreConfig::ForkAware(ForkAwareStoreConfig {
+                inner: inner(&config.inner)?,
+                ..config.clone()
+            }),
+            StoreConfig::Archived(config) => StoreConfig::Archived(ArchivedStoreConfig {
+                archive: ArchiveConfig {
+                    url: format!("{}/{}", config.archive.url.trim_end_matches('/'), namespace),
+                    ..config.archive.clone()
+                },
+                local: inner(&config.local)?,
+            }),
+            StoreConfig::Indexed(config) => StoreConfig::Indexed(IndexedStoreConfig {
+                inner: inner(&config.inner)?,
+                ..config.clone()
+            }),
+            StoreConfig::Tenants(_) => return Err(anyhow!("tenants backend cannot be namespaced")),
+        })
+    }
+
+    /// Check the configuration for missing or inconsistent settings
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            StoreConfig::Memory => Ok(()),
+            StoreConfig::Rocksdb(config) => {
+                if config.path.trim().is_empty() {
+                    return Err(anyhow!("rocksdb backend requires a non-empty 'path'"));
+                }
+                if config.max_open_files == 0 {
+                    return Err(anyhow!("rocksdb 'max_open_files' must be positive or -1 for unlimited"));
+                }
+                Ok(())
+            }
+            StoreConfig::Parquet(config) => {
+                if config.directory.trim().is_empty() {
+                    return Err(anyhow!("parquet backend requires a non-empty 'directory'"));
+                }
+                if config.max_file_size_mb == 0 {
+                    return Err(anyhow!("parquet 'max_file_size_mb' must be greater than zero"));
+                }
+                Ok(())
+            }
+            StoreConfig::Postgres(config) => {
+                let url = config.connection_string.trim();
+                if url.is_empty() {
+                    return Err(anyhow!("postgres backend requires a 'connection_string'"));
+                }
+                if !(url.starts_with("postgres://") || url.starts_with("postgresql://")) {
+                    return Err(anyhow!(
+                        "postgres 'connection_string' must start with postgres:// or postgresql://"
+                    ));
+                }
+                if config.max_connections == 0 {
+                    return Err(anyhow!("postgres 'max_connections' must be greater than zero"));
+                }
+                Ok(())
+            }
+            StoreConfig::Clickhouse(config) => {
+                let url = config.url.trim();
+                if url.is_empty() {
+                    return Err(anyhow!("clickhouse backend requires a 'url'"));
+                }
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err(anyhow!("clickhouse 'url' must start with http:// or https://"));
+                }
+                if config.database.trim().is_empty() {
+                    return Err(anyhow!("clickhouse 'database' must not be empty"));
+                }
+                if config.batch_size == 0 {
+                    return Err(anyhow!("clickhouse 'batch_size' must be greater than zero"));
+                }
+                Ok(())
+            }
+            StoreConfig::Tiered(config) => {
+                if config.cache_capacity == 0 {
+                    return Err(anyhow!("tiered 'cache_capacity' must be greater than zero"));
+                }
+                match config.cold.as_ref() {
+                    StoreConfig::Tiered(_) => Err(anyhow!("tiered backend cannot wrap another tiered backend")),
+                    StoreConfig::Memory => Err(anyhow!("tiered backend needs a persistent cold backend, not 'memory'")),
+                    cold => cold.validate().context("invalid cold backend for tiered store"),
+                }
+            }
+            StoreConfig::Dedup(config) => {
+                if config.window == 0 {
+                    return Err(anyhow!("dedup 'window' must be greater than zero"));
+                }
+                match config.inner.as_ref() {
+                    StoreConfig::Dedup(_) => Err(anyhow!("dedup backend cannot wrap another dedup backend")),
+                    inner => inner.validate().context("invalid inner backend for dedup store"),
+                }
+            }
+            StoreConfig::ForkAware(config) => {
+                if config.max_staged_slots == 0 {
+                    return Err(anyhow!("fork_aware 'max_staged_slots' must be greater than zero"));
+                }
+                match config.inner.as_ref() {
+                    StoreConfig::ForkAware(_) => Err(anyhow!("fork_aware backend cannot wrap another fork_aware backend")),
+                    inner => inner.validate().context("invalid inner backend for fork_aware store"),
+                }
+            }
+            StoreConfig::Archived(config) => {
+                let url = config.archive.url.trim();
+                if !["s3://", "gs://", "file://"].iter().any(|scheme| url.starts_with(scheme)) {
+                    return Err(anyhow!("archived 'archive.url' must start with s3://, gs:// or file://"));
+                }
+                if let StoreConfig::Archived(_) = config.local.as_ref() {
+                    return Err(anyhow!("archived backend cannot wrap another archived backend"));
+                }
+                match config.local.parquet_config() {
+                    Some(parquet) if parquet.partition_by_slot => {}
+                    _ => return Err(anyhow!("archived backend needs a slot-partitioned parquet 'local' backend")),
+                }
+                config.local.validate().context("invalid local backend for archived store")
+            }
+            StoreConfig::Indexed(config) => {
+                match config.inner.as_ref() {
+                    StoreConfig::Indexed(_) => Err(anyhow!("indexed backend cannot wrap another indexed backend")),
+                    inner => inner.validate().context("invalid inner backend for indexed store"),
+                }
+            }
+            StoreConfig::Tenants(config) => {
+                if let StoreConfig::Tenants(_) = config.inner.as_ref() {
+                    return Err(anyhow!("tenants backend cannot wrap another tenants backend"));
+                }
+                config.inner.validate().context("invalid inner backend for tenants store")?;
+
+                let mut ids = std::collections::HashSet::new();
+                for tenant in &config.tenants {
+                    validate_tenant_id(&tenant.id)?;
+                    if !ids.insert(tenant.id.as_str()) {
+                        return Err(anyhow!("tenant {} is configured twice", tenant.id));
+                    }
+                    tenant.store_config(&config.inner)?
+                        .validate()
+                        .with_context(|| format!("invalid backend for tenant {}", tenant.id))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tiered_toml() {
+        let config: StoreConfig = toml::from_str(
+            r#"
+            backend = "tiered"
+            cache_capacity = 10
+
+            [cold]
+            backend = "rocksdb"
+            path = "/tmp/windexer"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.backend_name(), "tiered");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_missing_connection_string() {
+        let config: StoreConfig = serde_json::from_str(
+            r#"{ "backend": "postgres", "connection_string": "" }"#,
+        )
+        .unwrap();
+
+        assert!(config.validate().is_err());
+    }
+}
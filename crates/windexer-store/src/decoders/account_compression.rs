@@ -0,0 +1,130 @@
+//! Built-in SPL account-compression decoder
+//!
+//! Decodes concurrent merkle tree accounts, with their current root, and the
+//! program's instructions. The leaves themselves are followed by the
+//! [`CompressionIndex`](crate::index::CompressionIndex).
+
+use {
+    super::{DecodedAccount, DecodedInstruction, ProgramDecoder},
+    crate::index::compression::SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+    serde_json::{json, Value},
+    sha2::{Digest, Sha256},
+    solana_sdk::{hash::Hash, pubkey::Pubkey},
+    std::sync::OnceLock,
+};
+
+/// `CompressionAccountType::ConcurrentMerkleTree`
+const CONCURRENT_MERKLE_TREE: u8 = 1;
+/// Account type, header version and `ConcurrentMerkleTreeHeaderDataV1`
+const HEADER_LEN: usize = 56;
+/// Sequence number, active index and buffer size
+const TREE_PREFIX_LEN: usize = 24;
+
+/// Instructions by name, with the names of their accounts
+const INSTRUCTIONS: [(&str, &[&str]); 7] = [
+    ("init_empty_merkle_tree", &["merkle_tree", "authority", "noop"]),
+    ("replace_leaf", &["merkle_tree", "authority", "noop"]),
+    ("transfer_authority", &["merkle_tree", "authority"]),
+    ("verify_leaf", &["merkle_tree"]),
+    ("append", &["merkle_tree", "authority", "noop"]),
+    ("insert_or_append", &["merkle_tree", "authority", "noop"]),
+    ("close_empty_tree", &["merkle_tree", "authority", "recipient"]),
+];
+
+/// Anchor discriminators of [`INSTRUCTIONS`], in the same order
+fn discriminators() -> &'static [[u8; 8]] {
+    static DISCRIMINATORS: OnceLock<Vec<[u8; 8]>> = OnceLock::new();
+    DISCRIMINATORS.get_or_init(|| {
+        INSTRUCTIONS
+            .iter()
+            .map(|(name, _)| {
+                let hash = Sha256::digest(format!("global:{}", name).as_bytes());
+                hash[..8].try_into().expect("sha256 is 32 bytes")
+            })
+            .collect()
+    })
+}
+
+fn hash(data: &[u8], offset: usize) -> Option<String> {
+    let bytes: [u8; 32] = data.get(offset..offset + 32)?.try_into().ok()?;
+    Some(Hash::new_from_array(bytes).to_string())
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+/// Decodes merkle tree accounts and instructions of the account-compression
+/// program
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AccountCompressionDecoder;
+
+impl ProgramDecoder for AccountCompressionDecoder {
+    fn name(&self) -> &str {
+        "spl-account-compression"
+    }
+
+    fn program_ids(&self) -> Vec<Pubkey> {
+        vec![SPL_ACCOUNT_COMPRESSION_PROGRAM_ID]
+    }
+
+    fn decode_account(&self, _owner: &Pubkey, data: &[u8]) -> Option<DecodedAccount> {
+        if data.len() < HEADER_LEN + TREE_PREFIX_LEN || data[0] != CONCURRENT_MERKLE_TREE || data[1] != 0 {
+            return None;
+        }
+        let max_buffer_size = u32_at(data, 2)?;
+        let max_depth = u32_at(data, 6)?;
+        let authority = Pubkey::new_from_array(data[10..42].try_into().ok()?);
+        let active_index = u64_at(data, HEADER_LEN + 8)?;
+        // Each change log holds a root, the path below it and the leaf index
+        let change_log_len = 40 + 32 * max_depth as usize;
+        let root = hash(data, HEADER_LEN + TREE_PREFIX_LEN + active_index as usize * change_log_len)?;
+
+        Some(DecodedAccount {
+            account_type: "ConcurrentMerkleTree".to_string(),
+            parsed: json!({
+                "max_buffer_size": max_buffer_size,
+                "max_depth": max_depth,
+                "authority": authority.to_string(),
+                "creation_slot": u64_at(data, 42)?,
+                "sequence_number": u64_at(data, HEADER_LEN)?,
+                "active_index": active_index,
+                "buffer_size": u64_at(data, HEADER_LEN + 16)?,
+                "root": root,
+            }),
+        })
+    }
+
+    fn decode_instruction(&self, _program_id: &Pubkey, data: &[u8]) -> Option<DecodedInstruction> {
+        let position = discriminators().iter().position(|discriminator| data.starts_with(discriminator))?;
+        let (name, accounts) = INSTRUCTIONS[position];
+        let args = &data[8..];
+        let args = match name {
+            "init_empty_merkle_tree" => json!({ "max_depth": u32_at(args, 0)?, "max_buffer_size": u32_at(args, 4)? }),
+            "replace_leaf" => json!({
+                "root": hash(args, 0)?,
+                "previous_leaf": hash(args, 32)?,
+                "new_leaf": hash(args, 64)?,
+                "index": u32_at(args, 96)?,
+            }),
+            "transfer_authority" => {
+                json!({ "new_authority": Pubkey::new_from_array(args.get(..32)?.try_into().ok()?).to_string() })
+            }
+            "verify_leaf" | "insert_or_append" => {
+                json!({ "root": hash(args, 0)?, "leaf": hash(args, 32)?, "index": u32_at(args, 64)? })
+            }
+            "append" => json!({ "leaf": hash(args, 0)? }),
+            _ => Value::Object(Default::default()),
+        };
+
+        Some(DecodedInstruction {
+            name: name.to_string(),
+            args,
+            account_names: accounts.iter().map(|account| account.to_string()).collect(),
+        })
+    }
+}
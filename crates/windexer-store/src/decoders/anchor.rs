@@ -0,0 +1,658 @@
+//! Anchor IDL decoders
+//!
+//! Builds a [`ProgramDecoder`] at runtime from an Anchor IDL. Accounts,
+//! instructions and events are matched on their 8 byte discriminator and the
+//! remaining Borsh data is decoded into JSON following the IDL types.
+//!
+//! Both the legacy IDL format (Anchor < 0.30, discriminators derived from
+//! `sha256("account:<Name>")`, `sha256("global:<snake_name>")` and
+//! `sha256("event:<Name>")`) and the 0.30 format with explicit
+//! `discriminator` arrays are accepted.
+//!
+//! JSON encoding follows the Anchor TypeScript client where it can: `u128`
+//! and `i128` become strings, `bytes` hex and public keys base58. Enums become
+//! the variant name, or an object keyed by it when the variant has fields.
+//!
+//! [`IdlRegistry`] loads IDLs from disk or at runtime and keeps the decoders
+//! registered in a [`DecoderRegistry`].
+
+use {
+    super::{DecodedAccount, DecodedEvent, DecodedInstruction, DecoderRegistry, ProgramDecoder},
+    anyhow::{anyhow, Context, Result},
+    serde::Serialize,
+    serde_json::{json, Map, Value},
+    sha2::{Digest, Sha256},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, RwLock},
+    },
+    tracing::{info, warn},
+};
+
+const DISCRIMINATOR_LEN: usize = 8;
+
+/// Nested types deeper than this are rejected rather than recursed into
+const MAX_DEPTH: usize = 32;
+
+type Discriminator = [u8; DISCRIMINATOR_LEN];
+
+#[derive(Debug, Clone, PartialEq)]
+enum IdlType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+    F32,
+    F64,
+    String,
+    Bytes,
+    Pubkey,
+    Vec(Box<IdlType>),
+    Option(Box<IdlType>),
+    COption(Box<IdlType>),
+    Array(Box<IdlType>, usize),
+    Defined(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum IdlFields {
+    Named(Vec<(String, IdlType)>),
+    Tuple(Vec<IdlType>),
+    Unit,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum IdlTypeDef {
+    Struct(IdlFields),
+    Enum(Vec<(String, IdlFields)>),
+    Alias(IdlType),
+}
+
+#[derive(Debug, Clone)]
+struct IdlAccount {
+    name: String,
+    discriminator: Discriminator,
+}
+
+#[derive(Debug, Clone)]
+struct IdlEvent {
+    name: String,
+    discriminator: Discriminator,
+}
+
+#[derive(Debug, Clone)]
+struct IdlInstruction {
+    name: String,
+    discriminator: Discriminator,
+    args: Vec<(String, IdlType)>,
+    accounts: Vec<String>,
+}
+
+fn parse_type(value: &Value) -> Result<IdlType> {
+    if let Some(name) = value.as_str() {
+        return Ok(match name {
+            "bool" => IdlType::Bool,
+            "u8" => IdlType::U8,
+            "i8" => IdlType::I8,
+            "u16" => IdlType::U16,
+            "i16" => IdlType::I16,
+            "u32" => IdlType::U32,
+            "i32" => IdlType::I32,
+            "u64" => IdlType::U64,
+            "i64" => IdlType::I64,
+            "u128" => IdlType::U128,
+            "i128" => IdlType::I128,
+            "f32" => IdlType::F32,
+            "f64" => IdlType::F64,
+            "string" => IdlType::String,
+            "bytes" => IdlType::Bytes,
+            "publicKey" | "pubkey" => IdlType::Pubkey,
+            other => return Err(anyhow!("Unsupported IDL type '{}'", other)),
+        });
+    }
+
+    let object = value.as_object().ok_or_else(|| anyhow!("Invalid IDL type {}", value))?;
+    if let Some(inner) = object.get("vec") {
+        Ok(IdlType::Vec(Box::new(parse_type(inner)?)))
+    } else if let Some(inner) = object.get("option") {
+        Ok(IdlType::Option(Box::new(parse_type(inner)?)))
+    } else if let Some(inner) = object.get("coption") {
+        Ok(IdlType::COption(Box::new(parse_type(inner)?)))
+    } else if let Some(array) = object.get("array").and_then(Value::as_array) {
+        let len = array.get(1).and_then(Value::as_u64).ok_or_else(|| anyhow!("Unsupported IDL array length in {}", value))?;
+        Ok(IdlType::Array(Box::new(parse_type(array.first().unwrap_or(&Value::Null))?), len as usize))
+    } else if let Some(defined) = object.get("defined") {
+        // Legacy IDLs name the type directly, 0.30 wraps it in an object
+        let name = defined.as_str().or_else(|| defined.get("name").and_then(Value::as_str));
+        Ok(IdlType::Defined(name.ok_or_else(|| anyhow!("Invalid defined type {}", value))?.to_string()))
+    } else {
+        Err(anyhow!("Unsupported IDL type {}", value))
+    }
+}
+
+fn parse_fields(value: Option<&Value>) -> Result<IdlFields> {
+    let Some(fields) = value.and_then(Value::as_array).filter(|fields| !fields.is_empty()) else {
+        return Ok(IdlFields::Unit);
+    };
+
+    if fields[0].get("name").is_some() {
+        fields
+            .iter()
+            .map(|field| Ok((str_field(field, "name")?.to_string(), parse_type(&field["type"])?)))
+            .collect::<Result<_>>()
+            .map(IdlFields::Named)
+    } else {
+        fields.iter().map(parse_type).collect::<Result<_>>().map(IdlFields::Tuple)
+    }
+}
+
+fn parse_type_def(value: &Value) -> Result<IdlTypeDef> {
+    match str_field(value, "kind")? {
+        "struct" => Ok(IdlTypeDef::Struct(parse_fields(value.get("fields"))?)),
+        "enum" => value["variants"]
+            .as_array()
+            .ok_or_else(|| anyhow!("Enum without variants"))?
+            .iter()
+            .map(|variant| Ok((str_field(variant, "name")?.to_string(), parse_fields(variant.get("fields"))?)))
+            .collect::<Result<_>>()
+            .map(IdlTypeDef::Enum),
+        "type" | "alias" => Ok(IdlTypeDef::Alias(parse_type(&value["alias"])?)),
+        other => Err(anyhow!("Unsupported IDL type kind '{}'", other)),
+    }
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> Result<&'a str> {
+    value.get(field).and_then(Value::as_str).ok_or_else(|| anyhow!("IDL entry is missing '{}'", field))
+}
+
+fn discriminator(value: &Value, namespace: &str, name: &str) -> Result<Discriminator> {
+    match value.get("discriminator").and_then(Value::as_array) {
+        Some(bytes) => bytes
+            .iter()
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| anyhow!("Invalid discriminator for {}", name)),
+        None => {
+            let hash = Sha256::digest(format!("{}:{}", namespace, name).as_bytes());
+            Ok(hash[..DISCRIMINATOR_LEN].try_into().expect("sha256 is 32 bytes"))
+        }
+    }
+}
+
+/// `initializeVault` -> `initialize_vault`, as Anchor < 0.30 hashes it
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn account_names(accounts: &[Value], prefix: &str, names: &mut Vec<String>) {
+    for account in accounts {
+        let name = account.get("name").and_then(Value::as_str).unwrap_or_default();
+        // Composite account groups are flattened as `group.account`
+        match account.get("accounts").and_then(Value::as_array) {
+            Some(nested) => account_names(nested, &format!("{}{}.", prefix, name), names),
+            None => names.push(format!("{}{}", prefix, name)),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn length_prefix(&mut self) -> Option<usize> {
+        Some(u32::from_le_bytes(self.array()?) as usize)
+    }
+}
+
+/// Decoder for one Anchor program generated from its IDL
+#[derive(Debug)]
+pub struct AnchorDecoder {
+    name: String,
+    program_id: Pubkey,
+    accounts: Vec<IdlAccount>,
+    instructions: Vec<IdlInstruction>,
+    events: Vec<IdlEvent>,
+    types: HashMap<String, IdlTypeDef>,
+}
+
+impl AnchorDecoder {
+    /// Build a decoder from an IDL; `program_id` overrides the address the
+    /// IDL declares
+    pub fn from_idl(idl: &Value, program_id: Option<Pubkey>) -> Result<Self> {
+        let declared = idl
+            .get("address")
+            .or_else(|| idl.pointer("/metadata/address"))
+            .and_then(Value::as_str)
+            .map(Pubkey::from_str)
+            .transpose()
+            .context("Invalid program address in IDL")?;
+        let program_id = program_id.or(declared).ok_or_else(|| anyhow!("IDL does not declare a program address"))?;
+        let name = idl
+            .get("name")
+            .or_else(|| idl.pointer("/metadata/name"))
+            .and_then(Value::as_str)
+            .unwrap_or("anchor")
+            .to_string();
+        let legacy = idl.get("address").is_none();
+
+        let mut types = HashMap::new();
+        for def in idl.get("types").and_then(Value::as_array).into_iter().flatten() {
+            let type_name = str_field(def, "name")?;
+            let parsed = parse_type_def(&def["type"]).with_context(|| format!("Invalid IDL type {}", type_name))?;
+            types.insert(type_name.to_string(), parsed);
+        }
+
+        let mut accounts = Vec::new();
+        for account in idl.get("accounts").and_then(Value::as_array).into_iter().flatten() {
+            let account_name = str_field(account, "name")?;
+            // Legacy IDLs define account layouts inline, 0.30 in `types`
+            if let Some(def) = account.get("type") {
+                let parsed = parse_type_def(def).with_context(|| format!("Invalid IDL account {}", account_name))?;
+                types.insert(account_name.to_string(), parsed);
+            }
+            accounts.push(IdlAccount {
+                name: account_name.to_string(),
+                discriminator: discriminator(account, "account", account_name)?,
+            });
+        }
+
+        let mut instructions = Vec::new();
+        for instruction in idl.get("instructions").and_then(Value::as_array).into_iter().flatten() {
+            let ix_name = str_field(instruction, "name")?;
+            let hashed_name = if legacy { snake_case(ix_name) } else { ix_name.to_string() };
+            let args = match parse_fields(instruction.get("args"))? {
+                IdlFields::Named(args) => args,
+                IdlFields::Unit => Vec::new(),
+                IdlFields::Tuple(_) => return Err(anyhow!("Instruction {} has unnamed args", ix_name)),
+            };
+            let mut names = Vec::new();
+            account_names(instruction.get("accounts").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default(), "", &mut names);
+
+            instructions.push(IdlInstruction {
+                name: ix_name.to_string(),
+                discriminator: discriminator(instruction, "global", &hashed_name)?,
+                args,
+                accounts: names,
+            });
+        }
+
+        let mut events = Vec::new();
+        for event in idl.get("events").and_then(Value::as_array).into_iter().flatten() {
+            let event_name = str_field(event, "name")?;
+            // Legacy IDLs list event fields inline, 0.30 defines them in `types`
+            if let Some(fields) = event.get("fields") {
+                let parsed = parse_fields(Some(fields)).with_context(|| format!("Invalid IDL event {}", event_name))?;
+                types.insert(event_name.to_string(), IdlTypeDef::Struct(parsed));
+            }
+            events.push(IdlEvent {
+                name: event_name.to_string(),
+                discriminator: discriminator(event, "event", event_name)?,
+            });
+        }
+
+        Ok(Self {
+            name,
+            program_id,
+            accounts,
+            instructions,
+            events,
+            types,
+        })
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    pub fn program_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn summary(&self) -> IdlSummary {
+        IdlSummary {
+            program_id: self.program_id,
+            name: self.name.clone(),
+            accounts: self.accounts.iter().map(|account| account.name.clone()).collect(),
+            instructions: self.instructions.iter().map(|instruction| instruction.name.clone()).collect(),
+            events: self.events.iter().map(|event| event.name.clone()).collect(),
+        }
+    }
+
+    fn decode_type(&self, ty: &IdlType, reader: &mut Reader<'_>, depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        Some(match ty {
+            IdlType::Bool => json!(reader.array::<1>()?[0] != 0),
+            IdlType::U8 => json!(reader.array::<1>()?[0]),
+            IdlType::I8 => json!(i8::from_le_bytes(reader.array()?)),
+            IdlType::U16 => json!(u16::from_le_bytes(reader.array()?)),
+            IdlType::I16 => json!(i16::from_le_bytes(reader.array()?)),
+            IdlType::U32 => json!(u32::from_le_bytes(reader.array()?)),
+            IdlType::I32 => json!(i32::from_le_bytes(reader.array()?)),
+            IdlType::U64 => json!(u64::from_le_bytes(reader.array()?)),
+            IdlType::I64 => json!(i64::from_le_bytes(reader.array()?)),
+            IdlType::U128 => json!(u128::from_le_bytes(reader.array()?).to_string()),
+            IdlType::I128 => json!(i128::from_le_bytes(reader.array()?).to_string()),
+            IdlType::F32 => json!(f32::from_le_bytes(reader.array()?)),
+            IdlType::F64 => json!(f64::from_le_bytes(reader.array()?)),
+            IdlType::String => {
+                let len = reader.length_prefix()?;
+                json!(String::from_utf8_lossy(reader.take(len)?))
+            }
+            IdlType::Bytes => {
+                let len = reader.length_prefix()?;
+                json!(hex::encode(reader.take(len)?))
+            }
+            IdlType::Pubkey => json!(Pubkey::new_from_array(reader.array()?).to_string()),
+            IdlType::Vec(inner) => {
+                let len = reader.length_prefix()?;
+                // Every element takes at least one byte, so a bogus length fails fast
+                if len > reader.data.len() {
+                    return None;
+                }
+                Value::Array((0..len).map(|_| self.decode_type(inner, reader, depth + 1)).collect::<Option<_>>()?)
+            }
+            IdlType::Option(inner) => match reader.array::<1>()?[0] {
+                0 => Value::Null,
+                _ => self.decode_type(inner, reader, depth + 1)?,
+            },
+            IdlType::COption(inner) => match u32::from_le_bytes(reader.array()?) {
+                0 => Value::Null,
+                _ => self.decode_type(inner, reader, depth + 1)?,
+            },
+            IdlType::Array(inner, len) => {
+                Value::Array((0..*len).map(|_| self.decode_type(inner, reader, depth + 1)).collect::<Option<_>>()?)
+            }
+            IdlType::Defined(name) => self.decode_defined(name, reader, depth + 1)?,
+        })
+    }
+
+    fn decode_fields(&self, fields: &IdlFields, reader: &mut Reader<'_>, depth: usize) -> Option<Value> {
+        match fields {
+            IdlFields::Named(fields) => {
+                let mut object = Map::new();
+                for (name, ty) in fields {
+                    object.insert(name.clone(), self.decode_type(ty, reader, depth)?);
+                }
+                Some(Value::Object(object))
+            }
+            IdlFields::Tuple(types) => {
+                Some(Value::Array(types.iter().map(|ty| self.decode_type(ty, reader, depth)).collect::<Option<_>>()?))
+            }
+            IdlFields::Unit => Some(Value::Null),
+        }
+    }
+
+    fn decode_defined(&self, name: &str, reader: &mut Reader<'_>, depth: usize) -> Option<Value> {
+        match self.types.get(name)? {
+            IdlTypeDef::Struct(fields) => self.decode_fields(fields, reader, depth),
+            IdlTypeDef::Enum(variants) => {
+                let (variant, fields) = variants.get(reader.array::<1>()?[0] as usize)?;
+                match fields {
+                    IdlFields::Unit => Some(json!(variant)),
+                    fields => {
+                        let mut object = Map::new();
+                        object.insert(variant.clone(), self.decode_fields(fields, reader, depth)?);
+                        Some(Value::Object(object))
+                    }
+                }
+            }
+            IdlTypeDef::Alias(ty) => self.decode_type(ty, reader, depth),
+        }
+    }
+}
+
+impl ProgramDecoder for AnchorDecoder {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn program_ids(&self) -> Vec<Pubkey> {
+        vec![self.program_id]
+    }
+
+    fn decode_account(&self, _owner: &Pubkey, data: &[u8]) -> Option<DecodedAccount> {
+        let prefix = data.get(..DISCRIMINATOR_LEN)?;
+        let account = self.accounts.iter().find(|account| account.discriminator == prefix)?;
+        let mut reader = Reader { data: &data[DISCRIMINATOR_LEN..] };
+
+        // Accounts are usually allocated larger than their layout, so trailing bytes are fine
+        Some(DecodedAccount {
+            account_type: account.name.clone(),
+            parsed: self.decode_defined(&account.name, &mut reader, 0)?,
+        })
+    }
+
+    fn decode_instruction(&self, _program_id: &Pubkey, data: &[u8]) -> Option<DecodedInstruction> {
+        let prefix = data.get(..DISCRIMINATOR_LEN)?;
+        let instruction = self.instructions.iter().find(|instruction| instruction.discriminator == prefix)?;
+        let mut reader = Reader { data: &data[DISCRIMINATOR_LEN..] };
+
+        let mut args = Map::new();
+        for (name, ty) in &instruction.args {
+            args.insert(name.clone(), self.decode_type(ty, &mut reader, 0)?);
+        }
+
+        Some(DecodedInstruction {
+            name: instruction.name.clone(),
+            args: Value::Object(args),
+            account_names: instruction.accounts.clone(),
+        })
+    }
+
+    fn decode_event(&self, _program_id: &Pubkey, data: &[u8]) -> Option<DecodedEvent> {
+        let prefix = data.get(..DISCRIMINATOR_LEN)?;
+        let event = self.events.iter().find(|event| event.discriminator == prefix)?;
+        let mut reader = Reader { data: &data[DISCRIMINATOR_LEN..] };
+
+        Some(DecodedEvent {
+            name: event.name.clone(),
+            fields: self.decode_defined(&event.name, &mut reader, 0)?,
+        })
+    }
+}
+
+/// Summary of a loaded IDL
+#[derive(Debug, Clone, Serialize)]
+pub struct IdlSummary {
+    pub program_id: Pubkey,
+    pub name: String,
+    pub accounts: Vec<String>,
+    pub instructions: Vec<String>,
+    pub events: Vec<String>,
+}
+
+/// Loads Anchor IDLs and keeps their decoders registered
+pub struct IdlRegistry {
+    decoders: Arc<DecoderRegistry>,
+    loaded: RwLock<HashMap<Pubkey, Arc<AnchorDecoder>>>,
+    directory: RwLock<Option<PathBuf>>,
+}
+
+impl IdlRegistry {
+    pub fn new(decoders: Arc<DecoderRegistry>) -> Self {
+        Self {
+            decoders,
+            loaded: RwLock::new(HashMap::new()),
+            directory: RwLock::new(None),
+        }
+    }
+
+    /// Registry the loaded decoders are kept in
+    pub fn decoders(&self) -> &Arc<DecoderRegistry> {
+        &self.decoders
+    }
+
+    /// Load or replace the IDL of a program
+    pub fn load(&self, idl: &Value, program_id: Option<Pubkey>) -> Result<IdlSummary> {
+        let decoder = Arc::new(AnchorDecoder::from_idl(idl, program_id)?);
+        let summary = decoder.summary();
+
+        self.decoders.register(decoder.clone());
+        self.loaded
+            .write()
+            .map_err(|_| anyhow!("IDL registry lock poisoned"))?
+            .insert(summary.program_id, decoder);
+        Ok(summary)
+    }
+
+    /// Load every `*.json` IDL in a directory. A file named after a program
+    /// ID is registered for that program even if the IDL declares no address.
+    /// The directory is remembered for [`reload`](Self::reload).
+    pub fn load_from_dir(&self, path: impl AsRef<Path>) -> Result<Vec<IdlSummary>> {
+        let path = path.as_ref();
+        let mut loaded = Vec::new();
+
+        for entry in fs::read_dir(path).with_context(|| format!("Failed to read IDL directory {}", path.display()))? {
+            let file = entry?.path();
+            if file.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let program_id = file.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| Pubkey::from_str(stem).ok());
+            let result = fs::read_to_string(&file)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| Ok(serde_json::from_str::<Value>(&contents)?))
+                .and_then(|idl| self.load(&idl, program_id));
+
+            // One bad IDL should not keep the others from loading
+            match result {
+                Ok(summary) => loaded.push(summary),
+                Err(e) => warn!("Skipping IDL {}: {:#}", file.display(), e),
+            }
+        }
+
+        if let Ok(mut directory) = self.directory.write() {
+            *directory = Some(path.to_path_buf());
+        }
+        info!("Loaded {} Anchor IDLs from {}", loaded.len(), path.display());
+        Ok(loaded)
+    }
+
+    /// Load the last directory passed to [`load_from_dir`](Self::load_from_dir) again
+    pub fn reload(&self) -> Result<Vec<IdlSummary>> {
+        let directory = self.directory.read().ok().and_then(|directory| directory.clone());
+        match directory {
+            Some(directory) => self.load_from_dir(directory),
+            None => Err(anyhow!("No IDL directory has been loaded")),
+        }
+    }
+
+    /// Unregister the IDL of a program
+    pub fn remove(&self, program_id: &Pubkey) -> bool {
+        let removed = self.loaded.write().ok().and_then(|mut loaded| loaded.remove(program_id)).is_some();
+        if removed {
+            self.decoders.unregister(program_id);
+        }
+        removed
+    }
+
+    pub fn list(&self) -> Vec<IdlSummary> {
+        let Ok(loaded) = self.loaded.read() else {
+            return Vec::new();
+        };
+
+        let mut summaries: Vec<_> = loaded.values().map(|decoder| decoder.summary()).collect();
+        summaries.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_idl_decodes_accounts_instructions_and_events() {
+        let program_id = Pubkey::new_unique();
+        let idl = json!({
+            "version": "0.1.0",
+            "name": "vault",
+            "instructions": [{
+                "name": "depositFunds",
+                "accounts": [{ "name": "vault", "isMut": true, "isSigner": false }],
+                "args": [{ "name": "amount", "type": "u64" }]
+            }],
+            "events": [{
+                "name": "Deposited",
+                "fields": [{ "name": "amount", "type": "u64", "index": false }]
+            }],
+            "accounts": [{
+                "name": "Vault",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "authority", "type": "publicKey" },
+                        { "name": "state", "type": { "defined": "State" } },
+                        { "name": "label", "type": { "option": "string" } }
+                    ]
+                }
+            }],
+            "types": [{
+                "name": "State",
+                "type": { "kind": "enum", "variants": [{ "name": "Open" }, { "name": "Locked" }] }
+            }]
+        });
+        let decoder = AnchorDecoder::from_idl(&idl, Some(program_id)).unwrap();
+
+        let authority = Pubkey::new_unique();
+        let mut data = discriminator(&Value::Null, "account", "Vault").unwrap().to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(&[1, 1, 2, 0, 0, 0]);
+        data.extend_from_slice(b"hi");
+        let account = decoder.decode_account(&program_id, &data).unwrap();
+        assert_eq!(account.account_type, "Vault");
+        assert_eq!(account.parsed, json!({ "authority": authority.to_string(), "state": "Locked", "label": "hi" }));
+
+        let mut data = discriminator(&Value::Null, "global", "deposit_funds").unwrap().to_vec();
+        data.extend_from_slice(&42u64.to_le_bytes());
+        let instruction = decoder.decode_instruction(&program_id, &data).unwrap();
+        assert_eq!(instruction.name, "depositFunds");
+        assert_eq!(instruction.args["amount"], 42);
+        assert_eq!(instruction.account_names, vec!["vault".to_string()]);
+
+        let mut data = discriminator(&Value::Null, "event", "Deposited").unwrap().to_vec();
+        data.extend_from_slice(&42u64.to_le_bytes());
+        let event = decoder.decode_event(&program_id, &data).unwrap();
+        assert_eq!((event.name.as_str(), event.fields), ("Deposited", json!({ "amount": 42 })));
+    }
+}
@@ -0,0 +1,182 @@
+//! Program events
+//!
+//! [`DecoderRegistry::decode_events`] turns the logs of a transaction into
+//! [`ProgramEvent`]s; the [`EventIndex`] keeps the most recent ones written.
+
+use {
+    super::DecoderRegistry,
+    crate::index::DerivedIndex,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{
+        collections::VecDeque,
+        sync::{Arc, RwLock},
+    },
+    windexer_common::types::{AccountData, TransactionData},
+};
+
+/// Default number of events kept
+pub const DEFAULT_EVENT_CAPACITY: usize = 100_000;
+
+/// Name of the event a program invocation starts with
+pub const INVOKE: &str = "invoke";
+/// Name of the event a successful invocation ends with
+pub const SUCCESS: &str = "success";
+/// Name of the event a failed invocation ends with, carrying the error
+pub const FAILURE: &str = "failure";
+/// Name of logged data no decoder recognized, carrying its base64 chunks
+pub const DATA: &str = "data";
+
+/// An event found in the logs of a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramEvent {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Line of the transaction's logs the event was read from
+    pub log_index: usize,
+    pub program_id: Pubkey,
+    /// Invocation depth, 1 for top-level instructions
+    pub depth: u32,
+    /// [`INVOKE`], [`SUCCESS`], [`FAILURE`], [`DATA`] or the name of a
+    /// decoded event
+    pub name: String,
+    /// Decoder of a decoded event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoder: Option<String>,
+    /// Fields of a decoded event, the error of a failure or the chunks of
+    /// undecoded data
+    pub data: Value,
+}
+
+impl ProgramEvent {
+    /// Whether the event is from `program_id`, if given, and named `name`,
+    /// if given
+    pub fn matches(&self, program_id: Option<&Pubkey>, name: Option<&str>) -> bool {
+        program_id.map_or(true, |program_id| self.program_id == *program_id)
+            && name.map_or(true, |name| self.name == name)
+    }
+}
+
+/// Events of the most recently written transactions, newest first
+pub struct EventIndex {
+    registry: Arc<DecoderRegistry>,
+    capacity: usize,
+    events: RwLock<VecDeque<ProgramEvent>>,
+}
+
+impl EventIndex {
+    /// Keep up to `capacity` events, decoding data with the decoders in
+    /// `registry`
+    pub fn new(registry: Arc<DecoderRegistry>, capacity: usize) -> Self {
+        Self {
+            registry,
+            capacity: capacity.max(1),
+            events: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// The latest events matching `program_id` and `name`, newest first
+    pub fn query(&self, program_id: Option<&Pubkey>, name: Option<&str>, limit: usize) -> Vec<ProgramEvent> {
+        let Ok(events) = self.events.read() else {
+            return Vec::new();
+        };
+        events.iter().filter(|event| event.matches(program_id, name)).take(limit).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.read().map(|events| events.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DerivedIndex for EventIndex {
+    fn name(&self) -> &'static str {
+        "events"
+    }
+
+    fn apply_account(&self, _account: &AccountData) {}
+
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        let decoded = self.registry.decode_events(transaction);
+        if decoded.is_empty() {
+            return;
+        }
+        let Ok(mut events) = self.events.write() else {
+            return;
+        };
+        for event in decoded {
+            events.push_front(event);
+        }
+        events.truncate(self.capacity);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::decoders::AnchorDecoder,
+        serde_json::json,
+        sha2::{Digest, Sha256},
+        windexer_common::utils::SerializableTransactionMeta,
+    };
+
+    fn transaction(logs: Vec<String>, status: u64) -> TransactionData {
+        TransactionData {
+            signature: Signature::new_unique(),
+            slot: 7,
+            is_vote: false,
+            message: Default::default(),
+            signatures: Vec::new(),
+            meta: Default::default(),
+            serializable_meta: SerializableTransactionMeta {
+                status: Some(status),
+                fee: 5000,
+                pre_balances: Vec::new(),
+                post_balances: Vec::new(),
+                inner_instructions: None,
+                log_messages: Some(logs),
+                pre_token_balances: None,
+                post_token_balances: None,
+                rewards: None,
+            },
+            index: 0,
+            instructions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn indexes_framing_and_decoded_anchor_events() {
+        let program_id = Pubkey::new_unique();
+        let idl = json!({
+            "name": "vault",
+            "instructions": [],
+            "events": [{ "name": "Deposited", "fields": [{ "name": "amount", "type": "u64", "index": false }] }]
+        });
+        let registry = Arc::new(DecoderRegistry::new());
+        registry.register(Arc::new(AnchorDecoder::from_idl(&idl, Some(program_id)).unwrap()));
+
+        let mut event = Sha256::digest(b"event:Deposited")[..8].to_vec();
+        event.extend_from_slice(&42u64.to_le_bytes());
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            format!("Program data: {}", base64::encode(&event)),
+            format!("Program {} success", program_id),
+        ];
+
+        let index = EventIndex::new(registry, 10);
+        index.apply_transaction(&transaction(logs.clone(), 0));
+        let names: Vec<_> = index.query(Some(&program_id), None, 10).into_iter().map(|event| event.name).collect();
+        assert_eq!(names, vec![SUCCESS, "Deposited", INVOKE]);
+        let deposited = index.query(None, Some("Deposited"), 10);
+        assert_eq!((deposited[0].depth, &deposited[0].data), (1, &json!({ "amount": 42 })));
+
+        // Data logged by a failed transaction was rolled back
+        index.apply_transaction(&transaction(logs, 1));
+        assert_eq!(index.len(), 5);
+    }
+}
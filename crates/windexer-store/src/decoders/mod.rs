@@ -0,0 +1,377 @@
+//! Program account decoders
+//!
+//! A [`ProgramDecoder`] turns the raw data of accounts owned by one or more
+//! programs into JSON. Decoders are registered by program ID in a
+//! [`DecoderRegistry`]; the [`DecodedAccountIndex`] runs every account written
+//! through an [`IndexedStore`](crate::index::IndexedStore) past the registry
+//! and keeps the latest decoded form of each account, served by
+//! [`Storage::get_decoded_account`](crate::traits::Storage::get_decoded_account).
+//!
+//! Top-level instructions of written transactions are decoded the same way
+//! into a bounded [`DecodedInstructionIndex`], served by
+//! [`Storage::get_decoded_instructions`](crate::traits::Storage::get_decoded_instructions).
+//! Program logs become [`ProgramEvent`]s: the start and end of every
+//! invocation, and the `Program data:` events decoders recognize, kept in a
+//! bounded [`EventIndex`] served by
+//! [`Storage::get_events`](crate::traits::Storage::get_events).
+//!
+//! The SPL Token and SPL account-compression decoders are built in. Custom
+//! decoders are added with [`DecoderRegistry::register`] before or after the
+//! store is created; [`IdlRegistry`] generates them from Anchor IDLs.
+
+pub mod account_compression;
+pub mod anchor;
+pub mod events;
+pub mod spl_token;
+
+pub use {
+    account_compression::AccountCompressionDecoder,
+    anchor::{AnchorDecoder, IdlRegistry, IdlSummary},
+    events::{EventIndex, ProgramEvent, DEFAULT_EVENT_CAPACITY},
+    spl_token::SplTokenDecoder,
+};
+
+use {
+    crate::index::DerivedIndex,
+    lru::LruCache,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{
+        collections::HashMap,
+        num::NonZeroUsize,
+        sync::{Arc, Mutex, RwLock},
+    },
+    tracing::info,
+    windexer_common::{
+        logs::{parse_logs, LogKind},
+        types::{AccountData, TransactionData},
+    },
+};
+
+/// Default number of transactions whose decoded instructions are kept
+pub const DEFAULT_INSTRUCTION_CAPACITY: usize = 100_000;
+
+/// Output of a decoder for one account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedAccount {
+    /// Program-defined account type, such as `Mint`
+    pub account_type: String,
+    /// Decoded fields
+    pub parsed: serde_json::Value,
+}
+
+/// A decoded account as held by the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedAccount {
+    pub pubkey: Pubkey,
+    pub program_id: Pubkey,
+    /// Name of the decoder that produced it
+    pub decoder: String,
+    pub slot: u64,
+    #[serde(flatten)]
+    pub decoded: DecodedAccount,
+}
+
+/// Output of a decoder for one instruction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedInstruction {
+    /// Instruction name
+    pub name: String,
+    /// Decoded arguments
+    pub args: serde_json::Value,
+    /// Names of the instruction accounts in order, if the decoder knows them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub account_names: Vec<String>,
+}
+
+/// Output of a decoder for one event logged with `sol_log_data`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecodedEvent {
+    /// Event name
+    pub name: String,
+    /// Decoded fields
+    pub fields: serde_json::Value,
+}
+
+/// A decoded top-level instruction of a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedInstruction {
+    pub signature: Signature,
+    pub slot: u64,
+    /// Position of the instruction in the transaction message
+    pub index: usize,
+    pub program_id: Pubkey,
+    pub decoder: String,
+    pub accounts: Vec<Pubkey>,
+    #[serde(flatten)]
+    pub decoded: DecodedInstruction,
+}
+
+/// Decodes accounts owned by specific programs
+pub trait ProgramDecoder: Send + Sync + 'static {
+    /// Name used in logs and in decoded output
+    fn name(&self) -> &str;
+
+    /// Programs whose accounts this decoder understands
+    fn program_ids(&self) -> Vec<Pubkey>;
+
+    /// Decode the data of an account owned by `owner`; `None` if the data is
+    /// not recognized
+    fn decode_account(&self, owner: &Pubkey, data: &[u8]) -> Option<DecodedAccount>;
+
+    /// Decode instruction data sent to `program_id`; decoders that only
+    /// understand accounts keep the default
+    fn decode_instruction(&self, program_id: &Pubkey, data: &[u8]) -> Option<DecodedInstruction> {
+        let _ = (program_id, data);
+        None
+    }
+
+    /// Decode a data chunk `program_id` logged with `sol_log_data`; decoders
+    /// of programs that emit no events keep the default
+    fn decode_event(&self, program_id: &Pubkey, data: &[u8]) -> Option<DecodedEvent> {
+        let _ = (program_id, data);
+        None
+    }
+}
+
+/// Program ID to decoder mapping
+#[derive(Default)]
+pub struct DecoderRegistry {
+    decoders: RwLock<HashMap<Pubkey, Arc<dyn ProgramDecoder>>>,
+}
+
+impl DecoderRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in decoders
+    pub fn with_builtin() -> Self {
+        let registry = Self::new();
+        registry.register(Arc::new(SplTokenDecoder));
+        registry.register(Arc::new(AccountCompressionDecoder));
+        registry
+    }
+
+    /// Register a decoder for each of its programs, replacing any decoder
+    /// previously registered for them
+    pub fn register(&self, decoder: Arc<dyn ProgramDecoder>) {
+        let Ok(mut decoders) = self.decoders.write() else {
+            return;
+        };
+        for program_id in decoder.program_ids() {
+            info!("Registered {} decoder for program {}", decoder.name(), program_id);
+            decoders.insert(program_id, decoder.clone());
+        }
+    }
+
+    /// Remove the decoder registered for a program
+    pub fn unregister(&self, program_id: &Pubkey) -> Option<Arc<dyn ProgramDecoder>> {
+        self.decoders.write().ok()?.remove(program_id)
+    }
+
+    pub fn get(&self, program_id: &Pubkey) -> Option<Arc<dyn ProgramDecoder>> {
+        self.decoders.read().ok()?.get(program_id).cloned()
+    }
+
+    /// Programs with a registered decoder
+    pub fn program_ids(&self) -> Vec<Pubkey> {
+        self.decoders.read().map(|decoders| decoders.keys().copied().collect()).unwrap_or_default()
+    }
+
+    /// Decode an account with the decoder registered for its owner
+    pub fn decode(&self, account: &AccountData) -> Option<ParsedAccount> {
+        let decoder = self.get(&account.owner)?;
+        let decoded = decoder.decode_account(&account.owner, &account.data)?;
+
+        Some(ParsedAccount {
+            pubkey: account.pubkey,
+            program_id: account.owner,
+            decoder: decoder.name().to_string(),
+            slot: account.slot,
+            decoded,
+        })
+    }
+
+    /// Decode the top-level instructions of a transaction that have a
+    /// registered decoder
+    pub fn decode_transaction(&self, transaction: &TransactionData) -> Vec<ParsedInstruction> {
+        let keys = &transaction.message.account_keys;
+
+        transaction.message.instructions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, instruction)| {
+                let program_id = *keys.get(instruction.program_id_index as usize)?;
+                let decoder = self.get(&program_id)?;
+                let decoded = decoder.decode_instruction(&program_id, &instruction.data)?;
+
+                Some(ParsedInstruction {
+                    signature: transaction.signature,
+                    slot: transaction.slot,
+                    index,
+                    program_id,
+                    decoder: decoder.name().to_string(),
+                    accounts: instruction.accounts.iter().filter_map(|i| keys.get(*i as usize).copied()).collect(),
+                    decoded,
+                })
+            })
+            .collect()
+    }
+
+    /// Events in the logs of a transaction: the start and end of every
+    /// invocation and, if the transaction succeeded, the data each program
+    /// logged, decoded when its decoder recognizes the first chunk
+    pub fn decode_events(&self, transaction: &TransactionData) -> Vec<ProgramEvent> {
+        let Some(logs) = &transaction.serializable_meta.log_messages else {
+            return Vec::new();
+        };
+        let succeeded = transaction.serializable_meta.status == Some(0);
+        let mut events = Vec::new();
+
+        for log in parse_logs(logs) {
+            let Some(program_id) = log.program_id else {
+                continue;
+            };
+            let (name, decoder, data) = match log.kind {
+                LogKind::Invoke => (events::INVOKE.to_string(), None, Value::Null),
+                LogKind::Success => (events::SUCCESS.to_string(), None, Value::Null),
+                LogKind::Failure(error) => (events::FAILURE.to_string(), None, Value::String(error)),
+                // Events of failed transactions were never committed
+                LogKind::Data(chunks) if succeeded => {
+                    let decoded = self.get(&program_id).and_then(|decoder| {
+                        let event = decoder.decode_event(&program_id, chunks.first()?)?;
+                        Some((decoder.name().to_string(), event))
+                    });
+                    match decoded {
+                        Some((decoder, event)) => (event.name, Some(decoder), event.fields),
+                        None => {
+                            let chunks = chunks.iter().map(|chunk| Value::String(base64::encode(chunk))).collect();
+                            (events::DATA.to_string(), None, Value::Array(chunks))
+                        }
+                    }
+                }
+                _ => continue,
+            };
+
+            events.push(ProgramEvent {
+                signature: transaction.signature,
+                slot: transaction.slot,
+                log_index: log.line,
+                program_id,
+                depth: log.depth,
+                name,
+                decoder,
+                data,
+            });
+        }
+        events
+    }
+}
+
+/// Latest decoded form of every account with a registered decoder
+pub struct DecodedAccountIndex {
+    registry: Arc<DecoderRegistry>,
+    accounts: RwLock<HashMap<Pubkey, (ParsedAccount, (u64, u64))>>,
+}
+
+impl DecodedAccountIndex {
+    pub fn new(registry: Arc<DecoderRegistry>) -> Self {
+        Self {
+            registry,
+            accounts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn registry(&self) -> &Arc<DecoderRegistry> {
+        &self.registry
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<ParsedAccount> {
+        self.accounts.read().ok()?.get(pubkey).map(|(parsed, _)| parsed.clone())
+    }
+
+    /// Number of accounts currently decoded
+    pub fn len(&self) -> usize {
+        self.accounts.read().map(|accounts| accounts.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DerivedIndex for DecodedAccountIndex {
+    fn name(&self) -> &'static str {
+        "decoded_accounts"
+    }
+
+    fn apply_account(&self, account: &AccountData) {
+        let version = (account.slot, account.write_version);
+        let parsed = match account.lamports {
+            0 => None,
+            _ => self.registry.decode(account),
+        };
+
+        let Ok(mut accounts) = self.accounts.write() else {
+            return;
+        };
+        if accounts.get(&account.pubkey).is_some_and(|(_, current)| *current > version) {
+            return;
+        }
+
+        // Closed accounts and data the decoder no longer recognizes drop out
+        match parsed {
+            Some(parsed) => {
+                accounts.insert(account.pubkey, (parsed, version));
+            }
+            None => {
+                accounts.remove(&account.pubkey);
+            }
+        }
+    }
+}
+
+/// Decoded instructions of the most recently written transactions
+pub struct DecodedInstructionIndex {
+    registry: Arc<DecoderRegistry>,
+    transactions: Mutex<LruCache<Signature, Vec<ParsedInstruction>>>,
+}
+
+impl DecodedInstructionIndex {
+    /// Keep the decoded instructions of up to `capacity` transactions
+    pub fn new(registry: Arc<DecoderRegistry>, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            registry,
+            transactions: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    pub fn get(&self, signature: &Signature) -> Vec<ParsedInstruction> {
+        self.transactions
+            .lock()
+            .ok()
+            .and_then(|mut transactions| transactions.get(signature).cloned())
+            .unwrap_or_default()
+    }
+}
+
+impl DerivedIndex for DecodedInstructionIndex {
+    fn name(&self) -> &'static str {
+        "decoded_instructions"
+    }
+
+    fn apply_account(&self, _account: &AccountData) {}
+
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        let instructions = self.registry.decode_transaction(transaction);
+        if instructions.is_empty() {
+            return;
+        }
+        if let Ok(mut transactions) = self.transactions.lock() {
+            transactions.put(transaction.signature, instructions);
+        }
+    }
+}
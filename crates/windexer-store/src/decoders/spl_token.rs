@@ -0,0 +1,76 @@
+//! Built-in SPL Token and Token-2022 decoder
+
+use {
+    super::{DecodedAccount, ProgramDecoder},
+    crate::index::token::{TokenProgramAccount, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID},
+    serde_json::json,
+    solana_sdk::pubkey::Pubkey,
+};
+
+/// Decodes token accounts and mints of both token programs
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SplTokenDecoder;
+
+impl ProgramDecoder for SplTokenDecoder {
+    fn name(&self) -> &str {
+        "spl-token"
+    }
+
+    fn program_ids(&self) -> Vec<Pubkey> {
+        vec![TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID]
+    }
+
+    fn decode_account(&self, owner: &Pubkey, data: &[u8]) -> Option<DecodedAccount> {
+        match TokenProgramAccount::parse_data(owner, data)? {
+            TokenProgramAccount::Account { mint, owner, amount } => Some(DecodedAccount {
+                account_type: "Account".to_string(),
+                parsed: json!({
+                    "mint": mint.to_string(),
+                    "owner": owner.to_string(),
+                    "amount": amount.to_string(),
+                }),
+            }),
+            TokenProgramAccount::Mint { decimals } => Some(DecodedAccount {
+                account_type: "Mint".to_string(),
+                parsed: json!({ "decimals": decimals }),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::decoders::{DecodedAccountIndex, DecoderRegistry},
+        crate::index::DerivedIndex,
+        std::sync::Arc,
+        windexer_common::types::AccountData,
+    };
+
+    #[test]
+    fn test_registry_decodes_mints() {
+        let index = DecodedAccountIndex::new(Arc::new(DecoderRegistry::with_builtin()));
+        let mut data = vec![0u8; 82];
+        data[44] = 6;
+        data[45] = 1;
+        let mint = AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1_461_600,
+            owner: TOKEN_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+            data,
+            write_version: 0,
+            slot: 1,
+            is_startup: false,
+            transaction_signature: None,
+        };
+
+        index.apply_account(&mint);
+        let parsed = index.get(&mint.pubkey).unwrap();
+        assert_eq!(parsed.decoder, "spl-token");
+        assert_eq!(parsed.decoded.account_type, "Mint");
+        assert_eq!(parsed.decoded.parsed["decimals"], 6);
+    }
+}
@@ -0,0 +1,486 @@
+//! Write deduplication
+//!
+//! When several ingestion sources (the Geyser publisher, the P2P network,
+//! backfill jobs) feed the same store, identical updates arrive more than once.
+//! [`DedupStore`] drops them before they reach the backend using a bounded LRU
+//! of their [`IdempotencyKey`]s, blocks also by status so commitment
+//! transitions still get through.
+//!
+//! Keys are only remembered once the backend accepted the write, so a failed
+//! write can be retried. The window is best-effort; an update that falls out
+//! of it, or that arrives again while its first write is still in flight, is
+//! written again, which is harmless because every backend but Parquet upserts
+//! idempotently on the same keys.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::Result,
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    lru::LruCache,
+    std::{
+        collections::HashSet,
+        fmt::{Debug, Formatter, Result as FmtResult},
+        num::NonZeroUsize,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    },
+    windexer_common::types::{AccountData, ApiKeyRecord, IdempotencyKey, Idempotent, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// Key identifying a unique write
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DedupKey(IdempotencyKey, Option<&'static str>);
+
+impl DedupKey {
+    fn account(account: &AccountData) -> Self {
+        DedupKey(account.idempotency_key(), None)
+    }
+
+    fn transaction(transaction: &TransactionData) -> Self {
+        DedupKey(transaction.idempotency_key(), None)
+    }
+
+    fn block(block: &BlockData) -> Self {
+        DedupKey(block.idempotency_key(), Some(block.status.as_str()))
+    }
+}
+
+/// Deduplication metrics
+#[derive(Default)]
+pub struct DedupMetrics {
+    pub accounts_dropped: AtomicU64,
+    pub transactions_dropped: AtomicU64,
+    pub blocks_dropped: AtomicU64,
+    pub writes_passed: AtomicU64,
+}
+
+impl DedupMetrics {
+    /// Total number of duplicate writes dropped
+    pub fn duplicates_dropped(&self) -> u64 {
+        self.accounts_dropped.load(Ordering::Relaxed)
+            + self.transactions_dropped.load(Ordering::Relaxed)
+            + self.blocks_dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Debug for DedupMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("DedupMetrics")
+            .field("accounts_dropped", &self.accounts_dropped.load(Ordering::Relaxed))
+            .field("transactions_dropped", &self.transactions_dropped.load(Ordering::Relaxed))
+            .field("blocks_dropped", &self.blocks_dropped.load(Ordering::Relaxed))
+            .field("writes_passed", &self.writes_passed.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Drops duplicate writes before they reach the wrapped backend
+pub struct DedupStore {
+    inner: Arc<dyn Storage>,
+    seen: Mutex<LruCache<DedupKey, ()>>,
+    metrics: Arc<DedupMetrics>,
+}
+
+impl DedupStore {
+    /// Wrap a backend, remembering up to `capacity` recent writes
+    pub fn new(inner: Arc<dyn Storage>, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+
+        Self {
+            inner,
+            seen: Mutex::new(LruCache::new(capacity)),
+            metrics: Arc::new(DedupMetrics::default()),
+        }
+    }
+
+    /// Get the deduplication metrics
+    pub fn metrics(&self) -> Arc<DedupMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Whether `key` was written recently, counting the write as dropped if so
+    fn seen(&self, key: &DedupKey, dropped: &AtomicU64) -> bool {
+        let seen = match self.seen.lock() {
+            Ok(mut seen) => seen.get(key).is_some(),
+            // A poisoned window only costs us deduplication, never writes
+            Err(_) => false,
+        };
+        if seen {
+            dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        seen
+    }
+
+    /// The items of a batch not written recently, with their keys; repeats
+    /// within the batch are dropped as well
+    fn unseen<T>(&self, items: Vec<T>, key: impl Fn(&T) -> DedupKey, dropped: &AtomicU64) -> (Vec<T>, Vec<DedupKey>) {
+        let mut batch = HashSet::with_capacity(items.len());
+        let mut keys = Vec::with_capacity(items.len());
+        let items = items
+            .into_iter()
+            .filter(|item| {
+                let key = key(item);
+                if self.seen(&key, dropped) || !batch.insert(key.clone()) {
+                    return false;
+                }
+                keys.push(key);
+                true
+            })
+            .collect();
+        (items, keys)
+    }
+
+    /// Remember the keys of a write the backend accepted
+    fn remember(&self, keys: impl IntoIterator<Item = DedupKey>) {
+        let Ok(mut seen) = self.seen.lock() else {
+            return;
+        };
+        for key in keys {
+            seen.put(key, ());
+            self.metrics.writes_passed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Debug for DedupStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("DedupStore")
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for DedupStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        let key = DedupKey::account(&account);
+        if !self.seen(&key, &self.metrics.accounts_dropped) {
+            self.inner.store_account(account).await?;
+            self.remember([key]);
+        }
+        Ok(())
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        let key = DedupKey::transaction(&transaction);
+        if !self.seen(&key, &self.metrics.transactions_dropped) {
+            self.inner.store_transaction(transaction).await?;
+            self.remember([key]);
+        }
+        Ok(())
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        let key = DedupKey::block(&block);
+        if !self.seen(&key, &self.metrics.blocks_dropped) {
+            self.inner.store_block(block).await?;
+            self.remember([key]);
+        }
+        Ok(())
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        let (accounts, keys) = self.unseen(accounts, DedupKey::account, &self.metrics.accounts_dropped);
+        if accounts.is_empty() {
+            return Ok(());
+        }
+        self.inner.store_accounts(accounts).await?;
+        self.remember(keys);
+        Ok(())
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        let (transactions, keys) = self.unseen(transactions, DedupKey::transaction, &self.metrics.transactions_dropped);
+        if transactions.is_empty() {
+            return Ok(());
+        }
+        self.inner.store_transactions(transactions).await?;
+        self.remember(keys);
+        Ok(())
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        let (blocks, keys) = self.unseen(blocks, DedupKey::block, &self.metrics.blocks_dropped);
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        self.inner.store_blocks(blocks).await?;
+        self.remember(keys);
+        Ok(())
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        let SlotBundle { block, transactions, accounts } = bundle;
+        let (accounts, account_keys) = self.unseen(accounts, DedupKey::account, &self.metrics.accounts_dropped);
+        let (transactions, transaction_keys) =
+            self.unseen(transactions, DedupKey::transaction, &self.metrics.transactions_dropped);
+        let block_key = DedupKey::block(&block);
+        let block_is_new = !self.seen(&block_key, &self.metrics.blocks_dropped);
+
+        // A repeated block is still written with new contents so the bundle stays whole
+        if !block_is_new && accounts.is_empty() && transactions.is_empty() {
+            return Ok(());
+        }
+        self.inner.store_slot_bundle(SlotBundle { block, transactions, accounts }).await?;
+        self.remember(account_keys.into_iter().chain(transaction_keys).chain(block_is_new.then_some(block_key)));
+        Ok(())
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.inner.update_slot_status(status).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.inner.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.inner.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.inner.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.inner.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.inner.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.inner.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.inner.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.inner.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.inner.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.inner.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.inner.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.inner.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{memory_store::MemoryStore, testing::FailingStore},
+        solana_sdk::pubkey::Pubkey,
+    };
+
+    #[tokio::test]
+    async fn test_duplicate_account_updates_are_dropped() {
+        let store = DedupStore::new(Arc::new(MemoryStore::new()), 16);
+        let account = AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1_000_000,
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+            data: vec![],
+            write_version: 1,
+            slot: 10,
+            is_startup: false,
+            transaction_signature: None,
+        };
+
+        store.store_account(account.clone()).await.unwrap();
+        store.store_account(account.clone()).await.unwrap();
+        store.store_account(AccountData { write_version: 2, ..account }).await.unwrap();
+
+        let metrics = store.metrics();
+        assert_eq!(metrics.duplicates_dropped(), 1);
+        assert_eq!(metrics.writes_passed.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_are_not_remembered() {
+        let inner = Arc::new(FailingStore::default());
+        let store = DedupStore::new(inner.clone(), 16);
+        let block = BlockData { slot: 7, ..Default::default() };
+
+        inner.fail_writes(true);
+        assert!(store.store_blocks(vec![block.clone()]).await.is_err());
+        inner.fail_writes(false);
+        store.store_blocks(vec![block.clone(), block]).await.unwrap();
+
+        assert_eq!(store.metrics().duplicates_dropped(), 1);
+        assert!(inner.get_block(7).await.unwrap().is_some());
+    }
+}
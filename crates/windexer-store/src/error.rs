@@ -0,0 +1,73 @@
+//! Storage errors
+//!
+//! The [`Storage`](crate::traits::Storage) trait reports failures as
+//! `anyhow::Error` so backends can pass driver errors through. Failures a
+//! caller can act on are raised as a [`StorageError`] inside that error and
+//! recovered with [`StorageError::find`]; anything else is an internal error.
+
+use {
+    thiserror::Error,
+    windexer_common::errors::ErrorCode,
+};
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("{0} is not supported by this backend")]
+    Unsupported(String),
+
+    #[error("Invalid {0}")]
+    Invalid(String),
+
+    #[error("Write pipeline is full")]
+    Overloaded,
+
+    #[error("Storage unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl StorageError {
+    /// Error for a default [`Storage`](crate::traits::Storage) method the
+    /// backend does not override
+    pub fn unsupported(feature: impl Into<String>) -> anyhow::Error {
+        Self::Unsupported(feature.into()).into()
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::NotFound(_) => ErrorCode::NotFound,
+            Self::Unsupported(_) => ErrorCode::Unimplemented,
+            Self::Invalid(_) => ErrorCode::InvalidArgument,
+            Self::Overloaded => ErrorCode::ResourceExhausted,
+            Self::Unavailable(_) => ErrorCode::Unavailable,
+        }
+    }
+
+    /// The storage error carried by `error` or one of its causes
+    pub fn find(error: &anyhow::Error) -> Option<&StorageError> {
+        error.chain().find_map(|cause| cause.downcast_ref())
+    }
+
+    /// Class of any storage failure, internal unless it carries a
+    /// [`StorageError`]
+    pub fn code_of(error: &anyhow::Error) -> ErrorCode {
+        Self::find(error).map_or(ErrorCode::Internal, Self::code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, anyhow::Context};
+
+    #[test]
+    fn finds_the_cause_through_context() {
+        let error = Err::<(), _>(anyhow::Error::from(StorageError::Overloaded))
+            .context("Failed to store account")
+            .unwrap_err();
+        assert!(matches!(StorageError::find(&error), Some(StorageError::Overloaded)));
+        assert_eq!(StorageError::code_of(&error), ErrorCode::ResourceExhausted);
+        assert_eq!(StorageError::code_of(&anyhow::anyhow!("disk on fire")), ErrorCode::Internal);
+    }
+}
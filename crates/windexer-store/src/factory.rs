@@ -1,65 +1,220 @@
 use {
     crate::{
+        archive::{ArchivedStore, Archiver},
+        clickhouse_store::ClickHouseStore,
+        config::StoreConfig,
+        decoders::{DecoderRegistry, IdlRegistry},
+        dedup::DedupStore,
+        feed::FeedStore,
+        forks::ForkAwareStore,
+        index::{IndexedStore, DEFAULT_CPI_PROGRAMS},
+        internal::{Store as RocksDbStore, StoreConfig as RocksDbOptions},
+        memory_store::MemoryStore,
+        metrics::{InstrumentedStore, StoreMetrics},
         traits::{Storage, StorageFactory},
-        Store,
         parquet_store::ParquetStore,
+        pipeline::{BatchingStore, PipelineConfig},
         postgres_store::PostgresStore,
+        state::IndexerStateStore,
+        tenant::TenantStore,
+        tiered::TieredStore,
     },
-    anyhow::{Result, anyhow},
+    anyhow::{Context, Result},
     async_trait::async_trait,
+    futures::future::BoxFuture,
     std::sync::Arc,
-    windexer_geyser::config::{StorageConfig, StorageType},
+    tracing::info,
+    windexer_common::dlq::DeadLetterQueue,
+    windexer_common::feed::DataFeed,
+    windexer_common::config::storage::StorageConfig,
 };
 
 /// Factory for creating storage instances based on configuration
 pub struct WindexerStorageFactory {
-    config: StorageConfig,
+    config: StoreConfig,
+    metrics: Arc<StoreMetrics>,
+    decoders: Arc<DecoderRegistry>,
+    idls: Arc<IdlRegistry>,
+    feed: DataFeed,
+    dead_letters: Option<Arc<DeadLetterQueue>>,
 }
 
 impl WindexerStorageFactory {
-    pub fn new(config: StorageConfig) -> Self {
-        Self { config }
+    pub fn new(config: StoreConfig) -> Self {
+        let decoders = Arc::new(DecoderRegistry::with_builtin());
+        Self {
+            config,
+            metrics: Arc::new(StoreMetrics::new()),
+            idls: Arc::new(IdlRegistry::new(decoders.clone())),
+            decoders,
+            feed: DataFeed::default(),
+            dead_letters: None,
+        }
+    }
+
+    /// Get the metrics recorded by backends this factory creates; register
+    /// them with the process's `MetricsRegistry` to export them
+    pub fn metrics(&self) -> Arc<StoreMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Get the decoder registry used by indexed backends; register custom
+    /// program decoders here
+    pub fn decoders(&self) -> Arc<DecoderRegistry> {
+        self.decoders.clone()
+    }
+
+    /// Get the Anchor IDL registry feeding the decoder registry
+    pub fn idls(&self) -> Arc<IdlRegistry> {
+        self.idls.clone()
+    }
+
+    /// Get the feed every write to the created storage is published to
+    pub fn feed(&self) -> DataFeed {
+        self.feed.clone()
+    }
+
+    /// Keep the writes batching backends fail to flush in `queue`
+    pub fn with_dead_letters(mut self, queue: Arc<DeadLetterQueue>) -> Self {
+        self.dead_letters = Some(queue);
+        self
+    }
+
+    /// Create a factory from the storage section of the Geyser plugin config
+    pub fn from_geyser_config(config: &StorageConfig) -> Result<Self> {
+        Ok(Self::new(StoreConfig::from_geyser(config)?))
+    }
+
+    // Boxed so the tiered backend can recurse into its cold backend
+    fn create_backend<'a>(&'a self, config: &'a StoreConfig) -> BoxFuture<'a, Result<Arc<dyn Storage>>> {
+        let metrics = &self.metrics;
+        // Only concrete backends are instrumented so wrappers are not counted twice
+        let instrument = |store: Arc<dyn Storage>| -> Arc<dyn Storage> {
+            Arc::new(InstrumentedStore::new(store, config.backend_name(), metrics.clone()))
+        };
+
+        Box::pin(async move {
+            let storage: Arc<dyn Storage> = match config {
+                StoreConfig::Memory => instrument(Arc::new(MemoryStore::new())),
+                StoreConfig::Rocksdb(rocksdb) => {
+                    let store = RocksDbStore::open(RocksDbOptions {
+                        path: rocksdb.path.clone().into(),
+                        max_open_files: rocksdb.max_open_files,
+                        cache_capacity: rocksdb.cache_capacity,
+                        compression: rocksdb.account_data_compression.clone(),
+                    })
+                    .with_context(|| format!("Failed to open RocksDB at {}", rocksdb.path))?;
+                    metrics.watch_directory("rocksdb", &rocksdb.path);
+                    instrument(Arc::new(store))
+                },
+                StoreConfig::Parquet(parquet) => {
+                    let store = ParquetStore::new(parquet.clone()).await
+                        .with_context(|| format!("Failed to initialize Parquet store in {}", parquet.directory))?;
+                    metrics.watch_directory("parquet", &parquet.directory);
+                    instrument(Arc::new(store))
+                },
+                StoreConfig::Postgres(postgres) => {
+                    let store = PostgresStore::new(postgres.clone()).await
+                        .context("Failed to connect to PostgreSQL")?;
+                    instrument(Arc::new(store))
+                },
+                StoreConfig::Clickhouse(clickhouse) => {
+                    let store = ClickHouseStore::new(clickhouse.clone()).await
+                        .context("Failed to initialize ClickHouse store")?;
+                    // ClickHouse wants few large inserts rather than many small ones
+                    let pipeline = PipelineConfig {
+                        dead_letters: self.dead_letters.clone(),
+                        ..clickhouse.pipeline_config()
+                    };
+                    let batching = BatchingStore::new(instrument(Arc::new(store)), pipeline);
+                    metrics.watch_queue("clickhouse", batching.metrics());
+                    Arc::new(batching)
+                },
+                StoreConfig::Tiered(tiered) => {
+                    let cold = self.create_backend(&tiered.cold).await?;
+                    let mut config = tiered.tiered_config();
+                    config.pipeline.dead_letters = self.dead_letters.clone();
+                    let store = TieredStore::new(cold, config);
+                    metrics.watch_queue("tiered", store.pipeline_metrics());
+                    Arc::new(store)
+                },
+                StoreConfig::Dedup(dedup) => {
+                    let inner = self.create_backend(&dedup.inner).await?;
+                    Arc::new(DedupStore::new(inner, dedup.window))
+                },
+                StoreConfig::ForkAware(forks) => {
+                    let inner = self.create_backend(&forks.inner).await?;
+                    Arc::new(ForkAwareStore::new(inner, forks.fork_config()))
+                },
+                StoreConfig::Archived(archived) => {
+                    let parquet = archived.local.parquet_config()
+                        .context("archived backend needs a parquet local backend")?;
+                    let archiver = Archiver::new(archived.archive.clone(), parquet)
+                        .context("Failed to initialize archiver")?;
+                    let local = self.create_backend(&archived.local).await?;
+                    Arc::new(ArchivedStore::new(local, Arc::new(archiver)))
+                },
+                StoreConfig::Indexed(indexed) => {
+                    let inner = self.create_backend(&indexed.inner).await?;
+                    let mut store = IndexedStore::new(inner);
+                    if indexed.token_balances {
+                        store = store.with_token_index();
+                    }
+                    if indexed.nfts {
+                        store = store.with_nft_index();
+                    }
+                    if indexed.compressed_nfts {
+                        store = store.with_compression_index();
+                    }
+                    if indexed.lookup_tables {
+                        store = store.with_lookup_table_index();
+                    }
+                    if indexed.cpi_calls {
+                        store = store.with_cpi_index(DEFAULT_CPI_PROGRAMS, indexed.cpi_calls_per_program);
+                    }
+                    if indexed.search {
+                        store = store.with_search_index(indexed.search_capacity);
+                    }
+                    if let Some(directory) = &indexed.idl_directory {
+                        self.idls.load_from_dir(directory)?;
+                    }
+                    if indexed.decoded_accounts {
+                        store = store.with_decoders(self.decoders.clone());
+                    }
+                    if indexed.events {
+                        store = store.with_event_index(self.decoders.clone(), indexed.event_capacity);
+                    }
+                    if indexed.rebuild_on_start {
+                        store.rebuild().await.context("Failed to rebuild derived indexes")?;
+                    }
+                    Arc::new(store)
+                },
+                StoreConfig::Tenants(tenants) => {
+                    let inner = self.create_backend(&tenants.inner).await?;
+                    let mut store = TenantStore::new(inner);
+                    for tenant in &tenants.tenants {
+                        let storage = self.create_backend(&tenant.store_config(&tenants.inner)?).await
+                            .with_context(|| format!("Failed to create the store of tenant {}", tenant.id))?;
+                        store = store.with_tenant(&tenant.id, storage, &tenant.filter)?;
+                    }
+                    info!("Serving tenants {}", store.tenant_ids().collect::<Vec<_>>().join(", "));
+                    Arc::new(store)
+                },
+            };
+
+            Ok(storage)
+        })
     }
 }
 
 #[async_trait]
 impl StorageFactory for WindexerStorageFactory {
     async fn create_storage(&self) -> Result<Arc<dyn Storage>> {
-        match self.config.storage_type {
-            StorageType::RocksDB => {
-                let path = match &self.config.rocksdb_path {
-                    Some(path) => path.clone(),
-                    None => return Err(anyhow!("RocksDB path not configured")),
-                };
-                
-                let store_config = crate::StoreConfig {
-                    path: path.into(),
-                    max_open_files: 1000, // Default
-                    cache_capacity: 100 * 1024 * 1024, // 100 MB default
-                };
-                
-                let store = Store::open(store_config)?;
-                Ok(Arc::new(store))
-            },
-            StorageType::Parquet => {
-                let config = match &self.config.parquet {
-                    Some(config) => config.clone(),
-                    None => return Err(anyhow!("Parquet configuration not provided")),
-                };
-                
-                let store = ParquetStore::new(config).await?;
-                Ok(Arc::new(store))
-            },
-            StorageType::Postgres => {
-                let config = match &self.config.postgres {
-                    Some(config) => config.clone(),
-                    None => return Err(anyhow!("PostgreSQL configuration not provided")),
-                };
-                
-                let store = PostgresStore::new(config).await?;
-                Ok(Arc::new(store))
-            }
-        }
+        self.config.validate()?;
+        let storage = self.create_backend(&self.config).await?;
+        info!("Created {} storage backend", self.config.backend_name());
+        let storage = Arc::new(FeedStore::new(storage, self.feed.clone()).with_metrics(self.metrics.clone()));
+        Ok(Arc::new(IndexerStateStore::open(storage).await))
     }
 }
 
@@ -73,25 +228,25 @@ pub struct HotColdStorageFactory {
 
 impl HotColdStorageFactory {
     pub fn new(hot_config: StorageConfig, cold_config: Option<StorageConfig>) -> Self {
-        Self { 
+        Self {
             hot_config,
             cold_config,
         }
     }
-    
+
     pub async fn create_hot_storage(&self) -> Result<Arc<dyn Storage>> {
-        let factory = WindexerStorageFactory::new(self.hot_config.clone());
+        let factory = WindexerStorageFactory::from_geyser_config(&self.hot_config)?;
         factory.create_storage().await
     }
-    
+
     pub async fn create_cold_storage(&self) -> Result<Option<Arc<dyn Storage>>> {
         match &self.cold_config {
             Some(config) => {
-                let factory = WindexerStorageFactory::new(config.clone());
+                let factory = WindexerStorageFactory::from_geyser_config(config)?;
                 let storage = factory.create_storage().await?;
                 Ok(Some(storage))
             },
             None => Ok(None),
         }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,334 @@
+//! Live feed publishing
+//!
+//! [`FeedStore`] publishes every write to a [`DataFeed`] once the wrapped
+//! backend has accepted it, so streaming APIs serve exactly what the local
+//! store holds.
+//!
+//! It also audits the live slot status updates passing through it and
+//! publishes a [`SlotGap`](windexer_common::utils::SlotGap) event for every
+//! hole in the rooted chain, which gap backfills pick up to fetch the missing
+//! slots. Bundles are not audited, since backfills write them out of order.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        metrics::StoreMetrics,
+        traits::{SlotBundle, Storage},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    anyhow::Result,
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        sync::{Arc, Mutex},
+    },
+    tracing::warn,
+    windexer_common::{
+        feed::DataFeed,
+        types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+        utils::SlotAudit,
+    },
+};
+
+/// Publishes successful writes of the wrapped backend to a feed
+pub struct FeedStore {
+    inner: Arc<dyn Storage>,
+    feed: DataFeed,
+    audit: Mutex<SlotAudit>,
+    metrics: Option<Arc<StoreMetrics>>,
+}
+
+impl FeedStore {
+    pub fn new(inner: Arc<dyn Storage>, feed: DataFeed) -> Self {
+        Self {
+            inner,
+            feed,
+            audit: Mutex::new(SlotAudit::default()),
+            metrics: None,
+        }
+    }
+
+    /// Count missed slots in these metrics
+    pub fn with_metrics(mut self, metrics: Arc<StoreMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    fn audit(&self, slot: u64, parent: Option<u64>, status: &SlotStatus) {
+        let Some(gap) = self.audit.lock().unwrap().observe(slot, parent, status) else {
+            return;
+        };
+        warn!("Rooted slots {}..={} were never ingested", gap.first_slot, gap.last_slot);
+        if let Some(metrics) = &self.metrics {
+            metrics.record_gap(&gap);
+        }
+        self.feed.publish_slot_gap(gap);
+    }
+
+    pub fn feed(&self) -> &DataFeed {
+        &self.feed
+    }
+}
+
+impl Debug for FeedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("FeedStore")
+            .field("subscribers", &self.feed.subscriber_count())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for FeedStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.inner.store_account(account.clone()).await?;
+        self.feed.publish_account(account);
+        Ok(())
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.inner.store_transaction(transaction.clone()).await?;
+        self.feed.publish_transaction(transaction);
+        Ok(())
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.inner.store_block(block.clone()).await?;
+        self.feed.publish_block(block);
+        Ok(())
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        self.inner.store_accounts(accounts.clone()).await?;
+        accounts.into_iter().for_each(|account| self.feed.publish_account(account));
+        Ok(())
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        self.inner.store_transactions(transactions.clone()).await?;
+        transactions.into_iter().for_each(|transaction| self.feed.publish_transaction(transaction));
+        Ok(())
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        self.inner.store_blocks(blocks.clone()).await?;
+        blocks.into_iter().for_each(|block| self.feed.publish_block(block));
+        Ok(())
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        self.inner.store_slot_bundle(bundle.clone()).await?;
+        let SlotBundle { block, transactions, accounts } = bundle;
+        accounts.into_iter().for_each(|account| self.feed.publish_account(account));
+        transactions.into_iter().for_each(|transaction| self.feed.publish_transaction(transaction));
+        self.feed.publish_block(block);
+        Ok(())
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.inner.update_slot_status(status.clone()).await?;
+        self.audit(status.slot, status.parent, &status.status);
+        self.feed.publish_slot_status(status);
+        Ok(())
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.inner.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.inner.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.inner.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.inner.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.inner.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.inner.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.inner.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.inner.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.inner.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.inner.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.inner.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.inner.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
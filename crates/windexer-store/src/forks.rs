@@ -0,0 +1,697 @@
+//! Slot-status-aware storage
+//!
+//! [`ForkAwareStore`] holds writes for slots that are not yet rooted in an
+//! in-memory staging area instead of passing them straight to the backend.
+//! Slot status updates drive what happens next:
+//!
+//! - when a slot is rooted, it and its staged ancestors are promoted to the
+//!   wrapped backend in slot order, with their blocks marked rooted
+//! - staged slots that branch off below the new root belong to abandoned
+//!   forks and are discarded, together with their descendants. A slot
+//!   staged without its block stays until the parent of a rooted slot
+//!   skips over it, as its fork is unknown until then
+//! - dead slots are discarded immediately, together with their descendants
+//!
+//! Reads go to the wrapped backend, so only rooted data is visible. Writes for
+//! slots at or below the latest root (startup snapshots, late updates) are
+//! passed through unchanged.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    std::{
+        collections::{BTreeMap, HashSet},
+        fmt::{Debug, Formatter, Result as FmtResult},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    },
+    tracing::{debug, warn},
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// Configuration for the fork-aware store
+#[derive(Debug, Clone)]
+pub struct ForkConfig {
+    /// Maximum number of unrooted slots kept in staging. When exceeded, the
+    /// oldest staged slots are discarded.
+    pub max_staged_slots: usize,
+}
+
+impl Default for ForkConfig {
+    fn default() -> Self {
+        Self {
+            // Roughly three minutes of slots
+            max_staged_slots: 512,
+        }
+    }
+}
+
+/// Fork handling metrics
+#[derive(Default)]
+pub struct ForkMetrics {
+    pub slots_promoted: AtomicU64,
+    pub slots_rolled_back: AtomicU64,
+    pub records_promoted: AtomicU64,
+    pub records_rolled_back: AtomicU64,
+    pub staged_slots: AtomicU64,
+}
+
+impl Debug for ForkMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ForkMetrics")
+            .field("slots_promoted", &self.slots_promoted.load(Ordering::Relaxed))
+            .field("slots_rolled_back", &self.slots_rolled_back.load(Ordering::Relaxed))
+            .field("records_promoted", &self.records_promoted.load(Ordering::Relaxed))
+            .field("records_rolled_back", &self.records_rolled_back.load(Ordering::Relaxed))
+            .field("staged_slots", &self.staged_slots.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Data written for a slot that is not yet rooted
+#[derive(Clone, Default)]
+struct StagedSlot {
+    parent: Option<u64>,
+    accounts: Vec<AccountData>,
+    transactions: Vec<TransactionData>,
+    block: Option<BlockData>,
+}
+
+impl StagedSlot {
+    fn record_count(&self) -> u64 {
+        (self.accounts.len() + self.transactions.len() + self.block.is_some() as usize) as u64
+    }
+}
+
+#[derive(Default)]
+struct ForkState {
+    staged: BTreeMap<u64, StagedSlot>,
+    latest_rooted: Option<u64>,
+    latest_confirmed: Option<u64>,
+    /// Parent of each rooted slot, kept while older slots are staged; a
+    /// staged slot between a rooted slot and its parent is on another fork
+    rooted_parents: BTreeMap<u64, u64>,
+}
+
+impl ForkState {
+    fn is_rooted(&self, slot: u64) -> bool {
+        self.latest_rooted.map_or(false, |root| slot <= root)
+    }
+
+    fn slot_mut(&mut self, slot: u64) -> &mut StagedSlot {
+        self.staged.entry(slot).or_default()
+    }
+
+    /// Remove `slot` and every staged slot descending from it
+    fn remove_with_descendants(&mut self, slot: u64) -> Vec<StagedSlot> {
+        let mut doomed = HashSet::from([slot]);
+        for (&s, staged) in self.staged.range(slot + 1..) {
+            if staged.parent.map_or(false, |p| doomed.contains(&p)) {
+                doomed.insert(s);
+            }
+        }
+        doomed.into_iter().filter_map(|s| self.staged.remove(&s)).collect()
+    }
+
+    /// Root `root`, returning the slots to promote in ascending order and the
+    /// slots discarded as abandoned forks
+    fn root(&mut self, root: u64) -> (Vec<(u64, StagedSlot)>, Vec<StagedSlot>) {
+        let previous_root = self.latest_rooted;
+        self.latest_rooted = Some(root);
+
+        // Walk the parent chain down from the new root
+        let mut chain = Vec::new();
+        let mut cursor = Some(root);
+        while let Some(slot) = cursor {
+            if previous_root.map_or(false, |prev| slot <= prev) {
+                break;
+            }
+            match self.staged.remove(&slot) {
+                Some(staged) => {
+                    cursor = staged.parent;
+                    chain.push((slot, staged));
+                }
+                None => break,
+            }
+        }
+        chain.reverse();
+        self.rooted_parents.extend(chain.iter().filter_map(|(slot, staged)| Some((*slot, staged.parent?))));
+
+        // Anything else at or below the root is on an abandoned fork, once
+        // its block or a rooted slot skipping over it shows so
+        let mut abandoned: Vec<StagedSlot> = Vec::new();
+        let mut dead = HashSet::new();
+        let remaining: Vec<u64> = self.staged.range(..=root).map(|(&s, _)| s).collect();
+        for slot in remaining {
+            let skipped = self.rooted_parents.iter().any(|(&child, &parent)| parent < slot && slot < child);
+            if skipped || self.staged[&slot].parent.is_some() {
+                dead.insert(slot);
+                abandoned.extend(self.staged.remove(&slot));
+            }
+        }
+
+        // Slots above the root survive only if they descend from it
+        let above: Vec<u64> = self.staged.range(root + 1..).map(|(&s, _)| s).collect();
+        for slot in above {
+            let orphaned = match self.staged.get(&slot).and_then(|s| s.parent) {
+                Some(parent) => dead.contains(&parent) || (parent < root && !self.staged.contains_key(&parent)),
+                None => false,
+            };
+            if orphaned {
+                dead.insert(slot);
+                abandoned.extend(self.staged.remove(&slot));
+            }
+        }
+
+        // Only links above the oldest staged slot can still skip over one
+        match self.staged.keys().next() {
+            Some(&oldest) => self.rooted_parents.retain(|&child, _| child > oldest),
+            None => self.rooted_parents.clear(),
+        }
+
+        (chain, abandoned)
+    }
+}
+
+/// Stages unrooted writes and applies them once their slot is rooted
+pub struct ForkAwareStore {
+    inner: Arc<dyn Storage>,
+    config: ForkConfig,
+    state: Mutex<ForkState>,
+    /// Held while a root is applied, so a failed promotion can hand its
+    /// slots back before the next root is taken
+    rooting: tokio::sync::Mutex<()>,
+    metrics: Arc<ForkMetrics>,
+}
+
+impl ForkAwareStore {
+    pub fn new(inner: Arc<dyn Storage>, config: ForkConfig) -> Self {
+        Self {
+            inner,
+            config,
+            state: Mutex::new(ForkState::default()),
+            rooting: tokio::sync::Mutex::new(()),
+            metrics: Arc::new(ForkMetrics::default()),
+        }
+    }
+
+    /// Get the fork handling metrics
+    pub fn metrics(&self) -> Arc<ForkMetrics> {
+        self.metrics.clone()
+    }
+
+    fn state(&self) -> Result<std::sync::MutexGuard<'_, ForkState>> {
+        self.state.lock().map_err(|e| anyhow!("Lock error: {}", e))
+    }
+
+    fn roll_back(&self, slots: Vec<StagedSlot>) {
+        if slots.is_empty() {
+            return;
+        }
+        let records: u64 = slots.iter().map(StagedSlot::record_count).sum();
+        self.metrics.slots_rolled_back.fetch_add(slots.len() as u64, Ordering::Relaxed);
+        self.metrics.records_rolled_back.fetch_add(records, Ordering::Relaxed);
+        debug!("Rolled back {} slots ({} records) from abandoned forks", slots.len(), records);
+    }
+
+    fn update_staged_gauge(&self, state: &ForkState) {
+        self.metrics.staged_slots.store(state.staged.len() as u64, Ordering::Relaxed);
+    }
+
+    /// Drop the oldest staged slots once staging grows past its limit
+    fn enforce_limit(&self, state: &mut ForkState) {
+        let mut evicted = Vec::new();
+        while state.staged.len() > self.config.max_staged_slots {
+            if let Some((slot, staged)) = state.staged.pop_first() {
+                warn!("Staging limit reached, discarding unrooted slot {}", slot);
+                evicted.push(staged);
+            }
+        }
+        self.roll_back(evicted);
+    }
+
+    async fn write_staged(&self, staged: StagedSlot) -> Result<()> {
+        match staged.block {
+            Some(mut block) => {
+                block.status = SlotStatus::Rooted;
+                self.inner.store_slot_bundle(SlotBundle {
+                    block,
+                    transactions: staged.transactions,
+                    accounts: staged.accounts,
+                }).await?;
+            }
+            None => {
+                if !staged.accounts.is_empty() {
+                    self.inner.store_accounts(staged.accounts).await?;
+                }
+                if !staged.transactions.is_empty() {
+                    self.inner.store_transactions(staged.transactions).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `chain` to the backend in slot order. If a write fails, that
+    /// slot and the rest of the chain go back into staging and the root is
+    /// moved back to the last slot written, so the next root retries them.
+    async fn promote(&self, chain: Vec<(u64, StagedSlot)>, mut rooted: Option<u64>) -> Result<()> {
+        let mut chain = chain.into_iter();
+        while let Some((slot, staged)) = chain.next() {
+            let records = staged.record_count();
+
+            if let Err(e) = self.write_staged(staged.clone()).await {
+                let mut state = self.state()?;
+                state.latest_rooted = rooted;
+                state.staged.insert(slot, staged);
+                state.staged.extend(chain);
+                self.update_staged_gauge(&state);
+                warn!("Failed to promote slot {}, keeping it staged: {}", slot, e);
+                return Err(e);
+            }
+            rooted = Some(slot);
+
+            self.metrics.slots_promoted.fetch_add(1, Ordering::Relaxed);
+            self.metrics.records_promoted.fetch_add(records, Ordering::Relaxed);
+            debug!("Promoted slot {} ({} records)", slot, records);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for ForkAwareStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ForkAwareStore")
+            .field("config", &self.config)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for ForkAwareStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        {
+            let mut state = self.state()?;
+            if !state.is_rooted(account.slot) {
+                state.slot_mut(account.slot).accounts.push(account);
+                self.enforce_limit(&mut state);
+                self.update_staged_gauge(&state);
+                return Ok(());
+            }
+        }
+        self.inner.store_account(account).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        {
+            let mut state = self.state()?;
+            if !state.is_rooted(transaction.slot) {
+                state.slot_mut(transaction.slot).transactions.push(transaction);
+                self.enforce_limit(&mut state);
+                self.update_staged_gauge(&state);
+                return Ok(());
+            }
+        }
+        self.inner.store_transaction(transaction).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        {
+            let mut state = self.state()?;
+            if !state.is_rooted(block.slot) {
+                let staged = state.slot_mut(block.slot);
+                if block.parent_slot.is_some() {
+                    staged.parent = block.parent_slot;
+                }
+                staged.block = Some(block);
+                self.enforce_limit(&mut state);
+                self.update_staged_gauge(&state);
+                return Ok(());
+            }
+        }
+        self.inner.store_block(block).await
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        {
+            let mut state = self.state()?;
+            if !state.is_rooted(bundle.slot()) {
+                let staged = state.slot_mut(bundle.slot());
+                if bundle.block.parent_slot.is_some() {
+                    staged.parent = bundle.block.parent_slot;
+                }
+                staged.accounts.extend(bundle.accounts);
+                staged.transactions.extend(bundle.transactions);
+                staged.block = Some(bundle.block);
+                self.enforce_limit(&mut state);
+                self.update_staged_gauge(&state);
+                return Ok(());
+            }
+        }
+        self.inner.store_slot_bundle(bundle).await
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        let _rooting = self.rooting.lock().await;
+        let (chain, rooted) = {
+            let mut state = self.state()?;
+            if state.is_rooted(status.slot) {
+                return Ok(());
+            }
+            let rooted = state.latest_rooted;
+
+            let chain = match status.status {
+                SlotStatus::Rooted => {
+                    if status.parent.is_some() {
+                        state.slot_mut(status.slot).parent = status.parent;
+                    }
+                    let (chain, abandoned) = state.root(status.slot);
+                    self.roll_back(abandoned);
+                    chain
+                }
+                SlotStatus::Dead(ref reason) => {
+                    warn!("Slot {} is dead: {}", status.slot, reason);
+                    let removed = state.remove_with_descendants(status.slot);
+                    self.roll_back(removed);
+                    Vec::new()
+                }
+                _ => {
+                    if matches!(status.status, SlotStatus::Confirmed) {
+                        state.latest_confirmed = state.latest_confirmed.max(Some(status.slot));
+                    }
+                    if status.parent.is_some() {
+                        state.slot_mut(status.slot).parent = status.parent;
+                        self.enforce_limit(&mut state);
+                    }
+                    Vec::new()
+                }
+            };
+
+            self.update_staged_gauge(&state);
+            (chain, rooted)
+        };
+
+        self.promote(chain, rooted).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        let latest = self.state()?.latest_rooted;
+        match latest {
+            Some(slot) => Ok(Some(slot)),
+            None => self.inner.get_latest_rooted_slot().await,
+        }
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        let confirmed = self.state()?.latest_confirmed;
+        let rooted = self.get_latest_rooted_slot().await?;
+        match commitment {
+            Commitment::Processed => Ok(None),
+            // Confirmed slots wait in staging for their root, so the backend
+            // cannot tell how far confirmation has got
+            Commitment::Confirmed => Ok(Some(confirmed.max(rooted).unwrap_or(0))),
+            Commitment::Finalized => Ok(Some(rooted.unwrap_or(0))),
+        }
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.inner.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.inner.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.inner.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.inner.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.inner.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.inner.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.inner.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.inner.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.inner.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.inner.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        let staged = self.state()?.staged.len();
+        if staged > 0 {
+            warn!("Closing with {} unrooted slots still staged; they will be discarded", staged);
+        }
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::testing::FailingStore};
+
+    fn staged(parent: u64) -> StagedSlot {
+        StagedSlot {
+            parent: Some(parent),
+            ..StagedSlot::default()
+        }
+    }
+
+    #[test]
+    fn test_root_promotes_ancestors_and_drops_forks() {
+        let mut state = ForkState {
+            latest_rooted: Some(10),
+            ..ForkState::default()
+        };
+        // 10 <- 11 <- 12 <- 14 is canonical, 10 <- 13 <- 15 is a fork
+        state.staged.insert(11, staged(10));
+        state.staged.insert(12, staged(11));
+        state.staged.insert(13, staged(10));
+        state.staged.insert(14, staged(12));
+        state.staged.insert(15, staged(13));
+
+        let (chain, abandoned) = state.root(12);
+
+        let promoted: Vec<u64> = chain.iter().map(|(slot, _)| *slot).collect();
+        assert_eq!(promoted, vec![11, 12]);
+        assert_eq!(abandoned.len(), 2);
+        assert_eq!(state.staged.keys().copied().collect::<Vec<_>>(), vec![14]);
+        assert_eq!(state.latest_rooted, Some(12));
+    }
+
+    #[test]
+    fn test_root_keeps_slots_whose_fork_is_unknown() {
+        let mut state = ForkState {
+            latest_rooted: Some(10),
+            ..ForkState::default()
+        };
+        // 10 <- 12 <- 13 is canonical; 11 only has accounts so far
+        state.staged.insert(11, StagedSlot::default());
+        state.staged.insert(12, staged(10));
+        state.staged.insert(13, staged(12));
+
+        let (chain, abandoned) = state.root(13);
+        assert_eq!(chain.len(), 2);
+        // 12 skips over 11, so 11 is on another fork
+        assert_eq!(abandoned.len(), 1);
+
+        // 16's parent 15 was never seen, so nothing shows where 14 belongs
+        state.staged.insert(14, StagedSlot::default());
+        state.staged.insert(16, staged(15));
+
+        let (chain, abandoned) = state.root(16);
+        assert_eq!(chain.len(), 1);
+        assert!(abandoned.is_empty());
+        assert_eq!(state.staged.keys().copied().collect::<Vec<_>>(), vec![14]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_promotion_keeps_slots_staged() {
+        let inner = Arc::new(FailingStore::default());
+        let store = ForkAwareStore::new(inner.clone(), ForkConfig::default());
+        store.store_block(BlockData { slot: 5, parent_slot: Some(4), ..Default::default() }).await.unwrap();
+        let rooted = SlotStatusData { slot: 5, parent: Some(4), status: SlotStatus::Rooted };
+
+        inner.fail_writes(true);
+        assert!(store.update_slot_status(rooted.clone()).await.is_err());
+        assert_eq!(store.get_latest_rooted_slot().await.unwrap(), None);
+
+        inner.fail_writes(false);
+        store.update_slot_status(rooted).await.unwrap();
+        assert!(inner.get_block(5).await.unwrap().is_some());
+        assert_eq!(store.metrics().slots_promoted.load(Ordering::Relaxed), 1);
+    }
+}
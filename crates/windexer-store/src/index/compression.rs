@@ -0,0 +1,477 @@
+//! State compression index
+//!
+//! Compressed NFTs are leaves of SPL account-compression concurrent merkle
+//! trees rather than accounts. The account-compression program logs every
+//! change to a tree through a CPI to the SPL Noop program as a
+//! `ChangeLogEvent`, with the index and new hash of the changed leaf and the
+//! new root; Bubblegum logs a `LeafSchemaEvent` the same way, naming the
+//! asset and owner a leaf stands for. [`CompressionIndex`] follows both in
+//! the inner instructions of written transactions and keeps the leaves of
+//! every tree, so an asset is served with its current leaf, the tree's root
+//! and a proof.
+//!
+//! Changes are applied by sequence number, so transactions written out of
+//! order leave the same state. Nodes are hashed with keccak256 as the program
+//! does, empty leaves being zero. A tree first seen after it was created
+//! misses the leaves written before; its computed root then differs from the
+//! logged one and its assets are served without a proof. Transactions are not
+//! replayed on start, so the index only holds what was written since.
+
+use {
+    super::DerivedIndex,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, hash::Hash, keccak, pubkey, pubkey::Pubkey, signature::Signature},
+    std::{collections::HashMap, iter, sync::RwLock},
+    windexer_common::types::{AccountData, InstructionData, TransactionData},
+};
+
+pub const SPL_ACCOUNT_COMPRESSION_PROGRAM_ID: Pubkey = pubkey!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+pub const SPL_NOOP_PROGRAM_ID: Pubkey = pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+pub const BUBBLEGUM_PROGRAM_ID: Pubkey = pubkey!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+
+/// Deepest tree the program allows
+const MAX_DEPTH: usize = 30;
+
+const EMPTY: [u8; 32] = [0; 32];
+
+/// `AccountCompressionEvent` variants
+const EVENT_CHANGE_LOG: u8 = 0;
+const EVENT_APPLICATION_DATA: u8 = 1;
+/// Bubblegum `BubblegumEventType::LeafSchemaEvent`
+const BUBBLEGUM_LEAF_SCHEMA_EVENT: u8 = 1;
+
+/// A compressed asset with the state of its leaf
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressedAsset {
+    pub id: Pubkey,
+    pub tree: Pubkey,
+    pub leaf_index: u32,
+    pub owner: Pubkey,
+    pub delegate: Pubkey,
+    pub nonce: u64,
+    /// Hashes, base58 encoded
+    pub data_hash: String,
+    pub creator_hash: String,
+    pub leaf_hash: String,
+    /// The leaf was emptied since, as burning does
+    pub burned: bool,
+    /// Root of the tree as last logged
+    pub root: String,
+    /// Sibling hashes from the leaf up to the root, when every leaf of the
+    /// tree is known
+    pub proof: Option<Vec<String>>,
+    /// Sequence number of the tree change that last wrote the asset
+    pub seq: u64,
+    pub slot: Slot,
+    pub signature: Signature,
+}
+
+/// Minimal Borsh reader over instruction data
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn hash(&mut self) -> Option<[u8; 32]> {
+        self.take(32)?.try_into().ok()
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        self.hash().map(Pubkey::new_from_array)
+    }
+}
+
+/// A `ChangeLogEvent`: the path from a changed leaf up to the new root
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChangeLog {
+    tree: Pubkey,
+    /// Node hashes from the leaf to the root
+    path: Vec<[u8; 32]>,
+    seq: u64,
+    index: u32,
+}
+
+impl ChangeLog {
+    fn leaf(&self) -> [u8; 32] {
+        self.path[0]
+    }
+
+    fn root(&self) -> [u8; 32] {
+        self.path[self.path.len() - 1]
+    }
+
+    fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+/// A Bubblegum `LeafSchemaEvent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LeafSchema {
+    id: Pubkey,
+    owner: Pubkey,
+    delegate: Pubkey,
+    nonce: u64,
+    data_hash: [u8; 32],
+    creator_hash: [u8; 32],
+    leaf_hash: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NoopEvent {
+    ChangeLog(ChangeLog),
+    LeafSchema(LeafSchema),
+}
+
+impl NoopEvent {
+    /// Decodes the data of a Noop instruction, `None` for events of other
+    /// programs
+    fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader { data };
+        match (reader.u8()?, reader.u8()?) {
+            (EVENT_CHANGE_LOG, 0) => {
+                let tree = reader.pubkey()?;
+                let len = reader.u32()? as usize;
+                if len == 0 || len > MAX_DEPTH + 1 {
+                    return None;
+                }
+                let path = (0..len)
+                    .map(|_| {
+                        let node = reader.hash()?;
+                        reader.u32()?;
+                        Some(node)
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(NoopEvent::ChangeLog(ChangeLog { tree, path, seq: reader.u64()?, index: reader.u32()? }))
+            }
+            (EVENT_APPLICATION_DATA, 0) => {
+                let len = reader.u32()? as usize;
+                let mut reader = Reader { data: reader.take(len)? };
+                // Event type, version and schema version
+                if (reader.u8()?, reader.u8()?, reader.u8()?) != (BUBBLEGUM_LEAF_SCHEMA_EVENT, 0, 0) {
+                    return None;
+                }
+                Some(NoopEvent::LeafSchema(LeafSchema {
+                    id: reader.pubkey()?,
+                    owner: reader.pubkey()?,
+                    delegate: reader.pubkey()?,
+                    nonce: reader.u64()?,
+                    data_hash: reader.hash()?,
+                    creator_hash: reader.hash()?,
+                    leaf_hash: reader.hash()?,
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[left.as_ref(), right.as_ref()]).to_bytes()
+}
+
+/// Leaves of one tree, with the inner nodes over them
+#[derive(Debug, Clone)]
+struct Tree {
+    depth: usize,
+    /// Hash of an empty subtree at each level, leaves at 0
+    empty: Vec<[u8; 32]>,
+    /// Nodes that differ from the empty subtree, by level and index
+    nodes: HashMap<(usize, u32), [u8; 32]>,
+    /// Sequence number of the change each leaf was last written by
+    leaf_seqs: HashMap<u32, u64>,
+    /// Root and sequence number of the latest change
+    root: [u8; 32],
+    seq: u64,
+}
+
+impl Tree {
+    fn new(depth: usize) -> Self {
+        let mut empty = vec![EMPTY];
+        for level in 0..depth {
+            empty.push(hash_pair(&empty[level], &empty[level]));
+        }
+        Self {
+            depth,
+            root: empty[depth],
+            empty,
+            nodes: HashMap::new(),
+            leaf_seqs: HashMap::new(),
+            seq: 0,
+        }
+    }
+
+    fn node(&self, level: usize, index: u32) -> [u8; 32] {
+        self.nodes.get(&(level, index)).copied().unwrap_or(self.empty[level])
+    }
+
+    fn leaf(&self, index: u32) -> [u8; 32] {
+        self.node(0, index)
+    }
+
+    /// Applies a change unless the leaf was since written by a later one
+    fn apply(&mut self, change: &ChangeLog) {
+        if change.seq > self.seq {
+            self.seq = change.seq;
+            self.root = change.root();
+        }
+        if self.leaf_seqs.get(&change.index).is_some_and(|seq| *seq >= change.seq) {
+            return;
+        }
+        self.leaf_seqs.insert(change.index, change.seq);
+
+        let mut hash = change.leaf();
+        for level in 0..=self.depth {
+            let index = change.index >> level;
+            if hash == self.empty[level] {
+                self.nodes.remove(&(level, index));
+            } else {
+                self.nodes.insert((level, index), hash);
+            }
+            if level < self.depth {
+                let sibling = self.node(level, index ^ 1);
+                hash = match index & 1 {
+                    0 => hash_pair(&hash, &sibling),
+                    _ => hash_pair(&sibling, &hash),
+                };
+            }
+        }
+    }
+
+    fn computed_root(&self) -> [u8; 32] {
+        self.node(self.depth, 0)
+    }
+
+    /// Proof of a leaf, if the leaves add up to the logged root
+    fn proof(&self, index: u32) -> Option<Vec<[u8; 32]>> {
+        if self.computed_root() != self.root {
+            return None;
+        }
+        Some((0..self.depth).map(|level| self.node(level, (index >> level) ^ 1)).collect())
+    }
+}
+
+/// The latest leaf schema of an asset
+#[derive(Debug, Clone)]
+struct AssetLeaf {
+    tree: Pubkey,
+    index: u32,
+    schema: LeafSchema,
+    seq: u64,
+    slot: u64,
+    signature: Signature,
+}
+
+#[derive(Debug, Default)]
+struct CompressionState {
+    trees: HashMap<Pubkey, Tree>,
+    assets: HashMap<Pubkey, AssetLeaf>,
+}
+
+/// Leaves of every concurrent merkle tree changed by written transactions,
+/// and the compressed assets they hold
+#[derive(Debug, Default)]
+pub struct CompressionIndex {
+    state: RwLock<CompressionState>,
+}
+
+impl CompressionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An asset by its id, the Bubblegum asset address
+    pub fn get(&self, id: &Pubkey) -> Option<CompressedAsset> {
+        let state = self.state.read().ok()?;
+        let asset = state.assets.get(id)?;
+        let tree = state.trees.get(&asset.tree)?;
+        let encode = |hash: &[u8; 32]| Hash::new_from_array(*hash).to_string();
+
+        Some(CompressedAsset {
+            id: asset.schema.id,
+            tree: asset.tree,
+            leaf_index: asset.index,
+            owner: asset.schema.owner,
+            delegate: asset.schema.delegate,
+            nonce: asset.schema.nonce,
+            data_hash: encode(&asset.schema.data_hash),
+            creator_hash: encode(&asset.schema.creator_hash),
+            leaf_hash: encode(&asset.schema.leaf_hash),
+            burned: tree.leaf(asset.index) == EMPTY,
+            root: encode(&tree.root),
+            proof: tree.proof(asset.index).map(|proof| proof.iter().map(encode).collect()),
+            seq: asset.seq,
+            slot: asset.slot,
+            signature: asset.signature,
+        })
+    }
+
+    /// Number of trees followed
+    pub fn tree_count(&self) -> usize {
+        self.state.read().map(|state| state.trees.len()).unwrap_or(0)
+    }
+
+    /// Applies the events logged by one top-level instruction and the
+    /// instructions it invoked
+    fn apply_events(&self, instruction: &InstructionData, slot: Slot, signature: Signature) {
+        let mut changes = Vec::new();
+        let mut schemas = Vec::new();
+        for invoked in iter::once(instruction).chain(&instruction.inner_instructions) {
+            if invoked.program_id != SPL_NOOP_PROGRAM_ID {
+                continue;
+            }
+            match NoopEvent::parse(&invoked.data) {
+                Some(NoopEvent::ChangeLog(change)) => changes.push(change),
+                Some(NoopEvent::LeafSchema(schema)) => schemas.push(schema),
+                None => {}
+            }
+        }
+        if changes.is_empty() {
+            return;
+        }
+
+        let Ok(mut state) = self.state.write() else {
+            return;
+        };
+        for change in &changes {
+            state.trees
+                .entry(change.tree)
+                .or_insert_with(|| Tree::new(change.depth()))
+                .apply(change);
+        }
+        // A schema describes the leaf a change of the same instruction wrote
+        for schema in schemas {
+            let Some(change) = changes.iter().find(|change| change.leaf() == schema.leaf_hash) else {
+                continue;
+            };
+            if state.assets.get(&schema.id).is_some_and(|asset| asset.seq >= change.seq) {
+                continue;
+            }
+            state.assets.insert(schema.id, AssetLeaf {
+                tree: change.tree,
+                index: change.index,
+                schema,
+                seq: change.seq,
+                slot,
+                signature,
+            });
+        }
+    }
+}
+
+impl DerivedIndex for CompressionIndex {
+    fn name(&self) -> &'static str {
+        "compression"
+    }
+
+    fn apply_account(&self, _account: &AccountData) {}
+
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        // Failed transactions change no tree
+        if transaction.serializable_meta.status != Some(0) {
+            return;
+        }
+        for instruction in &transaction.instructions {
+            self.apply_events(instruction, transaction.slot, transaction.signature);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change_log(tree: &Tree, tree_id: Pubkey, index: u32, leaf: [u8; 32], seq: u64) -> Vec<u8> {
+        // Path of the leaf once written, computed the way the program does
+        let mut updated = tree.clone();
+        updated.apply(&ChangeLog { tree: tree_id, path: vec![leaf; tree.depth + 1], seq, index });
+        let path: Vec<[u8; 32]> = (0..=tree.depth).map(|level| updated.node(level, index >> level)).collect();
+
+        let mut data = vec![EVENT_CHANGE_LOG, 0];
+        data.extend_from_slice(tree_id.as_ref());
+        data.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        for (level, node) in path.iter().enumerate() {
+            data.extend_from_slice(node);
+            data.extend_from_slice(&(((1u32 << tree.depth) + index) >> level).to_le_bytes());
+        }
+        data.extend_from_slice(&seq.to_le_bytes());
+        data.extend_from_slice(&index.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn follows_trees_and_proves_assets() {
+        let tree_id = Pubkey::new_unique();
+        let mut tree = Tree::new(3);
+        let mut events = Vec::new();
+        for (seq, leaf) in [[1u8; 32], [2; 32], [3; 32]].into_iter().enumerate() {
+            let data = change_log(&tree, tree_id, seq as u32, leaf, seq as u64 + 1);
+            let Some(NoopEvent::ChangeLog(change)) = NoopEvent::parse(&data) else {
+                panic!("change log not parsed");
+            };
+            tree.apply(&change);
+            events.push(data);
+        }
+        assert_eq!(tree.computed_root(), tree.root);
+
+        let asset = Pubkey::new_unique();
+        let mut schema = vec![EVENT_APPLICATION_DATA, 0, 203, 0, 0, 0, BUBBLEGUM_LEAF_SCHEMA_EVENT, 0, 0];
+        schema.extend_from_slice(asset.as_ref());
+        schema.extend_from_slice(Pubkey::new_unique().as_ref());
+        schema.extend_from_slice(Pubkey::new_unique().as_ref());
+        schema.extend_from_slice(&1u64.to_le_bytes());
+        schema.extend_from_slice(&[[7u8; 32], [8; 32], [2; 32]].concat());
+
+        let noop = |data: Vec<u8>| InstructionData {
+            program_id: SPL_NOOP_PROGRAM_ID,
+            accounts: Vec::new(),
+            data,
+            stack_height: Some(2),
+            inner_instructions: Vec::new(),
+        };
+        let instruction = InstructionData {
+            program_id: BUBBLEGUM_PROGRAM_ID,
+            accounts: Vec::new(),
+            data: Vec::new(),
+            stack_height: Some(1),
+            inner_instructions: events.into_iter().map(noop).chain(iter::once(noop(schema))).collect(),
+        };
+
+        let index = CompressionIndex::new();
+        index.apply_events(&instruction, 42, Signature::default());
+        let found = index.get(&asset).unwrap();
+        assert_eq!((found.tree, found.leaf_index, found.seq, found.burned), (tree_id, 1, 2, false));
+
+        let proof = found.proof.unwrap();
+        let mut hash = [2u8; 32];
+        for (level, sibling) in proof.iter().enumerate() {
+            let sibling = sibling.parse::<Hash>().unwrap().to_bytes();
+            hash = match (1u32 >> level) & 1 {
+                0 => hash_pair(&hash, &sibling),
+                _ => hash_pair(&sibling, &hash),
+            };
+        }
+        assert_eq!(Hash::new_from_array(hash).to_string(), found.root);
+    }
+}
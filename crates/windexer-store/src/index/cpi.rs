@@ -0,0 +1,164 @@
+//! Cross-program invocation index
+//!
+//! Follows the call tree of every successful transaction written and keeps
+//! the most recent calls into each program made by another program, with the
+//! program that made them. Top-level instructions are not CPIs and are not
+//! kept; [`Storage::get_transactions_by_program`](crate::traits::Storage)
+//! covers those.
+
+use {
+    super::DerivedIndex,
+    lru::LruCache,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature},
+    std::{collections::VecDeque, num::NonZeroUsize, sync::Mutex},
+    windexer_common::types::{AccountData, InstructionData, Invocation, TransactionData},
+};
+
+/// Programs whose calls are kept
+pub const DEFAULT_CPI_PROGRAMS: usize = 10_000;
+/// Calls kept for each program
+pub const DEFAULT_CPI_CALLS_PER_PROGRAM: usize = 1_000;
+
+/// A call into a program made by another program
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpiCall {
+    pub signature: Signature,
+    pub slot: Slot,
+    /// Position of the top-level instruction the call descends from
+    pub instruction_index: usize,
+    /// Position of the call among the inner instructions of that instruction
+    pub inner_index: usize,
+    pub caller: Pubkey,
+    pub program_id: Pubkey,
+    /// 2 for calls made by a top-level instruction, 3 for the calls those
+    /// make and so on
+    pub depth: u32,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+pub struct CpiIndex {
+    calls_per_program: usize,
+    programs: Mutex<LruCache<Pubkey, VecDeque<CpiCall>>>,
+}
+
+impl Default for CpiIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_CPI_PROGRAMS, DEFAULT_CPI_CALLS_PER_PROGRAM)
+    }
+}
+
+impl CpiIndex {
+    /// Keep up to `calls_per_program` calls into each of up to `programs`
+    /// programs
+    pub fn new(programs: usize, calls_per_program: usize) -> Self {
+        let programs = NonZeroUsize::new(programs).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            calls_per_program: calls_per_program.max(1),
+            programs: Mutex::new(LruCache::new(programs)),
+        }
+    }
+
+    /// The most recent calls into `program_id`, newest first
+    pub fn calls(&self, program_id: &Pubkey, limit: usize) -> Vec<CpiCall> {
+        let Ok(mut programs) = self.programs.lock() else {
+            return Vec::new();
+        };
+        programs
+            .get(program_id)
+            .map(|calls| calls.iter().take(limit).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, call: CpiCall) {
+        let Ok(mut programs) = self.programs.lock() else {
+            return;
+        };
+        let calls = programs.get_or_insert_mut(call.program_id, VecDeque::new);
+        calls.push_front(call);
+        calls.truncate(self.calls_per_program);
+    }
+}
+
+/// The calls below a top-level instruction, in execution order
+fn cpi_calls(instruction: &InstructionData, instruction_index: usize, signature: Signature, slot: Slot) -> Vec<CpiCall> {
+    let mut calls = Vec::new();
+    walk(&instruction.call_tree(), &mut |caller, call| {
+        calls.push(CpiCall {
+            signature,
+            slot,
+            instruction_index,
+            inner_index: calls.len(),
+            caller: caller.program_id,
+            program_id: call.program_id,
+            depth: call.depth,
+            accounts: call.accounts.clone(),
+            data: call.data.clone(),
+        });
+    });
+    calls
+}
+
+/// Visits every call below `caller` with the program that made it, in
+/// execution order
+fn walk(caller: &Invocation, visit: &mut impl FnMut(&Invocation, &Invocation)) {
+    for call in &caller.calls {
+        visit(caller, call);
+        walk(call, visit);
+    }
+}
+
+impl DerivedIndex for CpiIndex {
+    fn name(&self) -> &'static str {
+        "cpi"
+    }
+
+    fn apply_account(&self, _account: &AccountData) {}
+
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        // Calls made by failed transactions were rolled back
+        if transaction.serializable_meta.status != Some(0) {
+            return;
+        }
+        for (index, instruction) in transaction.instructions.iter().enumerate() {
+            for call in cpi_calls(instruction, index, transaction.signature, transaction.slot) {
+                self.record(call);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(program_id: Pubkey, stack_height: u32) -> InstructionData {
+        InstructionData {
+            program_id,
+            accounts: Vec::new(),
+            data: vec![stack_height as u8],
+            stack_height: Some(stack_height),
+            inner_instructions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn keeps_calls_by_callee_with_caller() {
+        let (program, router, token) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut top = instruction(program, 1);
+        top.inner_instructions = vec![instruction(router, 2), instruction(token, 3), instruction(token, 2)];
+
+        let index = CpiIndex::new(4, 1);
+        for call in cpi_calls(&top, 0, Signature::default(), 1) {
+            index.record(call);
+        }
+
+        assert_eq!(index.calls(&router, 10)[0].caller, program);
+        // Only the newest call into the token program is kept
+        let calls = index.calls(&token, 10);
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].caller, calls[0].depth, calls[0].inner_index), (program, 2, 2));
+        assert!(index.calls(&program, 10).is_empty());
+    }
+}
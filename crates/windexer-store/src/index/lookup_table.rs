@@ -0,0 +1,51 @@
+//! Address lookup table index
+//!
+//! Keeps the addresses of every lookup table account written, so the lookups
+//! of version 0 transactions can be resolved without asking an RPC node; see
+//! [`windexer_common::lookup_tables`].
+
+use {
+    super::DerivedIndex,
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::sync::Arc,
+    windexer_common::{lookup_tables::LookupTables, types::AccountData},
+};
+
+/// A lookup table and the addresses it holds, in index order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupTable {
+    pub address: Pubkey,
+    pub addresses: Vec<Pubkey>,
+}
+
+#[derive(Debug, Default)]
+pub struct LookupTableIndex {
+    tables: Arc<LookupTables>,
+}
+
+impl LookupTableIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tables held, to resolve lookups with
+    pub fn tables(&self) -> Arc<LookupTables> {
+        self.tables.clone()
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<LookupTable> {
+        let addresses = self.tables.get(address)?;
+        Some(LookupTable { address: *address, addresses })
+    }
+}
+
+impl DerivedIndex for LookupTableIndex {
+    fn name(&self) -> &'static str {
+        "lookup_tables"
+    }
+
+    fn apply_account(&self, account: &AccountData) {
+        self.tables.apply_account(account);
+    }
+}
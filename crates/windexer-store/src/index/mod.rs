@@ -0,0 +1,491 @@
+//! Derived indexes
+//!
+//! A [`DerivedIndex`] builds a secondary view from account updates as they
+//! are written, such as token balances by owner, NFT metadata by mint or a
+//! prefix search over keys. [`IndexedStore`] feeds every
+//! account that reaches the wrapped backend through its indexes and answers
+//! the index queries of the [`Storage`] trait.
+//!
+//! Indexes are held in memory. With `rebuild_on_start` they are rebuilt by
+//! streaming every stored account when the store is created, so write-only
+//! backends such as Parquet start with empty indexes.
+
+pub mod compression;
+pub mod cpi;
+pub mod lookup_table;
+pub mod nft;
+pub mod search;
+pub mod token;
+
+pub use {
+    compression::{CompressedAsset, CompressionIndex},
+    cpi::{CpiCall, CpiIndex, DEFAULT_CPI_CALLS_PER_PROGRAM, DEFAULT_CPI_PROGRAMS},
+    lookup_table::{LookupTable, LookupTableIndex},
+    nft::{NftIndex, NftMetadata},
+    search::{SearchHit, SearchIndex, SearchKind, DEFAULT_SEARCH_CAPACITY},
+    token::{TokenBalance, TokenIndex},
+};
+
+use {
+    crate::{
+        decoders::{
+            DecodedAccountIndex, DecodedInstructionIndex, DecoderRegistry, EventIndex, ParsedAccount,
+            ParsedInstruction, ProgramEvent, DEFAULT_INSTRUCTION_CAPACITY,
+        },
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::Result,
+    async_trait::async_trait,
+    futures::stream::{BoxStream, StreamExt},
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        str::FromStr,
+        sync::Arc,
+    },
+    tracing::info,
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// A secondary view maintained from account updates
+pub trait DerivedIndex: Send + Sync + 'static {
+    /// Name used in logs
+    fn name(&self) -> &'static str;
+
+    /// Apply an account update; updates may arrive out of order
+    fn apply_account(&self, account: &AccountData);
+
+    /// Apply a written transaction; most indexes only follow accounts
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        let _ = transaction;
+    }
+
+    /// Apply a written block
+    fn apply_block(&self, block: &BlockData) {
+        let _ = block;
+    }
+}
+
+/// Storage wrapper that maintains derived indexes over the accounts it writes
+pub struct IndexedStore {
+    inner: Arc<dyn Storage>,
+    indexes: Vec<Arc<dyn DerivedIndex>>,
+    tokens: Option<Arc<TokenIndex>>,
+    nfts: Option<Arc<NftIndex>>,
+    compression: Option<Arc<CompressionIndex>>,
+    cpi: Option<Arc<CpiIndex>>,
+    lookup_tables: Option<Arc<LookupTableIndex>>,
+    search: Option<Arc<SearchIndex>>,
+    decoded: Option<Arc<DecodedAccountIndex>>,
+    instructions: Option<Arc<DecodedInstructionIndex>>,
+    events: Option<Arc<EventIndex>>,
+}
+
+impl IndexedStore {
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self {
+            inner,
+            indexes: Vec::new(),
+            tokens: None,
+            nfts: None,
+            compression: None,
+            cpi: None,
+            lookup_tables: None,
+            search: None,
+            decoded: None,
+            instructions: None,
+            events: None,
+        }
+    }
+
+    /// Maintain the SPL token balance index
+    pub fn with_token_index(mut self) -> Self {
+        let tokens = Arc::new(TokenIndex::new());
+        self.indexes.push(tokens.clone());
+        self.tokens = Some(tokens);
+        self
+    }
+
+    /// Maintain the Metaplex NFT metadata index
+    pub fn with_nft_index(mut self) -> Self {
+        let nfts = Arc::new(NftIndex::new());
+        self.indexes.push(nfts.clone());
+        self.nfts = Some(nfts);
+        self
+    }
+
+    /// Maintain the state compression index of merkle trees and compressed
+    /// NFTs, followed through the transactions written
+    pub fn with_compression_index(mut self) -> Self {
+        let compression = Arc::new(CompressionIndex::new());
+        self.indexes.push(compression.clone());
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Maintain the index of cross-program invocations, keeping the latest
+    /// `calls_per_program` calls into each of up to `programs` programs
+    pub fn with_cpi_index(mut self, programs: usize, calls_per_program: usize) -> Self {
+        let cpi = Arc::new(CpiIndex::new(programs, calls_per_program));
+        self.indexes.push(cpi.clone());
+        self.cpi = Some(cpi);
+        self
+    }
+
+    /// Maintain the address lookup table index
+    pub fn with_lookup_table_index(mut self) -> Self {
+        let lookup_tables = Arc::new(LookupTableIndex::new());
+        self.indexes.push(lookup_tables.clone());
+        self.lookup_tables = Some(lookup_tables);
+        self
+    }
+
+    /// Maintain the prefix search index, keeping up to `capacity` signatures
+    /// and blockhashes
+    pub fn with_search_index(mut self, capacity: usize) -> Self {
+        let search = Arc::new(SearchIndex::new(capacity));
+        self.indexes.push(search.clone());
+        self.search = Some(search);
+        self
+    }
+
+    /// Decode accounts and instructions with the decoders in `registry`;
+    /// decoders registered later apply to data written from then on
+    pub fn with_decoders(mut self, registry: Arc<DecoderRegistry>) -> Self {
+        let decoded = Arc::new(DecodedAccountIndex::new(registry.clone()));
+        let instructions = Arc::new(DecodedInstructionIndex::new(registry, DEFAULT_INSTRUCTION_CAPACITY));
+        self.indexes.push(decoded.clone());
+        self.indexes.push(instructions.clone());
+        self.decoded = Some(decoded);
+        self.instructions = Some(instructions);
+        self
+    }
+
+    /// Keep up to `capacity` events from the logs of written transactions,
+    /// decoding logged data with the decoders in `registry`
+    pub fn with_event_index(mut self, registry: Arc<DecoderRegistry>, capacity: usize) -> Self {
+        let events = Arc::new(EventIndex::new(registry, capacity));
+        self.indexes.push(events.clone());
+        self.events = Some(events);
+        self
+    }
+
+    /// Rebuild every index from the accounts already in the wrapped backend
+    pub async fn rebuild(&self) -> Result<u64> {
+        let mut accounts = self.inner.stream_accounts_by_slot_range(0, u64::MAX);
+        let mut applied = 0u64;
+
+        while let Some(account) = accounts.next().await {
+            self.apply(&account?);
+            applied += 1;
+        }
+
+        info!("Rebuilt {} derived indexes from {} accounts", self.indexes.len(), applied);
+        Ok(applied)
+    }
+
+    fn apply(&self, account: &AccountData) {
+        for index in &self.indexes {
+            index.apply_account(account);
+        }
+    }
+
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        for index in &self.indexes {
+            index.apply_transaction(transaction);
+        }
+    }
+
+    fn apply_block(&self, block: &BlockData) {
+        for index in &self.indexes {
+            index.apply_block(block);
+        }
+    }
+}
+
+impl Debug for IndexedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("IndexedStore")
+            .field("indexes", &self.indexes.iter().map(|index| index.name()).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for IndexedStore {
+    // Indexes are updated before the write so accounts need not be cloned
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.apply(&account);
+        self.inner.store_account(account).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.apply_transaction(&transaction);
+        self.inner.store_transaction(transaction).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.apply_block(&block);
+        self.inner.store_block(block).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        accounts.iter().for_each(|account| self.apply(account));
+        self.inner.store_accounts(accounts).await
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        transactions.iter().for_each(|transaction| self.apply_transaction(transaction));
+        self.inner.store_transactions(transactions).await
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        blocks.iter().for_each(|block| self.apply_block(block));
+        self.inner.store_blocks(blocks).await
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        bundle.accounts.iter().for_each(|account| self.apply(account));
+        bundle.transactions.iter().for_each(|transaction| self.apply_transaction(transaction));
+        self.apply_block(&bundle.block);
+        self.inner.store_slot_bundle(bundle).await
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.inner.update_slot_status(status).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.inner.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.inner.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.inner.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        match &self.tokens {
+            Some(tokens) => Ok(tokens.balances(&Pubkey::from_str(owner)?)),
+            None => self.inner.get_token_balances(owner).await,
+        }
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        match &self.tokens {
+            Some(tokens) => Ok(tokens.holders(&Pubkey::from_str(mint)?, limit)),
+            None => self.inner.get_token_holders(mint, limit).await,
+        }
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        match &self.nfts {
+            Some(nfts) => Ok(nfts.by_owner(&Pubkey::from_str(owner)?)),
+            None => self.inner.get_nfts_by_owner(owner).await,
+        }
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        match &self.nfts {
+            Some(nfts) => Ok(nfts.by_mint(&Pubkey::from_str(mint)?)),
+            None => self.inner.get_nft_by_mint(mint).await,
+        }
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        match &self.compression {
+            Some(compression) => Ok(compression.get(&Pubkey::from_str(asset_id)?)),
+            None => self.inner.get_compressed_asset(asset_id).await,
+        }
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        match &self.cpi {
+            Some(cpi) => Ok(cpi.calls(&Pubkey::from_str(program_id)?, limit)),
+            None => self.inner.get_cpi_calls(program_id, limit).await,
+        }
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        match &self.lookup_tables {
+            Some(lookup_tables) => Ok(lookup_tables.get(&Pubkey::from_str(address)?)),
+            None => self.inner.get_lookup_table(address).await,
+        }
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        match &self.decoded {
+            Some(decoded) => Ok(decoded.get(&Pubkey::from_str(pubkey)?)),
+            None => self.inner.get_decoded_account(pubkey).await,
+        }
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        match &self.instructions {
+            Some(instructions) => Ok(instructions.get(&Signature::from_str(signature)?)),
+            None => self.inner.get_decoded_instructions(signature).await,
+        }
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        match &self.events {
+            Some(events) => {
+                let program_id = program_id.map(Pubkey::from_str).transpose()?;
+                Ok(events.query(program_id.as_ref(), name, limit))
+            }
+            None => self.inner.get_events(program_id, name, limit).await,
+        }
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        match &self.search {
+            Some(search) => Ok(search.search(prefix, limit)),
+            None => self.inner.search_prefix(prefix, limit).await,
+        }
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.inner.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
@@ -0,0 +1,407 @@
+//! Metaplex NFT metadata index
+//!
+//! Decodes Token Metadata accounts (`name`, `symbol`, `uri`, creators and
+//! collection) keyed by mint, and tracks the current holder of each mint from
+//! token accounts holding exactly one unit.
+//!
+//! Metadata accounts are Borsh encoded and fixed size, with strings padded by
+//! `\0`. Fields added in later program versions (`edition_nonce`,
+//! `token_standard`, `collection`) may be missing from older accounts and are
+//! decoded as `None`.
+
+use {
+    super::{token::TokenProgramAccount, DerivedIndex},
+    serde::{Deserialize, Serialize},
+    solana_sdk::{pubkey, pubkey::Pubkey},
+    std::{
+        collections::{HashMap, HashSet},
+        sync::RwLock,
+    },
+    windexer_common::types::AccountData,
+};
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+const KEY_METADATA_V1: u8 = 4;
+
+/// A creator listed in the metadata
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    /// Share of royalties in percent
+    pub share: u8,
+}
+
+/// The collection an NFT belongs to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftCollection {
+    pub key: Pubkey,
+    pub verified: bool,
+}
+
+/// Decoded Token Metadata for one mint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftMetadata {
+    pub mint: Pubkey,
+    /// Metadata account address
+    pub metadata_account: Pubkey,
+    pub update_authority: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Vec<NftCreator>,
+    pub collection: Option<NftCollection>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub token_standard: Option<u8>,
+    /// Current holder, if a token account holding the mint has been seen
+    pub owner: Option<Pubkey>,
+    /// Slot the metadata was last written at
+    pub slot: u64,
+}
+
+/// Minimal Borsh reader over account data
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        self.u8().map(|byte| byte != 0)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        Pubkey::try_from(self.take(32)?).ok()
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        Some(String::from_utf8_lossy(bytes).trim_end_matches('\0').to_string())
+    }
+
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> Option<T>) -> Option<Option<T>> {
+        match self.u8()? {
+            0 => Some(None),
+            1 => read(self).map(Some),
+            _ => None,
+        }
+    }
+}
+
+impl NftMetadata {
+    /// Decode a Token Metadata `MetadataV1` account
+    pub fn parse(account: &AccountData) -> Option<Self> {
+        if account.owner != TOKEN_METADATA_PROGRAM_ID || account.lamports == 0 {
+            return None;
+        }
+
+        let mut reader = Reader { data: &account.data };
+        if reader.u8()? != KEY_METADATA_V1 {
+            return None;
+        }
+
+        let update_authority = reader.pubkey()?;
+        let mint = reader.pubkey()?;
+        let name = reader.string()?;
+        let symbol = reader.string()?;
+        let uri = reader.string()?;
+        let seller_fee_basis_points = reader.u16()?;
+        let creators = reader
+            .option(|reader| {
+                let count = reader.u32()?;
+                (0..count)
+                    .map(|_| {
+                        Some(NftCreator {
+                            address: reader.pubkey()?,
+                            verified: reader.bool()?,
+                            share: reader.u8()?,
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })?
+            .unwrap_or_default();
+        let primary_sale_happened = reader.bool()?;
+        let is_mutable = reader.bool()?;
+
+        // Trailing fields were appended over time; treat truncation as absent
+        let _edition_nonce = reader.option(|reader| reader.u8()).flatten();
+        let token_standard = reader.option(|reader| reader.u8()).flatten();
+        let collection = reader
+            .option(|reader| {
+                Some(NftCollection {
+                    verified: reader.bool()?,
+                    key: reader.pubkey()?,
+                })
+            })
+            .flatten();
+
+        Some(Self {
+            mint,
+            metadata_account: account.pubkey,
+            update_authority,
+            name,
+            symbol,
+            uri,
+            seller_fee_basis_points,
+            creators,
+            collection,
+            primary_sale_happened,
+            is_mutable,
+            token_standard,
+            owner: None,
+            slot: account.slot,
+        })
+    }
+}
+
+struct Holder {
+    mint: Pubkey,
+    owner: Pubkey,
+    version: (u64, u64),
+}
+
+#[derive(Default)]
+struct NftState {
+    metadata: HashMap<Pubkey, (NftMetadata, (u64, u64))>,
+    metadata_accounts: HashMap<Pubkey, Pubkey>,
+    holders: HashMap<Pubkey, Holder>,
+    owner_by_mint: HashMap<Pubkey, Pubkey>,
+    mints_by_owner: HashMap<Pubkey, HashSet<Pubkey>>,
+}
+
+impl NftState {
+    fn release(&mut self, token_account: &Pubkey) {
+        let Some(holder) = self.holders.remove(token_account) else {
+            return;
+        };
+        if self.owner_by_mint.get(&holder.mint) == Some(&holder.owner) {
+            self.owner_by_mint.remove(&holder.mint);
+        }
+        if let Some(mints) = self.mints_by_owner.get_mut(&holder.owner) {
+            mints.remove(&holder.mint);
+            if mints.is_empty() {
+                self.mints_by_owner.remove(&holder.owner);
+            }
+        }
+    }
+
+    fn with_owner(&self, mint: &Pubkey) -> Option<NftMetadata> {
+        self.metadata.get(mint).map(|(metadata, _)| NftMetadata {
+            owner: self.owner_by_mint.get(mint).copied(),
+            ..metadata.clone()
+        })
+    }
+}
+
+/// `mint -> metadata` and `owner -> mints` index
+#[derive(Default)]
+pub struct NftIndex {
+    state: RwLock<NftState>,
+}
+
+impl NftIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// NFTs currently held by `owner`, ordered by mint
+    pub fn by_owner(&self, owner: &Pubkey) -> Vec<NftMetadata> {
+        let Ok(state) = self.state.read() else {
+            return Vec::new();
+        };
+
+        let mut nfts: Vec<_> = state.mints_by_owner
+            .get(owner)
+            .into_iter()
+            .flatten()
+            .filter_map(|mint| state.with_owner(mint))
+            .collect();
+        nfts.sort_unstable_by(|a, b| a.mint.cmp(&b.mint));
+        nfts
+    }
+
+    /// Metadata and current holder of `mint`
+    pub fn by_mint(&self, mint: &Pubkey) -> Option<NftMetadata> {
+        self.state.read().ok()?.with_owner(mint)
+    }
+
+    /// Number of mints with decoded metadata
+    pub fn tracked_mints(&self) -> usize {
+        self.state.read().map(|state| state.metadata.len()).unwrap_or(0)
+    }
+
+    fn apply_metadata(state: &mut NftState, account: &AccountData, version: (u64, u64)) {
+        let parsed = NftMetadata::parse(account);
+
+        if let Some(mint) = state.metadata_accounts.get(&account.pubkey).copied() {
+            if state.metadata.get(&mint).is_some_and(|(_, current)| *current > version) {
+                return;
+            }
+            if parsed.as_ref().map(|metadata| metadata.mint) != Some(mint) {
+                state.metadata.remove(&mint);
+                state.metadata_accounts.remove(&account.pubkey);
+            }
+        }
+
+        if let Some(metadata) = parsed {
+            state.metadata_accounts.insert(account.pubkey, metadata.mint);
+            state.metadata.insert(metadata.mint, (metadata, version));
+        }
+    }
+
+    fn apply_token_account(state: &mut NftState, account: &AccountData, version: (u64, u64)) {
+        if state.holders.get(&account.pubkey).is_some_and(|holder| holder.version > version) {
+            return;
+        }
+        state.release(&account.pubkey);
+
+        // Only single-unit holdings can be an NFT; metadata may arrive later
+        if let Some(TokenProgramAccount::Account { mint, owner, amount: 1 }) = TokenProgramAccount::parse(account) {
+            if let Some(previous) = state.owner_by_mint.insert(mint, owner) {
+                if let Some(mints) = state.mints_by_owner.get_mut(&previous) {
+                    mints.remove(&mint);
+                }
+            }
+            state.mints_by_owner.entry(owner).or_default().insert(mint);
+            state.holders.insert(account.pubkey, Holder { mint, owner, version });
+        }
+    }
+}
+
+impl DerivedIndex for NftIndex {
+    fn name(&self) -> &'static str {
+        "nft_metadata"
+    }
+
+    fn apply_account(&self, account: &AccountData) {
+        let tracked = {
+            let Ok(state) = self.state.read() else {
+                return;
+            };
+            state.metadata_accounts.contains_key(&account.pubkey) || state.holders.contains_key(&account.pubkey)
+        };
+        if !tracked
+            && account.owner != TOKEN_METADATA_PROGRAM_ID
+            && TokenProgramAccount::parse(account).is_none()
+        {
+            return;
+        }
+
+        let Ok(mut state) = self.state.write() else {
+            return;
+        };
+        let version = (account.slot, account.write_version);
+
+        if account.owner == TOKEN_METADATA_PROGRAM_ID || state.metadata_accounts.contains_key(&account.pubkey) {
+            Self::apply_metadata(&mut state, account, version);
+        } else {
+            Self::apply_token_account(&mut state, account, version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::index::token::TOKEN_PROGRAM_ID};
+
+    fn borsh_string(out: &mut Vec<u8>, value: &str, padded: usize) {
+        out.extend_from_slice(&(padded as u32).to_le_bytes());
+        out.extend_from_slice(value.as_bytes());
+        out.resize(out.len() + padded - value.len(), 0);
+    }
+
+    fn metadata_account(mint: Pubkey, collection: Pubkey) -> AccountData {
+        let mut data = vec![KEY_METADATA_V1];
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(mint.as_ref());
+        borsh_string(&mut data, "Degen #1", 32);
+        borsh_string(&mut data, "DGN", 10);
+        borsh_string(&mut data, "https://example.com/1.json", 200);
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&[1, 1, 1, 254, 0, 1, 1]);
+        data.extend_from_slice(collection.as_ref());
+
+        AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 5_616_720,
+            owner: TOKEN_METADATA_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+            data,
+            write_version: 0,
+            slot: 1,
+            is_startup: false,
+            transaction_signature: None,
+        }
+    }
+
+    fn token_account(pubkey: Pubkey, mint: Pubkey, owner: Pubkey, slot: u64) -> AccountData {
+        let mut data = vec![0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&1u64.to_le_bytes());
+        data[108] = 1;
+
+        AccountData {
+            pubkey,
+            lamports: 2_039_280,
+            owner: TOKEN_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+            data,
+            write_version: 0,
+            slot,
+            is_startup: false,
+            transaction_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_metadata_and_transfers() {
+        let index = NftIndex::new();
+        let (mint, collection) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (alice_ata, bob_ata) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        index.apply_account(&token_account(alice_ata, mint, alice, 1));
+        index.apply_account(&metadata_account(mint, collection));
+
+        let nft = index.by_mint(&mint).unwrap();
+        assert_eq!(nft.name, "Degen #1");
+        assert_eq!(nft.uri, "https://example.com/1.json");
+        assert_eq!(nft.collection.unwrap().key, collection);
+        assert_eq!(nft.owner, Some(alice));
+
+        let mut emptied = token_account(alice_ata, mint, alice, 2);
+        emptied.data[64..72].copy_from_slice(&0u64.to_le_bytes());
+        index.apply_account(&emptied);
+        index.apply_account(&token_account(bob_ata, mint, bob, 2));
+        assert!(index.by_owner(&alice).is_empty());
+        assert_eq!(index.by_owner(&bob)[0].mint, mint);
+    }
+}
@@ -0,0 +1,167 @@
+//! Prefix search over pubkeys, signatures and blockhashes
+//!
+//! Keys are kept in their base58 form in one ordered map, so every key that
+//! starts with a prefix is a contiguous range. Account pubkeys stay indexed
+//! for as long as the account exists; transaction signatures and blockhashes
+//! are evicted oldest first once more than `capacity` of them are held. Vote
+//! transactions are not indexed.
+
+use {
+    super::DerivedIndex,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, VecDeque},
+        sync::RwLock,
+    },
+    windexer_common::types::{AccountData, BlockData, TransactionData},
+};
+
+/// Signatures and blockhashes kept by default
+pub const DEFAULT_SEARCH_CAPACITY: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Account,
+    Transaction,
+    Block,
+}
+
+/// A key matching a search prefix
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    /// Pubkey, signature or blockhash
+    pub key: String,
+    /// Slot the key was last seen at
+    pub slot: u64,
+}
+
+#[derive(Default)]
+struct SearchState {
+    keys: BTreeMap<String, (SearchKind, u64)>,
+    /// Signatures and blockhashes in insertion order, for eviction
+    recent: VecDeque<String>,
+}
+
+pub struct SearchIndex {
+    capacity: usize,
+    state: RwLock<SearchState>,
+}
+
+impl SearchIndex {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: RwLock::new(SearchState::default()),
+        }
+    }
+
+    /// Up to `limit` keys starting with `prefix`, in key order
+    pub fn search(&self, prefix: &str, limit: usize) -> Vec<SearchHit> {
+        let Ok(state) = self.state.read() else {
+            return Vec::new();
+        };
+
+        state.keys
+            .range::<str, _>(prefix..)
+            .take_while(|(key, _)| key.starts_with(prefix))
+            .take(limit)
+            .map(|(key, (kind, slot))| SearchHit {
+                kind: *kind,
+                key: key.clone(),
+                slot: *slot,
+            })
+            .collect()
+    }
+
+    fn insert_recent(&self, key: String, kind: SearchKind, slot: u64) {
+        let Ok(mut state) = self.state.write() else {
+            return;
+        };
+        if state.keys.insert(key.clone(), (kind, slot)).is_some() {
+            return;
+        }
+
+        state.recent.push_back(key);
+        while state.recent.len() > self.capacity {
+            if let Some(evicted) = state.recent.pop_front() {
+                state.keys.remove(&evicted);
+            }
+        }
+    }
+}
+
+impl DerivedIndex for SearchIndex {
+    fn name(&self) -> &'static str {
+        "search"
+    }
+
+    fn apply_account(&self, account: &AccountData) {
+        let Ok(mut state) = self.state.write() else {
+            return;
+        };
+        let key = account.pubkey.to_string();
+
+        // Closed accounts drop out, unless a newer update already reopened them
+        if account.lamports == 0 {
+            if state.keys.get(&key).is_some_and(|(_, slot)| *slot <= account.slot) {
+                state.keys.remove(&key);
+            }
+            return;
+        }
+        let slot = state.keys.get(&key).map_or(account.slot, |(_, slot)| account.slot.max(*slot));
+        state.keys.insert(key, (SearchKind::Account, slot));
+    }
+
+    fn apply_transaction(&self, transaction: &TransactionData) {
+        if transaction.is_vote {
+            return;
+        }
+        self.insert_recent(transaction.signature.to_string(), SearchKind::Transaction, transaction.slot);
+    }
+
+    fn apply_block(&self, block: &BlockData) {
+        if let Some(blockhash) = &block.blockhash {
+            self.insert_recent(blockhash.clone(), SearchKind::Block, block.slot);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::pubkey::Pubkey};
+
+    #[test]
+    fn finds_keys_by_prefix_and_evicts_old_blockhashes() {
+        let index = SearchIndex::new(1);
+        let account = AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1,
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+            data: Vec::new(),
+            write_version: 1,
+            slot: 5,
+            is_startup: false,
+            transaction_signature: None,
+        };
+        index.apply_account(&account);
+        for (slot, blockhash) in [(6, "Blockhash1"), (7, "Blockhash2")] {
+            index.apply_block(&BlockData {
+                slot,
+                blockhash: Some(blockhash.to_string()),
+                ..Default::default()
+            });
+        }
+
+        let pubkey = account.pubkey.to_string();
+        let hits = index.search(&pubkey[..8], 10);
+        assert_eq!(hits, vec![SearchHit { kind: SearchKind::Account, key: pubkey, slot: 5 }]);
+
+        let hits = index.search("Blockhash", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].slot, 7);
+    }
+}
@@ -0,0 +1,289 @@
+//! SPL token balance index
+//!
+//! Parses SPL Token and Token-2022 accounts as they are written and keeps
+//! per-owner balances and per-mint holder sets. An owner may hold several
+//! token accounts for the same mint; their amounts are summed.
+//!
+//! Token accounts are 165 bytes (`mint`, `owner`, `amount`, ...). Token-2022
+//! accounts with extensions are longer and carry an account type byte at
+//! offset 165. Mints are read for their `decimals` only.
+
+use {
+    super::DerivedIndex,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{pubkey, pubkey::Pubkey},
+    std::{
+        collections::{HashMap, HashSet},
+        sync::RwLock,
+    },
+    windexer_common::types::AccountData,
+};
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const MINT_LEN: usize = 82;
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+const STATE_OFFSET: usize = 108;
+const DECIMALS_OFFSET: usize = 44;
+const MINT_INITIALIZED_OFFSET: usize = 45;
+
+/// Balance of one mint held by one owner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenBalance {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    /// Raw amount in base units
+    pub amount: u64,
+    /// Mint decimals, if the mint account has been seen
+    pub decimals: Option<u8>,
+    /// Number of token accounts the amount is summed over
+    pub token_accounts: u32,
+}
+
+/// A decoded token program account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenProgramAccount {
+    Account { mint: Pubkey, owner: Pubkey, amount: u64 },
+    Mint { decimals: u8 },
+}
+
+impl TokenProgramAccount {
+    /// Decode an account owned by either token program
+    pub(crate) fn parse(account: &AccountData) -> Option<Self> {
+        if account.lamports == 0 {
+            return None;
+        }
+        Self::parse_data(&account.owner, &account.data)
+    }
+
+    /// Decode raw account data owned by `owner`
+    pub(crate) fn parse_data(owner: &Pubkey, data: &[u8]) -> Option<Self> {
+        if *owner != TOKEN_PROGRAM_ID && *owner != TOKEN_2022_PROGRAM_ID {
+            return None;
+        }
+
+        let account_type = match data.len() {
+            MINT_LEN => ACCOUNT_TYPE_MINT,
+            TOKEN_ACCOUNT_LEN => ACCOUNT_TYPE_ACCOUNT,
+            len if len > ACCOUNT_TYPE_OFFSET && *owner == TOKEN_2022_PROGRAM_ID => {
+                data[ACCOUNT_TYPE_OFFSET]
+            }
+            _ => return None,
+        };
+
+        match account_type {
+            ACCOUNT_TYPE_ACCOUNT if data[STATE_OFFSET] != 0 => Some(Self::Account {
+                mint: Pubkey::try_from(&data[0..32]).ok()?,
+                owner: Pubkey::try_from(&data[32..64]).ok()?,
+                amount: u64::from_le_bytes(data[64..72].try_into().ok()?),
+            }),
+            ACCOUNT_TYPE_MINT if data[MINT_INITIALIZED_OFFSET] != 0 => Some(Self::Mint {
+                decimals: data[DECIMALS_OFFSET],
+            }),
+            _ => None,
+        }
+    }
+}
+
+struct TrackedAccount {
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+    version: (u64, u64),
+}
+
+#[derive(Default, Clone, Copy)]
+struct Holding {
+    amount: u64,
+    token_accounts: u32,
+}
+
+#[derive(Default)]
+struct TokenState {
+    accounts: HashMap<Pubkey, TrackedAccount>,
+    holdings: HashMap<(Pubkey, Pubkey), Holding>,
+    mints_by_owner: HashMap<Pubkey, HashSet<Pubkey>>,
+    owners_by_mint: HashMap<Pubkey, HashSet<Pubkey>>,
+    decimals: HashMap<Pubkey, u8>,
+}
+
+impl TokenState {
+    fn add(&mut self, owner: Pubkey, mint: Pubkey, amount: u64) {
+        let holding = self.holdings.entry((owner, mint)).or_default();
+        holding.amount = holding.amount.saturating_add(amount);
+        holding.token_accounts += 1;
+        self.mints_by_owner.entry(owner).or_default().insert(mint);
+        self.owners_by_mint.entry(mint).or_default().insert(owner);
+    }
+
+    fn remove(&mut self, owner: Pubkey, mint: Pubkey, amount: u64) {
+        let Some(holding) = self.holdings.get_mut(&(owner, mint)) else {
+            return;
+        };
+        holding.amount = holding.amount.saturating_sub(amount);
+        holding.token_accounts = holding.token_accounts.saturating_sub(1);
+
+        if holding.token_accounts == 0 {
+            self.holdings.remove(&(owner, mint));
+            if let Some(mints) = self.mints_by_owner.get_mut(&owner) {
+                mints.remove(&mint);
+                if mints.is_empty() {
+                    self.mints_by_owner.remove(&owner);
+                }
+            }
+            if let Some(owners) = self.owners_by_mint.get_mut(&mint) {
+                owners.remove(&owner);
+                if owners.is_empty() {
+                    self.owners_by_mint.remove(&mint);
+                }
+            }
+        }
+    }
+
+    fn balance(&self, owner: Pubkey, mint: Pubkey) -> Option<TokenBalance> {
+        self.holdings.get(&(owner, mint)).map(|holding| TokenBalance {
+            owner,
+            mint,
+            amount: holding.amount,
+            decimals: self.decimals.get(&mint).copied(),
+            token_accounts: holding.token_accounts,
+        })
+    }
+}
+
+/// `(owner, mint) -> balance` and `mint -> holders` index
+#[derive(Default)]
+pub struct TokenIndex {
+    state: RwLock<TokenState>,
+}
+
+impl TokenIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Balances of every mint held by `owner`, largest first
+    pub fn balances(&self, owner: &Pubkey) -> Vec<TokenBalance> {
+        let Ok(state) = self.state.read() else {
+            return Vec::new();
+        };
+
+        let mut balances: Vec<_> = state.mints_by_owner
+            .get(owner)
+            .into_iter()
+            .flatten()
+            .filter_map(|mint| state.balance(*owner, *mint))
+            .collect();
+        balances.sort_unstable_by(|a, b| b.amount.cmp(&a.amount).then(a.mint.cmp(&b.mint)));
+        balances
+    }
+
+    /// Owners with a non-zero balance of `mint`, largest first
+    pub fn holders(&self, mint: &Pubkey, limit: usize) -> Vec<TokenBalance> {
+        let Ok(state) = self.state.read() else {
+            return Vec::new();
+        };
+
+        let mut holders: Vec<_> = state.owners_by_mint
+            .get(mint)
+            .into_iter()
+            .flatten()
+            .filter_map(|owner| state.balance(*owner, *mint))
+            .filter(|balance| balance.amount > 0)
+            .collect();
+        holders.sort_unstable_by(|a, b| b.amount.cmp(&a.amount).then(a.owner.cmp(&b.owner)));
+        holders.truncate(limit);
+        holders
+    }
+
+    /// Number of token accounts currently tracked
+    pub fn tracked_accounts(&self) -> usize {
+        self.state.read().map(|state| state.accounts.len()).unwrap_or(0)
+    }
+}
+
+impl DerivedIndex for TokenIndex {
+    fn name(&self) -> &'static str {
+        "token_balances"
+    }
+
+    fn apply_account(&self, account: &AccountData) {
+        let Ok(mut state) = self.state.write() else {
+            return;
+        };
+        let version = (account.slot, account.write_version);
+
+        if let Some(tracked) = state.accounts.get(&account.pubkey) {
+            if tracked.version > version {
+                return;
+            }
+        }
+
+        let parsed = TokenProgramAccount::parse(account);
+
+        if let Some(previous) = state.accounts.remove(&account.pubkey) {
+            state.remove(previous.owner, previous.mint, previous.amount);
+        }
+
+        match parsed {
+            Some(TokenProgramAccount::Account { mint, owner, amount }) => {
+                state.add(owner, mint, amount);
+                state.accounts.insert(account.pubkey, TrackedAccount { mint, owner, amount, version });
+            }
+            Some(TokenProgramAccount::Mint { decimals }) => {
+                state.decimals.insert(account.pubkey, decimals);
+            }
+            // Closed or reassigned accounts simply drop out of the index
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_account(pubkey: Pubkey, mint: Pubkey, owner: Pubkey, amount: u64, slot: u64) -> AccountData {
+        let mut data = vec![0u8; TOKEN_ACCOUNT_LEN];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[STATE_OFFSET] = 1;
+
+        AccountData {
+            pubkey,
+            lamports: 2_039_280,
+            owner: TOKEN_PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+            data,
+            write_version: 0,
+            slot,
+            is_startup: false,
+            transaction_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_balances_follow_updates_and_closes() {
+        let index = TokenIndex::new();
+        let (mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        index.apply_account(&token_account(first, mint, owner, 100, 1));
+        index.apply_account(&token_account(second, mint, owner, 50, 1));
+        index.apply_account(&token_account(first, mint, owner, 70, 2));
+        assert_eq!(index.balances(&owner)[0].amount, 120);
+        assert_eq!(index.holders(&mint, 10)[0].token_accounts, 2);
+
+        let mut closed = token_account(second, mint, owner, 0, 3);
+        closed.lamports = 0;
+        index.apply_account(&closed);
+        assert_eq!(index.balances(&owner)[0].amount, 70);
+        assert_eq!(index.tracked_accounts(), 1);
+    }
+}
@@ -0,0 +1,156 @@
+//! Ingestion of published frames
+//!
+//! Nodes receive what validators publish as frames of the common wire
+//! format. [`ingest_frame`] writes one frame to a [`Storage`] backend in a
+//! single batch. Backends don't keep entries, so entry frames are skipped.
+//!
+//! Account patches are applied to the last version of their account
+//! ingested, kept in [`PatchBases`], or else to the stored version. Reads
+//! alone would miss versions still staged by a fork-aware store or buffered
+//! by a batching one. A patch taken from a version neither holds is skipped;
+//! the account is corrected by the next full update the publisher sends.
+//!
+//! [`StoreRedelivery`] retries the dead letters of a store the same way.
+
+use {
+    crate::traits::Storage,
+    anyhow::Result,
+    async_trait::async_trait,
+    lru::LruCache,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        num::NonZeroUsize,
+        sync::{Arc, Mutex},
+    },
+    tracing::debug,
+    windexer_common::{
+        dlq::Redeliver,
+        types::{
+            wire::{WireFrame, WireKind},
+            AccountData, AccountPatch, BlockData, TransactionData,
+        },
+    },
+};
+
+/// Accounts whose last ingested version is kept as a patch base
+pub const DEFAULT_PATCH_BASES: usize = 10_000;
+
+/// Last version ingested of recently updated accounts
+pub struct PatchBases {
+    accounts: Mutex<LruCache<Pubkey, AccountData>>,
+}
+
+impl Default for PatchBases {
+    fn default() -> Self {
+        Self::new(DEFAULT_PATCH_BASES)
+    }
+}
+
+impl PatchBases {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { accounts: Mutex::new(LruCache::new(capacity)) }
+    }
+
+    fn get(&self, pubkey: &Pubkey) -> Option<AccountData> {
+        self.accounts.lock().ok()?.get(pubkey).cloned()
+    }
+
+    /// Keep the newest of `accounts` and the versions already kept
+    fn record(&self, accounts: &[AccountData]) {
+        let Ok(mut bases) = self.accounts.lock() else {
+            return;
+        };
+        for account in accounts {
+            let newer = bases.peek(&account.pubkey).map_or(true, |base| {
+                (account.slot, account.write_version) >= (base.slot, base.write_version)
+            });
+            if newer {
+                bases.put(account.pubkey, account.clone());
+            }
+        }
+    }
+}
+
+/// Writes the records of a frame, returning how many were stored
+pub async fn ingest_frame(storage: &dyn Storage, bases: &PatchBases, payload: &[u8]) -> Result<usize> {
+    let frame = WireFrame::parse(payload)?;
+    match frame.kind {
+        WireKind::Accounts => {
+            let accounts = frame.decode::<AccountData>()?;
+            let count = accounts.len();
+            bases.record(&accounts);
+            storage.store_accounts(accounts).await?;
+            Ok(count)
+        }
+        WireKind::Transactions => {
+            let transactions = frame.decode::<TransactionData>()?;
+            let count = transactions.len();
+            storage.store_transactions(transactions).await?;
+            Ok(count)
+        }
+        WireKind::Blocks => {
+            let blocks = frame.decode::<BlockData>()?;
+            let count = blocks.len();
+            storage.store_blocks(blocks).await?;
+            Ok(count)
+        }
+        WireKind::Entries => Ok(0),
+        WireKind::AccountPatches => {
+            let accounts = apply_patches(storage, bases, frame.decode::<AccountPatch>()?).await?;
+            let count = accounts.len();
+            bases.record(&accounts);
+            storage.store_accounts(accounts).await?;
+            Ok(count)
+        }
+    }
+}
+
+/// The accounts `patches` rebuild, in order; a frame can patch an account
+/// more than once, each onto the previous patch
+async fn apply_patches(storage: &dyn Storage, bases: &PatchBases, patches: Vec<AccountPatch>) -> Result<Vec<AccountData>> {
+    let mut patched: HashMap<_, usize> = HashMap::new();
+    let mut accounts: Vec<AccountData> = Vec::with_capacity(patches.len());
+    for patch in patches {
+        let applied = match patched.get(&patch.pubkey) {
+            Some(&index) => Some(patch.apply(&accounts[index])),
+            None => {
+                let cached = bases.get(&patch.pubkey).filter(|base| base.write_version == patch.base_write_version);
+                let base = match cached {
+                    Some(base) => Some(base),
+                    None => storage.get_account(&patch.pubkey.to_string()).await?,
+                };
+                base.map(|base| patch.apply(&base))
+            }
+        };
+        match applied {
+            Some(Ok(account)) => {
+                patched.insert(account.pubkey, accounts.len());
+                accounts.push(account);
+            }
+            Some(Err(e)) => debug!("Skipping account patch: {}", e),
+            None => debug!("Skipping patch of {}, which isn't stored", patch.pubkey),
+        }
+    }
+    Ok(accounts)
+}
+
+/// Writes dead letters to a storage backend again, as frames
+pub struct StoreRedelivery {
+    storage: Arc<dyn Storage>,
+    bases: PatchBases,
+}
+
+impl StoreRedelivery {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage, bases: PatchBases::default() }
+    }
+}
+
+#[async_trait]
+impl Redeliver for StoreRedelivery {
+    async fn redeliver(&self, _source: &str, payload: &[u8]) -> Result<()> {
+        ingest_frame(self.storage.as_ref(), &self.bases, payload).await.map(drop)
+    }
+}
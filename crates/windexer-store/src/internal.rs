@@ -6,12 +6,33 @@ use {
     },
     rocksdb::{
         DB, Options, ReadOptions, WriteBatch, ColumnFamilyDescriptor, Cache, 
-        DBCompressionType, BlockBasedOptions, SliceTransform,
+        DBCompressionType, BlockBasedOptions, SliceTransform, Direction, IteratorMode,
+        MergeOperands,
     },
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::str::FromStr,
+    crate::{
+        compression::{self, CompressionMetrics, Compressor},
+        retention::DataKind,
+        traits::{SlotBundle, Storage},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    async_trait::async_trait,
+    futures::stream::{self, BoxStream, StreamExt},
+    tokio::sync::mpsc,
+    windexer_common::config::storage::CompressionConfig,
     windexer_common::types::{
         AccountData,
         TransactionData,
         BlockData,
+        IndexerState,
+        SlotStatusData,
+        ApiKeyRecord,
+        SlotAttestation,
+        OperatorRecord,
+        DelegationRecord,
+        PerformanceRecord,
+        SlashRecord,
     },
 };
 
@@ -19,17 +40,145 @@ pub const CF_ACCOUNTS: &str = "accounts";
 pub const CF_TRANSACTIONS: &str = "transactions";
 pub const CF_BLOCKS: &str = "blocks";
 pub const CF_METADATA: &str = "metadata";
+pub const CF_TX_BY_ACCOUNT: &str = "tx_by_account";
+pub const CF_TX_BY_PROGRAM: &str = "tx_by_program";
+pub const CF_ACCOUNT_HISTORY: &str = "account_history";
+/// Operator and delegation accounting of the staking service
+pub const CF_STAKING: &str = "staking";
+
+const KEY_LATEST_ROOTED_SLOT: &[u8] = b"latest_rooted_slot";
+const KEY_INDEXER_STATE: &[u8] = b"indexer_state";
+
+/// API keys are stored in the metadata family as `api_key/<id>`
+const API_KEY_PREFIX: &[u8] = b"api_key/";
+
+fn api_key_key(id: &str) -> Vec<u8> {
+    [API_KEY_PREFIX, id.as_bytes()].concat()
+}
+
+/// Staking records are stored as `operator/<operator>` and
+/// `delegation/<operator>/<staker>`
+const OPERATOR_PREFIX: &[u8] = b"operator/";
+const DELEGATION_PREFIX: &[u8] = b"delegation/";
+
+fn operator_key(operator: &str) -> Vec<u8> {
+    [OPERATOR_PREFIX, operator.as_bytes()].concat()
+}
+
+fn delegation_key(operator: &str, staker: &str) -> Vec<u8> {
+    [DELEGATION_PREFIX, operator.as_bytes(), b"/", staker.as_bytes()].concat()
+}
+
+/// Slashes are stored as `slash/<timestamp><operator><stake before>`, in the
+/// order they happened; the stake before tells apart slashes of one operator
+/// within a second
+const SLASH_PREFIX: &[u8] = b"slash/";
+/// First key after every slash
+const SLASH_END: &[u8] = b"slash0";
+
+fn slash_key(record: &SlashRecord) -> Vec<u8> {
+    [
+        SLASH_PREFIX,
+        &(record.timestamp.max(0) as u64).to_be_bytes(),
+        record.operator.as_bytes(),
+        &record.stake_before.to_be_bytes(),
+    ]
+    .concat()
+}
+
+/// Performance records are stored as `performance/<epoch><operator>`, so
+/// those of past epochs are pruned from the front
+const PERFORMANCE_PREFIX: &[u8] = b"performance/";
+
+fn performance_key(epoch: u64, operator: &str) -> Vec<u8> {
+    [PERFORMANCE_PREFIX, &epoch.to_be_bytes(), operator.as_bytes()].concat()
+}
+
+const ATTESTATION_PREFIX: &[u8] = b"attestation/";
+
+fn attestation_key(slot: u64) -> Vec<u8> {
+    [ATTESTATION_PREFIX, &slot.to_be_bytes()].concat()
+}
+
+/// Rows buffered between the RocksDB scan thread and a stream consumer
+const STREAM_BUFFER: usize = 1024;
+
+/// Transactions deleted per write batch when pruning
+const PRUNE_CHUNK: usize = 10_000;
+
+/// First key after every key starting with `prefix`, if there is one
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Index keys are `pubkey (32) | slot (8, big-endian) | signature (64)`
+const INDEX_KEY_LEN: usize = 32 + 8 + 64;
+
+fn tx_index_key(pubkey: &Pubkey, slot: u64, signature: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(INDEX_KEY_LEN);
+    key.extend_from_slice(pubkey.as_ref());
+    key.extend_from_slice(&slot.to_be_bytes());
+    key.extend_from_slice(signature);
+    key
+}
+
+/// Account history keys are `pubkey (32) | slot (8, big-endian) | write_version (8, big-endian)`
+const HISTORY_KEY_LEN: usize = 32 + 8 + 8;
+
+fn account_history_key(pubkey: &Pubkey, slot: u64, write_version: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(HISTORY_KEY_LEN);
+    key.extend_from_slice(pubkey.as_ref());
+    key.extend_from_slice(&slot.to_be_bytes());
+    key.extend_from_slice(&write_version.to_be_bytes());
+    key
+}
+
+/// Decode a stored account, decompressing its data payload
+fn decode_account(bytes: &[u8]) -> Result<AccountData> {
+    let mut account: AccountData = bincode::deserialize(bytes)?;
+    account.data = compression::decompress(&account.data)?;
+    Ok(account)
+}
+
+/// `(slot, write_version)` of a stored account; unreadable values sort first
+fn account_version(value: &[u8]) -> (u64, u64) {
+    bincode::deserialize::<AccountData>(value)
+        .map(|account| (account.slot, account.write_version))
+        .unwrap_or_default()
+}
+
+/// Merge operator of the latest-state account table: keeps whichever of the
+/// stored account and the merged versions has the highest
+/// `(slot, write_version)`, the later one on ties. RocksDB applies it under
+/// its own locking, so concurrent writers can't regress an account the way
+/// a read followed by a put could.
+fn merge_latest_account(_key: &[u8], existing: Option<&[u8]>, operands: &MergeOperands) -> Option<Vec<u8>> {
+    existing
+        .into_iter()
+        .chain(operands.iter())
+        .max_by_key(|value| account_version(value))
+        .map(<[u8]>::to_vec)
+}
 
 #[derive(Clone, Debug)]
 pub struct StoreConfig {
     pub path: PathBuf,
     pub max_open_files: i32,
     pub cache_capacity: usize,
+    pub compression: CompressionConfig,
 }
 
 #[derive(Clone)]
 pub struct Store {
     db: Arc<DB>,
+    compressor: Arc<Compressor>,
 }
 
 impl Store {
@@ -60,59 +209,140 @@ impl Store {
         
         // Define column families
         let cf_opts = options.clone();
-        let cf_accounts = ColumnFamilyDescriptor::new(CF_ACCOUNTS, cf_opts.clone());
+        let mut accounts_opts = cf_opts.clone();
+        accounts_opts.set_merge_operator_associative("latest_account", merge_latest_account);
+        let cf_accounts = ColumnFamilyDescriptor::new(CF_ACCOUNTS, accounts_opts);
         let cf_transactions = ColumnFamilyDescriptor::new(CF_TRANSACTIONS, cf_opts.clone());
         let cf_blocks = ColumnFamilyDescriptor::new(CF_BLOCKS, cf_opts.clone());
         let cf_metadata = ColumnFamilyDescriptor::new(CF_METADATA, cf_opts.clone());
+        let cf_tx_by_account = ColumnFamilyDescriptor::new(CF_TX_BY_ACCOUNT, cf_opts.clone());
+        let cf_tx_by_program = ColumnFamilyDescriptor::new(CF_TX_BY_PROGRAM, cf_opts.clone());
+        let cf_account_history = ColumnFamilyDescriptor::new(CF_ACCOUNT_HISTORY, cf_opts.clone());
+        let cf_staking = ColumnFamilyDescriptor::new(CF_STAKING, cf_opts.clone());
         
         // Open database
         let db = DB::open_cf_descriptors(
             &options, 
             &path, 
-            vec![cf_accounts, cf_transactions, cf_blocks, cf_metadata]
+            vec![
+                cf_accounts, cf_transactions, cf_blocks, cf_metadata,
+                cf_tx_by_account, cf_tx_by_program, cf_account_history,
+                cf_staking,
+            ]
         )?;
         
         Ok(Self {
             db: Arc::new(db),
+            compressor: Arc::new(Compressor::new(config.compression)),
         })
     }
     
+    /// Get the account data compression metrics
+    pub fn compression_metrics(&self) -> &CompressionMetrics {
+        self.compressor.metrics()
+    }
+    
     pub fn store_account(&self, account: AccountData) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        self.batch_account(&mut batch, account)?;
+        self.db.write(batch)?;
+        
+        Ok(())
+    }
+    
+    pub fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        // Write the transaction and its index entries atomically
+        let mut batch = WriteBatch::default();
+        self.batch_transaction(&mut batch, &transaction)?;
+        self.db.write(batch)?;
+        
+        Ok(())
+    }
+    
+    pub fn store_block(&self, block: BlockData) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        self.batch_block(&mut batch, &block)?;
+        self.db.write(batch)?;
+        
+        Ok(())
+    }
+    
+    /// Write a block, its transactions and its account updates in one batch
+    pub fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        let SlotBundle { block, transactions, mut accounts } = bundle;
+        
+        // Equal versions resolve to the later merge, so apply account
+        // versions oldest first
+        accounts.sort_by_key(|a| (a.slot, a.write_version));
+        
+        let mut batch = WriteBatch::default();
+        for account in accounts {
+            self.batch_account(&mut batch, account)?;
+        }
+        for transaction in &transactions {
+            self.batch_transaction(&mut batch, transaction)?;
+        }
+        self.batch_block(&mut batch, &block)?;
+        self.db.write(batch)?;
+        
+        Ok(())
+    }
+    
+    fn batch_account(&self, batch: &mut WriteBatch, mut account: AccountData) -> Result<()> {
         let cf = self.db.cf_handle(CF_ACCOUNTS)
             .ok_or_else(|| anyhow!("Column family '{}' not found", CF_ACCOUNTS))?;
+        let cf_history = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_ACCOUNT_HISTORY))?;
         
-        // Serialize account to byte array
+        // Serialize account to byte array with its data payload compressed
+        account.data = self.compressor.compress(&account.data)?;
         let data = bincode::serialize(&account)?;
+        let history_key = account_history_key(&account.pubkey, account.slot, account.write_version);
         
-        // Store in RocksDB
-        self.db.put_cf(&cf, account.pubkey.as_bytes(), &data)?;
+        // Every version goes into the history; the latest-state table is
+        // merged so out-of-order updates cannot regress it
+        batch.put_cf(&cf_history, &history_key, &data);
+        batch.merge_cf(&cf, account.pubkey.as_ref(), &data);
         
         Ok(())
     }
     
-    pub fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+    fn batch_transaction(&self, batch: &mut WriteBatch, transaction: &TransactionData) -> Result<()> {
         let cf = self.db.cf_handle(CF_TRANSACTIONS)
             .ok_or_else(|| anyhow!("Column family '{}' not found", CF_TRANSACTIONS))?;
         
+        let cf_by_account = self.db.cf_handle(CF_TX_BY_ACCOUNT)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_TX_BY_ACCOUNT))?;
+        let cf_by_program = self.db.cf_handle(CF_TX_BY_PROGRAM)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_TX_BY_PROGRAM))?;
+        
         // Serialize transaction to byte array
-        let data = bincode::serialize(&transaction)?;
+        let data = bincode::serialize(transaction)?;
+        let signature = transaction.signature.as_ref();
+        
+        batch.put_cf(&cf, signature, &data);
         
-        // Store in RocksDB
-        self.db.put_cf(&cf, transaction.signature.as_bytes(), &data)?;
+        for account in &transaction.message.account_keys {
+            batch.put_cf(&cf_by_account, tx_index_key(account, transaction.slot, signature), []);
+        }
+        
+        for program_id in transaction.message.program_ids() {
+            batch.put_cf(&cf_by_program, tx_index_key(program_id, transaction.slot, signature), []);
+        }
         
         Ok(())
     }
     
-    pub fn store_block(&self, block: BlockData) -> Result<()> {
+    fn batch_block(&self, batch: &mut WriteBatch, block: &BlockData) -> Result<()> {
         let cf = self.db.cf_handle(CF_BLOCKS)
             .ok_or_else(|| anyhow!("Column family '{}' not found", CF_BLOCKS))?;
         
         // Serialize block to byte array
-        let data = bincode::serialize(&block)?;
+        let data = bincode::serialize(block)?;
         
         // Store in RocksDB using slot as key
         let key = block.slot.to_be_bytes();
-        self.db.put_cf(&cf, &key, &data)?;
+        batch.put_cf(&cf, key, &data);
         
         Ok(())
     }
@@ -121,9 +351,12 @@ impl Store {
         let cf = self.db.cf_handle(CF_ACCOUNTS)
             .ok_or_else(|| anyhow!("Column family '{}' not found", CF_ACCOUNTS))?;
         
-        match self.db.get_cf(&cf, pubkey.as_bytes())? {
+        let pubkey = Pubkey::from_str(pubkey)
+            .map_err(|e| anyhow!("Invalid pubkey '{}': {}", pubkey, e))?;
+        
+        match self.db.get_cf(&cf, pubkey.as_ref())? {
             Some(data) => {
-                let account: AccountData = bincode::deserialize(&data)?;
+                let account = decode_account(&data)?;
                 Ok(Some(account))
             },
             None => Ok(None),
@@ -134,7 +367,10 @@ impl Store {
         let cf = self.db.cf_handle(CF_TRANSACTIONS)
             .ok_or_else(|| anyhow!("Column family '{}' not found", CF_TRANSACTIONS))?;
         
-        match self.db.get_cf(&cf, signature.as_bytes())? {
+        let signature = Signature::from_str(signature)
+            .map_err(|e| anyhow!("Invalid signature '{}': {}", signature, e))?;
+        
+        match self.db.get_cf(&cf, signature.as_ref())? {
             Some(data) => {
                 let tx: TransactionData = bincode::deserialize(&data)?;
                 Ok(Some(tx))
@@ -165,7 +401,7 @@ impl Store {
         let iter = self.db.iterator_cf(&cf, rocksdb::IteratorMode::End);
         
         for (_, value) in iter.take(limit) {
-            let account: AccountData = bincode::deserialize(&value)?;
+            let account = decode_account(&value)?;
             accounts.push(account);
         }
         
@@ -199,7 +435,7 @@ impl Store {
                 break;
             }
             
-            let account: AccountData = bincode::deserialize(&value)?;
+            let account = decode_account(&value)?;
             
             if account.slot >= start_slot && account.slot <= end_slot {
                 accounts.push(account);
@@ -230,4 +466,779 @@ impl Store {
         
         Ok(transactions)
     }
-} 
\ No newline at end of file
+    
+    pub fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        let cf = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_BLOCKS))?;
+        
+        let mut blocks = Vec::with_capacity(limit);
+        for item in self.db.iterator_cf(&cf, IteratorMode::End).take(limit) {
+            let (_, value) = item?;
+            blocks.push(bincode::deserialize(&value)?);
+        }
+        
+        Ok(blocks)
+    }
+    
+    pub fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        let cf = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_BLOCKS))?;
+        
+        let start = start_slot.to_be_bytes();
+        let end = end_slot.to_be_bytes();
+        
+        let mut blocks = Vec::with_capacity(limit);
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(&start, Direction::Forward)) {
+            if blocks.len() >= limit {
+                break;
+            }
+            
+            let (key, value) = item?;
+            if key.as_ref() > end.as_slice() {
+                break;
+            }
+            blocks.push(bincode::deserialize(&value)?);
+        }
+        
+        Ok(blocks)
+    }
+    
+    /// Scan a column family on a blocking thread, streaming every value whose
+    /// slot falls within `[start_slot, end_slot]`
+    fn stream_slot_range<T, D, F>(&self, cf_name: &'static str, start_slot: u64, end_slot: u64, decode: D, slot_of: F) -> BoxStream<'static, Result<T>>
+    where
+        T: Send + 'static,
+        D: Fn(&[u8]) -> Result<T> + Send + 'static,
+        F: Fn(&T) -> u64 + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let db = self.db.clone();
+        
+        tokio::task::spawn_blocking(move || {
+            let Some(cf) = db.cf_handle(cf_name) else {
+                let _ = sender.blocking_send(Err(anyhow!("Column family '{}' not found", cf_name)));
+                return;
+            };
+            
+            for item in db.iterator_cf(&cf, IteratorMode::Start) {
+                let value = match item {
+                    Ok((_, value)) => decode(&value),
+                    Err(e) => Err(e.into()),
+                };
+                
+                let in_range = match &value {
+                    Ok(record) => (start_slot..=end_slot).contains(&slot_of(record)),
+                    Err(_) => true,
+                };
+                
+                // Stop scanning once the consumer has gone away
+                if in_range && sender.blocking_send(value).is_err() {
+                    return;
+                }
+            }
+        });
+        
+        stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|item| (item, receiver))
+        })
+        .boxed()
+    }
+    
+    /// Record a slot status, updating the stored block and the rooted watermark
+    pub fn update_slot_status(&self, status: &SlotStatusData) -> Result<()> {
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_BLOCKS))?;
+        let cf_metadata = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        let mut batch = WriteBatch::default();
+        
+        let key = status.slot.to_be_bytes();
+        if let Some(data) = self.db.get_cf(&cf_blocks, &key)? {
+            let mut block: BlockData = bincode::deserialize(&data)?;
+            block.status = status.status.clone();
+            batch.put_cf(&cf_blocks, &key, bincode::serialize(&block)?);
+        }
+        
+        if let SlotStatus::Rooted = status.status {
+            if self.get_latest_rooted_slot()?.map_or(true, |latest| status.slot > latest) {
+                batch.put_cf(&cf_metadata, KEY_LATEST_ROOTED_SLOT, status.slot.to_be_bytes());
+            }
+        }
+        
+        self.db.write(batch)?;
+        Ok(())
+    }
+    
+    pub fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        match self.db.get_cf(&cf, KEY_LATEST_ROOTED_SLOT)? {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes.as_slice().try_into()
+                    .map_err(|_| anyhow!("Corrupt latest rooted slot"))?;
+                Ok(Some(u64::from_be_bytes(bytes)))
+            },
+            None => Ok(None),
+        }
+    }
+    
+    pub fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        self.db.put_cf(&cf, KEY_INDEXER_STATE, serde_json::to_vec(state)?)?;
+        Ok(())
+    }
+    
+    pub fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        match self.db.get_cf(&cf, KEY_INDEXER_STATE)? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+    
+    pub fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        self.db.put_cf(&cf, api_key_key(&key.id), serde_json::to_vec(key)?)?;
+        Ok(())
+    }
+    
+    pub fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        let mut keys = Vec::new();
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(API_KEY_PREFIX, Direction::Forward)) {
+            let (key, value) = item?;
+            if !key.starts_with(API_KEY_PREFIX) {
+                break;
+            }
+            keys.push(serde_json::from_slice(&value)?);
+        }
+        
+        Ok(keys)
+    }
+    
+    pub fn delete_api_key(&self, id: &str) -> Result<bool> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        let key = api_key_key(id);
+        let existed = self.db.get_cf(&cf, &key)?.is_some();
+        self.db.delete_cf(&cf, key)?;
+        Ok(existed)
+    }
+    
+    pub fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        self.db.put_cf(&cf, operator_key(&record.operator), serde_json::to_vec(record)?)?;
+        Ok(())
+    }
+    
+    pub fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.load_staking_records(OPERATOR_PREFIX)
+    }
+    
+    pub fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        self.db.put_cf(&cf, delegation_key(&record.operator, &record.staker), serde_json::to_vec(record)?)?;
+        Ok(())
+    }
+    
+    pub fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.load_staking_records(DELEGATION_PREFIX)
+    }
+    
+    pub fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        let key = delegation_key(operator, staker);
+        let existed = self.db.get_cf(&cf, &key)?.is_some();
+        self.db.delete_cf(&cf, key)?;
+        Ok(existed)
+    }
+    
+    pub fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        self.db.put_cf(&cf, slash_key(record), serde_json::to_vec(record)?)?;
+        Ok(())
+    }
+    
+    pub fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        let mut records = Vec::new();
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(SLASH_END, Direction::Reverse)) {
+            if records.len() >= limit {
+                break;
+            }
+            let (key, value) = item?;
+            if !key.starts_with(SLASH_PREFIX) {
+                break;
+            }
+            let record: SlashRecord = serde_json::from_slice(&value)?;
+            if operator.map_or(true, |operator| record.operator == operator) {
+                records.push(record);
+            }
+        }
+        
+        Ok(records)
+    }
+    
+    pub fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        self.db.put_cf(&cf, performance_key(record.epoch, &record.operator), serde_json::to_vec(record)?)?;
+        Ok(())
+    }
+    
+    pub fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        let records: Vec<PerformanceRecord> = self.load_staking_records(PERFORMANCE_PREFIX)?;
+        Ok(records
+            .into_iter()
+            .filter(|record| record.timestamp >= since)
+            .filter(|record| operator.map_or(true, |operator| record.operator == operator))
+            .collect())
+    }
+    
+    pub fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        let end = performance_key(epoch, "");
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(PERFORMANCE_PREFIX, Direction::Forward)) {
+            let (key, _) = item?;
+            if !key.starts_with(PERFORMANCE_PREFIX) || key.as_ref() >= end.as_slice() {
+                break;
+            }
+            batch.delete_cf(&cf, key);
+        }
+        let pruned = batch.len();
+        self.db.write(batch)?;
+        Ok(pruned)
+    }
+    
+    fn load_staking_records<T: serde::de::DeserializeOwned>(&self, prefix: &[u8]) -> Result<Vec<T>> {
+        let cf = self.db.cf_handle(CF_STAKING)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_STAKING))?;
+        
+        let mut records = Vec::new();
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(prefix, Direction::Forward)) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            records.push(serde_json::from_slice(&value)?);
+        }
+        
+        Ok(records)
+    }
+    
+    pub fn mark_network_finalized(&self, attestation: &SlotAttestation) -> Result<()> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        self.db.put_cf(&cf, attestation_key(attestation.slot), bincode::serialize(attestation)?)?;
+        Ok(())
+    }
+    
+    pub fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        let cf = self.db.cf_handle(CF_METADATA)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_METADATA))?;
+        
+        match self.db.get_cf(&cf, attestation_key(slot))? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Get the latest version of an account written at or before `slot`
+    pub fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        let cf = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_ACCOUNT_HISTORY))?;
+        
+        let pubkey = Pubkey::from_str(pubkey)
+            .map_err(|e| anyhow!("Invalid pubkey '{}': {}", pubkey, e))?;
+        let upper = account_history_key(&pubkey, slot, u64::MAX);
+        
+        match self.db.iterator_cf(&cf, IteratorMode::From(&upper, Direction::Reverse)).next() {
+            Some(item) => {
+                let (key, value) = item?;
+                if key.len() == HISTORY_KEY_LEN && key[..32] == pubkey.to_bytes() {
+                    Ok(Some(decode_account(&value)?))
+                } else {
+                    Ok(None)
+                }
+            },
+            None => Ok(None),
+        }
+    }
+    
+    /// Get every stored version of an account within a slot range, oldest first
+    pub fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let cf = self.db.cf_handle(CF_ACCOUNT_HISTORY)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_ACCOUNT_HISTORY))?;
+        
+        let pubkey = Pubkey::from_str(pubkey)
+            .map_err(|e| anyhow!("Invalid pubkey '{}': {}", pubkey, e))?;
+        let lower = account_history_key(&pubkey, start_slot, 0);
+        let upper = account_history_key(&pubkey, end_slot, u64::MAX);
+        
+        let mut versions = Vec::with_capacity(limit);
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(&lower, Direction::Forward)) {
+            if versions.len() >= limit {
+                break;
+            }
+            
+            let (key, value) = item?;
+            if key.as_ref() > upper.as_slice() {
+                break;
+            }
+            versions.push(decode_account(&value)?);
+        }
+        
+        Ok(versions)
+    }
+    
+    /// Get transactions that reference an account, newest first
+    pub fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.get_indexed_transactions(CF_TX_BY_ACCOUNT, account, limit)
+    }
+    
+    /// Get transactions that invoke a program, newest first
+    pub fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.get_indexed_transactions(CF_TX_BY_PROGRAM, program_id, limit)
+    }
+    
+    fn get_indexed_transactions(&self, index_cf: &str, pubkey: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        let cf_index = self.db.cf_handle(index_cf)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", index_cf))?;
+        let cf_transactions = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_TRANSACTIONS))?;
+        
+        let pubkey = Pubkey::from_str(pubkey)
+            .map_err(|e| anyhow!("Invalid pubkey '{}': {}", pubkey, e))?;
+        let prefix = pubkey.to_bytes();
+        
+        // Seek to the end of this pubkey's key range and walk backwards so the
+        // highest slots come first
+        let upper = tx_index_key(&pubkey, u64::MAX, &[0xff; 64]);
+        let iter = self.db.iterator_cf(&cf_index, IteratorMode::From(&upper, Direction::Reverse));
+        
+        let mut transactions = Vec::with_capacity(limit);
+        for item in iter {
+            if transactions.len() >= limit {
+                break;
+            }
+            
+            let (key, _) = item?;
+            if key.len() != INDEX_KEY_LEN || key[..32] != prefix {
+                break;
+            }
+            
+            if let Some(data) = self.db.get_cf(&cf_transactions, &key[40..])? {
+                let tx: TransactionData = bincode::deserialize(&data)?;
+                transactions.push(tx);
+            }
+        }
+        
+        Ok(transactions)
+    }
+    
+    /// Delete data of the given kind older than `slot` and compact the
+    /// affected column families. Returns the number of records removed.
+    /// Delete the entries older than `slot` of a family keyed
+    /// `pubkey (32) | slot (8, big-endian) | ...`, one range per pubkey
+    ///
+    /// `visit` sees every deleted key first. Returns the number deleted.
+    fn prune_slot_keyed(&self, name: &str, slot: u64, mut visit: impl FnMut(&[u8]) -> Result<()>) -> Result<u64> {
+        let cf = self.db.cf_handle(name)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", name))?;
+        
+        let mut pruned = 0;
+        let mut from = Vec::new();
+        loop {
+            let Some(item) = self.db.iterator_cf(&cf, IteratorMode::From(&from, Direction::Forward)).next() else {
+                break;
+            };
+            let (first, _) = item?;
+            let Some(pubkey) = first.get(..32) else {
+                from = [first.as_ref(), &[0]].concat();
+                continue;
+            };
+            
+            let end = [pubkey, &slot.to_be_bytes()].concat();
+            if first.as_ref() < end.as_slice() {
+                for item in self.db.iterator_cf(&cf, IteratorMode::From(&first, Direction::Forward)) {
+                    let (key, _) = item?;
+                    if key.as_ref() >= end.as_slice() {
+                        break;
+                    }
+                    visit(&key)?;
+                    pruned += 1;
+                }
+                self.db.delete_range_cf(&cf, &first, &end)?;
+            }
+            
+            match prefix_end(pubkey) {
+                Some(next) => from = next,
+                None => break,
+            }
+        }
+        
+        Ok(pruned)
+    }
+    
+    /// Delete the transactions of `signatures` in one batch, returning how
+    /// many were still stored
+    fn delete_transactions(&self, signatures: &mut Vec<Vec<u8>>) -> Result<u64> {
+        let cf = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or_else(|| anyhow!("Column family '{}' not found", CF_TRANSACTIONS))?;
+        
+        signatures.sort_unstable();
+        signatures.dedup();
+        let stored = self.db.multi_get_cf(signatures.iter().map(|signature| (&cf, signature)));
+        
+        let mut batch = WriteBatch::default();
+        let mut deleted = 0;
+        for (signature, stored) in signatures.iter().zip(stored) {
+            if stored?.is_some() {
+                batch.delete_cf(&cf, signature);
+                deleted += 1;
+            }
+        }
+        self.db.write(batch)?;
+        signatures.clear();
+        
+        Ok(deleted)
+    }
+    
+    pub fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        let pruned = match kind {
+            DataKind::Blocks => {
+                let cf = self.db.cf_handle(CF_BLOCKS)
+                    .ok_or_else(|| anyhow!("Column family '{}' not found", CF_BLOCKS))?;
+                
+                // Blocks are keyed by big-endian slot, so this is a contiguous range
+                let end = slot.to_be_bytes();
+                let pruned = self.db.iterator_cf(&cf, IteratorMode::Start)
+                    .map_while(|item| item.ok())
+                    .take_while(|(key, _)| key.as_ref() < end.as_slice())
+                    .count() as u64;
+                self.db.delete_range_cf(&cf, 0u64.to_be_bytes(), end)?;
+                self.db.compact_range_cf(&cf, None::<&[u8]>, Some(end));
+                pruned
+            },
+            DataKind::Accounts => {
+                // Only older versions go; the latest state of an account is
+                // kept however long ago it last changed
+                self.prune_slot_keyed(CF_ACCOUNT_HISTORY, slot, |_| Ok(()))?
+            },
+            DataKind::Transactions => {
+                // Every transaction is indexed under its fee payer, so the
+                // account index reaches all of those older than the cutoff
+                let mut signatures = Vec::new();
+                let mut pruned = 0;
+                self.prune_slot_keyed(CF_TX_BY_ACCOUNT, slot, |key| {
+                    if let Some(signature) = key.get(40..) {
+                        signatures.push(signature.to_vec());
+                    }
+                    if signatures.len() >= PRUNE_CHUNK {
+                        pruned += self.delete_transactions(&mut signatures)?;
+                    }
+                    Ok(())
+                })?;
+                pruned += self.delete_transactions(&mut signatures)?;
+                self.prune_slot_keyed(CF_TX_BY_PROGRAM, slot, |_| Ok(()))?;
+                pruned
+            },
+        };
+        
+        Ok(pruned)
+    }
+}
+
+#[async_trait]
+impl Storage for Store {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.store_account(account)).await?
+    }
+    
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.store_transaction(transaction)).await?
+    }
+    
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.store_block(block)).await?
+    }
+    
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.store_slot_bundle(bundle)).await?
+    }
+    
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.update_slot_status(&status)).await?
+    }
+    
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_latest_rooted_slot()).await?
+    }
+    
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        let store = self.clone();
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || store.save_indexer_state(&state)).await?
+    }
+    
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.load_indexer_state()).await?
+    }
+    
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        let store = self.clone();
+        let key = key.clone();
+        tokio::task::spawn_blocking(move || store.save_api_key(&key)).await?
+    }
+    
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.load_api_keys()).await?
+    }
+    
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        let store = self.clone();
+        let id = id.to_string();
+        tokio::task::spawn_blocking(move || store.delete_api_key(&id)).await?
+    }
+    
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        let store = self.clone();
+        let record = record.clone();
+        tokio::task::spawn_blocking(move || store.save_operator_record(&record)).await?
+    }
+    
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.load_operator_records()).await?
+    }
+    
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        let store = self.clone();
+        let record = record.clone();
+        tokio::task::spawn_blocking(move || store.save_delegation(&record)).await?
+    }
+    
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.load_delegations()).await?
+    }
+    
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        let store = self.clone();
+        let (operator, staker) = (operator.to_string(), staker.to_string());
+        tokio::task::spawn_blocking(move || store.delete_delegation(&operator, &staker)).await?
+    }
+    
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        let store = self.clone();
+        let record = record.clone();
+        tokio::task::spawn_blocking(move || store.save_slash_event(&record)).await?
+    }
+    
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        let store = self.clone();
+        let operator = operator.map(str::to_string);
+        tokio::task::spawn_blocking(move || store.load_slash_events(operator.as_deref(), limit)).await?
+    }
+    
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        let store = self.clone();
+        let record = record.clone();
+        tokio::task::spawn_blocking(move || store.save_performance_record(&record)).await?
+    }
+    
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        let store = self.clone();
+        let operator = operator.map(str::to_string);
+        tokio::task::spawn_blocking(move || store.load_performance_records(operator.as_deref(), since)).await?
+    }
+    
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.prune_performance_records(epoch)).await?
+    }
+    
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.mark_network_finalized(&attestation)).await?
+    }
+    
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_slot_attestation(slot)).await?
+    }
+    
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        let store = self.clone();
+        let pubkey = pubkey.to_string();
+        tokio::task::spawn_blocking(move || store.get_account(&pubkey)).await?
+    }
+    
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        let store = self.clone();
+        let signature = signature.to_string();
+        tokio::task::spawn_blocking(move || store.get_transaction(&signature)).await?
+    }
+    
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_block(slot)).await?
+    }
+    
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_recent_accounts(limit)).await?
+    }
+    
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_recent_transactions(limit)).await?
+    }
+    
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_recent_blocks(limit)).await?
+    }
+    
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        let store = self.clone();
+        let pubkey = pubkey.to_string();
+        tokio::task::spawn_blocking(move || store.get_account_at_slot(&pubkey, slot)).await?
+    }
+    
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let store = self.clone();
+        let pubkey = pubkey.to_string();
+        tokio::task::spawn_blocking(move || store.get_account_history(&pubkey, start_slot, end_slot, limit)).await?
+    }
+    
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        let store = self.clone();
+        let account = account.to_string();
+        tokio::task::spawn_blocking(move || store.get_transactions_by_account(&account, limit)).await?
+    }
+    
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        let store = self.clone();
+        let program_id = program_id.to_string();
+        tokio::task::spawn_blocking(move || store.get_transactions_by_program(&program_id, limit)).await?
+    }
+    
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_accounts_by_slot_range(start_slot, end_slot, limit)).await?
+    }
+    
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_transactions_by_slot_range(start_slot, end_slot, limit)).await?
+    }
+    
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.get_blocks_by_slot_range(start_slot, end_slot, limit)).await?
+    }
+    
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.stream_slot_range(CF_ACCOUNTS, start_slot, end_slot, decode_account, |account: &AccountData| account.slot)
+    }
+    
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.stream_slot_range(
+            CF_TRANSACTIONS,
+            start_slot,
+            end_slot,
+            |bytes: &[u8]| bincode::deserialize(bytes).map_err(Into::into),
+            |tx: &TransactionData| tx.slot,
+        )
+    }
+    
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.prune_before_slot(kind, slot)).await?
+    }
+    
+    async fn close(&self) -> Result<()> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.db.flush().map_err(Into::into)).await?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(pubkey: Pubkey, slot: u64) -> AccountData {
+        AccountData {
+            pubkey,
+            lamports: slot,
+            owner: Pubkey::default(),
+            executable: false,
+            rent_epoch: 0,
+            data: Vec::new(),
+            write_version: 0,
+            slot,
+            is_startup: false,
+            transaction_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_prune_keeps_the_latest_account_state() {
+        let path = std::env::temp_dir().join(format!("windexer-prune-{}", std::process::id()));
+        let store = Store::open(StoreConfig {
+            path: path.clone(),
+            max_open_files: 64,
+            cache_capacity: 1 << 20,
+            compression: CompressionConfig::default(),
+        }).unwrap();
+
+        let (active, dormant) = (Pubkey::new_unique(), Pubkey::new_unique());
+        store.store_account(account(active, 5)).unwrap();
+        store.store_account(account(active, 20)).unwrap();
+        store.store_account(account(dormant, 3)).unwrap();
+
+        assert_eq!(store.prune_before_slot(DataKind::Accounts, 10).unwrap(), 2);
+        assert_eq!(store.get_account(&dormant.to_string()).unwrap().map(|account| account.slot), Some(3));
+        assert_eq!(store.get_account(&active.to_string()).unwrap().map(|account| account.slot), Some(20));
+        assert!(store.get_account_at_slot(&active.to_string(), 5).unwrap().is_none());
+        assert!(store.get_account_at_slot(&active.to_string(), 20).unwrap().is_some());
+
+        drop(store);
+        let _ = std::fs::remove_dir_all(path);
+    }
+}
@@ -2,9 +2,32 @@
 
 mod internal;
 pub mod traits;
+pub mod archive;
+pub mod backfill;
+pub mod compression;
+pub mod clickhouse_store;
+pub mod config;
+pub mod decoders;
+pub mod dedup;
+pub mod error;
 pub mod factory;
+pub mod feed;
+pub mod forks;
+pub mod index;
+pub mod ingest;
+pub mod memory_store;
+pub mod metrics;
 pub mod parquet_store;
 pub mod postgres_store;
+pub mod pipeline;
+pub mod retention;
+pub mod state;
+pub mod tenant;
+pub mod tiered;
+#[cfg(test)]
+mod testing;
+
+pub use error::StorageError;
 
 // Re-export for backward compatibility
 pub use internal::*;
@@ -0,0 +1,384 @@
+//! In-memory storage backend
+//!
+//! Keeps everything in process memory. Useful for tests, local development and
+//! short-lived indexers; data is lost on restart.
+
+use {
+    crate::{retention::DataKind, traits::Storage},
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    anyhow::{anyhow, Result},
+    async_trait::async_trait,
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::RwLock,
+    },
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// Transactions are ordered by `(slot, index, signature)`
+type TransactionKey = (u64, usize, String);
+
+/// Account versions are ordered by `(slot, write_version)`
+type AccountVersions = BTreeMap<(u64, u64), AccountData>;
+
+#[derive(Default)]
+pub struct MemoryStore {
+    accounts: RwLock<HashMap<String, AccountData>>,
+    account_history: RwLock<HashMap<String, AccountVersions>>,
+    transactions: RwLock<BTreeMap<TransactionKey, TransactionData>>,
+    signatures: RwLock<HashMap<String, TransactionKey>>,
+    blocks: RwLock<BTreeMap<u64, BlockData>>,
+    latest_confirmed: RwLock<Option<u64>>,
+    latest_rooted: RwLock<Option<u64>>,
+    indexer_state: RwLock<Option<IndexerState>>,
+    api_keys: RwLock<HashMap<String, ApiKeyRecord>>,
+    operators: RwLock<HashMap<String, OperatorRecord>>,
+    /// Keyed by `(operator, staker)`
+    delegations: RwLock<BTreeMap<(String, String), DelegationRecord>>,
+    /// In the order they were saved
+    slashes: RwLock<Vec<SlashRecord>>,
+    /// (operator, epoch) -> performance
+    performance: RwLock<BTreeMap<(String, u64), PerformanceRecord>>,
+    attestations: RwLock<BTreeMap<u64, SlotAttestation>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn filter_transactions<F>(&self, limit: usize, predicate: F) -> Result<Vec<TransactionData>>
+    where
+        F: Fn(&TransactionData) -> bool,
+    {
+        let transactions = self.transactions.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(transactions
+            .values()
+            .rev()
+            .filter(|tx| predicate(tx))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        let mut history = self.account_history.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let mut accounts = self.accounts.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let key = account.pubkey.to_string();
+
+        history
+            .entry(key.clone())
+            .or_default()
+            .insert((account.slot, account.write_version), account.clone());
+
+        // Keep the newest version of each account
+        let is_newer = accounts.get(&key).map_or(true, |existing| {
+            (account.slot, account.write_version) >= (existing.slot, existing.write_version)
+        });
+        if is_newer {
+            accounts.insert(key, account);
+        }
+
+        Ok(())
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        let signature = transaction.signature.to_string();
+        let key = (transaction.slot, transaction.index, signature.clone());
+
+        let mut signatures = self.signatures.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let mut transactions = self.transactions.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+
+        if let Some(previous) = signatures.insert(signature, key.clone()) {
+            transactions.remove(&previous);
+        }
+        transactions.insert(key, transaction);
+
+        Ok(())
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        let mut blocks = self.blocks.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        blocks.insert(block.slot, block);
+        Ok(())
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        let latest = match status.status {
+            SlotStatus::Confirmed => Some(&self.latest_confirmed),
+            SlotStatus::Rooted => Some(&self.latest_rooted),
+            _ => None,
+        };
+        if let Some(latest) = latest {
+            let mut latest = latest.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+            *latest = Some(latest.map_or(status.slot, |slot| slot.max(status.slot)));
+        }
+
+        let mut blocks = self.blocks.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        if let Some(block) = blocks.get_mut(&status.slot) {
+            block.status = status.status;
+        }
+        Ok(())
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        let latest = self.latest_rooted.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(*latest)
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        let rooted = self.latest_rooted.read().map_err(|e| anyhow!("Lock error: {}", e))?.unwrap_or(0);
+        match commitment {
+            Commitment::Processed => Ok(None),
+            // A rooted slot is also confirmed, even if its confirmation was never seen
+            Commitment::Confirmed => {
+                let confirmed = self.latest_confirmed.read().map_err(|e| anyhow!("Lock error: {}", e))?.unwrap_or(0);
+                Ok(Some(confirmed.max(rooted)))
+            }
+            Commitment::Finalized => Ok(Some(rooted)),
+        }
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        let mut current = self.indexer_state.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        *current = Some(state.clone());
+        Ok(())
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        let current = self.indexer_state.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(current.clone())
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        let mut keys = self.api_keys.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        keys.insert(key.id.clone(), key.clone());
+        Ok(())
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        let keys = self.api_keys.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(keys.values().cloned().collect())
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        let mut keys = self.api_keys.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(keys.remove(id).is_some())
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        let mut operators = self.operators.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        operators.insert(record.operator.clone(), record.clone());
+        Ok(())
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        let operators = self.operators.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(operators.values().cloned().collect())
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        let mut delegations = self.delegations.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        delegations.insert((record.operator.clone(), record.staker.clone()), record.clone());
+        Ok(())
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        let delegations = self.delegations.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(delegations.values().cloned().collect())
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        let mut delegations = self.delegations.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(delegations.remove(&(operator.to_string(), staker.to_string())).is_some())
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        let mut slashes = self.slashes.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        slashes.push(record.clone());
+        Ok(())
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        let slashes = self.slashes.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(slashes
+            .iter()
+            .rev()
+            .filter(|slash| operator.map_or(true, |operator| slash.operator == operator))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        let mut performance = self.performance.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        performance.insert((record.operator.clone(), record.epoch), record.clone());
+        Ok(())
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        let performance = self.performance.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let mut records: Vec<PerformanceRecord> = performance
+            .values()
+            .filter(|record| record.timestamp >= since)
+            .filter(|record| operator.map_or(true, |operator| record.operator == operator))
+            .cloned()
+            .collect();
+        records.sort_by_key(|record| record.epoch);
+        Ok(records)
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        let mut performance = self.performance.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let before = performance.len();
+        performance.retain(|(_, record_epoch), _| *record_epoch >= epoch);
+        Ok(before - performance.len())
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        let mut attestations = self.attestations.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+        attestations.insert(attestation.slot, attestation);
+        Ok(())
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        let attestations = self.attestations.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(attestations.get(&slot).cloned())
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        let accounts = self.accounts.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(accounts.get(pubkey).cloned())
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        let signatures = self.signatures.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let transactions = self.transactions.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(signatures.get(signature).and_then(|key| transactions.get(key)).cloned())
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        let blocks = self.blocks.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(blocks.get(&slot).cloned())
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        let history = self.account_history.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(history
+            .get(pubkey)
+            .and_then(|versions| versions.range(..=(slot, u64::MAX)).next_back())
+            .map(|(_, account)| account.clone()))
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let history = self.account_history.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(history
+            .get(pubkey)
+            .map(|versions| {
+                versions
+                    .range((start_slot, 0)..=(end_slot, u64::MAX))
+                    .take(limit)
+                    .map(|(_, account)| account.clone())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        let accounts = self.accounts.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let mut recent: Vec<AccountData> = accounts.values().cloned().collect();
+        recent.sort_by(|a, b| (b.slot, b.write_version).cmp(&(a.slot, a.write_version)));
+        recent.truncate(limit);
+        Ok(recent)
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.filter_transactions(limit, |_| true)
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        let blocks = self.blocks.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(blocks.values().rev().take(limit).cloned().collect())
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.filter_transactions(limit, |tx| {
+            tx.message.account_keys.iter().any(|key| key.to_string() == account)
+        })
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.filter_transactions(limit, |tx| {
+            tx.message.program_ids().iter().any(|key| key.to_string() == program_id)
+        })
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let accounts = self.accounts.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        let mut matching: Vec<AccountData> = accounts
+            .values()
+            .filter(|a| a.slot >= start_slot && a.slot <= end_slot)
+            .cloned()
+            .collect();
+        matching.sort_by_key(|a| (a.slot, a.write_version));
+        matching.truncate(limit);
+        Ok(matching)
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        let transactions = self.transactions.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(transactions
+            .range((start_slot, 0, String::new())..)
+            .map(|(_, tx)| tx)
+            .take_while(|tx| tx.slot <= end_slot)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        let blocks = self.blocks.read().map_err(|e| anyhow!("Lock error: {}", e))?;
+        Ok(blocks.range(start_slot..=end_slot).take(limit).map(|(_, b)| b.clone()).collect())
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        let pruned = match kind {
+            DataKind::Accounts => {
+                // The latest state stays in `accounts` however old it is
+                let mut history = self.account_history.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+                let mut pruned = 0;
+                history.retain(|_, versions| {
+                    let kept = versions.split_off(&(slot, 0));
+                    pruned += versions.len();
+                    *versions = kept;
+                    !versions.is_empty()
+                });
+                pruned
+            }
+            DataKind::Transactions => {
+                let mut signatures = self.signatures.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+                let mut transactions = self.transactions.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+                let kept = transactions.split_off(&(slot, 0, String::new()));
+                let pruned = std::mem::replace(&mut *transactions, kept);
+                for (_, _, signature) in pruned.keys() {
+                    signatures.remove(signature);
+                }
+                pruned.len()
+            }
+            DataKind::Blocks => {
+                let mut blocks = self.blocks.write().map_err(|e| anyhow!("Lock error: {}", e))?;
+                let kept = blocks.split_off(&slot);
+                std::mem::replace(&mut *blocks, kept).len()
+            }
+        };
+
+        Ok(pruned as u64)
+    }
+
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,495 @@
+//! Prometheus storage metrics
+//!
+//! [`StoreMetrics`] holds latency histograms, batch sizes and error counters
+//! for every backend, labeled by `backend` and `op`. [`InstrumentedStore`]
+//! records them around a backend; the storage factory wraps every concrete
+//! backend it creates, so wrappers such as `tiered` or `dedup` are not counted
+//! twice.
+//!
+//! The metrics form a [`MetricSet`] to register with the process's
+//! [`MetricsRegistry`](windexer_common::metrics::MetricsRegistry). Pending
+//! queue depth and on-disk size are sampled when the registry is scraped, from the write pipelines and data directories registered with
+//! [`StoreMetrics::watch_queue`] and [`StoreMetrics::watch_directory`].
+//!
+//! | metric                                   | type      | labels          |
+//! |------------------------------------------|-----------|-----------------|
+//! | `windexer_store_write_duration_seconds`  | histogram | `backend`, `op` |
+//! | `windexer_store_read_duration_seconds`   | histogram | `backend`, `op` |
+//! | `windexer_store_batch_size`              | histogram | `backend`, `op` |
+//! | `windexer_store_errors_total`            | counter   | `backend`, `op` |
+//! | `windexer_store_queue_depth`             | gauge     | `pipeline`      |
+//! | `windexer_store_disk_bytes`              | gauge     | `backend`       |
+//! | `windexer_store_missed_slots_total`      | counter   |                 |
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        pipeline::PipelineMetrics,
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::Result,
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    prometheus::exponential_buckets,
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        future::Future,
+        path::{Path, PathBuf},
+        sync::{atomic::Ordering, Arc, Mutex},
+    },
+    tracing::warn,
+    windexer_common::{
+        metrics::{self, Collector, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, MetricSet},
+        types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+        utils::SlotGap,
+    },
+};
+
+const SUBSYSTEM: &str = "store";
+
+/// Storage metrics shared by every backend created by a factory
+pub struct StoreMetrics {
+    write_duration: HistogramVec,
+    read_duration: HistogramVec,
+    batch_size: HistogramVec,
+    errors: IntCounterVec,
+    queue_depth: IntGaugeVec,
+    disk_bytes: IntGaugeVec,
+    missed_slots: IntCounter,
+    queues: Mutex<Vec<(&'static str, Arc<PipelineMetrics>)>>,
+    directories: Mutex<Vec<(&'static str, PathBuf)>>,
+}
+
+impl StoreMetrics {
+    pub fn new() -> Self {
+        let labels = &["backend", "op"];
+        let batch_buckets = exponential_buckets(1.0, 4.0, 10).expect("valid batch size buckets");
+
+        Self {
+            write_duration: metrics::histogram_vec(
+                SUBSYSTEM,
+                "write_duration_seconds",
+                "Storage write latency",
+                labels,
+                metrics::latency_buckets(),
+            ),
+            read_duration: metrics::histogram_vec(
+                SUBSYSTEM,
+                "read_duration_seconds",
+                "Storage read latency",
+                labels,
+                metrics::latency_buckets(),
+            ),
+            batch_size: metrics::histogram_vec(
+                SUBSYSTEM,
+                "batch_size",
+                "Items per batched storage write",
+                labels,
+                batch_buckets,
+            ),
+            errors: metrics::counter_vec(SUBSYSTEM, "errors_total", "Failed storage operations", labels),
+            queue_depth: metrics::gauge_vec(
+                SUBSYSTEM,
+                "queue_depth",
+                "Writes waiting in a storage write pipeline",
+                &["pipeline"],
+            ),
+            disk_bytes: metrics::gauge_vec(SUBSYSTEM, "disk_bytes", "On-disk size of a storage backend", &["backend"]),
+            missed_slots: metrics::counter(SUBSYSTEM, "missed_slots_total", "Slots in gaps of the ingested rooted chain"),
+            queues: Mutex::new(Vec::new()),
+            directories: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Report the queue depth of a write pipeline
+    pub fn watch_queue(&self, pipeline: &'static str, metrics: Arc<PipelineMetrics>) {
+        if let Ok(mut queues) = self.queues.lock() {
+            queues.push((pipeline, metrics));
+        }
+    }
+
+    /// Report the on-disk size of a backend's data directory
+    pub fn watch_directory(&self, backend: &'static str, path: impl Into<PathBuf>) {
+        if let Ok(mut directories) = self.directories.lock() {
+            directories.push((backend, path.into()));
+        }
+    }
+
+    /// Count the slots of a gap in the ingested slot stream
+    pub fn record_gap(&self, gap: &SlotGap) {
+        self.missed_slots.inc_by(gap.slot_count());
+    }
+}
+
+impl MetricSet for StoreMetrics {
+    /// Refresh the sampled gauges
+    fn sample(&self) {
+        if let Ok(queues) = self.queues.lock() {
+            for (pipeline, metrics) in queues.iter() {
+                self.queue_depth
+                    .with_label_values(&[*pipeline])
+                    .set(metrics.queue_depth.load(Ordering::Relaxed) as i64);
+            }
+        }
+
+        if let Ok(directories) = self.directories.lock() {
+            for (backend, path) in directories.iter() {
+                match directory_size(path) {
+                    Ok(bytes) => self.disk_bytes.with_label_values(&[*backend]).set(bytes as i64),
+                    Err(e) => warn!("Failed to measure {}: {}", path.display(), e),
+                }
+            }
+        }
+    }
+
+    fn collectors(&self) -> Vec<&dyn Collector> {
+        vec![
+            &self.write_duration,
+            &self.read_duration,
+            &self.batch_size,
+            &self.errors,
+            &self.queue_depth,
+            &self.disk_bytes,
+            &self.missed_slots,
+        ]
+    }
+}
+
+impl Default for StoreMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for StoreMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("StoreMetrics").finish_non_exhaustive()
+    }
+}
+
+fn directory_size(path: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        total += if metadata.is_dir() {
+            directory_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(total)
+}
+
+/// Records latency, batch size and error metrics around a backend
+pub struct InstrumentedStore {
+    inner: Arc<dyn Storage>,
+    backend: &'static str,
+    metrics: Arc<StoreMetrics>,
+}
+
+impl InstrumentedStore {
+    pub fn new(inner: Arc<dyn Storage>, backend: &'static str, metrics: Arc<StoreMetrics>) -> Self {
+        Self {
+            inner,
+            backend,
+            metrics,
+        }
+    }
+
+    async fn write<T>(&self, op: &'static str, future: impl Future<Output = Result<T>>) -> Result<T> {
+        self.observe(&self.metrics.write_duration, op, future).await
+    }
+
+    async fn read<T>(&self, op: &'static str, future: impl Future<Output = Result<T>>) -> Result<T> {
+        self.observe(&self.metrics.read_duration, op, future).await
+    }
+
+    async fn observe<T>(
+        &self,
+        histogram: &HistogramVec,
+        op: &'static str,
+        future: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let timer = histogram.with_label_values(&[self.backend, op]).start_timer();
+        let result = future.await;
+        timer.observe_duration();
+
+        if result.is_err() {
+            self.metrics.errors.with_label_values(&[self.backend, op]).inc();
+        }
+        result
+    }
+
+    fn batch(&self, op: &'static str, len: usize) {
+        self.metrics.batch_size.with_label_values(&[self.backend, op]).observe(len as f64);
+    }
+}
+
+impl Debug for InstrumentedStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("InstrumentedStore")
+            .field("backend", &self.backend)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for InstrumentedStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.write("store_account", self.inner.store_account(account)).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.write("store_transaction", self.inner.store_transaction(transaction)).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.write("store_block", self.inner.store_block(block)).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        self.batch("store_accounts", accounts.len());
+        self.write("store_accounts", self.inner.store_accounts(accounts)).await
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        self.batch("store_transactions", transactions.len());
+        self.write("store_transactions", self.inner.store_transactions(transactions)).await
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        self.batch("store_blocks", blocks.len());
+        self.write("store_blocks", self.inner.store_blocks(blocks)).await
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        self.batch("store_slot_bundle", bundle.record_count());
+        self.write("store_slot_bundle", self.inner.store_slot_bundle(bundle)).await
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.write("update_slot_status", self.inner.update_slot_status(status)).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.read("get_latest_rooted_slot", self.inner.get_latest_rooted_slot()).await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.read("get_latest_slot_with_commitment", self.inner.get_latest_slot_with_commitment(commitment)).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.write("save_indexer_state", self.inner.save_indexer_state(state)).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.read("load_indexer_state", self.inner.load_indexer_state()).await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.write("save_api_key", self.inner.save_api_key(key)).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.read("load_api_keys", self.inner.load_api_keys()).await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.write("delete_api_key", self.inner.delete_api_key(id)).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.write("save_operator_record", self.inner.save_operator_record(record)).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.read("load_operator_records", self.inner.load_operator_records()).await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.write("save_delegation", self.inner.save_delegation(record)).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.read("load_delegations", self.inner.load_delegations()).await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.write("delete_delegation", self.inner.delete_delegation(operator, staker)).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.write("save_slash_event", self.inner.save_slash_event(record)).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.read("load_slash_events", self.inner.load_slash_events(operator, limit)).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.write("save_performance_record", self.inner.save_performance_record(record)).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.read("load_performance_records", self.inner.load_performance_records(operator, since)).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.write("prune_performance_records", self.inner.prune_performance_records(epoch)).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.write("mark_network_finalized", self.inner.mark_network_finalized(attestation)).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.read("get_slot_attestation", self.inner.get_slot_attestation(slot)).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.read("get_account", self.inner.get_account(pubkey)).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.read("get_transaction", self.inner.get_transaction(signature)).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.read("get_block", self.inner.get_block(slot)).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.read("get_recent_accounts", self.inner.get_recent_accounts(limit)).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.read("get_recent_transactions", self.inner.get_recent_transactions(limit)).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.read("get_recent_blocks", self.inner.get_recent_blocks(limit)).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.read("get_account_at_slot", self.inner.get_account_at_slot(pubkey, slot)).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.read("get_account_history", self.inner.get_account_history(pubkey, start_slot, end_slot, limit)).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.read("get_transactions_by_account", self.inner.get_transactions_by_account(account, limit)).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.read("get_transactions_by_program", self.inner.get_transactions_by_program(program_id, limit)).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.read("get_token_balances", self.inner.get_token_balances(owner)).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.read("get_token_holders", self.inner.get_token_holders(mint, limit)).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.read("get_nfts_by_owner", self.inner.get_nfts_by_owner(owner)).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.read("get_nft_by_mint", self.inner.get_nft_by_mint(mint)).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.read("get_compressed_asset", self.inner.get_compressed_asset(asset_id)).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.read("get_cpi_calls", self.inner.get_cpi_calls(program_id, limit)).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.read("get_lookup_table", self.inner.get_lookup_table(address)).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.read("get_decoded_account", self.inner.get_decoded_account(pubkey)).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.read("get_decoded_instructions", self.inner.get_decoded_instructions(signature)).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.read("get_events", self.inner.get_events(program_id, name, limit)).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.read("search_prefix", self.inner.search_prefix(prefix, limit)).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.read("get_accounts_by_slot_range", self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit)).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.read("get_transactions_by_slot_range", self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit)).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.read("get_blocks_by_slot_range", self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit)).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.write("prune_before_slot", self.inner.prune_before_slot(kind, slot)).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::memory_store::MemoryStore, windexer_common::metrics::MetricsRegistry};
+
+    #[tokio::test]
+    async fn test_reads_are_recorded() {
+        let metrics = Arc::new(StoreMetrics::new());
+        let registry = MetricsRegistry::new();
+        registry.register(metrics.clone()).unwrap();
+
+        let store = InstrumentedStore::new(Arc::new(MemoryStore::new()), "memory", metrics);
+        store.get_block(1).await.unwrap();
+
+        let families = registry.gather();
+        let reads = families
+            .iter()
+            .find(|family| family.get_name() == "windexer_store_read_duration_seconds")
+            .unwrap();
+        assert_eq!(reads.get_metric()[0].get_histogram().get_sample_count(), 1);
+    }
+}
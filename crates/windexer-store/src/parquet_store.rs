@@ -1,81 +1,192 @@
+//! Parquet storage backend
+//!
+//! Writes accounts, transactions and blocks as columnar Parquet files intended
+//! for offline analytics (Spark, DuckDB, Polars, ...). Each table lives in its
+//! own directory under `ParquetConfig::directory`. When `partition_by_slot` is
+//! enabled, files are grouped into Hive-style partitions keyed by the first
+//! slot of a fixed-width slot range:
+//!
+//! ```text
+//! <directory>/accounts/slot_start=312000000/part-1718000000-0.parquet
+//! <directory>/transactions/slot_start=312000000/part-1718000000-1.parquet
+//! <directory>/blocks/slot_start=312432000/part-1718000360-0.parquet
+//! ```
+//!
+//! Rows are buffered and written `row_group_size` at a time, one row group
+//! per write, on the blocking thread pool. A file is rolled when a write
+//! falls into a different partition, when it grows past `max_file_size_mb`,
+//! or when it has been open longer than `max_file_age_secs`; a timer rolls
+//! files and writes buffered rows that old even when no writes arrive.
+//! Files are only valid Parquet once closed, so readers should ignore the
+//! newest part of each table while the store is running.
+//! Sealed partitions can be moved to object storage with [`crate::archive`].
+//!
+//! The account `data` column uses its own codec from
+//! `account_data_compression`, so large payloads can use zstd while the rest
+//! of the file stays on Snappy. Readers decompress it transparently.
+//!
+//! # Schemas
+//!
+//! `accounts`
+//!
+//! | column                  | type    | nullable |
+//! |-------------------------|---------|----------|
+//! | `pubkey`                | utf8    | no       |
+//! | `owner`                 | utf8    | no       |
+//! | `lamports`              | uint64  | no       |
+//! | `slot`                  | uint64  | no       |
+//! | `executable`            | bool    | no       |
+//! | `rent_epoch`            | uint64  | no       |
+//! | `data`                  | binary  | no       |
+//! | `write_version`         | uint64  | no       |
+//! | `is_startup`            | bool    | no       |
+//! | `transaction_signature` | utf8    | yes      |
+//!
+//! `transactions`
+//!
+//! | column             | type         | nullable |
+//! |--------------------|--------------|----------|
+//! | `signature`        | utf8         | no       |
+//! | `slot`             | uint64       | no       |
+//! | `index`            | uint64       | no       |
+//! | `is_vote`          | bool         | no       |
+//! | `success`          | bool         | no       |
+//! | `fee`              | uint64       | no       |
+//! | `recent_blockhash` | utf8         | no       |
+//! | `account_keys`     | list<utf8>   | no       |
+//! | `program_ids`      | list<utf8>   | no       |
+//! | `log_messages`     | list<utf8>   | yes      |
+//! | `message`          | binary       | no       |
+//!
+//! `message` holds the bincode-encoded Solana message so full fidelity is kept.
+//!
+//! `blocks`
+//!
+//! | column              | type   | nullable |
+//! |---------------------|--------|----------|
+//! | `slot`              | uint64 | no       |
+//! | `parent_slot`       | uint64 | yes      |
+//! | `status`            | utf8   | no       |
+//! | `blockhash`         | utf8   | yes      |
+//! | `parent_blockhash`  | utf8   | yes      |
+//! | `block_time`        | int64  | yes      |
+//! | `block_height`      | uint64 | yes      |
+//! | `transaction_count` | uint64 | yes      |
+//! | `entry_count`       | uint64 | no       |
+
 use {
-    crate::traits::Storage,
+    crate::{compression::CompressionMetrics, traits::Storage},
     anyhow::{Result, anyhow},
+    bytes::Bytes,
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
     std::{
-        path::{Path, PathBuf},
-        sync::{Arc, Mutex},
+        fs::File,
+        marker::PhantomData,
+        str::FromStr,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex as StdMutex, Weak,
+        },
+        time::{Duration, Instant},
     },
     async_trait::async_trait,
-    tokio::fs,
-    tokio::sync::RwLock,
-    windexer_geyser::config::{ParquetConfig, StorageConfig},
-    windexer_common::types::{
-        AccountData,
-        TransactionData,
-        BlockData,
+    tokio::{sync::Mutex, time::MissedTickBehavior},
+    tracing::{debug, info, warn},
+    windexer_common::config::storage::{CompressionCodec, ParquetConfig},
+    windexer_common::{
+        types::{
+            AccountData,
+            TransactionData,
+            BlockData,
+            InstructionData,
+        },
+        utils::{SerializableSlotStatus, SerializableTransactionMeta},
     },
 };
 
 // We'll use Apache Arrow for in-memory operations and Parquet for storage
 use {
     arrow::{
-        array::{StringArray, UInt64Array, BooleanArray, Array, ArrayRef},
-        datatypes::{Schema as ArrowSchema, Field, DataType},
+        array::{
+            Array, ArrayRef, BinaryArray, BooleanArray, Int64Array, ListArray, ListBuilder,
+            StringArray, StringBuilder, UInt64Array,
+        },
+        datatypes::{Schema as ArrowSchema, SchemaRef, Field, DataType},
         record_batch::RecordBatch,
     },
     parquet::{
+        arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter},
+        basic::{Compression, ZstdLevel},
         file::properties::WriterProperties,
-        arrow::{ArrowWriter, ArrowReader, ParquetFileArrowReader},
+        schema::types::ColumnPath,
     },
 };
 
-/// Struct representing a table in Parquet
-struct ParquetTable<T> {
-    name: String,
-    directory: PathBuf,
-    schema: ArrowSchema,
-    max_file_size_mb: usize,
-    partition_by_slot: bool,
-    current_file: Option<PathBuf>,
-    current_batch: Vec<T>,
-    batch_size: usize,
-    writer_properties: WriterProperties,
+/// A row type that can be written to and read back from a Parquet table
+pub(crate) trait ParquetRecord: Send + Sync + 'static {
+    /// Table (and directory) name
+    const TABLE: &'static str;
+
+    /// Arrow schema of the table
+    fn schema() -> ArrowSchema;
+
+    /// Large binary column compressed with the account data codec, if any
+    const BLOB_COLUMN: Option<&'static str> = None;
+
+    /// Slot used for partitioning
+    fn slot(&self) -> u64;
+
+    /// Convert a batch of rows to an Arrow record batch
+    fn to_record_batch(schema: SchemaRef, rows: &[Self]) -> Result<RecordBatch>
+    where
+        Self: Sized;
+
+    /// Convert an Arrow record batch back to rows
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Self>>
+    where
+        Self: Sized;
 }
 
-impl<T> ParquetTable<T> {
-    fn new(
-        name: String, 
-        directory: PathBuf,
-        schema: ArrowSchema,
-        max_file_size_mb: usize,
-        partition_by_slot: bool,
-    ) -> Self {
-        let writer_props = WriterProperties::builder()
-            .set_compression(parquet::basic::Compression::SNAPPY)
-            .build();
-
-        Self {
-            name,
-            directory,
-            schema,
-            max_file_size_mb,
-            partition_by_slot,
-            current_file: None,
-            current_batch: Vec::new(),
-            batch_size: 1000, // Default batch size
-            writer_properties: writer_props,
-        }
+/// Decode every row of a complete Parquet file
+pub(crate) fn read_parquet<T: ParquetRecord>(file: Bytes) -> Result<Vec<T>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)?.build()?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        rows.extend(T::from_record_batch(&batch?)?);
     }
+    Ok(rows)
+}
+
+/// Look up a column by name and downcast it to its concrete array type
+fn column<'a, A: Array + 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a A> {
+    batch.column_by_name(name)
+        .and_then(|column| column.as_any().downcast_ref::<A>())
+        .ok_or_else(|| anyhow!("Missing or mistyped Parquet column '{}'", name))
+}
+
+fn opt_u64(array: &UInt64Array, i: usize) -> Option<u64> {
+    (!array.is_null(i)).then(|| array.value(i))
 }
 
-/// Implementation for Account data
-impl ParquetTable<AccountData> {
-    fn new_accounts_table(
-        directory: PathBuf,
-        max_file_size_mb: usize,
-        partition_by_slot: bool,
-    ) -> Self {
-        let schema = ArrowSchema::new(vec![
+fn opt_str(array: &StringArray, i: usize) -> Option<String> {
+    (!array.is_null(i)).then(|| array.value(i).to_string())
+}
+
+fn string_list(array: &ListArray, i: usize) -> Result<Vec<String>> {
+    let values = array.value(i);
+    let values = values.as_any().downcast_ref::<StringArray>()
+        .ok_or_else(|| anyhow!("Expected a list of strings"))?;
+    Ok(values.iter().flatten().map(str::to_string).collect())
+}
+
+impl ParquetRecord for AccountData {
+    const TABLE: &'static str = "accounts";
+    const BLOB_COLUMN: Option<&'static str> = Some("data");
+
+    fn schema() -> ArrowSchema {
+        ArrowSchema::new(vec![
             Field::new("pubkey", DataType::Utf8, false),
             Field::new("owner", DataType::Utf8, false),
             Field::new("lamports", DataType::UInt64, false),
@@ -86,236 +197,672 @@ impl ParquetTable<AccountData> {
             Field::new("write_version", DataType::UInt64, false),
             Field::new("is_startup", DataType::Boolean, false),
             Field::new("transaction_signature", DataType::Utf8, true),
-        ]);
+        ])
+    }
+
+    fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    fn to_record_batch(schema: SchemaRef, rows: &[Self]) -> Result<RecordBatch> {
+        let pubkeys = StringArray::from_iter_values(rows.iter().map(|a| a.pubkey.to_string()));
+        let owners = StringArray::from_iter_values(rows.iter().map(|a| a.owner.to_string()));
+        let lamports = UInt64Array::from_iter_values(rows.iter().map(|a| a.lamports));
+        let slots = UInt64Array::from_iter_values(rows.iter().map(|a| a.slot));
+        let executables = BooleanArray::from(rows.iter().map(|a| a.executable).collect::<Vec<_>>());
+        let rent_epochs = UInt64Array::from_iter_values(rows.iter().map(|a| a.rent_epoch));
+        let data = BinaryArray::from_iter_values(rows.iter().map(|a| a.data.as_slice()));
+        let write_versions = UInt64Array::from_iter_values(rows.iter().map(|a| a.write_version));
+        let is_startup = BooleanArray::from(rows.iter().map(|a| a.is_startup).collect::<Vec<_>>());
+        let signatures = StringArray::from(
+            rows.iter()
+                .map(|a| a.transaction_signature.map(|s| s.to_string()))
+                .collect::<Vec<_>>(),
+        );
+
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(pubkeys) as ArrayRef,
+                Arc::new(owners) as ArrayRef,
+                Arc::new(lamports) as ArrayRef,
+                Arc::new(slots) as ArrayRef,
+                Arc::new(executables) as ArrayRef,
+                Arc::new(rent_epochs) as ArrayRef,
+                Arc::new(data) as ArrayRef,
+                Arc::new(write_versions) as ArrayRef,
+                Arc::new(is_startup) as ArrayRef,
+                Arc::new(signatures) as ArrayRef,
+            ],
+        )?)
+    }
+
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Self>> {
+        let pubkeys = column::<StringArray>(batch, "pubkey")?;
+        let owners = column::<StringArray>(batch, "owner")?;
+        let lamports = column::<UInt64Array>(batch, "lamports")?;
+        let slots = column::<UInt64Array>(batch, "slot")?;
+        let executables = column::<BooleanArray>(batch, "executable")?;
+        let rent_epochs = column::<UInt64Array>(batch, "rent_epoch")?;
+        let data = column::<BinaryArray>(batch, "data")?;
+        let write_versions = column::<UInt64Array>(batch, "write_version")?;
+        let is_startup = column::<BooleanArray>(batch, "is_startup")?;
+        let signatures = column::<StringArray>(batch, "transaction_signature")?;
+
+        (0..batch.num_rows())
+            .map(|i| {
+                Ok(AccountData {
+                    pubkey: Pubkey::from_str(pubkeys.value(i))?,
+                    owner: Pubkey::from_str(owners.value(i))?,
+                    lamports: lamports.value(i),
+                    slot: slots.value(i),
+                    executable: executables.value(i),
+                    rent_epoch: rent_epochs.value(i),
+                    data: data.value(i).to_vec(),
+                    write_version: write_versions.value(i),
+                    is_startup: is_startup.value(i),
+                    transaction_signature: opt_str(signatures, i)
+                        .map(|s| Signature::from_str(&s))
+                        .transpose()?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ParquetRecord for TransactionData {
+    const TABLE: &'static str = "transactions";
+
+    fn schema() -> ArrowSchema {
+        let utf8_list = || DataType::List(Arc::new(Field::new("item", DataType::Utf8, true)));
+
+        ArrowSchema::new(vec![
+            Field::new("signature", DataType::Utf8, false),
+            Field::new("slot", DataType::UInt64, false),
+            Field::new("index", DataType::UInt64, false),
+            Field::new("is_vote", DataType::Boolean, false),
+            Field::new("success", DataType::Boolean, false),
+            Field::new("fee", DataType::UInt64, false),
+            Field::new("recent_blockhash", DataType::Utf8, false),
+            Field::new("account_keys", utf8_list(), false),
+            Field::new("program_ids", utf8_list(), false),
+            Field::new("log_messages", utf8_list(), true),
+            Field::new("message", DataType::Binary, false),
+        ])
+    }
+
+    fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    fn to_record_batch(schema: SchemaRef, rows: &[Self]) -> Result<RecordBatch> {
+        let signatures = StringArray::from_iter_values(rows.iter().map(|t| t.signature.to_string()));
+        let slots = UInt64Array::from_iter_values(rows.iter().map(|t| t.slot));
+        let indexes = UInt64Array::from_iter_values(rows.iter().map(|t| t.index as u64));
+        let is_vote = BooleanArray::from(rows.iter().map(|t| t.is_vote).collect::<Vec<_>>());
+        let success = BooleanArray::from(
+            rows.iter().map(|t| t.serializable_meta.status == Some(0)).collect::<Vec<_>>(),
+        );
+        let fees = UInt64Array::from_iter_values(rows.iter().map(|t| t.serializable_meta.fee));
+        let blockhashes = StringArray::from_iter_values(
+            rows.iter().map(|t| t.message.recent_blockhash.to_string()),
+        );
+
+        let mut account_keys = ListBuilder::new(StringBuilder::new());
+        let mut program_ids = ListBuilder::new(StringBuilder::new());
+        let mut log_messages = ListBuilder::new(StringBuilder::new());
+        let mut messages = Vec::with_capacity(rows.len());
+
+        for tx in rows {
+            for key in &tx.message.account_keys {
+                account_keys.values().append_value(key.to_string());
+            }
+            account_keys.append(true);
+
+            for program_id in tx.message.program_ids() {
+                program_ids.values().append_value(program_id.to_string());
+            }
+            program_ids.append(true);
+
+            match &tx.serializable_meta.log_messages {
+                Some(logs) => {
+                    for log in logs {
+                        log_messages.values().append_value(log);
+                    }
+                    log_messages.append(true);
+                }
+                None => log_messages.append(false),
+            }
+
+            messages.push(bincode::serialize(&tx.message)?);
+        }
+
+        let messages = BinaryArray::from_iter_values(messages.iter().map(Vec::as_slice));
+
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(signatures) as ArrayRef,
+                Arc::new(slots) as ArrayRef,
+                Arc::new(indexes) as ArrayRef,
+                Arc::new(is_vote) as ArrayRef,
+                Arc::new(success) as ArrayRef,
+                Arc::new(fees) as ArrayRef,
+                Arc::new(blockhashes) as ArrayRef,
+                Arc::new(account_keys.finish()) as ArrayRef,
+                Arc::new(program_ids.finish()) as ArrayRef,
+                Arc::new(log_messages.finish()) as ArrayRef,
+                Arc::new(messages) as ArrayRef,
+            ],
+        )?)
+    }
+
+    /// The transactions table keeps only the columns needed for analytics, so
+    /// the decoded meta carries status, fee and logs but no balances, inner
+    /// instructions or rewards, and only the first signature is recovered.
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Self>> {
+        let signatures = column::<StringArray>(batch, "signature")?;
+        let slots = column::<UInt64Array>(batch, "slot")?;
+        let indexes = column::<UInt64Array>(batch, "index")?;
+        let is_vote = column::<BooleanArray>(batch, "is_vote")?;
+        let success = column::<BooleanArray>(batch, "success")?;
+        let fees = column::<UInt64Array>(batch, "fee")?;
+        let log_messages = column::<ListArray>(batch, "log_messages")?;
+        let messages = column::<BinaryArray>(batch, "message")?;
+
+        (0..batch.num_rows())
+            .map(|i| {
+                let signature = Signature::from_str(signatures.value(i))?;
+                let log_messages = if log_messages.is_null(i) {
+                    None
+                } else {
+                    Some(string_list(log_messages, i)?)
+                };
+
+                let message = bincode::deserialize(messages.value(i))?;
+                let instructions = InstructionData::parse(&message, None);
+
+                Ok(TransactionData {
+                    signature,
+                    slot: slots.value(i),
+                    is_vote: is_vote.value(i),
+                    message,
+                    signatures: vec![signature],
+                    meta: Default::default(),
+                    serializable_meta: SerializableTransactionMeta {
+                        status: Some(if success.value(i) { 0 } else { 1 }),
+                        fee: fees.value(i),
+                        pre_balances: Vec::new(),
+                        post_balances: Vec::new(),
+                        inner_instructions: None,
+                        log_messages,
+                        pre_token_balances: None,
+                        post_token_balances: None,
+                        rewards: None,
+                    },
+                    index: indexes.value(i) as usize,
+                    instructions,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ParquetRecord for BlockData {
+    const TABLE: &'static str = "blocks";
+
+    fn schema() -> ArrowSchema {
+        ArrowSchema::new(vec![
+            Field::new("slot", DataType::UInt64, false),
+            Field::new("parent_slot", DataType::UInt64, true),
+            Field::new("status", DataType::Utf8, false),
+            Field::new("blockhash", DataType::Utf8, true),
+            Field::new("parent_blockhash", DataType::Utf8, true),
+            Field::new("block_time", DataType::Int64, true),
+            Field::new("block_height", DataType::UInt64, true),
+            Field::new("transaction_count", DataType::UInt64, true),
+            Field::new("entry_count", DataType::UInt64, false),
+        ])
+    }
+
+    fn slot(&self) -> u64 {
+        self.slot
+    }
 
-        Self::new(
-            "accounts".to_string(),
-            directory,
+    fn to_record_batch(schema: SchemaRef, rows: &[Self]) -> Result<RecordBatch> {
+        let slots = UInt64Array::from_iter_values(rows.iter().map(|b| b.slot));
+        let parent_slots = UInt64Array::from(rows.iter().map(|b| b.parent_slot).collect::<Vec<_>>());
+        let statuses = StringArray::from_iter_values(rows.iter().map(|b| b.status.as_str()));
+        let blockhashes = StringArray::from(
+            rows.iter().map(|b| b.blockhash.as_deref()).collect::<Vec<_>>(),
+        );
+        let parent_blockhashes = StringArray::from(
+            rows.iter().map(|b| b.parent_blockhash.as_deref()).collect::<Vec<_>>(),
+        );
+        let block_times = Int64Array::from(rows.iter().map(|b| b.timestamp).collect::<Vec<_>>());
+        let block_heights = UInt64Array::from(rows.iter().map(|b| b.block_height).collect::<Vec<_>>());
+        let transaction_counts = UInt64Array::from(
+            rows.iter().map(|b| b.transaction_count).collect::<Vec<_>>(),
+        );
+        let entry_counts = UInt64Array::from_iter_values(rows.iter().map(|b| b.entry_count));
+
+        Ok(RecordBatch::try_new(
             schema,
-            max_file_size_mb,
-            partition_by_slot,
-        )
-    }
-    
-    async fn add_account(&mut self, account: AccountData) -> Result<()> {
-        self.current_batch.push(account);
-        
-        if self.current_batch.len() >= self.batch_size {
+            vec![
+                Arc::new(slots) as ArrayRef,
+                Arc::new(parent_slots) as ArrayRef,
+                Arc::new(statuses) as ArrayRef,
+                Arc::new(blockhashes) as ArrayRef,
+                Arc::new(parent_blockhashes) as ArrayRef,
+                Arc::new(block_times) as ArrayRef,
+                Arc::new(block_heights) as ArrayRef,
+                Arc::new(transaction_counts) as ArrayRef,
+                Arc::new(entry_counts) as ArrayRef,
+            ],
+        )?)
+    }
+
+    fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Self>> {
+        let slots = column::<UInt64Array>(batch, "slot")?;
+        let parent_slots = column::<UInt64Array>(batch, "parent_slot")?;
+        let statuses = column::<StringArray>(batch, "status")?;
+        let blockhashes = column::<StringArray>(batch, "blockhash")?;
+        let parent_blockhashes = column::<StringArray>(batch, "parent_blockhash")?;
+        let block_times = column::<Int64Array>(batch, "block_time")?;
+        let block_heights = column::<UInt64Array>(batch, "block_height")?;
+        let transaction_counts = column::<UInt64Array>(batch, "transaction_count")?;
+        let entry_counts = column::<UInt64Array>(batch, "entry_count")?;
+
+        (0..batch.num_rows())
+            .map(|i| {
+                let status: SerializableSlotStatus = serde_json::from_value(
+                    serde_json::Value::String(statuses.value(i).to_string()),
+                )?;
+
+                Ok(BlockData {
+                    slot: slots.value(i),
+                    parent_slot: opt_u64(parent_slots, i),
+                    status: status.into(),
+                    blockhash: opt_str(blockhashes, i),
+                    parent_blockhash: opt_str(parent_blockhashes, i),
+                    timestamp: (!block_times.is_null(i)).then(|| block_times.value(i)),
+                    block_height: opt_u64(block_heights, i),
+                    transaction_count: opt_u64(transaction_counts, i),
+                    entry_count: entry_counts.value(i),
+                    entries: Vec::new(),
+                    rewards: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Parquet file currently being written
+struct OpenFile {
+    path: PathBuf,
+    partition: Option<u64>,
+    writer: ArrowWriter<File>,
+    opened_at: Instant,
+}
+
+/// Part files of a table. Writing them is blocking I/O, so every call runs
+/// on the blocking thread pool through [`ParquetTable::blocking`].
+struct TableFiles<T: ParquetRecord> {
+    directory: PathBuf,
+    schema: SchemaRef,
+    max_file_size_bytes: u64,
+    max_file_age: Duration,
+    slots_per_partition: Option<u64>,
+    current_file: Option<OpenFile>,
+    writer_properties: WriterProperties,
+    file_sequence: Arc<AtomicU64>,
+    compression_metrics: Arc<CompressionMetrics>,
+    rows: PhantomData<fn(&T)>,
+}
+
+impl<T: ParquetRecord> TableFiles<T> {
+    fn new(config: &ParquetConfig, file_sequence: Arc<AtomicU64>, compression_metrics: Arc<CompressionMetrics>) -> Result<Self> {
+        let compression = if config.compression_enabled {
+            Compression::SNAPPY
+        } else {
+            Compression::UNCOMPRESSED
+        };
+
+        let mut writer_properties = WriterProperties::builder()
+            .set_compression(compression);
+
+        if let Some(column) = T::BLOB_COLUMN {
+            let blob = &config.account_data_compression;
+            let codec = match blob.codec {
+                CompressionCodec::None => compression,
+                CompressionCodec::Lz4 => Compression::LZ4_RAW,
+                CompressionCodec::Zstd => Compression::ZSTD(ZstdLevel::try_new(blob.level)?),
+            };
+            writer_properties = writer_properties
+                .set_column_compression(ColumnPath::from(column), codec);
+        }
+
+        Ok(Self {
+            directory: PathBuf::from(&config.directory).join(T::TABLE),
+            schema: Arc::new(T::schema()),
+            max_file_size_bytes: config.max_file_size_mb as u64 * 1024 * 1024,
+            max_file_age: Duration::from_secs(config.max_file_age_secs),
+            slots_per_partition: config.partition_by_slot
+                .then_some(config.slots_per_partition.max(1)),
+            current_file: None,
+            writer_properties: writer_properties.build(),
+            file_sequence,
+            compression_metrics,
+            rows: PhantomData,
+        })
+    }
+
+    /// Write rows to part files, one row group per partition they span
+    fn write(&mut self, rows: Vec<T>) -> Result<()> {
+        // Rows arrive roughly in slot order, so split the batch into runs that
+        // share a partition and write each run to its own part file
+        let mut start = 0;
+        while start < rows.len() {
+            let partition = self.partition_of(rows[start].slot());
+            let end = rows[start..]
+                .iter()
+                .position(|row| self.partition_of(row.slot()) != partition)
+                .map_or(rows.len(), |offset| start + offset);
+
+            self.write_run(partition, &rows[start..end])?;
+            start = end;
+        }
+        Ok(())
+    }
+
+    fn write_run(&mut self, partition: Option<u64>, rows: &[T]) -> Result<()> {
+        let must_roll = match &self.current_file {
+            Some(file) => {
+                file.partition != partition
+                    || self.is_stale()
+                    || std::fs::metadata(&file.path).map(|m| m.len()).unwrap_or(0) >= self.max_file_size_bytes
+            }
+            None => true,
+        };
+
+        if must_roll {
+            self.close_file()?;
+            self.open_file(partition)?;
+        }
+
+        let batch = T::to_record_batch(self.schema.clone(), rows)?;
+        let file = self.current_file.as_mut()
+            .ok_or_else(|| anyhow!("No open Parquet file for table {}", T::TABLE))?;
+
+        file.writer.write(&batch)?;
+        // End the row group so the on-disk size reflects what was written
+        file.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Whether the open file has been open for `max_file_age_secs`
+    fn is_stale(&self) -> bool {
+        self.current_file.as_ref().map_or(false, |file| file.opened_at.elapsed() >= self.max_file_age)
+    }
+
+    fn partition_of(&self, slot: u64) -> Option<u64> {
+        self.slots_per_partition.map(|width| slot - slot % width)
+    }
+
+    fn open_file(&mut self, partition: Option<u64>) -> Result<()> {
+        let directory = match partition {
+            Some(slot_start) => self.directory.join(format!("slot_start={}", slot_start)),
+            None => self.directory.clone(),
+        };
+        std::fs::create_dir_all(&directory)?;
+
+        let path = directory.join(format!(
+            "part-{}-{}.parquet",
+            chrono::Utc::now().timestamp(),
+            self.file_sequence.fetch_add(1, Ordering::Relaxed),
+        ));
+
+        let file = File::create(&path)?;
+        let writer = ArrowWriter::try_new(file, self.schema.clone(), Some(self.writer_properties.clone()))?;
+
+        debug!("Opened Parquet file {}", path.display());
+
+        self.current_file = Some(OpenFile {
+            path,
+            partition,
+            writer,
+            opened_at: Instant::now(),
+        });
+
+        Ok(())
+    }
+
+    fn close_file(&mut self) -> Result<()> {
+        if let Some(file) = self.current_file.take() {
+            let metadata = file.writer.close()?;
+
+            if let Some(column) = T::BLOB_COLUMN {
+                let (uncompressed, compressed) = metadata.row_groups
+                    .iter()
+                    .flat_map(|row_group| &row_group.columns)
+                    .filter_map(|chunk| chunk.meta_data.as_ref())
+                    .filter(|meta| meta.path_in_schema.iter().map(String::as_str).eq([column]))
+                    .fold((0i64, 0i64), |(u, c), meta| {
+                        (u + meta.total_uncompressed_size, c + meta.total_compressed_size)
+                    });
+                self.compression_metrics.record(uncompressed as u64, compressed as u64);
+            }
+
+            info!("Closed Parquet file {}", file.path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Struct representing a table in Parquet
+struct ParquetTable<T: ParquetRecord> {
+    files: Arc<StdMutex<TableFiles<T>>>,
+    /// Rows not yet written, up to a row group
+    current_batch: Vec<T>,
+    /// When the oldest buffered row arrived
+    batch_started: Option<Instant>,
+    row_group_size: usize,
+    max_file_age: Duration,
+}
+
+impl<T: ParquetRecord> ParquetTable<T> {
+    fn new(config: &ParquetConfig, file_sequence: Arc<AtomicU64>, compression_metrics: Arc<CompressionMetrics>) -> Result<Self> {
+        Ok(Self {
+            files: Arc::new(StdMutex::new(TableFiles::new(config, file_sequence, compression_metrics)?)),
+            current_batch: Vec::new(),
+            batch_started: None,
+            row_group_size: config.row_group_size.max(1),
+            max_file_age: Duration::from_secs(config.max_file_age_secs),
+        })
+    }
+
+    /// Run `f` on the part files on the blocking thread pool
+    async fn blocking<R, F>(&self, f: F) -> Result<R>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut TableFiles<T>) -> Result<R> + Send + 'static,
+    {
+        let files = self.files.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut files = files.lock().map_err(|e| anyhow!("Lock error: {}", e))?;
+            f(&mut files)
+        })
+        .await?
+    }
+
+    async fn add(&mut self, row: T) -> Result<()> {
+        self.current_batch.push(row);
+        self.batch_started.get_or_insert_with(Instant::now);
+
+        if self.current_batch.len() >= self.row_group_size {
             self.flush().await?;
         }
-        
+
         Ok(())
     }
-    
+
+    /// Write buffered rows to the current part file, rolling as needed
     async fn flush(&mut self) -> Result<()> {
         if self.current_batch.is_empty() {
             return Ok(());
         }
-        
-        // Create directory if it doesn't exist
-        fs::create_dir_all(&self.directory).await?;
-        
-        // Determine file path
-        let file_path = if self.current_file.is_none() || self.check_file_size().await? {
-            let timestamp = chrono::Utc::now().timestamp();
-            let new_file = self.directory.join(format!("{}_{}.parquet", self.name, timestamp));
-            self.current_file = Some(new_file.clone());
-            new_file
-        } else {
-            self.current_file.clone().unwrap()
-        };
-        
-        // Convert batch to Arrow RecordBatch
-        let batch = self.create_record_batch()?;
-        
-        // Write to Parquet file
-        let file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&file_path)?;
-            
-        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(self.writer_properties.clone()))?;
-        writer.write(&batch)?;
-        writer.close()?;
-        
-        // Clear batch
-        self.current_batch.clear();
-        
-        Ok(())
+
+        let rows = std::mem::take(&mut self.current_batch);
+        self.batch_started = None;
+        self.blocking(move |files| files.write(rows)).await
     }
-    
-    async fn check_file_size(&self) -> Result<bool> {
-        if let Some(file_path) = &self.current_file {
-            if file_path.exists() {
-                let metadata = fs::metadata(file_path).await?;
-                let size_mb = metadata.len() as f64 / (1024.0 * 1024.0);
-                return Ok(size_mb >= self.max_file_size_mb as f64);
-            }
+
+    /// Write rows buffered for `max_file_age_secs` and close a file open
+    /// that long, so both become readable without waiting for more writes
+    async fn roll_if_stale(&mut self) -> Result<()> {
+        if self.batch_started.map_or(false, |started| started.elapsed() >= self.max_file_age) {
+            self.flush().await?;
         }
-        Ok(false)
-    }
-    
-    fn create_record_batch(&self) -> Result<RecordBatch> {
-        // Extract data from accounts
-        let pubkeys: Vec<&str> = self.current_batch.iter().map(|a| a.pubkey.as_str()).collect();
-        let owners: Vec<&str> = self.current_batch.iter().map(|a| a.owner.as_str()).collect();
-        let lamports: Vec<u64> = self.current_batch.iter().map(|a| a.lamports).collect();
-        let slots: Vec<u64> = self.current_batch.iter().map(|a| a.slot).collect();
-        let executables: Vec<bool> = self.current_batch.iter().map(|a| a.executable).collect();
-        let rent_epochs: Vec<u64> = self.current_batch.iter().map(|a| a.rent_epoch).collect();
-        let write_versions: Vec<u64> = self.current_batch.iter().map(|a| a.write_version).collect();
-        
-        // Create Arrow arrays
-        let pubkey_array = StringArray::from(pubkeys);
-        let owner_array = StringArray::from(owners);
-        let lamports_array = UInt64Array::from(lamports);
-        let slot_array = UInt64Array::from(slots);
-        let executable_array = BooleanArray::from(executables);
-        let rent_epoch_array = UInt64Array::from(rent_epochs);
-        // Placeholder for data (simplification)
-        let data_array = StringArray::from(vec!["data"; self.current_batch.len()]);
-        let write_version_array = UInt64Array::from(write_versions);
-        
-        // Create RecordBatch
-        let batch = RecordBatch::try_new(
-            Arc::new(self.schema.clone()),
-            vec![
-                Arc::new(pubkey_array) as ArrayRef,
-                Arc::new(owner_array) as ArrayRef,
-                Arc::new(lamports_array) as ArrayRef,
-                Arc::new(slot_array) as ArrayRef,
-                Arc::new(executable_array) as ArrayRef,
-                Arc::new(rent_epoch_array) as ArrayRef,
-                Arc::new(data_array) as ArrayRef,
-                Arc::new(write_version_array) as ArrayRef,
-            ],
-        )?;
-        
-        Ok(batch)
+        self.blocking(|files| {
+            if files.is_stale() {
+                files.close_file()?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.flush().await?;
+        self.blocking(|files| files.close_file()).await
     }
 }
 
+/// Rolls the stale files of `table` on a timer, since writes alone only roll
+/// them when the next one arrives; stops once the store is dropped
+fn spawn_roller<T: ParquetRecord>(table: Weak<Mutex<ParquetTable<T>>>, max_file_age: Duration) {
+    let period = (max_file_age / 2).max(Duration::from_secs(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let Some(table) = table.upgrade() else {
+                break;
+            };
+            if let Err(e) = table.lock().await.roll_if_stale().await {
+                warn!("Failed to roll Parquet files of table {}: {}", T::TABLE, e);
+            }
+        }
+    });
+}
+
 /// Parquet storage implementation
 pub struct ParquetStore {
     config: ParquetConfig,
-    accounts_table: RwLock<ParquetTable<AccountData>>,
-    transactions_table: RwLock<ParquetTable<TransactionData>>,
-    blocks_table: RwLock<ParquetTable<BlockData>>,
+    compression_metrics: Arc<CompressionMetrics>,
+    accounts_table: Arc<Mutex<ParquetTable<AccountData>>>,
+    transactions_table: Arc<Mutex<ParquetTable<TransactionData>>>,
+    blocks_table: Arc<Mutex<ParquetTable<BlockData>>>,
 }
 
 impl ParquetStore {
+    /// Must be called from within a tokio runtime
     pub async fn new(config: ParquetConfig) -> Result<Self> {
-        let base_dir = PathBuf::from(&config.directory);
-        
         // Create directories if they don't exist
-        fs::create_dir_all(&base_dir).await?;
-        
-        let accounts_dir = base_dir.join("accounts");
-        let transactions_dir = base_dir.join("transactions");
-        let blocks_dir = base_dir.join("blocks");
-        
-        // Create table handlers
-        let accounts_table = ParquetTable::new_accounts_table(
-            accounts_dir,
-            config.max_file_size_mb,
-            config.partition_by_slot,
-        );
-        
-        // Similar for transactions and blocks (simplified for brevity)
-        let transactions_table = ParquetTable::new(
-            "transactions".to_string(),
-            transactions_dir,
-            ArrowSchema::new(vec![]), // Simplified
-            config.max_file_size_mb,
-            config.partition_by_slot,
-        );
-        
-        let blocks_table = ParquetTable::new(
-            "blocks".to_string(),
-            blocks_dir,
-            ArrowSchema::new(vec![]), // Simplified
-            config.max_file_size_mb,
-            config.partition_by_slot,
-        );
-        
+        tokio::fs::create_dir_all(&config.directory).await?;
+
+        let file_sequence = Arc::new(AtomicU64::new(0));
+        let compression_metrics = Arc::new(CompressionMetrics::default());
+
+        let accounts_table = Arc::new(Mutex::new(ParquetTable::new(&config, file_sequence.clone(), compression_metrics.clone())?));
+        let transactions_table = Arc::new(Mutex::new(ParquetTable::new(&config, file_sequence.clone(), compression_metrics.clone())?));
+        let blocks_table = Arc::new(Mutex::new(ParquetTable::new(&config, file_sequence, compression_metrics.clone())?));
+
+        let max_file_age = Duration::from_secs(config.max_file_age_secs);
+        spawn_roller(Arc::downgrade(&accounts_table), max_file_age);
+        spawn_roller(Arc::downgrade(&transactions_table), max_file_age);
+        spawn_roller(Arc::downgrade(&blocks_table), max_file_age);
+
         Ok(Self {
+            accounts_table,
+            transactions_table,
+            blocks_table,
+            compression_metrics,
             config,
-            accounts_table: RwLock::new(accounts_table),
-            transactions_table: RwLock::new(transactions_table),
-            blocks_table: RwLock::new(blocks_table),
         })
     }
+
+    /// Get compression metrics for the account data column, updated as
+    /// files are finalized
+    pub fn compression_metrics(&self) -> Arc<CompressionMetrics> {
+        self.compression_metrics.clone()
+    }
+
+    /// Get the store configuration
+    pub fn config(&self) -> &ParquetConfig {
+        &self.config
+    }
 }
 
 #[async_trait]
 impl Storage for ParquetStore {
     async fn store_account(&self, account: AccountData) -> Result<()> {
-        let mut table = self.accounts_table.write().await;
-        table.add_account(account).await
+        self.accounts_table.lock().await.add(account).await
     }
-    
+
     async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
-        // Simplified implementation
-        Ok(())
+        self.transactions_table.lock().await.add(transaction).await
     }
-    
+
     async fn store_block(&self, block: BlockData) -> Result<()> {
-        // Simplified implementation
-        Ok(())
+        self.blocks_table.lock().await.add(block).await
     }
-    
-    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
-        // Simplified implementation
+
+    async fn get_account(&self, _pubkey: &str) -> Result<Option<AccountData>> {
+        // Parquet output is write-only; query it with an external engine
         Ok(None)
     }
-    
-    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
-        // Simplified implementation
+
+    async fn get_transaction(&self, _signature: &str) -> Result<Option<TransactionData>> {
         Ok(None)
     }
-    
-    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
-        // Simplified implementation
+
+    async fn get_block(&self, _slot: u64) -> Result<Option<BlockData>> {
         Ok(None)
     }
-    
-    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
-        // Simplified implementation
+
+    async fn get_recent_accounts(&self, _limit: usize) -> Result<Vec<AccountData>> {
         Ok(Vec::new())
     }
-    
-    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
-        // Simplified implementation
+
+    async fn get_recent_transactions(&self, _limit: usize) -> Result<Vec<TransactionData>> {
         Ok(Vec::new())
     }
-    
-    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
-        // Simplified implementation
+
+    async fn get_recent_blocks(&self, _limit: usize) -> Result<Vec<BlockData>> {
         Ok(Vec::new())
     }
-    
-    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
-        // Simplified implementation
+
+    async fn get_accounts_by_slot_range(&self, _start_slot: u64, _end_slot: u64, _limit: usize) -> Result<Vec<AccountData>> {
         Ok(Vec::new())
     }
-    
-    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
-        // Simplified implementation
+
+    async fn get_transactions_by_slot_range(&self, _start_slot: u64, _end_slot: u64, _limit: usize) -> Result<Vec<TransactionData>> {
         Ok(Vec::new())
     }
-    
-    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
-        // Simplified implementation
+
+    async fn get_blocks_by_slot_range(&self, _start_slot: u64, _end_slot: u64, _limit: usize) -> Result<Vec<BlockData>> {
         Ok(Vec::new())
     }
-    
+
     async fn close(&self) -> Result<()> {
-        // Flush any pending data
-        let mut accounts = self.accounts_table.write().await;
-        accounts.flush().await?;
-        
-        // Simplified for transactions and blocks
-        
+        // Flush pending rows and finalize every open file
+        self.accounts_table.lock().await.close().await?;
+        self.transactions_table.lock().await.close().await?;
+        self.blocks_table.lock().await.close().await?;
+
         Ok(())
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,746 @@
+//! Batched write pipeline
+//!
+//! Wraps any [`Storage`] backend with a bounded queue and a background flusher
+//! task. Producers enqueue writes without waiting on the backend; the flusher
+//! drains the queue in batches, either when `batch_size` writes are pending or
+//! when `flush_interval` elapses. When the queue is full, writes are rejected
+//! immediately with [`StorageError::Overloaded`] and counted in
+//! [`PipelineMetrics`] so callers can apply their own backpressure.
+//!
+//! Writes the backend fails are retried with backoff. Those still failing
+//! after [`PipelineConfig::flush_retries`] retries are logged and counted,
+//! kept in the dead-letter queue when [`PipelineConfig::dead_letters`] is set,
+//! handed to the [`FailedWrites`] listener if there is one, and reported by
+//! the next [`BatchingStore::flush`] or `close`.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        error::StorageError,
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::{anyhow, Context, Result},
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        future::Future,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::{
+        sync::{mpsc, oneshot, Mutex},
+        task::JoinHandle,
+    },
+    tracing::{debug, error, info, warn},
+    windexer_common::{
+        dlq::DeadLetterQueue,
+        shutdown::ShutdownToken,
+        types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+        types::wire::{self, WireRecord},
+    },
+};
+
+/// Source of the dead letters kept by the pipeline
+const DEAD_LETTER_SOURCE: &str = "store";
+
+/// Configuration for the write pipeline
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Maximum number of queued writes before producers are rejected
+    pub channel_capacity: usize,
+    /// Number of writes that triggers an immediate flush
+    pub batch_size: usize,
+    /// Maximum time a write may sit in the queue before being flushed
+    pub flush_interval: Duration,
+    /// Times a failed write is retried before it is given up on
+    pub flush_retries: u32,
+    /// Wait before the first retry, doubled for every further one
+    pub retry_backoff: Duration,
+    /// Queue keeping the writes that failed to flush
+    pub dead_letters: Option<Arc<DeadLetterQueue>>,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            channel_capacity: 100_000,
+            batch_size: 1_000,
+            flush_interval: Duration::from_millis(100),
+            flush_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            dead_letters: None,
+        }
+    }
+}
+
+/// Pipeline metrics
+#[derive(Default)]
+pub struct PipelineMetrics {
+    pub writes_enqueued: AtomicU64,
+    pub writes_flushed: AtomicU64,
+    pub writes_rejected: AtomicU64,
+    pub write_errors: AtomicU64,
+    pub batches_flushed: AtomicU64,
+    pub queue_depth: AtomicU64,
+}
+
+impl PipelineMetrics {
+    /// Create new metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Debug for PipelineMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("PipelineMetrics")
+            .field("writes_enqueued", &self.writes_enqueued.load(Ordering::Relaxed))
+            .field("writes_flushed", &self.writes_flushed.load(Ordering::Relaxed))
+            .field("writes_rejected", &self.writes_rejected.load(Ordering::Relaxed))
+            .field("write_errors", &self.write_errors.load(Ordering::Relaxed))
+            .field("batches_flushed", &self.batches_flushed.load(Ordering::Relaxed))
+            .field("queue_depth", &self.queue_depth.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Told about the writes a [`BatchingStore`] gave up on
+pub trait FailedWrites: Send + Sync {
+    fn accounts(&self, accounts: &[AccountData]);
+    fn transactions(&self, transactions: &[TransactionData]);
+    fn blocks(&self, blocks: &[BlockData]);
+}
+
+/// A single queued write
+enum WriteOp {
+    Account(AccountData),
+    Transaction(TransactionData),
+    Block(BlockData),
+    /// Applied after every write queued before it has been flushed
+    SlotStatus(SlotStatusData),
+    /// Written as one unit after every write queued before it
+    Bundle(SlotBundle),
+    /// Answered with the last write that failed since the previous flush
+    Flush(oneshot::Sender<Result<()>>),
+}
+
+/// Storage wrapper that batches writes through a background flusher
+pub struct BatchingStore {
+    inner: Arc<dyn Storage>,
+    sender: mpsc::Sender<WriteOp>,
+    metrics: Arc<PipelineMetrics>,
+    flusher: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BatchingStore {
+    /// Wrap a storage backend and spawn the flusher task
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn new(inner: Arc<dyn Storage>, config: PipelineConfig) -> Self {
+        Self::with_listener(inner, config, None)
+    }
+
+    /// Wrap a storage backend, telling `listener` about every write given up on
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn with_listener(inner: Arc<dyn Storage>, config: PipelineConfig, listener: Option<Arc<dyn FailedWrites>>) -> Self {
+        let (sender, receiver) = mpsc::channel(config.channel_capacity.max(1));
+        let metrics = Arc::new(PipelineMetrics::new());
+
+        let flusher = tokio::spawn(run_flusher(
+            inner.clone(),
+            receiver,
+            config,
+            listener,
+            metrics.clone(),
+        ));
+
+        Self {
+            inner,
+            sender,
+            metrics,
+            flusher: Mutex::new(Some(flusher)),
+        }
+    }
+
+    /// Get the pipeline metrics
+    pub fn metrics(&self) -> Arc<PipelineMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Wait until every write enqueued before this call has been flushed,
+    /// failing if a write was given up on since the previous flush
+    pub async fn flush(&self) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+        self.sender
+            .send(WriteOp::Flush(tx))
+            .await
+            .map_err(|_| anyhow!("Write pipeline is closed"))?;
+        rx.await.map_err(|_| anyhow!("Write pipeline flusher exited"))?
+    }
+
+    fn enqueue(&self, op: WriteOp) -> Result<()> {
+        match self.sender.try_send(op) {
+            Ok(()) => {
+                self.metrics.writes_enqueued.fetch_add(1, Ordering::Relaxed);
+                self.metrics.queue_depth.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.metrics.writes_rejected.fetch_add(1, Ordering::Relaxed);
+                Err(StorageError::Overloaded.into())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                Err(StorageError::Unavailable("write pipeline is closed".to_string()).into())
+            }
+        }
+    }
+}
+
+/// Close `storage` once its shutdown stage is stopped, so a
+/// [`BatchingStore`] drains its queue into the backend before the process
+/// exits
+pub fn close_on_shutdown(storage: Arc<dyn Storage>, shutdown: ShutdownToken) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        shutdown.cancelled().await;
+        match storage.close().await {
+            Ok(()) => info!("Storage closed"),
+            Err(e) => warn!("Failed to close storage: {}", e),
+        }
+        // Held until here so the storage stage waits for the close
+        drop(shutdown);
+    })
+}
+
+impl Debug for BatchingStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("BatchingStore")
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+async fn run_flusher(
+    storage: Arc<dyn Storage>,
+    mut receiver: mpsc::Receiver<WriteOp>,
+    config: PipelineConfig,
+    listener: Option<Arc<dyn FailedWrites>>,
+    metrics: Arc<PipelineMetrics>,
+) {
+    let batch_size = config.batch_size.max(1);
+    let mut batch = Batch::new(batch_size, &config, listener.clone());
+    let mut interval = tokio::time::interval(config.flush_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // Last write given up on, reported to the next flush
+    let mut failure: Option<anyhow::Error> = None;
+
+    info!(
+        "Write pipeline started (batch_size={}, flush_interval={:?})",
+        batch_size, config.flush_interval
+    );
+
+    loop {
+        let result = tokio::select! {
+            op = receiver.recv() => match op {
+                Some(WriteOp::Flush(done)) => {
+                    if let Err(e) = batch.flush(&storage, &metrics).await {
+                        failure = Some(e);
+                    }
+                    let _ = done.send(failure.take().map_or(Ok(()), Err));
+                    Ok(())
+                }
+                Some(WriteOp::SlotStatus(status)) => {
+                    metrics.queue_depth.fetch_sub(1, Ordering::Relaxed);
+                    let flushed = batch.flush(&storage, &metrics).await;
+                    let slot = status.slot;
+                    let applied = with_retries(&config, || storage.update_slot_status(status.clone())).await
+                        .with_context(|| format!("Failed to apply status for slot {}", slot));
+                    record(&metrics, &applied);
+                    flushed.and(applied)
+                }
+                Some(WriteOp::Bundle(bundle)) => {
+                    metrics.queue_depth.fetch_sub(1, Ordering::Relaxed);
+                    let flushed = batch.flush(&storage, &metrics).await;
+                    let slot = bundle.slot();
+                    let written = with_retries(&config, || storage.store_slot_bundle(bundle.clone())).await
+                        .with_context(|| format!("Failed to write bundle for slot {}", slot));
+                    record(&metrics, &written);
+                    if let (Err(_), Some(listener)) = (&written, &listener) {
+                        listener.accounts(&bundle.accounts);
+                        listener.transactions(&bundle.transactions);
+                        listener.blocks(std::slice::from_ref(&bundle.block));
+                    }
+                    flushed.and(written)
+                }
+                Some(op) => {
+                    metrics.queue_depth.fetch_sub(1, Ordering::Relaxed);
+                    batch.push(op);
+                    if batch.len() >= batch_size {
+                        batch.flush(&storage, &metrics).await
+                    } else {
+                        Ok(())
+                    }
+                }
+                None => {
+                    if let Err(e) = batch.flush(&storage, &metrics).await {
+                        error!("Write pipeline stopped with unflushed writes: {:#}", e);
+                    }
+                    break;
+                }
+            },
+            _ = interval.tick() => batch.flush(&storage, &metrics).await,
+        };
+
+        if let Err(e) = result {
+            failure = Some(e);
+        }
+    }
+
+    info!("Write pipeline stopped");
+}
+
+/// Count a write that is not part of a batch
+fn record(metrics: &PipelineMetrics, result: &Result<()>) {
+    match result {
+        Ok(()) => metrics.writes_flushed.fetch_add(1, Ordering::Relaxed),
+        Err(e) => {
+            error!("{:#}", e);
+            metrics.write_errors.fetch_add(1, Ordering::Relaxed)
+        }
+    };
+}
+
+/// Run `write` until it succeeds or has been retried
+/// [`PipelineConfig::flush_retries`] times, doubling the wait between tries
+async fn with_retries<F, Fut>(config: &PipelineConfig, mut write: F) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut backoff = config.retry_backoff;
+    for _ in 0..config.flush_retries {
+        match write().await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                warn!("Write failed, retrying in {:?}: {}", backoff, e);
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+        }
+    }
+    write().await
+}
+
+/// Writes accumulated between flushes, grouped by kind
+struct Batch {
+    accounts: Vec<AccountData>,
+    transactions: Vec<TransactionData>,
+    blocks: Vec<BlockData>,
+    config: PipelineConfig,
+    listener: Option<Arc<dyn FailedWrites>>,
+}
+
+impl Batch {
+    fn new(capacity: usize, config: &PipelineConfig, listener: Option<Arc<dyn FailedWrites>>) -> Self {
+        Self {
+            accounts: Vec::with_capacity(capacity),
+            transactions: Vec::with_capacity(capacity),
+            blocks: Vec::new(),
+            config: config.clone(),
+            listener,
+        }
+    }
+
+    fn keep<T: WireRecord>(&self, records: &[T], error: &anyhow::Error) {
+        let Some(queue) = &self.config.dead_letters else {
+            return;
+        };
+        let kept = wire::encode(None, records)
+            .and_then(|frame| queue.push(DEAD_LETTER_SOURCE, frame, &error.to_string()));
+        match kept {
+            Ok(Some(id)) => warn!("Kept {} failed {} as dead letter {}", records.len(), T::KIND.name(), id),
+            Ok(None) => warn!("Dead-letter queue is full, dropping {} failed {}", records.len(), T::KIND.name()),
+            Err(e) => error!("Failed to keep {} failed {} as a dead letter: {}", records.len(), T::KIND.name(), e),
+        }
+    }
+
+    /// Write `records` with retries, keeping them as dead letters and passing
+    /// them to `give_up` if they still fail
+    async fn write<T, F, Fut, G>(&self, records: Vec<T>, store: F, give_up: G) -> Result<()>
+    where
+        T: Clone + WireRecord,
+        F: Fn(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+        G: FnOnce(&dyn FailedWrites, &[T]),
+    {
+        let result = with_retries(&self.config, || store(records.clone())).await
+            .with_context(|| format!("Failed to flush {} {}", records.len(), T::KIND.name()));
+        if let Err(e) = &result {
+            error!("{:#}", e);
+            self.keep(&records, e);
+            if let Some(listener) = &self.listener {
+                give_up(listener.as_ref(), &records);
+            }
+        }
+        result
+    }
+
+    fn len(&self) -> usize {
+        self.accounts.len() + self.transactions.len() + self.blocks.len()
+    }
+
+    fn push(&mut self, op: WriteOp) {
+        match op {
+            WriteOp::Account(account) => self.accounts.push(account),
+            WriteOp::Transaction(transaction) => self.transactions.push(transaction),
+            WriteOp::Block(block) => self.blocks.push(block),
+            WriteOp::SlotStatus(_) | WriteOp::Bundle(_) | WriteOp::Flush(_) => {}
+        }
+    }
+
+    /// Write every pending write, failing with the last kind that could not
+    /// be written
+    async fn flush(&mut self, storage: &Arc<dyn Storage>, metrics: &PipelineMetrics) -> Result<()> {
+        let total = self.len() as u64;
+        if total == 0 {
+            return Ok(());
+        }
+
+        let mut failed = 0u64;
+        let mut result = Ok(());
+
+        if !self.accounts.is_empty() {
+            let accounts = std::mem::take(&mut self.accounts);
+            let count = accounts.len() as u64;
+            if let Err(e) = self.write(accounts, |records| storage.store_accounts(records), |l, r| l.accounts(r)).await {
+                failed += count;
+                result = Err(e);
+            }
+        }
+
+        if !self.transactions.is_empty() {
+            let transactions = std::mem::take(&mut self.transactions);
+            let count = transactions.len() as u64;
+            if let Err(e) = self.write(transactions, |records| storage.store_transactions(records), |l, r| l.transactions(r)).await {
+                failed += count;
+                result = Err(e);
+            }
+        }
+
+        if !self.blocks.is_empty() {
+            let blocks = std::mem::take(&mut self.blocks);
+            let count = blocks.len() as u64;
+            if let Err(e) = self.write(blocks, |records| storage.store_blocks(records), |l, r| l.blocks(r)).await {
+                failed += count;
+                result = Err(e);
+            }
+        }
+
+        metrics.writes_flushed.fetch_add(total - failed, Ordering::Relaxed);
+        metrics.write_errors.fetch_add(failed, Ordering::Relaxed);
+        metrics.batches_flushed.fetch_add(1, Ordering::Relaxed);
+        debug!("Flushed batch of {} writes ({} failed)", total, failed);
+        result
+    }
+}
+
+#[async_trait]
+impl Storage for BatchingStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.enqueue(WriteOp::Account(account))
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.enqueue(WriteOp::Transaction(transaction))
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.enqueue(WriteOp::Block(block))
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        self.enqueue(WriteOp::Bundle(bundle))
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.enqueue(WriteOp::SlotStatus(status))
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.inner.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.inner.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.flush().await?;
+        self.inner.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.inner.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.inner.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.inner.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.inner.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.inner.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.inner.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.inner.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.inner.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.inner.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        let drained = self.flush().await;
+
+        if let Some(handle) = self.flusher.lock().await.take() {
+            handle.abort();
+        }
+
+        self.inner.close().await?;
+        drained.context("Failed to drain write pipeline on close")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::{memory_store::MemoryStore, testing::FailingStore}};
+
+    fn block(slot: u64) -> BlockData {
+        BlockData { slot, ..Default::default() }
+    }
+
+    /// Wait for the flusher to write the block of `slot` through to `inner`
+    async fn flushed(inner: &dyn Storage, slot: u64) -> bool {
+        for _ in 0..100 {
+            if inner.get_block(slot).await.unwrap().is_some() {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn test_flushes_on_batch_size_and_interval() {
+        let inner = Arc::new(MemoryStore::default());
+        let config = PipelineConfig { batch_size: 2, flush_interval: Duration::from_secs(3600), ..PipelineConfig::default() };
+        let store = BatchingStore::new(inner.clone(), config);
+
+        store.store_block(block(1)).await.unwrap();
+        store.store_block(block(2)).await.unwrap();
+        assert!(flushed(inner.as_ref(), 2).await);
+
+        let inner = Arc::new(MemoryStore::default());
+        let config = PipelineConfig { batch_size: 100, flush_interval: Duration::from_millis(20), ..PipelineConfig::default() };
+        let store = BatchingStore::new(inner.clone(), config);
+
+        store.store_block(block(3)).await.unwrap();
+        assert!(flushed(inner.as_ref(), 3).await);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_rejects_writes() {
+        // The flusher cannot run before this test yields, so the queue stays full
+        let config = PipelineConfig { channel_capacity: 1, ..PipelineConfig::default() };
+        let store = BatchingStore::new(Arc::new(MemoryStore::default()), config);
+
+        store.store_block(block(1)).await.unwrap();
+        let rejected = store.store_block(block(2)).await.unwrap_err();
+        assert!(matches!(StorageError::find(&rejected), Some(StorageError::Overloaded)));
+        assert_eq!(store.metrics().writes_rejected.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_flush_reports_writes_given_up_on() {
+        let inner = Arc::new(FailingStore::default());
+        let config = PipelineConfig { flush_retries: 1, retry_backoff: Duration::from_millis(1), ..PipelineConfig::default() };
+        let store = BatchingStore::new(inner.clone(), config);
+
+        inner.fail_writes(true);
+        store.store_block(block(1)).await.unwrap();
+        assert!(store.flush().await.is_err());
+        assert_eq!(store.metrics().write_errors.load(Ordering::Relaxed), 1);
+
+        inner.fail_writes(false);
+        store.store_block(block(2)).await.unwrap();
+        store.flush().await.unwrap();
+        assert!(inner.get_block(2).await.unwrap().is_some());
+    }
+}
@@ -1,20 +1,112 @@
 use {
-    crate::traits::Storage,
+    crate::{retention::DataKind, traits::{SlotBundle, Storage}},
     anyhow::{Result, anyhow},
-    std::sync::Arc,
+    std::{
+        collections::{hash_map::Entry, HashMap, HashSet},
+        str::FromStr,
+        sync::OnceLock,
+    },
     async_trait::async_trait,
+    futures::stream::{BoxStream, StreamExt},
     sqlx::{
-        postgres::{PgPool, PgPoolOptions, PgRow},
-        Row,
+        postgres::{PgConnectOptions, PgPool, PgPoolOptions, PgRow},
+        Postgres, Row, Transaction,
+    },
+    solana_sdk::{
+        pubkey::Pubkey,
+        signature::Signature,
     },
-    windexer_geyser::config::PostgresConfig,
-    windexer_common::types::{
-        AccountData,
-        TransactionData,
-        BlockData,
+    tracing::{debug, info},
+    windexer_common::config::storage::PostgresConfig,
+    windexer_common::{
+        types::{
+            AccountData,
+            TransactionData,
+            InstructionData,
+            BlockData,
+            IndexerState,
+            SlotStatusData,
+            ApiKeyRecord,
+            ApiScope,
+            OperatorRecord,
+            PerformanceRecord,
+            SlashRecord,
+            DelegationRecord,
+        },
+        utils::{SerializableSlotStatus, SerializableTransactionMeta},
     },
 };
 
+/// The last version of each transaction in `transactions`, in batch order.
+/// An upsert cannot touch the same row twice, so a signature received twice
+/// in one batch is only written once.
+fn unique_transactions(transactions: &[TransactionData]) -> Vec<&TransactionData> {
+    let mut seen = HashSet::with_capacity(transactions.len());
+    let mut unique: Vec<&TransactionData> = transactions.iter().rev().filter(|tx| seen.insert(tx.signature)).collect();
+    unique.reverse();
+    unique
+}
+
+/// One block per slot, folding the updates of a slot in batch order the same
+/// way the upsert in `merge_blocks` applies them across batches
+fn collapse_blocks(blocks: &[BlockData]) -> Vec<BlockData> {
+    let mut slots: HashMap<u64, BlockData> = HashMap::with_capacity(blocks.len());
+    let mut order = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        match slots.entry(block.slot) {
+            Entry::Vacant(entry) => {
+                order.push(block.slot);
+                entry.insert(block.clone());
+            }
+            Entry::Occupied(mut entry) => {
+                let current = entry.get_mut();
+                current.parent_slot = block.parent_slot;
+                current.status = block.status.clone();
+                current.blockhash = block.blockhash.clone().or(current.blockhash.take());
+                current.parent_blockhash = block.parent_blockhash.clone().or(current.parent_blockhash.take());
+                current.timestamp = block.timestamp.or(current.timestamp);
+                current.block_height = block.block_height.or(current.block_height);
+                current.transaction_count = block.transaction_count.or(current.transaction_count);
+                current.entry_count = current.entry_count.max(block.entry_count);
+                current.rewards = block.rewards.clone().or(current.rewards.take());
+            }
+        }
+    }
+    order.into_iter().filter_map(|slot| slots.remove(&slot)).collect()
+}
+
+/// Schema migrations embedded at compile time from `migrations/`
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+/// Streaming queries need a `'static` SQL string, so build them once
+fn account_range_query() -> &'static str {
+    static QUERY: OnceLock<String> = OnceLock::new();
+    QUERY.get_or_init(|| format!(
+        "SELECT {} FROM accounts WHERE slot BETWEEN $1 AND $2 ORDER BY slot, write_version",
+        ACCOUNT_COLUMNS
+    ))
+}
+
+fn transaction_range_query() -> &'static str {
+    static QUERY: OnceLock<String> = OnceLock::new();
+    QUERY.get_or_init(|| format!(
+        "SELECT {} FROM transactions WHERE slot BETWEEN $1 AND $2 ORDER BY slot, tx_index",
+        TRANSACTION_COLUMNS
+    ))
+}
+
+/// Allowed values for the `synchronous_commit` setting
+const SYNC_COMMIT_LEVELS: &[&str] = &["on", "off", "local", "remote_write", "remote_apply"];
+
+const ACCOUNT_COLUMNS: &str =
+    "pubkey, owner, lamports, slot, executable, rent_epoch, data, write_version, is_startup, transaction_signature";
+
+const TRANSACTION_COLUMNS: &str =
+    "signature, slot, tx_index, is_vote, success, fee, message, signatures, meta";
+
+const BLOCK_COLUMNS: &str =
+    "slot, parent_slot, status, blockhash, parent_blockhash, block_time, block_height, transaction_count, entry_count, rewards";
+
 /// PostgreSQL storage implementation
 pub struct PostgresStore {
     config: PostgresConfig,
@@ -23,109 +115,161 @@ pub struct PostgresStore {
 
 impl PostgresStore {
     pub async fn new(config: PostgresConfig) -> Result<Self> {
+        let mut options = PgConnectOptions::from_str(&config.connection_string)
+            .map_err(|e| anyhow!("Invalid PostgreSQL connection string: {}", e))?;
+
+        if let Some(level) = &config.synchronous_commit {
+            if !SYNC_COMMIT_LEVELS.contains(&level.as_str()) {
+                return Err(anyhow!(
+                    "Invalid synchronous_commit level '{}', expected one of {:?}",
+                    level, SYNC_COMMIT_LEVELS
+                ));
+            }
+            options = options.options([("synchronous_commit", level.as_str())]);
+        }
+
         let pool = PgPoolOptions::new()
             .max_connections(config.max_connections as u32)
-            .connect(&config.connection_string)
+            .connect_with(options)
             .await?;
-            
+
         let store = Self {
             config,
             pool,
         };
-        
-        // Initialize database schema if needed
-        if config.create_tables {
-            store.initialize_schema().await?;
+
+        // Bring the schema up to date if needed
+        if store.config.create_tables {
+            store.run_migrations().await?;
         }
-        
+
         Ok(store)
     }
-    
-    async fn initialize_schema(&self) -> Result<()> {
-        // Create accounts table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS accounts (
-                pubkey TEXT PRIMARY KEY,
-                owner TEXT NOT NULL,
-                lamports BIGINT NOT NULL,
-                slot BIGINT NOT NULL,
-                executable BOOLEAN NOT NULL,
-                rent_epoch BIGINT NOT NULL,
-                data BYTEA,
-                write_version BIGINT NOT NULL,
-                is_startup BOOLEAN NOT NULL DEFAULT FALSE,
-                transaction_signature TEXT,
-                last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-            );
-            
-            CREATE INDEX IF NOT EXISTS accounts_slot_idx ON accounts(slot);
-            CREATE INDEX IF NOT EXISTS accounts_owner_idx ON accounts(owner);
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        // Create transactions table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS transactions (
-                signature TEXT PRIMARY KEY,
-                slot BIGINT NOT NULL,
-                is_vote BOOLEAN NOT NULL,
-                message BYTEA,
-                meta JSONB,
-                index BIGINT NOT NULL,
-                last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-            );
-            
-            CREATE INDEX IF NOT EXISTS transactions_slot_idx ON transactions(slot);
-            "#
-        )
-        .execute(&self.pool)
-        .await?;
-        
-        // Create blocks table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS blocks (
-                slot BIGINT PRIMARY KEY,
-                blockhash TEXT,
-                parent_blockhash TEXT,
-                last_updated TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
-            );
-            "#
-        )
-        .execute(&self.pool)
+
+    async fn run_migrations(&self) -> Result<()> {
+        MIGRATOR.run(&self.pool).await?;
+        info!("PostgreSQL schema is up to date");
+        Ok(())
+    }
+
+    /// Stage rows with binary COPY, then upsert them into the target table
+    async fn copy_and_merge(
+        tx: &mut Transaction<'_, Postgres>,
+        table: &str,
+        columns: &str,
+        rows: Vec<u8>,
+        merge: &str,
+    ) -> Result<u64> {
+        let staging = format!("{}_staging", table);
+
+        sqlx::query(&format!(
+            "CREATE TEMP TABLE IF NOT EXISTS {} (LIKE {} INCLUDING DEFAULTS) ON COMMIT DELETE ROWS",
+            staging, table
+        ))
+        .execute(&mut **tx)
         .await?;
-        
-        // Create transaction_mentions table for efficient querying
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS transaction_mentions (
-                signature TEXT NOT NULL REFERENCES transactions(signature) ON DELETE CASCADE,
-                pubkey TEXT NOT NULL,
-                is_signer BOOLEAN NOT NULL,
-                is_writable BOOLEAN NOT NULL,
-                PRIMARY KEY (signature, pubkey)
-            );
-            
-            CREATE INDEX IF NOT EXISTS transaction_mentions_pubkey_idx ON transaction_mentions(pubkey);
-            "#
-        )
-        .execute(&self.pool)
+
+        let mut copy = tx
+            .copy_in_raw(&format!("COPY {} ({}) FROM STDIN (FORMAT BINARY)", staging, columns))
+            .await?;
+        copy.send(rows).await?;
+        let copied = copy.finish().await?;
+
+        sqlx::query(&format!(
+            "INSERT INTO {table} ({columns}) SELECT {columns} FROM {staging} {merge}",
+            table = table,
+            columns = columns,
+            staging = staging,
+            merge = merge,
+        ))
+        .execute(&mut **tx)
         .await?;
-        
+
+        Ok(copied)
+    }
+
+    async fn copy_accounts(&self, accounts: &[AccountData]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let copied = Self::merge_accounts(&mut tx, accounts).await?;
+        tx.commit().await?;
+
+        debug!("Copied {} accounts", copied);
         Ok(())
     }
-    
-    async fn insert_account(&self, account: &AccountData) -> Result<()> {
-        sqlx::query(
+
+    async fn copy_transactions(&self, transactions: &[TransactionData]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let copied = Self::merge_transactions(&mut tx, transactions).await?;
+        tx.commit().await?;
+
+        debug!("Copied {} transactions", copied);
+        Ok(())
+    }
+
+    async fn copy_blocks(&self, blocks: &[BlockData]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let copied = Self::merge_blocks(&mut tx, blocks).await?;
+        tx.commit().await?;
+
+        debug!("Copied {} blocks", copied);
+        Ok(())
+    }
+
+    fn encode_accounts<'a>(accounts: impl IntoIterator<Item = &'a AccountData>) -> Vec<u8> {
+        let mut encoder = BinaryCopyEncoder::new();
+        for account in accounts {
+            encoder.start_row(10);
+            encoder.text(&account.pubkey.to_string());
+            encoder.text(&account.owner.to_string());
+            encoder.bigint(account.lamports as i64);
+            encoder.bigint(account.slot as i64);
+            encoder.boolean(account.executable);
+            encoder.bigint(account.rent_epoch as i64);
+            encoder.bytea(&account.data);
+            encoder.bigint(account.write_version as i64);
+            encoder.boolean(account.is_startup);
+            encoder.opt_text(account.transaction_signature.map(|s| s.to_string()).as_deref());
+        }
+        encoder.finish()
+    }
+
+    async fn merge_accounts(tx: &mut Transaction<'_, Postgres>, accounts: &[AccountData]) -> Result<u64> {
+        // An upsert cannot touch the same row twice, so only the newest
+        // version of each account goes to the latest-state table
+        let mut latest: HashMap<Pubkey, &AccountData> = HashMap::with_capacity(accounts.len());
+        for account in accounts {
+            latest
+                .entry(account.pubkey)
+                .and_modify(|current| {
+                    if (account.slot, account.write_version) > (current.slot, current.write_version) {
+                        *current = account;
+                    }
+                })
+                .or_insert(account);
+        }
+
+        let history_rows = Self::encode_accounts(accounts);
+        let latest_rows = if latest.len() == accounts.len() {
+            history_rows.clone()
+        } else {
+            Self::encode_accounts(latest.into_values())
+        };
+
+        Self::copy_and_merge(
+            tx,
+            "account_history",
+            ACCOUNT_COLUMNS,
+            history_rows,
+            "ON CONFLICT (pubkey, slot, write_version) DO NOTHING",
+        ).await?;
+        Self::copy_and_merge(
+            tx,
+            "accounts",
+            ACCOUNT_COLUMNS,
+            latest_rows,
             r#"
-            INSERT INTO accounts (pubkey, owner, lamports, slot, executable, rent_epoch, data, write_version, is_startup, transaction_signature)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-            ON CONFLICT (pubkey) 
-            DO UPDATE SET 
+            ON CONFLICT (pubkey)
+            DO UPDATE SET
                 owner = EXCLUDED.owner,
                 lamports = EXCLUDED.lamports,
                 slot = EXCLUDED.slot,
@@ -136,187 +280,869 @@ impl PostgresStore {
                 is_startup = EXCLUDED.is_startup,
                 transaction_signature = EXCLUDED.transaction_signature,
                 last_updated = CURRENT_TIMESTAMP
-            WHERE accounts.slot <= EXCLUDED.slot OR 
+            WHERE accounts.slot < EXCLUDED.slot OR
                   (accounts.slot = EXCLUDED.slot AND accounts.write_version < EXCLUDED.write_version)
-            "#
-        )
-        .bind(&account.pubkey)
-        .bind(&account.owner)
-        .bind(account.lamports as i64)
-        .bind(account.slot as i64)
-        .bind(account.executable)
-        .bind(account.rent_epoch as i64)
-        .bind(&account.data.as_slice())
-        .bind(account.write_version as i64)
-        .bind(account.is_startup)
-        .bind(&account.transaction_signature)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
+            "#,
+        ).await
+    }
+
+    async fn merge_transactions(tx: &mut Transaction<'_, Postgres>, transactions: &[TransactionData]) -> Result<u64> {
+        let mut tx_rows = BinaryCopyEncoder::new();
+        let mut mention_rows = BinaryCopyEncoder::new();
+        let mut program_rows = BinaryCopyEncoder::new();
+
+        for transaction in unique_transactions(transactions) {
+            let signature = transaction.signature.to_string();
+            let message = &transaction.message;
+
+            tx_rows.start_row(9);
+            tx_rows.text(&signature);
+            tx_rows.bigint(transaction.slot as i64);
+            tx_rows.bigint(transaction.index as i64);
+            tx_rows.boolean(transaction.is_vote);
+            tx_rows.boolean(transaction.serializable_meta.status == Some(0));
+            tx_rows.bigint(transaction.serializable_meta.fee as i64);
+            tx_rows.bytea(&bincode::serialize(message)?);
+            tx_rows.bytea(&bincode::serialize(&transaction.signatures)?);
+            tx_rows.jsonb(&serde_json::to_value(&transaction.serializable_meta)?);
+
+            for (i, account) in message.account_keys.iter().enumerate() {
+                mention_rows.start_row(5);
+                mention_rows.text(&signature);
+                mention_rows.text(&account.to_string());
+                mention_rows.bigint(transaction.slot as i64);
+                mention_rows.boolean(message.is_signer(i));
+                mention_rows.boolean(message.is_maybe_writable(i, None));
+            }
+
+            for program_id in message.program_ids() {
+                program_rows.start_row(3);
+                program_rows.text(&program_id.to_string());
+                program_rows.bigint(transaction.slot as i64);
+                program_rows.text(&signature);
+            }
+        }
+
+        let copied = Self::copy_and_merge(
+            tx,
+            "transactions",
+            TRANSACTION_COLUMNS,
+            tx_rows.finish(),
+            r#"
+            ON CONFLICT (signature)
+            DO UPDATE SET
+                slot = EXCLUDED.slot,
+                tx_index = EXCLUDED.tx_index,
+                is_vote = EXCLUDED.is_vote,
+                success = EXCLUDED.success,
+                fee = EXCLUDED.fee,
+                message = EXCLUDED.message,
+                signatures = EXCLUDED.signatures,
+                meta = EXCLUDED.meta,
+                last_updated = CURRENT_TIMESTAMP
+            "#,
+        ).await?;
+        Self::copy_and_merge(
+            tx,
+            "transaction_mentions",
+            "signature, pubkey, slot, is_signer, is_writable",
+            mention_rows.finish(),
+            "ON CONFLICT (signature, pubkey) DO NOTHING",
+        ).await?;
+        Self::copy_and_merge(
+            tx,
+            "transaction_programs",
+            "program_id, slot, signature",
+            program_rows.finish(),
+            "ON CONFLICT DO NOTHING",
+        ).await?;
+
+        Ok(copied)
+    }
+
+    async fn merge_blocks(tx: &mut Transaction<'_, Postgres>, blocks: &[BlockData]) -> Result<u64> {
+        let mut encoder = BinaryCopyEncoder::new();
+        for block in &collapse_blocks(blocks) {
+            encoder.start_row(10);
+            encoder.bigint(block.slot as i64);
+            encoder.opt_bigint(block.parent_slot.map(|s| s as i64));
+            encoder.text(block.status.as_str());
+            encoder.opt_text(block.blockhash.as_deref());
+            encoder.opt_text(block.parent_blockhash.as_deref());
+            encoder.opt_bigint(block.timestamp);
+            encoder.opt_bigint(block.block_height.map(|h| h as i64));
+            encoder.opt_bigint(block.transaction_count.map(|c| c as i64));
+            encoder.bigint(block.entry_count as i64);
+            match &block.rewards {
+                Some(rewards) => encoder.jsonb(&serde_json::to_value(rewards)?),
+                None => encoder.null(),
+            }
+        }
+
+        Self::copy_and_merge(
+            tx,
+            "blocks",
+            BLOCK_COLUMNS,
+            encoder.finish(),
+            r#"
+            ON CONFLICT (slot)
+            DO UPDATE SET
+                parent_slot = EXCLUDED.parent_slot,
+                status = EXCLUDED.status,
+                blockhash = COALESCE(EXCLUDED.blockhash, blocks.blockhash),
+                parent_blockhash = COALESCE(EXCLUDED.parent_blockhash, blocks.parent_blockhash),
+                block_time = COALESCE(EXCLUDED.block_time, blocks.block_time),
+                block_height = COALESCE(EXCLUDED.block_height, blocks.block_height),
+                transaction_count = COALESCE(EXCLUDED.transaction_count, blocks.transaction_count),
+                entry_count = GREATEST(EXCLUDED.entry_count, blocks.entry_count),
+                rewards = COALESCE(EXCLUDED.rewards, blocks.rewards),
+                last_updated = CURRENT_TIMESTAMP
+            "#,
+        ).await
     }
-    
-    async fn account_from_row(row: PgRow) -> Result<AccountData> {
-        let account = AccountData {
-            pubkey: row.try_get("pubkey")?,
-            owner: row.try_get("owner")?,
+
+    /// Resolve rows holding a `signature` column into full transactions
+    async fn transactions_by_signature(&self, rows: Vec<PgRow>) -> Result<Vec<TransactionData>> {
+        let mut transactions = Vec::with_capacity(rows.len());
+        for row in rows {
+            let signature: String = row.try_get("signature")?;
+            if let Some(tx) = self.get_transaction(&signature).await? {
+                transactions.push(tx);
+            }
+        }
+
+        Ok(transactions)
+    }
+
+    fn account_from_row(row: &PgRow) -> Result<AccountData> {
+        let pubkey: String = row.try_get("pubkey")?;
+        let owner: String = row.try_get("owner")?;
+        let transaction_signature: Option<String> = row.try_get("transaction_signature")?;
+
+        Ok(AccountData {
+            pubkey: Pubkey::from_str(&pubkey)?,
+            owner: Pubkey::from_str(&owner)?,
             lamports: row.try_get::<i64, _>("lamports")? as u64,
             slot: row.try_get::<i64, _>("slot")? as u64,
             executable: row.try_get("executable")?,
             rent_epoch: row.try_get::<i64, _>("rent_epoch")? as u64,
-            data: row.try_get::<Vec<u8>, _>("data")?,
+            data: row.try_get::<Option<Vec<u8>>, _>("data")?.unwrap_or_default(),
             write_version: row.try_get::<i64, _>("write_version")? as u64,
-            is_startup: false,
-            transaction_signature: None,
+            is_startup: row.try_get("is_startup")?,
+            transaction_signature: transaction_signature
+                .map(|s| Signature::from_str(&s))
+                .transpose()?,
+        })
+    }
+
+    fn transaction_from_row(row: &PgRow) -> Result<TransactionData> {
+        let signature: String = row.try_get("signature")?;
+        let message: Vec<u8> = row.try_get("message")?;
+        let signatures: Vec<u8> = row.try_get("signatures")?;
+        let meta: Option<serde_json::Value> = row.try_get("meta")?;
+
+        let serializable_meta: SerializableTransactionMeta = match meta {
+            Some(meta) => serde_json::from_value(meta)?,
+            None => return Err(anyhow!("Transaction {} has no stored meta", signature)),
         };
-        
-        Ok(account)
+
+        let message = bincode::deserialize(&message)?;
+        let instructions = InstructionData::parse(&message, serializable_meta.inner_instructions().as_deref());
+
+        Ok(TransactionData {
+            signature: Signature::from_str(&signature)?,
+            slot: row.try_get::<i64, _>("slot")? as u64,
+            is_vote: row.try_get("is_vote")?,
+            message,
+            signatures: bincode::deserialize(&signatures)?,
+            meta: Default::default(),
+            serializable_meta,
+            index: row.try_get::<i64, _>("tx_index")? as usize,
+            instructions,
+        })
+    }
+
+    fn block_from_row(row: &PgRow) -> Result<BlockData> {
+        let status: String = row.try_get("status")?;
+        let status: SerializableSlotStatus = serde_json::from_value(serde_json::Value::String(status))?;
+        let rewards: Option<serde_json::Value> = row.try_get("rewards")?;
+
+        Ok(BlockData {
+            slot: row.try_get::<i64, _>("slot")? as u64,
+            parent_slot: row.try_get::<Option<i64>, _>("parent_slot")?.map(|s| s as u64),
+            status: status.into(),
+            blockhash: row.try_get("blockhash")?,
+            parent_blockhash: row.try_get("parent_blockhash")?,
+            timestamp: row.try_get("block_time")?,
+            block_height: row.try_get::<Option<i64>, _>("block_height")?.map(|h| h as u64),
+            transaction_count: row.try_get::<Option<i64>, _>("transaction_count")?.map(|c| c as u64),
+            entry_count: row.try_get::<i64, _>("entry_count")? as u64,
+            entries: Vec::new(),
+            rewards: rewards.map(serde_json::from_value).transpose()?,
+        })
     }
 }
 
 #[async_trait]
 impl Storage for PostgresStore {
     async fn store_account(&self, account: AccountData) -> Result<()> {
-        self.insert_account(&account).await
+        self.copy_accounts(std::slice::from_ref(&account)).await
     }
-    
+
     async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
-        // Begin transaction
+        self.copy_transactions(std::slice::from_ref(&transaction)).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.copy_blocks(std::slice::from_ref(&block)).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        for chunk in accounts.chunks(self.config.batch_size.max(1)) {
+            self.copy_accounts(chunk).await?;
+        }
+        Ok(())
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        for chunk in transactions.chunks(self.config.batch_size.max(1)) {
+            self.copy_transactions(chunk).await?;
+        }
+        Ok(())
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        for chunk in blocks.chunks(self.config.batch_size.max(1)) {
+            self.copy_blocks(chunk).await?;
+        }
+        Ok(())
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        let slot = bundle.slot();
         let mut tx = self.pool.begin().await?;
-        
-        // Insert transaction
+        if !bundle.accounts.is_empty() {
+            Self::merge_accounts(&mut tx, &bundle.accounts).await?;
+        }
+        if !bundle.transactions.is_empty() {
+            Self::merge_transactions(&mut tx, &bundle.transactions).await?;
+        }
+        Self::merge_blocks(&mut tx, std::slice::from_ref(&bundle.block)).await?;
+        tx.commit().await?;
+
+        debug!("Committed bundle for slot {}", slot);
+        Ok(())
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        sqlx::query("UPDATE blocks SET status = $2 WHERE slot = $1")
+            .bind(status.slot as i64)
+            .bind(status.status.as_str())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        let slot: Option<i64> = sqlx::query_scalar("SELECT MAX(slot) FROM blocks WHERE status = 'rooted'")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(slot.map(|s| s as u64))
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
         sqlx::query(
-            r#"
-            INSERT INTO transactions (signature, slot, is_vote, message, meta, index)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            ON CONFLICT (signature) 
-            DO UPDATE SET 
-                slot = EXCLUDED.slot,
-                is_vote = EXCLUDED.is_vote,
-                message = EXCLUDED.message,
-                meta = EXCLUDED.meta,
-                index = EXCLUDED.index,
-                last_updated = CURRENT_TIMESTAMP
-            "#
+            "INSERT INTO indexer_state (id, last_processed_slot, total_accounts, total_transactions, last_updated) \
+             VALUES (1, $1, $2, $3, NOW()) \
+             ON CONFLICT (id) DO UPDATE SET \
+             last_processed_slot = EXCLUDED.last_processed_slot, \
+             total_accounts = EXCLUDED.total_accounts, \
+             total_transactions = EXCLUDED.total_transactions, \
+             last_updated = EXCLUDED.last_updated",
         )
-        .bind(&transaction.signature)
-        .bind(transaction.slot as i64)
-        .bind(transaction.is_vote)
-        .bind(&transaction.message)
-        .bind(&serde_json::to_value(&transaction.meta).unwrap_or_default())
-        .bind(transaction.index as i64)
-        .execute(&mut tx)
+        .bind(state.last_processed_slot as i64)
+        .bind(state.total_accounts as i64)
+        .bind(state.total_transactions as i64)
+        .execute(&self.pool)
         .await?;
-        
-        // Insert mentions (simplified for brevity)
-        
-        // Commit transaction
-        tx.commit().await?;
-        
         Ok(())
     }
-    
-    async fn store_block(&self, block: BlockData) -> Result<()> {
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        let row = sqlx::query("SELECT last_processed_slot, total_accounts, total_transactions FROM indexer_state WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| {
+            Ok(IndexerState {
+                last_processed_slot: row.try_get::<i64, _>("last_processed_slot")? as u64,
+                total_accounts: row.try_get::<i64, _>("total_accounts")? as u64,
+                total_transactions: row.try_get::<i64, _>("total_transactions")? as u64,
+            })
+        })
+        .transpose()
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        let scopes: Vec<&str> = key.scopes.iter().map(ApiScope::as_str).collect();
         sqlx::query(
-            r#"
-            INSERT INTO blocks (slot, blockhash, parent_blockhash)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (slot) 
-            DO UPDATE SET 
-                blockhash = EXCLUDED.blockhash,
-                parent_blockhash = EXCLUDED.parent_blockhash,
-                last_updated = CURRENT_TIMESTAMP
-            "#
+            "INSERT INTO api_keys (id, name, secret_hash, scopes, rate_limit_per_minute, created_at, expires_at, revoked, tenant) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+             ON CONFLICT (id) DO UPDATE SET \
+             name = EXCLUDED.name, \
+             secret_hash = EXCLUDED.secret_hash, \
+             scopes = EXCLUDED.scopes, \
+             rate_limit_per_minute = EXCLUDED.rate_limit_per_minute, \
+             expires_at = EXCLUDED.expires_at, \
+             revoked = EXCLUDED.revoked, \
+             tenant = EXCLUDED.tenant",
         )
-        .bind(block.slot as i64)
-        .bind(block.blockhash)
-        .bind(block.parent_blockhash)
+        .bind(&key.id)
+        .bind(&key.name)
+        .bind(&key.secret_hash)
+        .bind(&scopes)
+        .bind(key.rate_limit_per_minute.map(|limit| limit as i64))
+        .bind(key.created_at)
+        .bind(key.expires_at)
+        .bind(key.revoked)
+        .bind(&key.tenant)
         .execute(&self.pool)
         .await?;
-        
         Ok(())
     }
-    
-    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
-        let row = sqlx::query(
-            "SELECT * FROM accounts WHERE pubkey = $1"
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        let rows = sqlx::query(
+            "SELECT id, name, secret_hash, scopes, rate_limit_per_minute, created_at, expires_at, revoked, tenant FROM api_keys",
         )
-        .bind(pubkey)
-        .fetch_optional(&self.pool)
+        .fetch_all(&self.pool)
         .await?;
-        
-        match row {
-            Some(row) => Ok(Some(Self::account_from_row(row).await?)),
-            None => Ok(None),
-        }
+
+        rows.into_iter()
+            .map(|row| {
+                let scopes = row.try_get::<Vec<String>, _>("scopes")?
+                    .iter()
+                    .map(|scope| scope.parse::<ApiScope>().map_err(|e| anyhow!(e)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(ApiKeyRecord {
+                    id: row.try_get("id")?,
+                    name: row.try_get("name")?,
+                    secret_hash: row.try_get("secret_hash")?,
+                    scopes,
+                    rate_limit_per_minute: row.try_get::<Option<i64>, _>("rate_limit_per_minute")?.map(|limit| limit as u32),
+                    created_at: row.try_get("created_at")?,
+                    expires_at: row.try_get("expires_at")?,
+                    revoked: row.try_get("revoked")?,
+                    tenant: row.try_get("tenant")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM api_keys WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
     }
-    
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO staking_operators (operator, total_stake, performance_score, last_active, reward_balance, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (operator) DO UPDATE SET \
+             total_stake = EXCLUDED.total_stake, \
+             performance_score = EXCLUDED.performance_score, \
+             last_active = EXCLUDED.last_active, \
+             reward_balance = EXCLUDED.reward_balance, \
+             updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&record.operator)
+        .bind(record.total_stake as i64)
+        .bind(record.performance_score)
+        .bind(record.last_active)
+        .bind(record.reward_balance as i64)
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        let rows = sqlx::query(
+            "SELECT operator, total_stake, performance_score, last_active, reward_balance, updated_at FROM staking_operators",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(OperatorRecord {
+                    operator: row.try_get("operator")?,
+                    total_stake: row.try_get::<i64, _>("total_stake")? as u64,
+                    performance_score: row.try_get("performance_score")?,
+                    last_active: row.try_get("last_active")?,
+                    reward_balance: row.try_get::<i64, _>("reward_balance")? as u64,
+                    updated_at: row.try_get("updated_at")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO staking_delegations (operator, staker, amount, reward_balance, pending_withdrawals, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (operator, staker) DO UPDATE SET \
+             amount = EXCLUDED.amount, \
+             reward_balance = EXCLUDED.reward_balance, \
+             pending_withdrawals = EXCLUDED.pending_withdrawals, \
+             updated_at = EXCLUDED.updated_at",
+        )
+        .bind(&record.operator)
+        .bind(&record.staker)
+        .bind(record.amount as i64)
+        .bind(record.reward_balance as i64)
+        .bind(serde_json::to_value(&record.pending_withdrawals)?)
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        let rows = sqlx::query(
+            "SELECT operator, staker, amount, reward_balance, pending_withdrawals, updated_at FROM staking_delegations",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(DelegationRecord {
+                    operator: row.try_get("operator")?,
+                    staker: row.try_get("staker")?,
+                    amount: row.try_get::<i64, _>("amount")? as u64,
+                    reward_balance: row.try_get::<i64, _>("reward_balance")? as u64,
+                    pending_withdrawals: serde_json::from_value(row.try_get("pending_withdrawals")?)?,
+                    updated_at: row.try_get("updated_at")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM staking_delegations WHERE operator = $1 AND staker = $2")
+            .bind(operator)
+            .bind(staker)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO staking_slashes (operator, violation, amount, stake_before, epoch, timestamp) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(&record.operator)
+        .bind(&record.violation)
+        .bind(record.amount as i64)
+        .bind(record.stake_before as i64)
+        .bind(record.epoch as i64)
+        .bind(record.timestamp)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        let rows = sqlx::query(
+            "SELECT operator, violation, amount, stake_before, epoch, timestamp FROM staking_slashes \
+             WHERE $1::TEXT IS NULL OR operator = $1 \
+             ORDER BY id DESC LIMIT $2",
+        )
+        .bind(operator)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(SlashRecord {
+                    operator: row.try_get("operator")?,
+                    violation: row.try_get("violation")?,
+                    amount: row.try_get::<i64, _>("amount")? as u64,
+                    stake_before: row.try_get::<i64, _>("stake_before")? as u64,
+                    epoch: row.try_get::<i64, _>("epoch")? as u64,
+                    timestamp: row.try_get("timestamp")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO staking_performance (operator, epoch, uptime, missed_rounds, rounds, response_time, \
+             message_success_rate, slashes, performance_score, timestamp) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) \
+             ON CONFLICT (operator, epoch) DO UPDATE SET \
+             uptime = EXCLUDED.uptime, \
+             missed_rounds = EXCLUDED.missed_rounds, \
+             rounds = EXCLUDED.rounds, \
+             response_time = EXCLUDED.response_time, \
+             message_success_rate = EXCLUDED.message_success_rate, \
+             slashes = EXCLUDED.slashes, \
+             performance_score = EXCLUDED.performance_score, \
+             timestamp = EXCLUDED.timestamp",
+        )
+        .bind(&record.operator)
+        .bind(record.epoch as i64)
+        .bind(record.uptime)
+        .bind(record.missed_rounds as i64)
+        .bind(record.rounds as i64)
+        .bind(record.response_time)
+        .bind(record.message_success_rate)
+        .bind(record.slashes as i64)
+        .bind(record.performance_score)
+        .bind(record.timestamp)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        let rows = sqlx::query(
+            "SELECT operator, epoch, uptime, missed_rounds, rounds, response_time, message_success_rate, slashes, \
+             performance_score, timestamp FROM staking_performance \
+             WHERE ($1::TEXT IS NULL OR operator = $1) AND timestamp >= $2 \
+             ORDER BY epoch, operator",
+        )
+        .bind(operator)
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(PerformanceRecord {
+                    operator: row.try_get("operator")?,
+                    epoch: row.try_get::<i64, _>("epoch")? as u64,
+                    uptime: row.try_get("uptime")?,
+                    missed_rounds: row.try_get::<i64, _>("missed_rounds")? as u64,
+                    rounds: row.try_get::<i64, _>("rounds")? as u64,
+                    response_time: row.try_get("response_time")?,
+                    message_success_rate: row.try_get("message_success_rate")?,
+                    slashes: row.try_get::<i64, _>("slashes")? as u64,
+                    performance_score: row.try_get("performance_score")?,
+                    timestamp: row.try_get("timestamp")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        let result = sqlx::query("DELETE FROM staking_performance WHERE epoch < $1")
+            .bind(epoch as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() as usize)
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        let row = sqlx::query(&format!("SELECT {} FROM accounts WHERE pubkey = $1", ACCOUNT_COLUMNS))
+            .bind(pubkey)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::account_from_row).transpose()
+    }
+
     async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
-        // Simplified implementation
-        Ok(None)
+        let row = sqlx::query(&format!("SELECT {} FROM transactions WHERE signature = $1", TRANSACTION_COLUMNS))
+            .bind(signature)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::transaction_from_row).transpose()
     }
-    
+
     async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
-        // Simplified implementation
-        Ok(None)
+        let row = sqlx::query(&format!("SELECT {} FROM blocks WHERE slot = $1", BLOCK_COLUMNS))
+            .bind(slot as i64)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(Self::block_from_row).transpose()
     }
-    
+
     async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
-        let rows = sqlx::query(
-            "SELECT * FROM accounts ORDER BY last_updated DESC LIMIT $1"
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM accounts ORDER BY last_updated DESC LIMIT $1",
+            ACCOUNT_COLUMNS
+        ))
         .bind(limit as i64)
         .fetch_all(&self.pool)
         .await?;
-        
-        let mut accounts = Vec::with_capacity(rows.len());
-        for row in rows {
-            accounts.push(Self::account_from_row(row).await?);
-        }
-        
-        Ok(accounts)
+
+        rows.iter().map(Self::account_from_row).collect()
     }
-    
+
     async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
-        // Simplified implementation
-        Ok(Vec::new())
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM transactions ORDER BY slot DESC, tx_index DESC LIMIT $1",
+            TRANSACTION_COLUMNS
+        ))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::transaction_from_row).collect()
     }
-    
+
     async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
-        // Simplified implementation
-        Ok(Vec::new())
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM blocks ORDER BY slot DESC LIMIT $1",
+            BLOCK_COLUMNS
+        ))
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::block_from_row).collect()
     }
-    
-    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM account_history WHERE pubkey = $1 AND slot <= $2 \
+             ORDER BY slot DESC, write_version DESC LIMIT 1",
+            ACCOUNT_COLUMNS
+        ))
+        .bind(pubkey)
+        .bind(slot as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(Self::account_from_row).transpose()
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM account_history WHERE pubkey = $1 AND slot BETWEEN $2 AND $3 \
+             ORDER BY slot, write_version LIMIT $4",
+            ACCOUNT_COLUMNS
+        ))
+        .bind(pubkey)
+        .bind(start_slot as i64)
+        .bind(end_slot as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::account_from_row).collect()
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
         let rows = sqlx::query(
-            "SELECT * FROM accounts WHERE slot BETWEEN $1 AND $2 ORDER BY slot, write_version LIMIT $3"
+            "SELECT signature FROM transaction_mentions WHERE pubkey = $1 ORDER BY slot DESC LIMIT $2"
         )
+        .bind(account)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.transactions_by_signature(rows).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        let rows = sqlx::query(
+            "SELECT signature FROM transaction_programs WHERE program_id = $1 ORDER BY slot DESC LIMIT $2"
+        )
+        .bind(program_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        self.transactions_by_signature(rows).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM accounts WHERE slot BETWEEN $1 AND $2 ORDER BY slot, write_version LIMIT $3",
+            ACCOUNT_COLUMNS
+        ))
         .bind(start_slot as i64)
         .bind(end_slot as i64)
         .bind(limit as i64)
         .fetch_all(&self.pool)
         .await?;
-        
-        let mut accounts = Vec::with_capacity(rows.len());
-        for row in rows {
-            accounts.push(Self::account_from_row(row).await?);
-        }
-        
-        Ok(accounts)
+
+        rows.iter().map(Self::account_from_row).collect()
     }
-    
+
     async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
-        // Simplified implementation
-        Ok(Vec::new())
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM transactions WHERE slot BETWEEN $1 AND $2 ORDER BY slot, tx_index LIMIT $3",
+            TRANSACTION_COLUMNS
+        ))
+        .bind(start_slot as i64)
+        .bind(end_slot as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::transaction_from_row).collect()
     }
-    
+
     async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
-        // Simplified implementation
-        Ok(Vec::new())
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM blocks WHERE slot BETWEEN $1 AND $2 ORDER BY slot LIMIT $3",
+            BLOCK_COLUMNS
+        ))
+        .bind(start_slot as i64)
+        .bind(end_slot as i64)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::block_from_row).collect()
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        sqlx::query(account_range_query())
+            .bind(start_slot as i64)
+            .bind(end_slot as i64)
+            .fetch(&self.pool)
+            .map(|row| Self::account_from_row(&row?))
+            .boxed()
     }
-    
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        sqlx::query(transaction_range_query())
+            .bind(start_slot as i64)
+            .bind(end_slot as i64)
+            .fetch(&self.pool)
+            .map(|row| Self::transaction_from_row(&row?))
+            .boxed()
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        // The latest state in `accounts` is kept however old it is
+        let query = match kind {
+            DataKind::Accounts => "DELETE FROM account_history WHERE slot < $1",
+            DataKind::Transactions => "DELETE FROM transactions WHERE slot < $1",
+            DataKind::Blocks => "DELETE FROM blocks WHERE slot < $1",
+        };
+
+        let result = sqlx::query(query)
+            .bind(slot as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
     async fn close(&self) -> Result<()> {
         self.pool.close().await;
         Ok(())
     }
-} 
\ No newline at end of file
+}
+
+/// Encoder for PostgreSQL's binary COPY format
+///
+/// See <https://www.postgresql.org/docs/current/sql-copy.html#id-1.9.3.55.9.4>.
+struct BinaryCopyEncoder {
+    buf: Vec<u8>,
+}
+
+impl BinaryCopyEncoder {
+    fn new() -> Self {
+        let mut buf = Vec::with_capacity(64 * 1024);
+        buf.extend_from_slice(b"PGCOPY\n\xff\r\n\0");
+        buf.extend_from_slice(&0i32.to_be_bytes()); // flags
+        buf.extend_from_slice(&0i32.to_be_bytes()); // header extension length
+        Self { buf }
+    }
+
+    fn start_row(&mut self, fields: i16) {
+        self.buf.extend_from_slice(&fields.to_be_bytes());
+    }
+
+    fn field(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn null(&mut self) {
+        self.buf.extend_from_slice(&(-1i32).to_be_bytes());
+    }
+
+    fn text(&mut self, value: &str) {
+        self.field(value.as_bytes());
+    }
+
+    fn opt_text(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => self.text(value),
+            None => self.null(),
+        }
+    }
+
+    fn bigint(&mut self, value: i64) {
+        self.field(&value.to_be_bytes());
+    }
+
+    fn opt_bigint(&mut self, value: Option<i64>) {
+        match value {
+            Some(value) => self.bigint(value),
+            None => self.null(),
+        }
+    }
+
+    fn boolean(&mut self, value: bool) {
+        self.field(&[value as u8]);
+    }
+
+    fn bytea(&mut self, value: &[u8]) {
+        self.field(value);
+    }
+
+    fn jsonb(&mut self, value: &serde_json::Value) {
+        // jsonb binary format is a version byte followed by the JSON text
+        let text = value.to_string();
+        self.buf.extend_from_slice(&(text.len() as i32 + 1).to_be_bytes());
+        self.buf.push(1);
+        self.buf.extend_from_slice(text.as_bytes());
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.buf.extend_from_slice(&(-1i16).to_be_bytes());
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+        solana_sdk::clock::Slot,
+    };
+
+    fn block(slot: Slot, status: SlotStatus, blockhash: Option<&str>) -> BlockData {
+        BlockData { slot, status, blockhash: blockhash.map(str::to_string), ..Default::default() }
+    }
+
+    #[test]
+    fn collapses_block_updates_to_one_row_per_slot() {
+        let blocks = [
+            block(5, SlotStatus::Processed, Some("hash")),
+            block(6, SlotStatus::Processed, None),
+            block(5, SlotStatus::Confirmed, None),
+        ];
+        let collapsed = collapse_blocks(&blocks);
+        let rows: Vec<_> = collapsed.iter().map(|block| (block.slot, block.status.as_str(), block.blockhash.as_deref())).collect();
+        assert_eq!(rows, vec![(5, "confirmed", Some("hash")), (6, "processed", None)]);
+    }
+}
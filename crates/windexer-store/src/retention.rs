@@ -0,0 +1,289 @@
+//! Data retention and pruning
+//!
+//! The [`RetentionManager`] periodically removes data that has fallen outside
+//! the configured retention window for each data type. Windows are expressed
+//! either as a number of slots behind the latest stored slot or as a wall-clock
+//! age, which is converted to slots using the nominal slot duration.
+
+use {
+    crate::traits::Storage,
+    anyhow::{anyhow, Result},
+    serde::{Deserialize, Serialize},
+    solana_sdk::clock::DEFAULT_MS_PER_SLOT,
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc,
+        },
+        time::Duration,
+    },
+    tokio::task::JoinHandle,
+    tracing::{debug, error, info, warn},
+};
+
+/// Kind of data a retention policy applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataKind {
+    Accounts,
+    Transactions,
+    Blocks,
+}
+
+/// How long data of a given kind is kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "mode", content = "value")]
+pub enum RetentionPolicy {
+    /// Never prune
+    Forever,
+    /// Keep data newer than the given number of seconds
+    MaxAgeSecs(u64),
+    /// Keep data within the given number of slots of the latest slot
+    MaxSlots(u64),
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy::Forever
+    }
+}
+
+impl RetentionPolicy {
+    /// Oldest slot to keep, given the latest stored slot
+    pub fn cutoff_slot(&self, latest_slot: u64) -> Option<u64> {
+        match *self {
+            RetentionPolicy::Forever => None,
+            RetentionPolicy::MaxAgeSecs(secs) => {
+                let slots = secs.saturating_mul(1000) / DEFAULT_MS_PER_SLOT;
+                Some(latest_slot.saturating_sub(slots))
+            }
+            RetentionPolicy::MaxSlots(slots) => Some(latest_slot.saturating_sub(slots)),
+        }
+    }
+}
+
+/// Retention configuration per data type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    #[serde(default)]
+    pub accounts: RetentionPolicy,
+    #[serde(default)]
+    pub transactions: RetentionPolicy,
+    #[serde(default)]
+    pub blocks: RetentionPolicy,
+    #[serde(default = "default_prune_interval_secs")]
+    pub interval_seconds: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            accounts: RetentionPolicy::Forever,
+            transactions: RetentionPolicy::MaxAgeSecs(30 * 24 * 60 * 60),
+            blocks: RetentionPolicy::MaxAgeSecs(7 * 24 * 60 * 60),
+            interval_seconds: default_prune_interval_secs(),
+        }
+    }
+}
+
+impl RetentionConfig {
+    fn policy(&self, kind: DataKind) -> RetentionPolicy {
+        match kind {
+            DataKind::Accounts => self.accounts,
+            DataKind::Transactions => self.transactions,
+            DataKind::Blocks => self.blocks,
+        }
+    }
+}
+
+fn default_prune_interval_secs() -> u64 {
+    3600
+}
+
+/// Pruning statistics
+#[derive(Default)]
+pub struct RetentionStats {
+    pub runs: AtomicU64,
+    pub run_errors: AtomicU64,
+    pub accounts_pruned: AtomicU64,
+    pub transactions_pruned: AtomicU64,
+    pub blocks_pruned: AtomicU64,
+    /// Cutoff slot of the last successful prune of each data type
+    pub accounts_cutoff_slot: AtomicU64,
+    pub transactions_cutoff_slot: AtomicU64,
+    pub blocks_cutoff_slot: AtomicU64,
+    pub last_run_timestamp: AtomicU64,
+}
+
+impl RetentionStats {
+    fn pruned(&self, kind: DataKind) -> &AtomicU64 {
+        match kind {
+            DataKind::Accounts => &self.accounts_pruned,
+            DataKind::Transactions => &self.transactions_pruned,
+            DataKind::Blocks => &self.blocks_pruned,
+        }
+    }
+
+    fn cutoff_slot(&self, kind: DataKind) -> &AtomicU64 {
+        match kind {
+            DataKind::Accounts => &self.accounts_cutoff_slot,
+            DataKind::Transactions => &self.transactions_cutoff_slot,
+            DataKind::Blocks => &self.blocks_cutoff_slot,
+        }
+    }
+}
+
+impl Debug for RetentionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("RetentionStats")
+            .field("runs", &self.runs.load(Ordering::Relaxed))
+            .field("run_errors", &self.run_errors.load(Ordering::Relaxed))
+            .field("accounts_pruned", &self.accounts_pruned.load(Ordering::Relaxed))
+            .field("transactions_pruned", &self.transactions_pruned.load(Ordering::Relaxed))
+            .field("blocks_pruned", &self.blocks_pruned.load(Ordering::Relaxed))
+            .field("accounts_cutoff_slot", &self.accounts_cutoff_slot.load(Ordering::Relaxed))
+            .field("transactions_cutoff_slot", &self.transactions_cutoff_slot.load(Ordering::Relaxed))
+            .field("blocks_cutoff_slot", &self.blocks_cutoff_slot.load(Ordering::Relaxed))
+            .field("last_run_timestamp", &self.last_run_timestamp.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// Runs periodic pruning jobs against a storage backend
+pub struct RetentionManager {
+    storage: Arc<dyn Storage>,
+    config: RetentionConfig,
+    stats: Arc<RetentionStats>,
+}
+
+impl RetentionManager {
+    pub fn new(storage: Arc<dyn Storage>, config: RetentionConfig) -> Self {
+        Self {
+            storage,
+            config,
+            stats: Arc::new(RetentionStats::default()),
+        }
+    }
+
+    /// Get the pruning statistics
+    pub fn stats(&self) -> Arc<RetentionStats> {
+        self.stats.clone()
+    }
+
+    /// Spawn the periodic pruning task
+    pub fn start(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(self.config.interval_seconds.max(1));
+            let mut interval = tokio::time::interval(period);
+            info!("Retention manager started (interval={:?})", period);
+
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_once().await {
+                    error!("Retention run failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Run a single pruning pass over every data type, counting a failed
+    /// pass in [`RetentionStats::run_errors`]
+    ///
+    /// A data type that fails to prune does not stop the others; the pass
+    /// then fails naming every one that did.
+    pub async fn run_once(&self) -> Result<()> {
+        let result = self.prune().await;
+        if result.is_err() {
+            self.stats.run_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    async fn prune(&self) -> Result<()> {
+        let latest_slot = match self.latest_slot().await? {
+            Some(slot) => slot,
+            None => {
+                debug!("Nothing stored yet, skipping retention run");
+                return Ok(());
+            }
+        };
+
+        let mut failed = Vec::new();
+        for kind in [DataKind::Accounts, DataKind::Transactions, DataKind::Blocks] {
+            let Some(cutoff) = self.config.policy(kind).cutoff_slot(latest_slot) else {
+                continue;
+            };
+
+            match self.storage.prune_before_slot(kind, cutoff).await {
+                Ok(pruned) => {
+                    self.stats.pruned(kind).fetch_add(pruned, Ordering::Relaxed);
+                    self.stats.cutoff_slot(kind).store(cutoff, Ordering::Relaxed);
+                    if pruned > 0 {
+                        info!("Pruned {} {:?} older than slot {}", pruned, kind, cutoff);
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to prune {:?}: {}", kind, e);
+                    failed.push(format!("{:?}: {}", kind, e));
+                }
+            }
+        }
+
+        self.stats.runs.fetch_add(1, Ordering::Relaxed);
+        self.stats.last_run_timestamp.store(
+            windexer_common::utils::current_timestamp() as u64,
+            Ordering::Relaxed,
+        );
+
+        if !failed.is_empty() {
+            return Err(anyhow!("Failed to prune {}", failed.join("; ")));
+        }
+        Ok(())
+    }
+
+    async fn latest_slot(&self) -> Result<Option<u64>> {
+        if let Some(block) = self.storage.get_recent_blocks(1).await?.first() {
+            return Ok(Some(block.slot));
+        }
+
+        let transactions = self.storage.get_recent_transactions(1).await
+            .map_err(|e| anyhow!("Failed to determine latest slot: {}", e))?;
+        Ok(transactions.first().map(|tx| tx.slot))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::memory_store::MemoryStore,
+        windexer_common::types::BlockData,
+    };
+
+    #[tokio::test]
+    async fn test_failed_prune_is_counted_per_kind() {
+        // The memory store prunes blocks; the failing store below cannot
+        let storage = Arc::new(MemoryStore::default());
+        for slot in [10, 200] {
+            storage.store_block(BlockData { slot, ..Default::default() }).await.unwrap();
+        }
+        let config = RetentionConfig {
+            accounts: RetentionPolicy::Forever,
+            transactions: RetentionPolicy::Forever,
+            blocks: RetentionPolicy::MaxSlots(100),
+            interval_seconds: 3600,
+        };
+        let manager = RetentionManager::new(storage, config.clone());
+        manager.run_once().await.unwrap();
+        assert_eq!(manager.stats().blocks_pruned.load(Ordering::Relaxed), 1);
+        assert_eq!(manager.stats().blocks_cutoff_slot.load(Ordering::Relaxed), 100);
+
+        let storage = Arc::new(crate::testing::FailingStore::default());
+        storage.store_block(BlockData { slot: 200, ..Default::default() }).await.unwrap();
+        let manager = RetentionManager::new(storage, config);
+        assert!(manager.run_once().await.is_err());
+        assert_eq!(manager.stats().run_errors.load(Ordering::Relaxed), 1);
+        assert_eq!(manager.stats().blocks_cutoff_slot.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,367 @@
+//! Indexing progress tracking
+//!
+//! [`IndexerStateStore`] counts what is written through it and persists an
+//! [`IndexerState`] in the wrapped backend every time a slot is rooted. The
+//! state is saved after the slot's data has been handed to the backend, and
+//! wrappers that buffer writes flush them before saving, so after a crash
+//! `last_processed_slot` never points past data that was lost. The Geyser
+//! plugin and the backfill engine read it at startup to resume from there.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    anyhow::Result,
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        sync::Arc,
+    },
+    tokio::sync::Mutex,
+    tracing::{info, warn},
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// Tracks and persists indexing progress of the wrapped backend
+pub struct IndexerStateStore {
+    inner: Arc<dyn Storage>,
+    state: Mutex<IndexerState>,
+    /// Cleared when the backend cannot persist state
+    persist: bool,
+}
+
+impl IndexerStateStore {
+    /// Wrap a backend, resuming from the state it last persisted
+    pub async fn open(inner: Arc<dyn Storage>) -> Self {
+        let (state, persist) = match inner.load_indexer_state().await {
+            Ok(Some(state)) => {
+                info!("Resuming indexing after slot {}", state.last_processed_slot);
+                (state, true)
+            }
+            Ok(None) => (IndexerState::default(), true),
+            Err(e) => {
+                warn!("Indexing progress will not be persisted: {}", e);
+                (IndexerState::default(), false)
+            }
+        };
+
+        Self {
+            inner,
+            state: Mutex::new(state),
+            persist,
+        }
+    }
+
+    /// Current indexing progress
+    pub async fn state(&self) -> IndexerState {
+        self.state.lock().await.clone()
+    }
+
+    async fn record_writes(&self, accounts: usize, transactions: usize) {
+        let mut state = self.state.lock().await;
+        state.total_accounts += accounts as u64;
+        state.total_transactions += transactions as u64;
+    }
+
+    /// Advance to a rooted slot and persist the result
+    ///
+    /// The lock is held while saving so concurrent roots are persisted in order.
+    async fn record_rooted_slot(&self, slot: u64) -> Result<()> {
+        let mut state = self.state.lock().await;
+        if !state.record_rooted_slot(slot) || !self.persist {
+            return Ok(());
+        }
+        self.inner.save_indexer_state(&state).await
+    }
+}
+
+impl Debug for IndexerStateStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("IndexerStateStore")
+            .field("persist", &self.persist)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for IndexerStateStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.inner.store_account(account).await?;
+        self.record_writes(1, 0).await;
+        Ok(())
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.inner.store_transaction(transaction).await?;
+        self.record_writes(0, 1).await;
+        Ok(())
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.inner.store_block(block).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        let count = accounts.len();
+        self.inner.store_accounts(accounts).await?;
+        self.record_writes(count, 0).await;
+        Ok(())
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        let count = transactions.len();
+        self.inner.store_transactions(transactions).await?;
+        self.record_writes(0, count).await;
+        Ok(())
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        self.inner.store_blocks(blocks).await
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        let slot = bundle.slot();
+        let rooted = matches!(bundle.block.status, SlotStatus::Rooted);
+        let (accounts, transactions) = (bundle.accounts.len(), bundle.transactions.len());
+
+        self.inner.store_slot_bundle(bundle).await?;
+        self.record_writes(accounts, transactions).await;
+        if rooted {
+            self.record_rooted_slot(slot).await?;
+        }
+        Ok(())
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        let slot = status.slot;
+        let rooted = matches!(status.status, SlotStatus::Rooted);
+
+        self.inner.update_slot_status(status).await?;
+        if rooted {
+            self.record_rooted_slot(slot).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.inner.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.inner.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        let mut current = self.state.lock().await;
+        self.inner.save_indexer_state(state).await?;
+        *current = state.clone();
+        Ok(())
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.inner.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.inner.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.inner.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.inner.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.inner.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.inner.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.inner.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.inner.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        self.inner.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.inner.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.inner.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::memory_store::MemoryStore};
+
+    #[tokio::test]
+    async fn test_rooted_slot_is_persisted_and_resumed() {
+        let backend: Arc<dyn Storage> = Arc::new(MemoryStore::new());
+        let store = IndexerStateStore::open(backend.clone()).await;
+
+        store.update_slot_status(SlotStatusData { slot: 42, parent: Some(41), status: SlotStatus::Rooted }).await.unwrap();
+        store.update_slot_status(SlotStatusData { slot: 40, parent: Some(39), status: SlotStatus::Rooted }).await.unwrap();
+
+        let resumed = IndexerStateStore::open(backend).await;
+        assert_eq!(resumed.state().await.last_processed_slot, 42);
+    }
+}
@@ -0,0 +1,482 @@
+//! Tenant namespaces
+//!
+//! [`TenantStore`] lets one deployment serve several isolated tenants. Every
+//! write reaches the shared backend it wraps, and the records that pass a
+//! tenant's [`TenantFilter`] are also written to that tenant's own backend,
+//! usually the shared configuration with paths prefixed by the tenant id.
+//! Blocks and slot statuses go to every tenant.
+//!
+//! Reads through the wrapper are answered by the shared backend; a tenant's
+//! backend is reached with [`Storage::tenant`]. API keys and indexer state are
+//! only kept in the shared backend.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::{anyhow, Context, Result},
+    async_trait::async_trait,
+    futures::{future::try_join_all, stream::BoxStream},
+    serde::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashSet},
+        fmt::{Debug, Formatter, Result as FmtResult},
+        future::Future,
+        str::FromStr,
+        sync::Arc,
+    },
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// Data a tenant receives; an empty filter receives everything but votes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantFilter {
+    /// Accounts to index, and whose transactions to index
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    /// Index the accounts owned by these programs
+    #[serde(default)]
+    pub owners: Vec<String>,
+    /// Index the transactions invoking these programs
+    #[serde(default)]
+    pub programs: Vec<String>,
+    #[serde(default)]
+    pub include_votes: bool,
+}
+
+/// A tenant id must be usable as a path segment and a header value
+pub fn validate_tenant_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && id.bytes().all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-' || byte == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid tenant id '{}': use 1 to 64 lowercase letters, digits, '-' or '_'", id))
+    }
+}
+
+fn parse_pubkeys(pubkeys: &[String]) -> Result<HashSet<Pubkey>> {
+    pubkeys.iter()
+        .map(|pubkey| Pubkey::from_str(pubkey).map_err(|e| anyhow!("Invalid pubkey {}: {}", pubkey, e)))
+        .collect()
+}
+
+/// A [`TenantFilter`] with its keys parsed, deciding which records reach a tenant
+pub struct TenantMatcher {
+    accounts: HashSet<Pubkey>,
+    owners: HashSet<Pubkey>,
+    programs: HashSet<Pubkey>,
+    include_votes: bool,
+}
+
+impl TenantMatcher {
+    pub fn new(filter: &TenantFilter) -> Result<Self> {
+        Ok(Self {
+            accounts: parse_pubkeys(&filter.accounts)?,
+            owners: parse_pubkeys(&filter.owners)?,
+            programs: parse_pubkeys(&filter.programs)?,
+            include_votes: filter.include_votes,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.owners.is_empty() && self.programs.is_empty()
+    }
+
+    pub fn matches_account(&self, account: &AccountData) -> bool {
+        self.is_empty() || self.accounts.contains(&account.pubkey) || self.owners.contains(&account.owner)
+    }
+
+    pub fn matches_transaction(&self, transaction: &TransactionData) -> bool {
+        if transaction.is_vote && !self.include_votes {
+            return false;
+        }
+        if self.is_empty() {
+            return true;
+        }
+
+        let keys = &transaction.message.account_keys;
+        keys.iter().any(|key| self.accounts.contains(key))
+            || transaction.message.instructions.iter()
+                .filter_map(|instruction| keys.get(instruction.program_id_index as usize))
+                .any(|program| self.programs.contains(program))
+    }
+}
+
+struct Tenant {
+    storage: Arc<dyn Storage>,
+    filter: Arc<TenantMatcher>,
+}
+
+/// Storage wrapper that copies writes into per-tenant backends
+pub struct TenantStore {
+    inner: Arc<dyn Storage>,
+    tenants: BTreeMap<String, Tenant>,
+}
+
+impl TenantStore {
+    pub fn new(inner: Arc<dyn Storage>) -> Self {
+        Self {
+            inner,
+            tenants: BTreeMap::new(),
+        }
+    }
+
+    /// Add a tenant receiving the writes that pass `filter`
+    pub fn with_tenant(mut self, id: &str, storage: Arc<dyn Storage>, filter: &TenantFilter) -> Result<Self> {
+        validate_tenant_id(id)?;
+        let filter = TenantMatcher::new(filter).with_context(|| format!("Invalid filter for tenant {}", id))?;
+        let filter = Arc::new(filter);
+        if self.tenants.insert(id.to_string(), Tenant { storage, filter }).is_some() {
+            return Err(anyhow!("Tenant {} is configured twice", id));
+        }
+        Ok(self)
+    }
+
+    pub fn tenant_ids(&self) -> impl Iterator<Item = &str> {
+        self.tenants.keys().map(String::as_str)
+    }
+
+    /// Run `write` against every tenant, tagging failures with the tenant id
+    async fn each_tenant<'a, F, Fut>(&'a self, write: F) -> Result<()>
+    where
+        F: Fn(&'a Tenant) -> Option<Fut>,
+        Fut: Future<Output = Result<()>> + 'a,
+    {
+        try_join_all(self.tenants.iter().filter_map(|(id, tenant)| {
+            write(tenant).map(|fut| async move {
+                fut.await.with_context(|| format!("Failed to write to tenant {}", id))
+            })
+        }))
+        .await?;
+        Ok(())
+    }
+}
+
+impl Debug for TenantStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("TenantStore")
+            .field("tenants", &self.tenants.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for TenantStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.each_tenant(|tenant| {
+            tenant.filter.matches_account(&account).then(|| tenant.storage.store_account(account.clone()))
+        }).await?;
+        self.inner.store_account(account).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.each_tenant(|tenant| {
+            tenant.filter.matches_transaction(&transaction).then(|| tenant.storage.store_transaction(transaction.clone()))
+        }).await?;
+        self.inner.store_transaction(transaction).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.each_tenant(|tenant| Some(tenant.storage.store_block(block.clone()))).await?;
+        self.inner.store_block(block).await
+    }
+
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        self.each_tenant(|tenant| {
+            let accounts: Vec<_> = accounts.iter().filter(|account| tenant.filter.matches_account(account)).cloned().collect();
+            (!accounts.is_empty()).then(|| tenant.storage.store_accounts(accounts))
+        }).await?;
+        self.inner.store_accounts(accounts).await
+    }
+
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        self.each_tenant(|tenant| {
+            let transactions: Vec<_> = transactions.iter()
+                .filter(|transaction| tenant.filter.matches_transaction(transaction))
+                .cloned()
+                .collect();
+            (!transactions.is_empty()).then(|| tenant.storage.store_transactions(transactions))
+        }).await?;
+        self.inner.store_transactions(transactions).await
+    }
+
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        self.each_tenant(|tenant| Some(tenant.storage.store_blocks(blocks.clone()))).await?;
+        self.inner.store_blocks(blocks).await
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        self.each_tenant(|tenant| {
+            Some(tenant.storage.store_slot_bundle(SlotBundle {
+                block: bundle.block.clone(),
+                transactions: bundle.transactions.iter()
+                    .filter(|transaction| tenant.filter.matches_transaction(transaction))
+                    .cloned()
+                    .collect(),
+                accounts: bundle.accounts.iter().filter(|account| tenant.filter.matches_account(account)).cloned().collect(),
+            }))
+        }).await?;
+        self.inner.store_slot_bundle(bundle).await
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.each_tenant(|tenant| Some(tenant.storage.update_slot_status(status.clone()))).await?;
+        self.inner.update_slot_status(status).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.inner.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.inner.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        self.inner.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.inner.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.inner.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.inner.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.inner.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.inner.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.inner.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.inner.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.inner.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.inner.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.inner.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.inner.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.inner.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.inner.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.inner.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.inner.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.inner.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.inner.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.inner.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.inner.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.inner.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.inner.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.inner.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.inner.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.inner.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.inner.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.inner.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.inner.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.inner.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.inner.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    // Retention applies to every tenant alike
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        let mut removed = self.inner.prune_before_slot(kind, slot).await?;
+        for (id, tenant) in &self.tenants {
+            removed += tenant.storage.prune_before_slot(kind, slot).await
+                .with_context(|| format!("Failed to prune tenant {}", id))?;
+        }
+        Ok(removed)
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        match self.tenants.get(id) {
+            Some(tenant) => Some(tenant.storage.clone()),
+            None => self.inner.tenant(id),
+        }
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        match self.tenants.get(id) {
+            Some(tenant) => Some(tenant.filter.clone()),
+            None => self.inner.tenant_matcher(id),
+        }
+    }
+
+    async fn close(&self) -> Result<()> {
+        for tenant in self.tenants.values() {
+            tenant.storage.close().await?;
+        }
+        self.inner.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::memory_store::MemoryStore};
+
+    fn account(owner: Pubkey) -> AccountData {
+        AccountData {
+            pubkey: Pubkey::new_unique(),
+            lamports: 1_000_000,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+            data: vec![],
+            write_version: 1,
+            slot: 10,
+            is_startup: false,
+            transaction_signature: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tenants_only_receive_matching_accounts() {
+        let program = Pubkey::new_unique();
+        let filter = TenantFilter { owners: vec![program.to_string()], ..TenantFilter::default() };
+        let store = TenantStore::new(Arc::new(MemoryStore::new()))
+            .with_tenant("acme", Arc::new(MemoryStore::new()), &filter)
+            .unwrap();
+
+        let owned = account(program);
+        let other = account(Pubkey::new_unique());
+        store.store_accounts(vec![owned.clone(), other.clone()]).await.unwrap();
+
+        let tenant = store.tenant("acme").unwrap();
+        assert!(tenant.get_account(&owned.pubkey.to_string()).await.unwrap().is_some());
+        assert!(tenant.get_account(&other.pubkey.to_string()).await.unwrap().is_none());
+        assert!(store.get_account(&other.pubkey.to_string()).await.unwrap().is_some());
+        assert!(store.tenant("globex").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+//! Test doubles for the storage wrappers
+
+use {
+    crate::{memory_store::MemoryStore, traits::Storage},
+    anyhow::{bail, Result},
+    async_trait::async_trait,
+    std::sync::atomic::{AtomicBool, Ordering},
+    windexer_common::types::{AccountData, BlockData, SlotStatusData, TransactionData},
+};
+
+/// A [`MemoryStore`] whose writes fail while [`FailingStore::fail_writes`] is set
+#[derive(Default)]
+pub struct FailingStore {
+    inner: MemoryStore,
+    failing: AtomicBool,
+}
+
+impl FailingStore {
+    pub fn fail_writes(&self, fail: bool) {
+        self.failing.store(fail, Ordering::SeqCst);
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.failing.load(Ordering::SeqCst) {
+            bail!("write failed");
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for FailingStore {
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.check()?;
+        self.inner.store_account(account).await
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.check()?;
+        self.inner.store_transaction(transaction).await
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.check()?;
+        self.inner.store_block(block).await
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        self.check()?;
+        self.inner.update_slot_status(status).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        self.inner.get_account(pubkey).await
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        self.inner.get_transaction(signature).await
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        self.inner.get_block(slot).await
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_recent_blocks(limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.inner.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.inner.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.inner.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
@@ -0,0 +1,556 @@
+//! Tiered storage
+//!
+//! [`TieredStore`] keeps the most recently used accounts, transactions and
+//! blocks in an in-memory LRU cache in front of any cold [`Storage`] backend.
+//! Point lookups are served from the cache when possible. Writes update the
+//! cache immediately and are written through to the cold backend
+//! asynchronously via a [`BatchingStore`], so range and recency queries,
+//! which always go to the cold tier, may briefly lag behind the cache. Writes
+//! the pipeline gives up on are evicted again, so the cache never keeps
+//! serving data the cold tier does not have.
+
+use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        pipeline::{BatchingStore, FailedWrites, PipelineConfig, PipelineMetrics},
+        retention::DataKind,
+        tenant::TenantMatcher,
+        traits::{SlotBundle, Storage},
+    },
+    anyhow::Result,
+    async_trait::async_trait,
+    futures::stream::BoxStream,
+    lru::LruCache,
+    std::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        hash::Hash,
+        num::NonZeroUsize,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    },
+    windexer_common::types::{AccountData, ApiKeyRecord, BlockData, Commitment, DelegationRecord, IndexerState, OperatorRecord, PerformanceRecord, SlashRecord, SlotAttestation, SlotStatusData, TransactionData},
+};
+
+/// Configuration for the tiered store
+#[derive(Debug, Clone)]
+pub struct TieredConfig {
+    /// Maximum number of entries cached per data type
+    pub cache_capacity: usize,
+    /// Write-through pipeline settings for the cold tier
+    pub pipeline: PipelineConfig,
+}
+
+impl Default for TieredConfig {
+    fn default() -> Self {
+        Self {
+            cache_capacity: 100_000,
+            pipeline: PipelineConfig::default(),
+        }
+    }
+}
+
+/// Cache metrics
+#[derive(Default)]
+pub struct CacheMetrics {
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub inserts: AtomicU64,
+}
+
+impl CacheMetrics {
+    /// Fraction of lookups served from the cache
+    pub fn hit_ratio(&self) -> f64 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let total = hits + self.misses.load(Ordering::Relaxed);
+        if total == 0 {
+            0.0
+        } else {
+            hits as f64 / total as f64
+        }
+    }
+}
+
+impl Debug for CacheMetrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("CacheMetrics")
+            .field("hits", &self.hits.load(Ordering::Relaxed))
+            .field("misses", &self.misses.load(Ordering::Relaxed))
+            .field("inserts", &self.inserts.load(Ordering::Relaxed))
+            .field("hit_ratio", &self.hit_ratio())
+            .finish()
+    }
+}
+
+/// A single LRU table guarded by a mutex
+struct CacheTable<K: Hash + Eq, V> {
+    entries: Mutex<LruCache<K, V>>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> CacheTable<K, V> {
+    fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn get(&self, key: &K, metrics: &CacheMetrics) -> Option<V> {
+        let value = self.entries.lock().ok()?.get(key).cloned();
+        let counter = if value.is_some() { &metrics.hits } else { &metrics.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    /// Insert a value unless the cached one is newer according to `is_newer`
+    fn put_if<F>(&self, key: K, value: V, metrics: &CacheMetrics, is_newer: F)
+    where
+        F: FnOnce(&V, &V) -> bool,
+    {
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(existing) = entries.peek(&key) {
+                if !is_newer(&value, existing) {
+                    return;
+                }
+            }
+            entries.put(key, value);
+            metrics.inserts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn put(&self, key: K, value: V, metrics: &CacheMetrics) {
+        self.put_if(key, value, metrics, |_, _| true);
+    }
+
+    /// Remove a value unless the cached one is newer according to `is_newer`
+    fn remove_if<F>(&self, key: &K, is_newer: F)
+    where
+        F: FnOnce(&V) -> bool,
+    {
+        if let Ok(mut entries) = self.entries.lock() {
+            if entries.peek(key).is_some_and(|cached| !is_newer(cached)) {
+                entries.pop(key);
+            }
+        }
+    }
+
+    fn retain<F: Fn(&V) -> bool>(&self, keep: F) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let stale: Vec<K> = entries
+                .iter()
+                .filter(|(_, v)| !keep(v))
+                .map(|(k, _)| k.clone())
+                .collect();
+            for key in stale {
+                entries.pop(&key);
+            }
+        }
+    }
+}
+
+/// The cached tables, shared with the writer so it can evict failed writes
+struct Cache {
+    accounts: CacheTable<String, AccountData>,
+    transactions: CacheTable<String, TransactionData>,
+    blocks: CacheTable<u64, BlockData>,
+    metrics: Arc<CacheMetrics>,
+}
+
+impl Cache {
+    fn cache_account(&self, account: AccountData) {
+        self.accounts.put_if(account.pubkey.to_string(), account, &self.metrics, |new, old| {
+            (new.slot, new.write_version) >= (old.slot, old.write_version)
+        });
+    }
+
+    /// Cache a block unless the cached one has a higher commitment
+    fn cache_block(&self, block: BlockData) {
+        self.blocks.put_if(block.slot, block, &self.metrics, |new, old| {
+            Commitment::from_slot_status(new.status.as_str()) >= Commitment::from_slot_status(old.status.as_str())
+        });
+    }
+
+    fn cache_transaction(&self, transaction: TransactionData) {
+        self.transactions.put(transaction.signature.to_string(), transaction, &self.metrics);
+    }
+}
+
+impl FailedWrites for Cache {
+    // Entries cached by a later write are kept, their own write may still land
+
+    fn accounts(&self, accounts: &[AccountData]) {
+        for account in accounts {
+            self.accounts.remove_if(&account.pubkey.to_string(), |cached| {
+                (cached.slot, cached.write_version) > (account.slot, account.write_version)
+            });
+        }
+    }
+
+    fn transactions(&self, transactions: &[TransactionData]) {
+        for transaction in transactions {
+            self.transactions.remove_if(&transaction.signature.to_string(), |_| false);
+        }
+    }
+
+    fn blocks(&self, blocks: &[BlockData]) {
+        for block in blocks {
+            self.blocks.remove_if(&block.slot, |cached| {
+                Commitment::from_slot_status(cached.status.as_str()) > Commitment::from_slot_status(block.status.as_str())
+            });
+        }
+    }
+}
+
+/// In-memory LRU cache in front of a cold storage backend
+pub struct TieredStore {
+    cold: Arc<dyn Storage>,
+    writer: BatchingStore,
+    cache: Arc<Cache>,
+}
+
+impl TieredStore {
+    /// Wrap a cold backend
+    ///
+    /// Must be called from within a tokio runtime.
+    pub fn new(cold: Arc<dyn Storage>, config: TieredConfig) -> Self {
+        let capacity = NonZeroUsize::new(config.cache_capacity).unwrap_or(NonZeroUsize::MIN);
+        let cache = Arc::new(Cache {
+            accounts: CacheTable::new(capacity),
+            transactions: CacheTable::new(capacity),
+            blocks: CacheTable::new(capacity),
+            metrics: Arc::new(CacheMetrics::default()),
+        });
+
+        Self {
+            writer: BatchingStore::with_listener(cold.clone(), config.pipeline, Some(cache.clone())),
+            cold,
+            cache,
+        }
+    }
+
+    /// Get the cache metrics
+    pub fn cache_metrics(&self) -> Arc<CacheMetrics> {
+        self.cache.metrics.clone()
+    }
+
+    /// Get the write-through pipeline metrics
+    pub fn pipeline_metrics(&self) -> Arc<PipelineMetrics> {
+        self.writer.metrics()
+    }
+}
+
+impl Debug for TieredStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("TieredStore")
+            .field("metrics", &self.cache.metrics)
+            .field("writer", &self.writer)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Storage for TieredStore {
+    // The cache is only filled once the writer has taken the write, so a
+    // rejected write is never served from it, and is evicted again if the
+    // writer gives up on it
+
+    async fn store_account(&self, account: AccountData) -> Result<()> {
+        self.writer.store_account(account.clone()).await?;
+        self.cache.cache_account(account);
+        Ok(())
+    }
+
+    async fn store_transaction(&self, transaction: TransactionData) -> Result<()> {
+        self.writer.store_transaction(transaction.clone()).await?;
+        self.cache.cache_transaction(transaction);
+        Ok(())
+    }
+
+    async fn store_block(&self, block: BlockData) -> Result<()> {
+        self.writer.store_block(block.clone()).await?;
+        self.cache.cache_block(block);
+        Ok(())
+    }
+
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        self.writer.store_slot_bundle(bundle.clone()).await?;
+        for account in bundle.accounts {
+            self.cache.cache_account(account);
+        }
+        for transaction in bundle.transactions {
+            self.cache.cache_transaction(transaction);
+        }
+        self.cache.cache_block(bundle.block);
+        Ok(())
+    }
+
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        // Ordered behind pending writes so the cold tier sees them first
+        self.writer.update_slot_status(status).await
+    }
+
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        self.cold.get_latest_rooted_slot().await
+    }
+
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        self.cold.get_latest_slot_with_commitment(commitment).await
+    }
+
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        // Only recorded once the cold tier has every write it covers
+        self.writer.save_indexer_state(state).await
+    }
+
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        self.cold.load_indexer_state().await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        self.cold.save_api_key(key).await
+    }
+
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.cold.load_api_keys().await
+    }
+
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        self.cold.delete_api_key(id).await
+    }
+
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        self.cold.save_operator_record(record).await
+    }
+
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        self.cold.load_operator_records().await
+    }
+
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        self.cold.save_delegation(record).await
+    }
+
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        self.cold.load_delegations().await
+    }
+
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        self.cold.delete_delegation(operator, staker).await
+    }
+
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        self.cold.save_slash_event(record).await
+    }
+
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        self.cold.load_slash_events(operator, limit).await
+    }
+
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        self.cold.save_performance_record(record).await
+    }
+
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        self.cold.load_performance_records(operator, since).await
+    }
+
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        self.cold.prune_performance_records(epoch).await
+    }
+
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        self.cold.mark_network_finalized(attestation).await
+    }
+
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        self.cold.get_slot_attestation(slot).await
+    }
+
+    async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>> {
+        if let Some(account) = self.cache.accounts.get(&pubkey.to_string(), &self.cache.metrics) {
+            return Ok(Some(account));
+        }
+
+        let account = self.cold.get_account(pubkey).await?;
+        if let Some(account) = &account {
+            self.cache.cache_account(account.clone());
+        }
+        Ok(account)
+    }
+
+    async fn get_transaction(&self, signature: &str) -> Result<Option<TransactionData>> {
+        if let Some(tx) = self.cache.transactions.get(&signature.to_string(), &self.cache.metrics) {
+            return Ok(Some(tx));
+        }
+
+        let tx = self.cold.get_transaction(signature).await?;
+        if let Some(tx) = &tx {
+            self.cache.transactions.put(signature.to_string(), tx.clone(), &self.cache.metrics);
+        }
+        Ok(tx)
+    }
+
+    async fn get_block(&self, slot: u64) -> Result<Option<BlockData>> {
+        if let Some(block) = self.cache.blocks.get(&slot, &self.cache.metrics) {
+            return Ok(Some(block));
+        }
+
+        let block = self.cold.get_block(slot).await?;
+        if let Some(block) = &block {
+            self.cache.blocks.put(slot, block.clone(), &self.cache.metrics);
+        }
+        Ok(block)
+    }
+
+    async fn get_recent_accounts(&self, limit: usize) -> Result<Vec<AccountData>> {
+        self.cold.get_recent_accounts(limit).await
+    }
+
+    async fn get_recent_transactions(&self, limit: usize) -> Result<Vec<TransactionData>> {
+        self.cold.get_recent_transactions(limit).await
+    }
+
+    async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>> {
+        self.cold.get_recent_blocks(limit).await
+    }
+
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        self.cold.get_account_at_slot(pubkey, slot).await
+    }
+
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.cold.get_account_history(pubkey, start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.cold.get_transactions_by_account(account, limit).await
+    }
+
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        self.cold.get_transactions_by_program(program_id, limit).await
+    }
+
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        self.cold.get_token_balances(owner).await
+    }
+
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        self.cold.get_token_holders(mint, limit).await
+    }
+
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        self.cold.get_nfts_by_owner(owner).await
+    }
+
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        self.cold.get_nft_by_mint(mint).await
+    }
+
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        self.cold.get_compressed_asset(asset_id).await
+    }
+
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        self.cold.get_cpi_calls(program_id, limit).await
+    }
+
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        self.cold.get_lookup_table(address).await
+    }
+
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        self.cold.get_decoded_account(pubkey).await
+    }
+
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        self.cold.get_decoded_instructions(signature).await
+    }
+
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        self.cold.get_events(program_id, name, limit).await
+    }
+
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.cold.search_prefix(prefix, limit).await
+    }
+
+    async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        self.cold.get_accounts_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<TransactionData>> {
+        self.cold.get_transactions_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>> {
+        self.cold.get_blocks_by_slot_range(start_slot, end_slot, limit).await
+    }
+
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        self.cold.stream_accounts_by_slot_range(start_slot, end_slot)
+    }
+
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        self.cold.stream_transactions_by_slot_range(start_slot, end_slot)
+    }
+
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        match kind {
+            DataKind::Accounts => self.cache.accounts.retain(|a| a.slot >= slot),
+            DataKind::Transactions => self.cache.transactions.retain(|t| t.slot >= slot),
+            DataKind::Blocks => self.cache.blocks.retain(|b| b.slot >= slot),
+        }
+        self.cold.prune_before_slot(kind, slot).await
+    }
+
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        self.cold.tenant(id)
+    }
+
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        self.cold.tenant_matcher(id)
+    }
+
+    async fn close(&self) -> Result<()> {
+        // Closing the writer drains pending writes and closes the cold tier
+        self.writer.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::{memory_store::MemoryStore, testing::FailingStore},
+        agave_geyser_plugin_interface::geyser_plugin_interface::SlotStatus,
+    };
+
+    #[tokio::test]
+    async fn test_cache_keeps_the_higher_commitment() {
+        let store = TieredStore::new(Arc::new(MemoryStore::new()), TieredConfig::default());
+        let block = BlockData { slot: 3, status: SlotStatus::Confirmed, ..Default::default() };
+
+        store.store_block(block.clone()).await.unwrap();
+        store.store_block(BlockData { status: SlotStatus::Processed, ..block }).await.unwrap();
+
+        let cached = store.get_block(3).await.unwrap().unwrap();
+        assert_eq!(cached.status.as_str(), SlotStatus::Confirmed.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_through_is_evicted() {
+        let cold = Arc::new(FailingStore::default());
+        let pipeline = PipelineConfig { flush_retries: 0, ..PipelineConfig::default() };
+        let store = TieredStore::new(cold.clone(), TieredConfig { pipeline, ..TieredConfig::default() });
+
+        cold.fail_writes(true);
+        store.store_block(BlockData { slot: 3, ..Default::default() }).await.unwrap();
+        assert!(store.writer.flush().await.is_err());
+        assert!(store.get_block(3).await.unwrap().is_none());
+
+        cold.fail_writes(false);
+        store.store_block(BlockData { slot: 4, ..Default::default() }).await.unwrap();
+        store.writer.flush().await.unwrap();
+        assert!(store.get_block(4).await.unwrap().is_some());
+    }
+}
@@ -1,18 +1,73 @@
 use {
+    crate::{
+        decoders::{ParsedAccount, ParsedInstruction, ProgramEvent},
+        error::StorageError,
+        index::{CompressedAsset, CpiCall, LookupTable, NftMetadata, SearchHit, TokenBalance},
+        retention::DataKind,
+        tenant::TenantMatcher,
+    },
     anyhow::Result,
-    std::sync::Arc,
+    std::{future::Future, sync::Arc},
     async_trait::async_trait,
+    futures::stream::{self, BoxStream, StreamExt, TryStreamExt},
     windexer_common::{
         types::{
             AccountData,
             TransactionData,
             BlockData,
+            SlotStatusData,
+            Cursor,
+            IndexerState,
+            MAX_PAGE_WINDOW,
+            Page,
+            ApiKeyRecord,
+            Commitment,
+            DelegationRecord,
+            OperatorRecord,
+            PerformanceRecord,
+            SlashRecord,
+            SlotAttestation,
+            SlotContent,
         },
     },
 };
 
+/// Page size used by the default streaming implementations
+pub const STREAM_PAGE_SIZE: usize = 1_000;
+
+/// A block together with the transactions and account updates of its slot
+#[derive(Debug, Clone)]
+pub struct SlotBundle {
+    pub block: BlockData,
+    pub transactions: Vec<TransactionData>,
+    pub accounts: Vec<AccountData>,
+}
+
+impl SlotBundle {
+    pub fn slot(&self) -> u64 {
+        self.block.slot
+    }
+
+    /// Number of records in the bundle, including the block
+    pub fn record_count(&self) -> usize {
+        self.accounts.len() + self.transactions.len() + 1
+    }
+
+    /// Leaf hashes of the bundle's records, whose root is the content hash
+    /// nodes vote on for network finalization
+    pub fn content(&self) -> SlotContent {
+        SlotContent::new(&self.block, &self.transactions, &self.accounts)
+    }
+}
+
 /// A trait representing the core storage capabilities required by wIndexer.
 /// This abstraction allows for pluggable storage backends.
+///
+/// Writes are upserts on the records' [`IdempotencyKey`]s: storing a record
+/// again leaves the backend as it was, except that a block takes the status
+/// of the later write, so batches sent more than once are harmless.
+///
+/// [`IdempotencyKey`]: windexer_common::types::IdempotencyKey
 #[async_trait]
 pub trait Storage: Send + Sync + 'static {
     /// Store an account update
@@ -24,6 +79,181 @@ pub trait Storage: Send + Sync + 'static {
     /// Store a block
     async fn store_block(&self, block: BlockData) -> Result<()>;
     
+    /// Store a batch of account updates
+    ///
+    /// Backends that support bulk writes should override this.
+    async fn store_accounts(&self, accounts: Vec<AccountData>) -> Result<()> {
+        for account in accounts {
+            self.store_account(account).await?;
+        }
+        Ok(())
+    }
+    
+    /// Store a batch of transactions
+    async fn store_transactions(&self, transactions: Vec<TransactionData>) -> Result<()> {
+        for transaction in transactions {
+            self.store_transaction(transaction).await?;
+        }
+        Ok(())
+    }
+    
+    /// Store a batch of blocks
+    async fn store_blocks(&self, blocks: Vec<BlockData>) -> Result<()> {
+        for block in blocks {
+            self.store_block(block).await?;
+        }
+        Ok(())
+    }
+    
+    /// Store a block with its transactions and account updates
+    ///
+    /// Backends with transactions or write batches commit the bundle
+    /// atomically. The default writes the block last, so readers that find
+    /// the block also find its contents.
+    async fn store_slot_bundle(&self, bundle: SlotBundle) -> Result<()> {
+        let SlotBundle { block, transactions, accounts } = bundle;
+        if !accounts.is_empty() {
+            self.store_accounts(accounts).await?;
+        }
+        if !transactions.is_empty() {
+            self.store_transactions(transactions).await?;
+        }
+        self.store_block(block).await
+    }
+    
+    /// Record a slot status transition
+    ///
+    /// Backends that do not track commitment ignore status updates.
+    async fn update_slot_status(&self, status: SlotStatusData) -> Result<()> {
+        let _ = status;
+        Ok(())
+    }
+    
+    /// Get the highest slot known to be rooted
+    ///
+    /// Returns `None` if no rooted slot has been observed yet.
+    async fn get_latest_rooted_slot(&self) -> Result<Option<u64>> {
+        Err(StorageError::unsupported("Slot status tracking"))
+    }
+    
+    /// Get the highest slot that reached `commitment`
+    ///
+    /// Data from later slots is not visible at that commitment; `None` means
+    /// every stored slot is. Backends that only track rooted slots treat
+    /// confirmed like finalized, which never shows unconfirmed data.
+    async fn get_latest_slot_with_commitment(&self, commitment: Commitment) -> Result<Option<u64>> {
+        match commitment {
+            Commitment::Processed => Ok(None),
+            Commitment::Confirmed | Commitment::Finalized => Ok(Some(self.get_latest_rooted_slot().await?.unwrap_or(0))),
+        }
+    }
+    
+    /// Persist indexing progress
+    ///
+    /// Backends must only make the state durable once the data written
+    /// before the call is durable, so a resumed indexer never skips data.
+    async fn save_indexer_state(&self, state: &IndexerState) -> Result<()> {
+        let _ = state;
+        Err(StorageError::unsupported("Indexer state persistence"))
+    }
+    
+    /// Load the last persisted indexing progress
+    async fn load_indexer_state(&self) -> Result<Option<IndexerState>> {
+        Err(StorageError::unsupported("Indexer state persistence"))
+    }
+    
+    /// Insert or replace an API key
+    async fn save_api_key(&self, key: &ApiKeyRecord) -> Result<()> {
+        let _ = key;
+        Err(StorageError::unsupported("API key persistence"))
+    }
+    
+    /// Load every stored API key
+    async fn load_api_keys(&self) -> Result<Vec<ApiKeyRecord>> {
+        Err(StorageError::unsupported("API key persistence"))
+    }
+    
+    /// Delete an API key, returning whether it existed
+    async fn delete_api_key(&self, id: &str) -> Result<bool> {
+        let _ = id;
+        Err(StorageError::unsupported("API key persistence"))
+    }
+    
+    /// Insert or replace the accounting of an operator
+    async fn save_operator_record(&self, record: &OperatorRecord) -> Result<()> {
+        let _ = record;
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Load the accounting of every operator
+    async fn load_operator_records(&self) -> Result<Vec<OperatorRecord>> {
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Insert or replace the delegation of `record.staker` to `record.operator`
+    async fn save_delegation(&self, record: &DelegationRecord) -> Result<()> {
+        let _ = record;
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Load every delegation
+    async fn load_delegations(&self) -> Result<Vec<DelegationRecord>> {
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Delete a delegation, returning whether it existed
+    async fn delete_delegation(&self, operator: &str, staker: &str) -> Result<bool> {
+        let _ = (operator, staker);
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Record a slash of an operator's stake
+    async fn save_slash_event(&self, record: &SlashRecord) -> Result<()> {
+        let _ = record;
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Load up to `limit` slashes, newest first, of `operator` or of every
+    /// operator
+    async fn load_slash_events(&self, operator: Option<&str>, limit: usize) -> Result<Vec<SlashRecord>> {
+        let _ = (operator, limit);
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Insert or replace the performance of `record.operator` in `record.epoch`
+    async fn save_performance_record(&self, record: &PerformanceRecord) -> Result<()> {
+        let _ = record;
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Load the performance records, oldest first, of `operator` or of every
+    /// operator, for epochs closed at or after the unix timestamp `since`
+    async fn load_performance_records(&self, operator: Option<&str>, since: i64) -> Result<Vec<PerformanceRecord>> {
+        let _ = (operator, since);
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Delete the performance records of epochs before `epoch`, returning
+    /// how many were deleted
+    async fn prune_performance_records(&self, epoch: u64) -> Result<usize> {
+        let _ = epoch;
+        Err(StorageError::unsupported("Staking state persistence"))
+    }
+    
+    /// Mark a slot network-finalized, keeping the votes that finalized it
+    async fn mark_network_finalized(&self, attestation: SlotAttestation) -> Result<()> {
+        let _ = attestation;
+        Err(StorageError::unsupported("Slot attestation persistence"))
+    }
+    
+    /// Get the attestation of a network-finalized slot
+    ///
+    /// Returns `None` if the slot has not been network-finalized.
+    async fn get_slot_attestation(&self, slot: u64) -> Result<Option<SlotAttestation>> {
+        let _ = slot;
+        Err(StorageError::unsupported("Slot attestation persistence"))
+    }
+    
     /// Get account by public key
     async fn get_account(&self, pubkey: &str) -> Result<Option<AccountData>>;
     
@@ -42,6 +272,131 @@ pub trait Storage: Send + Sync + 'static {
     /// Get recent blocks up to a limit
     async fn get_recent_blocks(&self, limit: usize) -> Result<Vec<BlockData>>;
     
+    /// Get the version of an account that was current at `slot`
+    ///
+    /// Returns the latest version written at or before `slot`. Backends that
+    /// only keep the latest account state return an error.
+    async fn get_account_at_slot(&self, pubkey: &str, slot: u64) -> Result<Option<AccountData>> {
+        let _ = (pubkey, slot);
+        Err(StorageError::unsupported("Account history"))
+    }
+    
+    /// Get every stored version of an account within a slot range
+    ///
+    /// Versions are ordered by `(slot, write_version)`, oldest first.
+    async fn get_account_history(&self, pubkey: &str, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>> {
+        let _ = (pubkey, start_slot, end_slot, limit);
+        Err(StorageError::unsupported("Account history"))
+    }
+    
+    /// Get transactions that reference an account, newest first
+    ///
+    /// Backends without a native account index return an error.
+    async fn get_transactions_by_account(&self, account: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        let _ = (account, limit);
+        Err(StorageError::unsupported("Transaction lookup by account"))
+    }
+    
+    /// Get transactions that invoke a program, newest first
+    ///
+    /// Backends without a native program index return an error.
+    async fn get_transactions_by_program(&self, program_id: &str, limit: usize) -> Result<Vec<TransactionData>> {
+        let _ = (program_id, limit);
+        Err(StorageError::unsupported("Transaction lookup by program"))
+    }
+    
+    /// Get the token balances held by an owner, largest first
+    ///
+    /// Requires the token balance index.
+    async fn get_token_balances(&self, owner: &str) -> Result<Vec<TokenBalance>> {
+        let _ = owner;
+        Err(StorageError::unsupported("Token balance index"))
+    }
+    
+    /// Get the largest holders of a mint
+    ///
+    /// Requires the token balance index.
+    async fn get_token_holders(&self, mint: &str, limit: usize) -> Result<Vec<TokenBalance>> {
+        let _ = (mint, limit);
+        Err(StorageError::unsupported("Token balance index"))
+    }
+    
+    /// Get the NFTs currently held by an owner
+    ///
+    /// Requires the NFT metadata index.
+    async fn get_nfts_by_owner(&self, owner: &str) -> Result<Vec<NftMetadata>> {
+        let _ = owner;
+        Err(StorageError::unsupported("NFT metadata index"))
+    }
+    
+    /// Get the metadata and current holder of an NFT mint
+    ///
+    /// Requires the NFT metadata index.
+    async fn get_nft_by_mint(&self, mint: &str) -> Result<Option<NftMetadata>> {
+        let _ = mint;
+        Err(StorageError::unsupported("NFT metadata index"))
+    }
+
+    /// Get a compressed NFT with its leaf, the root of its tree and a proof
+    ///
+    /// Requires the compression index.
+    async fn get_compressed_asset(&self, asset_id: &str) -> Result<Option<CompressedAsset>> {
+        let _ = asset_id;
+        Err(StorageError::unsupported("compression index"))
+    }
+
+    /// Get the most recent calls into a program made by other programs,
+    /// newest first
+    ///
+    /// Requires the CPI index.
+    async fn get_cpi_calls(&self, program_id: &str, limit: usize) -> Result<Vec<CpiCall>> {
+        let _ = (program_id, limit);
+        Err(StorageError::unsupported("CPI index"))
+    }
+
+    /// Get the addresses of an address lookup table
+    ///
+    /// Requires the lookup table index.
+    async fn get_lookup_table(&self, address: &str) -> Result<Option<LookupTable>> {
+        let _ = address;
+        Err(StorageError::unsupported("lookup table index"))
+    }
+    
+    /// Get the latest decoded form of an account
+    ///
+    /// Requires the decoded account index and a decoder registered for the
+    /// account's owner program.
+    async fn get_decoded_account(&self, pubkey: &str) -> Result<Option<ParsedAccount>> {
+        let _ = pubkey;
+        Err(StorageError::unsupported("Decoded account index"))
+    }
+    
+    /// Get the decoded top-level instructions of a recent transaction
+    ///
+    /// Requires the decoded instruction index; only instructions with a
+    /// registered decoder are returned.
+    async fn get_decoded_instructions(&self, signature: &str) -> Result<Vec<ParsedInstruction>> {
+        let _ = signature;
+        Err(StorageError::unsupported("Decoded instruction index"))
+    }
+
+    /// Get the latest events logged by transactions, newest first,
+    /// optionally only those of one program or with one name
+    ///
+    /// Requires the event index.
+    async fn get_events(&self, program_id: Option<&str>, name: Option<&str>, limit: usize) -> Result<Vec<ProgramEvent>> {
+        let _ = (program_id, name, limit);
+        Err(StorageError::unsupported("Event index"))
+    }
+    
+    /// Get up to `limit` pubkeys, signatures and blockhashes starting with `prefix`
+    ///
+    /// Requires the search index.
+    async fn search_prefix(&self, prefix: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let _ = (prefix, limit);
+        Err(StorageError::unsupported("Search index"))
+    }
+    
     /// Get accounts by slot range
     async fn get_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<AccountData>>;
     
@@ -51,10 +406,138 @@ pub trait Storage: Send + Sync + 'static {
     /// Get blocks by slot range
     async fn get_blocks_by_slot_range(&self, start_slot: u64, end_slot: u64, limit: usize) -> Result<Vec<BlockData>>;
     
+    /// Get a page of recent accounts, resuming from an opaque cursor
+    ///
+    /// The default implementation pages over `get_recent_accounts`; backends with
+    /// native key ordering should override it.
+    async fn get_recent_accounts_page(&self, limit: usize, cursor: Option<&str>) -> Result<Page<AccountData>> {
+        let offset = Cursor::decode_opt(cursor)?.offset as usize;
+        let items = self.get_recent_accounts(page_window(offset, limit)?).await?;
+        Ok(Page::from_offset(items, offset, limit))
+    }
+    
+    /// Get a page of recent transactions, resuming from an opaque cursor
+    async fn get_recent_transactions_page(&self, limit: usize, cursor: Option<&str>) -> Result<Page<TransactionData>> {
+        let offset = Cursor::decode_opt(cursor)?.offset as usize;
+        let items = self.get_recent_transactions(page_window(offset, limit)?).await?;
+        Ok(Page::from_offset(items, offset, limit))
+    }
+    
+    /// Get a page of recent blocks, resuming from an opaque cursor
+    async fn get_recent_blocks_page(&self, limit: usize, cursor: Option<&str>) -> Result<Page<BlockData>> {
+        let offset = Cursor::decode_opt(cursor)?.offset as usize;
+        let items = self.get_recent_blocks(page_window(offset, limit)?).await?;
+        Ok(Page::from_offset(items, offset, limit))
+    }
+    
+    /// Get a page of accounts in a slot range, resuming from an opaque cursor
+    async fn get_accounts_by_slot_range_page(&self, start_slot: u64, end_slot: u64, limit: usize, cursor: Option<&str>) -> Result<Page<AccountData>> {
+        let cursor = Cursor::decode_opt(cursor)?;
+        let from = start_slot.max(cursor.slot);
+        let items = self.get_accounts_by_slot_range(from, end_slot, page_window(cursor.offset as usize, limit)?).await?;
+        Ok(Page::from_slot_range(items, &Cursor { slot: from, ..cursor }, limit, |a| a.slot))
+    }
+    
+    /// Get a page of transactions in a slot range, resuming from an opaque cursor
+    async fn get_transactions_by_slot_range_page(&self, start_slot: u64, end_slot: u64, limit: usize, cursor: Option<&str>) -> Result<Page<TransactionData>> {
+        let cursor = Cursor::decode_opt(cursor)?;
+        let from = start_slot.max(cursor.slot);
+        let items = self.get_transactions_by_slot_range(from, end_slot, page_window(cursor.offset as usize, limit)?).await?;
+        Ok(Page::from_slot_range(items, &Cursor { slot: from, ..cursor }, limit, |t| t.slot))
+    }
+    
+    /// Get a page of blocks in a slot range, resuming from an opaque cursor
+    async fn get_blocks_by_slot_range_page(&self, start_slot: u64, end_slot: u64, limit: usize, cursor: Option<&str>) -> Result<Page<BlockData>> {
+        let cursor = Cursor::decode_opt(cursor)?;
+        let from = start_slot.max(cursor.slot);
+        let items = self.get_blocks_by_slot_range(from, end_slot, page_window(cursor.offset as usize, limit)?).await?;
+        Ok(Page::from_slot_range(items, &Cursor { slot: from, ..cursor }, limit, |b| b.slot))
+    }
+    
+    /// Stream accounts in a slot range without materializing the result
+    ///
+    /// The default implementation walks `get_accounts_by_slot_range_page` in
+    /// pages of [`STREAM_PAGE_SIZE`]; backends with native cursors should
+    /// override it.
+    fn stream_accounts_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<AccountData>> {
+        paged_stream(move |cursor: Option<String>| async move {
+            self.get_accounts_by_slot_range_page(start_slot, end_slot, STREAM_PAGE_SIZE, cursor.as_deref()).await
+        })
+    }
+    
+    /// Stream transactions in a slot range without materializing the result
+    fn stream_transactions_by_slot_range(&self, start_slot: u64, end_slot: u64) -> BoxStream<'_, Result<TransactionData>> {
+        paged_stream(move |cursor: Option<String>| async move {
+            self.get_transactions_by_slot_range_page(start_slot, end_slot, STREAM_PAGE_SIZE, cursor.as_deref()).await
+        })
+    }
+    
+    /// Delete data of the given kind stored for slots older than `slot`
+    ///
+    /// Accounts lose only versions that a later one superseded; the latest
+    /// state of every account is kept however old it is. Returns the number
+    /// of records removed. Backends that cannot prune return an error.
+    async fn prune_before_slot(&self, kind: DataKind, slot: u64) -> Result<u64> {
+        let _ = slot;
+        Err(StorageError::unsupported(format!("Pruning {:?}", kind)))
+    }
+    
+    /// The isolated backend of a tenant
+    ///
+    /// Only answered by a `tenants` backend or a wrapper around one.
+    fn tenant(&self, id: &str) -> Option<Arc<dyn Storage>> {
+        let _ = id;
+        None
+    }
+    
+    /// The filter deciding which records reach a tenant, for narrowing live
+    /// data to what the tenant's backend receives
+    ///
+    /// Only answered by a `tenants` backend or a wrapper around one.
+    fn tenant_matcher(&self, id: &str) -> Option<Arc<TenantMatcher>> {
+        let _ = id;
+        None
+    }
+    
     /// Close the storage (flush any pending writes, close connections, etc.)
     async fn close(&self) -> Result<()>;
 }
 
+/// Number of items the default page methods fetch to serve `limit` items
+/// after `offset`, one more than needed to tell whether another page follows
+///
+/// Offsets past [`MAX_PAGE_WINDOW`] are rejected rather than fetched.
+fn page_window(offset: usize, limit: usize) -> Result<usize> {
+    offset.checked_add(limit)
+        .and_then(|window| window.checked_add(1))
+        .filter(|_| offset <= MAX_PAGE_WINDOW)
+        .ok_or_else(|| StorageError::Invalid(format!("cursor, pages reach at most {} items back", MAX_PAGE_WINDOW)).into())
+}
+
+/// Turn a paged query into a stream, fetching the next page only once the
+/// previous one has been consumed
+pub fn paged_stream<'a, T, F, Fut>(fetch: F) -> BoxStream<'a, Result<T>>
+where
+    T: Send + 'a,
+    F: FnMut(Option<String>) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Page<T>>> + Send + 'a,
+{
+    // `None` once the last page has been fetched
+    let start: Option<Option<String>> = Some(None);
+    
+    stream::try_unfold((fetch, start), |(mut fetch, next)| async move {
+        let Some(cursor) = next else {
+            return Ok(None);
+        };
+        
+        let page = fetch(cursor).await?;
+        let next = page.next_cursor.map(Some);
+        Ok(Some((stream::iter(page.items.into_iter().map(Ok)), (fetch, next))))
+    })
+    .try_flatten()
+    .boxed()
+}
+
 /// Factory trait for creating storage instances
 #[async_trait]
 pub trait StorageFactory: Send + Sync + 'static {